@@ -0,0 +1,176 @@
+use clap::ValueEnum;
+use std::fmt::Write;
+use viable_compiler::metadata::CaptureInfo;
+use viable_compiler::target::Target;
+
+/// The language of a module exporting a compiled regex
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    Js,
+    Python,
+    Rust,
+}
+
+impl Language {
+    /// The language whose regex engine a target is for, if it has one
+    pub const fn for_target(target: Target) -> Option<Self> {
+        match target {
+            Target::EcmaScript => Some(Self::Js),
+            Target::PythonRe => Some(Self::Python),
+            Target::RustRegex | Target::RustRegexBytes => Some(Self::Rust),
+            Target::Pcre2 | Target::Re2 => None,
+        }
+    }
+}
+
+/// A module in `language` exporting the regex and the index of each named capture group, with a header naming
+/// the source it was generated from
+///
+/// `flags` are the flag letters compiled separately from the regex, for a target without an inline flag syntax.
+/// A JavaScript module exports them to pass to `RegExp`, the engines of the other languages take them inline
+pub fn artifact(language: Language, source_name: &str, regex: &str, flags: &str, captures: &[CaptureInfo]) -> String {
+    let named: Vec<(&str, usize)> = captures
+        .iter()
+        .filter_map(|capture| Some((capture.name.as_deref()?, capture.index)))
+        .collect();
+    let inline = || {
+        if flags.is_empty() {
+            regex.to_owned()
+        } else {
+            format!("(?{flags}){regex}")
+        }
+    };
+
+    match language {
+        Language::Js => js_artifact(source_name, regex, flags, &named),
+        Language::Python => python_artifact(source_name, &inline(), &named),
+        Language::Rust => rust_artifact(source_name, &inline(), &named),
+    }
+}
+
+// JSON strings are valid string literals in both JavaScript and Python
+
+fn js_artifact(source_name: &str, regex: &str, flags: &str, captures: &[(&str, usize)]) -> String {
+    let mut artifact = format!(
+        "// Generated by viable from {source_name}, do not edit\n\nexport const pattern = {};\n\nexport const flags = {};\n\n",
+        json_string(regex),
+        json_string(flags)
+    );
+
+    if captures.is_empty() {
+        artifact.push_str("export const captures = {};\n");
+    } else {
+        artifact.push_str("export const captures = {\n");
+        for (name, index) in captures {
+            let _ = writeln!(artifact, "  {}: {index},", json_string(name));
+        }
+        artifact.push_str("};\n");
+    }
+
+    artifact
+}
+
+fn python_artifact(source_name: &str, regex: &str, captures: &[(&str, usize)]) -> String {
+    let mut artifact = format!(
+        "# Generated by viable from {source_name}, do not edit\n\nPATTERN = {}\n\n",
+        json_string(regex)
+    );
+
+    if captures.is_empty() {
+        artifact.push_str("CAPTURES = {}\n");
+    } else {
+        artifact.push_str("CAPTURES = {\n");
+        for (name, index) in captures {
+            let _ = writeln!(artifact, "    {}: {index},", json_string(name));
+        }
+        artifact.push_str("}\n");
+    }
+
+    artifact
+}
+
+fn rust_artifact(source_name: &str, regex: &str, captures: &[(&str, usize)]) -> String {
+    // the debug representation of a string is a valid Rust string literal
+    let mut artifact =
+        format!("// Generated by viable from {source_name}, do not edit\n\npub const PATTERN: &str = {regex:?};\n\n");
+
+    if captures.is_empty() {
+        artifact.push_str("pub const CAPTURES: &[(&str, usize)] = &[];\n");
+    } else {
+        artifact.push_str("pub const CAPTURES: &[(&str, usize)] = &[\n");
+        for (name, index) in captures {
+            let _ = writeln!(artifact, "    ({name:?}, {index}),");
+        }
+        artifact.push_str("];\n");
+    }
+
+    artifact
+}
+
+fn json_string(string: &str) -> String {
+    serde_json::Value::from(string).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{artifact, Language};
+    use viable_compiler::target::Target;
+    use viable_compiler::Compiler;
+
+    const SOURCE: &str =
+        r#"capture year { 4 of <digit>; } "-"; capture { 2 of <digit>; } "\"\n"; capture day { 2 of <digit>; }"#;
+
+    fn snapshot(language: Language) -> String {
+        let target = match language {
+            Language::Js => Target::EcmaScript,
+            Language::Python => Target::PythonRe,
+            Language::Rust => Target::RustRegex,
+        };
+        let output = Compiler::new().target(target).compile(SOURCE).unwrap();
+
+        artifact(
+            language,
+            "date.viable",
+            &output.pattern,
+            &output.flags,
+            &output.captures,
+        )
+    }
+
+    #[test]
+    fn js_artifact_test() {
+        assert_eq!(snapshot(Language::Js), include_str!("../tests/snapshots/artifact.js"));
+    }
+
+    #[test]
+    fn python_artifact_test() {
+        assert_eq!(
+            snapshot(Language::Python),
+            include_str!("../tests/snapshots/artifact.py")
+        );
+    }
+
+    #[test]
+    fn rust_artifact_test() {
+        assert_eq!(snapshot(Language::Rust), include_str!("../tests/snapshots/artifact.rs"));
+    }
+
+    #[test]
+    fn empty_artifact_test() {
+        assert_eq!(
+            artifact(Language::Rust, "-", "a+", "", &[]),
+            "// Generated by viable from -, do not edit\n\npub const PATTERN: &str = \"a+\";\n\npub const CAPTURES: &[(&str, usize)] = &[];\n"
+        );
+    }
+
+    #[test]
+    fn flags_artifact_test() {
+        let output = Compiler::new().compile(r#"flags { insensitive; } "a";"#).unwrap();
+
+        assert_eq!(
+            artifact(Language::Js, "-", &output.pattern, &output.flags, &[]),
+            "// Generated by viable from -, do not edit\n\nexport const pattern = \"a\";\n\nexport const flags = \"i\";\n\nexport const captures = {};\n"
+        );
+        assert!(artifact(Language::Python, "-", &output.pattern, &output.flags, &[]).contains("PATTERN = \"(?i)a\"\n"));
+    }
+}
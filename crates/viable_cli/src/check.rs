@@ -0,0 +1,30 @@
+use crate::compile::{import_resolver, read_source};
+use crate::consts::STDIN_MARKER;
+use crate::errors::CliError;
+use crate::output::report_diagnostic;
+use viable_compiler::diagnostics::render_error;
+use viable_compiler::target::Target;
+use viable_compiler::Compiler;
+
+pub fn check_file(input_file_path: &str, target: Target) -> anyhow::Result<()> {
+    let source = read_source(input_file_path)?;
+
+    let compiled = Compiler::new()
+        .target(target)
+        .compile_with_imports(&source, import_resolver(input_file_path));
+
+    if let Err(error) = compiled {
+        report_diagnostic(&render_error(&source, &error));
+        return Err(CliError::CheckFailed(display_path(input_file_path)).into());
+    }
+
+    Ok(())
+}
+
+pub fn display_path(input_file_path: &str) -> String {
+    if input_file_path == STDIN_MARKER {
+        String::from("stdin")
+    } else {
+        input_file_path.to_owned()
+    }
+}
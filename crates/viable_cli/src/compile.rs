@@ -1,22 +1,153 @@
-use crate::consts::STDIN_MARKER;
+use crate::consts::{SOURCE_EXTENSION, STDIN_MARKER};
 use crate::errors::CliError;
-use crate::utils::read_stdin;
-use viable_compiler::compiler;
-use std::fs::read_to_string;
+use crate::output::{print_output, report_flags};
+use crate::utils::{read_stdin, write_output_to_file};
+use serde_json::json;
+use std::fs::{create_dir_all, read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+use viable_compiler::metadata::CaptureInfo;
+use viable_compiler::target::Target;
+use viable_compiler::{CompileOutput, Compiler};
 
 fn read_file(path: &str) -> anyhow::Result<String> {
     let contents = read_to_string(path).map_err(|_| CliError::ReadFileError(path.to_owned()))?;
     Ok(contents)
 }
 
-pub fn compile_file(input_file_path: &str) -> anyhow::Result<String> {
-    let source = if input_file_path == STDIN_MARKER {
-        read_stdin()?
+/// Reads a source from a file, or from stdin if the path is `-`
+pub fn read_source(input_file_path: &str) -> anyhow::Result<String> {
+    if input_file_path == STDIN_MARKER {
+        read_stdin()
     } else {
-        read_file(input_file_path)?
-    };
+        read_file(input_file_path)
+    }
+}
+
+/// The directory imports are resolved relative to, that of the input file or the working directory for stdin
+pub fn base_directory(input_file_path: &str) -> PathBuf {
+    match Path::new(input_file_path).parent() {
+        Some(parent) if input_file_path != STDIN_MARKER => parent.to_path_buf(),
+        _ => Path::new(".").to_path_buf(),
+    }
+}
+
+pub fn import_resolver(input_file_path: &str) -> impl Fn(&str) -> Option<String> + 'static {
+    let base_directory = base_directory(input_file_path);
 
-    let output = compiler(&source)?;
+    move |path| read_to_string(base_directory.join(path)).ok()
+}
+
+pub fn compile_file(input_file_path: &str) -> anyhow::Result<CompileOutput> {
+    let source = read_source(input_file_path)?;
+    let output = Compiler::new().compile_with_imports(&source, import_resolver(input_file_path))?;
 
     Ok(output)
 }
+
+/// Compiles a file or every source of a directory, printing the output or writing it to `out`
+pub fn compile_command(path: &str, target: Target, out: Option<&str>, metadata: bool) -> anyhow::Result<()> {
+    if Path::new(path).is_dir() {
+        return compile_directory(path, target, out, metadata);
+    }
+
+    let output = compile_output(path, target)?;
+    if !metadata {
+        report_flags(&output.flags);
+    }
+
+    if let Some(out) = out {
+        return write_output_to_file(out, &output.text(metadata));
+    }
+
+    print_output(&output.text(metadata));
+    Ok(())
+}
+
+/// Compiles the `.viable` files directly within a directory in order of their names
+///
+/// Without `out` the outputs are printed after the path of each file (or as a JSON array with `metadata`),
+/// otherwise each is written to a file named after its source in `out`
+fn compile_directory(path: &str, target: Target, out: Option<&str>, metadata: bool) -> anyhow::Result<()> {
+    let mut source_paths: Vec<PathBuf> = read_dir(path)
+        .map_err(|_| CliError::ReadDirectoryError(path.to_owned()))?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|extension| extension == SOURCE_EXTENSION))
+        .collect();
+    source_paths.sort();
+
+    let mut outputs = Vec::new();
+    for source_path in &source_paths {
+        let display_path = source_path.display().to_string();
+        let output = compile_output(&display_path, target)
+            .map_err(|error| CliError::ParseError(format!("{display_path}: {error}")))?;
+        outputs.push((display_path, output));
+    }
+
+    if let Some(out) = out {
+        create_dir_all(out).map_err(|_| CliError::WriteFileError(out.to_owned()))?;
+
+        let extension = if metadata { "json" } else { "regex" };
+        for (source_path, (_, output)) in source_paths.iter().zip(outputs) {
+            let output_path = Path::new(out)
+                .join(source_path.file_stem().unwrap_or_default())
+                .with_extension(extension);
+
+            write_output_to_file(&output_path.display().to_string(), &output.text(metadata))?;
+        }
+    } else if metadata {
+        let outputs: Vec<_> = outputs
+            .into_iter()
+            .map(|(display_path, output)| {
+                let mut json = output.json();
+                json["path"] = json!(display_path);
+                json
+            })
+            .collect();
+
+        print_output(&json!(outputs).to_string());
+    } else {
+        for (display_path, output) in outputs {
+            print_output(&format!("{display_path}: {}", output.regex));
+            report_flags(&output.flags);
+        }
+    }
+
+    Ok(())
+}
+
+fn compile_output(path: &str, target: Target) -> anyhow::Result<Output> {
+    let source = read_source(path)?;
+
+    let output = Compiler::new()
+        .target(target)
+        .compile_with_imports(&source, import_resolver(path))
+        .map_err(|error| CliError::ParseError(error.to_string()))?;
+
+    Ok(Output {
+        regex: output.pattern,
+        flags: output.flags,
+        captures: output.captures,
+    })
+}
+
+struct Output {
+    regex: String,
+    /// the flags to pass along with the regex, for a target without an inline flag syntax
+    flags: String,
+    captures: Vec<CaptureInfo>,
+}
+
+impl Output {
+    fn json(&self) -> serde_json::Value {
+        json!({ "regex": self.regex, "flags": self.flags, "captures": self.captures })
+    }
+
+    /// The regex, or the regex with its capture groups as JSON if `metadata` is set
+    fn text(&self, metadata: bool) -> String {
+        if metadata {
+            self.json().to_string()
+        } else {
+            self.regex.clone()
+        }
+    }
+}
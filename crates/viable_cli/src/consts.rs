@@ -1,2 +1,3 @@
 pub const COMMAND_MARKER: &str = ":";
 pub const STDIN_MARKER: &str = "-";
+pub const SOURCE_EXTENSION: &str = "viable";
@@ -8,6 +8,8 @@ use thiserror::Error;
 pub enum CliError {
     #[error("unable read file at path {0}")]
     ReadFileError(String),
+    #[error("unable read directory at path {0}")]
+    ReadDirectoryError(String),
     #[error("{0}")]
     ParseError(String),
     #[error("{0}")]
@@ -22,6 +24,16 @@ pub enum CliError {
     StdinWithoutPipe,
     #[error("could not compile regex\nCause: {0}\nRegex: {1}")]
     CompileRegex(String, String),
+    #[error("could not compile {0}")]
+    CheckFailed(String),
+    #[error("{0} is not formatted")]
+    NotFormatted(String),
+    #[error("could not format {0}\nCause: formatting would drop {1}")]
+    Unformattable(String, String),
+    #[error("no artifact language for the {0} target\nTry adding a language argument: '--language rust'")]
+    MissingArtifactLanguage(String),
+    #[error("unable to handle interrupts\nCause: {0}")]
+    SignalHandlerError(String),
 }
 
 #[derive(Debug)]
@@ -55,11 +67,18 @@ impl CliError {
 
     const fn to_exit_code(&self) -> exitcode::ExitCode {
         match self {
-            Self::WriteFileError(_) | Self::ReadFileError(_) | Self::ReadInputError | Self::ReadStdinError => {
-                exitcode::IOERR
-            }
-            Self::CompileRegex(_, _) | Self::ParseError(_) => exitcode::DATAERR,
-            Self::ReplWithPipe => exitcode::USAGE,
+            Self::WriteFileError(_)
+            | Self::ReadFileError(_)
+            | Self::ReadDirectoryError(_)
+            | Self::ReadInputError
+            | Self::ReadStdinError => exitcode::IOERR,
+            Self::CompileRegex(_, _)
+            | Self::ParseError(_)
+            | Self::CheckFailed(_)
+            | Self::NotFormatted(_)
+            | Self::Unformattable(_, _) => exitcode::DATAERR,
+            Self::ReplWithPipe | Self::MissingArtifactLanguage(_) => exitcode::USAGE,
+            Self::SignalHandlerError(_) => exitcode::OSERR,
             Self::StdinWithoutPipe => exitcode::NOINPUT,
         }
     }
@@ -0,0 +1,61 @@
+use crate::check::display_path;
+use crate::compile::read_source;
+use crate::errors::CliError;
+use crate::output::print_source;
+use viable_compiler::ast::{ast_to_source_with_flags, to_ast_with_diagnostics, to_ast_with_flags};
+use viable_compiler::options::FormatOptions;
+use viable_compiler::tokens::{tokenize, TokenKind};
+use viable_compiler::warnings::Warning;
+
+/// Prints a file in canonical formatting, or only checks that it already is if `check` is set
+///
+/// The output is written from the AST, so sources holding what the AST doesn't keep (comments, `tests` blocks,
+/// custom symbols and unused variables) are rejected rather than changed, as are sources with imports
+pub fn format_file(input_file_path: &str, check: bool) -> anyhow::Result<()> {
+    let source = read_source(input_file_path)?;
+    let formatted = format_source(&source, input_file_path)?;
+
+    if check {
+        if formatted != source {
+            return Err(CliError::NotFormatted(display_path(input_file_path)).into());
+        }
+    } else {
+        print_source(&formatted);
+    }
+
+    Ok(())
+}
+
+fn format_source(source: &str, input_file_path: &str) -> anyhow::Result<String> {
+    let (ast, flags) = to_ast_with_flags(source).map_err(|error| CliError::ParseError(error.to_string()))?;
+
+    if let Some(construct) = unformattable(source) {
+        return Err(CliError::Unformattable(display_path(input_file_path), construct).into());
+    }
+
+    Ok(ast_to_source_with_flags(&ast, &flags, &FormatOptions::default()))
+}
+
+/// The first construct of a source that formatting would drop, if any
+fn unformattable(source: &str) -> Option<String> {
+    let tokens = tokenize(source);
+    for (index, token) in tokens.iter().enumerate() {
+        // `tests` and `define` are also valid capture and variable names, only as statements are they keywords
+        let statement =
+            index == 0 || matches!(&source[tokens[index - 1].start..tokens[index - 1].end], ";" | "{" | "}");
+        match (token.kind, &source[token.start..token.end]) {
+            (TokenKind::Comment, _) => return Some(String::from("a comment")),
+            (TokenKind::Keyword, "tests") if statement => return Some(String::from("a `tests` block")),
+            (TokenKind::Keyword, "define") if statement => {
+                return Some(String::from("a `define symbol` declaration"));
+            }
+            _ => {}
+        }
+    }
+
+    let (_, warnings) = to_ast_with_diagnostics(source).ok()?;
+    warnings.into_iter().find_map(|warning| match warning {
+        Warning::UnusedVariable { name, .. } => Some(format!("the unused variable `.{name}`")),
+        _ => None,
+    })
+}
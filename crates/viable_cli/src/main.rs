@@ -1,29 +1,36 @@
 #![forbid(unsafe_code)]
 #![allow(clippy::module_name_repetitions)]
 
+mod artifact;
+mod check;
 mod compile;
 mod completions;
 mod consts;
 mod errors;
+mod format;
 mod macros;
 mod output;
 mod repl;
 mod test;
 mod types;
 mod utils;
+mod watch;
 
+use check::check_file;
 use clap::Parser;
 use colored::control::{ShouldColorize, SHOULD_COLORIZE};
-use compile::compile_file;
+use compile::{compile_command, compile_file};
 use completions::generate_completions;
 use consts::STDIN_MARKER;
 use errors::{handle_error, CliError};
-use output::{print_output, report_error, report_info};
+use format::format_file;
+use output::{print_output, report_error, report_flags, report_info};
 use repl::repl;
 use std::process;
 use test::{test_input, test_input_file};
-use types::{Args, Streams};
+use types::{Args, Command, Streams};
 use utils::write_output_to_file;
+use watch::watch;
 
 fn main() {
     ShouldColorize::from_env();
@@ -36,6 +43,7 @@ fn main() {
 
 fn try_main() -> anyhow::Result<()> {
     let Args {
+        command,
         start_repl,
         input_file_path,
         output_file_path,
@@ -54,6 +62,10 @@ fn try_main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    if let Some(command) = command {
+        return run_command(command);
+    }
+
     let input_file_path = input_file_path.unwrap_or_else(|| STDIN_MARKER.to_owned());
 
     argument_env_validation(start_repl, &input_file_path)?;
@@ -65,19 +77,39 @@ fn try_main() -> anyhow::Result<()> {
     let output = compile_file(&input_file_path).map_err(|error| CliError::ParseError(error.to_string()))?;
 
     if let Some(test) = test {
-        test_input(&output, &test)?;
+        test_input(&output.pattern, &output.flags, &test)?;
     } else if let Some(test_file) = test_file {
-        test_input_file(&output, &test_file)?;
+        test_input_file(&output.pattern, &output.flags, &test_file)?;
     } else {
+        report_flags(&output.flags);
         match output_file_path {
-            Some(output_file_path) => write_output_to_file(&output_file_path, &output)?,
-            None => print_output(&output),
+            Some(output_file_path) => write_output_to_file(&output_file_path, &output.pattern)?,
+            None => print_output(&output.pattern),
         };
     }
 
     Ok(())
 }
 
+fn run_command(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Compile {
+            path,
+            target,
+            out,
+            metadata,
+        } => compile_command(&path, target, out.as_deref(), metadata),
+        Command::Check { path, target } => check_file(&path, target),
+        Command::Fmt { path, check } => format_file(&path, check),
+        Command::Watch {
+            path,
+            target,
+            out,
+            language,
+        } => watch(&path, target, &out, language),
+    }
+}
+
 fn argument_env_validation(start_repl: bool, input_file_path: &str) -> anyhow::Result<()> {
     let streams = Streams::new();
 
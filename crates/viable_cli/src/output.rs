@@ -4,6 +4,10 @@ pub fn print_output(output: &str) {
     println!("{}", output.bright_blue());
 }
 
+pub fn print_source(source: &str) {
+    print!("{}", source.bright_blue());
+}
+
 pub fn print_repl_output(output: &str) {
     println!("{}\n", output.bright_blue());
 }
@@ -50,6 +54,18 @@ pub fn print_repl_welcome() {
     );
 }
 
+pub fn report_watch_started(path: &str) {
+    println!("{}", format!("watching {path}, press Ctrl-C to exit").bright_green());
+}
+
+pub fn report_watch_compiled(path: &str, out: &str) {
+    println!(
+        "{} {}",
+        "compiled".bright_green(),
+        format!("{path} to {out}").bright_blue()
+    );
+}
+
 pub fn report_nothing_to_undo() {
     eprintln!("{}", "nothing to undo\n".bright_red());
 }
@@ -90,6 +106,18 @@ pub fn report_error(error: &str) {
     eprintln!("{}", format!("Error: {error}").bright_red());
 }
 
+/// Reports the flags to pass to the engine along with a regex, compiled separately for a target without an inline
+/// flag syntax
+pub fn report_flags(flags: &str) {
+    if !flags.is_empty() {
+        eprintln!("{}", format!("flags: {flags}").bright_blue());
+    }
+}
+
+pub fn report_diagnostic(diagnostic: &str) {
+    eprint!("{diagnostic}");
+}
+
 pub fn report_unhandled_error(error: &str) {
     eprintln!(
         "{}",
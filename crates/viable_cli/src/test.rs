@@ -7,8 +7,8 @@ use crate::{
     output::{report_test_result, report_test_result_file},
 };
 
-pub fn test_input(regex: &str, input: &str) -> anyhow::Result<()> {
-    let regex = Regex::new(regex)
+pub fn test_input(regex: &str, flags: &str, input: &str) -> anyhow::Result<()> {
+    let regex = Regex::with_flags(regex, flags)
         .map_err(|error| CliError::CompileRegex(error.to_string().to_lowercase(), regex.to_string()))?;
     let matched = regex.find(input).is_some();
     report_test_result(matched, input);
@@ -16,9 +16,9 @@ pub fn test_input(regex: &str, input: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn test_input_file(regex: &str, file_path: &str) -> anyhow::Result<()> {
+pub fn test_input_file(regex: &str, flags: &str, file_path: &str) -> anyhow::Result<()> {
     let input = read_to_string(file_path).map_err(|_| CliError::ReadFileError(file_path.to_owned()))?;
-    let regex = Regex::new(regex)
+    let regex = Regex::with_flags(regex, flags)
         .map_err(|error| CliError::CompileRegex(error.to_string().to_lowercase(), regex.to_string()))?;
     let matched = regex.find(&input).is_some();
     report_test_result_file(matched, file_path);
@@ -1,9 +1,14 @@
-use clap::Parser;
+use crate::artifact::Language;
+use crate::consts::STDIN_MARKER;
+use clap::{Parser, Subcommand};
 use std::io::IsTerminal;
+use viable_compiler::target::Target;
 
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
 pub struct Args {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
     #[clap(
         id = "input",
         value_name = "INPUT_FILE_PATH",
@@ -18,7 +23,13 @@ pub struct Args {
         help = "Write to a file"
     )]
     pub output_file_path: Option<String>,
-    #[clap(id = "no-color", short = 'n', long = "no-color", help = "Print output with no color")]
+    #[clap(
+        id = "no-color",
+        short = 'n',
+        long = "no-color",
+        global = true,
+        help = "Print output with no color"
+    )]
     pub no_color_output: bool,
     #[clap(id = "repl", short = 'r', long = "repl", help = "Start the Viable REPL")]
     pub start_repl: bool,
@@ -47,6 +58,102 @@ pub struct Args {
     pub test_file: Option<String>,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    #[clap(about = "Compile a file, or every .viable file of a directory")]
+    Compile {
+        #[clap(
+            value_name = "PATH",
+            default_value = STDIN_MARKER,
+            help = "Read from a file or a directory\nUse '-' to read from stdin"
+        )]
+        path: String,
+        #[clap(
+            short = 't',
+            long = "target",
+            value_parser = parse_target,
+            default_value = "ecmascript",
+            help = "The regex flavor to compile for\nOne of 'js', 'pcre', 're2', 'python' or 'rust'"
+        )]
+        target: Target,
+        #[clap(
+            short = 'o',
+            long = "out",
+            value_name = "OUTPUT_PATH",
+            help = "Write to a file\nWhen compiling a directory, write to a file per source in this directory"
+        )]
+        out: Option<String>,
+        #[clap(
+            short = 'm',
+            long = "metadata",
+            help = "Output the capture groups along with the regex as JSON"
+        )]
+        metadata: bool,
+    },
+    #[clap(about = "Check a file for errors without outputting the regex")]
+    Check {
+        #[clap(
+            value_name = "PATH",
+            default_value = STDIN_MARKER,
+            help = "Read from a file\nUse '-' to read from stdin"
+        )]
+        path: String,
+        #[clap(
+            short = 't',
+            long = "target",
+            value_parser = parse_target,
+            default_value = "ecmascript",
+            help = "The regex flavor to check support for\nOne of 'js', 'pcre', 're2', 'python' or 'rust'"
+        )]
+        target: Target,
+    },
+    #[clap(about = "Print a file in canonical formatting")]
+    Fmt {
+        #[clap(
+            value_name = "PATH",
+            default_value = STDIN_MARKER,
+            help = "Read from a file\nUse '-' to read from stdin"
+        )]
+        path: String,
+        #[clap(
+            long = "check",
+            help = "Print nothing and exit with an error if the file is not formatted"
+        )]
+        check: bool,
+    },
+    #[clap(about = "Compile a file to a module exporting the regex whenever it changes")]
+    Watch {
+        #[clap(value_name = "PATH", help = "Read from a file")]
+        path: String,
+        #[clap(
+            short = 't',
+            long = "target",
+            value_parser = parse_target,
+            default_value = "ecmascript",
+            help = "The regex flavor to compile for\nOne of 'js', 'pcre', 're2', 'python' or 'rust'"
+        )]
+        target: Target,
+        #[clap(
+            short = 'o',
+            long = "out",
+            value_name = "OUTPUT_PATH",
+            help = "Write the module to a file"
+        )]
+        out: String,
+        #[clap(
+            short = 'l',
+            long = "language",
+            value_enum,
+            help = "The language of the module, that of the target by default"
+        )]
+        language: Option<Language>,
+    },
+}
+
+fn parse_target(name: &str) -> Result<Target, String> {
+    Target::from_name(name).ok_or_else(|| format!("unknown target '{name}'"))
+}
+
 pub enum NextLoop {
     Continue,
     Exit,
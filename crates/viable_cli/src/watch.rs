@@ -0,0 +1,119 @@
+use crate::artifact::{artifact, Language};
+use crate::compile::base_directory;
+use crate::errors::CliError;
+use crate::output::{report_diagnostic, report_exit, report_watch_compiled, report_watch_started};
+use crate::utils::write_output_to_file;
+use std::cell::RefCell;
+use std::fs::{metadata, read_to_string};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime};
+use viable_compiler::diagnostics::render_error;
+use viable_compiler::target::Target;
+use viable_compiler::Compiler;
+
+/// How often the watched files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long the watched files have to stay unchanged before recompiling, so that rapid saves compile once
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Compiles a file to an artifact whenever it or a file it imports changes, until interrupted
+///
+/// Compile errors are reported and keep the previous artifact, the artifact is written for `language` or else
+/// the language of the target
+pub fn watch(input_file_path: &str, target: Target, out: &str, language: Option<Language>) -> anyhow::Result<()> {
+    let language = language
+        .or_else(|| Language::for_target(target))
+        .ok_or_else(|| CliError::MissingArtifactLanguage(target.name().to_owned()))?;
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = Arc::clone(&running);
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst))
+        .map_err(|error| CliError::SignalHandlerError(error.to_string()))?;
+
+    report_watch_started(input_file_path);
+
+    let mut watched = compile_artifact(input_file_path, target, out, language)?;
+    let mut stamps = modification_times(&watched);
+    let mut changed_at: Option<Instant> = None;
+
+    while running.load(Ordering::SeqCst) {
+        sleep(POLL_INTERVAL);
+
+        let current = modification_times(&watched);
+        if current != stamps {
+            stamps = current;
+            changed_at = Some(Instant::now());
+        }
+
+        if changed_at.is_some_and(|changed_at| changed_at.elapsed() >= DEBOUNCE) {
+            changed_at = None;
+            watched = compile_artifact(input_file_path, target, out, language)?;
+            stamps = modification_times(&watched);
+        }
+    }
+
+    report_exit();
+
+    Ok(())
+}
+
+/// Compiles the file and writes its artifact, returning the files read to compile it
+fn compile_artifact(
+    input_file_path: &str,
+    target: Target,
+    out: &str,
+    language: Language,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut watched = vec![PathBuf::from(input_file_path)];
+
+    let Ok(source) = read_to_string(input_file_path) else {
+        // the file may be replaced rather than written to, it is compiled once it exists again
+        report_diagnostic(&format!("{}\n", CliError::ReadFileError(input_file_path.to_owned())));
+        return Ok(watched);
+    };
+
+    let imports = Rc::new(RefCell::new(Vec::new()));
+    let resolver = {
+        let imports = Rc::clone(&imports);
+        let base_directory = base_directory(input_file_path);
+
+        move |path: &str| {
+            let path = base_directory.join(path);
+            imports.borrow_mut().push(path.clone());
+            read_to_string(path).ok()
+        }
+    };
+
+    let compiled = Compiler::new().target(target).compile_with_imports(&source, resolver);
+    watched.extend(imports.take());
+
+    match compiled {
+        Ok(output) => {
+            let artifact = artifact(
+                language,
+                input_file_path,
+                &output.pattern,
+                &output.flags,
+                &output.captures,
+            );
+            write_output_to_file(out, &artifact)?;
+            report_watch_compiled(input_file_path, out);
+        }
+        Err(error) => report_diagnostic(&render_error(&source, &error)),
+    }
+
+    Ok(watched)
+}
+
+/// The modification time of each file, `None` for files that can't be read
+fn modification_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| metadata(path).and_then(|metadata| metadata.modified()).ok())
+        .collect()
+}
@@ -3,6 +3,7 @@
 use assert_cmd::Command;
 use assert_fs::{fixture::FileWriteStr, NamedTempFile};
 use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
 use tempfile::tempdir;
 use unindent::unindent;
 
@@ -36,7 +37,7 @@ fn cli_file_stdout_test() -> anyhow::Result<()> {
       some of "a";
     "#;
 
-    let expected_output = "(?:A'){5}(?:(?<test>xy))[abc](?:a\"|b|.)a+\n";
+    let expected_output = "(?:A'){5}(?<test>xy)[abc](?:a\"|b|.)a+\n";
 
     viable_file.write_str(&unindent(source))?;
 
@@ -154,7 +155,7 @@ fn cli_file_test() -> anyhow::Result<()> {
     some of "a";
   "#;
 
-    let expected_output = "(?:A'){5}(?:(?<test>xy))[abc](?:a\"|b|.)a+";
+    let expected_output = "(?:A'){5}(?<test>xy)[abc](?:a\"|b|.)a+";
 
     viable_file.write_str(&unindent(source))?;
 
@@ -173,3 +174,319 @@ fn cli_file_test() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn cli_file_import_test() -> anyhow::Result<()> {
+    let mut command = Command::cargo_bin("viable")?;
+    let dir = tempdir()?;
+
+    std::fs::create_dir(dir.path().join("lib"))?;
+    std::fs::write(
+        dir.path().join("lib").join("common.mdy"),
+        r"let .digits = { some of <digit>; }",
+    )?;
+    std::fs::write(
+        dir.path().join("main.mdy"),
+        r#"import "lib/common.mdy"; .digits; "."; .digits;"#,
+    )?;
+
+    command
+        .arg(dir.path().join("main.mdy"))
+        .assert()
+        .stdout("\\d+\\.\\d+\n");
+
+    Ok(())
+}
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures")
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn cli_compile_target_test() -> anyhow::Result<()> {
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["compile", "flags.viable", "--target", "rust"])
+        .assert()
+        .success()
+        .stdout("(?i)\\d+\n");
+
+    // ECMAScript has no inline flags, so they are reported to pass to `RegExp` along with the regex
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["compile", "flags.viable", "--target", "js"])
+        .assert()
+        .success()
+        .stdout("\\d+\n")
+        .stderr("flags: i\n");
+
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["compile", "flags.viable", "--metadata"])
+        .assert()
+        .success()
+        .stdout("{\"captures\":[],\"flags\":\"i\",\"regex\":\"\\\\d+\"}\n")
+        .stderr("");
+
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["check", "flags.viable", "--target", "js"])
+        .assert()
+        .success();
+
+    Command::cargo_bin("viable")?
+        .args(["compile", "-", "--target", "perl"])
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn cli_compile_stdin_metadata_test() -> anyhow::Result<()> {
+    Command::cargo_bin("viable")?
+        .args(["compile", "-", "--metadata"])
+        .write_stdin(r#"capture word { some of <word>; } capture { "!"; }"#)
+        .assert()
+        .success()
+        .stdout(concat!(
+            r#"{"captures":[{"in_negative_assertion":false,"index":1,"name":"word","span":{"column":1,"end":32,"line":1,"start":0}},"#,
+            r#"{"in_negative_assertion":false,"index":2,"name":null,"span":{"column":34,"end":49,"line":1,"start":33}}],"flags":"","regex":"(?<word>\\w+)(!)"}"#,
+            "\n"
+        ));
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn cli_compile_directory_test() -> anyhow::Result<()> {
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["compile", "directory"])
+        .assert()
+        .success()
+        .stdout("directory/version.viable: \\d+\\.\\d+\ndirectory/word.viable: \\w+\n");
+
+    let dir = tempdir()?;
+
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["compile", "directory", "--out"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout("");
+
+    assert_eq!(read_to_string(dir.path().join("version.regex"))?, "\\d+\\.\\d+");
+    assert_eq!(read_to_string(dir.path().join("word.regex"))?, "\\w+");
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn cli_check_test() -> anyhow::Result<()> {
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["check", "date.viable"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    let expected_stderr = unindent(
+        "
+        error[E0009]: empty range, `z` comes after `a`
+         --> 2:1
+          |
+        2 | z to a;
+          | ^^^^^^
+          = help: swap the bounds of the range, i.e. `a to z`
+        Error: could not compile invalid.viable
+        ",
+    );
+
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["check", "invalid.viable"])
+        .assert()
+        .code(exitcode::DATAERR)
+        .stdout("")
+        .stderr(expected_stderr);
+
+    // unsupported constructs are errors of the target
+    Command::cargo_bin("viable")?
+        .args(["check", "-", "--target", "rust"])
+        .write_stdin(r#"possessive some of "a";"#)
+        .assert()
+        .code(exitcode::DATAERR);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn cli_fmt_test() -> anyhow::Result<()> {
+    let formatted = read_to_string(fixtures().join("formatted.viable"))?;
+
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["fmt", "date.viable"])
+        .assert()
+        .success()
+        .stdout(formatted);
+
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["fmt", "formatted.viable", "--check"])
+        .assert()
+        .success()
+        .stdout("");
+
+    Command::cargo_bin("viable")?
+        .current_dir(fixtures())
+        .args(["fmt", "date.viable", "--check"])
+        .assert()
+        .code(exitcode::DATAERR)
+        .stdout("")
+        .stderr("Error: date.viable is not formatted\n");
+
+    Command::cargo_bin("viable")?
+        .args(["fmt", "-"])
+        .write_stdin(r#"flags { multiline; } some of "a";"#)
+        .assert()
+        .success()
+        .stdout("flags {\n  multiline;\n}\nsome of \"a\";\n");
+
+    // parameterized variables are declared once and invoked by name, also when quantified
+    let parameterized =
+        "let .pair(open, close) = {\n  .open;\n  some of <word>;\n  .close;\n}\n.pair(\"(\", \")\");\n3 of .pair(\"[\", \"]\");\n";
+    Command::cargo_bin("viable")?
+        .args(["fmt", "-"])
+        .write_stdin(
+            r#"let .pair(open, close) = { .open; some of <word>; .close; } .pair("(", ")"); 3 of .pair("[", "]");"#,
+        )
+        .assert()
+        .success()
+        .stdout(parameterized);
+
+    Command::cargo_bin("viable")?
+        .args(["fmt", "-", "--check"])
+        .write_stdin(parameterized)
+        .assert()
+        .success()
+        .stdout("");
+
+    // sources holding what the AST doesn't keep are rejected rather than changed
+    for (source, construct) in [
+        ("// a year\n4 of <digit>;\n", "a comment"),
+        ("4 of <digit>; /* a year */\n", "a comment"),
+        ("\"a\";\ntests {\n  match \"a\";\n}\n", "a `tests` block"),
+        (
+            "define symbol quote = {\n  '\"';\n}\n<quote>;\n",
+            "a `define symbol` declaration",
+        ),
+        (
+            "let .unused = {\n  \"a\";\n}\n\"b\";\n",
+            "the unused variable `.unused`",
+        ),
+    ] {
+        for args in [["fmt", "-"].as_slice(), ["fmt", "-", "--check"].as_slice()] {
+            Command::cargo_bin("viable")?
+                .args(args)
+                .write_stdin(source)
+                .assert()
+                .code(exitcode::DATAERR)
+                .stdout("")
+                .stderr(format!(
+                    "Error: could not format stdin\nCause: formatting would drop {construct}\n"
+                ));
+        }
+    }
+
+    // keywords used as names are kept
+    Command::cargo_bin("viable")?
+        .args(["fmt", "-"])
+        .write_stdin(r#"capture tests { "a"; } let .define = { "b"; } .define;"#)
+        .assert()
+        .success()
+        .stdout("capture tests {\n  \"a\";\n}\nlet .define = {\n  \"b\";\n}\n.define;\n");
+
+    Ok(())
+}
+
+/// Waits for a file to hold the given contents, giving up after a few seconds
+fn wait_for_contents(path: &Path, expected: &str) -> bool {
+    for _ in 0..100 {
+        if read_to_string(path).is_ok_and(|contents| contents == expected) {
+            return true;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+
+    false
+}
+
+#[test]
+#[cfg(unix)]
+#[cfg_attr(miri, ignore)]
+fn cli_watch_test() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let source_path = dir.path().join("pattern.viable");
+    let out_path = dir.path().join("pattern.rs");
+    std::fs::write(&source_path, r"capture word { some of <word>; }")?;
+
+    let child = std::process::Command::new(assert_cmd::cargo::cargo_bin("viable"))
+        .current_dir(dir.path())
+        .args(["watch", "pattern.viable", "--target", "rust", "--out", "pattern.rs"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+
+    assert!(wait_for_contents(
+        &out_path,
+        &unindent(
+            r#"
+            // Generated by viable from pattern.viable, do not edit
+
+            pub const PATTERN: &str = "(?<word>\\w+)";
+
+            pub const CAPTURES: &[(&str, usize)] = &[
+                ("word", 1),
+            ];
+            "#
+        )
+    ));
+
+    // errors keep the previous artifact and the watch running
+    std::fs::write(&source_path, "z to a;")?;
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    std::fs::write(&source_path, r#""a";"#)?;
+
+    assert!(wait_for_contents(
+        &out_path,
+        &unindent(
+            r#"
+            // Generated by viable from pattern.viable, do not edit
+
+            pub const PATTERN: &str = "a";
+
+            pub const CAPTURES: &[(&str, usize)] = &[];
+            "#
+        )
+    ));
+
+    std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()?;
+    let output = child.wait_with_output()?;
+
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)?.contains("error[E0009]: empty range"));
+
+    Ok(())
+}
@@ -0,0 +1,10 @@
+// Generated by viable from date.viable, do not edit
+
+export const pattern = "(?<year>\\d{4})-(\\d{2})\"\\n(?<day>\\d{2})";
+
+export const flags = "";
+
+export const captures = {
+  "year": 1,
+  "day": 3,
+};
@@ -0,0 +1,8 @@
+# Generated by viable from date.viable, do not edit
+
+PATTERN = "(?P<year>\\d{4})-(\\d{2})\"\\n(?P<day>\\d{2})"
+
+CAPTURES = {
+    "year": 1,
+    "day": 3,
+}
@@ -0,0 +1,8 @@
+// Generated by viable from date.viable, do not edit
+
+pub const PATTERN: &str = "(?<year>\\d{4})-(\\d{2})\"\\n(?<day>\\d{2})";
+
+pub const CAPTURES: &[(&str, usize)] = &[
+    ("year", 1),
+    ("day", 3),
+];
@@ -1,5 +1,7 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::measurement::WallTime;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkGroup, Criterion};
 use indoc::indoc;
+use viable_compiler::ast::{to_ast, IncrementalParser};
 use viable_compiler::compiler;
 
 fn criterion_benchmark(criterion: &mut Criterion) {
@@ -137,6 +139,105 @@ fn criterion_benchmark(criterion: &mut Criterion) {
     benchmark_group.bench_function("deeply nested", |bencher| {
         bencher.iter(|| compiler(black_box(deeply_nested_source)));
     });
+
+    large_source_benchmarks(&mut benchmark_group);
+}
+
+fn large_source_benchmarks(benchmark_group: &mut BenchmarkGroup<'_, WallTime>) {
+    statements_benchmark(benchmark_group);
+    invocations_benchmark(benchmark_group);
+    constructs_benchmark(benchmark_group);
+    atoms_benchmark(benchmark_group);
+}
+
+// identifiers can't contain digits, so the index is spelled with a letter for each digit
+fn name(index: usize) -> String {
+    index
+        .to_string()
+        .chars()
+        .filter_map(|digit| char::from_digit(digit.to_digit(10)? + 10, 36))
+        .collect()
+}
+
+fn statements_benchmark(benchmark_group: &mut BenchmarkGroup<'_, WallTime>) {
+    let statements: Vec<String> = (0..500)
+        .map(|index| match index % 5 {
+            0 => format!("let .digits_{} = {{ some of <digit>; }}", name(index)),
+            1 => format!(".digits_{};", name(index - 1)),
+            2 => format!("capture group_{} {{ 2 of \"{index}\"; }}", name(index)),
+            3 => String::from("some of match { <space>; \"batman\"; }"),
+            _ => format!("\"{index}\";"),
+        })
+        .collect();
+    let statements_source = statements.join("\n");
+    let edited_source = statements_source.replace("\"254\";", "\"255\";");
+
+    benchmark_group.bench_function("full conversion (500 statements)", |bencher| {
+        bencher.iter(|| to_ast(black_box(&edited_source)));
+    });
+
+    let mut parser = IncrementalParser::new();
+    let mut edited = false;
+    let _ = parser.update(&statements_source);
+
+    benchmark_group.bench_function("incremental single statement edit (500 statements)", |bencher| {
+        bencher.iter(|| {
+            edited = !edited;
+            let source = if edited { &edited_source } else { &statements_source };
+            let _ = black_box(parser.update(black_box(source)));
+        });
+    });
+}
+
+fn invocations_benchmark(benchmark_group: &mut BenchmarkGroup<'_, WallTime>) {
+    let body: Vec<String> = (0..1_000).map(|index| format!("some of \"{index}\";")).collect();
+    let source = format!("let .body = {{\n{}\n}}\n{}", body.join("\n"), ".body;\n".repeat(100));
+
+    benchmark_group.bench_function("1000 node variable invoked 100 times", |bencher| {
+        bencher.iter(|| compiler(black_box(&source)));
+    });
+}
+
+fn constructs_benchmark(benchmark_group: &mut BenchmarkGroup<'_, WallTime>) {
+    // about 100KB of every kind of statement
+    let source: String = (0..320)
+        .map(|index| {
+            let name = name(index);
+            indoc::formatdoc! {r#"
+                let .word_{name} = {{ some of <word>; }}
+                capture group_{name} {{ 1 to 3 of "ab"; not ahead {{ <digit>; }} }}
+                either {{ "a"; 'b'; `c`; a to f; 0 to 9; }}
+                lazy 3 or more of match {{ .word_{name}; <space>; }}
+                class {{ "x"; a to z; without {{ "q"; }} }}
+                not abc;
+                same as group_{name};
+                up to 4 of char 0x41;
+                let .pair_{name}(x) = {{ .x; "-"; .x; }}
+                .pair_{name}("y");
+            "#}
+        })
+        .collect();
+
+    benchmark_group.bench_function("every construct (100KB)", |bencher| {
+        bencher.iter(|| to_ast(black_box(&source)));
+    });
+}
+
+fn atoms_benchmark(benchmark_group: &mut BenchmarkGroup<'_, WallTime>) {
+    // mostly the same few words, as in pattern files of keywords or tokens
+    let words = ["if", "else", "while", "return", "function"];
+    let source: String = (0..20_000)
+        .map(|index| match index % 4 {
+            0 => format!("\"{}\";\n", words[index % words.len()]),
+            1 => format!("'{}';\n", words[index % words.len()]),
+            2 => format!("`{}`;\n", words[index % words.len()]),
+            _ => format!("\"{index}\";\n"),
+        })
+        .collect();
+
+    benchmark_group.bench_function("atoms (20000 statements)", |bencher| {
+        bencher.iter(|| to_ast(black_box(&source)));
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
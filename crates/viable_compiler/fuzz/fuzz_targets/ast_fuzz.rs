@@ -1,7 +1,7 @@
 #![no_main]
 use libfuzzer_sys::fuzz_target;
-use viable_compiler::{ast::types::ast::*, ast_to_regex};
+use viable_compiler::{ast::types::ast::*, ast_to_regex, target::Target};
 
 fuzz_target!(|data: ViableAst| {
-    drop(ast_to_regex(&data));
+    drop(ast_to_regex(&data, Target::default()));
 });
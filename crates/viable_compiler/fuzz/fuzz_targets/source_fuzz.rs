@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use viable_compiler::{ast::to_ast_lenient, compile_for, target::Target};
+
+fuzz_target!(|source: &str| {
+    drop(to_ast_lenient(source));
+
+    for target in Target::ALL {
+        drop(compile_for(source, target));
+    }
+});
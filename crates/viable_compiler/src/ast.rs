@@ -1,6 +1,47 @@
+mod cleanup;
+mod compose;
 mod consts;
+mod explain;
+mod format;
+pub mod from_regex;
+mod graphemes;
+pub(crate) use self::graphemes::approximate_graphemes;
+mod json;
+mod metrics;
+mod optimization;
+pub(crate) use self::optimization::atom_text;
+pub(crate) use self::utils::raw_regex_captures;
+pub mod outline;
+mod prefix;
+pub mod redos;
+pub mod references;
 pub mod source_to_ast;
 pub mod types;
 mod utils;
-pub use self::source_to_ast::to_ast;
+pub use self::explain::{explain, explain_at};
+pub use self::format::{ast_to_source, ast_to_source_with_flags};
+pub use self::from_regex::regex_to_viable;
+pub use self::json::{ast_to_json, to_ast_json};
+pub use self::metrics::AstMetrics;
+pub use self::optimization::optimize;
+pub use self::outline::outline;
+pub use self::prefix::{literal_prefix, PrefixInfo};
+pub use self::redos::analyze_redos;
+pub use self::references::variable_references;
+pub use self::source_to_ast::{
+    to_ast, to_ast_all_errors, to_ast_lenient, to_ast_with_custom_symbols, to_ast_with_diagnostics, to_ast_with_flags,
+    to_ast_with_imports, to_ast_with_nesting_limit, to_ast_with_symbols, to_ast_with_tests, to_ast_with_variables,
+    IncrementalParser, DEFAULT_NESTING_LIMIT,
+};
+mod substitution;
 mod symbols;
+pub mod transform;
+pub use self::transform::{fold, Transform};
+mod validation;
+pub mod visitor;
+pub(crate) use self::validation::{
+    check_astral_ranges, check_bytes_mode, check_capture_names, duplicate_capture_names,
+};
+pub use self::visitor::{walk, Visitor};
+mod width;
+pub(crate) use self::width::{supports_lookbehind_body, width_bounds};
@@ -0,0 +1,168 @@
+use super::types::ast::{Expression, Group, GroupKind, Span, Spanned, ViableAst, ViableAstNode};
+use super::utils::SharedStatements;
+use crate::errors::CompilerError;
+use crate::prelude::*;
+use crate::warnings::Warning;
+use alloc::rc::Rc;
+
+/// Removes groups that match nothing from a fully expanded AST, so that variables expanding to nothing
+/// don't leave behind empty groups like `(?:)`
+///
+/// Empty `either` groups and quantifiers of nothing are errors and empty capture groups are warnings
+pub fn remove_empty_groups(
+    ast: ViableAst,
+    errors: &mut Vec<Spanned<CompilerError>>,
+    warnings: &mut Vec<Warning>,
+) -> ViableAst {
+    Cleaner {
+        errors,
+        warnings,
+        shared: SharedStatements::default(),
+    }
+    .ast(ast, false)
+}
+
+/// The errors and warnings found while cleaning statements
+type Diagnostics = (Vec<Spanned<CompilerError>>, Vec<Warning>);
+
+struct Cleaner<'diagnostics> {
+    errors: &'diagnostics mut Vec<Spanned<CompilerError>>,
+    warnings: &'diagnostics mut Vec<Warning>,
+    shared: SharedStatements<Diagnostics>,
+}
+
+impl Cleaner<'_> {
+    /// Cleans the statements of a block, the branches of an `either` group are kept even if empty
+    /// as removing one would change what the group matches
+    fn ast(&mut self, ast: ViableAst, branches: bool) -> ViableAst {
+        let ViableAst::Root(nodes) = ast else {
+            return ViableAst::Empty;
+        };
+
+        let nodes = nodes
+            .into_iter()
+            .filter(|node| !(branches && matches!(node.node, ViableAstNode::Skip)))
+            .map(|node| self.node(node))
+            .filter(|node| branches || !is_removable(&node.node))
+            .collect();
+
+        ViableAst::Root(nodes)
+    }
+
+    fn node(&mut self, node: Spanned<ViableAstNode>) -> Spanned<ViableAstNode> {
+        let Spanned { node, span } = node;
+
+        let node = match node {
+            ViableAstNode::Group(group) => ViableAstNode::Group(self.group(group, span)),
+            ViableAstNode::Assertion(mut assertion) => {
+                assertion.statements = Box::new(self.ast(*assertion.statements, false));
+                ViableAstNode::Assertion(assertion)
+            }
+            ViableAstNode::Conditional(mut conditional) => {
+                conditional.then = Box::new(self.ast(*conditional.then, false));
+                conditional.otherwise = conditional
+                    .otherwise
+                    .map(|otherwise| Box::new(self.ast(*otherwise, false)));
+                ViableAstNode::Conditional(conditional)
+            }
+            ViableAstNode::VariableInvocation(mut invocation) => {
+                invocation.statements = self.invocation_statements(invocation.statements);
+                ViableAstNode::VariableInvocation(invocation)
+            }
+            ViableAstNode::Quantifier(mut quantifier) => {
+                let expression_span = quantifier.expression.span;
+
+                if let Expression::Group(group) = quantifier.expression.node {
+                    quantifier.expression.node = Expression::Group(self.group(group, expression_span));
+                }
+
+                let empty = match &quantifier.expression.node {
+                    Expression::Group(group) => group.kind != GroupKind::Either && is_empty(&group.statements),
+                    Expression::Atom(atom) => atom.is_empty(),
+                    _ => false,
+                };
+
+                if empty {
+                    self.errors.push(Spanned {
+                        node: CompilerError::EmptyQuantifiedBlock,
+                        span,
+                    });
+                }
+
+                ViableAstNode::Quantifier(quantifier)
+            }
+            node => node,
+        };
+
+        Spanned { node, span }
+    }
+
+    /// Cleans the statements of an invocation, the diagnostics of statements shared with other invocations are
+    /// reported again for each of them
+    fn invocation_statements(&mut self, statements: Rc<ViableAst>) -> Rc<ViableAst> {
+        if let Some((cleaned, (errors, warnings))) = self.shared.get(&statements) {
+            self.errors.extend(errors);
+            self.warnings.extend(warnings);
+            return cleaned;
+        }
+
+        let statements = match Rc::try_unwrap(statements) {
+            Ok(statements) => return Rc::new(self.ast(statements, false)),
+            Err(statements) => statements,
+        };
+
+        let (error_count, warning_count) = (self.errors.len(), self.warnings.len());
+        let cleaned = Rc::new(self.ast((*statements).clone(), false));
+        let diagnostics = (
+            self.errors[error_count..].to_vec(),
+            self.warnings[warning_count..].to_vec(),
+        );
+
+        self.shared.insert(statements, cleaned.clone(), diagnostics);
+        cleaned
+    }
+
+    fn group(&mut self, mut group: Group, span: Span) -> Group {
+        let branches = group.kind == GroupKind::Either;
+        group.statements = Box::new(self.ast(*group.statements, branches));
+
+        match group.kind {
+            GroupKind::Either if is_empty(&group.statements) => self.errors.push(Spanned {
+                node: CompilerError::EmptyAlternation,
+                span,
+            }),
+            GroupKind::Capture if is_empty(&group.statements) => {
+                self.warnings.push(Warning::EmptyCapture { span });
+            }
+            _ => {}
+        }
+
+        group
+    }
+}
+
+/// Whether a node can be removed without changing what the pattern matches
+fn is_removable(node: &ViableAstNode) -> bool {
+    match node {
+        ViableAstNode::Group(group) => {
+            matches!(
+                group.kind,
+                GroupKind::Match | GroupKind::Atomic | GroupKind::Flagged { .. }
+            ) && is_empty(&group.statements)
+        }
+        _ => false,
+    }
+}
+
+/// Whether a block matches nothing, i.e. only contains skipped nodes and empty atoms or invocations
+fn is_empty(ast: &ViableAst) -> bool {
+    match ast {
+        ViableAst::Root(nodes) => nodes.iter().all(|node| match &node.node {
+            ViableAstNode::Skip => true,
+            ViableAstNode::Atom(atom) => atom.is_empty(),
+            ViableAstNode::VariableInvocation(invocation) => is_empty(&invocation.statements),
+            node => is_removable(node),
+        }),
+        ViableAst::Empty => true,
+    }
+}
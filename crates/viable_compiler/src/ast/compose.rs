@@ -0,0 +1,163 @@
+use super::types::ast::{
+    Expression, Group, GroupKind, Quantifier, QuantifierKind, Span, Spanned, ViableAst, ViableAstNode,
+};
+use crate::prelude::*;
+use alloc::rc::Rc;
+
+/**
+Combinators building a pattern from ASTs at runtime, e.g. from fragments picked by a user, to be compiled with
+[`crate::compile_ast`].
+
+The ASTs produced hold a single statement without a span. Capture groups sharing a name across the combined parts
+are rejected when compiled, as in source.
+
+# Example
+
+```rust
+use viable_compiler::ast::types::ast::ViableAst;
+use viable_compiler::compile_ast;
+use viable_compiler::options::CompilerOptions;
+use viable_compiler::target::Target;
+
+let century = ViableAst::atom("20").captured(Some(String::from("century")));
+let separator = ViableAst::any_of(vec![ViableAst::atom("-"), ViableAst::atom("/")]);
+let pattern = ViableAst::concat(vec![century, separator.optional()]);
+
+let (output, _) = compile_ast(&pattern, Target::default(), &CompilerOptions::default()).unwrap();
+assert_eq!(output, r"(?<century>20)[\-/]?");
+```
+*/
+impl ViableAst {
+    /// An AST matching each of the parts in order
+    #[must_use]
+    pub fn concat(parts: Vec<Self>) -> Self {
+        Self::Root(parts.into_iter().flat_map(Self::into_nodes).collect())
+    }
+
+    /// An AST matching any one of the parts, an empty part matching the empty string like `nothing;`
+    ///
+    /// Without parts, the AST matches the empty string like an `either` group without statements
+    #[must_use]
+    pub fn any_of(parts: Vec<Self>) -> Self {
+        let branches = parts.into_iter().map(Self::into_statement).collect();
+
+        Self::group(GroupKind::Either, None, Self::Root(branches))
+    }
+
+    /// An AST matching the AST or the empty string, like `option of`
+    #[must_use]
+    pub fn optional(self) -> Self {
+        self.repeated(0, Some(1))
+    }
+
+    /// An AST repeating the AST from `min` to `max` times, or `min` times or more without a `max`. A `max` below
+    /// `min` repeats the AST exactly `min` times
+    #[must_use]
+    pub fn repeated(self, min: usize, max: Option<usize>) -> Self {
+        let kind = match (min, max.map(|max| max.max(min))) {
+            (0, None) => QuantifierKind::Any,
+            (1, None) => QuantifierKind::Some,
+            (min, None) => QuantifierKind::AtLeast(min),
+            (0, Some(1)) => QuantifierKind::Option,
+            (0, Some(max)) => QuantifierKind::AtMost(max),
+            (min, Some(max)) if min == max => QuantifierKind::Amount(min),
+            (start, Some(end)) => QuantifierKind::Range { start, end },
+        };
+
+        let quantifier = Quantifier {
+            kind,
+            lazy: false,
+            possessive: false,
+            unit: None,
+            expression: Box::new(Spanned {
+                node: self.into_expression(),
+                span: Span::default(),
+            }),
+        };
+
+        Self::from_nodes(vec![ViableAstNode::Quantifier(quantifier)])
+    }
+
+    /// An AST capturing what the AST matches, in a group named `name` if given
+    #[must_use]
+    pub fn captured(self, name: Option<String>) -> Self {
+        Self::group(GroupKind::Capture, name.map(Into::into), self)
+    }
+
+    fn group(kind: GroupKind, ident: Option<Rc<str>>, statements: Self) -> Self {
+        Self::from_nodes(vec![ViableAstNode::Group(Group {
+            ident,
+            kind,
+            statements: Box::new(statements),
+        })])
+    }
+
+    /// The statements of the AST, without the ones matching nothing
+    fn into_nodes(self) -> Vec<Spanned<ViableAstNode>> {
+        match self {
+            Self::Root(mut nodes) => {
+                nodes.retain(|node| !matches!(node.node, ViableAstNode::Skip));
+                nodes
+            }
+            Self::Empty => Vec::new(),
+        }
+    }
+
+    /// The AST as a single statement, grouping several with a `match` group
+    fn into_statement(self) -> Spanned<ViableAstNode> {
+        let mut nodes = self.into_nodes();
+
+        let node = match nodes.len() {
+            0 => ViableAstNode::Empty,
+            1 => return nodes.remove(0),
+            _ => ViableAstNode::Group(match_group(nodes)),
+        };
+
+        Spanned {
+            node,
+            span: Span::default(),
+        }
+    }
+
+    /// The AST as the expression of a quantifier, grouping statements that can't be quantified on their own (e.g.
+    /// several statements or another quantifier) with a `match` group
+    fn into_expression(self) -> Expression {
+        let mut nodes = self.into_nodes();
+
+        if nodes.len() == 1 {
+            let Spanned { node, span } = nodes.remove(0);
+            match node_expression(node) {
+                Ok(expression) => return expression,
+                Err(node) => nodes.push(Spanned { node, span }),
+            }
+        }
+
+        Expression::Group(match_group(nodes))
+    }
+}
+
+fn match_group(nodes: Vec<Spanned<ViableAstNode>>) -> Group {
+    Group {
+        ident: None,
+        kind: GroupKind::Match,
+        statements: Box::new(ViableAst::Root(nodes)),
+    }
+}
+
+/// The node as a quantifier expression, or the node back if it can't be quantified directly
+fn node_expression(node: ViableAstNode) -> Result<Expression, ViableAstNode> {
+    let expression = match node {
+        ViableAstNode::Group(group) => Expression::Group(group),
+        ViableAstNode::Atom(atom) => Expression::Atom(atom),
+        ViableAstNode::Range(range) => Expression::Range(range),
+        ViableAstNode::Symbol(symbol) => Expression::Symbol(symbol),
+        ViableAstNode::NegativeCharClass(class) => Expression::NegativeCharClass(class),
+        ViableAstNode::CharClass(class) => Expression::CharClass(class),
+        ViableAstNode::UnicodeCategory(category) => Expression::UnicodeCategory(category),
+        ViableAstNode::UnicodeProperty(property) => Expression::UnicodeProperty(property),
+        ViableAstNode::Backreference(backreference) => Expression::Backreference(backreference),
+        node => return Err(node),
+    };
+
+    Ok(expression)
+}
@@ -1,3 +1,124 @@
 pub static NOT: &str = "not";
 pub static LAZY: &str = "lazy";
+pub static POSSESSIVE: &str = "possessive";
 pub static SYMBOL_NAMESPACE_DELIMITER: &str = "::";
+
+/// The names of the symbols outside of a namespace, suggested for misspelled symbols
+pub static SYMBOL_NAMES: &[&str] = &[
+    "alphabetic",
+    "alphanumeric",
+    "backspace",
+    "boundary",
+    "carriage_return",
+    "char",
+    "digit",
+    "end",
+    "feed",
+    "form_feed",
+    "grapheme",
+    "input_end",
+    "input_start",
+    "line_break",
+    "line_end",
+    "line_start",
+    "newline",
+    "null",
+    "return",
+    "space",
+    "start",
+    "tab",
+    "vertical",
+    "vertical_tab",
+    "whitespace",
+    "word",
+    "word_boundary",
+];
+
+/// The keywords starting a group or an assertion, suggested for a misspelled word before a block
+pub static BLOCK_KEYWORDS: &[&str] = &["ahead", "atomic", "behind", "capture", "either", "match"];
+
+/// Unicode scripts usable as `<script::name>`, the name being the lowercased script name
+pub static UNICODE_SCRIPTS: &[&str] = &[
+    "Arabic",
+    "Armenian",
+    "Bengali",
+    "Bopomofo",
+    "Braille",
+    "Canadian_Aboriginal",
+    "Cherokee",
+    "Common",
+    "Coptic",
+    "Cyrillic",
+    "Devanagari",
+    "Ethiopic",
+    "Georgian",
+    "Gothic",
+    "Greek",
+    "Gujarati",
+    "Gurmukhi",
+    "Han",
+    "Hangul",
+    "Hebrew",
+    "Hiragana",
+    "Inherited",
+    "Kannada",
+    "Katakana",
+    "Khmer",
+    "Lao",
+    "Latin",
+    "Malayalam",
+    "Mongolian",
+    "Myanmar",
+    "Ogham",
+    "Old_Italic",
+    "Oriya",
+    "Runic",
+    "Sinhala",
+    "Syriac",
+    "Tagalog",
+    "Tamil",
+    "Telugu",
+    "Thaana",
+    "Thai",
+    "Tibetan",
+    "Yi",
+];
+
+/// Binary unicode properties usable as `<property::name>`, the name being the lowercased property name
+pub static UNICODE_BINARY_PROPERTIES: &[&str] = &[
+    "Alphabetic",
+    "ASCII",
+    "ASCII_Hex_Digit",
+    "Bidi_Control",
+    "Dash",
+    "Default_Ignorable_Code_Point",
+    "Deprecated",
+    "Diacritic",
+    "Emoji",
+    "Emoji_Component",
+    "Emoji_Modifier",
+    "Emoji_Modifier_Base",
+    "Emoji_Presentation",
+    "Extended_Pictographic",
+    "Extender",
+    "Hex_Digit",
+    "Ideographic",
+    "Join_Control",
+    "Lowercase",
+    "Math",
+    "Noncharacter_Code_Point",
+    "Pattern_Syntax",
+    "Pattern_White_Space",
+    "Quotation_Mark",
+    "Radical",
+    "Regional_Indicator",
+    "Sentence_Terminal",
+    "Soft_Dotted",
+    "Terminal_Punctuation",
+    "Unified_Ideograph",
+    "Uppercase",
+    "Variation_Selector",
+    "White_Space",
+    "XID_Continue",
+    "XID_Start",
+];
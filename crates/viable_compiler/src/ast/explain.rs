@@ -0,0 +1,424 @@
+use super::optimization::atom_text;
+use super::source_to_ast::to_ast_with_declarations;
+use super::types::ast::{
+    Assertion, AssertionKind, Backreference, CharClass, ClassItem, Conditional, Expression, Group, GroupKind,
+    Quantifier, QuantifierKind, QuantifierUnit, Range, Recursion, Span, SpecialSymbolKind, Symbol, SymbolKind,
+    UnicodeCategory, UnicodeProperty, UnicodePropertyKind, VariableInvocation, ViableAst, ViableAstNode,
+};
+use crate::prelude::*;
+use crate::types::Result;
+
+/// How long the summary of the statements of a block may get before the rest are only counted
+const SUMMARY_LENGTH: usize = 80;
+
+/**
+Describes what a node matches in plain English, e.g. for the tooltips of an editor.
+
+Quantifiers and assertions describe the statements they hold, blocks summarize their statements until the summary
+reaches a length cap and count the statements left out.
+
+# Example
+
+```rust
+use viable_compiler::ast::explain;
+use viable_compiler::ast::to_ast;
+use viable_compiler::ast::types::ast::ViableAst;
+
+let ViableAst::Root(nodes) = to_ast(r#"3 to 5 of <digit>; not behind { "foo"; }"#).unwrap() else {
+    unreachable!();
+};
+
+assert_eq!(explain(&nodes[0].node), "between 3 and 5 of: any digit");
+assert_eq!(explain(&nodes[1].node), "negative lookbehind for: literal 'foo'");
+```
+*/
+#[must_use]
+pub fn explain(node: &ViableAstNode) -> String {
+    match node {
+        ViableAstNode::Group(group) => group_description(group),
+        ViableAstNode::Assertion(assertion) => assertion_description(assertion),
+        ViableAstNode::Conditional(conditional) => conditional_description(conditional),
+        ViableAstNode::Quantifier(quantifier) => quantifier_description(quantifier),
+        ViableAstNode::Atom(atom) => atom_description(atom),
+        ViableAstNode::Range(range) => range_description(range),
+        ViableAstNode::Symbol(symbol) => symbol_description(symbol),
+        ViableAstNode::SpecialSymbol(special_symbol) => special_symbol_description(special_symbol).to_owned(),
+        ViableAstNode::UnicodeCategory(category) => unicode_category_description(category),
+        ViableAstNode::UnicodeProperty(property) => unicode_property_description(property),
+        ViableAstNode::NegativeCharClass(items) => format!("any character except: {}", class_items(items)),
+        ViableAstNode::CharClass(class) => char_class_description(class),
+        ViableAstNode::VariableInvocation(invocation) => invocation_description(invocation),
+        ViableAstNode::Backreference(backreference) => backreference_description(backreference),
+        ViableAstNode::Recursion(Recursion::Pattern) => String::from("the whole pattern, recursively"),
+        ViableAstNode::Recursion(Recursion::Group(name)) => {
+            format!("the pattern of capture group '{name}', recursively")
+        }
+        ViableAstNode::Parameter(name) => format!("the argument for parameter .{name}"),
+        ViableAstNode::Empty => String::from("the empty string, as an alternative of the enclosing either"),
+        ViableAstNode::RawRegex(regex) => format!("the regex `{regex}`, as written"),
+        ViableAstNode::Skip => String::from("nothing"),
+        ViableAstNode::Error => String::from("a statement that failed to convert"),
+    }
+}
+
+/**
+Describes the innermost node of a source covering the byte `offset`, along with the span of that node.
+
+Statements within variable declarations are found too, a declaration itself is described by its statements.
+Statements that fail to convert are left out like in [`to_ast_lenient`](super::to_ast_lenient), `None` is
+returned when no statement covers the offset.
+
+# Errors
+
+Returns the [`CompilerError::ParseError`](crate::errors::CompilerError::ParseError) if the source doesn't parse
+at all
+
+# Example
+
+```rust
+use viable_compiler::ast::explain_at;
+
+let source = r#"capture { some of <digit>; }"#;
+let (span, description) = explain_at(source, 20).unwrap().unwrap();
+
+assert_eq!(&source[span.start..span.end], "<digit>");
+assert_eq!(description, "any digit");
+```
+*/
+pub fn explain_at(source: &str, offset: usize) -> Result<Option<(Span, String)>> {
+    let (ast, declarations) = to_ast_with_declarations(source)?;
+
+    let declared = declarations
+        .iter()
+        .filter(|declaration| covers(declaration.span, offset))
+        .map(|declaration| {
+            find(&declaration.statements, offset).unwrap_or_else(|| {
+                let description = format!(
+                    "variable .{} matching: {}",
+                    declaration.name,
+                    summary(&declaration.statements, ", then ")
+                );
+                (declaration.span, description)
+            })
+        });
+
+    // declarations nested within blocks are not part of the AST of the block, the smallest span is the innermost
+    Ok(find(&ast, offset)
+        .into_iter()
+        .chain(declared)
+        .min_by_key(|(span, _)| span.end - span.start))
+}
+
+const fn covers(span: Span, offset: usize) -> bool {
+    span.start <= offset && offset < span.end
+}
+
+fn find(ast: &ViableAst, offset: usize) -> Option<(Span, String)> {
+    let ViableAst::Root(nodes) = ast else {
+        return None;
+    };
+    let node = nodes.iter().find(|node| covers(node.span, offset))?;
+
+    let inner = match &node.node {
+        ViableAstNode::Group(group) => find(&group.statements, offset),
+        ViableAstNode::Assertion(assertion) => find(&assertion.statements, offset),
+        ViableAstNode::Conditional(conditional) => find(&conditional.then, offset).or_else(|| {
+            conditional
+                .otherwise
+                .as_ref()
+                .and_then(|otherwise| find(otherwise, offset))
+        }),
+        ViableAstNode::Quantifier(quantifier) => {
+            let expression = &quantifier.expression;
+            match &expression.node {
+                _ if !covers(expression.span, offset) => None,
+                Expression::Group(group) => {
+                    find(&group.statements, offset).or_else(|| Some((expression.span, group_description(group))))
+                }
+                node => Some((expression.span, expression_description(node))),
+            }
+        }
+        // the statements of an invocation are written in the declaration, only the arguments are written here
+        ViableAstNode::VariableInvocation(invocation) => invocation
+            .arguments
+            .iter()
+            .find(|argument| covers(argument.span, offset))
+            .map(|argument| (argument.span, explain(&argument.node))),
+        _ => None,
+    };
+
+    inner.or_else(|| Some((node.span, explain(&node.node))))
+}
+
+/// Joins the descriptions of the statements of a block with `separator`, until the summary reaches
+/// [`SUMMARY_LENGTH`]
+fn summary(ast: &ViableAst, separator: &str) -> String {
+    let descriptions: Vec<String> = match ast {
+        ViableAst::Root(nodes) => nodes
+            .iter()
+            .filter(|node| node.node != ViableAstNode::Skip)
+            .map(|node| explain(&node.node))
+            .collect(),
+        ViableAst::Empty => Vec::new(),
+    };
+
+    if descriptions.is_empty() {
+        return String::from("nothing");
+    }
+
+    let mut summary = String::new();
+    for (index, description) in descriptions.iter().enumerate() {
+        let joined = if index == 0 { 0 } else { separator.len() };
+        if summary.len() + joined + description.len() > SUMMARY_LENGTH {
+            return match (index, descriptions.len() - index) {
+                (0, 1) => String::from("1 statement"),
+                (0, count) => format!("{count} statements"),
+                (_, 1) => format!("{summary}, and 1 more statement"),
+                (_, count) => format!("{summary}, and {count} more statements"),
+            };
+        }
+
+        if index > 0 {
+            summary.push_str(separator);
+        }
+        summary.push_str(description);
+    }
+
+    summary
+}
+
+fn group_description(group: &Group) -> String {
+    let kind = match &group.kind {
+        GroupKind::Match => String::from("group"),
+        GroupKind::Capture => match &group.ident {
+            Some(ident) => format!("capture group '{ident}'"),
+            None => String::from("capture group"),
+        },
+        GroupKind::Either => return format!("either: {}", summary(&group.statements, ", or ")),
+        GroupKind::Atomic => String::from("atomic group"),
+        GroupKind::Flagged { set, clear } => {
+            let flags: Vec<String> = set
+                .iter()
+                .map(|flag| flag.name().to_owned())
+                .chain(clear.iter().map(|flag| format!("not {}", flag.name())))
+                .collect();
+            format!("group with {}", flags.join(", "))
+        }
+    };
+
+    format!("{kind} of: {}", summary(&group.statements, ", then "))
+}
+
+fn assertion_description(assertion: &Assertion) -> String {
+    let negative = if assertion.negative { "negative " } else { "" };
+    let kind = match assertion.kind {
+        AssertionKind::Ahead => "lookahead",
+        AssertionKind::Behind => "lookbehind",
+    };
+
+    format!("{negative}{kind} for: {}", summary(&assertion.statements, ", then "))
+}
+
+fn conditional_description(conditional: &Conditional) -> String {
+    let condition = match &conditional.condition {
+        Backreference::Named(name) => format!("capture group '{name}'"),
+        Backreference::Numbered(index) => format!("capture group {index}"),
+    };
+    let otherwise = conditional
+        .otherwise
+        .as_ref()
+        .map_or_else(|| String::from("nothing"), |otherwise| summary(otherwise, ", then "));
+
+    format!(
+        "if {condition} matched: {}, otherwise: {otherwise}",
+        summary(&conditional.then, ", then ")
+    )
+}
+
+fn quantifier_description(quantifier: &Quantifier) -> String {
+    let quantity = match &quantifier.kind {
+        QuantifierKind::Range { start, end } => format!("between {start} and {end}"),
+        QuantifierKind::Some => String::from("one or more"),
+        QuantifierKind::Any => String::from("zero or more"),
+        QuantifierKind::Over(amount) | QuantifierKind::AtLeast(amount) => format!("at least {amount}"),
+        QuantifierKind::AtMost(amount) => format!("at most {amount}"),
+        QuantifierKind::Option => String::from("optionally one"),
+        QuantifierKind::Amount(amount) => format!("exactly {amount}"),
+    };
+    let mode = match (quantifier.lazy, quantifier.possessive) {
+        (true, _) => ", as few as possible,",
+        (false, true) => ", without backtracking,",
+        (false, false) => "",
+    };
+    let unit = match quantifier.unit {
+        Some(QuantifierUnit::Chars) => " (in characters)",
+        Some(QuantifierUnit::Bytes) => " (in bytes)",
+        None => "",
+    };
+
+    format!(
+        "{quantity}{unit}{mode} of: {}",
+        expression_description(&quantifier.expression.node)
+    )
+}
+
+fn expression_description(expression: &Expression) -> String {
+    match expression {
+        Expression::Atom(atom) => atom_description(atom),
+        Expression::Group(group) => group_description(group),
+        Expression::Symbol(symbol) => symbol_description(symbol),
+        Expression::UnicodeCategory(category) => unicode_category_description(category),
+        Expression::UnicodeProperty(property) => unicode_property_description(property),
+        Expression::Range(range) => range_description(range),
+        Expression::NegativeCharClass(items) => format!("any character except: {}", class_items(items)),
+        Expression::CharClass(class) => char_class_description(class),
+        Expression::Backreference(backreference) => backreference_description(backreference),
+    }
+}
+
+fn atom_description(atom: &str) -> String {
+    match atom_text(atom) {
+        Some(text) => format!("literal '{}'", text.escape_debug()),
+        None => format!("raw regex `{atom}`"),
+    }
+}
+
+fn char_description(char: char) -> String {
+    format!("'{}'", char.escape_debug())
+}
+
+fn range_description(range: &Range) -> String {
+    let (negative, start, end, kind) = match range {
+        Range::CharRange(range) => (range.negative, range.start, range.end, "character"),
+        Range::NumericRange(range) => (range.negative, range.start, range.end, "digit"),
+    };
+    let except = if negative { "except" } else { "from" };
+
+    format!(
+        "any {kind} {except} {} to {}",
+        char_description(start),
+        char_description(end)
+    )
+}
+
+fn symbol_description(symbol: &Symbol) -> String {
+    let description = match symbol.kind {
+        SymbolKind::Space => "a space",
+        SymbolKind::Newline => "a line feed",
+        SymbolKind::Vertical => "a vertical tab",
+        SymbolKind::Return => "a carriage return",
+        SymbolKind::Tab => "a tab",
+        SymbolKind::Null => "a null character",
+        SymbolKind::Whitespace => "any whitespace",
+        SymbolKind::Alphabetic => "any ASCII letter",
+        SymbolKind::Alphanumeric => "any ASCII letter or digit",
+        SymbolKind::Char => "any character",
+        SymbolKind::Digit => "any digit",
+        SymbolKind::Word => "any word character",
+        SymbolKind::Feed => "a form feed",
+        SymbolKind::Backspace => "a backspace",
+        SymbolKind::Grapheme => "a grapheme cluster",
+        SymbolKind::LineBreak => "a line break",
+        SymbolKind::Boundary => {
+            return String::from(if symbol.negative {
+                "not a word boundary"
+            } else {
+                "a word boundary"
+            })
+        }
+    };
+
+    if symbol.negative {
+        format!("anything but {description}")
+    } else {
+        description.to_owned()
+    }
+}
+
+const fn special_symbol_description(special_symbol: &SpecialSymbolKind) -> &'static str {
+    match special_symbol {
+        SpecialSymbolKind::Start => "the start of the input",
+        SpecialSymbolKind::End => "the end of the input",
+        SpecialSymbolKind::LineStart => "the start of a line",
+        SpecialSymbolKind::LineEnd => "the end of a line",
+        SpecialSymbolKind::InputStart => "the very start of the input",
+        SpecialSymbolKind::InputEnd => "the very end of the input",
+        SpecialSymbolKind::WordBoundary => "a word boundary",
+        SpecialSymbolKind::NotWordBoundary => "not a word boundary",
+    }
+}
+
+fn unicode_category_description(category: &UnicodeCategory) -> String {
+    // the variants are named after the categories, e.g. `UppercaseLetter` for "uppercase letter"
+    let mut name = String::new();
+    for char in format!("{:?}", category.kind).chars() {
+        if char.is_uppercase() && !name.is_empty() {
+            name.push(' ');
+        }
+        name.push(char.to_ascii_lowercase());
+    }
+    let not = if category.negative { "not " } else { "" };
+
+    format!("any character {not}in the category '{name}'")
+}
+
+fn unicode_property_description(property: &UnicodeProperty) -> String {
+    let not = if property.negative { "not " } else { "" };
+
+    match property.kind {
+        UnicodePropertyKind::Script => format!("any character {not}of the {} script", property.name),
+        UnicodePropertyKind::Binary => format!("any character {not}with the {} property", property.name),
+    }
+}
+
+fn char_class_description(class: &CharClass) -> String {
+    if class.negative {
+        format!("any character except: {}", class_items(&class.items))
+    } else {
+        format!("any of: {}", class_items(&class.items))
+    }
+}
+
+fn class_items(items: &[ClassItem]) -> String {
+    let descriptions: Vec<String> = items.iter().map(class_item_description).collect();
+    descriptions.join(", ")
+}
+
+fn class_item_description(item: &ClassItem) -> String {
+    match item {
+        ClassItem::Range(Range::CharRange(range)) => {
+            let not = if range.negative { "not " } else { "" };
+            format!(
+                "{not}{} to {}",
+                char_description(range.start),
+                char_description(range.end)
+            )
+        }
+        ClassItem::Range(Range::NumericRange(range)) => {
+            let not = if range.negative { "not " } else { "" };
+            format!("{not}{} to {}", range.start, range.end)
+        }
+        ClassItem::Char(char) => char_description(*char),
+        ClassItem::Raw(raw) => format!("raw regex `{raw}`"),
+        ClassItem::Symbol(symbol) => symbol_description(symbol),
+        ClassItem::UnicodeCategory(category) => unicode_category_description(category),
+        ClassItem::UnicodeProperty(property) => unicode_property_description(property),
+        ClassItem::Subtract(items) => format!("but not ({})", class_items(items)),
+        ClassItem::Intersect(items) => format!("only if also ({})", class_items(items)),
+    }
+}
+
+fn invocation_description(invocation: &VariableInvocation) -> String {
+    format!(
+        "variable .{} matching: {}",
+        invocation.name,
+        summary(&invocation.statements, ", then ")
+    )
+}
+
+fn backreference_description(backreference: &Backreference) -> String {
+    match backreference {
+        Backreference::Named(name) => format!("the same text as capture group '{name}'"),
+        Backreference::Numbered(index) => format!("the same text as capture group {index}"),
+    }
+}
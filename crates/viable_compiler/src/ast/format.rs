@@ -0,0 +1,732 @@
+use super::optimization::atom_units;
+use super::substitution::substitute_parameters;
+use super::types::ast::{
+    Assertion, AssertionKind, Backreference, CharClass, ClassItem, Conditional, Expression, Flag, Group, GroupKind,
+    PatternFlags, Quantifier, QuantifierKind, Range, Recursion, Spanned, SpecialSymbolKind, Symbol, SymbolKind,
+    UnicodeCategory, UnicodeCategoryKind, UnicodeProperty, UnicodePropertyKind, VariableInvocation, ViableAst,
+    ViableAstNode,
+};
+use crate::options::FormatOptions;
+use crate::prelude::*;
+use crate::types::HashMap;
+use alloc::rc::Rc;
+
+/**
+Formats an AST back to canonical Viable source, one statement per line.
+
+Invoked variables are declared before the first statement invoking them and referenced by name, parameterized
+variables with their parameters. A variable whose name is reused for different statements is declared again
+before the statements invoking it. Variables whose statements were rewritten after parsing (e.g. by
+[`crate::ast::optimize`]) no longer match their declaration, so those invocations are written out in place.
+
+Flags aren't part of the AST, see [`ast_to_source_with_flags`] to write them.
+
+# Example
+
+```rust
+use viable_compiler::ast::{ast_to_source, to_ast};
+use viable_compiler::options::FormatOptions;
+
+let ast = to_ast(r#"let .digits = { some of <digit>; } capture { .digits; }"#).unwrap();
+let source = ast_to_source(&ast, &FormatOptions::default());
+
+assert_eq!(source, "let .digits = {\n  some of <digit>;\n}\ncapture {\n  .digits;\n}\n");
+```
+*/
+#[must_use]
+pub fn ast_to_source(ast: &ViableAst, options: &FormatOptions) -> String {
+    ast_to_source_with_flags(ast, &PatternFlags::default(), options)
+}
+
+/**
+Formats an AST back to canonical Viable source like [`ast_to_source`], starting with a `flags` block of the
+flags, e.g. those returned by [`crate::ast::to_ast_with_flags`].
+
+# Example
+
+```rust
+use viable_compiler::ast::{ast_to_source_with_flags, to_ast_with_flags};
+use viable_compiler::options::FormatOptions;
+
+let (ast, flags) = to_ast_with_flags(r#"flags { insensitive; } "a";"#).unwrap();
+let source = ast_to_source_with_flags(&ast, &flags, &FormatOptions::default());
+
+assert_eq!(source, "flags {\n  insensitive;\n}\n\"a\";\n");
+```
+*/
+#[must_use]
+pub fn ast_to_source_with_flags(ast: &ViableAst, flags: &PatternFlags, options: &FormatOptions) -> String {
+    let mut formatter = Formatter {
+        options,
+        declared: Vec::new(),
+    };
+
+    let mut source = String::new();
+
+    if !flags.is_empty() {
+        let indentation = formatter.indentation(1);
+        source.push_str("flags {\n");
+        for flag in &flags.flags {
+            source.push_str(&format!("{indentation}{};\n", flag_name(*flag)));
+        }
+        source.push_str("}\n");
+    }
+
+    source.push_str(&formatter.statements(ast, 0));
+
+    source
+}
+
+struct Formatter<'options> {
+    options: &'options FormatOptions,
+    /// the variables declared so far in the formatted source, a redeclaration replacing the declaration of its name
+    declared: Vec<Declared>,
+}
+
+/// A variable declared in the formatted source
+#[derive(PartialEq, Eq)]
+struct Declared {
+    name: Rc<str>,
+    parameters: Vec<Rc<str>>,
+    /// the statements of the declaration, with the parameters unsubstituted
+    statements: Rc<ViableAst>,
+}
+
+impl Declared {
+    /// The declaration an invocation was expanded from, `None` if it can't be written back to source: the name
+    /// isn't an identifier (e.g. custom symbols with a `:`) or an argument isn't one an invocation can take
+    fn of(invocation: &VariableInvocation) -> Option<Self> {
+        let identifier = invocation
+            .name
+            .chars()
+            .all(|char| char.is_ascii_alphabetic() || char == '_');
+        if invocation.name.is_empty()
+            || !identifier
+            || invocation
+                .arguments
+                .iter()
+                .any(|argument| argument_source(&argument.node).is_none())
+        {
+            return None;
+        }
+
+        let (parameters, statements) = match &invocation.declaration {
+            Some(declaration) => (declaration.parameters.clone(), Rc::clone(&declaration.statements)),
+            None if invocation.arguments.is_empty() => (Vec::new(), Rc::clone(&invocation.statements)),
+            None => return None,
+        };
+
+        Some(Self {
+            name: invocation.name.clone(),
+            parameters,
+            statements,
+        })
+    }
+}
+
+impl Formatter<'_> {
+    /// Formats statements, each preceded by the declarations of the variables it invokes that aren't declared yet
+    fn statements(&mut self, ast: &ViableAst, level: usize) -> String {
+        let ViableAst::Root(nodes) = ast else {
+            return String::new();
+        };
+
+        let mut source = String::new();
+
+        for node in nodes {
+            let mut invocations = Vec::new();
+            node_invocations(&node.node, &mut invocations);
+
+            let mut declared = Vec::new();
+            for invocation in invocations {
+                source.push_str(&self.declare(invocation, level, &mut declared));
+            }
+
+            source.push_str(&self.node(&node.node, level));
+        }
+
+        source
+    }
+
+    /// Formats the declaration of an invoked variable unless it is the one in scope, after the declarations of the
+    /// variables its statements invoke
+    ///
+    /// `declared` holds the names declared for the statement being formatted. A statement invoking two variables
+    /// of the same name can't be preceded by both declarations, so the second is left to the block of the
+    /// statement invoking it, or written out in place
+    fn declare(&mut self, invocation: &VariableInvocation, level: usize, declared: &mut Vec<Rc<str>>) -> String {
+        let Some(declaration) = Declared::of(invocation) else {
+            return String::new();
+        };
+
+        if self.declared.contains(&declaration) || declared.contains(&declaration.name) {
+            return String::new();
+        }
+
+        let mut invocations = Vec::new();
+        ast_invocations(&declaration.statements, &mut invocations);
+
+        let mut source = String::new();
+        for invocation in invocations {
+            source.push_str(&self.declare(invocation, level, declared));
+        }
+
+        let body = self.statements(&declaration.statements, level + 1);
+
+        // blocks can't be empty, so variables matching nothing are left out
+        if body.is_empty() {
+            return source;
+        }
+
+        let parameters = if declaration.parameters.is_empty() {
+            String::new()
+        } else {
+            format!("({})", declaration.parameters.join(", "))
+        };
+        let indentation = self.indentation(level);
+        source.push_str(&format!(
+            "{indentation}let .{}{parameters} = {{\n{body}{indentation}}}\n",
+            declaration.name
+        ));
+
+        declared.push(declaration.name.clone());
+        self.declared.retain(|other| other.name != declaration.name);
+        self.declared.push(declaration);
+
+        source
+    }
+
+    fn indentation(&self, level: usize) -> String {
+        " ".repeat(self.options.indent * level)
+    }
+
+    /// Formats a node as the lines of its statement, empty for nodes not written to source
+    fn node(&mut self, node: &ViableAstNode, level: usize) -> String {
+        let statement = match node {
+            ViableAstNode::Group(group) => self.group(group, level),
+            ViableAstNode::Assertion(assertion) => self.assertion(assertion, level),
+            ViableAstNode::Conditional(conditional) => self.conditional(conditional, level),
+            ViableAstNode::Quantifier(quantifier) => self.quantifier(quantifier, level),
+            ViableAstNode::VariableInvocation(invocation) => return self.invocation(invocation, level),
+            ViableAstNode::CharClass(class) => self.char_class(class, level),
+            ViableAstNode::Atom(atom) => format!("{};", atom_source(atom)),
+            ViableAstNode::Range(range) => format!("{};", range_source(range)),
+            ViableAstNode::Symbol(symbol) => format!("{};", symbol_source(symbol)),
+            ViableAstNode::SpecialSymbol(special_symbol) => format!("{};", special_symbol_source(special_symbol)),
+            ViableAstNode::UnicodeCategory(category) => format!("{};", unicode_category_source(category)),
+            ViableAstNode::UnicodeProperty(property) => format!("{};", unicode_property_source(property)),
+            ViableAstNode::NegativeCharClass(items) => self.negative_char_class(items, level),
+            ViableAstNode::Backreference(backreference) => format!("{};", backreference_source(backreference)),
+            ViableAstNode::Recursion(recursion) => format!("{};", recursion_source(recursion)),
+            ViableAstNode::Parameter(name) => format!(".{name};"),
+            ViableAstNode::Empty => String::from("nothing;"),
+            ViableAstNode::RawRegex(regex) => format!("raw_regex {};", string_source(regex)),
+            ViableAstNode::Skip | ViableAstNode::Error => return String::new(),
+        };
+
+        format!("{}{statement}\n", self.indentation(level))
+    }
+
+    /// Whether an invocation can be written as a reference to the declaration in scope, whose statements, with the
+    /// arguments substituted for its parameters, are those of the invocation
+    fn is_declared(&self, invocation: &VariableInvocation) -> bool {
+        let Some(declaration) = Declared::of(invocation) else {
+            return false;
+        };
+
+        if !self.declared.contains(&declaration) {
+            return false;
+        }
+
+        if declaration.parameters.is_empty() {
+            return true;
+        }
+
+        let bindings: HashMap<&str, &Spanned<ViableAstNode>> = declaration
+            .parameters
+            .iter()
+            .map(|parameter| &**parameter)
+            .zip(&invocation.arguments)
+            .collect();
+
+        substitute_parameters((*declaration.statements).clone(), &bindings) == *invocation.statements
+    }
+
+    fn invocation(&mut self, invocation: &VariableInvocation, level: usize) -> String {
+        if !self.is_declared(invocation) {
+            return self.statements(&invocation.statements, level);
+        }
+
+        let arguments = if invocation.arguments.is_empty() {
+            String::new()
+        } else {
+            let arguments: Vec<String> = invocation
+                .arguments
+                .iter()
+                .filter_map(|argument| argument_source(&argument.node))
+                .collect();
+            format!("({})", arguments.join(", "))
+        };
+
+        format!("{}.{}{arguments};\n", self.indentation(level), invocation.name)
+    }
+
+    /// Formats the branches of an `either` group, invocations written out in place are grouped so that
+    /// their statements remain a single branch
+    fn branches(&mut self, ast: &ViableAst, level: usize) -> String {
+        let ViableAst::Root(nodes) = ast else {
+            return String::new();
+        };
+
+        let mut source = String::new();
+
+        for node in nodes {
+            let mut invocations = Vec::new();
+            node_invocations(&node.node, &mut invocations);
+
+            let mut declared = Vec::new();
+            for invocation in invocations {
+                source.push_str(&self.declare(invocation, level, &mut declared));
+            }
+
+            source.push_str(&match &node.node {
+                ViableAstNode::VariableInvocation(invocation) if !self.is_declared(invocation) => {
+                    let indentation = self.indentation(level);
+                    let block = self.block(&invocation.statements, level);
+                    format!("{indentation}match {block}\n")
+                }
+                node => self.node(node, level),
+            });
+        }
+
+        source
+    }
+
+    /// Formats a block's statements between braces, the closing brace is indented to `level`
+    fn block(&mut self, ast: &ViableAst, level: usize) -> String {
+        let body = self.statements(ast, level + 1);
+        self.braces(body, level)
+    }
+
+    fn braces(&self, mut body: String, level: usize) -> String {
+        // blocks can't be empty, an empty literal matches the same nothing
+        if body.is_empty() {
+            body = format!("{}\"\";\n", self.indentation(level + 1));
+        }
+
+        format!("{{\n{body}{}}}", self.indentation(level))
+    }
+
+    fn group(&mut self, group: &Group, level: usize) -> String {
+        let kind = match &group.kind {
+            GroupKind::Match => String::from("match"),
+            GroupKind::Capture => match &group.ident {
+                Some(ident) => format!("capture {ident}"),
+                None => String::from("capture"),
+            },
+            GroupKind::Either => String::from("either"),
+            GroupKind::Atomic => String::from("atomic"),
+            GroupKind::Flagged { set, clear } => set
+                .iter()
+                .map(|flag| flag_name(*flag).to_owned())
+                .chain(clear.iter().map(|flag| format!("not {}", flag_name(*flag))))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+
+        let body = if group.kind == GroupKind::Either {
+            self.branches(&group.statements, level + 1)
+        } else {
+            self.statements(&group.statements, level + 1)
+        };
+
+        format!("{kind} {}", self.braces(body, level))
+    }
+
+    fn assertion(&mut self, assertion: &Assertion, level: usize) -> String {
+        let kind = match assertion.kind {
+            AssertionKind::Ahead => "ahead",
+            AssertionKind::Behind => "behind",
+        };
+        let not = if assertion.negative { "not " } else { "" };
+
+        format!("{not}{kind} {}", self.block(&assertion.statements, level))
+    }
+
+    fn conditional(&mut self, conditional: &Conditional, level: usize) -> String {
+        let condition = match &conditional.condition {
+            Backreference::Named(name) => name.to_string(),
+            Backreference::Numbered(index) => index.to_string(),
+        };
+        let then = self.block(&conditional.then, level);
+
+        match &conditional.otherwise {
+            Some(otherwise) => format!("if captured {condition} {then} else {}", self.block(otherwise, level)),
+            None => format!("if captured {condition} {then}"),
+        }
+    }
+
+    fn quantifier(&mut self, quantifier: &Quantifier, level: usize) -> String {
+        let mut quantity = String::new();
+
+        if quantifier.lazy {
+            quantity.push_str("lazy ");
+        }
+        if quantifier.possessive {
+            quantity.push_str("possessive ");
+        }
+
+        quantity.push_str(&match &quantifier.kind {
+            QuantifierKind::Range { start, end } => format!("{start} to {end}"),
+            QuantifierKind::Some => String::from("some"),
+            QuantifierKind::Any => String::from("any"),
+            // `over n` matches more than n times, so holds n + 1
+            QuantifierKind::Over(amount) => format!("over {}", amount.saturating_sub(1)),
+            QuantifierKind::AtLeast(amount) => format!("{amount} or more"),
+            QuantifierKind::AtMost(amount) => format!("up to {amount}"),
+            QuantifierKind::Option => String::from("option"),
+            QuantifierKind::Amount(amount) => amount.to_string(),
+        });
+        if let Some(unit) = quantifier.unit {
+            quantity.push(' ');
+            quantity.push_str(unit.name());
+        }
+
+        let expression = match &quantifier.expression.node {
+            Expression::Group(group) => match wrapped_statement(group) {
+                Some(ViableAstNode::RawRegex(regex)) => format!("raw_regex {};", string_source(regex)),
+                Some(ViableAstNode::Conditional(conditional)) => self.conditional(conditional, level),
+                Some(ViableAstNode::Recursion(recursion)) => format!("{};", recursion_source(recursion)),
+                Some(ViableAstNode::VariableInvocation(invocation)) if self.is_declared(invocation) => {
+                    self.invocation(invocation, 0).trim_end().to_owned()
+                }
+                _ => self.group(group, level),
+            },
+            Expression::CharClass(class) => self.char_class(class, level),
+            Expression::NegativeCharClass(items) => self.negative_char_class(items, level),
+            Expression::Atom(atom) => format!("{};", atom_source(atom)),
+            Expression::Range(range) => format!("{};", range_source(range)),
+            Expression::Symbol(symbol) => format!("{};", symbol_source(symbol)),
+            Expression::UnicodeCategory(category) => format!("{};", unicode_category_source(category)),
+            Expression::UnicodeProperty(property) => format!("{};", unicode_property_source(property)),
+            Expression::Backreference(backreference) => format!("{};", backreference_source(backreference)),
+        };
+
+        format!("{quantity} of {expression}")
+    }
+
+    fn negative_char_class(&mut self, items: &[ClassItem], level: usize) -> String {
+        match class_shorthand(items) {
+            Some(shorthand) => format!("not {shorthand};"),
+            None => self.char_class(
+                &CharClass {
+                    negative: true,
+                    items: items.to_vec(),
+                },
+                level,
+            ),
+        }
+    }
+
+    fn char_class(&self, class: &CharClass, level: usize) -> String {
+        let not = if class.negative { "not " } else { "" };
+
+        format!(
+            "{not}class {{\n{}{}}}",
+            self.class_items(&class.items, level + 1),
+            self.indentation(level)
+        )
+    }
+
+    fn class_items(&self, items: &[ClassItem], level: usize) -> String {
+        let indentation = self.indentation(level);
+        let mut source = String::new();
+
+        for item in items {
+            let item = match item {
+                ClassItem::Range(range) => format!("{};", range_source(range)),
+                ClassItem::Char(char) => format!("{};", char_source(*char)),
+                ClassItem::Raw(raw) => format!("{};", raw_source(raw)),
+                ClassItem::Symbol(symbol) => format!("{};", symbol_source(symbol)),
+                ClassItem::UnicodeCategory(category) => format!("{};", unicode_category_source(category)),
+                ClassItem::UnicodeProperty(property) => format!("{};", unicode_property_source(property)),
+                ClassItem::Subtract(items) => {
+                    format!("without {{\n{}{indentation}}}", self.class_items(items, level + 1))
+                }
+                ClassItem::Intersect(items) => {
+                    format!("and {{\n{}{indentation}}}", self.class_items(items, level + 1))
+                }
+            };
+            source.push_str(&format!("{indentation}{item}\n"));
+        }
+
+        source
+    }
+}
+
+/// Collects the invocations within the statements of an AST, but not those within the statements of the invoked
+/// variables
+fn ast_invocations<'ast>(ast: &'ast ViableAst, invocations: &mut Vec<&'ast VariableInvocation>) {
+    if let ViableAst::Root(nodes) = ast {
+        for node in nodes {
+            node_invocations(&node.node, invocations);
+        }
+    }
+}
+
+fn node_invocations<'ast>(node: &'ast ViableAstNode, invocations: &mut Vec<&'ast VariableInvocation>) {
+    match node {
+        ViableAstNode::Group(group) => ast_invocations(&group.statements, invocations),
+        ViableAstNode::Assertion(assertion) => ast_invocations(&assertion.statements, invocations),
+        ViableAstNode::Conditional(conditional) => {
+            ast_invocations(&conditional.then, invocations);
+            if let Some(otherwise) = &conditional.otherwise {
+                ast_invocations(otherwise, invocations);
+            }
+        }
+        ViableAstNode::Quantifier(quantifier) => {
+            if let Expression::Group(group) = &quantifier.expression.node {
+                ast_invocations(&group.statements, invocations);
+            }
+        }
+        ViableAstNode::VariableInvocation(invocation) => invocations.push(invocation),
+        _ => {}
+    }
+}
+
+/// Formats the argument of an invocation, `None` for nodes an argument can't be
+fn argument_source(node: &ViableAstNode) -> Option<String> {
+    match node {
+        ViableAstNode::Atom(atom) => Some(atom_source(atom)),
+        ViableAstNode::Range(range) => Some(range_source(range)),
+        ViableAstNode::Symbol(symbol) => Some(symbol_source(symbol)),
+        ViableAstNode::SpecialSymbol(special_symbol) => Some(special_symbol_source(special_symbol).to_owned()),
+        ViableAstNode::UnicodeCategory(category) => Some(unicode_category_source(category)),
+        ViableAstNode::UnicodeProperty(property) => Some(unicode_property_source(property)),
+        _ => None,
+    }
+}
+
+/// Formats an atom as a literal, or as a raw atom if it holds regex syntax a literal can't express
+fn atom_source(atom: &str) -> String {
+    let literal = atom_units(atom).and_then(|units| {
+        units
+            .into_iter()
+            .map(|unit| {
+                let mut chars = unit.chars();
+                match (chars.next(), chars.next()) {
+                    (Some('"'), None) => Some(String::from("\\\"")),
+                    // literals escape regex metacharacters themselves
+                    (Some('\\'), Some(escaped)) if "[](){}*+?|^$.".contains(escaped) => Some(escaped.to_string()),
+                    (Some(_), None) | (Some('\\'), Some('\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' | 'x' | 'u')) => {
+                        Some(unit.to_owned())
+                    }
+                    _ => None,
+                }
+            })
+            .collect::<Option<String>>()
+    });
+
+    match literal {
+        Some(literal) => format!("\"{literal}\""),
+        None => raw_source(atom),
+    }
+}
+
+fn raw_source(raw: &str) -> String {
+    format!("`{}`", raw.replace('`', "\\`"))
+}
+
+/// The statement a `match` group holds alone if it is one that is wrapped in such a group when quantified, a
+/// `raw_regex` statement, a conditional, a recursion or an invocation
+fn wrapped_statement(group: &Group) -> Option<&ViableAstNode> {
+    match (&group.kind, &group.ident, group.statements.as_ref()) {
+        (GroupKind::Match, None, ViableAst::Root(nodes)) => match nodes.as_slice() {
+            [Spanned {
+                node:
+                    node @ (ViableAstNode::RawRegex(_)
+                    | ViableAstNode::Conditional(_)
+                    | ViableAstNode::Recursion(_)
+                    | ViableAstNode::VariableInvocation(_)),
+                ..
+            }] => Some(node),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn recursion_source(recursion: &Recursion) -> String {
+    match recursion {
+        Recursion::Pattern => String::from("recurse"),
+        Recursion::Group(name) => format!("recurse {name}"),
+    }
+}
+
+/// Formats text as a literal of the same characters, escaping what the literal would otherwise unescape
+fn string_source(text: &str) -> String {
+    let mut literal = String::from("\"");
+
+    for char in text.chars() {
+        match char {
+            '"' | '\\' => {
+                literal.push('\\');
+                literal.push(char);
+            }
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            char if char.is_control() => literal.push_str(&format!("\\u{{{:X}}}", u32::from(char))),
+            char => literal.push(char),
+        }
+    }
+
+    literal.push('"');
+    literal
+}
+
+/// Formats a single character as a literal, or as a `char` statement if it is a control character
+fn char_source(char: char) -> String {
+    match char {
+        '"' | '\\' => format!("\"\\{char}\""),
+        char if char.is_control() => format!("char 0x{:X}", u32::from(char)),
+        char => format!("\"{char}\""),
+    }
+}
+
+fn range_source(range: &Range) -> String {
+    let (negative, start, end) = match range {
+        Range::CharRange(range) => (range.negative, range.start, range.end),
+        Range::NumericRange(range) => (range.negative, range.start, range.end),
+    };
+    let not = if negative { "not " } else { "" };
+
+    format!("{not}{start} to {end}")
+}
+
+/// Formats the members of a class as `abc` or `a-z` if they can be written as a `not abc;` shorthand
+fn class_shorthand(items: &[ClassItem]) -> Option<String> {
+    // a `-` between two members would be read as a range
+    let member = |char: char| (char.is_ascii_alphanumeric() || "_\\@*$#&^!%]".contains(char)).then_some(char);
+
+    items
+        .iter()
+        .map(|item| match item {
+            ClassItem::Char(char) => member(*char).map(String::from),
+            ClassItem::Range(Range::CharRange(range)) if !range.negative => {
+                Some(format!("{}-{}", member(range.start)?, member(range.end)?))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn symbol_source(symbol: &Symbol) -> String {
+    let name = match symbol.kind {
+        SymbolKind::Space => "space",
+        SymbolKind::Newline => "newline",
+        SymbolKind::Vertical => "vertical",
+        SymbolKind::Return => "return",
+        SymbolKind::Tab => "tab",
+        SymbolKind::Null => "null",
+        SymbolKind::Whitespace => "whitespace",
+        SymbolKind::Alphabetic => "alphabetic",
+        SymbolKind::Alphanumeric => "alphanumeric",
+        SymbolKind::Char => "char",
+        SymbolKind::Digit => "digit",
+        SymbolKind::Word => "word",
+        SymbolKind::Feed => "feed",
+        SymbolKind::Backspace => "backspace",
+        SymbolKind::Boundary => "boundary",
+        SymbolKind::Grapheme => "grapheme",
+        SymbolKind::LineBreak => "line_break",
+    };
+    let not = if symbol.negative { "not " } else { "" };
+
+    format!("{not}<{name}>")
+}
+
+fn special_symbol_source(special_symbol: &SpecialSymbolKind) -> &'static str {
+    match special_symbol {
+        SpecialSymbolKind::Start => "<start>",
+        SpecialSymbolKind::End => "<end>",
+        SpecialSymbolKind::LineStart => "<line_start>",
+        SpecialSymbolKind::LineEnd => "<line_end>",
+        SpecialSymbolKind::InputStart => "<input_start>",
+        SpecialSymbolKind::InputEnd => "<input_end>",
+        SpecialSymbolKind::WordBoundary => "<word_boundary>",
+        SpecialSymbolKind::NotWordBoundary => "not <word_boundary>",
+    }
+}
+
+fn unicode_category_source(category: &UnicodeCategory) -> String {
+    let name = match category.kind {
+        UnicodeCategoryKind::CasedLetter => "cased_letter",
+        UnicodeCategoryKind::ClosePunctuation => "close_punctuation",
+        UnicodeCategoryKind::ConnectorPunctuation => "connector_punctuation",
+        UnicodeCategoryKind::Control => "control",
+        UnicodeCategoryKind::CurrencySymbol => "currency_symbol",
+        UnicodeCategoryKind::DashPunctuation => "dash_punctuation",
+        UnicodeCategoryKind::DecimalDigitNumber => "decimal_digit_number",
+        UnicodeCategoryKind::EnclosingMark => "enclosing_mark",
+        UnicodeCategoryKind::FinalPunctuation => "final_punctuation",
+        UnicodeCategoryKind::Format => "format",
+        UnicodeCategoryKind::InitialPunctuation => "initial_punctuation",
+        UnicodeCategoryKind::LetterNumber => "letter_number",
+        UnicodeCategoryKind::Letter => "letter",
+        UnicodeCategoryKind::LineSeparator => "line_separator",
+        UnicodeCategoryKind::LowercaseLetter => "lowercase_letter",
+        UnicodeCategoryKind::Mark => "mark",
+        UnicodeCategoryKind::MathSymbol => "math_symbol",
+        UnicodeCategoryKind::ModifierLetter => "modifier_letter",
+        UnicodeCategoryKind::ModifierSymbol => "modifier_symbol",
+        UnicodeCategoryKind::NonSpacingMark => "non_spacing_mark",
+        UnicodeCategoryKind::Number => "number",
+        UnicodeCategoryKind::OpenPunctuation => "open_punctuation",
+        UnicodeCategoryKind::OtherLetter => "other_letter",
+        UnicodeCategoryKind::OtherNumber => "other_number",
+        UnicodeCategoryKind::OtherPunctuation => "other_punctuation",
+        UnicodeCategoryKind::OtherSymbol => "other_symbol",
+        UnicodeCategoryKind::Other => "other",
+        UnicodeCategoryKind::ParagraphSeparator => "paragraph_separator",
+        UnicodeCategoryKind::PrivateUse => "private_use",
+        UnicodeCategoryKind::Punctuation => "punctuation",
+        UnicodeCategoryKind::Separator => "separator",
+        UnicodeCategoryKind::SpaceSeparator => "space_separator",
+        UnicodeCategoryKind::SpacingCombiningMark => "spacing_combining_mark",
+        UnicodeCategoryKind::Surrogate => "surrogate",
+        UnicodeCategoryKind::Symbol => "symbol",
+        UnicodeCategoryKind::TitlecaseLetter => "titlecase_letter",
+        UnicodeCategoryKind::Unassigned => "unassigned",
+        UnicodeCategoryKind::UppercaseLetter => "uppercase_letter",
+    };
+    let not = if category.negative { "not " } else { "" };
+
+    format!("{not}<category::{name}>")
+}
+
+fn unicode_property_source(property: &UnicodeProperty) -> String {
+    let namespace = match property.kind {
+        UnicodePropertyKind::Script => "script",
+        UnicodePropertyKind::Binary => "property",
+    };
+    let not = if property.negative { "not " } else { "" };
+
+    format!("{not}<{namespace}::{}>", property.name.to_lowercase())
+}
+
+fn backreference_source(backreference: &Backreference) -> String {
+    match backreference {
+        Backreference::Named(name) => format!("same as {name}"),
+        Backreference::Numbered(index) => format!("same as capture {index}"),
+    }
+}
+
+const fn flag_name(flag: Flag) -> &'static str {
+    match flag {
+        Flag::Insensitive => "insensitive",
+        Flag::Multiline => "multiline",
+        Flag::DotAll => "dot_all",
+        Flag::Unicode => "unicode",
+    }
+}
@@ -0,0 +1,655 @@
+//! Conversion of existing regular expressions to Viable source, see [`regex_to_viable`]
+
+use super::format::ast_to_source;
+use super::types::ast::{
+    Assertion, AssertionKind, Backreference, CharClass, CharRange, ClassItem, Expression, Flag, Group, GroupKind,
+    Quantifier, QuantifierKind, Range, Span, Spanned, SpecialSymbolKind, Symbol, SymbolKind, ViableAst, ViableAstNode,
+};
+use super::utils::escape_char;
+use crate::options::FormatOptions;
+use crate::prelude::*;
+use thiserror::Error;
+
+/// The regex syntax a pattern is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// PCRE and PCRE2, as used by PHP, Perl compatible tools and most other engines
+    Pcre,
+    /// JavaScript regular expressions without the `u` or `v` flags
+    EcmaScript,
+    /// Python's `re` module
+    Python,
+}
+
+/// An error converting a regex to Viable source, `offset` is the byte offset of the construct in the pattern
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// returned for valid regex syntax that Viable can't express, rather than converting it to something that
+    /// matches differently
+    #[error("{construct} at offset {offset} has no Viable equivalent")]
+    Unsupported { construct: String, offset: usize },
+    /// returned if the pattern is not valid in its dialect
+    #[error("invalid regex at offset {offset}: {message}")]
+    InvalidRegex { message: String, offset: usize },
+}
+
+/**
+Converts a regex to equivalent Viable source, e.g. to migrate existing patterns.
+
+Supports literals, escapes, classes, `.`, anchors, groups (capturing, named, non-capturing, atomic and with
+scoped flags), alternation, quantifiers (including lazy and possessive ones), the `\d`, `\w` and `\s` shorthands
+and their negations, word boundaries, backreferences and lookarounds. The source is formatted with
+[`ast_to_source`].
+
+# Errors
+
+Returns [`ImportError::Unsupported`] for constructs without a Viable equivalent (e.g. unicode properties, inline
+flags for the rest of the pattern or conditionals) and [`ImportError::InvalidRegex`] for invalid patterns
+
+# Example
+
+```rust
+use viable_compiler::ast::from_regex::{regex_to_viable, Dialect};
+
+let source = regex_to_viable(r"^(?<year>\d{4})-[a-f]+?$", Dialect::Pcre).unwrap();
+
+assert_eq!(
+    source,
+    "<start>;\ncapture year {\n  4 of <digit>;\n}\n\"-\";\nlazy some of class {\n  a to f;\n}\n<end>;\n"
+);
+```
+*/
+pub fn regex_to_viable(pattern: &str, dialect: Dialect) -> Result<String, ImportError> {
+    let mut parser = RegexParser {
+        pattern,
+        offset: 0,
+        dialect,
+    };
+
+    let nodes = parser.alternation()?;
+    if parser.offset < pattern.len() {
+        return Err(parser.invalid("unmatched `)`"));
+    }
+
+    let ast = ViableAst::Root(nodes).normalize();
+
+    Ok(ast_to_source(&ast, &FormatOptions::default()))
+}
+
+struct RegexParser<'pattern> {
+    pattern: &'pattern str,
+    offset: usize,
+    dialect: Dialect,
+}
+
+type ImportResult<T> = Result<T, ImportError>;
+
+/// The smallest and largest amounts of a braced quantifier
+type Bounds = (Option<usize>, Option<usize>);
+
+fn spanned(node: ViableAstNode) -> Spanned<ViableAstNode> {
+    Spanned {
+        node,
+        span: Span::default(),
+    }
+}
+
+/// An atom matching a character literally
+fn char_atom(char: char) -> ViableAstNode {
+    let escaped = if char == '\\' {
+        String::from("\\\\")
+    } else {
+        escape_char(char)
+    };
+
+    ViableAstNode::Atom(escaped.into())
+}
+
+fn unsupported<T>(construct: &str, offset: usize) -> ImportResult<T> {
+    Err(ImportError::Unsupported {
+        construct: construct.to_owned(),
+        offset,
+    })
+}
+
+const fn symbol(kind: SymbolKind, negative: bool) -> Symbol {
+    Symbol { kind, negative }
+}
+
+impl RegexParser<'_> {
+    fn rest(&self) -> &str {
+        &self.pattern[self.offset..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.peek()?;
+        self.offset += char.len_utf8();
+        Some(char)
+    }
+
+    /// Consumes `prefix` if the rest of the pattern starts with it
+    fn eat(&mut self, prefix: &str) -> bool {
+        let found = self.rest().starts_with(prefix);
+        if found {
+            self.offset += prefix.len();
+        }
+        found
+    }
+
+    fn invalid(&self, message: &str) -> ImportError {
+        ImportError::InvalidRegex {
+            message: message.to_owned(),
+            offset: self.offset,
+        }
+    }
+
+    /// Parses branches separated by `|` up to the end of the enclosing group
+    fn alternation(&mut self) -> ImportResult<Vec<Spanned<ViableAstNode>>> {
+        let mut branches = vec![self.branch()?];
+        while self.eat("|") {
+            branches.push(self.branch()?);
+        }
+
+        if branches.len() == 1 {
+            return Ok(branches.swap_remove(0));
+        }
+
+        let branches = branches
+            .into_iter()
+            .map(|mut branch| match branch.len() {
+                // an empty branch matches an empty string
+                0 => spanned(ViableAstNode::Atom("".into())),
+                1 => branch.swap_remove(0),
+                _ => spanned(ViableAstNode::Group(Group {
+                    ident: None,
+                    kind: GroupKind::Match,
+                    statements: Box::new(ViableAst::Root(branch)),
+                })),
+            })
+            .collect();
+
+        Ok(vec![spanned(ViableAstNode::Group(Group {
+            ident: None,
+            kind: GroupKind::Either,
+            statements: Box::new(ViableAst::Root(branches)),
+        }))])
+    }
+
+    fn branch(&mut self) -> ImportResult<Vec<Spanned<ViableAstNode>>> {
+        let mut nodes = Vec::new();
+
+        while !matches!(self.peek(), None | Some('|' | ')')) {
+            let start = self.offset;
+            let Some(node) = self.atom()? else {
+                continue;
+            };
+
+            let node = self.quantified(node, start)?;
+            nodes.push(spanned(node));
+        }
+
+        Ok(nodes)
+    }
+
+    /// Parses the quantifier following a node, if there is one
+    fn quantified(&mut self, node: ViableAstNode, start: usize) -> ImportResult<ViableAstNode> {
+        let quantifier_offset = self.offset;
+        let Some(kind) = self.quantifier_kind()? else {
+            return Ok(node);
+        };
+
+        let lazy = self.eat("?");
+        let possessive = !lazy && self.dialect != Dialect::EcmaScript && self.eat("+");
+
+        if matches!(self.peek(), Some('*' | '+' | '?')) || self.quantifier_follows() {
+            return Err(self.invalid("nothing to repeat"));
+        }
+
+        let expression = match node {
+            ViableAstNode::Atom(atom) => Expression::Atom(atom),
+            ViableAstNode::Group(group) => Expression::Group(group),
+            ViableAstNode::Symbol(symbol) => Expression::Symbol(symbol),
+            ViableAstNode::CharClass(class) => Expression::CharClass(class),
+            ViableAstNode::Backreference(backreference) => Expression::Backreference(backreference),
+            ViableAstNode::Assertion(_) | ViableAstNode::SpecialSymbol(_) => {
+                return unsupported("a quantified assertion", start)
+            }
+            _ => return Err(self.invalid("nothing to repeat")),
+        };
+
+        if kind == QuantifierKind::Amount(0) {
+            return unsupported("a quantifier repeating zero times", quantifier_offset);
+        }
+
+        Ok(ViableAstNode::Quantifier(Quantifier {
+            kind,
+            lazy,
+            possessive,
+            unit: None,
+            expression: Box::new(Spanned {
+                node: expression,
+                span: Span::default(),
+            }),
+        }))
+    }
+
+    fn quantifier_follows(&self) -> bool {
+        self.rest().starts_with('{') && Self::braced_quantifier(self.rest()).is_some()
+    }
+
+    fn quantifier_kind(&mut self) -> ImportResult<Option<QuantifierKind>> {
+        let kind = match self.peek() {
+            Some('*') => QuantifierKind::Any,
+            Some('+') => QuantifierKind::Some,
+            Some('?') => QuantifierKind::Option,
+            Some('{') => {
+                // a brace that doesn't start a quantifier is literal
+                let Some((length, bounds)) = Self::braced_quantifier(self.rest()) else {
+                    return Ok(None);
+                };
+                let offset = self.offset;
+                self.offset += length;
+
+                return match bounds {
+                    (None, None) => Ok(None),
+                    (Some(start), Some(end)) if start > end => Err(ImportError::InvalidRegex {
+                        message: String::from("numbers out of order in quantifier"),
+                        offset,
+                    }),
+                    (Some(start), Some(end)) if start == end => Ok(Some(QuantifierKind::Amount(start))),
+                    (Some(0) | None, Some(end)) => Ok(Some(QuantifierKind::AtMost(end))),
+                    (Some(start), Some(end)) => Ok(Some(QuantifierKind::Range { start, end })),
+                    (Some(0), None) => Ok(Some(QuantifierKind::Any)),
+                    (Some(start), None) => Ok(Some(QuantifierKind::AtLeast(start))),
+                };
+            }
+            _ => return Ok(None),
+        };
+
+        self.offset += 1;
+        Ok(Some(kind))
+    }
+
+    /// The length and bounds of a `{n}`, `{n,}`, `{n,m}` or (in Python) `{,m}` quantifier at the start of `rest`
+    fn braced_quantifier(rest: &str) -> Option<(usize, Bounds)> {
+        let end = rest.find('}')?;
+        let content = &rest[1..end];
+        let number = |digits: &str| -> Option<Option<usize>> {
+            if digits.is_empty() {
+                Some(None)
+            } else if digits.chars().all(|char| char.is_ascii_digit()) {
+                digits.parse().ok().map(Some)
+            } else {
+                None
+            }
+        };
+
+        let bounds = match content.split_once(',') {
+            None => {
+                let amount = number(content)??;
+                (Some(amount), Some(amount))
+            }
+            Some((start, end)) => (number(start)?, number(end)?),
+        };
+
+        // `{,m}` is only a quantifier in Python, and `{,}` in none of the dialects
+        match bounds {
+            (None, _) if !content.starts_with(',') => None,
+            (None, None) => None,
+            _ => Some((end + 1, bounds)),
+        }
+    }
+
+    /// Parses a single atom, `None` for constructs matching nothing such as comments
+    fn atom(&mut self) -> ImportResult<Option<ViableAstNode>> {
+        let start = self.offset;
+        let Some(char) = self.next() else {
+            return Ok(None);
+        };
+
+        let node = match char {
+            '.' => ViableAstNode::Symbol(symbol(SymbolKind::Char, false)),
+            '^' => ViableAstNode::SpecialSymbol(SpecialSymbolKind::Start),
+            '$' => ViableAstNode::SpecialSymbol(SpecialSymbolKind::End),
+            '[' => ViableAstNode::CharClass(self.class(start)?),
+            '(' => return self.group(start),
+            '\\' => self.escape(start)?,
+            '*' | '+' | '?' => return Err(self.invalid("nothing to repeat")),
+            '{' if Self::braced_quantifier(&self.pattern[start..]).is_some() => {
+                return Err(self.invalid("nothing to repeat"))
+            }
+            char => char_atom(char),
+        };
+
+        Ok(Some(node))
+    }
+
+    fn group(&mut self, start: usize) -> ImportResult<Option<ViableAstNode>> {
+        let group = |kind, ident: Option<&str>, statements| {
+            ViableAstNode::Group(Group {
+                ident: ident.map(Into::into),
+                kind,
+                statements: Box::new(ViableAst::Root(statements)),
+            })
+        };
+        let assertion = |kind, negative, statements| {
+            ViableAstNode::Assertion(Assertion {
+                kind,
+                statements: Box::new(ViableAst::Root(statements)),
+                negative,
+            })
+        };
+
+        let node = if !self.eat("?") {
+            group(GroupKind::Capture, None, self.group_body()?)
+        } else if self.eat(":") {
+            group(GroupKind::Match, None, self.group_body()?)
+        } else if self.eat("=") {
+            assertion(AssertionKind::Ahead, false, self.group_body()?)
+        } else if self.eat("!") {
+            assertion(AssertionKind::Ahead, true, self.group_body()?)
+        } else if self.eat("<=") {
+            assertion(AssertionKind::Behind, false, self.group_body()?)
+        } else if self.eat("<!") {
+            assertion(AssertionKind::Behind, true, self.group_body()?)
+        } else if self.dialect != Dialect::EcmaScript && self.eat(">") {
+            group(GroupKind::Atomic, None, self.group_body()?)
+        } else if self.dialect != Dialect::EcmaScript && self.eat("#") {
+            let Some(end) = self.rest().find(')') else {
+                return Err(self.invalid("missing `)` at the end of a comment"));
+            };
+            self.offset += end + 1;
+            return Ok(None);
+        } else if self.dialect != Dialect::EcmaScript && self.eat("P=") {
+            let name = self.name(')')?;
+            ViableAstNode::Backreference(Backreference::Named(name.into()))
+        } else if self.eat("<") || (self.dialect != Dialect::EcmaScript && self.eat("P<")) {
+            let name = self.name('>')?;
+            group(GroupKind::Capture, Some(&name), self.group_body()?)
+        } else if self.dialect == Dialect::Pcre && self.eat("'") {
+            let name = self.name('\'')?;
+            group(GroupKind::Capture, Some(&name), self.group_body()?)
+        } else if self.dialect != Dialect::EcmaScript && self.peek().is_some_and(|char| "imsx-".contains(char)) {
+            let (set, clear) = self.scoped_flags(start)?;
+            group(GroupKind::Flagged { set, clear }, None, self.group_body()?)
+        } else {
+            return unsupported("a special group", start);
+        };
+
+        Ok(Some(node))
+    }
+
+    /// Parses the statements of a group along with its closing parenthesis
+    fn group_body(&mut self) -> ImportResult<Vec<Spanned<ViableAstNode>>> {
+        let statements = self.alternation()?;
+
+        if !self.eat(")") {
+            return Err(self.invalid("missing `)`"));
+        }
+
+        Ok(statements)
+    }
+
+    fn name(&mut self, terminator: char) -> ImportResult<String> {
+        let length = self
+            .rest()
+            .find(|char: char| !(char.is_ascii_alphanumeric() || char == '_'))
+            .unwrap_or(self.rest().len());
+        let name = self.rest()[..length].to_owned();
+        self.offset += length;
+
+        if name.is_empty() || !self.eat(&terminator.to_string()) {
+            return Err(self.invalid("invalid group name"));
+        }
+
+        Ok(name)
+    }
+
+    /// Parses the flags of a `(?i-m:...)` group up to its colon
+    fn scoped_flags(&mut self, start: usize) -> ImportResult<(Vec<Flag>, Vec<Flag>)> {
+        let mut set = Vec::new();
+        let mut clear = Vec::new();
+        let mut clearing = false;
+
+        loop {
+            let offset = self.offset;
+            let flag = match self.next() {
+                Some(':') => break,
+                Some(')') => return unsupported("inline flags for the rest of the pattern", start),
+                Some('-') if !clearing => {
+                    clearing = true;
+                    continue;
+                }
+                Some('i') => Flag::Insensitive,
+                Some('m') => Flag::Multiline,
+                Some('s') => Flag::DotAll,
+                Some('x') => return unsupported("the `x` flag", offset),
+                _ => return Err(self.invalid("invalid flag group")),
+            };
+
+            if clearing {
+                clear.push(flag);
+            } else {
+                set.push(flag);
+            }
+        }
+
+        Ok((set, clear))
+    }
+
+    /// Parses an escape outside of a class, after its backslash
+    fn escape(&mut self, start: usize) -> ImportResult<ViableAstNode> {
+        let Some(char) = self.next() else {
+            return Err(self.invalid("trailing backslash"));
+        };
+
+        let node = match char {
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' => ViableAstNode::Symbol(shorthand(char)),
+            'b' => ViableAstNode::SpecialSymbol(SpecialSymbolKind::WordBoundary),
+            'B' => ViableAstNode::SpecialSymbol(SpecialSymbolKind::NotWordBoundary),
+            'A' if self.dialect != Dialect::EcmaScript => ViableAstNode::SpecialSymbol(SpecialSymbolKind::InputStart),
+            'z' if self.dialect == Dialect::Pcre => ViableAstNode::SpecialSymbol(SpecialSymbolKind::InputEnd),
+            'Z' if self.dialect == Dialect::Python => ViableAstNode::SpecialSymbol(SpecialSymbolKind::InputEnd),
+            '1'..='9' => {
+                let length = self
+                    .rest()
+                    .find(|char: char| !char.is_ascii_digit())
+                    .unwrap_or(self.rest().len());
+                let index = format!("{char}{}", &self.rest()[..length]);
+                self.offset += length;
+                let index = index.parse().map_err(|_| self.invalid("invalid backreference"))?;
+                ViableAstNode::Backreference(Backreference::Numbered(index))
+            }
+            'k' if self.dialect != Dialect::Python => {
+                let terminator = match self.next() {
+                    Some('<') => '>',
+                    Some('{') if self.dialect == Dialect::Pcre => '}',
+                    Some('\'') if self.dialect == Dialect::Pcre => '\'',
+                    _ => return Err(self.invalid("invalid named backreference")),
+                };
+                let name = self.name(terminator)?;
+                ViableAstNode::Backreference(Backreference::Named(name.into()))
+            }
+            char => char_atom(self.escaped_char(char, start)?),
+        };
+
+        Ok(node)
+    }
+
+    /// The character matched by an escape that matches a single character, after its backslash and `char`
+    fn escaped_char(&mut self, char: char, start: usize) -> ImportResult<char> {
+        let escaped = match char {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'f' => '\u{c}',
+            // PCRE's `\v` is any vertical whitespace
+            'v' if self.dialect != Dialect::Pcre => '\u{b}',
+            '0' if !self.peek().is_some_and(|char| char.is_ascii_digit()) => '\0',
+            'x' if self.dialect == Dialect::Pcre && self.eat("{") => {
+                let length = self
+                    .rest()
+                    .find('}')
+                    .ok_or_else(|| self.invalid("missing `}` in escape"))?;
+                let digits = self.rest()[..length].to_owned();
+                self.offset += length + 1;
+                code_point(&digits, start)?
+            }
+            'x' => self.hex_digits(2, start)?,
+            'u' if self.dialect != Dialect::Pcre => self.hex_digits(4, start)?,
+            'U' if self.dialect == Dialect::Python => self.hex_digits(8, start)?,
+            char if char.is_ascii_punctuation() => char,
+            char if char.is_ascii_alphanumeric() => return unsupported(&format!("the `\\{char}` escape"), start),
+            char => char,
+        };
+
+        Ok(escaped)
+    }
+
+    fn hex_digits(&mut self, count: usize, start: usize) -> ImportResult<char> {
+        let digits = self.rest().get(..count).unwrap_or_default().to_owned();
+        if digits.len() != count || !digits.chars().all(|char| char.is_ascii_hexdigit()) {
+            return Err(self.invalid("invalid hex escape"));
+        }
+        self.offset += count;
+
+        code_point(&digits, start)
+    }
+
+    /// Parses a class, after its opening bracket
+    fn class(&mut self, start: usize) -> ImportResult<CharClass> {
+        let negative = self.eat("^");
+        let mut items = Vec::new();
+
+        if self.rest().starts_with(']') {
+            if self.dialect == Dialect::EcmaScript {
+                return unsupported("an empty class", start);
+            }
+            // a bracket at the start of the class is a member of it
+            self.offset += 1;
+            items.push(ClassItem::Char(']'));
+        }
+
+        loop {
+            let offset = self.offset;
+            let item = match self.next() {
+                None => return Err(self.invalid("missing `]`")),
+                Some(']') => break,
+                Some('[') if self.dialect != Dialect::EcmaScript && self.rest().starts_with(':') => {
+                    return unsupported("a POSIX class", offset)
+                }
+                Some('\\') => self.class_escape(offset)?,
+                Some(char) => ClassItem::Char(char),
+            };
+
+            // a `-` between two characters forms a range, elsewhere it is a member
+            let ClassItem::Char(range_start) = item else {
+                items.push(item);
+                continue;
+            };
+            if !self.rest().starts_with('-') || self.rest().starts_with("-]") {
+                items.push(item);
+                continue;
+            }
+
+            self.offset += 1;
+            let end_offset = self.offset;
+            let range_end = match self.next() {
+                Some('\\') => match self.class_escape(end_offset)? {
+                    ClassItem::Char(char) => char,
+                    _ => return unsupported("a range ending in a shorthand class", end_offset),
+                },
+                Some(char) => char,
+                None => return Err(self.invalid("missing `]`")),
+            };
+
+            if range_start > range_end {
+                return Err(ImportError::InvalidRegex {
+                    message: String::from("range out of order in class"),
+                    offset,
+                });
+            }
+            items.extend(class_range(range_start, range_end));
+        }
+
+        Ok(CharClass { negative, items })
+    }
+
+    fn class_escape(&mut self, start: usize) -> ImportResult<ClassItem> {
+        let Some(char) = self.next() else {
+            return Err(self.invalid("trailing backslash"));
+        };
+
+        let item = match char {
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' => ClassItem::Symbol(shorthand(char)),
+            // a backspace within a class rather than a word boundary
+            'b' => ClassItem::Char('\u{8}'),
+            char => ClassItem::Char(self.escaped_char(char, start)?),
+        };
+
+        Ok(item)
+    }
+}
+
+fn code_point(digits: &str, start: usize) -> ImportResult<char> {
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| ImportError::InvalidRegex {
+            message: String::from("invalid code point"),
+            offset: start,
+        })
+}
+
+/// The items of a class range, Viable ranges can't start or end with whitespace, a control character or a
+/// semicolon so those are listed on their own
+fn class_range(start: char, end: char) -> Vec<ClassItem> {
+    let writable = |char: char| !(char.is_whitespace() || char.is_control() || char == ';');
+    let run = |start: char, end: char| {
+        if start == end {
+            ClassItem::Char(start)
+        } else {
+            ClassItem::Range(Range::CharRange(CharRange {
+                negative: false,
+                start,
+                end,
+            }))
+        }
+    };
+
+    let mut items = Vec::new();
+    let mut run_start = None;
+    let mut previous = start;
+
+    for char in start..=end {
+        if writable(char) {
+            run_start.get_or_insert(char);
+        } else {
+            if let Some(run_start) = run_start.take() {
+                items.push(run(run_start, previous));
+            }
+            items.push(ClassItem::Char(char));
+        }
+        previous = char;
+    }
+    if let Some(run_start) = run_start {
+        items.push(run(run_start, end));
+    }
+
+    items
+}
+
+/// The symbol of a `\d`, `\w` or `\s` shorthand or of their negations
+const fn shorthand(char: char) -> Symbol {
+    let kind = match char.to_ascii_lowercase() {
+        'd' => SymbolKind::Digit,
+        'w' => SymbolKind::Word,
+        _ => SymbolKind::Whitespace,
+    };
+
+    symbol(kind, char.is_ascii_uppercase())
+}
@@ -0,0 +1,74 @@
+use super::fold;
+use super::transform::{fold_expression, fold_node, Transform};
+use super::types::ast::{
+    Expression, Group, GroupKind, Quantifier, QuantifierKind, Span, Spanned, Symbol, SymbolKind, UnicodeCategory,
+    UnicodeCategoryKind, ViableAst, ViableAstNode,
+};
+use crate::prelude::*;
+
+/// Replaces every `<grapheme>` with `(?:\P{M}\p{M}*)`, a character that isn't a mark followed by any marks,
+/// for targets without `\X`, see [`crate::options::CompilerOptions::approximate_graphemes`]
+pub fn approximate_graphemes(ast: ViableAst) -> ViableAst {
+    fold(ast, &mut GraphemeApproximation)
+}
+
+struct GraphemeApproximation;
+
+const fn is_grapheme(symbol: &Symbol) -> bool {
+    matches!(symbol.kind, SymbolKind::Grapheme)
+}
+
+fn approximation(span: Span) -> Group {
+    let mark = |negative| UnicodeCategory {
+        kind: UnicodeCategoryKind::Mark,
+        negative,
+    };
+
+    let statements = vec![
+        Spanned {
+            node: ViableAstNode::UnicodeCategory(mark(true)),
+            span,
+        },
+        Spanned {
+            node: ViableAstNode::Quantifier(Quantifier {
+                kind: QuantifierKind::Any,
+                lazy: false,
+                possessive: false,
+                unit: None,
+                expression: Box::new(Spanned {
+                    node: Expression::UnicodeCategory(mark(false)),
+                    span,
+                }),
+            }),
+            span,
+        },
+    ];
+
+    Group {
+        ident: None,
+        kind: GroupKind::Match,
+        statements: Box::new(ViableAst::Root(statements)),
+    }
+}
+
+impl Transform for GraphemeApproximation {
+    fn transform_node(&mut self, node: Spanned<ViableAstNode>) -> Vec<Spanned<ViableAstNode>> {
+        match node.node {
+            ViableAstNode::Symbol(symbol) if is_grapheme(&symbol) => vec![Spanned {
+                node: ViableAstNode::Group(approximation(node.span)),
+                span: node.span,
+            }],
+            _ => vec![fold_node(self, node)],
+        }
+    }
+
+    fn transform_expression(&mut self, expression: Spanned<Expression>) -> Spanned<Expression> {
+        match expression.node {
+            Expression::Symbol(symbol) if is_grapheme(&symbol) => Spanned {
+                node: Expression::Group(approximation(expression.span)),
+                span: expression.span,
+            },
+            _ => fold_expression(self, expression),
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use super::source_to_ast::to_ast;
+use super::types::ast::{
+    Assertion, AssertionKind, Backreference, ClassItem, Conditional, Expression, Group, GroupKind, Quantifier,
+    QuantifierKind, Range, Recursion, Span, Spanned, Symbol, UnicodeCategory, UnicodeProperty, UnicodePropertyKind,
+    VariableInvocation, ViableAst, ViableAstNode,
+};
+use crate::errors::CompilerError;
+use crate::prelude::*;
+
+/**
+Parses Viable source and dumps its AST as pretty-printed JSON, for tools rendering syntax trees.
+
+The output is an object with an `ast` key holding the root nodes, or with an `error` key holding the `message`
+of the error (and for parse errors its `expected`, `found`, `line`, `column` and `offset`) if the source fails to
+convert, so the output of any source can be read the same way.
+
+Each node is an object with a `kind` (e.g. `group` or `atom`) and a `span` of `start`, `end`, `line` and `column`.
+Groups, assertions, quantifiers and variable invocations hold their statements in `children`, nodes that can be
+negated have a `negative` flag and quantifiers have `lazy` and `possessive` flags. Conditionals hold the statements
+of their `else` block in `otherwise`, which is `null` without one.
+
+# Example
+
+```rust
+use viable_compiler::ast::to_ast_json;
+
+let json = to_ast_json(r#"some of "a";"#);
+assert!(json.starts_with("{\n  \"ast\": ["));
+
+let json = to_ast_json(r#"some of "a""#);
+assert!(json.starts_with("{\n  \"error\": {"));
+```
+*/
+#[must_use]
+pub fn to_ast_json(source: &str) -> String {
+    let json = match to_ast(source) {
+        Ok(ast) => Json::Object(vec![("ast", Json::Array(nodes(&ast)))]),
+        Err(error) => Json::Object(vec![("error", error_json(&error))]),
+    };
+
+    let mut output = String::new();
+    json.write(&mut output, 0);
+    output.push('\n');
+    output
+}
+
+/**
+Dumps the nodes of an AST as a pretty-printed JSON array, in the format of the `ast` key of [`to_ast_json`].
+
+# Example
+
+```rust
+use viable_compiler::ast::{ast_to_json, to_ast};
+
+let json = ast_to_json(&to_ast(r#""a";"#).unwrap());
+assert!(json.starts_with("[\n  {\n    \"kind\": \"atom\","));
+```
+*/
+#[must_use]
+pub fn ast_to_json(ast: &ViableAst) -> String {
+    let mut output = String::new();
+    Json::Array(nodes(ast)).write(&mut output, 0);
+    output.push('\n');
+    output
+}
+
+/// A JSON value, objects keep the order their keys are written in
+enum Json {
+    Null,
+    Bool(bool),
+    Number(usize),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn string(value: impl Into<String>) -> Self {
+        Self::String(value.into())
+    }
+
+    fn write(&self, output: &mut String, level: usize) {
+        match self {
+            Self::Null => output.push_str("null"),
+            Self::Bool(value) => output.push_str(if *value { "true" } else { "false" }),
+            Self::Number(value) => output.push_str(&value.to_string()),
+            Self::String(value) => write_string(output, value),
+            Self::Array(values) if values.is_empty() => output.push_str("[]"),
+            Self::Array(values) => {
+                output.push('[');
+                for (index, value) in values.iter().enumerate() {
+                    output.push_str(if index == 0 { "\n" } else { ",\n" });
+                    output.push_str(&"  ".repeat(level + 1));
+                    value.write(output, level + 1);
+                }
+                output.push('\n');
+                output.push_str(&"  ".repeat(level));
+                output.push(']');
+            }
+            Self::Object(entries) => {
+                output.push('{');
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    output.push_str(if index == 0 { "\n" } else { ",\n" });
+                    output.push_str(&"  ".repeat(level + 1));
+                    write_string(output, key);
+                    output.push_str(": ");
+                    value.write(output, level + 1);
+                }
+                output.push('\n');
+                output.push_str(&"  ".repeat(level));
+                output.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(output: &mut String, value: &str) {
+    output.push('"');
+
+    for char in value.chars() {
+        match char {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            char if u32::from(char) < 0x20 => output.push_str(&format!("\\u{:04x}", u32::from(char))),
+            char => output.push(char),
+        }
+    }
+
+    output.push('"');
+}
+
+fn error_json(error: &CompilerError) -> Json {
+    let mut entries = vec![("message", Json::string(error.to_string()))];
+
+    if let CompilerError::ParseError {
+        expected,
+        found,
+        line,
+        column,
+        offset,
+        ..
+    } = error
+    {
+        entries.extend([
+            (
+                "expected",
+                Json::Array(expected.iter().cloned().map(Json::String).collect()),
+            ),
+            ("found", Json::string(found.as_str())),
+            ("line", Json::Number(*line)),
+            ("column", Json::Number(*column)),
+            ("offset", Json::Number(*offset)),
+        ]);
+    }
+
+    Json::Object(entries)
+}
+
+fn span_json(span: Span) -> Json {
+    Json::Object(vec![
+        ("start", Json::Number(span.start)),
+        ("end", Json::Number(span.end)),
+        ("line", Json::Number(span.line as usize)),
+        ("column", Json::Number(span.column as usize)),
+    ])
+}
+
+fn nodes(ast: &ViableAst) -> Vec<Json> {
+    match ast {
+        ViableAst::Root(nodes) => nodes.iter().map(node).collect(),
+        ViableAst::Empty => Vec::new(),
+    }
+}
+
+/// An object of the node's kind and span followed by its fields
+fn spanned(kind: &str, span: Span, mut fields: Vec<(&'static str, Json)>) -> Json {
+    fields.splice(0..0, [("kind", Json::string(kind)), ("span", span_json(span))]);
+    Json::Object(fields)
+}
+
+fn node(node: &Spanned<ViableAstNode>) -> Json {
+    let span = node.span;
+
+    match &node.node {
+        ViableAstNode::Group(group) => group_json(group, span),
+        ViableAstNode::Assertion(assertion) => assertion_json(assertion, span),
+        ViableAstNode::Conditional(conditional) => conditional_json(conditional, span),
+        ViableAstNode::Quantifier(quantifier) => quantifier_json(quantifier, span),
+        ViableAstNode::VariableInvocation(invocation) => invocation_json(invocation, span),
+        ViableAstNode::Atom(atom) => spanned("atom", span, vec![("value", Json::string(&**atom))]),
+        ViableAstNode::Range(range) => spanned("range", span, range_fields(range)),
+        ViableAstNode::Symbol(symbol) => spanned("symbol", span, symbol_fields(symbol)),
+        ViableAstNode::SpecialSymbol(special_symbol) => spanned(
+            "special_symbol",
+            span,
+            vec![("symbol", Json::string(snake_case(&format!("{special_symbol:?}"))))],
+        ),
+        ViableAstNode::UnicodeCategory(category) => spanned("unicode_category", span, category_fields(category)),
+        ViableAstNode::UnicodeProperty(property) => spanned("unicode_property", span, property_fields(property)),
+        ViableAstNode::NegativeCharClass(items) => spanned("negative_char_class", span, class_fields(true, items)),
+        ViableAstNode::CharClass(class) => spanned("char_class", span, class_fields(class.negative, &class.items)),
+        ViableAstNode::Backreference(backreference) => {
+            spanned("backreference", span, backreference_fields(backreference))
+        }
+        ViableAstNode::Recursion(recursion) => {
+            let name = match recursion {
+                Recursion::Pattern => Json::Null,
+                Recursion::Group(name) => Json::string(&**name),
+            };
+            spanned("recursion", span, vec![("name", name)])
+        }
+        ViableAstNode::Parameter(name) => spanned("parameter", span, vec![("name", Json::string(&**name))]),
+        ViableAstNode::Empty => spanned("empty", span, Vec::new()),
+        ViableAstNode::RawRegex(regex) => spanned("raw_regex", span, vec![("value", Json::string(&**regex))]),
+        ViableAstNode::Skip => spanned("skip", span, Vec::new()),
+        ViableAstNode::Error => spanned("error", span, Vec::new()),
+    }
+}
+
+fn expression(expression: &Spanned<Expression>) -> Json {
+    let span = expression.span;
+
+    match &expression.node {
+        Expression::Group(group) => group_json(group, span),
+        Expression::Atom(atom) => spanned("atom", span, vec![("value", Json::string(&**atom))]),
+        Expression::Range(range) => spanned("range", span, range_fields(range)),
+        Expression::Symbol(symbol) => spanned("symbol", span, symbol_fields(symbol)),
+        Expression::UnicodeCategory(category) => spanned("unicode_category", span, category_fields(category)),
+        Expression::UnicodeProperty(property) => spanned("unicode_property", span, property_fields(property)),
+        Expression::NegativeCharClass(items) => spanned("negative_char_class", span, class_fields(true, items)),
+        Expression::CharClass(class) => spanned("char_class", span, class_fields(class.negative, &class.items)),
+        Expression::Backreference(backreference) => spanned("backreference", span, backreference_fields(backreference)),
+    }
+}
+
+fn group_json(group: &Group, span: Span) -> Json {
+    let flags = |flags: &[_]| {
+        Json::Array(
+            flags
+                .iter()
+                .map(|flag| Json::string(snake_case(&format!("{flag:?}"))))
+                .collect(),
+        )
+    };
+
+    let mut fields = match &group.kind {
+        GroupKind::Match => vec![("group_kind", Json::string("match"))],
+        GroupKind::Capture => vec![("group_kind", Json::string("capture"))],
+        GroupKind::Either => vec![("group_kind", Json::string("either"))],
+        GroupKind::Atomic => vec![("group_kind", Json::string("atomic"))],
+        GroupKind::Flagged { set, clear } => vec![
+            ("group_kind", Json::string("flagged")),
+            ("set", flags(set)),
+            ("clear", flags(clear)),
+        ],
+    };
+
+    fields.push(("name", group.ident.as_deref().map_or(Json::Null, Json::string)));
+    fields.push(("children", Json::Array(nodes(&group.statements))));
+
+    spanned("group", span, fields)
+}
+
+fn conditional_json(conditional: &Conditional, span: Span) -> Json {
+    let mut fields = backreference_fields(&conditional.condition);
+    fields.push(("children", Json::Array(nodes(&conditional.then))));
+    fields.push((
+        "otherwise",
+        conditional
+            .otherwise
+            .as_ref()
+            .map_or(Json::Null, |otherwise| Json::Array(nodes(otherwise))),
+    ));
+
+    spanned("conditional", span, fields)
+}
+
+fn assertion_json(assertion: &Assertion, span: Span) -> Json {
+    let kind = match assertion.kind {
+        AssertionKind::Ahead => "ahead",
+        AssertionKind::Behind => "behind",
+    };
+
+    spanned(
+        "assertion",
+        span,
+        vec![
+            ("assertion_kind", Json::string(kind)),
+            ("negative", Json::Bool(assertion.negative)),
+            ("children", Json::Array(nodes(&assertion.statements))),
+        ],
+    )
+}
+
+fn quantifier_json(quantifier: &Quantifier, span: Span) -> Json {
+    let mut fields = match &quantifier.kind {
+        QuantifierKind::Range { start, end } => vec![
+            ("quantifier_kind", Json::string("range")),
+            ("start", Json::Number(*start)),
+            ("end", Json::Number(*end)),
+        ],
+        QuantifierKind::Some => vec![("quantifier_kind", Json::string("some"))],
+        QuantifierKind::Any => vec![("quantifier_kind", Json::string("any"))],
+        // `over n` matches more than n times, so holds n + 1
+        QuantifierKind::Over(amount) => vec![
+            ("quantifier_kind", Json::string("over")),
+            ("amount", Json::Number(amount.saturating_sub(1))),
+        ],
+        QuantifierKind::AtLeast(amount) => vec![
+            ("quantifier_kind", Json::string("at_least")),
+            ("amount", Json::Number(*amount)),
+        ],
+        QuantifierKind::AtMost(amount) => vec![
+            ("quantifier_kind", Json::string("at_most")),
+            ("amount", Json::Number(*amount)),
+        ],
+        QuantifierKind::Option => vec![("quantifier_kind", Json::string("option"))],
+        QuantifierKind::Amount(amount) => vec![
+            ("quantifier_kind", Json::string("amount")),
+            ("amount", Json::Number(*amount)),
+        ],
+    };
+
+    fields.extend([
+        ("lazy", Json::Bool(quantifier.lazy)),
+        ("possessive", Json::Bool(quantifier.possessive)),
+        (
+            "unit",
+            quantifier.unit.map_or(Json::Null, |unit| Json::string(unit.name())),
+        ),
+        ("children", Json::Array(vec![expression(&quantifier.expression)])),
+    ]);
+
+    spanned("quantifier", span, fields)
+}
+
+fn invocation_json(invocation: &VariableInvocation, span: Span) -> Json {
+    spanned(
+        "variable_invocation",
+        span,
+        vec![
+            ("name", Json::string(&*invocation.name)),
+            (
+                "arguments",
+                Json::Array(invocation.arguments.iter().map(node).collect()),
+            ),
+            ("children", Json::Array(nodes(&invocation.statements))),
+        ],
+    )
+}
+
+fn range_fields(range: &Range) -> Vec<(&'static str, Json)> {
+    let (kind, negative, start, end) = match range {
+        Range::CharRange(range) => ("char", range.negative, range.start, range.end),
+        Range::NumericRange(range) => ("numeric", range.negative, range.start, range.end),
+    };
+
+    vec![
+        ("range_kind", Json::string(kind)),
+        ("negative", Json::Bool(negative)),
+        ("start", Json::String(start.to_string())),
+        ("end", Json::String(end.to_string())),
+    ]
+}
+
+fn symbol_fields(symbol: &Symbol) -> Vec<(&'static str, Json)> {
+    vec![
+        ("symbol", Json::string(snake_case(&format!("{:?}", symbol.kind)))),
+        ("negative", Json::Bool(symbol.negative)),
+    ]
+}
+
+fn category_fields(category: &UnicodeCategory) -> Vec<(&'static str, Json)> {
+    vec![
+        ("category", Json::string(snake_case(&format!("{:?}", category.kind)))),
+        ("negative", Json::Bool(category.negative)),
+    ]
+}
+
+fn property_fields(property: &UnicodeProperty) -> Vec<(&'static str, Json)> {
+    let kind = match property.kind {
+        UnicodePropertyKind::Script => "script",
+        UnicodePropertyKind::Binary => "binary",
+    };
+
+    vec![
+        ("property_kind", Json::string(kind)),
+        ("name", Json::string(&*property.name)),
+        ("negative", Json::Bool(property.negative)),
+    ]
+}
+
+fn backreference_fields(backreference: &Backreference) -> Vec<(&'static str, Json)> {
+    match backreference {
+        Backreference::Named(name) => vec![("name", Json::string(&**name))],
+        Backreference::Numbered(index) => vec![("index", Json::Number(*index))],
+    }
+}
+
+fn class_fields(negative: bool, items: &[ClassItem]) -> Vec<(&'static str, Json)> {
+    vec![("negative", Json::Bool(negative)), ("items", class_items(items))]
+}
+
+/// Class items have a `kind` but no span, as the AST only keeps the span of the class
+fn class_items(items: &[ClassItem]) -> Json {
+    let item = |kind: &str, mut fields: Vec<(&'static str, Json)>| {
+        fields.insert(0, ("kind", Json::string(kind)));
+        Json::Object(fields)
+    };
+
+    Json::Array(
+        items
+            .iter()
+            .map(|class_item| match class_item {
+                ClassItem::Range(range) => item("range", range_fields(range)),
+                ClassItem::Char(char) => item("char", vec![("value", Json::String(char.to_string()))]),
+                ClassItem::Raw(raw) => item("raw", vec![("value", Json::string(&**raw))]),
+                ClassItem::Symbol(symbol) => item("symbol", symbol_fields(symbol)),
+                ClassItem::UnicodeCategory(category) => item("unicode_category", category_fields(category)),
+                ClassItem::UnicodeProperty(property) => item("unicode_property", property_fields(property)),
+                ClassItem::Subtract(items) => item("subtract", vec![("items", class_items(items))]),
+                ClassItem::Intersect(items) => item("intersect", vec![("items", class_items(items))]),
+            })
+            .collect(),
+    )
+}
+
+/// Converts the name of a variant (e.g. `CasedLetter`) to snake case (e.g. `cased_letter`)
+fn snake_case(name: &str) -> String {
+    let mut snake_case = String::new();
+
+    for char in name.chars() {
+        if char.is_ascii_uppercase() && !snake_case.is_empty() {
+            snake_case.push('_');
+        }
+        snake_case.push(char.to_ascii_lowercase());
+    }
+
+    snake_case
+}
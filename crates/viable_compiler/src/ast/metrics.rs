@@ -0,0 +1,261 @@
+use super::types::ast::{
+    Assertion, Backreference, CharClass, ClassItem, Conditional, Expression, Group, GroupKind, Quantifier,
+    QuantifierKind, Range, Recursion, Span, Spanned, SpecialSymbolKind, Symbol, SymbolKind, UnicodeCategory,
+    UnicodeProperty, ViableAst, ViableAstNode,
+};
+use super::utils::raw_regex_captures;
+use super::visitor::{
+    walk_assertion, walk_conditional, walk_expression, walk_group, walk_node, walk_quantifier, Visitor,
+};
+use super::walk;
+use crate::prelude::*;
+
+/// Measures of the size and complexity of an AST, see [`ViableAst::metrics`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AstMetrics {
+    /// the number of nodes, including quantified expressions and the nodes of each expansion of a variable
+    pub node_count: usize,
+    /// how deep nodes are nested, 1 for statements without blocks and 0 for an empty AST
+    pub max_depth: usize,
+    pub capture_count: usize,
+    /// the number of `either` groups
+    pub alternation_count: usize,
+    /// an upper estimate of the length of the emitted pattern once the repetitions of bounded quantifiers are
+    /// expanded, as engines like RE2 do when compiling it
+    pub estimated_regex_len: usize,
+    /// the largest amount of any quantifier, e.g. 5 for `2 to 5 of`, 0 if it has none
+    pub max_repeat_bound: usize,
+}
+
+impl ViableAst {
+    /**
+    Measures the size and complexity of the AST, e.g. to bound the cost of a pattern before compiling it.
+
+    Variables are measured at each of their invocations, and the length estimate multiplies what a quantifier
+    repeats by its largest amount (or by its smallest for unbounded quantifiers) so that it errs on the long side.
+
+    # Example
+
+    ```rust
+    use viable_compiler::ast::to_ast;
+
+    let metrics = to_ast(r#"let .pair = { 2 of <digit>; } capture { .pair; } 10 of .pair;"#).unwrap().metrics();
+
+    assert_eq!(metrics.capture_count, 1);
+    assert_eq!(metrics.max_repeat_bound, 10);
+    assert!(metrics.estimated_regex_len >= 10 * "\\d{2}".len());
+    ```
+    */
+    #[must_use]
+    pub fn metrics(&self) -> AstMetrics {
+        let mut collector = MetricsCollector {
+            metrics: AstMetrics::default(),
+            depth: 0,
+            multiplier: 1,
+        };
+        walk(self, &mut collector);
+
+        collector.metrics
+    }
+}
+
+struct MetricsCollector {
+    metrics: AstMetrics,
+    /// the depth of the node being visited
+    depth: usize,
+    /// how many times the node being visited is repeated by the quantifiers around it
+    multiplier: usize,
+}
+
+impl MetricsCollector {
+    /// Adds the length of the regex syntax of a node itself, excluding its children
+    fn emit(&mut self, length: usize) {
+        self.metrics.estimated_regex_len = self
+            .metrics
+            .estimated_regex_len
+            .saturating_add(length.saturating_mul(self.multiplier));
+    }
+
+    fn enter(&mut self) {
+        self.metrics.node_count += 1;
+        self.depth += 1;
+        self.metrics.max_depth = self.metrics.max_depth.max(self.depth);
+    }
+}
+
+impl Visitor for MetricsCollector {
+    fn visit_node(&mut self, node: &Spanned<ViableAstNode>) {
+        self.enter();
+        walk_node(self, node);
+        self.depth -= 1;
+    }
+
+    fn visit_expression(&mut self, expression: &Spanned<Expression>) {
+        self.enter();
+        walk_expression(self, expression);
+        self.depth -= 1;
+    }
+
+    fn visit_group(&mut self, group: &Group, _span: Span) {
+        let length = match &group.kind {
+            // `(...)` or `(?<name>...)`
+            GroupKind::Capture => {
+                self.metrics.capture_count += 1;
+                group.ident.as_ref().map_or(2, |ident| ident.len() + 5)
+            }
+            // `(?:...|...)`
+            GroupKind::Either => {
+                self.metrics.alternation_count += 1;
+                let branches = match group.statements.as_ref() {
+                    ViableAst::Root(branches) => branches.len(),
+                    ViableAst::Empty => 0,
+                };
+                4 + branches.saturating_sub(1)
+            }
+            // `(?i-m:...)`
+            GroupKind::Flagged { set, clear } => 4 + set.len() + clear.len() + usize::from(!clear.is_empty()),
+            // `(?:...)` or `(?>...)`
+            GroupKind::Match | GroupKind::Atomic => 4,
+        };
+        self.emit(length);
+
+        walk_group(self, group);
+    }
+
+    fn visit_assertion(&mut self, assertion: &Assertion, _span: Span) {
+        // `(?<!...)`
+        self.emit(5);
+
+        walk_assertion(self, assertion);
+    }
+
+    fn visit_conditional(&mut self, conditional: &Conditional, _span: Span) {
+        // `(?(<name>)...|...)`
+        let condition = match &conditional.condition {
+            Backreference::Named(name) => name.len() + 2,
+            Backreference::Numbered(index) => index.to_string().len(),
+        };
+        self.emit(condition + 5);
+
+        walk_conditional(self, conditional);
+    }
+
+    fn visit_quantifier(&mut self, quantifier: &Quantifier, _span: Span) {
+        let (repeats, bound, syntax) = match quantifier.kind {
+            QuantifierKind::Range { start, end } => (end, Some(end), format!("{{{start},{end}}}")),
+            QuantifierKind::Amount(amount) => (amount, Some(amount), format!("{{{amount}}}")),
+            QuantifierKind::AtMost(amount) => (amount, Some(amount), format!("{{0,{amount}}}")),
+            QuantifierKind::Over(amount) | QuantifierKind::AtLeast(amount) => {
+                (amount, Some(amount), format!("{{{amount},}}"))
+            }
+            QuantifierKind::Some | QuantifierKind::Any | QuantifierKind::Option => (1, None, String::from("+")),
+        };
+
+        if let Some(bound) = bound {
+            self.metrics.max_repeat_bound = self.metrics.max_repeat_bound.max(bound);
+        }
+
+        // a lazy or possessive suffix, and a group around an atom of several characters or switching to a unit
+        let suffix = usize::from(quantifier.lazy || quantifier.possessive);
+        let grouping = match &quantifier.expression.node {
+            _ if quantifier.unit.is_some() => "(?-u:)".len(),
+            Expression::Atom(atom) if atom.chars().count() > 1 => 4,
+            _ => 0,
+        };
+        self.emit(syntax.len() + suffix + grouping);
+
+        let multiplier = self.multiplier;
+        self.multiplier = multiplier.saturating_mul(repeats.max(1));
+        walk_quantifier(self, quantifier);
+        self.multiplier = multiplier;
+    }
+
+    fn visit_atom(&mut self, atom: &str, _span: Span) {
+        self.emit(atom.len());
+    }
+
+    fn visit_raw_regex(&mut self, regex: &str, _span: Span) {
+        self.metrics.capture_count += raw_regex_captures(regex).map_or(0, |captures| captures.len());
+        self.emit(regex.len());
+    }
+
+    fn visit_range(&mut self, range: &Range, _span: Span) {
+        // `[a-z]` or `[^a-z]`
+        let negative = match range {
+            Range::CharRange(range) => range.negative,
+            Range::NumericRange(range) => range.negative,
+        };
+        self.emit(5 + usize::from(negative));
+    }
+
+    fn visit_symbol(&mut self, symbol: &Symbol, _span: Span) {
+        // `\d` or `\D`, other negated symbols are emitted as a class, e.g. `[^a-zA-Z]`
+        let shorthand = matches!(
+            symbol.kind,
+            SymbolKind::Digit | SymbolKind::Word | SymbolKind::Whitespace | SymbolKind::Boundary
+        );
+        self.emit(if symbol.negative && !shorthand { 12 } else { 2 });
+    }
+
+    fn visit_special_symbol(&mut self, special_symbol: &SpecialSymbolKind, _span: Span) {
+        // `^` or a lookaround emulating an anchor, e.g. `(?!\w)`
+        let length = match special_symbol {
+            SpecialSymbolKind::Start | SpecialSymbolKind::End | SpecialSymbolKind::LineStart => 1,
+            _ => 6,
+        };
+        self.emit(length);
+    }
+
+    fn visit_unicode_category(&mut self, _category: &UnicodeCategory, _span: Span) {
+        // `\p{Lu}`
+        self.emit(6);
+    }
+
+    fn visit_unicode_property(&mut self, property: &UnicodeProperty, _span: Span) {
+        // `\p{Greek}`
+        self.emit(property.name.len() + 4);
+    }
+
+    fn visit_char_class(&mut self, class: &CharClass, _span: Span) {
+        self.emit(2 + usize::from(class.negative) + class_items_length(&class.items));
+    }
+
+    fn visit_negative_char_class(&mut self, items: &[ClassItem], _span: Span) {
+        self.emit(3 + class_items_length(items));
+    }
+
+    fn visit_backreference(&mut self, backreference: &Backreference, _span: Span) {
+        // `\k<name>` or `\1`
+        let length = match backreference {
+            Backreference::Named(name) => name.len() + 4,
+            Backreference::Numbered(index) => index.to_string().len() + 1,
+        };
+        self.emit(length);
+    }
+
+    fn visit_recursion(&mut self, recursion: &Recursion, _span: Span) {
+        // `(?R)` or `(?&name)`
+        let length = match recursion {
+            Recursion::Pattern => 4,
+            Recursion::Group(name) => name.len() + 4,
+        };
+        self.emit(length);
+    }
+}
+
+fn class_items_length(items: &[ClassItem]) -> usize {
+    items
+        .iter()
+        .map(|item| match item {
+            // escaped characters take two, e.g. `\-` or `\d`
+            ClassItem::Char(_) | ClassItem::Symbol(_) => 2,
+            ClassItem::Range(_) => 5,
+            ClassItem::Raw(raw) => raw.len(),
+            ClassItem::UnicodeCategory(_) => 6,
+            ClassItem::UnicodeProperty(property) => property.name.len() + 4,
+            // `--[...]` or `&&[...]`
+            ClassItem::Subtract(items) | ClassItem::Intersect(items) => 4 + class_items_length(items),
+        })
+        .sum()
+}
@@ -0,0 +1,703 @@
+use super::transform::{fold, fold_ast, fold_expression, fold_group, fold_node, fold_variable_invocation, Transform};
+use super::types::ast::{
+    CharClass, ClassItem, Expression, Group, GroupKind, ParameterizedDeclaration, Quantifier, QuantifierKind, Range,
+    Span, Spanned, VariableInvocation, ViableAst, ViableAstNode,
+};
+use super::utils::{raw_regex_captures, SharedStatements};
+use super::visitor::{walk_group, walk_node, Visitor};
+use crate::options::{CompilerOptions, OptimizationLevel};
+use crate::prelude::*;
+use alloc::rc::Rc;
+
+/// Rewrites an AST into an equivalent one that compiles to a simpler regex, according to `options`
+///
+/// [`CompilerOptions::optimization`] decides which rewrites may apply and the options of each rewrite (e.g.
+/// [`CompilerOptions::either_to_class`]) whether it does, so [`OptimizationLevel::None`] applies none of them
+/// whatever those options are set to. Only [`CompilerOptions::normalize`] applies at every level.
+///
+/// Optimizations only add non-capturing groups, so capture group numbering is never changed
+#[must_use]
+pub fn optimize(ast: ViableAst, options: &CompilerOptions) -> ViableAst {
+    let ast = if options.normalize { ast.normalize() } else { ast };
+
+    if options.optimization == OptimizationLevel::None {
+        return ast;
+    }
+
+    Optimizer {
+        options,
+        shared: SharedStatements::default(),
+    }
+    .ast(ast)
+}
+
+impl ViableAst {
+    /// Merges consecutive atoms into a single atom at every nesting level (e.g. `"foo"; "bar";` into `foobar`)
+    ///
+    /// Atoms hold regex source, so merging them compiles to the same regex. The branches of an `either`
+    /// group and the expressions of quantifiers are never merged with their neighbours.
+    #[must_use]
+    pub fn normalize(self) -> Self {
+        fold(self, &mut Normalizer::default())
+    }
+
+    /**
+    Whether two ASTs match the same way, whichever way their sources were written
+
+    Unlike `==`, the comparison ignores:
+
+    - spans, including those of invocations, their arguments and the declarations of parameterized variables
+    - [`ViableAstNode::Skip`] nodes, and the difference between [`ViableAst::Empty`] and an empty root
+    - how atoms are split, as both ASTs are [normalized](ViableAst::normalize) (e.g. `"ab";` and `"a"; "b";`)
+    - ranges of a single amount (e.g. `3 to 3 of` and `3 of`)
+    - the difference between negated class shorthands and negated classes (e.g. `not abc;` and
+      `not class { "a"; "b"; "c"; }`)
+
+    The quotes and escapes of atoms are already resolved when converting, so `"a";` and `'a';` are equal either way.
+
+    # Example
+
+    ```rust
+    use viable_compiler::ast::to_ast;
+
+    let first = to_ast(r#""a"; "b"; 2 to 2 of "c";"#).unwrap();
+    let second = to_ast("'ab';\n2 of `c`;").unwrap();
+
+    assert!(first.semantically_eq(&second));
+    assert!(first != second);
+    ```
+    */
+    #[must_use]
+    pub fn semantically_eq(&self, other: &Self) -> bool {
+        canonical(self) == canonical(other)
+    }
+}
+
+/// The form two ASTs are compared in by [`ViableAst::semantically_eq`]
+fn canonical(ast: &ViableAst) -> ViableAst {
+    fold(ast.clone(), &mut Canonicalizer).normalize()
+}
+
+#[derive(Default)]
+struct Normalizer {
+    shared: SharedStatements<()>,
+}
+
+impl Transform for Normalizer {
+    fn transform_ast(&mut self, ast: ViableAst) -> ViableAst {
+        match fold_ast(self, ast) {
+            ViableAst::Root(nodes) => ViableAst::Root(merge_atoms(nodes)),
+            ViableAst::Empty => ViableAst::Empty,
+        }
+    }
+
+    fn transform_group(&mut self, mut group: Group, _span: Span) -> Group {
+        // each statement of an `either` group is a branch of its own
+        if group.kind == GroupKind::Either {
+            group.statements = Box::new(fold_ast(self, *group.statements));
+            group
+        } else {
+            fold_group(self, group)
+        }
+    }
+
+    fn transform_variable_invocation(&mut self, mut invocation: VariableInvocation, _span: Span) -> VariableInvocation {
+        invocation.statements = match self.shared.get(&invocation.statements) {
+            Some((normalized, ())) => normalized,
+            None => match Rc::try_unwrap(invocation.statements) {
+                Ok(statements) => Rc::new(self.transform_ast(statements)),
+                Err(statements) => {
+                    let normalized = Rc::new(self.transform_ast((*statements).clone()));
+                    self.shared.insert(statements, normalized.clone(), ());
+                    normalized
+                }
+            },
+        };
+        invocation
+    }
+}
+
+/// Removes what [`ViableAst::semantically_eq`] ignores, ahead of normalizing
+struct Canonicalizer;
+
+impl Transform for Canonicalizer {
+    fn transform_ast(&mut self, ast: ViableAst) -> ViableAst {
+        match fold_ast(self, ast) {
+            ViableAst::Empty => ViableAst::Root(Vec::new()),
+            ast @ ViableAst::Root(_) => ast,
+        }
+    }
+
+    fn transform_node(&mut self, node: Spanned<ViableAstNode>) -> Vec<Spanned<ViableAstNode>> {
+        if matches!(node.node, ViableAstNode::Skip) {
+            return Vec::new();
+        }
+
+        let node = match fold_node(self, node).node {
+            ViableAstNode::NegativeCharClass(items) => ViableAstNode::CharClass(CharClass { negative: true, items }),
+            node => node,
+        };
+        vec![Spanned {
+            node,
+            span: Span::default(),
+        }]
+    }
+
+    fn transform_quantifier(&mut self, mut quantifier: Quantifier, _span: Span) -> Quantifier {
+        if let QuantifierKind::Range { start, end } = quantifier.kind {
+            if start == end {
+                quantifier.kind = QuantifierKind::Amount(start);
+            }
+        }
+        quantifier.expression = Box::new(self.transform_expression(*quantifier.expression));
+        quantifier
+    }
+
+    fn transform_expression(&mut self, expression: Spanned<Expression>) -> Spanned<Expression> {
+        let node = match fold_expression(self, expression).node {
+            Expression::NegativeCharClass(items) => Expression::CharClass(CharClass { negative: true, items }),
+            node => node,
+        };
+        Spanned {
+            node,
+            span: Span::default(),
+        }
+    }
+
+    fn transform_variable_invocation(&mut self, invocation: VariableInvocation, _span: Span) -> VariableInvocation {
+        let mut invocation = fold_variable_invocation(self, invocation);
+        invocation.span = Span::default();
+        invocation.arguments = core::mem::take(&mut invocation.arguments)
+            .into_iter()
+            .flat_map(|argument| self.transform_node(argument))
+            .collect();
+        // the declaration is never normalized with the rest of the AST, so its atoms are merged here
+        invocation.declaration = invocation.declaration.map(|declaration| {
+            Rc::new(ParameterizedDeclaration {
+                parameters: declaration.parameters.clone(),
+                statements: Rc::new(self.transform_ast((*declaration.statements).clone()).normalize()),
+            })
+        });
+        invocation
+    }
+}
+
+fn merge_atoms(nodes: Vec<Spanned<ViableAstNode>>) -> Vec<Spanned<ViableAstNode>> {
+    let mut merged: Vec<Spanned<ViableAstNode>> = Vec::with_capacity(nodes.len());
+    let mut run: Vec<Spanned<ViableAstNode>> = Vec::new();
+
+    for node in nodes {
+        if matches!(node.node, ViableAstNode::Atom(_)) {
+            run.push(node);
+            continue;
+        }
+
+        push_atoms(&mut run, &mut merged);
+        merged.push(node);
+    }
+
+    push_atoms(&mut run, &mut merged);
+    merged
+}
+
+/// Pushes a run of consecutive atoms as a single atom, joined once rather than one atom at a time
+fn push_atoms(run: &mut Vec<Spanned<ViableAstNode>>, merged: &mut Vec<Spanned<ViableAstNode>>) {
+    let (Some(first), Some(last)) = (run.first(), run.last()) else {
+        return;
+    };
+
+    if run.len() == 1 {
+        merged.append(run);
+        return;
+    }
+
+    let span = Span {
+        end: last.span.end,
+        ..first.span
+    };
+    let atom: String = run
+        .drain(..)
+        .filter_map(|node| match node.node {
+            ViableAstNode::Atom(atom) => Some(atom),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .concat();
+
+    merged.push(Spanned {
+        node: ViableAstNode::Atom(atom.into()),
+        span,
+    });
+}
+
+struct Optimizer<'options> {
+    options: &'options CompilerOptions,
+    shared: SharedStatements<()>,
+}
+
+impl Optimizer<'_> {
+    fn ast(&mut self, ast: ViableAst) -> ViableAst {
+        match self.branches(ast) {
+            ViableAst::Root(nodes) if self.options.merge_negated_classes => ViableAst::Root(merge_negated(nodes)),
+            ast => ast,
+        }
+    }
+
+    /// Optimizes each statement on its own, as the statements of an `either` group are alternatives
+    fn branches(&mut self, ast: ViableAst) -> ViableAst {
+        match ast {
+            ViableAst::Root(nodes) => ViableAst::Root(nodes.into_iter().map(|node| self.node(node)).collect()),
+            ViableAst::Empty => ViableAst::Empty,
+        }
+    }
+
+    fn node(&mut self, node: Spanned<ViableAstNode>) -> Spanned<ViableAstNode> {
+        let Spanned { node, span } = node;
+
+        let node = match node {
+            ViableAstNode::Group(group) => match self.group(group, span) {
+                Ok(class) => ViableAstNode::CharClass(class),
+                Err(group) => ViableAstNode::Group(group),
+            },
+            ViableAstNode::Assertion(mut assertion) => {
+                assertion.statements = Box::new(self.ast(*assertion.statements));
+                ViableAstNode::Assertion(assertion)
+            }
+            ViableAstNode::Conditional(mut conditional) => {
+                conditional.then = Box::new(self.ast(*conditional.then));
+                conditional.otherwise = conditional.otherwise.map(|otherwise| Box::new(self.ast(*otherwise)));
+                ViableAstNode::Conditional(conditional)
+            }
+            ViableAstNode::VariableInvocation(mut invocation) => {
+                invocation.statements = match self.shared.get(&invocation.statements) {
+                    Some((optimized, ())) => optimized,
+                    None => match Rc::try_unwrap(invocation.statements) {
+                        Ok(statements) => Rc::new(self.ast(statements)),
+                        Err(statements) => {
+                            let optimized = Rc::new(self.ast((*statements).clone()));
+                            self.shared.insert(statements, optimized.clone(), ());
+                            optimized
+                        }
+                    },
+                };
+                ViableAstNode::VariableInvocation(invocation)
+            }
+            ViableAstNode::Quantifier(mut quantifier) => {
+                if let Expression::Group(group) = quantifier.expression.node {
+                    quantifier.expression.node = match self.group(group, quantifier.expression.span) {
+                        Ok(class) => Expression::CharClass(class),
+                        Err(group) => Expression::Group(group),
+                    };
+                }
+                ViableAstNode::Quantifier(quantifier)
+            }
+            node => node,
+        };
+
+        Spanned { node, span }
+    }
+
+    /// Lowers an `either` group to a character class if every branch matches a single character,
+    /// otherwise returns the group optimized
+    fn group(&mut self, mut group: Group, span: Span) -> Result<CharClass, Group> {
+        if group.kind != GroupKind::Either {
+            group.statements = Box::new(self.ast(*group.statements));
+            return Err(group);
+        }
+
+        if self.options.flatten_either {
+            if let ViableAst::Root(nodes) = *group.statements {
+                let mut branches = Vec::with_capacity(nodes.len());
+                flatten_branches(nodes, &mut branches);
+                group.statements = Box::new(ViableAst::Root(branches));
+            }
+        }
+
+        group.statements = Box::new(self.branches(*group.statements));
+
+        if self.options.flatten_either {
+            if let ViableAst::Root(nodes) = *group.statements {
+                group.statements = Box::new(ViableAst::Root(dedupe_branches(nodes)));
+            }
+        }
+
+        if self.options.either_to_class {
+            if let Some(items) = either_class_items(&group.statements) {
+                return Ok(CharClass { negative: false, items });
+            }
+        }
+
+        if self.options.optimization >= OptimizationLevel::Aggressive {
+            if let Some(statements) = self.factor_either(&group.statements, span) {
+                return Err(Group {
+                    ident: None,
+                    kind: GroupKind::Match,
+                    statements: Box::new(ViableAst::Root(statements)),
+                });
+            }
+        }
+
+        Err(group)
+    }
+
+    /// Splits an `either` group of literals into its common prefix, the group of what remains of each
+    /// branch and its common suffix, e.g. `foob`, `(?:ar|az)` and nothing for `foobar` and `foobaz`
+    fn factor_either(&mut self, statements: &ViableAst, span: Span) -> Option<Vec<Spanned<ViableAstNode>>> {
+        let ViableAst::Root(nodes) = statements else {
+            return None;
+        };
+
+        let branches = nodes
+            .iter()
+            .filter(|node| !matches!(node.node, ViableAstNode::Skip))
+            .map(|node| match &node.node {
+                ViableAstNode::Atom(atom) => atom_units(atom),
+                _ => None,
+            })
+            .collect::<Option<Vec<Vec<&str>>>>()?;
+
+        if branches.len() < 2 {
+            return None;
+        }
+
+        let shortest = branches.iter().map(Vec::len).min().unwrap_or_default();
+
+        let prefix_length = (0..shortest)
+            .take_while(|index| branches.iter().all(|branch| branch[*index] == branches[0][*index]))
+            .count();
+
+        let suffix_length = (0..shortest - prefix_length)
+            .take_while(|index| {
+                let last = &branches[0][branches[0].len() - 1 - index];
+                branches.iter().all(|branch| branch[branch.len() - 1 - index] == *last)
+            })
+            .count();
+
+        if prefix_length == 0 && suffix_length == 0 {
+            return None;
+        }
+
+        let atom = |units: &[&str]| Spanned {
+            node: ViableAstNode::Atom(units.concat().into()),
+            span,
+        };
+
+        let remainders = branches
+            .iter()
+            .map(|branch| atom(&branch[prefix_length..branch.len() - suffix_length]))
+            .collect();
+
+        let remainders = Group {
+            ident: None,
+            kind: GroupKind::Either,
+            statements: Box::new(ViableAst::Root(remainders)),
+        };
+
+        let remainders = match self.group(remainders, span) {
+            Ok(class) => ViableAstNode::CharClass(class),
+            Err(group) => ViableAstNode::Group(group),
+        };
+
+        let mut factored = Vec::new();
+
+        if prefix_length > 0 {
+            factored.push(atom(&branches[0][..prefix_length]));
+        }
+
+        factored.push(Spanned { node: remainders, span });
+
+        if suffix_length > 0 {
+            factored.push(atom(&branches[0][branches[0].len() - suffix_length..]));
+        }
+
+        Some(factored)
+    }
+}
+
+/// Pushes the branches of an `either` group, replacing each branch that is itself an `either` group (directly or
+/// as the only statement of a variable) by its own branches
+///
+/// The branches keep their order, so the capture groups within them keep their numbering
+fn flatten_branches(nodes: Vec<Spanned<ViableAstNode>>, flattened: &mut Vec<Spanned<ViableAstNode>>) {
+    for Spanned { node, span } in nodes {
+        match node {
+            ViableAstNode::Group(group) if nested_branches(&group).is_some() => {
+                if let ViableAst::Root(branches) = *group.statements {
+                    flatten_branches(branches, flattened);
+                }
+            }
+            ViableAstNode::VariableInvocation(invocation) => match invoked_branches(&invocation.statements) {
+                Some(branches) => flatten_branches(branches.to_vec(), flattened),
+                None => flattened.push(Spanned {
+                    node: ViableAstNode::VariableInvocation(invocation),
+                    span,
+                }),
+            },
+            node => flattened.push(Spanned { node, span }),
+        }
+    }
+}
+
+/// The branches of an `either` group, if it has any, as an empty one matches the empty string rather than
+/// nothing
+fn nested_branches(group: &Group) -> Option<&[Spanned<ViableAstNode>]> {
+    match group.statements.as_ref() {
+        ViableAst::Root(nodes)
+            if group.kind == GroupKind::Either
+                && nodes.iter().any(|node| !matches!(node.node, ViableAstNode::Skip)) =>
+        {
+            Some(nodes)
+        }
+        _ => None,
+    }
+}
+
+/// The branches of the statements of a variable, if they are a single `either` group
+fn invoked_branches(statements: &ViableAst) -> Option<&[Spanned<ViableAstNode>]> {
+    let ViableAst::Root(nodes) = statements else {
+        return None;
+    };
+
+    let mut statements = nodes.iter().filter(|node| !matches!(node.node, ViableAstNode::Skip));
+    match (statements.next(), statements.next()) {
+        (
+            Some(Spanned {
+                node: ViableAstNode::Group(group),
+                ..
+            }),
+            None,
+        ) => nested_branches(group),
+        _ => None,
+    }
+}
+
+/// Removes the branches of an `either` group that match like an earlier one, which can never match where the
+/// earlier branch doesn't
+///
+/// Branches holding a capture group are kept, as removing them would renumber the capture groups after them
+fn dedupe_branches(nodes: Vec<Spanned<ViableAstNode>>) -> Vec<Spanned<ViableAstNode>> {
+    let mut seen = Vec::with_capacity(nodes.len());
+
+    nodes
+        .into_iter()
+        .filter(|node| {
+            if matches!(node.node, ViableAstNode::Skip) || contains_capture(node) {
+                return true;
+            }
+
+            let branch = canonical(&ViableAst::Root(vec![node.clone()]));
+            if seen.contains(&branch) {
+                false
+            } else {
+                seen.push(branch);
+                true
+            }
+        })
+        .collect()
+}
+
+fn contains_capture(node: &Spanned<ViableAstNode>) -> bool {
+    struct CaptureFinder(bool);
+
+    impl Visitor for CaptureFinder {
+        fn visit_group(&mut self, group: &Group, _span: Span) {
+            self.0 |= group.kind == GroupKind::Capture;
+            walk_group(self, group);
+        }
+
+        fn visit_raw_regex(&mut self, regex: &str, _span: Span) {
+            self.0 |= raw_regex_captures(regex).is_ok_and(|captures| !captures.is_empty());
+        }
+    }
+
+    let mut finder = CaptureFinder(false);
+    walk_node(&mut finder, node);
+    finder.0
+}
+
+/// Merges runs of consecutive negated ranges, classes and symbols into a single negated class of their members,
+/// which matches one character none of them match rather than one character for each
+fn merge_negated(nodes: Vec<Spanned<ViableAstNode>>) -> Vec<Spanned<ViableAstNode>> {
+    let mut merged: Vec<Spanned<ViableAstNode>> = Vec::with_capacity(nodes.len());
+    let mut run: Vec<Spanned<ViableAstNode>> = Vec::new();
+
+    for node in nodes {
+        if negated_class_items(&node.node).is_some() {
+            run.push(node);
+            continue;
+        }
+
+        // skipped statements emit nothing, so they don't end a run
+        if matches!(node.node, ViableAstNode::Skip) && !run.is_empty() {
+            continue;
+        }
+
+        push_negated(&mut run, &mut merged);
+        merged.push(node);
+    }
+
+    push_negated(&mut run, &mut merged);
+    merged
+}
+
+/// Pushes a run of consecutive negated statements as a single negated class, a lone statement is kept as written
+fn push_negated(run: &mut Vec<Spanned<ViableAstNode>>, merged: &mut Vec<Spanned<ViableAstNode>>) {
+    let (Some(first), Some(last)) = (run.first(), run.last()) else {
+        return;
+    };
+
+    if run.len() == 1 {
+        merged.append(run);
+        return;
+    }
+
+    let span = Span {
+        end: last.span.end,
+        ..first.span
+    };
+    let items = run
+        .drain(..)
+        .filter_map(|node| negated_class_items(&node.node))
+        .flatten()
+        .collect();
+
+    merged.push(Spanned {
+        node: ViableAstNode::CharClass(CharClass { negative: true, items }),
+        span,
+    });
+}
+
+/// The members a negated node excludes, if it is a single character that can be merged with others
+fn negated_class_items(node: &ViableAstNode) -> Option<Vec<ClassItem>> {
+    match node {
+        ViableAstNode::Range(Range::CharRange(range)) if range.negative => {
+            let mut range = range.clone();
+            range.negative = false;
+            Some(vec![ClassItem::Range(Range::CharRange(range))])
+        }
+        ViableAstNode::Range(Range::NumericRange(range)) if range.negative => {
+            let mut range = range.clone();
+            range.negative = false;
+            Some(vec![ClassItem::Range(Range::NumericRange(range))])
+        }
+        ViableAstNode::NegativeCharClass(items) => Some(items.clone()),
+        // a class with set operations excludes less than the union of its members
+        ViableAstNode::CharClass(class)
+            if class.negative
+                && !class
+                    .items
+                    .iter()
+                    .any(|item| matches!(item, ClassItem::Subtract(_) | ClassItem::Intersect(_))) =>
+        {
+            Some(class.items.clone())
+        }
+        ViableAstNode::Symbol(symbol) if symbol.negative && symbol.kind.is_class_compatible(false) => {
+            let mut symbol = symbol.clone();
+            symbol.negative = false;
+            Some(vec![ClassItem::Symbol(symbol)])
+        }
+        _ => None,
+    }
+}
+
+fn either_class_items(statements: &ViableAst) -> Option<Vec<ClassItem>> {
+    let ViableAst::Root(nodes) = statements else {
+        return None;
+    };
+
+    let items = nodes
+        .iter()
+        .filter(|node| !matches!(node.node, ViableAstNode::Skip))
+        .map(|node| match &node.node {
+            ViableAstNode::Atom(atom) => atom_char(atom).map(ClassItem::Char),
+            // negated members would negate the class as a whole, so they aren't merged
+            ViableAstNode::Symbol(symbol) if !symbol.negative && symbol.kind.is_class_compatible(false) => {
+                Some(ClassItem::Symbol(symbol.clone()))
+            }
+            ViableAstNode::Range(Range::CharRange(range)) if !range.negative => {
+                Some(ClassItem::Range(Range::CharRange(range.clone())))
+            }
+            ViableAstNode::Range(Range::NumericRange(range)) if !range.negative => {
+                Some(ClassItem::Range(Range::NumericRange(range.clone())))
+            }
+            _ => None,
+        })
+        .collect::<Option<Vec<ClassItem>>>()?;
+
+    // a single branch gains nothing from being a class
+    (items.len() > 1).then_some(items)
+}
+
+const METACHARACTERS: &str = "[](){}*+?|^$.\\";
+
+/// The character an atom matches, if it matches exactly one
+///
+/// Atoms hold regex source, so an unescaped metacharacter (only possible from a raw atom) isn't a character
+pub(super) fn atom_char(atom: &str) -> Option<char> {
+    let mut chars = atom.chars();
+
+    match (chars.next()?, chars.next(), chars.as_str()) {
+        (char, None, _) if !METACHARACTERS.contains(char) => Some(char),
+        ('\\', Some(escaped), "") => match escaped {
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            'f' => Some('\u{c}'),
+            escaped if escaped.is_ascii_punctuation() => Some(escaped),
+            _ => None,
+        },
+        ('\\', Some('x'), digits) if digits.len() == 2 => u32::from_str_radix(digits, 16).ok().and_then(char::from_u32),
+        _ => None,
+    }
+}
+
+/// The text an atom matches, if it holds no regex syntax other than escapes
+pub(crate) fn atom_text(atom: &str) -> Option<String> {
+    atom_units(atom)?
+        .into_iter()
+        .map(|unit| {
+            let mut chars = unit.chars();
+            match (chars.next()?, chars.next()) {
+                (char, None) => Some(char),
+                ('\\', Some('n')) => Some('\n'),
+                ('\\', Some('r')) => Some('\r'),
+                ('\\', Some('t')) => Some('\t'),
+                ('\\', Some('f')) => Some('\u{c}'),
+                ('\\', Some('x' | 'u')) => char::from_u32(u32::from_str_radix(&unit[2..], 16).ok()?),
+                ('\\', Some(escaped)) if escaped.is_ascii_punctuation() => Some(escaped),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Splits an atom into the units of regex source that each match one character, e.g. `a`, `\.` or `\x0A`
+///
+/// Returns `None` if the atom contains an unescaped metacharacter, as splitting it could change its meaning
+pub(super) fn atom_units(atom: &str) -> Option<Vec<&str>> {
+    let mut units = Vec::new();
+    let mut rest = atom;
+
+    while let Some(char) = rest.chars().next() {
+        let length = if char == '\\' {
+            let escaped = rest[1..].chars().next()?;
+            match escaped {
+                'x' => 4,
+                'u' => 6,
+                // `\1` followed by `0` is a different backreference than `\10`
+                '0'..='9' => 1 + rest[1..].chars().take_while(char::is_ascii_digit).count(),
+                escaped => 1 + escaped.len_utf8(),
+            }
+        } else if METACHARACTERS.contains(char) {
+            return None;
+        } else {
+            char.len_utf8()
+        };
+
+        let unit = rest.get(..length)?;
+        units.push(unit);
+        rest = &rest[length..];
+    }
+
+    Some(units)
+}
@@ -0,0 +1,137 @@
+use super::source_to_ast::to_ast_with_declarations;
+use super::types::ast::{Assertion, Group, GroupKind, Span, ViableAst};
+use super::visitor::{walk_assertion, walk_group, Visitor};
+use super::walk;
+use crate::prelude::*;
+use crate::types::Result;
+
+/// What an [`OutlineItem`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutlineKind {
+    /// `let .name = { ... }`
+    Variable,
+    /// `capture { ... }` or `capture name { ... }`
+    CaptureGroup,
+    /// any other group, e.g. `match { ... }` or `either { ... }`
+    Group,
+    /// `ahead { ... }`, `behind { ... }` and their negations
+    Assertion,
+}
+
+/// A declaration or block of a source, holding the items nested within it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutlineItem {
+    pub kind: OutlineKind,
+    /// the name of a variable or named capture group
+    pub name: Option<String>,
+    /// the span of the whole statement, including its block
+    pub span: Span,
+    pub children: Vec<OutlineItem>,
+}
+
+/**
+Lists the variable declarations and blocks of a source as a tree mirroring their nesting, e.g. for the document
+symbols and folding ranges of an editor.
+
+Items are in source order and the statements of a variable are only listed within its declaration, not at each of
+its invocations. Statements that fail to convert are left out like in [`to_ast_lenient`](super::to_ast_lenient)
+so that the outline of the rest of the source is still returned.
+
+# Errors
+
+Returns the [`CompilerError::ParseError`](crate::errors::CompilerError::ParseError) if the source doesn't parse
+at all
+
+# Example
+
+```rust
+use viable_compiler::ast::outline::{outline, OutlineKind};
+
+let items = outline(r#"let .digits = { capture digits { some of <digit>; } } .digits;"#).unwrap();
+
+assert_eq!(items[0].kind, OutlineKind::Variable);
+assert_eq!(items[0].name.as_deref(), Some("digits"));
+assert_eq!(items[0].children[0].kind, OutlineKind::CaptureGroup);
+```
+*/
+pub fn outline(source: &str) -> Result<Vec<OutlineItem>> {
+    let (ast, declarations) = to_ast_with_declarations(source)?;
+
+    let mut items = items_of(&ast);
+    for declaration in declarations {
+        let item = OutlineItem {
+            kind: OutlineKind::Variable,
+            name: Some(declaration.name),
+            span: declaration.span,
+            children: items_of(&declaration.statements),
+        };
+        insert(&mut items, item);
+    }
+
+    Ok(items)
+}
+
+fn items_of(ast: &ViableAst) -> Vec<OutlineItem> {
+    let mut builder = OutlineBuilder::default();
+    walk(ast, &mut builder);
+    builder.items
+}
+
+/// Inserts a declaration into the innermost item containing it, the statements of a declaration are not part of
+/// the AST of the block it is declared in
+fn insert(items: &mut Vec<OutlineItem>, item: OutlineItem) {
+    if let Some(parent) = items
+        .iter_mut()
+        .find(|parent| parent.span.start <= item.span.start && item.span.end <= parent.span.end)
+    {
+        return insert(&mut parent.children, item);
+    }
+
+    let index = items.partition_point(|sibling| sibling.span.start < item.span.start);
+    items.insert(index, item);
+}
+
+#[derive(Default)]
+struct OutlineBuilder {
+    items: Vec<OutlineItem>,
+}
+
+impl OutlineBuilder {
+    /// Adds an item holding the items found while walking its children with `walk_children`
+    fn nest(&mut self, kind: OutlineKind, name: Option<String>, span: Span, walk_children: impl FnOnce(&mut Self)) {
+        let siblings = core::mem::take(&mut self.items);
+        walk_children(self);
+        let children = core::mem::replace(&mut self.items, siblings);
+
+        self.items.push(OutlineItem {
+            kind,
+            name,
+            span,
+            children,
+        });
+    }
+}
+
+impl Visitor for OutlineBuilder {
+    fn visit_invocation_statements(&self) -> bool {
+        false
+    }
+
+    fn visit_group(&mut self, group: &Group, span: Span) {
+        let kind = match group.kind {
+            GroupKind::Capture => OutlineKind::CaptureGroup,
+            _ => OutlineKind::Group,
+        };
+        let name = group.ident.as_deref().map(str::to_owned);
+
+        self.nest(kind, name, span, |builder| walk_group(builder, group));
+    }
+
+    fn visit_assertion(&mut self, assertion: &Assertion, span: Span) {
+        self.nest(OutlineKind::Assertion, None, span, |builder| {
+            walk_assertion(builder, assertion);
+        });
+    }
+}
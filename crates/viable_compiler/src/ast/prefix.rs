@@ -0,0 +1,286 @@
+use super::optimization::atom_text;
+use super::types::ast::{
+    Expression, Flag, Group, GroupKind, Quantifier, QuantifierKind, Range, SpecialSymbolKind, Symbol, SymbolKind,
+    ViableAst, ViableAstNode,
+};
+use crate::prelude::*;
+
+/// The literal text every match of a pattern starts with, see [`literal_prefix`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrefixInfo {
+    /// whether the pattern matches the prefix and nothing else, apart from where its anchors allow it to
+    pub exact: bool,
+    pub prefix: String,
+    /// whether the pattern starts with `<start>` or `<input_start>`
+    pub anchored_start: bool,
+    /// whether the pattern ends with `<end>` or `<input_end>`
+    pub anchored_end: bool,
+}
+
+/**
+Finds the literal text every match of an AST starts with, e.g. to skip to the candidates of a match with a fast
+substring search before running the regex.
+
+The prefix stops at the first statement that doesn't match a known text, such as a quantifier that may repeat
+nothing, a class or an assertion. Exact quantifiers of a literal repeat it, and the prefix of an `either` group is
+the one its branches have in common. Flags of a `flags` block aren't part of the AST, so the prefix assumes the
+pattern is case sensitive unless a scoped flag says otherwise.
+
+# Example
+
+```rust
+use viable_compiler::ast::{literal_prefix, to_ast};
+
+let ast = to_ast(r#"<start>; "v"; 2 of "1"; either { ".0"; ".1"; } some of <digit>;"#).unwrap();
+let info = literal_prefix(&ast);
+
+assert_eq!(info.prefix, "v11.");
+assert!(!info.exact);
+assert!(info.anchored_start);
+```
+*/
+#[must_use]
+pub fn literal_prefix(ast: &ViableAst) -> PrefixInfo {
+    let prefix = statements_prefix(ast);
+
+    PrefixInfo {
+        exact: prefix.complete,
+        prefix: prefix.text,
+        anchored_start: anchored(ast, Edge::Start),
+        anchored_end: anchored(ast, Edge::End),
+    }
+}
+
+/// The text a construct starts with, `complete` if it matches that text and nothing else
+struct Prefix {
+    text: String,
+    complete: bool,
+}
+
+impl Prefix {
+    const fn complete(text: String) -> Self {
+        Self { text, complete: true }
+    }
+
+    const fn partial(text: String) -> Self {
+        Self { text, complete: false }
+    }
+
+    /// A construct whose matches are not known to start with any text
+    const fn unknown() -> Self {
+        Self::partial(String::new())
+    }
+}
+
+fn statements_prefix(ast: &ViableAst) -> Prefix {
+    let ViableAst::Root(nodes) = ast else {
+        return Prefix::complete(String::new());
+    };
+
+    let mut text = String::new();
+    for node in nodes {
+        let prefix = node_prefix(&node.node);
+        text.push_str(&prefix.text);
+
+        if !prefix.complete {
+            return Prefix::partial(text);
+        }
+    }
+
+    Prefix::complete(text)
+}
+
+fn node_prefix(node: &ViableAstNode) -> Prefix {
+    match node {
+        ViableAstNode::Group(group) => group_prefix(group),
+        ViableAstNode::Quantifier(quantifier) => quantifier_prefix(quantifier),
+        ViableAstNode::Atom(atom) => atom_prefix(atom),
+        ViableAstNode::Range(range) => range_prefix(range),
+        ViableAstNode::Symbol(symbol) => symbol_prefix(symbol),
+        ViableAstNode::VariableInvocation(invocation) => statements_prefix(&invocation.statements),
+        // anchors match no text, so the text around them is still matched literally
+        ViableAstNode::SpecialSymbol(
+            SpecialSymbolKind::Start
+            | SpecialSymbolKind::End
+            | SpecialSymbolKind::LineStart
+            | SpecialSymbolKind::LineEnd
+            | SpecialSymbolKind::InputStart
+            | SpecialSymbolKind::InputEnd,
+        )
+        | ViableAstNode::Empty
+        | ViableAstNode::Skip => Prefix::complete(String::new()),
+        ViableAstNode::SpecialSymbol(SpecialSymbolKind::WordBoundary | SpecialSymbolKind::NotWordBoundary)
+        | ViableAstNode::Assertion(_)
+        | ViableAstNode::Conditional(_)
+        | ViableAstNode::UnicodeCategory(_)
+        | ViableAstNode::UnicodeProperty(_)
+        | ViableAstNode::NegativeCharClass(_)
+        | ViableAstNode::CharClass(_)
+        | ViableAstNode::Backreference(_)
+        | ViableAstNode::Recursion(_)
+        | ViableAstNode::Parameter(_)
+        | ViableAstNode::RawRegex(_)
+        | ViableAstNode::Error => Prefix::unknown(),
+    }
+}
+
+fn expression_prefix(expression: &Expression) -> Prefix {
+    match expression {
+        Expression::Group(group) => group_prefix(group),
+        Expression::Atom(atom) => atom_prefix(atom),
+        Expression::Range(range) => range_prefix(range),
+        Expression::Symbol(symbol) => symbol_prefix(symbol),
+        Expression::UnicodeCategory(_)
+        | Expression::UnicodeProperty(_)
+        | Expression::NegativeCharClass(_)
+        | Expression::CharClass(_)
+        | Expression::Backreference(_) => Prefix::unknown(),
+    }
+}
+
+fn group_prefix(group: &Group) -> Prefix {
+    match &group.kind {
+        GroupKind::Either => either_prefix(&group.statements),
+        GroupKind::Flagged { set, .. } if set.contains(&Flag::Insensitive) => Prefix::unknown(),
+        GroupKind::Match | GroupKind::Capture | GroupKind::Atomic | GroupKind::Flagged { .. } => {
+            statements_prefix(&group.statements)
+        }
+    }
+}
+
+/// The prefix the branches have in common, complete if every branch matches the same text
+fn either_prefix(branches: &ViableAst) -> Prefix {
+    let ViableAst::Root(branches) = branches else {
+        return Prefix::unknown();
+    };
+
+    let mut prefixes = branches
+        .iter()
+        .filter(|branch| branch.node != ViableAstNode::Skip)
+        .map(|branch| node_prefix(&branch.node));
+    let Some(first) = prefixes.next() else {
+        return Prefix::unknown();
+    };
+
+    prefixes.fold(first, |common, prefix| {
+        let length: usize = common
+            .text
+            .chars()
+            .zip(prefix.text.chars())
+            .take_while(|(common, char)| common == char)
+            .map(|(char, _)| char.len_utf8())
+            .sum();
+
+        Prefix {
+            complete: common.complete && prefix.complete && length == common.text.len() && length == prefix.text.len(),
+            text: common.text[..length].to_owned(),
+        }
+    })
+}
+
+fn quantifier_prefix(quantifier: &Quantifier) -> Prefix {
+    let (minimum, maximum) = match quantifier.kind {
+        QuantifierKind::Amount(amount) => (amount, Some(amount)),
+        QuantifierKind::Range { start, end } => (start, Some(end)),
+        QuantifierKind::Over(amount) | QuantifierKind::AtLeast(amount) => (amount, None),
+        QuantifierKind::Some => (1, None),
+        QuantifierKind::Any | QuantifierKind::Option | QuantifierKind::AtMost(_) => (0, None),
+    };
+
+    if minimum == 0 {
+        return Prefix::unknown();
+    }
+
+    let prefix = expression_prefix(&quantifier.expression.node);
+    if !prefix.complete {
+        return prefix;
+    }
+
+    let text = prefix.text.repeat(minimum);
+    if maximum == Some(minimum) {
+        Prefix::complete(text)
+    } else {
+        Prefix::partial(text)
+    }
+}
+
+fn atom_prefix(atom: &str) -> Prefix {
+    atom_text(atom).map_or_else(Prefix::unknown, Prefix::complete)
+}
+
+fn range_prefix(range: &Range) -> Prefix {
+    let (negative, start, end) = match range {
+        Range::CharRange(range) => (range.negative, range.start, range.end),
+        Range::NumericRange(range) => (range.negative, range.start, range.end),
+    };
+
+    if !negative && start == end {
+        Prefix::complete(start.to_string())
+    } else {
+        Prefix::unknown()
+    }
+}
+
+/// Symbols matching a single character match it literally
+fn symbol_prefix(symbol: &Symbol) -> Prefix {
+    let char = match symbol.kind {
+        _ if symbol.negative => return Prefix::unknown(),
+        SymbolKind::Space => ' ',
+        SymbolKind::Newline => '\n',
+        SymbolKind::Vertical => '\u{b}',
+        SymbolKind::Return => '\r',
+        SymbolKind::Tab => '\t',
+        SymbolKind::Null => '\0',
+        SymbolKind::Feed => '\u{c}',
+        SymbolKind::Backspace => '\u{8}',
+        SymbolKind::Whitespace
+        | SymbolKind::Alphabetic
+        | SymbolKind::Alphanumeric
+        | SymbolKind::Char
+        | SymbolKind::Digit
+        | SymbolKind::Word
+        | SymbolKind::Boundary
+        | SymbolKind::Grapheme
+        | SymbolKind::LineBreak => return Prefix::unknown(),
+    };
+
+    Prefix::complete(char.to_string())
+}
+
+#[derive(Clone, Copy)]
+enum Edge {
+    Start,
+    End,
+}
+
+/// Whether every match of the statements is anchored at an edge of the input
+fn anchored(ast: &ViableAst, edge: Edge) -> bool {
+    let ViableAst::Root(nodes) = ast else {
+        return false;
+    };
+
+    let mut nodes = nodes.iter().filter(|node| node.node != ViableAstNode::Skip);
+    let node = match edge {
+        Edge::Start => nodes.next(),
+        Edge::End => nodes.next_back(),
+    };
+
+    node.is_some_and(|node| node_anchored(&node.node, edge))
+}
+
+fn node_anchored(node: &ViableAstNode, edge: Edge) -> bool {
+    match (node, edge) {
+        (ViableAstNode::SpecialSymbol(SpecialSymbolKind::Start | SpecialSymbolKind::InputStart), Edge::Start)
+        | (ViableAstNode::SpecialSymbol(SpecialSymbolKind::End | SpecialSymbolKind::InputEnd), Edge::End) => true,
+        (ViableAstNode::Group(group), _) if group.kind == GroupKind::Either => match group.statements.as_ref() {
+            ViableAst::Root(branches) => {
+                !branches.is_empty() && branches.iter().all(|branch| node_anchored(&branch.node, edge))
+            }
+            ViableAst::Empty => false,
+        },
+        (ViableAstNode::Group(group), _) => anchored(&group.statements, edge),
+        (ViableAstNode::VariableInvocation(invocation), _) => anchored(&invocation.statements, edge),
+        _ => false,
+    }
+}
@@ -0,0 +1,600 @@
+//! Detection of patterns prone to catastrophic backtracking, see [`analyze_redos`]
+
+use super::optimization::{atom_char, atom_units};
+use super::types::ast::{
+    ClassItem, Expression, Group, GroupKind, Quantifier, QuantifierKind, Range, Recursion, Span, Spanned, Symbol,
+    SymbolKind, ViableAst, ViableAstNode,
+};
+use super::visitor::{walk, Visitor};
+use crate::prelude::*;
+use thiserror::Error;
+
+/// How the matching time of a pattern can grow with the length of the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// e.g. `\d+\d+`, slow on long inputs
+    Polynomial,
+    /// e.g. `(a+)+`, can hang on inputs of a few dozen characters
+    Exponential,
+}
+
+/// A construct that a backtracking engine may take exponential or polynomial time to match
+#[derive(Error, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RedosWarning {
+    /// emitted if an unbounded quantifier repeats another one (e.g. `some of match { some of "a"; }`), so that a
+    /// string can be split between their repetitions in exponentially many ways
+    #[error("unbounded quantifier repeats another unbounded quantifier")]
+    NestedQuantifiers { outer: Span, inner: Span },
+    /// emitted if an unbounded quantifier repeats an `either` group with branches that can start with the same
+    /// character (e.g. `any of either { "a"; "ab"; }`)
+    #[error("unbounded quantifier repeats branches that can match the same characters")]
+    OverlappingBranches {
+        quantifier: Span,
+        first_branch: Span,
+        second_branch: Span,
+    },
+    /// emitted if an unbounded quantifier is followed by another one repeating characters it also matches (e.g.
+    /// `some of <digit>; some of <digit>;`)
+    #[error("adjacent unbounded quantifiers can match the same characters")]
+    OverlappingAdjacentQuantifiers { quantifier: Span, following: Span },
+    /// emitted if an unbounded quantifier repeats a recursion (e.g. `some of match { "a"; recurse; }`), each
+    /// repetition being able to match what a deeper one does
+    #[error("unbounded quantifier repeats a recursion")]
+    QuantifiedRecursion { quantifier: Span, recursion: Span },
+}
+
+impl RedosWarning {
+    #[must_use]
+    pub const fn severity(&self) -> Severity {
+        match self {
+            Self::NestedQuantifiers { .. } | Self::OverlappingBranches { .. } | Self::QuantifiedRecursion { .. } => {
+                Severity::Exponential
+            }
+            Self::OverlappingAdjacentQuantifiers { .. } => Severity::Polynomial,
+        }
+    }
+
+    /// The spans of the interacting nodes, the outer or leading node first
+    #[must_use]
+    pub fn spans(&self) -> Vec<Span> {
+        match self {
+            Self::NestedQuantifiers { outer, inner } => vec![*outer, *inner],
+            Self::OverlappingBranches {
+                quantifier,
+                first_branch,
+                second_branch,
+            } => vec![*quantifier, *first_branch, *second_branch],
+            Self::OverlappingAdjacentQuantifiers { quantifier, following } => vec![*quantifier, *following],
+            Self::QuantifiedRecursion { quantifier, recursion } => vec![*quantifier, *recursion],
+        }
+    }
+}
+
+/**
+Finds the constructs of an AST that a backtracking engine (e.g. those of JavaScript, Python or PCRE) may take
+exponential or polynomial time to match, in source order.
+
+These are heuristics rather than proofs: unbounded quantifiers nesting another one or a recursion, repeating an
+`either` group with overlapping branches or followed by another one matching the same characters. Possessive quantifiers and
+atomic groups don't backtrack and are not reported. Variables are analyzed as expanded, so a warning may point
+into the declaration of an invoked variable. Characters that can't be analyzed (e.g. unicode categories or raw
+regexes) are assumed to overlap everything.
+
+# Example
+
+```rust
+use viable_compiler::ast::redos::{RedosWarning, Severity};
+use viable_compiler::ast::{analyze_redos, to_ast};
+
+let ast = to_ast(r#"some of match { some of "a"; }"#).unwrap();
+let warnings = analyze_redos(&ast);
+
+assert!(matches!(warnings[0], RedosWarning::NestedQuantifiers { .. }));
+assert_eq!(warnings[0].severity(), Severity::Exponential);
+```
+*/
+#[must_use]
+pub fn analyze_redos(ast: &ViableAst) -> Vec<RedosWarning> {
+    let mut warnings = Vec::new();
+    analyze(ast, &mut warnings);
+    warnings
+}
+
+fn analyze(ast: &ViableAst, warnings: &mut Vec<RedosWarning>) {
+    let ViableAst::Root(nodes) = ast else {
+        return;
+    };
+    let nodes: Vec<&Spanned<ViableAstNode>> = nodes.iter().filter(|node| node.node != ViableAstNode::Skip).collect();
+
+    for (index, node) in nodes.iter().enumerate() {
+        match &node.node {
+            ViableAstNode::Group(group) => analyze(&group.statements, warnings),
+            ViableAstNode::Assertion(assertion) => analyze(&assertion.statements, warnings),
+            ViableAstNode::Conditional(conditional) => {
+                analyze(&conditional.then, warnings);
+                if let Some(otherwise) = &conditional.otherwise {
+                    analyze(otherwise, warnings);
+                }
+            }
+            ViableAstNode::VariableInvocation(invocation) => analyze(&invocation.statements, warnings),
+            ViableAstNode::Quantifier(quantifier) => {
+                analyze_quantifier(quantifier, node.span, warnings);
+
+                let following = nodes.get(index + 1).and_then(|following| match &following.node {
+                    ViableAstNode::Quantifier(following_quantifier) if backtracks(following_quantifier) => {
+                        Some((following_quantifier, following.span))
+                    }
+                    _ => None,
+                });
+                if let Some((following, following_span)) = following {
+                    if backtracks(quantifier)
+                        && expression_chars(&quantifier.expression.node)
+                            .overlaps(&expression_chars(&following.expression.node))
+                    {
+                        push(
+                            warnings,
+                            RedosWarning::OverlappingAdjacentQuantifiers {
+                                quantifier: node.span,
+                                following: following_span,
+                            },
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn analyze_quantifier(quantifier: &Quantifier, span: Span, warnings: &mut Vec<RedosWarning>) {
+    let Expression::Group(group) = &quantifier.expression.node else {
+        return;
+    };
+
+    if backtracks(quantifier) {
+        if let Some(inner) = group_repeated_quantifier(group) {
+            push(warnings, RedosWarning::NestedQuantifiers { outer: span, inner });
+        }
+
+        if let Some((first_branch, second_branch)) = alternation(group).and_then(overlapping_branches) {
+            push(
+                warnings,
+                RedosWarning::OverlappingBranches {
+                    quantifier: span,
+                    first_branch,
+                    second_branch,
+                },
+            );
+        }
+
+        if let Some(recursion) = group_recursion(group) {
+            push(
+                warnings,
+                RedosWarning::QuantifiedRecursion {
+                    quantifier: span,
+                    recursion,
+                },
+            );
+        }
+    }
+
+    analyze(&group.statements, warnings);
+}
+
+/// The span of the first recursion within a group, however deeply nested
+fn group_recursion(group: &Group) -> Option<Span> {
+    let mut finder = RecursionFinder(None);
+    walk(&group.statements, &mut finder);
+    finder.0
+}
+
+struct RecursionFinder(Option<Span>);
+
+impl Visitor for RecursionFinder {
+    fn visit_recursion(&mut self, _recursion: &Recursion, span: Span) {
+        self.0 = self.0.or(Some(span));
+    }
+}
+
+/// Invocations of a variable share its statements, a warning within them is only reported once
+fn push(warnings: &mut Vec<RedosWarning>, warning: RedosWarning) {
+    if !warnings.contains(&warning) {
+        warnings.push(warning);
+    }
+}
+
+/// Whether a quantifier repeats without bound and may give repetitions back
+const fn backtracks(quantifier: &Quantifier) -> bool {
+    !quantifier.possessive
+        && matches!(
+            quantifier.kind,
+            QuantifierKind::Some | QuantifierKind::Any | QuantifierKind::Over(_) | QuantifierKind::AtLeast(_)
+        )
+}
+
+/// The span of an unbounded quantifier that can make up a whole match of the statements, as every other
+/// statement can match nothing
+fn repeated_quantifier(ast: &ViableAst) -> Option<Span> {
+    let ViableAst::Root(nodes) = ast else {
+        return None;
+    };
+
+    nodes.iter().enumerate().find_map(|(index, node)| {
+        let inner = node_repeated_quantifier(node)?;
+        let rest_nullable = nodes
+            .iter()
+            .enumerate()
+            .all(|(other, node)| other == index || node_nullable(&node.node));
+
+        rest_nullable.then_some(inner)
+    })
+}
+
+fn node_repeated_quantifier(node: &Spanned<ViableAstNode>) -> Option<Span> {
+    match &node.node {
+        ViableAstNode::Quantifier(quantifier) if backtracks(quantifier) => Some(node.span),
+        ViableAstNode::Group(group) => group_repeated_quantifier(group),
+        ViableAstNode::VariableInvocation(invocation) => repeated_quantifier(&invocation.statements),
+        _ => None,
+    }
+}
+
+fn group_repeated_quantifier(group: &Group) -> Option<Span> {
+    match group.kind {
+        GroupKind::Atomic => None,
+        GroupKind::Either => {
+            let ViableAst::Root(branches) = group.statements.as_ref() else {
+                return None;
+            };
+            branches.iter().find_map(node_repeated_quantifier)
+        }
+        GroupKind::Match | GroupKind::Capture | GroupKind::Flagged { .. } => repeated_quantifier(&group.statements),
+    }
+}
+
+/// The branches of the `either` group making up a quantified group, looking through groups holding only it
+fn alternation(group: &Group) -> Option<&[Spanned<ViableAstNode>]> {
+    let ViableAst::Root(nodes) = group.statements.as_ref() else {
+        return None;
+    };
+
+    match group.kind {
+        GroupKind::Either => Some(nodes),
+        GroupKind::Atomic => None,
+        GroupKind::Match | GroupKind::Capture | GroupKind::Flagged { .. } => {
+            let mut statements = nodes.iter().filter(|node| node.node != ViableAstNode::Skip);
+            match (statements.next().map(|node| &node.node), statements.next()) {
+                (Some(ViableAstNode::Group(group)), None) => alternation(group),
+                (Some(ViableAstNode::VariableInvocation(invocation)), None) => {
+                    let ViableAst::Root(nodes) = invocation.statements.as_ref() else {
+                        return None;
+                    };
+                    match nodes.as_slice() {
+                        [Spanned {
+                            node: ViableAstNode::Group(group),
+                            ..
+                        }] => alternation(group),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+    }
+}
+
+/// The spans of the first two branches that can start with the same character
+fn overlapping_branches(branches: &[Spanned<ViableAstNode>]) -> Option<(Span, Span)> {
+    let chars: Vec<(Span, Chars)> = branches
+        .iter()
+        .filter(|branch| branch.node != ViableAstNode::Skip)
+        .map(|branch| (branch.span, node_chars(&branch.node)))
+        .collect();
+
+    chars.iter().enumerate().find_map(|(index, (first_span, first))| {
+        chars[index + 1..]
+            .iter()
+            .find(|(_, second)| first.overlaps(second))
+            .map(|(second_span, _)| (*first_span, *second_span))
+    })
+}
+
+/// Whether a node can match an empty string
+fn node_nullable(node: &ViableAstNode) -> bool {
+    match node {
+        ViableAstNode::Group(group) => group_nullable(group),
+        ViableAstNode::Quantifier(quantifier) => {
+            matches!(
+                quantifier.kind,
+                QuantifierKind::Any
+                    | QuantifierKind::Option
+                    | QuantifierKind::AtMost(_)
+                    | QuantifierKind::Range { start: 0, .. }
+                    | QuantifierKind::AtLeast(0)
+                    | QuantifierKind::Amount(0)
+            ) || expression_nullable(&quantifier.expression.node)
+        }
+        ViableAstNode::Atom(atom) => atom.is_empty(),
+        ViableAstNode::Symbol(symbol) => symbol.kind == SymbolKind::Boundary,
+        ViableAstNode::VariableInvocation(invocation) => nullable(&invocation.statements),
+        ViableAstNode::Conditional(conditional) => {
+            nullable(&conditional.then) || conditional.otherwise.as_deref().map_or(true, nullable)
+        }
+        ViableAstNode::Assertion(_) | ViableAstNode::SpecialSymbol(_) | ViableAstNode::Empty | ViableAstNode::Skip => {
+            true
+        }
+        ViableAstNode::Range(_)
+        | ViableAstNode::UnicodeCategory(_)
+        | ViableAstNode::UnicodeProperty(_)
+        | ViableAstNode::NegativeCharClass(_)
+        | ViableAstNode::CharClass(_)
+        | ViableAstNode::Backreference(_)
+        | ViableAstNode::Recursion(_)
+        | ViableAstNode::Parameter(_)
+        | ViableAstNode::RawRegex(_)
+        | ViableAstNode::Error => false,
+    }
+}
+
+fn expression_nullable(expression: &Expression) -> bool {
+    match expression {
+        Expression::Group(group) => group_nullable(group),
+        Expression::Atom(atom) => atom.is_empty(),
+        Expression::Symbol(symbol) => symbol.kind == SymbolKind::Boundary,
+        Expression::UnicodeCategory(_)
+        | Expression::UnicodeProperty(_)
+        | Expression::Range(_)
+        | Expression::NegativeCharClass(_)
+        | Expression::CharClass(_)
+        | Expression::Backreference(_) => false,
+    }
+}
+
+fn nullable(ast: &ViableAst) -> bool {
+    match ast {
+        ViableAst::Root(nodes) => nodes.iter().all(|node| node_nullable(&node.node)),
+        ViableAst::Empty => true,
+    }
+}
+
+fn group_nullable(group: &Group) -> bool {
+    match (&group.kind, group.statements.as_ref()) {
+        (GroupKind::Either, ViableAst::Root(branches)) => branches.iter().any(|branch| node_nullable(&branch.node)),
+        (_, statements) => nullable(statements),
+    }
+}
+
+/// A set of characters as a union of parts
+struct Chars(Vec<CharsPart>);
+
+enum CharsPart {
+    /// the characters from the first to the second, inclusive
+    Range(char, char),
+    /// every character outside of the ranges
+    Outside(Vec<(char, char)>),
+    /// characters that can't be analyzed, assumed to overlap everything
+    Unknown,
+}
+
+impl CharsPart {
+    fn contains(&self, char: char) -> bool {
+        match self {
+            Self::Range(start, end) => (*start..=*end).contains(&char),
+            Self::Outside(ranges) => !ranges.iter().any(|(start, end)| (*start..=*end).contains(&char)),
+            Self::Unknown => true,
+        }
+    }
+
+    fn bounds(&self) -> Vec<char> {
+        match self {
+            Self::Range(start, end) => vec![*start, *end],
+            Self::Outside(ranges) => ranges.iter().flat_map(|(start, end)| [*start, *end]).collect(),
+            Self::Unknown => Vec::new(),
+        }
+    }
+}
+
+impl Chars {
+    const fn empty() -> Self {
+        Self(Vec::new())
+    }
+
+    fn union(mut self, other: Self) -> Self {
+        self.0.extend(other.0);
+        self
+    }
+
+    fn contains(&self, char: char) -> bool {
+        self.0.iter().any(|part| part.contains(char))
+    }
+
+    /// Whether a character is in both sets
+    ///
+    /// The sets are unions of ranges and their complements, so if they intersect one of the characters at or
+    /// next to the bounds of their ranges is in both
+    fn overlaps(&self, other: &Self) -> bool {
+        let unknown = |chars: &Self| chars.0.iter().any(|part| matches!(part, CharsPart::Unknown));
+        if (unknown(self) && !other.0.is_empty()) || (unknown(other) && !self.0.is_empty()) {
+            return true;
+        }
+
+        let bounds: Vec<char> = self.0.iter().chain(&other.0).flat_map(CharsPart::bounds).collect();
+        let neighbours = bounds.iter().flat_map(|char| {
+            let code = u32::from(*char);
+            [code.checked_sub(1), code.checked_add(1)]
+                .into_iter()
+                .flatten()
+                .filter_map(char::from_u32)
+        });
+
+        bounds
+            .iter()
+            .copied()
+            .chain(neighbours)
+            .chain(['\0', char::MAX])
+            .any(|char| self.contains(char) && other.contains(char))
+    }
+}
+
+/// The characters a node can start with
+fn node_chars(node: &ViableAstNode) -> Chars {
+    match node {
+        ViableAstNode::Group(group) => group_chars(group),
+        ViableAstNode::Quantifier(quantifier) => expression_chars(&quantifier.expression.node),
+        ViableAstNode::Atom(atom) => atom_chars(atom),
+        ViableAstNode::Range(range) => range_chars(range),
+        ViableAstNode::Symbol(symbol) => symbol_chars(symbol),
+        ViableAstNode::NegativeCharClass(items) => class_chars(true, items),
+        ViableAstNode::CharClass(class) => class_chars(class.negative, &class.items),
+        ViableAstNode::VariableInvocation(invocation) => chars(&invocation.statements),
+        ViableAstNode::Conditional(conditional) => match &conditional.otherwise {
+            Some(otherwise) => chars(&conditional.then).union(chars(otherwise)),
+            None => chars(&conditional.then),
+        },
+        ViableAstNode::UnicodeCategory(_)
+        | ViableAstNode::UnicodeProperty(_)
+        | ViableAstNode::Backreference(_)
+        | ViableAstNode::Recursion(_)
+        | ViableAstNode::Parameter(_)
+        | ViableAstNode::RawRegex(_) => Chars(vec![CharsPart::Unknown]),
+        ViableAstNode::Assertion(_)
+        | ViableAstNode::SpecialSymbol(_)
+        | ViableAstNode::Empty
+        | ViableAstNode::Skip
+        | ViableAstNode::Error => Chars::empty(),
+    }
+}
+
+fn expression_chars(expression: &Expression) -> Chars {
+    match expression {
+        Expression::Group(group) => group_chars(group),
+        Expression::Atom(atom) => atom_chars(atom),
+        Expression::Symbol(symbol) => symbol_chars(symbol),
+        Expression::Range(range) => range_chars(range),
+        Expression::NegativeCharClass(items) => class_chars(true, items),
+        Expression::CharClass(class) => class_chars(class.negative, &class.items),
+        Expression::UnicodeCategory(_) | Expression::UnicodeProperty(_) | Expression::Backreference(_) => {
+            Chars(vec![CharsPart::Unknown])
+        }
+    }
+}
+
+/// The characters statements can start with, those of each statement up to the first that can't match nothing
+fn chars(ast: &ViableAst) -> Chars {
+    let ViableAst::Root(nodes) = ast else {
+        return Chars::empty();
+    };
+
+    let mut chars = Chars::empty();
+    for node in nodes {
+        chars = chars.union(node_chars(&node.node));
+        if !node_nullable(&node.node) {
+            break;
+        }
+    }
+
+    chars
+}
+
+fn group_chars(group: &Group) -> Chars {
+    match (&group.kind, group.statements.as_ref()) {
+        (GroupKind::Either, ViableAst::Root(branches)) => branches
+            .iter()
+            .fold(Chars::empty(), |chars, branch| chars.union(node_chars(&branch.node))),
+        // the case of letters is not analyzed
+        (GroupKind::Flagged { .. }, _) => Chars(vec![CharsPart::Unknown]),
+        (_, statements) => chars(statements),
+    }
+}
+
+fn atom_chars(atom: &str) -> Chars {
+    let first = atom_units(atom).and_then(|units| units.first().copied());
+
+    match first.map(atom_char) {
+        Some(Some(char)) => Chars(vec![CharsPart::Range(char, char)]),
+        Some(None) | None if !atom.is_empty() => Chars(vec![CharsPart::Unknown]),
+        _ => Chars::empty(),
+    }
+}
+
+fn range_chars(range: &Range) -> Chars {
+    let (negative, start, end) = match range {
+        Range::CharRange(range) => (range.negative, range.start, range.end),
+        Range::NumericRange(range) => (range.negative, range.start, range.end),
+    };
+
+    if negative {
+        Chars(vec![CharsPart::Outside(vec![(start, end)])])
+    } else {
+        Chars(vec![CharsPart::Range(start, end)])
+    }
+}
+
+/// The ranges of characters a symbol matches, `None` for symbols matching any character
+fn symbol_ranges(kind: &SymbolKind) -> Option<Vec<(char, char)>> {
+    let ranges = match kind {
+        SymbolKind::Space => vec![(' ', ' ')],
+        SymbolKind::Newline => vec![('\n', '\n')],
+        SymbolKind::Vertical => vec![('\u{b}', '\u{b}')],
+        SymbolKind::Return => vec![('\r', '\r')],
+        SymbolKind::Tab => vec![('\t', '\t')],
+        SymbolKind::Null => vec![('\0', '\0')],
+        SymbolKind::Feed => vec![('\u{c}', '\u{c}')],
+        SymbolKind::Backspace => vec![('\u{8}', '\u{8}')],
+        SymbolKind::Whitespace => vec![(' ', ' '), ('\t', '\r')],
+        SymbolKind::Alphabetic => vec![('a', 'z'), ('A', 'Z')],
+        SymbolKind::Alphanumeric => vec![('a', 'z'), ('A', 'Z'), ('0', '9')],
+        SymbolKind::Digit => vec![('0', '9')],
+        SymbolKind::Word => vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+        SymbolKind::Boundary => Vec::new(),
+        SymbolKind::Char | SymbolKind::Grapheme | SymbolKind::LineBreak => return None,
+    };
+
+    Some(ranges)
+}
+
+fn symbol_chars(symbol: &Symbol) -> Chars {
+    match (symbol_ranges(&symbol.kind), symbol.negative) {
+        (_, true) if symbol.kind == SymbolKind::Boundary => Chars::empty(),
+        (Some(ranges), false) => Chars(
+            ranges
+                .into_iter()
+                .map(|(start, end)| CharsPart::Range(start, end))
+                .collect(),
+        ),
+        (Some(ranges), true) => Chars(vec![CharsPart::Outside(ranges)]),
+        (None, false) => Chars(vec![CharsPart::Outside(Vec::new())]),
+        (None, true) => Chars::empty(),
+    }
+}
+
+fn class_chars(negative: bool, items: &[ClassItem]) -> Chars {
+    let ranges: Option<Vec<(char, char)>> = items
+        .iter()
+        .map(class_item_ranges)
+        .collect::<Option<Vec<_>>>()
+        .map(|ranges| ranges.into_iter().flatten().collect());
+
+    match (ranges, negative) {
+        (Some(ranges), false) => Chars(
+            ranges
+                .into_iter()
+                .map(|(start, end)| CharsPart::Range(start, end))
+                .collect(),
+        ),
+        (Some(ranges), true) => Chars(vec![CharsPart::Outside(ranges)]),
+        (None, _) => Chars(vec![CharsPart::Unknown]),
+    }
+}
+
+fn class_item_ranges(item: &ClassItem) -> Option<Vec<(char, char)>> {
+    match item {
+        ClassItem::Range(Range::CharRange(range)) if !range.negative => Some(vec![(range.start, range.end)]),
+        ClassItem::Range(Range::NumericRange(range)) if !range.negative => Some(vec![(range.start, range.end)]),
+        ClassItem::Char(char) => Some(vec![(*char, *char)]),
+        ClassItem::Symbol(symbol) if !symbol.negative => symbol_ranges(&symbol.kind),
+        _ => None,
+    }
+}
@@ -0,0 +1,72 @@
+use super::source_to_ast::declarations_and_invocations;
+use super::types::ast::Span;
+use crate::prelude::*;
+use crate::types::Result;
+
+/// A variable declaration along with the invocations referring to it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VariableUsage {
+    /// the name of the variable, without the leading `.`
+    pub name: String,
+    /// the span of the whole declaration, e.g. `let .name = { ... }`
+    pub declaration: Span,
+    /// the spans of the invocations (e.g. `.name`) in source order
+    pub references: Vec<Span>,
+}
+
+/**
+Lists every variable declaration of a source with the invocations referring to it, e.g. for the go to
+definition and find references features of an editor.
+
+A declaration reusing the name of an earlier one gets an entry of its own, and every invocation refers to the
+latest declaration of its name before it, like when converting the source. Invocations within the statements of
+other variables are listed where they are written, not at each invocation of those variables. Statements that
+fail to convert are left out like in [`to_ast_lenient`](super::to_ast_lenient).
+
+# Errors
+
+Returns the [`CompilerError::ParseError`](crate::errors::CompilerError::ParseError) if the source doesn't parse
+at all
+
+# Example
+
+```rust
+use viable_compiler::ast::references::variable_references;
+
+let source = r#"let .digits = { some of <digit>; } .digits; "."; .digits;"#;
+let usages = variable_references(source).unwrap();
+
+assert_eq!(usages[0].name, "digits");
+assert_eq!(usages[0].references.len(), 2);
+assert_eq!(&source[usages[0].references[1].start..usages[0].references[1].end], ".digits");
+```
+*/
+pub fn variable_references(source: &str) -> Result<Vec<VariableUsage>> {
+    let (declarations, mut invocations) = declarations_and_invocations(source)?;
+
+    let mut usages: Vec<VariableUsage> = declarations
+        .into_iter()
+        .map(|declaration| VariableUsage {
+            name: declaration.name,
+            declaration: declaration.span,
+            references: Vec::new(),
+        })
+        .collect();
+    // nested declarations are converted before the declarations containing them
+    usages.sort_by_key(|usage| usage.declaration.start);
+
+    invocations.sort_by_key(|invocation| invocation.span.start);
+    for invocation in invocations {
+        let declaration = usages
+            .iter_mut()
+            .rev()
+            .find(|usage| usage.name == invocation.name && usage.declaration.end <= invocation.span.start);
+
+        if let Some(declaration) = declaration {
+            declaration.references.push(invocation.span);
+        }
+    }
+
+    Ok(usages)
+}
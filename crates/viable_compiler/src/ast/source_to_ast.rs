@@ -1,22 +1,39 @@
-use super::consts::{LAZY, NOT};
-use super::symbols::symbol;
+use super::cleanup::remove_empty_groups;
+use super::consts::{LAZY, NOT, POSSESSIVE};
+use super::substitution::substitute_parameters;
+use super::symbols::{is_builtin_symbol, symbol};
 use super::types::{
     ast::{
-        AsciiRange, Assertion, AssertionKind, Expression, Group, GroupKind, ViableAst, ViableAstNode, NumericRange,
-        Quantifier, QuantifierKind, Range, VariableInvocation,
+        Assertion, AssertionKind, Backreference, CharClass, CharRange, ClassItem, Conditional, Expression, Flag, Group,
+        GroupKind, NumericRange, ParameterizedDeclaration, PatternFlags, Quantifier, QuantifierKind, QuantifierUnit,
+        Range, Recursion, Span, Spanned, SpecialSymbolKind, VariableInvocation, ViableAst, ViableAstNode,
     },
     pest::{IdentParser, Rule},
 };
 use super::utils::{
-    alphabetic_first_char, first_inner, first_last_inner_str, last_inner, nth_inner, to_char, unquote_escape_literal,
-    unquote_escape_raw,
+    check_nesting, closest_match, decode_code_point, escape_char, first_inner, first_last_inner_str, inner_children,
+    last_inner, missing_node, nth_inner, parse_amount, parse_error, raw_regex_captures, resolve_import_path, span,
+    to_char, unescape_literal, unquote_escape_literal, unquote_escape_raw, unshare, Interner,
 };
+use super::validation::validate;
 use crate::errors::CompilerError;
-use crate::types::Result;
-use pest::iterators::Pairs;
+use crate::options::{CompilerOptions, Strictness};
+use crate::prelude::*;
+use crate::testing::{EmbeddedTest, Expectation};
+use crate::types::{HashMap, HashSet, RandomState, Result};
+use crate::warnings::Warning;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use core::hash::BuildHasher;
+use indexmap::IndexMap;
 use pest::{iterators::Pair, Parser};
-use std::collections::HashMap;
-use std::hash::BuildHasher;
+
+mod incremental;
+pub use self::incremental::IncrementalParser;
+
+/// How deep blocks and the expansions of variables may be nested by default, deeper nesting is rejected with
+/// [`CompilerError::NestingTooDeep`] rather than overflowing the stack
+pub const DEFAULT_NESTING_LIMIT: usize = 256;
 
 /// Converts a source string to a Viable AST
 ///
@@ -24,209 +41,1103 @@ use std::hash::BuildHasher;
 ///
 /// See [`CompilerError`]
 pub fn to_ast(source: &str) -> Result<ViableAst> {
+    crate::Compiler::new().parse(source)
+}
+
+/// Converts a source string to a Viable AST along with the flags declared by its `flags` block
+///
+/// # Errors
+///
+/// See [`CompilerError`]
+pub fn to_ast_with_flags(source: &str) -> Result<(ViableAst, PatternFlags)> {
+    parse(source, ParseContext::new())
+        .map(|parsed| (parsed.ast, parsed.flags))
+        .map_err(|mut errors| errors.swap_remove(0).node)
+}
+
+/// Converts a source string to a Viable AST along with any [`Warning`]s about likely mistakes
+///
+/// Warnings never change the resulting AST
+///
+/// # Errors
+///
+/// See [`CompilerError`]
+pub fn to_ast_with_diagnostics(source: &str) -> Result<(ViableAst, Vec<Warning>)> {
+    parse(source, ParseContext::new())
+        .map(|parsed| (parsed.ast, parsed.warnings))
+        .map_err(|mut errors| errors.swap_remove(0).node)
+}
+
+/// Converts a source string to a Viable AST along with the variables and custom symbols it declares
+///
+/// Each name maps to the AST of its last declaration, with any variables invoked by its body already expanded.
+/// Names are listed in the order they are first declared, so iterating the variables is deterministic. Custom
+/// symbols (`define symbol name = { ... }`) follow the variables, named with their angle brackets (`<name>`)
+///
+/// # Errors
+///
+/// See [`CompilerError`]
+pub fn to_ast_with_symbols(source: &str) -> Result<(ViableAst, IndexMap<String, ViableAst, RandomState>)> {
+    to_ast_with_custom_symbols(source, BTreeMap::new())
+}
+
+/// Converts a source string to a Viable AST with custom symbols supplied by the caller, along with the variables
+/// and custom symbols in scope by the end of the source like [`to_ast_with_symbols`]
+///
+/// The supplied symbols are listed first, in the order of their names, see
+/// [`CompilerOptions::custom_symbols`]
+///
+/// # Errors
+///
+/// See [`CompilerError`]
+pub fn to_ast_with_custom_symbols(
+    source: &str,
+    symbols: BTreeMap<String, ViableAst>,
+) -> Result<(ViableAst, IndexMap<String, ViableAst, RandomState>)> {
+    let mut context = ParseContext::new();
+    context.predefine_symbols(symbols)?;
+
+    parse(source, context)
+        .map(|parsed| {
+            let variables = parsed.variables.into_iter().map(|(name, ast)| (name, unshare(ast)));
+            let symbols = parsed
+                .symbols
+                .into_iter()
+                .map(|(name, symbol)| (format!("<{name}>"), unshare(symbol.statements)));
+            (parsed.ast, variables.chain(symbols).collect())
+        })
+        .map_err(|mut errors| errors.swap_remove(0).node)
+}
+
+/// Converts a source string to a Viable AST along with the test cases of its `tests` blocks, in source order
+///
+/// The test cases are not part of the AST, see [`crate::testing`]
+///
+/// # Errors
+///
+/// See [`CompilerError`]
+pub fn to_ast_with_tests(source: &str) -> Result<(ViableAst, Vec<EmbeddedTest>)> {
+    parse(source, ParseContext::new())
+        .map(|parsed| (parsed.ast, parsed.tests))
+        .map_err(|mut errors| errors.swap_remove(0).node)
+}
+
+/// Converts a source string to a Viable AST with variables supplied by the caller in scope from the start
+///
+/// Declarations in the source may shadow the predefined variables, see [`ViableAst::atom`] and
+/// [`ViableAst::from_nodes`] for building their values
+///
+/// # Errors
+///
+/// See [`CompilerError`]
+pub fn to_ast_with_variables(source: &str, predefined: BTreeMap<String, ViableAst>) -> Result<ViableAst> {
+    let mut context = ParseContext::new();
+    context.variables = predefined.into_iter().map(|(name, ast)| (name, Rc::new(ast))).collect();
+
+    parse(source, context)
+        .map(|parsed| parsed.ast)
+        .map_err(|mut errors| errors.swap_remove(0).node)
+}
+
+/// Converts a source string to a Viable AST, resolving `import "...";` statements with `resolver`
+///
+/// Import paths are resolved relative to the importing file and passed to `resolver`, which returns the
+/// source of the file or `None` if it does not exist. Only the variables and custom symbols declared by an
+/// imported file are brought into scope, its other statements are not part of the resulting AST.
+///
+/// # Errors
+///
+/// See [`CompilerError`]
+pub fn to_ast_with_imports(source: &str, resolver: impl Fn(&str) -> Option<String> + 'static) -> Result<ViableAst> {
+    to_ast_with_flags_and_imports(source, resolver).map(|(ast, _)| ast)
+}
+
+/// Converts a source string to a Viable AST, rejecting blocks and expansions of variables nested more than
+/// `limit` levels deep rather than [`DEFAULT_NESTING_LIMIT`]
+///
+/// # Errors
+///
+/// See [`CompilerError`]
+pub fn to_ast_with_nesting_limit(source: &str, limit: usize) -> Result<ViableAst> {
+    let options = CompilerOptions {
+        nesting_limit: limit,
+        ..CompilerOptions::default()
+    };
+
+    to_ast_with_flags_and_options(source, &options).map(|(ast, ..)| ast)
+}
+
+/// Converts a source string to a Viable AST with the parsing related options of `options` (the nesting limit,
+/// whether zero quantifiers are allowed, the custom symbols and the strictness), along with its warnings
+pub(crate) fn to_ast_with_flags_and_options(
+    source: &str,
+    options: &CompilerOptions,
+) -> Result<(ViableAst, PatternFlags, Vec<Warning>)> {
+    parse_with_options(source, options_context(options)?, options)
+}
+
+/// Converts a source string to a Viable AST like [`to_ast_with_flags_and_options`], resolving
+/// `import "...";` statements with `resolver` like [`to_ast_with_imports`]
+pub(crate) fn to_ast_with_flags_imports_and_options(
+    source: &str,
+    options: &CompilerOptions,
+    resolver: impl Fn(&str) -> Option<String> + 'static,
+) -> Result<(ViableAst, PatternFlags, Vec<Warning>)> {
+    let mut context = options_context(options)?;
+    context.resolver = Some(Rc::new(resolver));
+
+    parse_with_options(source, context, options)
+}
+
+/// A context set up with the parsing related options of `options`
+fn options_context(options: &CompilerOptions) -> Result<ParseContext> {
+    let mut context = ParseContext::new();
+    context.nesting_limit = options.nesting_limit;
+    context.allow_zero_quantifiers = options.allow_zero_quantifiers;
+    context.deny_negative_captures =
+        options.deny_captures_in_negative_assertions || options.strictness == Strictness::Strict;
+    context.variables = options
+        .variables
+        .iter()
+        .map(|(name, ast)| (name.clone(), Rc::new(ast.clone())))
+        .collect();
+    context.predefine_symbols(options.custom_symbols.clone())?;
+
+    Ok(context)
+}
+
+/// Parses a source with a context set up from `options`, denying its first warning at [`Strictness::Strict`]
+fn parse_with_options(
+    source: &str,
+    context: ParseContext,
+    options: &CompilerOptions,
+) -> Result<(ViableAst, PatternFlags, Vec<Warning>)> {
+    let parsed = parse(source, context).map_err(|mut errors| errors.swap_remove(0).node)?;
+
+    match parsed.warnings.first() {
+        Some(warning) if options.strictness == Strictness::Strict => Err(CompilerError::DeniedWarning(warning.clone())),
+        _ => Ok((parsed.ast, parsed.flags, parsed.warnings)),
+    }
+}
+
+pub(crate) fn to_ast_with_flags_and_imports(
+    source: &str,
+    resolver: impl Fn(&str) -> Option<String> + 'static,
+) -> Result<(ViableAst, PatternFlags)> {
+    let mut context = ParseContext::new();
+    context.resolver = Some(Rc::new(resolver));
+
+    parse(source, context)
+        .map(|parsed| (parsed.ast, parsed.flags))
+        .map_err(|mut errors| errors.swap_remove(0).node)
+}
+
+/// Converts a source string to a Viable AST, collecting every error rather than stopping at the first
+///
+/// A statement that fails is replaced by [`ViableAstNode::Error`] and its siblings (including those in nested
+/// blocks) continue to be converted. Errors are returned in source order along with the span of the
+/// innermost statement that produced them.
+///
+/// # Errors
+///
+/// Returns every [`CompilerError`] encountered
+pub fn to_ast_all_errors(source: &str) -> core::result::Result<ViableAst, Vec<Spanned<CompilerError>>> {
+    parse(source, ParseContext::new()).map(|parsed| parsed.ast)
+}
+
+/// Converts a source string to a possibly partial Viable AST along with every error encountered, for tools that
+/// need an AST of sources that are being edited
+///
+/// Like [`to_ast_all_errors`], a statement that fails is replaced by [`ViableAstNode::Error`] and the remaining
+/// statements are still converted, so an error within a block only replaces the failing statement of that block.
+/// The AST is [`ViableAst::Empty`] if the source doesn't parse at all.
+#[must_use]
+pub fn to_ast_lenient(source: &str) -> (ViableAst, Vec<Spanned<CompilerError>>) {
+    let (parsed, errors) = parse_lenient(source, ParseContext::new());
+    (parsed.ast, errors)
+}
+
+struct Parsed {
+    ast: ViableAst,
+    flags: PatternFlags,
+    warnings: Vec<Warning>,
+    variables: IndexMap<String, Rc<ViableAst>, RandomState>,
+    symbols: IndexMap<String, CustomSymbol, RandomState>,
+    tests: Vec<EmbeddedTest>,
+    declared: Vec<DeclaredVariable>,
+    invoked: Vec<InvokedVariable>,
+}
+
+/// A variable declaration of a source, with the statements of that declaration
+pub(crate) struct DeclaredVariable {
+    pub name: String,
+    pub span: Span,
+    pub statements: Rc<ViableAst>,
+}
+
+/// A variable invocation written in a source
+pub(crate) struct InvokedVariable {
+    pub name: String,
+    pub span: Span,
+}
+
+fn parse(source: &str, context: ParseContext) -> core::result::Result<Parsed, Vec<Spanned<CompilerError>>> {
+    match parse_lenient(source, context) {
+        (parsed, errors) if errors.is_empty() => Ok(parsed),
+        (_, errors) => Err(errors),
+    }
+}
+
+/// Converts a source string to a possibly partial AST along with the errors encountered
+fn parse_lenient(source: &str, mut context: ParseContext) -> (Parsed, Vec<Spanned<CompilerError>>) {
+    let empty = |context: ParseContext| Parsed {
+        ast: ViableAst::Empty,
+        flags: PatternFlags::default(),
+        warnings: Vec::new(),
+        variables: context.variables,
+        symbols: context.symbols,
+        tests: Vec::new(),
+        declared: Vec::new(),
+        invoked: Vec::new(),
+    };
+
     if source.is_empty() {
-        return Ok(ViableAst::Empty);
+        return (empty(context), Vec::new());
     }
 
-    let mut pairs =
-        IdentParser::parse(Rule::root, source).map_err(|error| CompilerError::ParseError(error.to_string()))?;
+    let root_statements = match parse_root(source, context.nesting_limit) {
+        Ok(root_statements) => root_statements,
+        Err(error) => return (empty(context), vec![error]),
+    };
 
-    let root_statements = pairs.next().ok_or(CompilerError::MissingRootNode)?;
+    context.dependencies = variable_dependencies(&root_statements);
 
-    let mut variables: HashMap<String, ViableAst> = HashMap::new();
+    let mut statements = root_statements.into_inner().peekable();
 
-    pairs_to_ast(root_statements.into_inner(), &mut variables)
+    let flags = match statements.next_if(|pair| pair.as_rule() == Rule::flags_block) {
+        Some(pair) => flags_block(pair, &mut context),
+        None => PatternFlags::default(),
+    };
+
+    let ast = pairs_to_ast(statements, &mut context);
+
+    finish(ast, flags, context)
+}
+
+/// Parses a source with the grammar, once it is known not to be nested too deep for it
+fn parse_root(source: &str, nesting_limit: usize) -> core::result::Result<Pair<'_, Rule>, Spanned<CompilerError>> {
+    check_nesting(source, nesting_limit)?;
+
+    let mut pairs = IdentParser::parse(Rule::root, source).map_err(|error| parse_error(&error, source))?;
+
+    pairs.next().ok_or(Spanned {
+        node: CompilerError::MissingRootNode,
+        span: Span::default(),
+    })
 }
 
-fn pairs_to_ast<T: BuildHasher>(
-    pairs: Pairs<'_, Rule>,
-    variables: &mut HashMap<String, ViableAst, T>,
+/// Runs the checks that need the whole AST and collects the results of a parse
+fn finish(ast: ViableAst, flags: PatternFlags, mut context: ParseContext) -> (Parsed, Vec<Spanned<CompilerError>>) {
+    let ast = remove_empty_groups(ast, &mut context.errors, &mut context.warnings);
+
+    validate(
+        &ast,
+        &mut context.errors,
+        &mut context.warnings,
+        context.deny_negative_captures,
+    );
+
+    let unused = context
+        .declarations
+        .iter()
+        .filter(|declaration| !declaration.used)
+        .map(|declaration| Warning::UnusedVariable {
+            name: declaration.name.clone(),
+            span: declaration.span,
+        });
+    context.warnings.extend(unused);
+    context.warnings.sort_by_key(|warning| warning.span().start);
+
+    let parsed = Parsed {
+        ast,
+        flags,
+        warnings: context.warnings,
+        variables: context.variables,
+        symbols: context.symbols,
+        tests: context.tests,
+        declared: context.declared,
+        invoked: context.invoked,
+    };
+
+    (parsed, context.errors)
+}
+
+/// Returns the source of an imported file given its resolved path
+type ImportResolver = dyn Fn(&str) -> Option<String>;
+
+/// Converts a source to a possibly partial AST along with its variable declarations, failing only if the source
+/// doesn't parse at all
+pub(crate) fn to_ast_with_declarations(source: &str) -> Result<(ViableAst, Vec<DeclaredVariable>)> {
+    parse_navigable(source).map(|parsed| (parsed.ast, parsed.declared))
+}
+
+/// Lists the variable declarations of a source along with the name and span of every variable invocation written
+/// in it, failing only if the source doesn't parse at all
+pub(crate) fn declarations_and_invocations(source: &str) -> Result<(Vec<DeclaredVariable>, Vec<InvokedVariable>)> {
+    parse_navigable(source).map(|parsed| (parsed.declared, parsed.invoked))
+}
+
+/// Parses a source for editor navigation, where a partial AST is still useful
+fn parse_navigable(source: &str) -> Result<Parsed> {
+    match parse_lenient(source, ParseContext::new()) {
+        (
+            Parsed {
+                ast: ViableAst::Empty, ..
+            },
+            mut errors,
+        ) if !errors.is_empty() => Err(errors.swap_remove(0).node),
+        (parsed, _) => Ok(parsed),
+    }
+}
+
+struct ParseContext<T: BuildHasher = RandomState> {
+    /// the variables in scope in the order they were first declared, so that passes iterating them are
+    /// deterministic
+    variables: IndexMap<String, Rc<ViableAst>, T>,
+    /// the custom symbols in scope, those supplied by the caller first
+    symbols: IndexMap<String, CustomSymbol, T>,
+    errors: Vec<Spanned<CompilerError>>,
+    warnings: Vec<Warning>,
+    /// variables whose declarations are currently being converted, outermost first
+    defining: Vec<String>,
+    /// the variables invoked by each declaration in the source with the spans of the invocations, used to
+    /// explain recursive definitions
+    dependencies: HashMap<String, Vec<(String, Span)>>,
+    /// every variable declaration converted so far, in source order
+    declarations: Vec<Declaration>,
+    /// the parameters of each parameterized variable
+    parameters: HashMap<String, Vec<String>>,
+    /// the parameters of the parameterized variables currently being declared
+    parameters_in_scope: Vec<String>,
+    /// provides the source of imported files, imports are rejected without one
+    resolver: Option<Rc<ImportResolver>>,
+    /// the resolved paths of the files currently being imported, outermost first
+    imports: Vec<String>,
+    /// how deep blocks and the expansions of variables may be nested
+    nesting_limit: usize,
+    /// the nesting level of the block currently being converted, 0 for top level statements
+    depth: usize,
+    /// the deepest level reached by the statements of the variable currently being declared, including the
+    /// expansions of the variables they invoke
+    deepest: usize,
+    /// how many levels the expansion of each variable adds to the statement invoking it
+    variable_depths: HashMap<String, usize>,
+    /// whether quantifiers repeating their expression zero times are accepted
+    allow_zero_quantifiers: bool,
+    /// whether capture groups within negative assertions are errors rather than warnings
+    deny_negative_captures: bool,
+    /// shares the strings of the AST between the nodes holding them
+    interner: Interner,
+    /// the test cases of the `tests` blocks converted so far
+    tests: Vec<EmbeddedTest>,
+    /// every variable declaration of the source converted so far with its statements, in the order they are
+    /// converted
+    declared: Vec<DeclaredVariable>,
+    /// every variable invocation converted so far, including those later nested in a
+    /// quantifier or group
+    invoked: Vec<InvokedVariable>,
+}
+
+struct Declaration {
+    name: String,
+    span: Span,
+    used: bool,
+}
+
+/// A symbol like `<uuid_char>` defined by the caller or by a `define symbol` statement
+struct CustomSymbol {
+    statements: Rc<ViableAst>,
+    /// the span of the definition, empty for symbols supplied by the caller
+    span: Span,
+    /// see `ParseContext::variable_depths`
+    depth: usize,
+}
+
+impl ParseContext {
+    fn new() -> Self {
+        Self {
+            variables: IndexMap::default(),
+            symbols: IndexMap::default(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            defining: Vec::new(),
+            dependencies: HashMap::new(),
+            declarations: Vec::new(),
+            parameters: HashMap::new(),
+            parameters_in_scope: Vec::new(),
+            resolver: None,
+            imports: Vec::new(),
+            nesting_limit: DEFAULT_NESTING_LIMIT,
+            depth: 0,
+            deepest: 0,
+            variable_depths: HashMap::new(),
+            allow_zero_quantifiers: false,
+            deny_negative_captures: false,
+            interner: Interner::default(),
+            tests: Vec::new(),
+            declared: Vec::new(),
+            invoked: Vec::new(),
+        }
+    }
+}
+
+impl ParseContext {
+    /// Brings symbols supplied by the caller into scope in the order of their names, failing if one is named
+    /// like a built in symbol
+    fn predefine_symbols(&mut self, symbols: BTreeMap<String, ViableAst>) -> Result<()> {
+        for (name, statements) in symbols {
+            if is_builtin_symbol(&name) {
+                return Err(CompilerError::DuplicateSymbol {
+                    name,
+                    first_span: Span::default(),
+                    second_span: Span::default(),
+                });
+            }
+
+            let symbol = CustomSymbol {
+                statements: Rc::new(statements),
+                span: Span::default(),
+                depth: 0,
+            };
+            self.symbols.insert(name, symbol);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: BuildHasher> ParseContext<T> {
+    fn intern(&mut self, string: &str) -> Rc<str> {
+        self.interner.intern(string)
+    }
+
+    /// Marks the declaration an invocation of `name` refers to as used
+    fn mark_used(&mut self, name: &str) {
+        // the invocation uses the latest declaration of the name, earlier ones are shadowed
+        if let Some(declaration) = self
+            .declarations
+            .iter_mut()
+            .rev()
+            .find(|declaration| declaration.name == name)
+        {
+            declaration.used = true;
+        }
+    }
+
+    /// Records that statements are converted `levels` deeper than the current block, failing if that goes past
+    /// the nesting limit
+    fn reach(&mut self, levels: usize) -> Result<()> {
+        let depth = self.depth + levels;
+
+        if depth > self.nesting_limit {
+            return Err(CompilerError::NestingTooDeep {
+                limit: self.nesting_limit,
+            });
+        }
+
+        self.deepest = self.deepest.max(depth);
+        Ok(())
+    }
+}
+
+fn variable_dependencies(root: &Pair<'_, Rule>) -> HashMap<String, Vec<(String, Span)>> {
+    let mut dependencies: HashMap<String, Vec<(String, Span)>> = HashMap::new();
+
+    for (name, invoked) in root.clone().into_inner().flat_map(|pair| statement_dependencies(&pair)) {
+        dependencies.entry(name).or_default().extend(invoked);
+    }
+
+    dependencies
+}
+
+/// The variables invoked by each declaration within a statement (including the statement itself), see
+/// [`variable_dependencies`]
+fn statement_dependencies(statement: &Pair<'_, Rule>) -> Vec<(String, Vec<(String, Span)>)> {
+    let mut dependencies = Vec::new();
+
+    for declaration in core::iter::once(statement.clone())
+        .chain(statement.clone().into_inner().flatten())
+        .filter(|pair| pair.as_rule() == Rule::variable_declaration)
+    {
+        let mut inner = declaration.into_inner();
+        let Some(identifier) = inner.next() else {
+            continue;
+        };
+
+        let parameters: Vec<&str> = inner
+            .clone()
+            .filter(|pair| pair.as_rule() == Rule::parameters)
+            .flat_map(Pair::into_inner)
+            .map(|parameter| parameter.as_str().trim())
+            .collect();
+
+        // parameters aren't variables so invoking them can't form a cycle
+        let invoked = inner
+            .flatten()
+            .filter(|pair| pair.as_rule() == Rule::variable_invocation)
+            .filter_map(|invocation| {
+                let identifier = invocation.clone().into_inner().next()?;
+                Some((identifier.as_str().trim().to_owned(), span(&invocation)))
+            })
+            .filter(|(name, _)| !parameters.contains(&name.as_str()))
+            .collect::<Vec<_>>();
+
+        dependencies.push((identifier.as_str().trim().to_owned(), invoked));
+    }
+
+    dependencies
+}
+
+/// Converts the statements of a block nested within the current one
+fn block_to_ast<'source, T: BuildHasher>(
+    pairs: impl Iterator<Item = Pair<'source, Rule>>,
+    context: &mut ParseContext<T>,
 ) -> Result<ViableAst> {
+    context.reach(1)?;
+
+    context.depth += 1;
+    let ast = pairs_to_ast(pairs, context);
+    context.depth -= 1;
+
+    Ok(ast)
+}
+
+fn pairs_to_ast<'source, T: BuildHasher>(
+    pairs: impl Iterator<Item = Pair<'source, Rule>>,
+    context: &mut ParseContext<T>,
+) -> ViableAst {
     let mut nodes = Vec::new();
 
     for pair in pairs {
-        let node = create_ast_node(pair, variables)?;
-        nodes.push(node);
+        let node = statement_to_ast(pair, context);
+
+        // declarations, imports, tests and the end of input only affect the context
+        if !matches!(node.node, ViableAstNode::Skip) {
+            nodes.push(node);
+        }
     }
 
-    Ok(ViableAst::Root(nodes))
+    ViableAst::Root(nodes)
+}
+
+/// Converts a statement, replacing it by [`ViableAstNode::Error`] if it fails
+fn statement_to_ast<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Spanned<ViableAstNode> {
+    let span = span(&pair);
+
+    create_ast_node(pair, context).unwrap_or_else(|error| {
+        context.errors.push(Spanned { node: error, span });
+        Spanned {
+            node: ViableAstNode::Error,
+            span,
+        }
+    })
 }
 
 fn create_ast_node<T: BuildHasher>(
     pair: Pair<'_, Rule>,
-    variables: &mut HashMap<String, ViableAst, T>,
-) -> Result<ViableAstNode> {
+    context: &mut ParseContext<T>,
+) -> Result<Spanned<ViableAstNode>> {
+    let span = span(&pair);
+
     let node = match pair.as_rule() {
-        Rule::raw => ViableAstNode::Atom(unquote_escape_raw(&pair)),
-        Rule::literal => ViableAstNode::Atom(unquote_escape_literal(&pair)),
-        Rule::symbol => symbol(pair)?,
-        Rule::range => range(pair)?,
-        Rule::quantifier => quantifier(pair, variables)?,
-        Rule::group => group(pair, variables)?,
-        Rule::assertion => assertion(pair, variables)?,
-        Rule::negative_char_class => negative_char_class(&pair)?,
-        Rule::variable_invocation => variable_invocation(&pair, variables)?,
-        Rule::variable_declaration => variable_declaration(pair, variables)?,
+        Rule::raw => ViableAstNode::Atom(context.intern(&unquote_escape_raw(&pair))),
+        Rule::literal => ViableAstNode::Atom(context.intern(&unquote_escape_literal(&pair)?)),
+        Rule::code_point => ViableAstNode::Atom(context.intern(&escape_char(code_point(pair)?))),
+        Rule::symbol => symbol_or_custom(pair, context)?,
+        Rule::range => range(pair, context)?,
+        Rule::quantifier => quantifier(pair, context)?,
+        Rule::group => group(pair, context)?,
+        Rule::quantified_block => quantified_block(pair, context)?,
+        Rule::assertion => assertion(pair, context)?,
+        Rule::conditional => conditional(pair, context)?,
+        Rule::negative_char_class => negative_char_class(pair)?,
+        Rule::class_shorthand => ViableAstNode::CharClass(CharClass {
+            negative: false,
+            items: class_content(pair)?,
+        }),
+        Rule::char_class => char_class(pair, context)?,
+        Rule::backreference => backreference(pair, context)?,
+        Rule::variable_invocation => variable_invocation(pair, context)?,
+        Rule::import_statement => import_statement(pair, context)?,
+        Rule::variable_declaration => variable_declaration(pair, context)?,
+        Rule::symbol_declaration => symbol_declaration(pair, context)?,
+        Rule::flags_block => return Err(CompilerError::MisplacedFlagsBlock),
+        Rule::tests_block => tests_block(pair, context),
+        Rule::nothing => ViableAstNode::Empty,
+        Rule::raw_regex => raw_regex(pair, context)?,
+        Rule::recursion => recursion(pair, context),
         Rule::EOI => ViableAstNode::Skip,
+        Rule::unterminated_atom => return Err(missing_terminator(&pair)),
         _ => return Err(CompilerError::UnrecognizedSyntax),
     };
 
-    Ok(node)
+    Ok(Spanned { node, span })
 }
 
-fn range(pair: Pair<'_, Rule>) -> Result<ViableAstNode> {
-    let (first, end) = first_last_inner_str(pair.clone())?;
-    let negative = first == NOT;
-    let start = if negative {
-        nth_inner(pair, 1).ok_or(CompilerError::MissingNode)?.as_str()
-    } else {
-        first
-    };
-    let range_node = if alphabetic_first_char(start)? {
-        ViableAstNode::Range(Range::AsciiRange(AsciiRange {
-            negative,
-            start: to_char(start)?,
-            end: to_char(end)?,
-        }))
+/// The error for a statement missing its `;`, pointing just past the statement rather than at whatever follows it
+fn missing_terminator(pair: &Pair<'_, Rule>) -> CompilerError {
+    let statement = pair
+        .clone()
+        .into_inner()
+        .last()
+        .map_or_else(|| pair.as_span(), |inner| inner.as_span());
+    let end = statement.end_pos();
+    let (line, column) = end.line_col();
+
+    CompilerError::MissingStatementTerminator {
+        span: Span {
+            start: end.pos(),
+            end: end.pos(),
+            line: u32::try_from(line).unwrap_or(u32::MAX),
+            column: u32::try_from(column).unwrap_or(u32::MAX),
+        },
+    }
+}
+
+fn flags_block<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> PatternFlags {
+    let mut flags = PatternFlags {
+        flags: Vec::new(),
+        span: span(&pair),
+    };
+
+    for flag_pair in pair.into_inner() {
+        let name = flag_pair.as_str().trim();
+
+        let Some(flag) = flag_from_name(name) else {
+            context.errors.push(Spanned {
+                node: CompilerError::UnknownFlag(name.to_owned()),
+                span: span(&flag_pair),
+            });
+            continue;
+        };
+
+        if flags.contains(flag) {
+            context.errors.push(Spanned {
+                node: CompilerError::DuplicateFlag(name.to_owned()),
+                span: span(&flag_pair),
+            });
+        } else {
+            flags.flags.push(flag);
+        }
+    }
+
+    flags
+}
+
+/// Collects the test cases of a `tests` block, reporting the errors of each case separately
+fn tests_block<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> ViableAstNode {
+    for case in pair.into_inner() {
+        let span = span(&case);
+
+        match test_case(case, span) {
+            Ok(test) => context.tests.push(test),
+            Err(error) => context.errors.push(Spanned { node: error, span }),
+        }
+    }
+
+    ViableAstNode::Skip
+}
+
+fn test_case(pair: Pair<'_, Rule>, span: Span) -> Result<EmbeddedTest> {
+    let mut children = pair.into_inner();
+
+    let expectation = match children.next().map(|child| child.as_rule()) {
+        Some(Rule::no_match_expectation) => Expectation::NoMatch,
+        Some(Rule::match_expectation) => Expectation::Match,
+        _ => return Err(missing_node("test_case", span)),
+    };
+    let input = unescape_literal(&children.next().ok_or(missing_node("test_case", span))?)?;
+
+    let mut captures = Vec::new();
+    if let Some(test_captures) = children.next() {
+        if expectation == Expectation::NoMatch {
+            return Err(CompilerError::TestCapturesWithoutMatch);
+        }
+
+        for capture in test_captures.into_inner() {
+            let name = first_inner(capture.clone(), "test_capture")?.as_str().to_owned();
+            let text = unescape_literal(&nth_inner(capture, 1, "test_capture")?)?;
+            captures.push((name, text));
+        }
+    }
+
+    Ok(EmbeddedTest {
+        expectation,
+        input,
+        captures,
+        span,
+    })
+}
+
+fn flag_from_name(name: &str) -> Option<Flag> {
+    match name {
+        "insensitive" => Some(Flag::Insensitive),
+        "multiline" => Some(Flag::Multiline),
+        "dot_all" => Some(Flag::DotAll),
+        "unicode" => Some(Flag::Unicode),
+        _ => None,
+    }
+}
+
+fn range<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let span = span(&pair);
+    let negative = first_inner(pair.clone(), "range")?.as_str() == NOT;
+    let start_pair = nth_inner(pair.clone(), usize::from(negative), "range")?;
+
+    let start = to_char(&start_pair, "range")?;
+    let end = to_char(&last_inner(pair, "range")?, "range")?;
+
+    if start > end {
+        return Err(CompilerError::EmptyRange { start, end, span });
+    }
+
+    if start == end {
+        context
+            .warnings
+            .push(Warning::SingleCharacterRange { char: start, span });
+    }
+
+    let range_node = if start_pair.as_rule() == Rule::numeric_range_start {
+        ViableAstNode::Range(Range::NumericRange(NumericRange { negative, start, end }))
     } else {
-        ViableAstNode::Range(Range::NumericRange(NumericRange {
-            negative,
-            start: to_char(start)?,
-            end: to_char(end)?,
-        }))
+        ViableAstNode::Range(Range::CharRange(CharRange { negative, start, end }))
     };
 
     Ok(range_node)
 }
 
-fn quantifier<T: BuildHasher>(
-    pair: Pair<'_, Rule>,
-    variables: &mut HashMap<String, ViableAst, T>,
-) -> Result<ViableAstNode> {
-    let quantity = first_inner(pair.clone())?;
-    let kind = first_inner(quantity.clone())?;
-    let expression = create_ast_node(last_inner(pair)?, variables)?;
-
-    let expression = match expression {
+fn quantified_expression(node: ViableAstNode, span: Span) -> Result<Expression> {
+    let expression = match node {
         ViableAstNode::Group(group) => Expression::Group(group),
         ViableAstNode::Atom(atom) => Expression::Atom(atom),
         ViableAstNode::Range(range) => Expression::Range(range),
         ViableAstNode::Symbol(symbol) => Expression::Symbol(symbol),
         ViableAstNode::NegativeCharClass(class) => Expression::NegativeCharClass(class),
+        ViableAstNode::CharClass(class) => Expression::CharClass(class),
         ViableAstNode::UnicodeCategory(category) => Expression::UnicodeCategory(category),
+        ViableAstNode::UnicodeProperty(property) => Expression::UnicodeProperty(property),
+        ViableAstNode::Backreference(backreference) => Expression::Backreference(backreference),
+        // the invocation is grouped to be quantified as a whole, its anchors rejected like anchors written in place
+        ViableAstNode::VariableInvocation(invocation) => {
+            check_quantified_anchors(&invocation.statements)?;
+
+            Expression::Group(Group {
+                ident: None,
+                kind: GroupKind::Match,
+                statements: Box::new(ViableAst::Root(vec![Spanned {
+                    node: ViableAstNode::VariableInvocation(invocation),
+                    span,
+                }])),
+            })
+        }
+        // grouped so that the quantifier reads as applying to the whole conditional, as for other targets
+        ViableAstNode::Conditional(conditional) => Expression::Group(Group {
+            ident: None,
+            kind: GroupKind::Match,
+            statements: Box::new(ViableAst::Root(vec![Spanned {
+                node: ViableAstNode::Conditional(conditional),
+                span,
+            }])),
+        }),
+        // grouped as there is no quantifier expression for it
+        ViableAstNode::Recursion(recursion) => Expression::Group(Group {
+            ident: None,
+            kind: GroupKind::Match,
+            statements: Box::new(ViableAst::Root(vec![Spanned {
+                node: ViableAstNode::Recursion(recursion),
+                span,
+            }])),
+        }),
+        // grouped so that the regex is quantified as a whole, whatever it holds
+        ViableAstNode::RawRegex(regex) => Expression::Group(Group {
+            ident: None,
+            kind: GroupKind::Match,
+            statements: Box::new(ViableAst::Root(vec![Spanned {
+                node: ViableAstNode::RawRegex(regex),
+                span,
+            }])),
+        }),
+        // grouped so that the argument is quantified as a whole once substituted
+        ViableAstNode::Parameter(name) => Expression::Group(Group {
+            ident: None,
+            kind: GroupKind::Match,
+            statements: Box::new(ViableAst::Root(vec![Spanned {
+                node: ViableAstNode::Parameter(name),
+                span,
+            }])),
+        }),
 
         // unexpected nodes
+        ViableAstNode::SpecialSymbol(SpecialSymbolKind::WordBoundary | SpecialSymbolKind::NotWordBoundary) => {
+            return Err(CompilerError::UnexpectedBoundaryInQuantifier)
+        }
         ViableAstNode::SpecialSymbol(_) => return Err(CompilerError::UnexpectedSpecialSymbolInQuantifier),
         ViableAstNode::Quantifier(_) => return Err(CompilerError::UnexpectedQuantifierInQuantifier),
         ViableAstNode::Assertion(_) => return Err(CompilerError::UnexpectedAssertionInQuantifier),
-        ViableAstNode::VariableInvocation(_) => return Err(CompilerError::UnexpectedVariableInvocationInQuantifier),
-        ViableAstNode::Skip => return Err(CompilerError::UnexpectedSkippedNodeInQuantifier),
+        ViableAstNode::Empty => return Err(CompilerError::NothingOutsideEither),
+        ViableAstNode::Skip | ViableAstNode::Error => return Err(CompilerError::UnexpectedSkippedNodeInQuantifier),
     };
 
-    let lazy = quantity.as_str().starts_with(LAZY);
+    Ok(expression)
+}
 
-    let quantifier_node = match kind.as_rule() {
-        Rule::amount => ViableAstNode::Quantifier(Quantifier {
-            kind: QuantifierKind::Amount(kind.as_str().to_owned()),
-            lazy,
-            expression: Box::new(expression),
-        }),
-        Rule::over => {
-            let raw_amount = last_inner(kind)?.as_str().to_owned();
-            let amount = raw_amount
-                .parse::<usize>()
-                .map_err(|_| CompilerError::CouldNotParseAnAmount)?
-                .checked_add(1)
-                .ok_or(CompilerError::CouldNotParseAnAmount)?;
-            ViableAstNode::Quantifier(Quantifier {
-                kind: QuantifierKind::Over(amount),
-                lazy,
-                expression: Box::new(expression),
-            })
+/// Rejects quantifying the statements of a variable if they are only anchors or boundaries, like anchors written
+/// in place, as they match nothing to repeat
+fn check_quantified_anchors(statements: &ViableAst) -> Result<()> {
+    match anchors_only(statements) {
+        Some(true) => Err(CompilerError::UnexpectedBoundaryInQuantifier),
+        Some(false) => Err(CompilerError::UnexpectedSpecialSymbolInQuantifier),
+        None => Ok(()),
+    }
+}
+
+/// Whether any of the statements is a boundary if they are all anchors or boundaries, `None` otherwise
+fn anchors_only(statements: &ViableAst) -> Option<bool> {
+    let ViableAst::Root(nodes) = statements else {
+        return None;
+    };
+    if nodes.is_empty() {
+        return None;
+    }
+
+    nodes.iter().try_fold(false, |boundary, node| match &node.node {
+        ViableAstNode::SpecialSymbol(SpecialSymbolKind::WordBoundary | SpecialSymbolKind::NotWordBoundary) => {
+            Some(true)
         }
-        Rule::option => ViableAstNode::Quantifier(Quantifier {
-            kind: QuantifierKind::Option,
-            lazy,
-            expression: Box::new(expression),
-        }),
-        Rule::any => ViableAstNode::Quantifier(Quantifier {
-            kind: QuantifierKind::Any,
-            lazy,
-            expression: Box::new(expression),
-        }),
-        Rule::some => ViableAstNode::Quantifier(Quantifier {
-            kind: QuantifierKind::Some,
-            lazy,
-            expression: Box::new(expression),
-        }),
+        ViableAstNode::SpecialSymbol(_) => Some(boundary),
+        ViableAstNode::VariableInvocation(invocation) => Some(anchors_only(&invocation.statements)? || boundary),
+        _ => None,
+    })
+}
 
-        Rule::quantifier_range => {
-            let (start, end) = first_last_inner_str(kind)?;
+fn quantifier<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let quantifier_span = span(&pair);
+    let mut children = pair.into_inner();
+    let quantity = children.next().ok_or(missing_node("quantifier", quantifier_span))?;
+    let quantity_span = span(&quantity);
+    let quantified = children
+        .next_back()
+        .ok_or(missing_node("quantifier", quantifier_span))?;
+    let Spanned { node, span } = create_ast_node(quantified, context)?;
+
+    let expression = Spanned {
+        node: quantified_expression(node, span)?,
+        span,
+    };
+
+    let mut quantity = inner_children(quantity);
+    let unit = match quantity.last().map(|last| (last.as_rule(), last.as_str())) {
+        Some((Rule::quantifier_unit, "chars")) => Some(QuantifierUnit::Chars),
+        Some((Rule::quantifier_unit, _)) => Some(QuantifierUnit::Bytes),
+        _ => None,
+    };
+    if unit.is_some() {
+        quantity.pop();
+    }
+    let kind = quantity
+        .pop()
+        .ok_or(missing_node("quantifier_quantity", quantity_span))?;
+
+    let modifiers: Vec<&str> = quantity
+        .iter()
+        .filter(|inner| inner.as_rule() == Rule::quantifier_modifier)
+        .map(Pair::as_str)
+        .collect();
+
+    let lazy = modifiers.contains(&LAZY);
+    let possessive = modifiers.contains(&POSSESSIVE);
+
+    if lazy && possessive {
+        return Err(CompilerError::LazyPossessiveQuantifier);
+    }
+
+    if let [first, second] = modifiers[..] {
+        if first == second {
+            return Err(CompilerError::RepeatedQuantifierModifier(first.to_owned()));
+        }
+    }
 
-            let parsed_start = start
-                .parse::<usize>()
-                .map_err(|_| CompilerError::InvalidQuantifierRange)?;
-            let parsed_end = end
-                .parse::<usize>()
-                .map_err(|_| CompilerError::InvalidQuantifierRange)?;
+    let kind = match kind.as_rule() {
+        Rule::amount => QuantifierKind::Amount(parse_amount(kind.as_str())?),
+        Rule::over => over(kind, quantifier_span, &mut context.warnings)?,
+        Rule::at_least => QuantifierKind::AtLeast(parse_amount(first_inner(kind, "at_least")?.as_str())?),
+        Rule::up_to => {
+            let amount = parse_amount(last_inner(kind, "up_to")?.as_str())?;
 
-            if parsed_start > parsed_end {
-                return Err(CompilerError::InvalidQuantifierRange);
+            if amount == 0 {
+                return Err(CompilerError::UpToZero);
             }
 
-            ViableAstNode::Quantifier(Quantifier {
-                kind: QuantifierKind::Range {
-                    start: start.to_owned(),
-                    end: end.to_owned(),
-                },
-                lazy,
-                expression: Box::new(expression),
-            })
+            QuantifierKind::AtMost(amount)
+        }
+        Rule::option => QuantifierKind::Option,
+        Rule::any => QuantifierKind::Any,
+        Rule::some => QuantifierKind::Some,
+
+        Rule::quantifier_range => {
+            let range = kind.as_str();
+            let invalid_range = |_| CompilerError::InvalidQuantifierRange(range.to_owned());
+            let (start, end) = first_last_inner_str(kind, "quantifier_range")?;
+            let start = parse_amount(start).map_err(invalid_range)?;
+            let end = parse_amount(end).map_err(invalid_range)?;
+
+            if start > end {
+                return Err(CompilerError::InvalidQuantifierRange(range.to_owned()));
+            }
+
+            QuantifierKind::Range { start, end }
         }
 
         _ => return Err(CompilerError::UnrecognizedSyntax),
     };
 
+    match kind {
+        QuantifierKind::Amount(0) | QuantifierKind::Range { start: 0, end: 0 } if !context.allow_zero_quantifiers => {
+            return Err(CompilerError::ZeroQuantifier);
+        }
+        QuantifierKind::Amount(1) | QuantifierKind::Range { start: 1, end: 1 } => {
+            context
+                .warnings
+                .push(Warning::RedundantQuantifier { span: quantifier_span });
+        }
+        QuantifierKind::AtLeast(amount @ (0 | 1)) => context.warnings.push(Warning::QuantifierKeyword {
+            keyword: if amount == 0 { "any of" } else { "some of" },
+            span: quantifier_span,
+        }),
+        _ => {}
+    }
+
+    if lazy && matches!(kind, QuantifierKind::Amount(_)) {
+        context
+            .warnings
+            .push(Warning::LazyExactQuantifier { span: quantifier_span });
+    }
+
+    let quantifier_node = ViableAstNode::Quantifier(Quantifier {
+        kind,
+        lazy,
+        possessive,
+        unit,
+        expression: Box::new(expression),
+    });
+
     Ok(quantifier_node)
 }
 
-fn group<T: BuildHasher>(pair: Pair<'_, Rule>, variables: &mut HashMap<String, ViableAst, T>) -> Result<ViableAstNode> {
-    let declaration = first_inner(pair.clone())?;
+/// Converts an `over n` quantity, which repeats more than `n` times, warning to write it as `n + 1 or more`
+fn over(pair: Pair<'_, Rule>, span: Span, warnings: &mut Vec<Warning>) -> Result<QuantifierKind> {
+    let raw_amount = last_inner(pair, "over")?.as_str();
+
+    let (kind, replacement) = match parse_amount(raw_amount)? {
+        // `over 0` is at least once
+        0 => (QuantifierKind::Some, String::from("some of")),
+        amount => {
+            let amount = amount
+                .checked_add(1)
+                .ok_or_else(|| CompilerError::CouldNotParseAnAmount(raw_amount.to_owned()))?;
+            (QuantifierKind::Over(amount), format!("{amount} or more of"))
+        }
+    };
+    warnings.push(Warning::DeprecatedOver { replacement, span });
+
+    Ok(kind)
+}
+
+fn group<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let block = last_inner(pair.clone(), "group")?;
+    let declaration = first_inner(pair, "group")?;
 
-    let kind = first_inner(declaration.clone())?.as_str();
+    let kind = first_inner(declaration.clone(), "group_declaration")?;
+    let mut declaration = declaration.into_inner().skip(1);
 
-    let kind = match kind {
+    let kind = match kind.as_str() {
         "either" => GroupKind::Either,
         "capture" => GroupKind::Capture,
         "match" => GroupKind::Match,
+        "atomic" => GroupKind::Atomic,
+
+        _ if kind.as_rule() == Rule::flag_group => flag_group(kind)?,
 
         _ => return Err(CompilerError::UnrecognizedGroup),
     };
 
-    let ident = nth_inner(declaration, 1).map(|ident| ident.as_str().trim().to_owned());
+    let ident = declaration.next().map(|ident| context.intern(ident.as_str()));
 
     if ident.is_some() && kind != GroupKind::Capture {
         return Err(CompilerError::UnexpectedIdentifierForNonCaptureGroup);
     }
 
-    let block = last_inner(pair)?;
-
     let group_node = ViableAstNode::Group(Group {
         ident,
         kind,
-        statements: Box::new(pairs_to_ast(block.into_inner(), variables)?),
+        statements: Box::new(block_to_ast(block.into_inner(), context)?),
     });
 
     Ok(group_node)
 }
 
-fn assertion<T: BuildHasher>(
-    pair: Pair<'_, Rule>,
-    variables: &mut HashMap<String, ViableAst, T>,
-) -> Result<ViableAstNode> {
-    let assertion_declaration = first_inner(pair.clone())?;
+fn flag_group(pair: Pair<'_, Rule>) -> Result<GroupKind> {
+    let mut set = Vec::new();
+    let mut clear = Vec::new();
+
+    for modifier in pair.into_inner() {
+        let (first, name) = first_last_inner_str(modifier, "flag_modifier")?;
+        let flag = flag_from_name(name).ok_or_else(|| CompilerError::UnknownFlag(name.to_owned()))?;
+
+        if set.contains(&flag) || clear.contains(&flag) {
+            return Err(CompilerError::DuplicateFlag(name.to_owned()));
+        }
+
+        if first == NOT {
+            clear.push(flag);
+        } else {
+            set.push(flag);
+        }
+    }
+
+    Ok(GroupKind::Flagged { set, clear })
+}
+
+fn quantified_block<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let mut statements = pair.into_inner().peekable();
+
+    if statements.peek().is_none() {
+        return Err(CompilerError::EmptyQuantifiedBlock);
+    }
+
+    if statements.clone().any(|statement| {
+        matches!(
+            statement.as_rule(),
+            Rule::variable_declaration | Rule::symbol_declaration
+        )
+    }) {
+        return Err(CompilerError::UnexpectedVariableDeclarationInQuantifier);
+    }
 
-    let (negative, kind) = first_last_inner_str(assertion_declaration)?;
+    let group_node = ViableAstNode::Group(Group {
+        ident: None,
+        kind: GroupKind::Match,
+        statements: Box::new(block_to_ast(statements, context)?),
+    });
+
+    Ok(group_node)
+}
+
+fn assertion<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let block = last_inner(pair.clone(), "assertion")?;
+    let assertion_declaration = first_inner(pair, "assertion")?;
+
+    let (negative, kind) = first_last_inner_str(assertion_declaration, "assertion_declaration")?;
 
     let negative = negative == NOT;
 
@@ -236,45 +1147,530 @@ fn assertion<T: BuildHasher>(
         _ => return Err(CompilerError::UnrecognizedAssertion),
     };
 
-    let block = last_inner(pair)?;
-
     let assertion_node = ViableAstNode::Assertion(Assertion {
         kind,
         negative,
-        statements: Box::new(pairs_to_ast(block.into_inner(), variables)?),
+        statements: Box::new(block_to_ast(block.into_inner(), context)?),
     });
 
     Ok(assertion_node)
 }
 
-fn negative_char_class(pair: &Pair<'_, Rule>) -> Result<ViableAstNode> {
-    let class = last_inner(pair.clone())?;
-    let negative_char_class_node = ViableAstNode::NegativeCharClass(class.as_str().to_owned());
-    Ok(negative_char_class_node)
+fn conditional<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let conditional_span = span(&pair);
+    let mut children = pair.into_inner();
+    let name = children
+        .next()
+        .ok_or(missing_node("conditional", conditional_span))?
+        .as_str();
+    let then = children.next().ok_or(missing_node("conditional", conditional_span))?;
+
+    let condition = if name.bytes().all(|byte| byte.is_ascii_digit()) {
+        Backreference::Numbered(parse_amount(name)?)
+    } else {
+        Backreference::Named(context.intern(name))
+    };
+
+    let otherwise = match children.next() {
+        Some(otherwise) => Some(Box::new(block_to_ast(
+            last_inner(otherwise, "conditional_else")?.into_inner(),
+            context,
+        )?)),
+        None => None,
+    };
+
+    Ok(ViableAstNode::Conditional(Conditional {
+        condition,
+        then: Box::new(block_to_ast(then.into_inner(), context)?),
+        otherwise,
+    }))
 }
 
-fn variable_invocation<T: BuildHasher>(
-    pair: &Pair<'_, Rule>,
-    variables: &mut HashMap<String, ViableAst, T>,
-) -> Result<ViableAstNode> {
-    let identifier = last_inner(pair.clone())?;
-    let statements = match variables.get(identifier.as_str()) {
-        Some(statements) => statements.clone(),
-        None => return Err(CompilerError::UninitializedVariable),
+fn negative_char_class(pair: Pair<'_, Rule>) -> Result<ViableAstNode> {
+    Ok(ViableAstNode::NegativeCharClass(class_content(pair)?))
+}
+
+/// Parses the members of `not abc;` and `class abc;` into chars and char ranges
+fn class_content(pair: Pair<'_, Rule>) -> Result<Vec<ClassItem>> {
+    let class = last_inner(pair, "class_content")?;
+
+    class
+        .into_inner()
+        .map(|member| match member.as_rule() {
+            Rule::class_content_range => {
+                let span = span(&member);
+                let start = to_char(
+                    &first_inner(member.clone(), "class_content_range")?,
+                    "class_content_range",
+                )?;
+                let end = to_char(&last_inner(member, "class_content_range")?, "class_content_range")?;
+
+                if start > end {
+                    return Err(CompilerError::EmptyRange { start, end, span });
+                }
+
+                Ok(ClassItem::Range(Range::CharRange(CharRange {
+                    negative: false,
+                    start,
+                    end,
+                })))
+            }
+            _ => Ok(ClassItem::Char(to_char(&member, "class_content")?)),
+        })
+        .collect()
+}
+
+fn code_point(pair: Pair<'_, Rule>) -> Result<char> {
+    let digits = last_inner(pair, "code_point")?;
+    decode_code_point(digits.as_str())
+}
+
+fn char_class<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let mut negative = false;
+    let mut items = Vec::new();
+
+    for item in pair.into_inner() {
+        match item.as_rule() {
+            Rule::not => negative = true,
+            Rule::class_subtract => items.push(ClassItem::Subtract(class_items(item, context)?)),
+            Rule::class_intersect => items.push(ClassItem::Intersect(class_items(item, context)?)),
+            _ => items.push(class_item(item, context)?),
+        }
+    }
+
+    Ok(ViableAstNode::CharClass(CharClass { negative, items }))
+}
+
+fn class_items<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<Vec<ClassItem>> {
+    pair.into_inner().map(|item| class_item(item, context)).collect()
+}
+
+fn class_item<T: BuildHasher>(item: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ClassItem> {
+    let class_item = match item.as_rule() {
+        Rule::literal => {
+            let literal = unescape_literal(&item)?;
+            let mut chars = literal.chars();
+            match (chars.next(), chars.next()) {
+                (Some(char), None) => ClassItem::Char(char),
+                _ => return Err(CompilerError::UnexpectedMultiCharacterAtomInClass),
+            }
+        }
+        Rule::code_point => ClassItem::Char(code_point(item)?),
+        Rule::raw => ClassItem::Raw(context.intern(&unquote_escape_raw(&item))),
+        Rule::range => match range(item, context)? {
+            ViableAstNode::Range(range) => class_range(range)?,
+            _ => return Err(CompilerError::UnrecognizedSyntax),
+        },
+        Rule::symbol if is_custom_symbol(&item, context) => return Err(CompilerError::UnexpectedSymbolInClass),
+        Rule::unterminated_class_item => return Err(missing_terminator(&item)),
+        Rule::symbol => match symbol(item).map_err(|error| suggest_custom_symbols(error, context))? {
+            ViableAstNode::Symbol(symbol) => {
+                if !symbol.kind.is_class_compatible(symbol.negative) {
+                    return Err(CompilerError::UnexpectedSymbolInClass);
+                }
+                ClassItem::Symbol(symbol)
+            }
+            ViableAstNode::UnicodeCategory(category) => ClassItem::UnicodeCategory(category),
+            ViableAstNode::UnicodeProperty(property) => ClassItem::UnicodeProperty(property),
+            _ => return Err(CompilerError::UnexpectedSymbolInClass),
+        },
+        _ => return Err(CompilerError::UnrecognizedSyntax),
+    };
+
+    Ok(class_item)
+}
+
+fn class_range(range: Range) -> Result<ClassItem> {
+    let negative = match &range {
+        Range::CharRange(range) => range.negative,
+        Range::NumericRange(range) => range.negative,
     };
+
+    if negative {
+        return Err(CompilerError::UnexpectedNegativeRangeInClass);
+    }
+
+    Ok(ClassItem::Range(range))
+}
+
+fn backreference<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let reference = last_inner(pair, "backreference")?;
+    let backreference = match reference.as_rule() {
+        Rule::backreference_index => {
+            let index = parse_amount(last_inner(reference, "backreference_index")?.as_str())?;
+            Backreference::Numbered(index)
+        }
+        _ => Backreference::Named(context.intern(reference.as_str())),
+    };
+    Ok(ViableAstNode::Backreference(backreference))
+}
+
+fn recursion<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> ViableAstNode {
+    let recursion = match pair.into_inner().next() {
+        Some(name) => Recursion::Group(context.intern(name.as_str())),
+        None => Recursion::Pattern,
+    };
+
+    ViableAstNode::Recursion(recursion)
+}
+
+fn raw_regex<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let span = span(&pair);
+    let regex = unescape_literal(&last_inner(pair, "raw_regex")?)?;
+
+    if let Err(unbalanced) = raw_regex_captures(&regex) {
+        return Err(CompilerError::UnbalancedRawRegex { unbalanced, span });
+    }
+
+    Ok(ViableAstNode::RawRegex(context.intern(&regex)))
+}
+
+fn variable_invocation<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let invocation_span = span(&pair);
+    let mut inner = pair.into_inner();
+    let identifier = inner
+        .next()
+        .ok_or(missing_node("variable_invocation", invocation_span))?;
+    let name = identifier.as_str();
+
+    let arguments = match inner.next() {
+        Some(arguments) => arguments
+            .into_inner()
+            .map(|argument| create_ast_node(argument, context))
+            .collect::<Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+
+    if context.parameters_in_scope.iter().any(|parameter| parameter == name) {
+        if !arguments.is_empty() {
+            return Err(CompilerError::ArityMismatch {
+                name: name.to_owned(),
+                expected: 0,
+                found: arguments.len(),
+            });
+        }
+        return Ok(ViableAstNode::Parameter(context.intern(name)));
+    }
+
+    if let Some((cycle, mut spans)) = recursive_cycle(name, context) {
+        spans.insert(0, invocation_span);
+        return Err(CompilerError::RecursiveVariable {
+            name: name.to_owned(),
+            cycle,
+            spans,
+        });
+    }
+
+    // checked before the statements are cloned, as cloning recurses through every level of them
+    context.reach(context.variable_depths.get(name).copied().unwrap_or_default())?;
+
+    let statements = match context.variables.get(name) {
+        Some(statements) => Rc::clone(statements),
+        None if !context.parameters_in_scope.is_empty() => {
+            return Err(CompilerError::UnknownParameter(name.to_owned()))
+        }
+        None => {
+            return Err(CompilerError::UninitializedVariable {
+                name: name.to_owned(),
+                suggestion: closest_match(name, context.variables.keys().map(String::as_str)).map(str::to_owned),
+            })
+        }
+    };
+
+    let parameters = context.parameters.get(name).cloned().unwrap_or_default();
+
+    if parameters.len() != arguments.len() {
+        return Err(CompilerError::ArityMismatch {
+            name: name.to_owned(),
+            expected: parameters.len(),
+            found: arguments.len(),
+        });
+    }
+
+    let (statements, declaration) = if parameters.is_empty() {
+        (statements, None)
+    } else {
+        let bindings: HashMap<&str, &Spanned<ViableAstNode>> =
+            parameters.iter().map(String::as_str).zip(&arguments).collect();
+        let substituted = substitute_parameters((*statements).clone(), &bindings);
+        let declaration = ParameterizedDeclaration {
+            parameters: parameters.iter().map(|parameter| context.intern(parameter)).collect(),
+            statements,
+        };
+        (Rc::new(substituted), Some(Rc::new(declaration)))
+    };
+
+    context.mark_used(name);
+    context.invoked.push(InvokedVariable {
+        name: name.to_owned(),
+        span: invocation_span,
+    });
+
     let variable_invocation_node = ViableAstNode::VariableInvocation(VariableInvocation {
-        statements: Box::new(statements),
+        name: context.intern(name),
+        span: invocation_span,
+        statements,
+        arguments,
+        declaration,
     });
     Ok(variable_invocation_node)
 }
 
-fn variable_declaration<T: BuildHasher>(
-    pair: Pair<'_, Rule>,
-    variables: &mut HashMap<String, ViableAst, T>,
-) -> Result<ViableAstNode> {
-    let identifier = first_inner(pair.clone())?;
-    let statements = last_inner(pair)?;
-    let variable_ast = pairs_to_ast(statements.into_inner(), variables)?;
-    variables.insert(identifier.as_str().trim().to_owned(), variable_ast);
+/// Finds the cycle formed by invoking `name` while the variables in `context.defining` are being declared,
+/// e.g. `["a", "b", "a"]` for an invocation of `b` within `a` where `b` invokes `a`
+///
+/// Also returns the spans of the invocations that close the cycle past the invocation of `name` itself
+fn recursive_cycle<T: BuildHasher>(name: &str, context: &ParseContext<T>) -> Option<(Vec<String>, Vec<Span>)> {
+    if let Some(position) = context.defining.iter().position(|defining| defining == name) {
+        let mut cycle = context.defining[position..].to_vec();
+        cycle.push(name.to_owned());
+        return Some((cycle, Vec::new()));
+    }
+
+    // a depth first search for a path from the invoked variable back to one being declared
+    let mut stack = vec![(vec![name.to_owned()], Vec::new())];
+    let mut visited = HashSet::new();
+
+    while let Some((path, spans)) = stack.pop() {
+        let last = path.last()?;
+
+        if let Some(position) = context.defining.iter().position(|defining| defining == last) {
+            let mut cycle = context.defining[position..].to_vec();
+            cycle.extend(path);
+            return Some((cycle, spans));
+        }
+
+        if !visited.insert(last.clone()) {
+            continue;
+        }
+
+        for (dependency, span) in context.dependencies.get(last).into_iter().flatten() {
+            let mut next = path.clone();
+            next.push(dependency.clone());
+            let mut next_spans = spans.clone();
+            next_spans.push(*span);
+            stack.push((next, next_spans));
+        }
+    }
+
+    None
+}
+
+fn import_statement<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let literal = first_inner(pair, "import_statement")?;
+    let path = resolve_import_path(context.imports.last().map(String::as_str), &unescape_literal(&literal)?);
+
+    if let Some(position) = context.imports.iter().position(|import| *import == path) {
+        let mut chain = context.imports[position..].to_vec();
+        chain.push(path);
+        return Err(CompilerError::ImportCycle { chain });
+    }
+
+    let Some(resolver) = context.resolver.clone() else {
+        return Err(CompilerError::MissingImportResolver(path));
+    };
+    let source = resolver(&path).ok_or_else(|| CompilerError::ImportNotFound(path.clone()))?;
+
+    let mut module = ParseContext::new();
+    module.resolver = Some(resolver);
+    module.imports.clone_from(&context.imports);
+    module.imports.push(path.clone());
+    module.nesting_limit = context.nesting_limit;
+    module.allow_zero_quantifiers = context.allow_zero_quantifiers;
+    module.deny_negative_captures = context.deny_negative_captures;
+
+    let module_error = |error: CompilerError| match error {
+        // errors from nested imports already refer to the file they occurred in
+        CompilerError::ImportCycle { .. } | CompilerError::ImportError { .. } => error,
+        error => CompilerError::ImportError {
+            path: path.clone(),
+            error: Box::new(error),
+        },
+    };
+
+    if !source.is_empty() {
+        let root_statements = parse_root(&source, context.nesting_limit).map_err(|error| module_error(error.node))?;
+
+        module.dependencies = variable_dependencies(&root_statements);
+        module.interner = core::mem::take(&mut context.interner);
+        // the statements of the imported file are discarded, only its variables and symbols are kept
+        pairs_to_ast(root_statements.into_inner(), &mut module);
+        context.interner = core::mem::take(&mut module.interner);
+
+        if !module.errors.is_empty() {
+            return Err(module_error(module.errors.swap_remove(0).node));
+        }
+    }
+
+    context.variables.extend(module.variables);
+    context.symbols.extend(module.symbols);
+    context.parameters.extend(module.parameters);
+    context.variable_depths.extend(module.variable_depths);
+    Ok(ViableAstNode::Skip)
+}
+
+fn variable_declaration<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let declaration_span = span(&pair);
+    let mut children = pair.into_inner();
+    let identifier = children
+        .next()
+        .ok_or(missing_node("variable_declaration", declaration_span))?;
+    let name = identifier.as_str().trim().to_owned();
+    let statements = children
+        .next_back()
+        .ok_or(missing_node("variable_declaration", declaration_span))?;
+
+    let mut parameters: Vec<String> = Vec::new();
+    for parameter in children
+        .filter(|inner| inner.as_rule() == Rule::parameters)
+        .flat_map(Pair::into_inner)
+    {
+        let parameter = parameter.as_str().trim().to_owned();
+        if parameters.contains(&parameter) {
+            return Err(CompilerError::DuplicateParameter(parameter));
+        }
+        parameters.push(parameter);
+    }
+
+    let scope_length = context.parameters_in_scope.len();
+    context.parameters_in_scope.extend(parameters.iter().cloned());
+    context.defining.push(name.clone());
+    // an invocation only nests the statements of the variable, not the block the variable is declared in
+    let deepest = core::mem::replace(&mut context.deepest, context.depth);
+    let variable_ast = block_to_ast(statements.into_inner(), context);
+    let variable_depth = context.deepest - context.depth;
+    context.deepest = deepest;
+    context.defining.pop();
+    context.parameters_in_scope.truncate(scope_length);
+    let variable_ast = variable_ast?;
+
+    if let Some(shadowed) = context
+        .declarations
+        .iter()
+        .rev()
+        .find(|declaration| declaration.name == name)
+    {
+        context.warnings.push(Warning::ShadowedVariable {
+            name: name.clone(),
+            first_span: shadowed.span,
+            second_span: declaration_span,
+        });
+    }
+
+    context.declarations.push(Declaration {
+        name: name.clone(),
+        span: declaration_span,
+        used: false,
+    });
+
+    if parameters.is_empty() {
+        context.parameters.remove(&name);
+    } else {
+        context.parameters.insert(name.clone(), parameters);
+    }
+
+    let statements = Rc::new(variable_ast);
+    context.declared.push(DeclaredVariable {
+        name: name.clone(),
+        span: declaration_span,
+        statements: Rc::clone(&statements),
+    });
+
+    context.variable_depths.insert(name.clone(), variable_depth);
+    context.variables.insert(name, statements);
+    Ok(ViableAstNode::Skip)
+}
+
+/// Whether a symbol refers to a custom symbol in scope rather than a built in one
+fn is_custom_symbol<T: BuildHasher>(pair: &Pair<'_, Rule>, context: &ParseContext<T>) -> bool {
+    last_inner(pair.clone(), "symbol").is_ok_and(|name| {
+        let name = name.as_str();
+        !is_builtin_symbol(name) && context.symbols.contains_key(name)
+    })
+}
+
+/// Suggests the closest of the custom symbols in scope for an unrecognized symbol if it is closer than the
+/// built in suggestion
+fn suggest_custom_symbols<T: BuildHasher>(error: CompilerError, context: &ParseContext<T>) -> CompilerError {
+    let CompilerError::UnrecognizedSymbol { name, suggestion } = error else {
+        return error;
+    };
+
+    let candidates = suggestion.iter().chain(context.symbols.keys());
+    let suggestion = closest_match(&name, candidates.map(String::as_str)).map(str::to_owned);
+
+    CompilerError::UnrecognizedSymbol { name, suggestion }
+}
+
+/// Converts a symbol, expanding custom symbols like an invocation of a variable without parameters
+fn symbol_or_custom<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    if !is_custom_symbol(&pair, context) {
+        return symbol(pair).map_err(|error| suggest_custom_symbols(error, context));
+    }
+
+    let symbol_span = span(&pair);
+    let (negative, name) = first_last_inner_str(pair, "symbol")?;
+    if negative == NOT {
+        return Err(CompilerError::NegativeCustomSymbol(name.to_owned()));
+    }
+
+    let (statements, depth) = match context.symbols.get(name) {
+        Some(custom) => (Rc::clone(&custom.statements), custom.depth),
+        None => {
+            return Err(CompilerError::UnrecognizedSymbol {
+                name: name.to_owned(),
+                suggestion: None,
+            })
+        }
+    };
+    context.reach(depth)?;
+
+    Ok(ViableAstNode::VariableInvocation(VariableInvocation {
+        name: context.intern(name),
+        span: symbol_span,
+        statements,
+        arguments: Vec::new(),
+        declaration: None,
+    }))
+}
+
+fn symbol_declaration<T: BuildHasher>(pair: Pair<'_, Rule>, context: &mut ParseContext<T>) -> Result<ViableAstNode> {
+    let declaration_span = span(&pair);
+    let mut children = pair.into_inner();
+    let name = children
+        .next()
+        .ok_or(missing_node("symbol_declaration", declaration_span))?
+        .as_str()
+        .trim()
+        .to_owned();
+    let statements = children
+        .next()
+        .ok_or(missing_node("symbol_declaration", declaration_span))?;
+
+    let first_span = match context.symbols.get(&name) {
+        Some(defined) => Some(defined.span),
+        None if is_builtin_symbol(&name) => Some(Span::default()),
+        None => None,
+    };
+    if let Some(first_span) = first_span {
+        return Err(CompilerError::DuplicateSymbol {
+            name,
+            first_span,
+            second_span: declaration_span,
+        });
+    }
+
+    // like a variable, the expansion only nests the statements of the symbol
+    let deepest = core::mem::replace(&mut context.deepest, context.depth);
+    let symbol_ast = block_to_ast(statements.into_inner(), context);
+    let depth = context.deepest - context.depth;
+    context.deepest = deepest;
+
+    let symbol = CustomSymbol {
+        statements: Rc::new(symbol_ast?),
+        span: declaration_span,
+        depth,
+    };
+    context.symbols.insert(name, symbol);
     Ok(ViableAstNode::Skip)
 }
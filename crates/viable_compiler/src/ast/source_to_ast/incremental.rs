@@ -0,0 +1,831 @@
+use super::{
+    finish, flags_block, parse_root, span, statement_dependencies, statement_to_ast, Declaration, ParseContext, Rule,
+    DEFAULT_NESTING_LIMIT,
+};
+use crate::ast::symbols::is_builtin_symbol;
+use crate::ast::transform::{fold, fold_expression, fold_node, fold_variable_invocation, Transform};
+use crate::ast::types::ast::{Expression, PatternFlags, Span, Spanned, VariableInvocation, ViableAst, ViableAstNode};
+use crate::ast::utils::{last_inner, unshare};
+use crate::prelude::*;
+use crate::types::{HashSet, Result};
+use crate::warnings::Warning;
+use alloc::rc::Rc;
+use core::iter;
+use core::ops::Range;
+use pest::iterators::Pair;
+
+/**
+Converts a source to a Viable AST again after every edit, only parsing and converting the top level statements
+around the edit.
+
+A statement whose text is unchanged keeps its AST from the previous update (with its spans moved to where it now
+is) unless it declares or invokes a variable whose declaration was changed, added or removed. The whole source is
+parsed again if that's the case for a statement after the edit, if the edit changes which variables the
+declarations invoke, or if a statement of the previous source failed to convert. The results are the same as those
+of [`to_ast`](super::to_ast) and [`to_ast_with_diagnostics`](super::to_ast_with_diagnostics), imports are not
+supported.
+
+# Example
+
+```rust
+use viable_compiler::ast::IncrementalParser;
+
+let mut parser = IncrementalParser::new();
+
+parser.update(r#"let .a = { "a"; } .a; "b";"#).unwrap();
+assert_eq!(parser.converted(), 3);
+
+parser.update(r#"let .a = { "a"; } .a; "c";"#).unwrap();
+assert_eq!(parser.converted(), 1);
+
+// every invocation of an edited variable is converted again
+parser.update(r#"let .a = { "d"; } .a; "c";"#).unwrap();
+assert_eq!(parser.converted(), 2);
+```
+*/
+pub struct IncrementalParser {
+    source: String,
+    flags: PatternFlags,
+    /// the top level statements of the source in source order, without the `flags` block and the end of input
+    statements: Vec<Statement>,
+    /// whether the `flags` block has errors, which are only found by parsing the whole source
+    flags_failed: bool,
+    /// the span of the end of input
+    end: Span,
+    ast: ViableAst,
+    warnings: Vec<Warning>,
+    converted: usize,
+}
+
+/// A converted top level statement along with its effects on the parse context, which are replayed when it
+/// is reused
+#[derive(Clone)]
+struct Statement {
+    span: Span,
+    node: Spanned<ViableAstNode>,
+    /// the invocations and declarations of variables within the statement, in the order they are converted
+    events: Vec<Event>,
+    /// the variables invoked by each declaration within the statement, see `ParseContext::dependencies`
+    dependencies: Vec<(String, Vec<(String, Span)>)>,
+    /// the variables declared by the statement, as they were after it
+    variables: Vec<Variable>,
+    warnings: Vec<Warning>,
+    /// statements that failed are always converted again
+    failed: bool,
+    /// whether the statement defines or uses a custom symbol, which aren't tracked like variables so the
+    /// statement is always converted again
+    custom_symbols: bool,
+}
+
+#[derive(Clone)]
+struct Variable {
+    name: String,
+    ast: Rc<ViableAst>,
+    parameters: Option<Vec<String>>,
+    /// see `ParseContext::variable_depths`
+    depth: usize,
+}
+
+#[derive(Clone)]
+enum Event {
+    Invoke(String),
+    Declare(String, Span),
+}
+
+/// The part of the source that changed, `start..old_end` of the previous source was replaced by `start..new_end`
+struct Edit {
+    start: usize,
+    old_end: usize,
+    new_end: usize,
+}
+
+/// A statement of the new source, either one of the previous statements or a new (or moved) one
+enum Slot {
+    Previous(usize),
+    Statement(Box<Statement>),
+}
+
+struct Conversion {
+    slots: Vec<Slot>,
+    nodes: Vec<Spanned<ViableAstNode>>,
+    context: ParseContext,
+    converted: usize,
+    flags_failed: bool,
+    /// false if a statement that wasn't parsed again would have to be converted again
+    complete: bool,
+}
+
+impl IncrementalParser {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            source: String::new(),
+            flags: PatternFlags::default(),
+            flags_failed: false,
+            statements: Vec::new(),
+            end: Span::default(),
+            ast: ViableAst::Empty,
+            warnings: Vec::new(),
+            converted: 0,
+        }
+    }
+
+    /**
+    Converts the new version of the source, reusing the statements that didn't change since the previous update.
+
+    If the source no longer parses, the previous source is kept and the next update is compared against it.
+
+    # Errors
+
+    Returns the first [`CompilerError`](crate::errors::CompilerError) of the source, like [`to_ast`](super::to_ast)
+    */
+    pub fn update(&mut self, source: &str) -> Result<&ViableAst> {
+        self.converted = 0;
+
+        if source.is_empty() {
+            *self = Self::new();
+            return Ok(&self.ast);
+        }
+
+        let edit = Edit::new(&self.source, source);
+        let mut shift = Shift {
+            old: Location::new(&self.source, edit.old_end),
+            new: Location::new(source, edit.new_end),
+        };
+
+        let (conversion, flags, end) = match self.update_part(source, &edit, &mut shift) {
+            Some(conversion) => conversion,
+            None => self.update_all(source, &edit, &mut shift)?,
+        };
+
+        let Conversion {
+            slots,
+            mut nodes,
+            context,
+            converted,
+            flags_failed,
+            ..
+        } = conversion;
+
+        nodes.retain(|node| !matches!(node.node, ViableAstNode::Skip));
+
+        let (parsed, errors) = finish(ViableAst::Root(nodes), flags.clone(), context);
+
+        let mut previous: Vec<_> = core::mem::take(&mut self.statements).into_iter().map(Some).collect();
+        self.statements = slots
+            .into_iter()
+            .filter_map(|slot| match slot {
+                Slot::Previous(index) => previous[index].take(),
+                Slot::Statement(statement) => Some(*statement),
+            })
+            .collect();
+
+        source.clone_into(&mut self.source);
+        self.flags = flags;
+        self.flags_failed = flags_failed;
+        self.end = end;
+        self.ast = parsed.ast;
+        self.warnings = parsed.warnings;
+        self.converted = converted;
+
+        match errors.into_iter().next() {
+            Some(error) => Err(error.node),
+            None => Ok(&self.ast),
+        }
+    }
+
+    /// The AST of the last source, possibly partial if it failed to convert (see [`super::to_ast_lenient`])
+    #[must_use]
+    pub const fn ast(&self) -> &ViableAst {
+        &self.ast
+    }
+
+    /// The warnings of the last source
+    #[must_use]
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// The number of top level statements converted by the last update, the others were reused
+    #[must_use]
+    pub const fn converted(&self) -> usize {
+        self.converted
+    }
+
+    /// Parses and converts the statements from the one before the edit to the one after it, if the other
+    /// statements can be reused
+    fn update_part(&self, source: &str, edit: &Edit, shift: &mut Shift) -> Option<(Conversion, PatternFlags, Span)> {
+        if self.statements.is_empty()
+            || self.flags_failed
+            || self
+                .statements
+                .iter()
+                .any(|statement| statement.failed || statement.custom_symbols)
+            || edit.start < self.flags.span.end
+        {
+            return None;
+        }
+
+        let touched = self
+            .statements
+            .partition_point(|statement| statement.span.end < edit.start);
+        let first = touched.saturating_sub(1);
+        let last = self
+            .statements
+            .partition_point(|statement| statement.span.start <= edit.old_end);
+
+        let start = match touched {
+            0 => self.flags.span.end,
+            _ => self.statements[first].span.start,
+        };
+        let moved = |offset: usize| offset - edit.old_end + edit.new_end;
+        // the part runs up to the start of the statement after the one following the edit, so that it includes
+        // the semicolon and comments of that statement
+        let (replaced_end, last_end, end) = match self.statements.get(last) {
+            Some(statement) => {
+                let end = self
+                    .statements
+                    .get(last + 1)
+                    .map_or(source.len(), |next| moved(next.span.start));
+                (last + 1, Some(moved(statement.span.end)), end)
+            }
+            None => (self.statements.len(), None, source.len()),
+        };
+
+        // the source before the part is blanked out to keep the spans of the part, its columns are only kept if
+        // the line the part starts on has no multibyte characters before it
+        let line_start = source[..start].rfind('\n').map_or(0, |position| position + 1);
+        if !source[line_start..start].is_ascii() {
+            return None;
+        }
+
+        let mut part = String::with_capacity(end);
+        part.extend(
+            source[..start]
+                .bytes()
+                .map(|byte| if byte == b'\n' { '\n' } else { ' ' }),
+        );
+        part.push_str(&source[start..end]);
+
+        let mut pairs = parse_root(&part, DEFAULT_NESTING_LIMIT).ok()?.into_inner();
+        let eoi = pairs.next_back()?;
+        let pairs: Vec<_> = pairs.collect();
+
+        // the part must end with the unchanged statement after the edit, otherwise a comment or literal could
+        // run on into the statements after it
+        let aligned = last_end.map_or(true, |last_end| {
+            pairs.last().map(|pair| pair.as_span().end()) == Some(last_end)
+        });
+        if !aligned || pairs.iter().any(|pair| pair.as_rule() == Rule::flags_block) {
+            return None;
+        }
+
+        let end = if end == source.len() {
+            span(&eoi)
+        } else {
+            shift.span(self.end)
+        };
+
+        let conversion = self.convert(edit, shift, first..replaced_end, pairs, ParseContext::new());
+        conversion.complete.then(|| (conversion, self.flags.clone(), end))
+    }
+
+    /// Parses the whole source, still reusing the statements that didn't change
+    fn update_all(&self, source: &str, edit: &Edit, shift: &mut Shift) -> Result<(Conversion, PatternFlags, Span)> {
+        let root_statements = parse_root(source, DEFAULT_NESTING_LIMIT).map_err(|error| error.node)?;
+
+        let mut context = ParseContext::new();
+        let mut statements = root_statements.into_inner().peekable();
+
+        let flags = match statements.next_if(|pair| pair.as_rule() == Rule::flags_block) {
+            Some(pair) => flags_block(pair, &mut context),
+            None => PatternFlags::default(),
+        };
+
+        let flags_failed = !context.errors.is_empty();
+
+        let mut pairs: Vec<_> = statements.collect();
+        let end = pairs.pop().map_or_else(Span::default, |eoi| span(&eoi));
+
+        let replaced = 0..self.statements.len();
+        let mut conversion = self.convert(edit, shift, replaced, pairs, context);
+        conversion.flags_failed = flags_failed;
+        Ok((conversion, flags, end))
+    }
+
+    /// Converts the statements `pairs` replacing the previous statements `replaced`, reusing those that didn't
+    /// change along with the statements before and after them
+    fn convert(
+        &self,
+        edit: &Edit,
+        shift: &mut Shift,
+        replaced: Range<usize>,
+        pairs: Vec<Pair<'_, Rule>>,
+        mut context: ParseContext,
+    ) -> Conversion {
+        let partial = replaced != (0..self.statements.len());
+        let before = &self.statements[..replaced.start];
+        let previous = &self.statements[replaced.clone()];
+        let after = &self.statements[replaced.end..];
+
+        let dependencies: Vec<_> = pairs.iter().map(statement_dependencies).collect();
+
+        // which variables are recursive depends on every declaration of the names they invoke, including later
+        // ones, so declarations are converted again if any invocation within a declaration changed
+        let edges = |(name, invoked): &(String, Vec<(String, Span)>)| {
+            invoked
+                .iter()
+                .map(|(invoked, _)| (name.clone(), invoked.clone()))
+                .collect::<Vec<_>>()
+        };
+        let dependencies_changed = !previous
+            .iter()
+            .flat_map(|statement| &statement.dependencies)
+            .flat_map(edges)
+            .eq(dependencies.iter().flatten().flat_map(edges));
+
+        let capacity = before.len() + pairs.len() + after.len();
+        let mut conversion = Conversion {
+            slots: Vec::with_capacity(capacity),
+            nodes: Vec::with_capacity(capacity + 1),
+            context: ParseContext::new(),
+            converted: 0,
+            flags_failed: false,
+            complete: !(partial && dependencies_changed),
+        };
+
+        if !conversion.complete {
+            return conversion;
+        }
+
+        let declarations = before
+            .iter()
+            .flat_map(|statement| statement.dependencies.iter().cloned())
+            .chain(dependencies.into_iter().flatten())
+            .chain(after.iter().flat_map(|statement| statement.shifted_dependencies(shift)));
+        for (name, invoked) in declarations {
+            context.dependencies.entry(name).or_default().extend(invoked);
+        }
+
+        let candidates: Vec<_> = pairs
+            .iter()
+            .map(|pair| {
+                self.candidate(span(pair), edit)
+                    .filter(|index| replaced.contains(index))
+            })
+            .collect();
+
+        let mut kept = vec![false; self.statements.len()];
+        for index in candidates.iter().flatten() {
+            kept[*index] = true;
+        }
+
+        // variables declared by removed statements change for the statements after the edit
+        let mut removed: Vec<String> = previous
+            .iter()
+            .zip(&kept[replaced.clone()])
+            .filter(|(_, kept)| !**kept)
+            .flat_map(|(statement, _)| statement.declared().map(str::to_owned))
+            .collect();
+        let mut changed: HashSet<String> = HashSet::new();
+
+        for (index, statement) in before.iter().enumerate() {
+            conversion.reuse(index, statement, &mut context);
+        }
+
+        for (pair, candidate) in pairs.into_iter().zip(candidates) {
+            if span(&pair).end > edit.start {
+                changed.extend(removed.drain(..));
+            }
+
+            let reused = candidate.filter(|index| {
+                let statement = &self.statements[*index];
+                !(statement.failed
+                    || statement.custom_symbols
+                    || statement.depends_on(&changed)
+                    || (dependencies_changed && statement.declares()))
+            });
+
+            match reused {
+                Some(index) if self.statements[index].span.start >= edit.old_end && shift.old != shift.new => {
+                    let statement = self.statements[index].clone().shifted(shift);
+                    statement.replay(&mut context);
+                    conversion.push(statement);
+                }
+                Some(index) => conversion.reuse(index, &self.statements[index], &mut context),
+                None => {
+                    conversion.converted += 1;
+                    let statement = Statement::convert(pair, &mut context);
+                    changed.extend(statement.declared().map(str::to_owned));
+                    conversion.push(statement);
+                }
+            }
+        }
+
+        changed.extend(removed);
+
+        for (index, statement) in after.iter().enumerate() {
+            if statement.depends_on(&changed) {
+                conversion.complete = false;
+                return conversion;
+            }
+
+            if shift.old == shift.new {
+                conversion.reuse(replaced.end + index, statement, &mut context);
+            } else {
+                let statement = statement.clone().shifted(shift);
+                statement.replay(&mut context);
+                conversion.push(statement);
+            }
+        }
+
+        conversion.context = context;
+        conversion
+    }
+
+    /// Finds the statement of the previous source a statement of the new source is a copy of, if it is outside
+    /// of the edit
+    fn candidate(&self, span: Span, edit: &Edit) -> Option<usize> {
+        let start = if span.end <= edit.start {
+            span.start
+        } else if span.start >= edit.new_end {
+            span.start - edit.new_end + edit.old_end
+        } else {
+            return None;
+        };
+
+        let index = self
+            .statements
+            .binary_search_by_key(&start, |statement| statement.span.start)
+            .ok()?;
+
+        (self.statements[index].span.end - start == span.end - span.start).then_some(index)
+    }
+}
+
+impl Default for IncrementalParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Conversion {
+    /// Adds a previous statement, applying it to the context
+    fn reuse(&mut self, index: usize, statement: &Statement, context: &mut ParseContext) {
+        statement.replay(context);
+        self.nodes.push(statement.node.clone());
+        self.slots.push(Slot::Previous(index));
+    }
+
+    /// Adds a statement that has already been applied to the context
+    fn push(&mut self, statement: Statement) {
+        self.nodes.push(statement.node.clone());
+        self.slots.push(Slot::Statement(Box::new(statement)));
+    }
+}
+
+impl Statement {
+    fn convert(pair: Pair<'_, Rule>, context: &mut ParseContext) -> Self {
+        let errors = context.errors.len();
+        let warnings = context.warnings.len();
+
+        let mut events = Vec::new();
+        variable_events(pair.clone(), &mut Vec::new(), &mut events);
+
+        let mut statement = Self {
+            span: span(&pair),
+            dependencies: statement_dependencies(&pair),
+            custom_symbols: has_custom_symbols(&pair),
+            node: statement_to_ast(pair, context),
+            events,
+            variables: Vec::new(),
+            warnings: context.warnings[warnings..].to_vec(),
+            failed: context.errors.len() > errors,
+        };
+
+        let mut declared: Vec<&str> = statement.declared().collect();
+        declared.sort_unstable();
+        declared.dedup();
+
+        statement.variables = declared
+            .into_iter()
+            .filter_map(|name| {
+                Some(Variable {
+                    name: name.to_owned(),
+                    ast: context.variables.get(name)?.clone(),
+                    parameters: context.parameters.get(name).cloned(),
+                    depth: context.variable_depths.get(name).copied().unwrap_or_default(),
+                })
+            })
+            .collect();
+
+        statement
+    }
+
+    /// The names of the variables the statement declares
+    fn declared(&self) -> impl Iterator<Item = &str> {
+        self.events.iter().filter_map(|event| match event {
+            Event::Declare(name, _) => Some(name.as_str()),
+            Event::Invoke(_) => None,
+        })
+    }
+
+    fn declares(&self) -> bool {
+        self.declared().next().is_some()
+    }
+
+    fn depends_on(&self, changed: &HashSet<String>) -> bool {
+        !changed.is_empty() && self.events.iter().any(|event| changed.contains(event.name()))
+    }
+
+    /// Applies the effects converting the statement had on the context
+    fn replay(&self, context: &mut ParseContext) {
+        for event in &self.events {
+            match event {
+                Event::Invoke(name) => context.mark_used(name),
+                Event::Declare(name, span) => context.declarations.push(Declaration {
+                    name: name.clone(),
+                    span: *span,
+                    used: false,
+                }),
+            }
+        }
+
+        context.warnings.extend(self.warnings.iter().cloned());
+
+        for variable in &self.variables {
+            match &variable.parameters {
+                Some(parameters) => context.parameters.insert(variable.name.clone(), parameters.clone()),
+                None => context.parameters.remove(&variable.name),
+            };
+            context.variable_depths.insert(variable.name.clone(), variable.depth);
+            context.variables.insert(variable.name.clone(), variable.ast.clone());
+        }
+    }
+
+    fn shifted_dependencies(&self, shift: &Shift) -> Vec<(String, Vec<(String, Span)>)> {
+        self.dependencies
+            .iter()
+            .map(|(name, invoked)| {
+                let invoked = invoked.iter().map(|(name, span)| (name.clone(), shift.span(*span)));
+                (name.clone(), invoked.collect())
+            })
+            .collect()
+    }
+
+    /// Moves the statement to where it is after the edit
+    fn shifted(self, shift: &mut Shift) -> Self {
+        let dependencies = self.shifted_dependencies(shift);
+
+        let events = self
+            .events
+            .into_iter()
+            .map(|event| match event {
+                Event::Declare(name, span) => Event::Declare(name, shift.span(span)),
+                event @ Event::Invoke(_) => event,
+            })
+            .collect();
+
+        let variables = self
+            .variables
+            .into_iter()
+            .map(|variable| Variable {
+                ast: Rc::new(fold(unshare(variable.ast), shift)),
+                ..variable
+            })
+            .collect();
+
+        Self {
+            span: shift.span(self.span),
+            node: shift.node(self.node),
+            events,
+            dependencies,
+            variables,
+            warnings: self
+                .warnings
+                .into_iter()
+                .map(|warning| shift.warning(warning))
+                .collect(),
+            failed: self.failed,
+            custom_symbols: self.custom_symbols,
+        }
+    }
+}
+
+impl Event {
+    fn name(&self) -> &str {
+        match self {
+            Self::Invoke(name) | Self::Declare(name, _) => name,
+        }
+    }
+}
+
+/// Lists the invocations and declarations of variables within a statement in the order they are converted,
+/// leaving out invocations of parameters
+fn variable_events(pair: Pair<'_, Rule>, parameters: &mut Vec<String>, events: &mut Vec<Event>) {
+    match pair.as_rule() {
+        Rule::variable_declaration => {
+            let declaration_span = span(&pair);
+            let mut inner = pair.into_inner();
+            let Some(identifier) = inner.next() else {
+                return;
+            };
+
+            let scope_length = parameters.len();
+            for pair in inner {
+                if pair.as_rule() == Rule::parameters {
+                    parameters.extend(pair.into_inner().map(|parameter| parameter.as_str().trim().to_owned()));
+                } else {
+                    variable_events(pair, parameters, events);
+                }
+            }
+            parameters.truncate(scope_length);
+
+            events.push(Event::Declare(identifier.as_str().trim().to_owned(), declaration_span));
+        }
+        Rule::variable_invocation => {
+            let mut inner = pair.into_inner();
+            let Some(identifier) = inner.next() else {
+                return;
+            };
+
+            // arguments are converted before the invocation itself
+            for pair in inner {
+                variable_events(pair, parameters, events);
+            }
+
+            let name = identifier.as_str();
+            if !parameters.iter().any(|parameter| parameter == name) {
+                events.push(Event::Invoke(name.to_owned()));
+            }
+        }
+        _ => {
+            for pair in pair.into_inner() {
+                variable_events(pair, parameters, events);
+            }
+        }
+    }
+}
+
+/// Whether a statement defines a custom symbol or contains a symbol that isn't built in
+fn has_custom_symbols(pair: &Pair<'_, Rule>) -> bool {
+    iter::once(pair.clone())
+        .chain(pair.clone().into_inner().flatten())
+        .any(|pair| match pair.as_rule() {
+            Rule::symbol_declaration => true,
+            Rule::symbol => last_inner(pair, "symbol").is_ok_and(|name| !is_builtin_symbol(name.as_str())),
+            _ => false,
+        })
+}
+
+impl Edit {
+    fn new(old: &str, new: &str) -> Self {
+        let start = old
+            .char_indices()
+            .zip(new.chars())
+            .find(|((_, old), new)| old != new)
+            .map_or(old.len().min(new.len()), |((index, _), _)| index);
+
+        let suffix: usize = old[start..]
+            .chars()
+            .rev()
+            .zip(new[start..].chars().rev())
+            .take_while(|(old, new)| old == new)
+            .map(|(char, _)| char.len_utf8())
+            .sum();
+
+        Self {
+            start,
+            old_end: old.len() - suffix,
+            new_end: new.len() - suffix,
+        }
+    }
+}
+
+/// A position in a source, with its line and column counted the way they are for spans
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Location {
+    offset: usize,
+    line: u32,
+    column: u32,
+}
+
+impl Location {
+    fn new(source: &str, offset: usize) -> Self {
+        let before = &source[..offset];
+        let line = before.bytes().filter(|byte| *byte == b'\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |position| position + 1);
+        let column = before[line_start..].chars().count() + 1;
+
+        Self {
+            offset,
+            line: u32::try_from(line).unwrap_or(u32::MAX),
+            column: u32::try_from(column).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+/// Moves the spans from the end of the edit in the previous source to the end of the edit in the new one
+struct Shift {
+    old: Location,
+    new: Location,
+}
+
+impl Shift {
+    fn span(&self, span: Span) -> Span {
+        // spans that don't point into the source (with a line of 0) are kept as well
+        if span.start < self.old.offset || span.line == 0 {
+            return span;
+        }
+
+        let column = if span.line == self.old.line {
+            span.column - self.old.column + self.new.column
+        } else {
+            span.column
+        };
+
+        Span {
+            start: span.start - self.old.offset + self.new.offset,
+            end: span.end - self.old.offset + self.new.offset,
+            line: span.line - self.old.line + self.new.line,
+            column,
+        }
+    }
+
+    fn node(&mut self, node: Spanned<ViableAstNode>) -> Spanned<ViableAstNode> {
+        let mut node = fold_node(self, node);
+        node.span = self.span(node.span);
+        node
+    }
+
+    fn warning(&self, warning: Warning) -> Warning {
+        match warning {
+            Warning::SingleCharacterRange { char, span } => Warning::SingleCharacterRange {
+                char,
+                span: self.span(span),
+            },
+            Warning::LazyExactQuantifier { span } => Warning::LazyExactQuantifier { span: self.span(span) },
+            Warning::RedundantQuantifier { span } => Warning::RedundantQuantifier { span: self.span(span) },
+            Warning::DeprecatedOver { replacement, span } => Warning::DeprecatedOver {
+                replacement,
+                span: self.span(span),
+            },
+            Warning::QuantifierKeyword { keyword, span } => Warning::QuantifierKeyword {
+                keyword,
+                span: self.span(span),
+            },
+            Warning::EmptyCapture { span } => Warning::EmptyCapture { span: self.span(span) },
+            Warning::UnusedVariable { name, span } => Warning::UnusedVariable {
+                name,
+                span: self.span(span),
+            },
+            Warning::ShadowedVariable {
+                name,
+                first_span,
+                second_span,
+            } => Warning::ShadowedVariable {
+                name,
+                first_span: self.span(first_span),
+                second_span: self.span(second_span),
+            },
+            Warning::CaptureInNegativeAssertion {
+                assertion_span,
+                capture_span,
+            } => Warning::CaptureInNegativeAssertion {
+                assertion_span: self.span(assertion_span),
+                capture_span: self.span(capture_span),
+            },
+            Warning::DuplicateCaptureName {
+                name,
+                first_span,
+                second_span,
+            } => Warning::DuplicateCaptureName {
+                name,
+                first_span: self.span(first_span),
+                second_span: self.span(second_span),
+            },
+        }
+    }
+}
+
+impl Transform for Shift {
+    fn transform_node(&mut self, node: Spanned<ViableAstNode>) -> Vec<Spanned<ViableAstNode>> {
+        vec![self.node(node)]
+    }
+
+    fn transform_expression(&mut self, expression: Spanned<Expression>) -> Spanned<Expression> {
+        let mut expression = fold_expression(self, expression);
+        expression.span = self.span(expression.span);
+        expression
+    }
+
+    fn transform_variable_invocation(&mut self, invocation: VariableInvocation, _span: Span) -> VariableInvocation {
+        let mut invocation = fold_variable_invocation(self, invocation);
+        invocation.span = self.span(invocation.span);
+        invocation.arguments = core::mem::take(&mut invocation.arguments)
+            .into_iter()
+            .map(|argument| self.node(argument))
+            .collect();
+        invocation
+    }
+}
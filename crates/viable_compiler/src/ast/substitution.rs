@@ -0,0 +1,59 @@
+use super::types::ast::{Expression, Group, Spanned, ViableAst, ViableAstNode};
+use super::utils::unshare;
+use crate::prelude::*;
+use crate::types::HashMap;
+use alloc::rc::Rc;
+
+/// Replaces the parameters of a parameterized variable's statements with the arguments bound to them
+pub fn substitute_parameters(ast: ViableAst, bindings: &HashMap<&str, &Spanned<ViableAstNode>>) -> ViableAst {
+    match ast {
+        ViableAst::Root(nodes) => {
+            ViableAst::Root(nodes.into_iter().map(|node| substitute_node(node, bindings)).collect())
+        }
+        ViableAst::Empty => ViableAst::Empty,
+    }
+}
+
+fn substitute_node(
+    node: Spanned<ViableAstNode>,
+    bindings: &HashMap<&str, &Spanned<ViableAstNode>>,
+) -> Spanned<ViableAstNode> {
+    let Spanned { node, span } = node;
+
+    let node = match node {
+        ViableAstNode::Parameter(name) => match bindings.get(&*name) {
+            Some(argument) => return (*argument).clone(),
+            None => ViableAstNode::Parameter(name),
+        },
+        ViableAstNode::Group(group) => ViableAstNode::Group(substitute_group(group, bindings)),
+        ViableAstNode::Assertion(mut assertion) => {
+            assertion.statements = Box::new(substitute_parameters(*assertion.statements, bindings));
+            ViableAstNode::Assertion(assertion)
+        }
+        ViableAstNode::Conditional(mut conditional) => {
+            conditional.then = Box::new(substitute_parameters(*conditional.then, bindings));
+            conditional.otherwise = conditional
+                .otherwise
+                .map(|otherwise| Box::new(substitute_parameters(*otherwise, bindings)));
+            ViableAstNode::Conditional(conditional)
+        }
+        ViableAstNode::VariableInvocation(mut invocation) => {
+            invocation.statements = Rc::new(substitute_parameters(unshare(invocation.statements), bindings));
+            ViableAstNode::VariableInvocation(invocation)
+        }
+        ViableAstNode::Quantifier(mut quantifier) => {
+            if let Expression::Group(group) = quantifier.expression.node {
+                quantifier.expression.node = Expression::Group(substitute_group(group, bindings));
+            }
+            ViableAstNode::Quantifier(quantifier)
+        }
+        node => node,
+    };
+
+    Spanned { node, span }
+}
+
+fn substitute_group(mut group: Group, bindings: &HashMap<&str, &Spanned<ViableAstNode>>) -> Group {
+    group.statements = Box::new(substitute_parameters(*group.statements, bindings));
+    group
+}
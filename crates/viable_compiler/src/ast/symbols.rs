@@ -1,21 +1,38 @@
-use super::consts::{NOT, SYMBOL_NAMESPACE_DELIMITER};
-use super::types::ast::{ViableAstNode, SpecialSymbolKind, Symbol, SymbolKind, UnicodeCategory, UnicodeCategoryKind};
-use super::utils::first_last_inner_str;
+use super::consts::{NOT, SYMBOL_NAMES, SYMBOL_NAMESPACE_DELIMITER, UNICODE_BINARY_PROPERTIES, UNICODE_SCRIPTS};
+use super::types::ast::{
+    SpecialSymbolKind, Symbol, SymbolKind, UnicodeCategory, UnicodeCategoryKind, UnicodeProperty, UnicodePropertyKind,
+    ViableAstNode,
+};
+use super::utils::{closest_match, first_last_inner_str};
 use crate::ast::types::pest::Rule;
 use crate::errors::CompilerError;
+use crate::prelude::*;
 use crate::types::Result;
 use pest::iterators::Pair;
 
 pub fn symbol(pair: Pair<'_, Rule>) -> Result<ViableAstNode> {
-    let (negative, ident) = first_last_inner_str(pair)?;
+    let (negative, ident) = first_last_inner_str(pair, "symbol")?;
 
-    let negative = negative == NOT;
+    builtin_symbol(ident, negative == NOT)
+}
+
+/// Whether `name` is taken by a built in symbol, which custom symbols can't redefine, names of a namespace
+/// (e.g. `category::letter`) are all reserved
+pub fn is_builtin_symbol(name: &str) -> bool {
+    name.contains(SYMBOL_NAMESPACE_DELIMITER) || builtin_symbol(name, false).is_ok()
+}
 
+#[allow(clippy::too_many_lines)]
+fn builtin_symbol(ident: &str, negative: bool) -> Result<ViableAstNode> {
     if negative {
         match ident {
-            "start" => return Err(CompilerError::NegativeStartNotAllowed),
-            "end" => return Err(CompilerError::NegativeEndNotAllowed),
+            "start" | "line_start" | "input_start" => {
+                return Err(CompilerError::NegativeStartNotAllowed(ident.to_owned()))
+            }
+            "end" | "line_end" | "input_end" => return Err(CompilerError::NegativeEndNotAllowed(ident.to_owned())),
             "char" => return Err(CompilerError::NegativeCharNotAllowed),
+            "grapheme" => return Err(CompilerError::NegativeGraphemeNotAllowed),
+            "line_break" => return Err(CompilerError::NegativeLineBreakNotAllowed),
             _ => {}
         }
     }
@@ -23,6 +40,8 @@ pub fn symbol(pair: Pair<'_, Rule>) -> Result<ViableAstNode> {
     if let Some((namespace, namespaced_ident)) = ident.split_once(SYMBOL_NAMESPACE_DELIMITER) {
         return match namespace {
             "category" => unicode_category(namespaced_ident, negative),
+            "script" => unicode_property(namespaced_ident, UnicodePropertyKind::Script, negative),
+            "property" => unicode_property(namespaced_ident, UnicodePropertyKind::Binary, negative),
             _ => return Err(CompilerError::UnrecognizedSymbolNamespace),
         };
     }
@@ -37,7 +56,7 @@ pub fn symbol(pair: Pair<'_, Rule>) -> Result<ViableAstNode> {
             kind: SymbolKind::Newline,
             negative,
         }),
-        "vertical" => ViableAstNode::Symbol(Symbol {
+        "vertical" | "vertical_tab" => ViableAstNode::Symbol(Symbol {
             kind: SymbolKind::Vertical,
             negative,
         }),
@@ -53,10 +72,6 @@ pub fn symbol(pair: Pair<'_, Rule>) -> Result<ViableAstNode> {
             kind: SymbolKind::Whitespace,
             negative,
         }),
-        "boundary" => ViableAstNode::Symbol(Symbol {
-            kind: SymbolKind::Boundary,
-            negative,
-        }),
         "alphabetic" => ViableAstNode::Symbol(Symbol {
             kind: SymbolKind::Alphabetic,
             negative,
@@ -65,7 +80,7 @@ pub fn symbol(pair: Pair<'_, Rule>) -> Result<ViableAstNode> {
             kind: SymbolKind::Alphanumeric,
             negative,
         }),
-        "return" => ViableAstNode::Symbol(Symbol {
+        "return" | "carriage_return" => ViableAstNode::Symbol(Symbol {
             kind: SymbolKind::Return,
             negative,
         }),
@@ -77,7 +92,7 @@ pub fn symbol(pair: Pair<'_, Rule>) -> Result<ViableAstNode> {
             kind: SymbolKind::Null,
             negative,
         }),
-        "feed" => ViableAstNode::Symbol(Symbol {
+        "feed" | "form_feed" => ViableAstNode::Symbol(Symbol {
             kind: SymbolKind::Feed,
             negative,
         }),
@@ -89,12 +104,34 @@ pub fn symbol(pair: Pair<'_, Rule>) -> Result<ViableAstNode> {
             kind: SymbolKind::Backspace,
             negative,
         }),
+        "grapheme" => ViableAstNode::Symbol(Symbol {
+            kind: SymbolKind::Grapheme,
+            negative,
+        }),
+        "line_break" => ViableAstNode::Symbol(Symbol {
+            kind: SymbolKind::LineBreak,
+            negative,
+        }),
 
         // special symbols
         "start" => ViableAstNode::SpecialSymbol(SpecialSymbolKind::Start),
         "end" => ViableAstNode::SpecialSymbol(SpecialSymbolKind::End),
+        "line_start" => ViableAstNode::SpecialSymbol(SpecialSymbolKind::LineStart),
+        "line_end" => ViableAstNode::SpecialSymbol(SpecialSymbolKind::LineEnd),
+        "input_start" => ViableAstNode::SpecialSymbol(SpecialSymbolKind::InputStart),
+        "input_end" => ViableAstNode::SpecialSymbol(SpecialSymbolKind::InputEnd),
+        "word_boundary" | "boundary" => ViableAstNode::SpecialSymbol(if negative {
+            SpecialSymbolKind::NotWordBoundary
+        } else {
+            SpecialSymbolKind::WordBoundary
+        }),
 
-        _ => return Err(CompilerError::UnrecognizedSymbol),
+        _ => {
+            return Err(CompilerError::UnrecognizedSymbol {
+                name: ident.to_owned(),
+                suggestion: closest_match(ident, SYMBOL_NAMES.iter().copied()).map(str::to_owned),
+            })
+        }
     };
 
     Ok(symbol_node)
@@ -261,3 +298,24 @@ fn unicode_category(ident: &str, negative: bool) -> Result<ViableAstNode> {
 
     Ok(unicode_group_node)
 }
+
+fn unicode_property(ident: &str, kind: UnicodePropertyKind, negative: bool) -> Result<ViableAstNode> {
+    let names = match kind {
+        UnicodePropertyKind::Script => UNICODE_SCRIPTS,
+        UnicodePropertyKind::Binary => UNICODE_BINARY_PROPERTIES,
+    };
+
+    let Some(name) = names.iter().find(|name| name.to_lowercase() == ident) else {
+        let lowercase_names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+        return Err(CompilerError::UnknownUnicodeProperty {
+            name: ident.to_owned(),
+            suggestion: closest_match(ident, lowercase_names.iter().map(String::as_str)).map(str::to_owned),
+        });
+    };
+
+    Ok(ViableAstNode::UnicodeProperty(UnicodeProperty {
+        kind,
+        name: (*name).into(),
+        negative,
+    }))
+}
@@ -0,0 +1,173 @@
+use super::types::ast::{
+    Assertion, Conditional, Expression, Group, Quantifier, Span, Spanned, VariableInvocation, ViableAst, ViableAstNode,
+};
+use super::utils::unshare;
+use crate::prelude::*;
+use alloc::rc::Rc;
+
+/**
+Rewrites an AST with a [`Transform`], see [`Transform::transform_node`] for replacing or removing statements.
+
+# Example
+
+```rust
+use std::rc::Rc;
+use viable_compiler::ast::types::ast::Span;
+use viable_compiler::ast::{ast_to_source, fold, to_ast, Transform};
+use viable_compiler::options::FormatOptions;
+
+struct Uppercase;
+
+impl Transform for Uppercase {
+    fn transform_atom(&mut self, atom: Rc<str>, _span: Span) -> Rc<str> {
+        atom.to_uppercase().into()
+    }
+}
+
+let ast = fold(to_ast(r#""a"; some of "b";"#).unwrap(), &mut Uppercase);
+
+assert_eq!(ast_to_source(&ast, &FormatOptions::default()), "\"A\";\nsome of \"B\";\n");
+```
+*/
+pub fn fold(ast: ViableAst, transform: &mut impl Transform) -> ViableAst {
+    transform.transform_ast(ast)
+}
+
+/// A rewrite of an AST, taking each node by value and returning the node to replace it with
+///
+/// Every method defaults to rebuilding its node from its transformed children (through the matching `fold_*`
+/// function), so implementors only override the nodes they rewrite and call the `fold_*` function themselves
+/// to keep transforming the children. Quantified expressions go through the same methods as statements.
+pub trait Transform {
+    /// Whether to transform the expanded statements of variable invocations
+    fn transform_invocation_statements(&self) -> bool {
+        true
+    }
+
+    fn transform_ast(&mut self, ast: ViableAst) -> ViableAst {
+        fold_ast(self, ast)
+    }
+
+    /// Transforms a statement into the statements replacing it, several to split it and none (or a
+    /// [`ViableAstNode::Skip`]) to remove it
+    fn transform_node(&mut self, node: Spanned<ViableAstNode>) -> Vec<Spanned<ViableAstNode>> {
+        vec![fold_node(self, node)]
+    }
+
+    fn transform_group(&mut self, group: Group, _span: Span) -> Group {
+        fold_group(self, group)
+    }
+
+    fn transform_assertion(&mut self, assertion: Assertion, _span: Span) -> Assertion {
+        fold_assertion(self, assertion)
+    }
+
+    fn transform_conditional(&mut self, conditional: Conditional, _span: Span) -> Conditional {
+        fold_conditional(self, conditional)
+    }
+
+    fn transform_quantifier(&mut self, quantifier: Quantifier, _span: Span) -> Quantifier {
+        fold_quantifier(self, quantifier)
+    }
+
+    fn transform_expression(&mut self, expression: Spanned<Expression>) -> Spanned<Expression> {
+        fold_expression(self, expression)
+    }
+
+    fn transform_variable_invocation(&mut self, invocation: VariableInvocation, _span: Span) -> VariableInvocation {
+        fold_variable_invocation(self, invocation)
+    }
+
+    fn transform_atom(&mut self, atom: Rc<str>, _span: Span) -> Rc<str> {
+        atom
+    }
+}
+
+pub fn fold_ast<T: Transform + ?Sized>(transform: &mut T, ast: ViableAst) -> ViableAst {
+    let ViableAst::Root(nodes) = ast else {
+        return ViableAst::Empty;
+    };
+
+    let mut transformed = Vec::with_capacity(nodes.len());
+
+    for node in nodes {
+        transformed.extend(transform.transform_node(node));
+    }
+
+    ViableAst::Root(transformed)
+}
+
+pub fn fold_node<T: Transform + ?Sized>(transform: &mut T, node: Spanned<ViableAstNode>) -> Spanned<ViableAstNode> {
+    let Spanned { node, span } = node;
+
+    let node = match node {
+        ViableAstNode::Group(group) => ViableAstNode::Group(transform.transform_group(group, span)),
+        ViableAstNode::Assertion(assertion) => ViableAstNode::Assertion(transform.transform_assertion(assertion, span)),
+        ViableAstNode::Conditional(conditional) => {
+            ViableAstNode::Conditional(transform.transform_conditional(conditional, span))
+        }
+        ViableAstNode::Quantifier(quantifier) => {
+            ViableAstNode::Quantifier(transform.transform_quantifier(quantifier, span))
+        }
+        ViableAstNode::VariableInvocation(invocation) => {
+            ViableAstNode::VariableInvocation(transform.transform_variable_invocation(invocation, span))
+        }
+        ViableAstNode::Atom(atom) => ViableAstNode::Atom(transform.transform_atom(atom, span)),
+        node => node,
+    };
+
+    Spanned { node, span }
+}
+
+pub fn fold_group<T: Transform + ?Sized>(transform: &mut T, mut group: Group) -> Group {
+    group.statements = Box::new(transform.transform_ast(*group.statements));
+    group
+}
+
+pub fn fold_assertion<T: Transform + ?Sized>(transform: &mut T, mut assertion: Assertion) -> Assertion {
+    assertion.statements = Box::new(transform.transform_ast(*assertion.statements));
+    assertion
+}
+
+pub fn fold_conditional<T: Transform + ?Sized>(transform: &mut T, mut conditional: Conditional) -> Conditional {
+    conditional.then = Box::new(transform.transform_ast(*conditional.then));
+    conditional.otherwise = conditional
+        .otherwise
+        .map(|otherwise| Box::new(transform.transform_ast(*otherwise)));
+    conditional
+}
+
+pub fn fold_quantifier<T: Transform + ?Sized>(transform: &mut T, mut quantifier: Quantifier) -> Quantifier {
+    quantifier.expression = Box::new(transform.transform_expression(*quantifier.expression));
+    quantifier
+}
+
+pub fn fold_expression<T: Transform + ?Sized>(
+    transform: &mut T,
+    expression: Spanned<Expression>,
+) -> Spanned<Expression> {
+    let Spanned { node, span } = expression;
+
+    let node = match node {
+        Expression::Group(group) => Expression::Group(transform.transform_group(group, span)),
+        Expression::Atom(atom) => Expression::Atom(transform.transform_atom(atom, span)),
+        node => node,
+    };
+
+    Spanned { node, span }
+}
+
+/// Transforms the expanded statements of an invocation, unless [`Transform::transform_invocation_statements`]
+/// is disabled
+///
+/// Statements shared with other invocations of the variable are copied, so that each invocation is transformed
+/// on its own
+pub fn fold_variable_invocation<T: Transform + ?Sized>(
+    transform: &mut T,
+    mut invocation: VariableInvocation,
+) -> VariableInvocation {
+    if transform.transform_invocation_statements() {
+        invocation.statements = Rc::new(transform.transform_ast(unshare(invocation.statements)));
+    }
+    invocation
+}
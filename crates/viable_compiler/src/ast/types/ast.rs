@@ -1,90 +1,267 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::ast::utils::escape_char;
+use crate::prelude::*;
+use alloc::rc::Rc;
+
+/// The location of a construct in the source it was parsed from
+///
+/// `start` and `end` are byte offsets, `line` and `column` are 1-based and refer to `start`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// A node paired with the [`Span`] of the source it was parsed from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+/// A matching mode that changes how the rest of a pattern is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Flag {
+    /// `insensitive`, matches letters regardless of case
+    Insensitive,
+    /// `multiline`, makes line anchors match at every line break
+    Multiline,
+    /// `dot_all`, makes `<char>` match line breaks
+    DotAll,
+    /// `unicode`, matches by code point rather than by code unit
+    Unicode,
+}
+
+impl Flag {
+    /// The name of the flag in a `flags` block, e.g. `dot_all`
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Insensitive => "insensitive",
+            Self::Multiline => "multiline",
+            Self::DotAll => "dot_all",
+            Self::Unicode => "unicode",
+        }
+    }
+}
+
+/// The flags declared by a `flags { ... }` block at the start of a source file
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatternFlags {
+    /// flags in declaration order
+    pub flags: Vec<Flag>,
+    /// the span of the `flags` block, empty if the source has none
+    pub span: Span,
+}
+
+impl PatternFlags {
+    #[must_use]
+    pub fn contains(&self, flag: Flag) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum GroupKind {
     Match,
     Capture,
     Either,
+    Atomic,
+    /// sets and clears flags for the extent of the group
+    Flagged {
+        set: Vec<Flag>,
+        clear: Vec<Flag>,
+    },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AssertionKind {
     Ahead,
     Behind,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum QuantifierKind {
-    Range { start: String, end: String },
+    Range { start: usize, end: usize },
     Some,
     Any,
     Over(usize),
+    AtLeast(usize),
+    AtMost(usize),
     Option,
-    Amount(String),
+    Amount(usize),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quantifier {
     pub kind: QuantifierKind,
     pub lazy: bool,
-    pub expression: Box<Expression>,
+    pub possessive: bool,
+    /// the unit written after the amount (e.g. `5 bytes of`), `None` to count in the unit of the pattern
+    pub unit: Option<QuantifierUnit>,
+    pub expression: Box<Spanned<Expression>>,
+}
+
+/// What the repetitions of a quantifier match, for targets matching byte strings (see
+/// [`crate::target::Target::matches_bytes`]) where a pattern may switch between bytes and code points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuantifierUnit {
+    /// `chars`, each repetition matches code points
+    Chars,
+    /// `bytes`, each repetition matches bytes
+    Bytes,
+}
+
+impl QuantifierUnit {
+    /// The name of the unit following the amount of a quantifier, e.g. `bytes`
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Chars => "chars",
+            Self::Bytes => "bytes",
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum Expression {
-    Atom(String),
+    Atom(Rc<str>),
     Group(Group),
     Symbol(Symbol),
     UnicodeCategory(UnicodeCategory),
+    UnicodeProperty(UnicodeProperty),
     Range(Range),
-    NegativeCharClass(String),
+    /// `not abc;`, holds only chars and char ranges
+    NegativeCharClass(Vec<ClassItem>),
+    CharClass(CharClass),
+    Backreference(Backreference),
 }
 
-#[derive(Debug, Clone)]
+/// A bracketed class combining ranges, characters and symbols, e.g. `class { a to f; "_"; <space>; }`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CharClass {
+    pub negative: bool,
+    pub items: Vec<ClassItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum ClassItem {
+    Range(Range),
+    Char(char),
+    /// inserted into the class as is
+    Raw(Rc<str>),
+    Symbol(Symbol),
+    UnicodeCategory(UnicodeCategory),
+    UnicodeProperty(UnicodeProperty),
+    /// `without { ... }`, removes the characters of its items from the class
+    Subtract(Vec<ClassItem>),
+    /// `and { ... }`, keeps only the characters of the class also matched by its items
+    Intersect(Vec<ClassItem>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum Range {
-    AsciiRange(AsciiRange),
+    CharRange(CharRange),
     NumericRange(NumericRange),
 }
 
-#[derive(Debug, Clone)]
+/// A range between two characters of any script, e.g. `a to z` or `à to ÿ`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
-pub struct AsciiRange {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CharRange {
     pub negative: bool,
     pub start: char,
     pub end: char,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NumericRange {
     pub negative: bool,
     pub start: char,
     pub end: char,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Symbol {
     pub kind: SymbolKind,
     pub negative: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnicodeCategory {
     pub kind: UnicodeCategoryKind,
     pub negative: bool,
 }
 
-#[derive(Debug, Clone)]
+/// A unicode script or binary property, e.g. `<script::greek>` or `<property::alphabetic>`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnicodeProperty {
+    pub kind: UnicodePropertyKind,
+    /// the canonical name of the script or property (e.g. `Greek`)
+    pub name: Rc<str>,
+    pub negative: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnicodePropertyKind {
+    Script,
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SymbolKind {
     Space,
     Newline,
@@ -101,10 +278,37 @@ pub enum SymbolKind {
     Feed,
     Backspace,
     Boundary,
+    /// an extended grapheme cluster, e.g. a letter followed by its combining marks or an emoji sequence
+    Grapheme,
+    /// a line break in any of the common conventions, `\r\n`, `\r` or `\n`
+    LineBreak,
+}
+
+impl SymbolKind {
+    /// Whether the symbol can be placed within a class, bracket expressions can only contain single characters
+    /// and the negated shorthand classes
+    #[must_use]
+    pub const fn is_class_compatible(&self, negative: bool) -> bool {
+        match self {
+            Self::Whitespace | Self::Digit | Self::Word => true,
+            Self::Space
+            | Self::Newline
+            | Self::Vertical
+            | Self::Return
+            | Self::Tab
+            | Self::Null
+            | Self::Alphabetic
+            | Self::Alphanumeric
+            | Self::Feed
+            | Self::Backspace => !negative,
+            Self::Char | Self::Boundary | Self::Grapheme | Self::LineBreak => false,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnicodeCategoryKind {
     CasedLetter,
     ClosePunctuation,
@@ -146,57 +350,198 @@ pub enum UnicodeCategoryKind {
     UppercaseLetter,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SpecialSymbolKind {
     Start,
     End,
+    /// `<line_start>`, the start of a line when the `multiline` flag is set
+    LineStart,
+    /// `<line_end>`, the end of a line when the `multiline` flag is set
+    LineEnd,
+    /// `<input_start>`, the start of the input regardless of flags
+    InputStart,
+    /// `<input_end>`, the very end of the input regardless of flags
+    InputEnd,
+    /// `<word_boundary>`
+    WordBoundary,
+    /// `not <word_boundary>`
+    NotWordBoundary,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Group {
-    pub ident: Option<String>,
+    pub ident: Option<Rc<str>>,
     pub kind: GroupKind,
     pub statements: Box<ViableAst>,
 }
 
-#[derive(Debug, Clone)]
+/// An invocation of a variable, holding the variable's expanded statements
+///
+/// Non-exhaustive so that invocations can carry more information without breaking code matching on them
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
+#[non_exhaustive]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VariableInvocation {
-    pub statements: Box<ViableAst>,
+    /// the name of the invoked variable, without the leading `.`
+    pub name: Rc<str>,
+    /// the span of the invocation itself (e.g. `.name`), the spans of the statements point into the declaration
+    pub span: Span,
+    /// the statements of the variable, with its parameters substituted by the arguments
+    ///
+    /// Invocations of a variable without parameters share its statements rather than each holding a copy
+    pub statements: Rc<ViableAst>,
+    pub arguments: Vec<Spanned<ViableAstNode>>,
+    /// the declaration of a parameterized variable the arguments were substituted into, `None` for variables
+    /// without parameters whose `statements` are those of the declaration
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub declaration: Option<Rc<ParameterizedDeclaration>>,
+}
+
+/// The parameters and statements of a parameterized variable as declared, shared by its invocations
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParameterizedDeclaration {
+    /// the names of the parameters, without the leading `.`, in declaration order
+    pub parameters: Vec<Rc<str>>,
+    /// the statements of the variable, with a [`ViableAstNode::Parameter`] wherever a parameter is used
+    pub statements: Rc<ViableAst>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Assertion {
     pub kind: AssertionKind,
     pub statements: Box<ViableAst>,
     pub negative: bool,
 }
 
-#[derive(Debug, Clone)]
+/// `if captured name { ... } else { ... }`, matching `then` if the capture group took part in the match so far,
+/// and `otherwise` (or the empty string without an `else`) if it didn't
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conditional {
+    /// the capture group, by name or by index as in `if captured 1 { ... }`
+    pub condition: Backreference,
+    pub then: Box<ViableAst>,
+    pub otherwise: Option<Box<ViableAst>>,
+}
+
+/// `recurse;` or `recurse name;`, matching the whole pattern or the pattern of a capture group again at the
+/// position of the statement
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum Recursion {
+    Pattern,
+    /// recurses into a named capture group, which may be declared before or after the statement
+    Group(Rc<str>),
+}
+
+/// A reference to the text matched by a capture group
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum Backreference {
+    /// refers to a previously declared named capture group
+    Named(Rc<str>),
+    /// refers to a capture group by its 1-based index in the expanded pattern
+    Numbered(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum ViableAstNode {
     Group(Group),
     Assertion(Assertion),
+    Conditional(Conditional),
     Quantifier(Quantifier),
-    Atom(String),
+    Atom(Rc<str>),
     Range(Range),
     Symbol(Symbol),
     SpecialSymbol(SpecialSymbolKind),
     UnicodeCategory(UnicodeCategory),
-    NegativeCharClass(String),
+    UnicodeProperty(UnicodeProperty),
+    /// `not abc;`, holds only chars and char ranges
+    NegativeCharClass(Vec<ClassItem>),
+    CharClass(CharClass),
     VariableInvocation(VariableInvocation),
+    Backreference(Backreference),
+    Recursion(Recursion),
+    /// a parameter within the declaration of a parameterized variable, replaced by an argument when invoked
+    Parameter(Rc<str>),
+    /// `nothing;`, a branch of an `either` group that matches the empty string
+    Empty,
+    /// `raw_regex "...";`, a regex spliced into the pattern as written, whose groups are checked to be balanced
+    RawRegex(Rc<str>),
+    /// a statement matching nothing, e.g. one removed by a [`crate::ast::Transform`], ASTs converted from
+    /// source never contain one
     Skip,
+    /// a statement that failed to convert, only found in the partial ASTs of [`crate::ast::to_ast_lenient`]
+    Error,
 }
 
-#[derive(Debug, Clone)]
+/// A parsed (and expanded) Viable source
+///
+/// The strings of the AST (atoms, names, ...) are shared `Rc<str>`s, a string repeated in the source is
+/// allocated once and held by every node it appears in.
+///
+/// With the `serde` feature the AST can be serialized. Enums holding data are adjacently tagged, e.g.
+/// `{ "type": "Atom", "value": "a" }` or `{ "type": "Skip" }`, enums without data are serialized as the name of
+/// the variant (e.g. `"Digit"`) and structs as maps of their fields. Deserializing does not validate the AST,
+/// invalid combinations are only rejected when the AST is compiled.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "fuzzer", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum ViableAst {
-    Root(Vec<ViableAstNode>),
+    Root(Vec<Spanned<ViableAstNode>>),
     Empty,
 }
+
+impl ViableAstNode {
+    /// A negative class of the characters of `class`, each matched literally
+    #[deprecated(note = "construct `ViableAstNode::NegativeCharClass` from class items instead")]
+    #[must_use]
+    pub fn negative_char_class(class: &str) -> Self {
+        Self::NegativeCharClass(class.chars().map(ClassItem::Char).collect())
+    }
+}
+
+impl ViableAst {
+    /// An AST matching `text` literally, special characters are escaped
+    #[must_use]
+    pub fn atom(text: &str) -> Self {
+        Self::from_nodes(vec![ViableAstNode::Atom(
+            text.chars().map(escape_char).collect::<String>().into(),
+        )])
+    }
+
+    /// An AST of the given nodes in order, each with an empty span as they have no source
+    #[must_use]
+    pub fn from_nodes(nodes: Vec<ViableAstNode>) -> Self {
+        Self::Root(
+            nodes
+                .into_iter()
+                .map(|node| Spanned {
+                    node,
+                    span: Span::default(),
+                })
+                .collect(),
+        )
+    }
+}
@@ -1,74 +1,590 @@
+use super::consts::BLOCK_KEYWORDS;
+use super::types::ast::{Span, Spanned, ViableAst};
 use super::types::pest::Rule;
 use crate::errors::CompilerError;
-use crate::types::Result;
-use once_cell::sync::Lazy;
+use crate::prelude::*;
+use crate::types::{HashMap, HashSet, Result};
+use alloc::borrow::Cow;
+use alloc::rc::Rc;
+use core::iter::Peekable;
+use core::str::Chars;
+use pest::error::{Error, ErrorVariant, InputLocation, LineColLocation};
 use pest::iterators::Pair;
-use std::collections::HashSet;
 
-pub fn first_inner(pair: Pair<'_, Rule>) -> Result<Pair<'_, Rule>> {
-    let last = pair.into_inner().next().ok_or(CompilerError::MissingNode)?;
+/// The error for a pair lacking a child the grammar guarantees, `context` naming the construct being parsed
+pub const fn missing_node(context: &'static str, span: Span) -> CompilerError {
+    CompilerError::MissingNode { context, span }
+}
 
-    Ok(last)
+pub fn first_inner<'source>(pair: Pair<'source, Rule>, context: &'static str) -> Result<Pair<'source, Rule>> {
+    let span = span(&pair);
+    let first = pair.into_inner().next().ok_or(missing_node(context, span))?;
+
+    Ok(first)
 }
 
-pub fn last_inner(pair: Pair<'_, Rule>) -> Result<Pair<'_, Rule>> {
-    let last = pair.into_inner().next_back().ok_or(CompilerError::MissingNode)?;
+pub fn last_inner<'source>(pair: Pair<'source, Rule>, context: &'static str) -> Result<Pair<'source, Rule>> {
+    let span = span(&pair);
+    let last = pair.into_inner().next_back().ok_or(missing_node(context, span))?;
 
     Ok(last)
 }
 
-pub fn first_last_inner_str(pair: Pair<'_, Rule>) -> Result<(&str, &str)> {
-    let pairs: Vec<Pair<'_, Rule>> = pair.into_inner().collect();
+pub fn nth_inner<'source>(
+    pair: Pair<'source, Rule>,
+    index: usize,
+    context: &'static str,
+) -> Result<Pair<'source, Rule>> {
+    let span = span(&pair);
+    let nth = pair.into_inner().nth(index).ok_or(missing_node(context, span))?;
+
+    Ok(nth)
+}
+
+/// The children of a pair, for reading several of them without cloning the pair
+pub fn inner_children(pair: Pair<'_, Rule>) -> Vec<Pair<'_, Rule>> {
+    pair.into_inner().collect()
+}
+
+pub fn first_last_inner_str<'source>(
+    pair: Pair<'source, Rule>,
+    context: &'static str,
+) -> Result<(&'source str, &'source str)> {
+    let span = span(&pair);
+    let pairs = inner_children(pair);
     Ok((
-        pairs.first().ok_or(CompilerError::MissingNode)?.as_str(),
-        pairs.last().ok_or(CompilerError::MissingNode)?.as_str(),
+        pairs.first().ok_or(missing_node(context, span))?.as_str(),
+        pairs.last().ok_or(missing_node(context, span))?.as_str(),
     ))
 }
 
-pub fn nth_inner(pair: Pair<'_, Rule>, n: usize) -> Option<Pair<'_, Rule>> {
-    pair.into_inner().nth(n)
+pub fn span(pair: &Pair<'_, Rule>) -> Span {
+    let pest_span = pair.as_span();
+    let (line, column) = pair.line_col();
+
+    Span {
+        start: pest_span.start(),
+        end: pest_span.end(),
+        line: u32::try_from(line).unwrap_or(u32::MAX),
+        column: u32::try_from(column).unwrap_or(u32::MAX),
+    }
+}
+
+pub fn parse_error(error: &Error<Rule>, source: &str) -> Spanned<CompilerError> {
+    let expected = match &error.variant {
+        // the rules recovering from a missing `;` match wherever a statement may start, so aren't worth listing
+        ErrorVariant::ParsingError { positives, .. } => positives
+            .iter()
+            .filter(|rule| !matches!(rule, Rule::unterminated_atom | Rule::unterminated_class_item))
+            .map(|rule| format!("{rule:?}"))
+            .collect(),
+        ErrorVariant::CustomError { message } => vec![message.clone()],
+    };
+
+    let (offset, end) = match error.location {
+        InputLocation::Pos(offset) => (offset, offset),
+        InputLocation::Span(span) => span,
+    };
+
+    let (line, column) = match error.line_col {
+        LineColLocation::Pos(line_col) | LineColLocation::Span(line_col, _) => line_col,
+    };
+
+    let found = source
+        .get(offset..)
+        .unwrap_or_default()
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_owned();
+
+    Spanned {
+        node: CompilerError::ParseError {
+            expected,
+            suggestion: block_keyword_suggestion(source, offset),
+            found,
+            line,
+            column,
+            offset,
+        },
+        span: Span {
+            start: offset,
+            end,
+            line: u32::try_from(line).unwrap_or(u32::MAX),
+            column: u32::try_from(column).unwrap_or(u32::MAX),
+        },
+    }
 }
 
-pub fn to_char(value: &str) -> Result<char> {
-    let char = value.chars().next().ok_or(CompilerError::MissingNode)?;
+/// The group or assertion keyword a misspelled word at `offset` is likely meant to be, if the word is followed by
+/// a block (e.g. `captur { ... }`) possibly after a capture name. A failed `not ahaed { ... }` stops at the block
+/// after the word rather than at the word itself
+fn block_keyword_suggestion(source: &str, offset: usize) -> Option<String> {
+    let rest = source.get(offset..).unwrap_or_default();
+    let is_word_char = |char: char| char.is_ascii_alphabetic();
+
+    let word = if rest.trim_start().starts_with('{') {
+        let before = source.get(..offset).unwrap_or_default().trim_end();
+        &before[before.trim_end_matches(is_word_char).len()..]
+    } else {
+        let word = &rest[..rest.find(|char| !is_word_char(char)).unwrap_or(rest.len())];
+        let before_block = rest[word.len()..].split([';', '}']).next().unwrap_or_default();
+        if !before_block.contains('{') {
+            return None;
+        }
+        word
+    };
+
+    if word.is_empty() || BLOCK_KEYWORDS.contains(&word) {
+        return None;
+    }
+
+    closest_match(word, BLOCK_KEYWORDS.iter().copied()).map(str::to_owned)
+}
+
+/// Checks that blocks and block comments aren't nested deeper than `limit`, before the source is parsed since the
+/// rules of the grammar recurse for every level of nesting
+///
+/// Braces within literals and comments aren't counted, the error points at the brace (or `/*`) that goes past
+/// the limit
+pub fn check_nesting(source: &str, limit: usize) -> core::result::Result<(), Spanned<CompilerError>> {
+    let bytes = source.as_bytes();
+    let mut blocks = 0usize;
+    let mut comments = 0usize;
+    let mut index = 0;
+
+    while let Some(&byte) = bytes.get(index) {
+        let start = index;
+        let rest = &bytes[index..];
+        let mut opened = false;
+
+        if rest.starts_with(b"/*") {
+            comments += 1;
+            opened = true;
+            index += 2;
+        } else if comments > 0 {
+            if rest.starts_with(b"*/") {
+                comments -= 1;
+                index += 2;
+            } else {
+                index += 1;
+            }
+        } else if rest.starts_with(b"//") {
+            index += rest.iter().position(|&byte| byte == b'\n').unwrap_or(rest.len());
+        } else {
+            match byte {
+                b'"' | b'\'' | b'`' => index += quoted_length(rest),
+                b'{' => {
+                    blocks += 1;
+                    opened = true;
+                    index += 1;
+                }
+                b'}' => {
+                    blocks = blocks.saturating_sub(1);
+                    index += 1;
+                }
+                _ => index += 1,
+            }
+        }
+
+        if opened && blocks + comments > limit {
+            let (line, column) = pest::Position::new(source, start).map_or((0, 0), |position| position.line_col());
+
+            return Err(Spanned {
+                node: CompilerError::NestingTooDeep { limit },
+                span: Span {
+                    start,
+                    end: index,
+                    line: u32::try_from(line).unwrap_or(u32::MAX),
+                    column: u32::try_from(column).unwrap_or(u32::MAX),
+                },
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// The length of the literal starting at the quote `quoted` starts with, or of the quote alone if it isn't
+/// closed (in which case the grammar doesn't read it as a literal either)
+fn quoted_length(quoted: &[u8]) -> usize {
+    let mut index = 1;
+
+    while let Some(&byte) = quoted.get(index) {
+        if byte == b'\\' {
+            index += 2;
+        } else if byte == quoted[0] {
+            return index + 1;
+        } else {
+            index += 1;
+        }
+    }
+
+    1
+}
+
+/// Takes the statements out of an `Rc`, cloning them if they are shared with other invocations
+pub fn unshare(statements: Rc<ViableAst>) -> ViableAst {
+    Rc::try_unwrap(statements).unwrap_or_else(|statements| (*statements).clone())
+}
+
+/// Remembers how a pass rewrote the statements shared by the invocations of a variable, so that they are
+/// rewritten once rather than once for every invocation
+///
+/// Along with the rewritten statements, whatever else the pass produced while rewriting them is kept (e.g.
+/// warnings) so that it can be reported for every invocation.
+pub struct SharedStatements<T> {
+    /// keyed by the address of the shared statements, which are kept alive so the address isn't reused
+    rewrites: HashMap<*const ViableAst, (Rc<ViableAst>, Rc<ViableAst>, T)>,
+}
+
+impl<T> Default for SharedStatements<T> {
+    fn default() -> Self {
+        Self {
+            rewrites: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Clone> SharedStatements<T> {
+    /// The rewrite of `statements` along with what was produced alongside it, if they were rewritten before
+    pub fn get(&self, statements: &Rc<ViableAst>) -> Option<(Rc<ViableAst>, T)> {
+        self.rewrites
+            .get(&Rc::as_ptr(statements))
+            .map(|(_, rewritten, produced)| (rewritten.clone(), produced.clone()))
+    }
+
+    pub fn insert(&mut self, statements: Rc<ViableAst>, rewritten: Rc<ViableAst>, produced: T) {
+        self.rewrites
+            .insert(Rc::as_ptr(&statements), (statements, rewritten, produced));
+    }
+}
+
+/// Hands out shared copies of the strings of an AST, so that each distinct string is allocated once however
+/// many nodes hold it
+#[derive(Default)]
+pub struct Interner {
+    strings: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, string: &str) -> Rc<str> {
+        if let Some(interned) = self.strings.get(string) {
+            return Rc::clone(interned);
+        }
+
+        let interned: Rc<str> = Rc::from(string);
+        self.strings.insert(Rc::clone(&interned));
+        interned
+    }
+}
+
+/// Parses the digits of an amount, rejecting anything but ASCII digits (e.g. a leading `+`) and amounts that
+/// don't fit in a `usize`
+pub fn parse_amount(amount: &str) -> Result<usize> {
+    amount
+        .bytes()
+        .all(|byte| byte.is_ascii_digit())
+        .then(|| amount.parse().ok())
+        .flatten()
+        .ok_or_else(|| CompilerError::CouldNotParseAnAmount(amount.to_owned()))
+}
+
+/// The character a pair spans, `context` naming the construct being parsed should it be empty
+pub fn to_char(pair: &Pair<'_, Rule>, context: &'static str) -> Result<char> {
+    let char = pair.as_str().chars().next().ok_or(missing_node(context, span(pair)))?;
 
     Ok(char)
 }
 
-pub fn alphabetic_first_char(value: &str) -> Result<bool> {
-    Ok(to_char(value)?.is_alphabetic())
+/// The content of a quoted literal, empty rather than panicking should the literal be shorter than its quotes
+fn unquote(quoted: &str) -> &str {
+    let end = quoted.len().saturating_sub(1);
+    quoted.get(1..end).unwrap_or_default()
 }
 
-pub fn unquote_escape_raw(pair: &Pair<'_, Rule>) -> String {
-    let pair_str = pair.as_str();
-    pair_str[1..pair_str.len() - 1].replace(r"\`", "`")
+pub fn unquote_escape_raw<'source>(pair: &Pair<'source, Rule>) -> Cow<'source, str> {
+    let content = unquote(pair.as_str());
+
+    if content.contains(r"\`") {
+        Cow::Owned(content.replace(r"\`", "`"))
+    } else {
+        Cow::Borrowed(content)
+    }
 }
 
-pub fn unquote_escape_literal(pair: &Pair<'_, Rule>) -> String {
+/// Unquotes a literal and escapes it for regex, borrowing it from the source when nothing needs escaping
+pub fn unquote_escape_literal<'source>(pair: &Pair<'source, Rule>) -> Result<Cow<'source, str>> {
     let raw_literal = pair.as_str();
     let quote_type = raw_literal.chars().next().unwrap_or('"');
-    let pair_str = escape_chars(raw_literal);
-    let literal = pair_str[1..pair_str.len() - 1].to_owned();
+    let content = unquote(raw_literal);
+
+    if !content.chars().any(|char| char == '\\' || needs_escape(char)) {
+        return Ok(Cow::Borrowed(content));
+    }
+
+    let mut literal = String::new();
+    let mut chars = content.chars().peekable();
 
-    match quote_type {
-        '"' => literal.replace(r#"\""#, r#"""#),
-        '\'' => literal.replace(r"\'", r"'"),
-        _ => unreachable!(),
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            literal.push_str(&escape_char(char));
+            continue;
+        }
+
+        match chars.next() {
+            Some(escape @ ('x' | 'u')) if escape == 'x' || chars.peek() == Some(&'{') => {
+                let code_point = code_point_escape(escape, &mut chars)?;
+                literal.push_str(&escape_char(code_point));
+            }
+            Some(quote) if quote == quote_type => literal.push(quote),
+            // other escapes have the same meaning in regex
+            Some(escaped) => {
+                literal.push('\\');
+                literal.push(escaped);
+            }
+            None => {}
+        }
     }
+
+    Ok(Cow::Owned(literal))
 }
 
-static RESERVED_CHARS: Lazy<HashSet<char>> =
-    Lazy::new(|| HashSet::from(['[', ']', '(', ')', '{', '}', '*', '+', '?', '|', '^', '$', '.']));
+/// Unquotes a literal and resolves its escape sequences to the characters they represent
+pub fn unescape_literal(pair: &Pair<'_, Rule>) -> Result<String> {
+    let raw_literal = pair.as_str();
+    let content = unquote(raw_literal);
 
-fn escape_chars(source: &str) -> String {
-    let mut escaped_source = String::new();
-    for char in source.chars() {
-        if RESERVED_CHARS.contains(&char) {
-            let escaped_char = format!("\\{char}");
-            escaped_source.push_str(&escaped_char);
-        } else {
-            escaped_source.push_str(&String::from(char));
+    let mut unescaped = String::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
+
+        match chars.next() {
+            Some('b') => unescaped.push('\u{8}'),
+            Some('f') => unescaped.push('\u{c}'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some(escape @ ('x' | 'u')) if escape == 'x' || chars.peek() == Some(&'{') => {
+                unescaped.push(code_point_escape(escape, &mut chars)?);
+            }
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                // the grammar guarantees 4 hex digits, surrogates are kept as the replacement character
+                let decoded = u32::from_str_radix(&code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+                unescaped.push(decoded);
+            }
+            Some(escaped) => unescaped.push(escaped),
+            None => {}
+        }
+    }
+
+    Ok(unescaped)
+}
+
+/// Reads the digits of a `\xNN` or `\u{...}` escape following its `x` or `u` and decodes them
+fn code_point_escape(escape: char, chars: &mut Peekable<Chars<'_>>) -> Result<char> {
+    let digits: String = if escape == 'x' {
+        let mut digits = String::new();
+        while digits.len() < 2 {
+            match chars.next_if(|char| !matches!(char, '"' | '\'' | '\\')) {
+                Some(char) => digits.push(char),
+                None => break,
+            }
+        }
+        if digits.chars().count() != 2 {
+            return Err(CompilerError::InvalidHexEscape(format!("\\x{digits}")));
+        }
+        digits
+    } else {
+        chars.next();
+        chars.by_ref().take_while(|char| *char != '}').collect()
+    };
+
+    decode_code_point(&digits).map_err(|error| match error {
+        CompilerError::InvalidHexEscape(_) if escape == 'x' => CompilerError::InvalidHexEscape(format!("\\x{digits}")),
+        CompilerError::InvalidHexEscape(_) => CompilerError::InvalidHexEscape(format!("\\u{{{digits}}}")),
+        error => error,
+    })
+}
+
+/// Decodes hex digits to the character they represent
+pub fn decode_code_point(digits: &str) -> Result<char> {
+    if digits.is_empty() || !digits.chars().all(|char| char.is_ascii_hexdigit()) {
+        return Err(CompilerError::InvalidHexEscape(digits.to_owned()));
+    }
+
+    let code_point = u32::from_str_radix(digits, 16)
+        .ok()
+        .filter(|code_point| *code_point <= u32::from(char::MAX))
+        .ok_or_else(|| CompilerError::CodePointOutOfRange(digits.to_owned()))?;
+
+    char::from_u32(code_point).ok_or_else(|| CompilerError::SurrogateCodePoint(digits.to_owned()))
+}
+
+const RESERVED_CHARS: [char; 14] = ['[', ']', '(', ')', '{', '}', '*', '+', '?', '|', '^', '$', '.', '\\'];
+
+/// Escapes a character for use in a regex outside of a class, control characters are written as `\xNN`
+fn needs_escape(char: char) -> bool {
+    RESERVED_CHARS.contains(&char) || char.is_control()
+}
+
+pub fn escape_char(char: char) -> String {
+    if RESERVED_CHARS.contains(&char) {
+        format!("\\{char}")
+    } else if char.is_control() {
+        format!("\\x{:02X}", u32::from(char))
+    } else {
+        String::from(char)
+    }
+}
+
+/// Resolves an import path relative to the file importing it, `..` and `.` segments are resolved lexically
+pub fn resolve_import_path(importer: Option<&str>, path: &str) -> String {
+    let joined = match importer.and_then(|importer| importer.rsplit_once('/')) {
+        Some((directory, _)) if !path.starts_with('/') => format!("{directory}/{path}"),
+        _ => path.to_owned(),
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." if segments.last().is_some_and(|last| *last != "..") => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    let resolved = segments.join("/");
+
+    if joined.starts_with('/') {
+        format!("/{resolved}")
+    } else {
+        resolved
+    }
+}
+
+/// Returns the candidate closest to `name` by edit distance, if it is close enough to be a likely typo
+pub fn closest_match<'candidate>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'candidate str>,
+) -> Option<&'candidate str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The edit distance between two strings, counting the transposition of two adjacent characters as a single edit
+/// (the optimal string alignment distance)
+fn edit_distance(first: &str, second: &str) -> usize {
+    let first: Vec<char> = first.chars().collect();
+    let second: Vec<char> = second.chars().collect();
+    let mut before_previous_row: Vec<usize> = Vec::new();
+    let mut previous_row: Vec<usize> = (0..=second.len()).collect();
+
+    for (first_index, first_char) in first.iter().enumerate() {
+        let mut current_row = vec![first_index + 1];
+
+        for (second_index, second_char) in second.iter().enumerate() {
+            let substitution_cost = usize::from(first_char != second_char);
+            let mut distance = (previous_row[second_index] + substitution_cost)
+                .min(previous_row[second_index + 1] + 1)
+                .min(current_row[second_index] + 1);
+
+            let transposed = first_index > 0
+                && second_index > 0
+                && *first_char == second[second_index - 1]
+                && first[first_index - 1] == *second_char;
+            if transposed {
+                distance = distance.min(before_previous_row[second_index - 1] + 1);
+            }
+
+            current_row.push(distance);
+        }
+
+        before_previous_row = core::mem::replace(&mut previous_row, current_row);
+    }
+
+    previous_row[second.len()]
+}
+
+/// The capture groups a raw regex opens in order, holding their names if they have one, or the first parenthesis
+/// or bracket left unbalanced
+///
+/// Escaped characters and characters within a class don't open or close anything, and a `]` right after the `[`
+/// (or `[^`) opening a class is literal. `(?<=`, `(?<!` and verbs such as `(*UTF)` aren't captures.
+pub fn raw_regex_captures(regex: &str) -> core::result::Result<Vec<Option<&str>>, char> {
+    let mut captures = Vec::new();
+    let mut depth = 0_usize;
+    let mut class_start = None;
+
+    let mut chars = regex.char_indices().peekable();
+
+    while let Some((index, char)) = chars.next() {
+        if char == '\\' {
+            chars.next();
+            continue;
+        }
+
+        if let Some(start) = class_start {
+            let literal_bracket = index == start + 1 || (index == start + 2 && regex[start..].starts_with("[^"));
+            if char == ']' && !literal_bracket {
+                class_start = None;
+            } else if regex[index..].starts_with("[:") {
+                // a POSIX class such as `[:alpha:]` ends with its own bracket
+                if let Some(end) = regex[index..].find(":]") {
+                    while chars.peek().is_some_and(|(next, _)| *next < index + end + 2) {
+                        chars.next();
+                    }
+                }
+            }
+            continue;
         }
+
+        match char {
+            '[' => class_start = Some(index),
+            ']' => return Err(']'),
+            ')' if depth == 0 => return Err(')'),
+            ')' => depth -= 1,
+            '(' => {
+                depth += 1;
+                let rest = &regex[index + 1..];
+                let named = ["?P<", "?<", "?'"]
+                    .iter()
+                    .find(|opener| rest.starts_with(**opener) && !rest.starts_with("?<=") && !rest.starts_with("?<!"));
+                match named {
+                    Some(opener) => {
+                        let name = &rest[opener.len()..];
+                        let end = name.find(['>', '\'']).unwrap_or(name.len());
+                        captures.push(Some(&name[..end]));
+                    }
+                    None if !rest.starts_with(['?', '*']) => captures.push(None),
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match (class_start, depth) {
+        (Some(_), _) => Err('['),
+        (None, 0) => Ok(captures),
+        (None, _) => Err('('),
     }
-    escaped_source
 }
@@ -0,0 +1,468 @@
+use super::types::ast::{
+    Backreference, CharClass, ClassItem, Conditional, Expression, Flag, Group, GroupKind, Quantifier, QuantifierUnit,
+    Range, Recursion, Span, Spanned, UnicodeCategory, UnicodeProperty, ViableAst, ViableAstNode,
+};
+use super::utils::raw_regex_captures;
+use super::visitor::{walk, walk_group, walk_quantifier, Visitor};
+use crate::errors::CompilerError;
+use crate::prelude::*;
+use crate::target::Target;
+use crate::types::{HashMap, HashSet};
+use crate::warnings::Warning;
+use alloc::rc::Rc;
+
+/// Validates a fully expanded AST, collecting errors that can only be detected once every
+/// variable invocation has been expanded (e.g. backreferences to captures defined in variables)
+///
+/// Capture groups within negative assertions are warnings, or errors if `deny_negative_captures` is set
+pub fn validate(
+    ast: &ViableAst,
+    errors: &mut Vec<Spanned<CompilerError>>,
+    warnings: &mut Vec<Warning>,
+    deny_negative_captures: bool,
+) {
+    let captures = collect_captures(ast);
+
+    let mut validator = Validator {
+        capture_names: HashSet::new(),
+        closed_captures: 0,
+        capture_count: captures.count,
+        all_capture_names: captures.names,
+        negative_assertion: None,
+        deny_negative_captures,
+        errors,
+        warnings,
+    };
+
+    validator.validate_ast(ast);
+}
+
+struct Validator<'diagnostics> {
+    /// capture group names closed so far, in source order
+    capture_names: HashSet<Rc<str>>,
+    /// how many capture groups (named and unnamed) are closed so far
+    closed_captures: usize,
+    /// total number of capture groups (named and unnamed) in the expanded pattern
+    capture_count: usize,
+    /// the names of every capture group in the expanded pattern, wherever they are declared
+    all_capture_names: HashSet<Rc<str>>,
+    /// the span of the outermost negative assertion the validated node is within
+    negative_assertion: Option<Span>,
+    deny_negative_captures: bool,
+    errors: &'diagnostics mut Vec<Spanned<CompilerError>>,
+    warnings: &'diagnostics mut Vec<Warning>,
+}
+
+impl Validator<'_> {
+    fn validate_ast(&mut self, ast: &ViableAst) {
+        if let ViableAst::Root(nodes) = ast {
+            for node in nodes {
+                self.validate_node(node);
+            }
+        }
+    }
+
+    fn validate_node(&mut self, node: &Spanned<ViableAstNode>) {
+        match &node.node {
+            ViableAstNode::Group(group) => self.validate_group(group, node.span),
+            ViableAstNode::Assertion(assertion) => {
+                let outer = self.negative_assertion;
+                if assertion.negative {
+                    self.negative_assertion = outer.or(Some(node.span));
+                }
+                self.validate_ast(&assertion.statements);
+                self.negative_assertion = outer;
+            }
+            ViableAstNode::Conditional(conditional) => self.validate_conditional(conditional, node.span),
+            ViableAstNode::VariableInvocation(invocation) => self.validate_ast(&invocation.statements),
+            ViableAstNode::Quantifier(quantifier) => self.validate_expression(&quantifier.expression),
+            ViableAstNode::Backreference(backreference) => self.validate_backreference(backreference, node),
+            // a group can be recursed into from anywhere, even before or within itself
+            ViableAstNode::Recursion(Recursion::Group(name)) if !self.all_capture_names.contains(name) => {
+                self.errors.push(Spanned {
+                    node: CompilerError::UnknownRecursionGroup(name.to_string()),
+                    span: node.span,
+                });
+            }
+            // the branches of an `either` are skipped by `validate_group`, so any other `nothing` is misplaced
+            ViableAstNode::Empty => self.errors.push(Spanned {
+                node: CompilerError::NothingOutsideEither,
+                span: node.span,
+            }),
+            // the groups of a raw regex are closed within it
+            ViableAstNode::RawRegex(regex) => {
+                self.closed_captures += raw_regex_captures(regex).map_or(0, |captures| captures.len());
+            }
+            ViableAstNode::Atom(_)
+            | ViableAstNode::Range(_)
+            | ViableAstNode::Symbol(_)
+            | ViableAstNode::SpecialSymbol(_)
+            | ViableAstNode::UnicodeCategory(_)
+            | ViableAstNode::UnicodeProperty(_)
+            | ViableAstNode::NegativeCharClass(_)
+            | ViableAstNode::CharClass(_)
+            | ViableAstNode::Recursion(_)
+            | ViableAstNode::Parameter(_)
+            | ViableAstNode::Skip
+            | ViableAstNode::Error => {}
+        }
+    }
+
+    /// Checks that the condition refers to a capture group closed before the conditional, the only ones that
+    /// can have taken part in the match when it is tested
+    fn validate_conditional(&mut self, conditional: &Conditional, span: Span) {
+        let unknown = match &conditional.condition {
+            Backreference::Named(name) if !self.capture_names.contains(name) => Some(name.to_string()),
+            Backreference::Numbered(index) if *index == 0 || *index > self.closed_captures => Some(index.to_string()),
+            Backreference::Named(_) | Backreference::Numbered(_) => None,
+        };
+
+        if let Some(name) = unknown {
+            self.errors.push(Spanned {
+                node: CompilerError::UnknownConditionalCapture(name),
+                span,
+            });
+        }
+
+        self.validate_ast(&conditional.then);
+        if let Some(otherwise) = &conditional.otherwise {
+            self.validate_ast(otherwise);
+        }
+    }
+
+    fn validate_expression(&mut self, expression: &Spanned<Expression>) {
+        match &expression.node {
+            Expression::Group(group) => self.validate_group(group, expression.span),
+            Expression::Backreference(backreference) => self.validate_backreference(backreference, expression),
+            Expression::Atom(_)
+            | Expression::Symbol(_)
+            | Expression::UnicodeCategory(_)
+            | Expression::UnicodeProperty(_)
+            | Expression::Range(_)
+            | Expression::NegativeCharClass(_)
+            | Expression::CharClass(_) => {}
+        }
+    }
+
+    fn validate_group(&mut self, group: &Group, span: Span) {
+        if let (GroupKind::Capture, Some(assertion_span)) = (&group.kind, self.negative_assertion) {
+            if self.deny_negative_captures {
+                self.errors.push(Spanned {
+                    node: CompilerError::CaptureInNegativeAssertion {
+                        assertion_span,
+                        capture_span: span,
+                    },
+                    span,
+                });
+            } else {
+                self.warnings.push(Warning::CaptureInNegativeAssertion {
+                    assertion_span,
+                    capture_span: span,
+                });
+            }
+        }
+
+        match (&group.kind, group.statements.as_ref()) {
+            (GroupKind::Either, ViableAst::Root(branches)) => {
+                for branch in branches.iter().filter(|branch| branch.node != ViableAstNode::Empty) {
+                    self.validate_node(branch);
+                }
+            }
+            _ => self.validate_ast(&group.statements),
+        }
+
+        if group.kind == GroupKind::Capture {
+            self.closed_captures += 1;
+        }
+        if let (GroupKind::Capture, Some(ident)) = (&group.kind, &group.ident) {
+            self.capture_names.insert(ident.clone());
+        }
+    }
+
+    fn validate_backreference<T>(&mut self, backreference: &Backreference, node: &Spanned<T>) {
+        let error = match backreference {
+            Backreference::Named(name) if !self.capture_names.contains(name) => {
+                CompilerError::UnknownBackreference(name.to_string())
+            }
+            Backreference::Numbered(index) if *index == 0 || *index > self.capture_count => {
+                CompilerError::InvalidBackreferenceIndex {
+                    index: *index,
+                    group_count: self.capture_count,
+                }
+            }
+            Backreference::Named(_) | Backreference::Numbered(_) => return,
+        };
+
+        self.errors.push(Spanned {
+            node: error,
+            span: node.span,
+        });
+    }
+}
+
+/// Counts and names the capture groups of an expanded AST, each variable invocation contributing its own copies
+fn collect_captures(ast: &ViableAst) -> Captures {
+    let mut captures = Captures {
+        count: 0,
+        names: HashSet::new(),
+    };
+    walk(ast, &mut captures);
+    captures
+}
+
+struct Captures {
+    count: usize,
+    names: HashSet<Rc<str>>,
+}
+
+impl Visitor for Captures {
+    fn visit_group(&mut self, group: &Group, _span: Span) {
+        if group.kind == GroupKind::Capture {
+            self.count += 1;
+            self.names.extend(group.ident.clone());
+        }
+        walk_group(self, group);
+    }
+
+    fn visit_raw_regex(&mut self, regex: &str, _span: Span) {
+        let captures = raw_regex_captures(regex).unwrap_or_default();
+        self.count += captures.len();
+        self.names.extend(captures.into_iter().flatten().map(Rc::from));
+    }
+}
+
+/// Checks that no two capture groups of a fully expanded AST share a name, including the copies of a capture
+/// made by invoking a variable more than once
+///
+/// # Errors
+///
+/// Returns [`CompilerError::DuplicateCaptureName`] for the first name declared twice
+pub fn check_capture_names(ast: &ViableAst) -> Result<(), CompilerError> {
+    match duplicate_capture_names(ast).into_iter().next() {
+        Some(Warning::DuplicateCaptureName {
+            name,
+            first_span,
+            second_span,
+        }) => Err(CompilerError::DuplicateCaptureName {
+            name,
+            first_span,
+            second_span,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Finds the capture groups of a fully expanded AST named like an earlier one, like [`check_capture_names`]
+pub fn duplicate_capture_names(ast: &ViableAst) -> Vec<Warning> {
+    let mut names = HashMap::new();
+    let mut duplicates = Vec::new();
+    collect_capture_names(ast, &mut names, &mut duplicates);
+    duplicates
+}
+
+fn collect_capture_names(ast: &ViableAst, names: &mut HashMap<Rc<str>, Span>, duplicates: &mut Vec<Warning>) {
+    let ViableAst::Root(nodes) = ast else {
+        return;
+    };
+
+    for node in nodes {
+        match &node.node {
+            ViableAstNode::Group(group) => collect_group_capture_names(group, node.span, names, duplicates),
+            ViableAstNode::Assertion(assertion) => collect_capture_names(&assertion.statements, names, duplicates),
+            ViableAstNode::Conditional(conditional) => {
+                collect_capture_names(&conditional.then, names, duplicates);
+                if let Some(otherwise) = &conditional.otherwise {
+                    collect_capture_names(otherwise, names, duplicates);
+                }
+            }
+            ViableAstNode::VariableInvocation(invocation) => {
+                collect_capture_names(&invocation.statements, names, duplicates);
+            }
+            ViableAstNode::Quantifier(quantifier) => {
+                if let Expression::Group(group) = &quantifier.expression.node {
+                    collect_group_capture_names(group, quantifier.expression.span, names, duplicates);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_group_capture_names(
+    group: &Group,
+    span: Span,
+    names: &mut HashMap<Rc<str>, Span>,
+    duplicates: &mut Vec<Warning>,
+) {
+    if let (GroupKind::Capture, Some(ident)) = (&group.kind, &group.ident) {
+        match names.get(ident) {
+            Some(first_span) => duplicates.push(Warning::DuplicateCaptureName {
+                name: ident.to_string(),
+                first_span: *first_span,
+                second_span: span,
+            }),
+            None => {
+                names.insert(ident.clone(), span);
+            }
+        }
+    }
+
+    collect_capture_names(&group.statements, names, duplicates);
+}
+
+/// Checks that the statements of a fully expanded AST matched by byte only hold ASCII characters: all of them
+/// outside of unicode mode, but for those of `chars` quantifiers and groups setting `unicode`, and those of
+/// `bytes` quantifiers within it
+///
+/// # Errors
+///
+/// Returns [`CompilerError::NonAsciiInBytesMode`] for the first statement matched by byte with a non-ASCII
+/// character or a unicode class
+pub fn check_bytes_mode(ast: &ViableAst, unicode: bool) -> Result<(), CompilerError> {
+    let mut checker = BytesModeChecker { unicode, span: None };
+    walk(ast, &mut checker);
+
+    match checker.span {
+        Some(span) => Err(CompilerError::NonAsciiInBytesMode { span }),
+        None => Ok(()),
+    }
+}
+
+struct BytesModeChecker {
+    /// whether the visited statements match by code point
+    unicode: bool,
+    /// the span of the first statement matched by byte that isn't ASCII
+    span: Option<Span>,
+}
+
+impl BytesModeChecker {
+    fn check(&mut self, ascii: bool, span: Span) {
+        if !ascii && !self.unicode && self.span.is_none() {
+            self.span = Some(span);
+        }
+    }
+}
+
+impl Visitor for BytesModeChecker {
+    fn visit_group(&mut self, group: &Group, _span: Span) {
+        let unicode = self.unicode;
+        if let GroupKind::Flagged { set, clear } = &group.kind {
+            self.unicode = (unicode || set.contains(&Flag::Unicode)) && !clear.contains(&Flag::Unicode);
+        }
+        walk_group(self, group);
+        self.unicode = unicode;
+    }
+
+    fn visit_quantifier(&mut self, quantifier: &Quantifier, _span: Span) {
+        let unicode = self.unicode;
+        if let Some(unit) = quantifier.unit {
+            self.unicode = unit == QuantifierUnit::Chars;
+        }
+        walk_quantifier(self, quantifier);
+        self.unicode = unicode;
+    }
+
+    fn visit_atom(&mut self, atom: &str, span: Span) {
+        self.check(atom.is_ascii(), span);
+    }
+
+    fn visit_range(&mut self, range: &Range, span: Span) {
+        self.check(range_is_ascii(range), span);
+    }
+
+    fn visit_unicode_category(&mut self, _category: &UnicodeCategory, span: Span) {
+        self.check(false, span);
+    }
+
+    fn visit_unicode_property(&mut self, _property: &UnicodeProperty, span: Span) {
+        self.check(false, span);
+    }
+
+    fn visit_char_class(&mut self, class: &CharClass, span: Span) {
+        self.check(class_items_are_ascii(&class.items), span);
+    }
+
+    fn visit_negative_char_class(&mut self, items: &[ClassItem], span: Span) {
+        self.check(class_items_are_ascii(items), span);
+    }
+}
+
+/// Checks that no range of a fully expanded AST has an endpoint outside of the Basic Multilingual Plane (e.g.
+/// `😀 to 😀`), which an ECMAScript pattern without the `u` flag reads as the two halves of a surrogate pair
+///
+/// # Errors
+///
+/// Returns [`CompilerError::UnsupportedByTarget`] for the first such range, standalone or within a class
+pub fn check_astral_ranges(ast: &ViableAst, target: Target) -> Result<(), CompilerError> {
+    let mut checker = AstralRangeChecker { span: None };
+    walk(ast, &mut checker);
+
+    match checker.span {
+        Some(span) => Err(CompilerError::UnsupportedByTarget {
+            construct: "a range of characters outside the Basic Multilingual Plane without the `unicode` flag",
+            target,
+            span,
+        }),
+        None => Ok(()),
+    }
+}
+
+struct AstralRangeChecker {
+    /// the span of the first statement with an astral range
+    span: Option<Span>,
+}
+
+impl AstralRangeChecker {
+    fn check(&mut self, astral: bool, span: Span) {
+        if astral && self.span.is_none() {
+            self.span = Some(span);
+        }
+    }
+}
+
+impl Visitor for AstralRangeChecker {
+    fn visit_range(&mut self, range: &Range, span: Span) {
+        self.check(range_is_astral(range), span);
+    }
+
+    fn visit_char_class(&mut self, class: &CharClass, span: Span) {
+        self.check(class_has_astral_range(&class.items), span);
+    }
+
+    fn visit_negative_char_class(&mut self, items: &[ClassItem], span: Span) {
+        self.check(class_has_astral_range(items), span);
+    }
+}
+
+fn range_is_astral(range: &Range) -> bool {
+    match range {
+        Range::CharRange(range) => u32::from(range.start) > 0xFFFF || u32::from(range.end) > 0xFFFF,
+        Range::NumericRange(_) => false,
+    }
+}
+
+fn class_has_astral_range(items: &[ClassItem]) -> bool {
+    items.iter().any(|item| match item {
+        ClassItem::Range(range) => range_is_astral(range),
+        ClassItem::Subtract(items) | ClassItem::Intersect(items) => class_has_astral_range(items),
+        _ => false,
+    })
+}
+
+fn range_is_ascii(range: &Range) -> bool {
+    match range {
+        Range::CharRange(range) => range.start.is_ascii() && range.end.is_ascii(),
+        Range::NumericRange(_) => true,
+    }
+}
+
+fn class_items_are_ascii(items: &[ClassItem]) -> bool {
+    items.iter().all(|item| match item {
+        ClassItem::Range(range) => range_is_ascii(range),
+        ClassItem::Char(char) => char.is_ascii(),
+        ClassItem::Raw(raw) => raw.is_ascii(),
+        ClassItem::Symbol(_) => true,
+        ClassItem::UnicodeCategory(_) | ClassItem::UnicodeProperty(_) => false,
+        ClassItem::Subtract(items) | ClassItem::Intersect(items) => class_items_are_ascii(items),
+    })
+}
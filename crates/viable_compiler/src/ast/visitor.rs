@@ -0,0 +1,191 @@
+use super::types::ast::{
+    Assertion, Backreference, CharClass, ClassItem, Conditional, Expression, Group, Quantifier, Range, Recursion, Span,
+    Spanned, SpecialSymbolKind, Symbol, UnicodeCategory, UnicodeProperty, VariableInvocation, ViableAst, ViableAstNode,
+};
+
+/**
+Walks an AST in document order, calling the `visit_*` method of the [`Visitor`] for each node.
+
+# Example
+
+```rust
+use viable_compiler::ast::types::ast::Span;
+use viable_compiler::ast::{to_ast, walk, Visitor};
+
+#[derive(Default)]
+struct Atoms(Vec<String>);
+
+impl Visitor for Atoms {
+    fn visit_atom(&mut self, atom: &str, _span: Span) {
+        self.0.push(atom.to_owned());
+    }
+}
+
+let ast = to_ast(r#""a"; some of "b"; ahead { "c"; }"#).unwrap();
+let mut atoms = Atoms::default();
+walk(&ast, &mut atoms);
+
+assert_eq!(atoms.0, ["a", "b", "c"]);
+```
+*/
+pub fn walk(ast: &ViableAst, visitor: &mut impl Visitor) {
+    visitor.visit_ast(ast);
+}
+
+/// A read only traversal of an AST
+///
+/// Every method defaults to visiting the children of its node (through the matching `walk_*` function), so
+/// implementors only override the nodes they are interested in and call the `walk_*` function themselves to
+/// keep recursing. Quantified expressions are visited through the same methods as statements, with the span
+/// of the expression.
+pub trait Visitor {
+    /// Whether to visit the expanded statements of variable invocations
+    ///
+    /// The statements of each invocation are those of the variable's declaration, so this can be disabled by
+    /// visitors that would otherwise count a variable once for every invocation of it
+    fn visit_invocation_statements(&self) -> bool {
+        true
+    }
+
+    fn visit_ast(&mut self, ast: &ViableAst) {
+        walk_ast(self, ast);
+    }
+
+    fn visit_node(&mut self, node: &Spanned<ViableAstNode>) {
+        walk_node(self, node);
+    }
+
+    fn visit_group(&mut self, group: &Group, _span: Span) {
+        walk_group(self, group);
+    }
+
+    fn visit_assertion(&mut self, assertion: &Assertion, _span: Span) {
+        walk_assertion(self, assertion);
+    }
+
+    fn visit_conditional(&mut self, conditional: &Conditional, _span: Span) {
+        walk_conditional(self, conditional);
+    }
+
+    fn visit_quantifier(&mut self, quantifier: &Quantifier, _span: Span) {
+        walk_quantifier(self, quantifier);
+    }
+
+    fn visit_expression(&mut self, expression: &Spanned<Expression>) {
+        walk_expression(self, expression);
+    }
+
+    fn visit_variable_invocation(&mut self, invocation: &VariableInvocation, _span: Span) {
+        walk_variable_invocation(self, invocation);
+    }
+
+    fn visit_atom(&mut self, _atom: &str, _span: Span) {}
+
+    fn visit_range(&mut self, _range: &Range, _span: Span) {}
+
+    fn visit_symbol(&mut self, _symbol: &Symbol, _span: Span) {}
+
+    fn visit_special_symbol(&mut self, _special_symbol: &SpecialSymbolKind, _span: Span) {}
+
+    fn visit_unicode_category(&mut self, _category: &UnicodeCategory, _span: Span) {}
+
+    fn visit_unicode_property(&mut self, _property: &UnicodeProperty, _span: Span) {}
+
+    fn visit_char_class(&mut self, _class: &CharClass, _span: Span) {}
+
+    fn visit_negative_char_class(&mut self, _items: &[ClassItem], _span: Span) {}
+
+    fn visit_backreference(&mut self, _backreference: &Backreference, _span: Span) {}
+
+    fn visit_recursion(&mut self, _recursion: &Recursion, _span: Span) {}
+
+    fn visit_parameter(&mut self, _name: &str, _span: Span) {}
+
+    fn visit_empty(&mut self, _span: Span) {}
+
+    fn visit_raw_regex(&mut self, _regex: &str, _span: Span) {}
+
+    fn visit_skip(&mut self, _span: Span) {}
+
+    fn visit_error(&mut self, _span: Span) {}
+}
+
+pub fn walk_ast<V: Visitor + ?Sized>(visitor: &mut V, ast: &ViableAst) {
+    if let ViableAst::Root(nodes) = ast {
+        for node in nodes {
+            visitor.visit_node(node);
+        }
+    }
+}
+
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &Spanned<ViableAstNode>) {
+    let span = node.span;
+
+    match &node.node {
+        ViableAstNode::Group(group) => visitor.visit_group(group, span),
+        ViableAstNode::Assertion(assertion) => visitor.visit_assertion(assertion, span),
+        ViableAstNode::Conditional(conditional) => visitor.visit_conditional(conditional, span),
+        ViableAstNode::Quantifier(quantifier) => visitor.visit_quantifier(quantifier, span),
+        ViableAstNode::VariableInvocation(invocation) => visitor.visit_variable_invocation(invocation, span),
+        ViableAstNode::Atom(atom) => visitor.visit_atom(atom, span),
+        ViableAstNode::Range(range) => visitor.visit_range(range, span),
+        ViableAstNode::Symbol(symbol) => visitor.visit_symbol(symbol, span),
+        ViableAstNode::SpecialSymbol(special_symbol) => visitor.visit_special_symbol(special_symbol, span),
+        ViableAstNode::UnicodeCategory(category) => visitor.visit_unicode_category(category, span),
+        ViableAstNode::UnicodeProperty(property) => visitor.visit_unicode_property(property, span),
+        ViableAstNode::NegativeCharClass(items) => visitor.visit_negative_char_class(items, span),
+        ViableAstNode::CharClass(class) => visitor.visit_char_class(class, span),
+        ViableAstNode::Backreference(backreference) => visitor.visit_backreference(backreference, span),
+        ViableAstNode::Recursion(recursion) => visitor.visit_recursion(recursion, span),
+        ViableAstNode::Parameter(name) => visitor.visit_parameter(name, span),
+        ViableAstNode::Empty => visitor.visit_empty(span),
+        ViableAstNode::RawRegex(regex) => visitor.visit_raw_regex(regex, span),
+        ViableAstNode::Skip => visitor.visit_skip(span),
+        ViableAstNode::Error => visitor.visit_error(span),
+    }
+}
+
+pub fn walk_group<V: Visitor + ?Sized>(visitor: &mut V, group: &Group) {
+    visitor.visit_ast(&group.statements);
+}
+
+pub fn walk_assertion<V: Visitor + ?Sized>(visitor: &mut V, assertion: &Assertion) {
+    visitor.visit_ast(&assertion.statements);
+}
+
+/// Visits the statements of both branches, the `else` one last
+pub fn walk_conditional<V: Visitor + ?Sized>(visitor: &mut V, conditional: &Conditional) {
+    visitor.visit_ast(&conditional.then);
+    if let Some(otherwise) = &conditional.otherwise {
+        visitor.visit_ast(otherwise);
+    }
+}
+
+pub fn walk_quantifier<V: Visitor + ?Sized>(visitor: &mut V, quantifier: &Quantifier) {
+    visitor.visit_expression(&quantifier.expression);
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Spanned<Expression>) {
+    let span = expression.span;
+
+    match &expression.node {
+        Expression::Group(group) => visitor.visit_group(group, span),
+        Expression::Atom(atom) => visitor.visit_atom(atom, span),
+        Expression::Range(range) => visitor.visit_range(range, span),
+        Expression::Symbol(symbol) => visitor.visit_symbol(symbol, span),
+        Expression::UnicodeCategory(category) => visitor.visit_unicode_category(category, span),
+        Expression::UnicodeProperty(property) => visitor.visit_unicode_property(property, span),
+        Expression::NegativeCharClass(items) => visitor.visit_negative_char_class(items, span),
+        Expression::CharClass(class) => visitor.visit_char_class(class, span),
+        Expression::Backreference(backreference) => visitor.visit_backreference(backreference, span),
+    }
+}
+
+/// Visits the expanded statements of an invocation, unless [`Visitor::visit_invocation_statements`] is disabled
+///
+/// The arguments of parameterized invocations are already substituted into the statements, so aren't visited
+pub fn walk_variable_invocation<V: Visitor + ?Sized>(visitor: &mut V, invocation: &VariableInvocation) {
+    if visitor.visit_invocation_statements() {
+        visitor.visit_ast(&invocation.statements);
+    }
+}
@@ -0,0 +1,153 @@
+use super::optimization::atom_units;
+use super::types::ast::{
+    Conditional, Expression, Group, GroupKind, Quantifier, QuantifierKind, Symbol, SymbolKind, ViableAst, ViableAstNode,
+};
+use crate::target::Target;
+
+/// The fewest and the most characters a block can match, the most being `None` if there is no limit
+type Bounds = (usize, Option<usize>);
+
+/// The fewest and the most characters a block can match, `None` if they can't be determined
+/// (e.g. for a raw atom or a backreference)
+pub fn width_bounds(ast: &ViableAst) -> Option<Bounds> {
+    match ast {
+        ViableAst::Root(nodes) => nodes
+            .iter()
+            .map(|node| node_bounds(&node.node))
+            .try_fold((0, Some(0)), |total, bounds| Some(concatenate(total, bounds?))),
+        ViableAst::Empty => Some((0, Some(0))),
+    }
+}
+
+/// Whether the target can match the body of a lookbehind assertion: every target with lookbehind accepts a body
+/// of a fixed length, PCRE2 also accepts a bounded one and ECMAScript any
+pub fn supports_lookbehind_body(statements: &ViableAst, target: Target) -> bool {
+    match width_bounds(statements) {
+        Some((min, max)) if max == Some(min) => true,
+        Some((_, None)) => target.supports_unbounded_lookbehind(),
+        Some((_, Some(_))) | None => target.supports_variable_length_lookbehind(),
+    }
+}
+
+fn concatenate((min, max): Bounds, (next_min, next_max): Bounds) -> Bounds {
+    (
+        min.saturating_add(next_min),
+        max.zip(next_max).and_then(|(max, next_max)| max.checked_add(next_max)),
+    )
+}
+
+fn node_bounds(node: &ViableAstNode) -> Option<Bounds> {
+    match node {
+        ViableAstNode::Group(group) => group_bounds(group),
+        ViableAstNode::Quantifier(quantifier) => quantifier_bounds(quantifier),
+        ViableAstNode::Atom(atom) => atom_bounds(atom),
+        ViableAstNode::Symbol(symbol) => Some(symbol_bounds(symbol)),
+        ViableAstNode::VariableInvocation(invocation) => width_bounds(&invocation.statements),
+        ViableAstNode::Conditional(conditional) => conditional_bounds(conditional),
+        ViableAstNode::Range(_)
+        | ViableAstNode::UnicodeCategory(_)
+        | ViableAstNode::UnicodeProperty(_)
+        | ViableAstNode::NegativeCharClass(_)
+        | ViableAstNode::CharClass(_) => Some((1, Some(1))),
+        // assertions and anchors are zero-width, whatever their statements match
+        ViableAstNode::Assertion(_)
+        | ViableAstNode::SpecialSymbol(_)
+        | ViableAstNode::Parameter(_)
+        | ViableAstNode::Empty
+        | ViableAstNode::Skip => Some((0, Some(0))),
+        ViableAstNode::Backreference(_)
+        | ViableAstNode::Recursion(_)
+        | ViableAstNode::RawRegex(_)
+        | ViableAstNode::Error => None,
+    }
+}
+
+fn expression_bounds(expression: &Expression) -> Option<Bounds> {
+    match expression {
+        Expression::Group(group) => group_bounds(group),
+        Expression::Atom(atom) => atom_bounds(atom),
+        Expression::Symbol(symbol) => Some(symbol_bounds(symbol)),
+        Expression::Range(_)
+        | Expression::UnicodeCategory(_)
+        | Expression::UnicodeProperty(_)
+        | Expression::NegativeCharClass(_)
+        | Expression::CharClass(_) => Some((1, Some(1))),
+        Expression::Backreference(_) => None,
+    }
+}
+
+fn quantifier_bounds(quantifier: &Quantifier) -> Option<Bounds> {
+    let (start, end) = match quantifier.kind {
+        QuantifierKind::Range { start, end } => (start, Some(end)),
+        QuantifierKind::Some => (1, None),
+        QuantifierKind::Any => (0, None),
+        QuantifierKind::Over(amount) | QuantifierKind::AtLeast(amount) => (amount, None),
+        QuantifierKind::AtMost(amount) => (0, Some(amount)),
+        QuantifierKind::Option => (0, Some(1)),
+        QuantifierKind::Amount(amount) => (amount, Some(amount)),
+    };
+
+    let (min, max) = expression_bounds(&quantifier.expression.node)?;
+
+    let max = match (max, end) {
+        // repeating an expression that matches nothing still matches nothing
+        (Some(0), _) | (_, Some(0)) => Some(0),
+        (Some(max), Some(end)) => max.checked_mul(end),
+        _ => None,
+    };
+
+    Some((min.saturating_mul(start), max))
+}
+
+fn group_bounds(group: &Group) -> Option<Bounds> {
+    if group.kind != GroupKind::Either {
+        return width_bounds(&group.statements);
+    }
+
+    let ViableAst::Root(nodes) = group.statements.as_ref() else {
+        return Some((0, Some(0)));
+    };
+
+    let mut branches = nodes
+        .iter()
+        .filter(|node| !matches!(node.node, ViableAstNode::Skip))
+        .map(|node| node_bounds(&node.node));
+
+    let first = branches.next().unwrap_or(Some((0, Some(0))))?;
+
+    branches.try_fold(first, |bounds, branch| Some(alternate(bounds, branch?)))
+}
+
+/// A conditional matches one of its branches, the one without an `else` matching nothing
+fn conditional_bounds(conditional: &Conditional) -> Option<Bounds> {
+    let otherwise = match &conditional.otherwise {
+        Some(otherwise) => width_bounds(otherwise)?,
+        None => (0, Some(0)),
+    };
+
+    Some(alternate(width_bounds(&conditional.then)?, otherwise))
+}
+
+/// The bounds of matching either of two alternatives
+fn alternate((min, max): Bounds, (other_min, other_max): Bounds) -> Bounds {
+    (
+        min.min(other_min),
+        max.zip(other_max).map(|(max, other_max)| max.max(other_max)),
+    )
+}
+
+fn atom_bounds(atom: &str) -> Option<Bounds> {
+    atom_units(atom).map(|units| (units.len(), Some(units.len())))
+}
+
+const fn symbol_bounds(symbol: &Symbol) -> Bounds {
+    match symbol.kind {
+        // word boundaries are zero-width
+        SymbolKind::Boundary => (0, Some(0)),
+        // a grapheme cluster spans any number of characters
+        SymbolKind::Grapheme => (1, None),
+        // `\r\n` is two characters
+        SymbolKind::LineBreak => (1, Some(2)),
+        _ => (1, Some(1)),
+    }
+}
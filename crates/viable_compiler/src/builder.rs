@@ -0,0 +1,214 @@
+use crate::ast::duplicate_capture_names;
+use crate::ast::source_to_ast::{to_ast_with_flags_and_options, to_ast_with_flags_imports_and_options};
+use crate::ast::types::ast::{PatternFlags, ViableAst};
+use crate::metadata::{CaptureInfo, SourceMap};
+use crate::options::{CompilerOptions, EmitStyle, OptimizationLevel, Strictness};
+use crate::prelude::*;
+use crate::regex::{capture_groups, source_map};
+use crate::target::Target;
+use crate::types::Result;
+use crate::warnings::Warning;
+use crate::{check_captures, check_size, emit, lower, optimize};
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+
+/**
+Compiles Viable source code with a target and options configured once, to compile any number of sources.
+
+# Example
+
+```rust
+use viable_compiler::options::Strictness;
+use viable_compiler::target::Target;
+use viable_compiler::Compiler;
+
+let compiler = Compiler::new().target(Target::Pcre2).strictness(Strictness::Strict);
+
+assert_eq!(compiler.compile(r#"some of "a";"#).unwrap().pattern, "a+");
+assert!(compiler.compile("let .unused = { \"a\"; } \"b\";").is_err());
+```
+
+The options are shared between clones until one of them is configured further, so a configured compiler can be
+cloned to compile sources elsewhere without copying its variables and symbols.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct Compiler {
+    target: Target,
+    options: Rc<CompilerOptions>,
+}
+
+/// The result of [`Compiler::compile`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileOutput {
+    /// The regular expression, with the flags of the source prefixed in the target's inline flag syntax
+    pub pattern: String,
+    /// The flag letters to pass to the engine along with the pattern (e.g. `"im"`) for a target without an
+    /// inline flag syntax, which are otherwise part of the pattern and left empty
+    pub flags: String,
+    /// The AST the pattern was compiled from, once optimized and with the constructs the target can't express
+    /// approximated
+    pub ast: ViableAst,
+    /// The capture groups of the pattern in the order it numbers them, see [`crate::compile_with_metadata`]
+    pub captures: Vec<CaptureInfo>,
+    /// The likely mistakes found in the source, in source order
+    pub warnings: Vec<Warning>,
+    /// The fragments of the pattern mapped back to their statements, with [`Compiler::source_map`] and a compact
+    /// pattern
+    pub source_map: Option<SourceMap>,
+}
+
+impl Compiler {
+    /// A compiler for [`Target::default`] with [`CompilerOptions::default`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the target the pattern is compiled for
+    #[must_use]
+    pub const fn target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Replaces every option, including the strictness
+    #[must_use]
+    pub fn options(mut self, options: CompilerOptions) -> Self {
+        self.options = Rc::new(options);
+        self
+    }
+
+    /// Sets which likely mistakes are rejected rather than warned about, see [`Strictness`]
+    #[must_use]
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        Rc::make_mut(&mut self.options).strictness = strictness;
+        self
+    }
+
+    /// Rejects every likely mistake with `true`, see [`Strictness::Strict`], and only those
+    /// [`Strictness::Standard`] rejects with `false`
+    #[must_use]
+    pub fn strict(self, strict: bool) -> Self {
+        self.strictness(if strict {
+            Strictness::Strict
+        } else {
+            Strictness::Standard
+        })
+    }
+
+    /// Sets which optimizations are applied to the AST before it is compiled
+    #[must_use]
+    pub fn optimization(mut self, optimization: OptimizationLevel) -> Self {
+        Rc::make_mut(&mut self.options).optimization = optimization;
+        self
+    }
+
+    /// Sets how deep blocks and the expansions of variables may be nested, see
+    /// [`CompilerOptions::nesting_limit`]
+    #[must_use]
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        Rc::make_mut(&mut self.options).nesting_limit = max_depth;
+        self
+    }
+
+    /// Sets the variables in scope from the start of every source, see [`CompilerOptions::variables`]
+    #[must_use]
+    pub fn variables(mut self, variables: BTreeMap<String, ViableAst>) -> Self {
+        Rc::make_mut(&mut self.options).variables = variables;
+        self
+    }
+
+    /// Sets whether [`CompileOutput::source_map`] is built, see [`CompilerOptions::source_map`]
+    #[must_use]
+    pub fn source_map(mut self, source_map: bool) -> Self {
+        Rc::make_mut(&mut self.options).source_map = source_map;
+        self
+    }
+
+    /**
+    Converts Viable source code to an AST with the configured options, without compiling it
+
+    # Errors
+
+    Returns an [`crate::errors::CompilerError`] upon encountering a syntax error, or for the first warning at
+    [`Strictness::Strict`]
+    */
+    pub fn parse(&self, source: &str) -> Result<ViableAst> {
+        to_ast_with_flags_and_options(source, &self.options).map(|(ast, _, _)| ast)
+    }
+
+    /**
+    Compiles Viable source code to a regular expression, along with the AST it was compiled from, its capture
+    groups and the warnings of the source.
+
+    # Errors
+
+    Returns an [`crate::errors::CompilerError`] upon encountering a syntax error or a construct the target does
+    not support, or for the first warning at [`Strictness::Strict`]
+    */
+    pub fn compile(&self, source: &str) -> Result<CompileOutput> {
+        let (ast, flags, warnings) = to_ast_with_flags_and_options(source, &self.options)?;
+
+        compile_parsed(ast, &flags, warnings, self.target, &self.options, Some(source))
+    }
+
+    /**
+    Compiles Viable source code like [`Compiler::compile`], resolving `import "...";` statements with `resolver`
+    like [`crate::compile_with_imports`].
+
+    The statements of imported declarations are located within their own files, so no source map is built and
+    an annotated pattern quotes no statements.
+
+    # Errors
+
+    Returns an [`crate::errors::CompilerError`] upon encountering a syntax error, a construct the target does
+    not support or a missing or cyclic import, or for the first warning at [`Strictness::Strict`]
+    */
+    pub fn compile_with_imports(
+        &self,
+        source: &str,
+        resolver: impl Fn(&str) -> Option<String> + 'static,
+    ) -> Result<CompileOutput> {
+        let (ast, flags, warnings) = to_ast_with_flags_imports_and_options(source, &self.options, resolver)?;
+
+        compile_parsed(ast, &flags, warnings, self.target, &self.options, None)
+    }
+}
+
+/// Checks the capture groups of a parsed source, then optimizes, lowers and emits it, which every compile function
+/// does alike. `source` is quoted by annotated patterns and located by the source map, and `None` when the AST
+/// holds statements of other files
+pub(crate) fn compile_parsed(
+    ast: ViableAst,
+    flags: &PatternFlags,
+    mut warnings: Vec<Warning>,
+    target: Target,
+    options: &CompilerOptions,
+    source: Option<&str>,
+) -> Result<CompileOutput> {
+    if options.strictness == Strictness::Lenient && !options.allow_duplicate_capture_names {
+        warnings.extend(duplicate_capture_names(&ast));
+        warnings.sort_by_key(|warning| warning.span().start);
+    } else {
+        check_captures(&ast, options)?;
+    }
+
+    let ast = lower(optimize(ast, options), target, options);
+    check_size(&ast, options)?;
+
+    let (pattern, inline_flags, flags) = emit(&ast, flags, target, options, source.unwrap_or_default())?;
+    let source_map = if options.source_map && options.emit_style == EmitStyle::Compact && source.is_some() {
+        Some(source_map(&ast, &inline_flags, target)?)
+    } else {
+        None
+    };
+
+    Ok(CompileOutput {
+        pattern,
+        flags,
+        captures: capture_groups(&ast),
+        ast,
+        warnings,
+        source_map,
+    })
+}
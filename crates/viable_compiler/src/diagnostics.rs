@@ -0,0 +1,150 @@
+use crate::ast::types::ast::{Span, Spanned};
+use crate::errors::CompilerError;
+use crate::prelude::*;
+use crate::warnings::Warning;
+
+/**
+Renders an error the way rustc does, with the source lines of its spans underlined and its help text.
+
+The first span of the error is underlined with `^`, any other location involved (e.g. the first capture group
+of a duplicate name) with `-`. Errors without a span are rendered as their message and help text.
+
+# Example
+
+```rust
+use viable_compiler::ast::to_ast;
+use viable_compiler::diagnostics::render_error;
+
+let source = "z to a;";
+let error = to_ast(source).unwrap_err();
+
+assert_eq!(
+    render_error(source, &error),
+    "error[E0009]: empty range, `z` comes after `a`\n --> 1:1\n  |\n1 | z to a;\n  | ^^^^^^\n  = help: swap the bounds of the range, i.e. `a to z`\n"
+);
+```
+*/
+#[must_use]
+pub fn render_error(source: &str, error: &CompilerError) -> String {
+    render(source, error, labels(error))
+}
+
+/// Renders an error collected by [`crate::ast::to_ast_all_errors`] like [`render_error`], errors without a span
+/// of their own are underlined at the span of the statement they were found in
+#[must_use]
+pub fn render_spanned_error(source: &str, error: &Spanned<CompilerError>) -> String {
+    let mut labels = labels(&error.node);
+
+    if labels.is_empty() {
+        labels.push((error.span, String::new()));
+    }
+
+    render(source, &error.node, labels)
+}
+
+/// The spans of an error with a label for each, the primary span first and spans not pointing into the
+/// source (e.g. with a line of 0) left out
+fn labels(error: &CompilerError) -> Vec<(Span, String)> {
+    let labels = match error {
+        CompilerError::DuplicateCaptureName {
+            first_span,
+            second_span,
+            ..
+        } => vec![
+            (*second_span, String::from("used again here")),
+            (*first_span, String::from("first used here")),
+        ],
+        CompilerError::DuplicateSymbol {
+            first_span,
+            second_span,
+            ..
+        } => vec![
+            (*second_span, String::from("defined again here")),
+            (*first_span, String::from("first defined here")),
+        ],
+        CompilerError::CaptureInNegativeAssertion {
+            assertion_span,
+            capture_span,
+        } => vec![
+            (*capture_span, String::from("captures nothing")),
+            (*assertion_span, String::from("within this negative assertion")),
+        ],
+        CompilerError::DeniedWarning(Warning::ShadowedVariable {
+            first_span,
+            second_span,
+            ..
+        }) => vec![
+            (*second_span, String::from("declared again here")),
+            (*first_span, String::from("first declared here")),
+        ],
+        CompilerError::RecursiveVariable { cycle, spans, .. } => {
+            // every span is the invocation of the next variable of the cycle
+            let invoked = &cycle[cycle.len().saturating_sub(spans.len())..];
+            spans
+                .iter()
+                .zip(invoked)
+                .map(|(span, name)| (*span, format!("`{name}` is invoked here")))
+                .collect()
+        }
+        _ => error.span().map(|span| (span, String::new())).into_iter().collect(),
+    };
+
+    labels.into_iter().filter(|(span, _)| span.line > 0).collect()
+}
+
+fn render(source: &str, error: &CompilerError, labels: Vec<(Span, String)>) -> String {
+    let code = error.code();
+    let message = error.to_string();
+    let message = message.strip_suffix(&format!(" [{code}]")).unwrap_or(&message);
+
+    let mut output = format!("error[{code}]: {message}\n");
+
+    let labels: Vec<_> = labels
+        .into_iter()
+        .filter(|(span, _)| {
+            span.start <= span.end && source.is_char_boundary(span.start) && source.is_char_boundary(span.end)
+        })
+        .collect();
+    let gutter = labels
+        .iter()
+        .map(|(span, _)| span.line.to_string().len())
+        .max()
+        .unwrap_or_default();
+    let padding = " ".repeat(gutter);
+
+    if let Some((span, _)) = labels.first() {
+        output.push_str(&format!("{padding}--> {}:{}\n", span.line, span.column));
+        output.push_str(&format!("{padding} |\n"));
+    }
+
+    for (index, (span, label)) in labels.iter().enumerate() {
+        let marker = if index == 0 { '^' } else { '-' };
+        let line_start = source[..span.start].rfind('\n').map_or(0, |position| position + 1);
+        let line_end = source[span.start..]
+            .find('\n')
+            .map_or(source.len(), |position| span.start + position);
+        let line = &source[line_start..line_end];
+
+        // tabs are kept so that the underline lines up with the source line
+        let indentation: String = source[line_start..span.start]
+            .chars()
+            .map(|char| if char == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = source[span.start..span.end.min(line_end)].chars().count().max(1);
+        let underline = marker.to_string().repeat(width);
+        let label = if label.is_empty() {
+            String::new()
+        } else {
+            format!(" {label}")
+        };
+
+        output.push_str(&format!("{:>gutter$} | {line}\n", span.line));
+        output.push_str(&format!("{padding} | {indentation}{underline}{label}\n"));
+    }
+
+    if let Some(help) = error.help() {
+        output.push_str(&format!("{padding} = help: {help}\n"));
+    }
+
+    output
+}
@@ -1,69 +1,522 @@
+use crate::ast::types::ast::Span;
+use crate::prelude::*;
+use crate::target::Target;
+use crate::warnings::Warning;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum CompilerError {
-    /// returned when `over` receives an amount that does not parse correctly
-    #[error("could not parse an amount")]
-    CouldNotParseAnAmount,
+    /// returned if a variable is invoked with a different number of arguments than it has parameters
+    #[error("variable `{name}` takes {expected} arguments but {found} were given [E0001]")]
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    /// returned if a capture group is within a negative assertion and
+    /// [`crate::options::CompilerOptions::deny_captures_in_negative_assertions`] is set
+    #[error("capture group within a negative assertion never captures anything [E0061]")]
+    CaptureInNegativeAssertion { assertion_span: Span, capture_span: Span },
+    /// returned if an escape or `char` statement refers to a code point above `0x10FFFF`
+    #[error("code point `{0}` is out of range, the maximum is 10FFFF [E0002]")]
+    CodePointOutOfRange(String),
+    /// returned when an amount (e.g. of `over` or `3 of`) is not a whole number or is too large
+    #[error("could not parse the amount `{0}` [E0003]")]
+    CouldNotParseAnAmount(String),
+    /// returned for the first warning when compiling at [`crate::options::Strictness::Strict`]
+    #[error("{0} [E0063]")]
+    DeniedWarning(Warning),
+    /// returned if two capture groups share a name, unless [`crate::options::CompilerOptions::allow_duplicate_capture_names`]
+    /// is set
+    #[error("the capture group name `{name}` is used more than once [E0004]")]
+    DuplicateCaptureName {
+        name: String,
+        first_span: Span,
+        second_span: Span,
+    },
+    /// returned if a flag is declared more than once in a `flags` block
+    #[error("the flag `{0}` is declared more than once [E0005]")]
+    DuplicateFlag(String),
+    /// returned if a parameterized variable declares the same parameter more than once
+    #[error("the parameter `{0}` is declared more than once [E0006]")]
+    DuplicateParameter(String),
+    /// returned if a custom symbol is named like a built in symbol or defined more than once, spans of definitions
+    /// outside of the source (built in symbols and [`crate::options::CompilerOptions::custom_symbols`]) are empty
+    #[error("the symbol `<{name}>` is already defined [E0058]")]
+    DuplicateSymbol {
+        name: String,
+        first_span: Span,
+        second_span: Span,
+    },
+    /// returned if an `either` group has no branches once its variables are expanded
+    #[error("unexpected empty either group [E0007]")]
+    EmptyAlternation,
+    /// returned if a quantifier is applied to an empty block (e.g. `3 of {}`) or to one that matches nothing
+    /// once its variables are expanded
+    #[error("unexpected empty block in quantifier [E0008]")]
+    EmptyQuantifiedBlock,
+    /// returned if a range starts after it ends (e.g. `z to a`) and therefore matches nothing
+    #[error("empty range, `{start}` comes after `{end}` [E0009]")]
+    EmptyRange { start: char, end: char, span: Span },
+    /// returned if files import each other, directly or through other files
+    #[error("import cycle ({}) [E0010]", chain.join(" -> "))]
+    ImportCycle { chain: Vec<String> },
+    /// returned if an imported file fails to convert, the message ends with the code of the imported file's error
+    /// rather than its own
+    #[error("in import `{path}`: {error}")]
+    ImportError { path: String, error: Box<CompilerError> },
+    /// returned if the import resolver cannot find an imported file
+    #[error("could not find the imported file `{0}` [E0012]")]
+    ImportNotFound(String),
+    /// returned if a numbered backreference refers to a capture group that does not exist
+    #[error("backreference to capture group {index}, but the pattern has {group_count} capture groups [E0013]")]
+    InvalidBackreferenceIndex { index: usize, group_count: usize },
+    /// returned if a capture group name breaks the naming rules of the selected target
+    #[error("invalid capture group name `{name}` for {target}, {reason} [E0014]")]
+    InvalidCaptureName {
+        name: String,
+        target: Target,
+        reason: &'static str,
+    },
+    /// returned if a `\\x` or `\\u{...}` escape contains invalid hex digits
+    #[error("invalid hex escape `{0}` [E0015]")]
+    InvalidHexEscape(String),
     /// returned when a quantifier range (`3 to 5 of ...`) does not
     /// parse correctly or when the start of the range is larger then the end
-    #[error("usage of an invalid quantifier range")]
-    InvalidQuantifierRange,
-    /// returned when an expected positional node does not exist
-    #[error("encountered a missing positional node")]
-    MissingNode,
+    #[error("invalid quantifier range `{0}` [E0016]")]
+    InvalidQuantifierRange(String),
+    /// returned if a quantifier is marked as both `lazy` and `possessive`
+    #[error("a quantifier cannot be both lazy and possessive [E0017]")]
+    LazyPossessiveQuantifier,
+    /// returned if a `flags` block is not the first statement of the source
+    #[error("a flags block must be the first statement [E0018]")]
+    MisplacedFlagsBlock,
+    /// returned if a source contains an import but was not converted with an import resolver
+    #[error("cannot import `{0}` without an import resolver [E0019]")]
+    MissingImportResolver(String),
+    /// returned when a node of the parse tree lacks a child the grammar guarantees, naming the construct being
+    /// parsed
+    #[error("expected an inner node while parsing `{context}` at {}:{} [E0020]", .span.line, .span.column)]
+    MissingNode { context: &'static str, span: Span },
+    /// returned if a statement isn't followed by the `;` ending it, the span pointing just past the statement
+    #[error("missing `;` at the end of a statement [E0068]")]
+    MissingStatementTerminator { span: Span },
     /// returned if the root node does not exist
-    #[error("missing root node")]
+    #[error("missing root node [E0021]")]
     MissingRootNode,
     /// returned if `not <char>` is encountered
-    #[error("negative char not allowed")]
+    #[error("negative char not allowed [E0022]")]
     NegativeCharNotAllowed,
-    /// returned if `not <end>` is encountered
-    #[error("negative end not allowed")]
-    NegativeEndNotAllowed,
-    /// returned if `not <start>` is encountered
-    #[error("negative start not allowed")]
-    NegativeStartNotAllowed,
+    /// returned if a custom symbol is negated, e.g. `not <uuid_char>`
+    #[error("the custom symbol `<{0}>` cannot be negated [E0059]")]
+    NegativeCustomSymbol(String),
+    /// returned if `not <end>`, `not <line_end>` or `not <input_end>` is encountered, holding the symbol
+    #[error("negative end `<{0}>` not allowed [E0023]")]
+    NegativeEndNotAllowed(String),
+    /// returned if `not <grapheme>` is encountered
+    #[error("negative grapheme not allowed [E0056]")]
+    NegativeGraphemeNotAllowed,
+    /// returned if `not <line_break>` is encountered
+    #[error("negative line break not allowed [E0057]")]
+    NegativeLineBreakNotAllowed,
+    /// returned if `not <start>`, `not <line_start>` or `not <input_start>` is encountered, holding the symbol
+    #[error("negative start `<{0}>` not allowed [E0024]")]
+    NegativeStartNotAllowed(String),
+    /// returned if blocks, or the expansions of variables, are nested deeper than the nesting limit
+    #[error("statements are nested more than {limit} levels deep [E0051]")]
+    NestingTooDeep { limit: usize },
+    /// returned if a non-ASCII character or a unicode class is matched by byte, see
+    /// [`crate::options::CompilerOptions::unicode_mode`]
+    #[error("non-ASCII character or unicode class in bytes mode [E0067]")]
+    NonAsciiInBytesMode { span: Span },
+    /// returned if `nothing;` is encountered anywhere but directly within an `either` group, including when quantified
+    #[error("`nothing` can only be a branch of an `either` group [E0062]")]
+    NothingOutsideEither,
     /// returned if the given input cannot be parsed
-    #[error("{0}")]
-    ParseError(String),
+    ///
+    /// `found` is the text at `offset` up to the next whitespace, empty at the end of the input. `suggestion` is
+    /// the kind of group or assertion `found` is likely a misspelling of, e.g. `capture` for `captur { ... }`
+    #[error(
+        "expected {} at {line}:{column}, found {}{} [E0025]",
+        format_expected(.expected),
+        format_found(.found),
+        format_suggestion(.suggestion.as_deref())
+    )]
+    ParseError {
+        expected: Vec<String>,
+        found: String,
+        line: usize,
+        column: usize,
+        offset: usize,
+        suggestion: Option<String>,
+    },
+    /// returned if the estimated length of the emitted pattern exceeds
+    /// [`crate::options::CompilerOptions::max_estimated_regex_len`], see [`crate::ast::AstMetrics`]
+    #[error("the pattern is estimated to be {estimated} characters long, over the limit of {limit} [E0055]")]
+    PatternTooLarge { estimated: usize, limit: usize },
+    /// returned if a variable is invoked within its own declaration, directly or through other variables
+    ///
+    /// `spans` are the invocations forming the cycle, starting with the one the error was found at
+    #[error("recursive variable `{name}` ({}) [E0026]", cycle.join(" -> "))]
+    RecursiveVariable {
+        name: String,
+        cycle: Vec<String>,
+        spans: Vec<Span>,
+    },
+    /// returned if a quantifier repeats its `lazy` or `possessive` modifier, holding the modifier
+    #[error("the quantifier modifier `{0}` is repeated [E0069]")]
+    RepeatedQuantifierModifier(String),
+    /// returned if an escape or `char` statement refers to a surrogate code point (`D800` to `DFFF`)
+    #[error("code point `{0}` is a surrogate and cannot be matched on its own [E0027]")]
+    SurrogateCodePoint(String),
+    /// returned by [`crate::compile_to_regex`] if the `regex` crate fails to build the emitted pattern, holding the
+    /// message of the `regex` crate and the pattern it rejected
+    #[error("the target rejected the emitted pattern `{emitted}`: {message} [E0053]")]
+    TargetRejectedOutput { message: String, emitted: String },
+    /// returned if a `no match` test case expects captures, which it can't have
+    #[error("`no match` test case expects captures [E0054]")]
+    TestCapturesWithoutMatch,
+    /// returned if a `raw_regex` statement closes a group or class it doesn't open, or leaves one open
+    #[error("unbalanced `{unbalanced}` in raw regex [E0064]")]
+    UnbalancedRawRegex { unbalanced: char, span: Span },
     /// returned if an assertion is quantified
-    #[error("unexpected assertion in quantifier")]
+    #[error("unexpected assertion in quantifier [E0028]")]
     UnexpectedAssertionInQuantifier,
+    /// returned if a word boundary is quantified, boundaries are zero-width
+    #[error("unexpected word boundary in quantifier [E0029]")]
+    UnexpectedBoundaryInQuantifier,
     /// returned if a non capture group (e.g. match) has an identifier
-    #[error("unexpected identifier for non capture group")]
+    #[error("unexpected identifier for non capture group [E0030]")]
     UnexpectedIdentifierForNonCaptureGroup,
+    /// returned if a class contains an atom that is not a single character
+    #[error("unexpected atom longer than a single character in class [E0031]")]
+    UnexpectedMultiCharacterAtomInClass,
+    /// returned if a class contains a negative range
+    #[error("unexpected negative range in class, negate the class instead [E0032]")]
+    UnexpectedNegativeRangeInClass,
     /// returned if a quantfier is nested
-    #[error("unexpected quantifier in quantifier")]
+    #[error("unexpected quantifier in quantifier [E0033]")]
     UnexpectedQuantifierInQuantifier,
     /// (unreachable) returned if a skipped node (currently only EOF) is quantified
-    #[error("unexpected skipped node in quantifier")]
+    #[error("unexpected skipped node in quantifier [E0034]")]
     UnexpectedSkippedNodeInQuantifier,
     /// returned if a special symbol (`<start>` or `<end>`) is quantified
-    #[error("unexpected special symbol in quantifier")]
+    #[error("unexpected special symbol in quantifier [E0035]")]
     UnexpectedSpecialSymbolInQuantifier,
-    /// returned if a variable invocation is quantified
-    #[error("unexpected variable invocation in quantifier")]
-    UnexpectedVariableInvocationInQuantifier,
-    /// returned if a variable invocation is not preceeded by a declaration
-    #[error("usage of an uninitialized variable")]
-    UninitializedVariable,
+    /// returned if a class contains a symbol that cannot be expressed within a class (e.g. `<char>`)
+    #[error("unexpected symbol in class [E0036]")]
+    UnexpectedSymbolInClass,
+    /// returned if a variable is declared within a quantified block
+    #[error("unexpected variable declaration in quantifier [E0037]")]
+    UnexpectedVariableDeclarationInQuantifier,
+    /// returned if a variable invocation is not preceeded by a declaration, with the declared variable whose name
+    /// is closest to `name` if there is a likely typo
+    #[error(
+        "usage of an uninitialized variable `.{name}`{} [E0038]",
+        format_suggestion(.suggestion.as_ref().map(|suggestion| format!(".{suggestion}")).as_deref())
+    )]
+    UninitializedVariable { name: String, suggestion: Option<String> },
+    /// returned if a backreference refers to a capture group name that was not declared before it
+    #[error("backreference to an unknown capture group `{0}` [E0039]")]
+    UnknownBackreference(String),
+    /// returned if a conditional refers to a capture group that isn't declared before it
+    #[error("conditional on an unknown capture group `{0}` [E0065]")]
+    UnknownConditionalCapture(String),
+    /// returned if a `flags` block contains an unknown flag name
+    #[error("unknown flag `{0}`, expected one of insensitive, multiline, dot_all, or unicode [E0040]")]
+    UnknownFlag(String),
+    /// returned if the body of a parameterized variable invokes a name that is neither a parameter nor a variable
+    #[error("unknown parameter `{0}` [E0041]")]
+    UnknownParameter(String),
+    /// returned if `recurse name;` refers to a capture group name that isn't declared anywhere in the pattern
+    #[error("recursion into an unknown capture group `{0}` [E0066]")]
+    UnknownRecursionGroup(String),
+    /// returned if a `script::` or `property::` symbol names an unknown unicode script or property
+    #[error("unknown unicode property `{name}`{} [E0042]", format_suggestion(.suggestion.as_deref()))]
+    UnknownUnicodeProperty { name: String, suggestion: Option<String> },
     /// (unreachable) returned if an assertion is not of a recognized kind
-    #[error("usage of an unrecognized assertion")]
+    #[error("usage of an unrecognized assertion [E0043]")]
     UnrecognizedAssertion,
     /// (unreachable) returned if a group is not of a recognized kind
-    #[error("usage of an unrecognized group")]
+    #[error("usage of an unrecognized group [E0044]")]
     UnrecognizedGroup,
-    /// (unreachable) returned if any parsed syntax is not recognized
-    #[error("usage of unrecognized syntax")]
+    /// returned if a partial AST from [`crate::ast::to_ast_lenient`] is compiled, otherwise unreachable
+    #[error("usage of unrecognized syntax [E0045]")]
     UnrecognizedSyntax,
-    /// returned if any parsed symbol is not recognized
-    #[error("usage of an unrecognized symbol")]
-    UnrecognizedSymbol,
+    /// returned if any parsed symbol is not recognized, with the built in or custom symbol whose name is closest
+    /// to `name` if there is a likely typo
+    #[error(
+        "usage of an unrecognized symbol `<{name}>`{} [E0046]",
+        format_suggestion(.suggestion.as_ref().map(|suggestion| format!("<{suggestion}>")).as_deref())
+    )]
+    UnrecognizedSymbol { name: String, suggestion: Option<String> },
     /// returned if any parsed symbol namespace is not recognized
-    #[error("usage of an unrecognized symbol namespace")]
+    #[error("usage of an unrecognized symbol namespace [E0047]")]
     UnrecognizedSymbolNamespace,
     /// returned if any parsed unicode category
-    #[error("usage of an unrecognized unicode category")]
+    #[error("usage of an unrecognized unicode category [E0048]")]
     UnrecognizedUnicodeCategory,
+    /// returned if a construct cannot be expressed by the selected target
+    #[error("{construct} is not supported by {target} [E0049]")]
+    UnsupportedByTarget {
+        construct: &'static str,
+        target: Target,
+        span: Span,
+    },
+    /// returned if the body of a lookbehind assertion can match strings of different lengths and the target
+    /// only accepts a fixed length (Python `re`), or if it can match strings of any length and the target only
+    /// accepts a bounded one (PCRE2)
+    #[error("usage of a variable length lookbehind assertion, which {target} does not support [E0060]")]
+    VariableLengthLookbehind { span: Span, target: Target },
+    /// returned if `up to` receives an amount of zero, which could only ever match nothing
+    #[error("usage of `up to 0` [E0050]")]
+    UpToZero,
+    /// returned if a quantifier repeats its expression zero times (e.g. `0 of` or `0 to 0 of`), which only ever
+    /// matches an empty string, unless [`crate::options::CompilerOptions::allow_zero_quantifiers`] is set
+    #[error("quantifier repeats its expression zero times [E0052]")]
+    ZeroQuantifier,
+}
+
+impl CompilerError {
+    /// A stable code identifying the kind of error (e.g. `E0001`), also appended to the error message
+    ///
+    /// Codes are never reused or changed, new kinds of errors are given the next free code
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::ArityMismatch { .. } => "E0001",
+            Self::CodePointOutOfRange(..) => "E0002",
+            Self::CouldNotParseAnAmount(_) => "E0003",
+            Self::DeniedWarning(_) => "E0063",
+            Self::DuplicateCaptureName { .. } => "E0004",
+            Self::DuplicateFlag(..) => "E0005",
+            Self::DuplicateParameter(..) => "E0006",
+            Self::DuplicateSymbol { .. } => "E0058",
+            Self::EmptyAlternation => "E0007",
+            Self::EmptyQuantifiedBlock => "E0008",
+            Self::EmptyRange { .. } => "E0009",
+            Self::ImportCycle { .. } => "E0010",
+            Self::ImportError { .. } => "E0011",
+            Self::ImportNotFound(..) => "E0012",
+            Self::InvalidBackreferenceIndex { .. } => "E0013",
+            Self::InvalidCaptureName { .. } => "E0014",
+            Self::InvalidHexEscape(..) => "E0015",
+            Self::InvalidQuantifierRange(_) => "E0016",
+            Self::LazyPossessiveQuantifier => "E0017",
+            Self::MisplacedFlagsBlock => "E0018",
+            Self::MissingImportResolver(..) => "E0019",
+            Self::MissingNode { .. } => "E0020",
+            Self::MissingRootNode => "E0021",
+            Self::MissingStatementTerminator { .. } => "E0068",
+            Self::NegativeCharNotAllowed => "E0022",
+            Self::NegativeCustomSymbol(_) => "E0059",
+            Self::NegativeEndNotAllowed(_) => "E0023",
+            Self::NegativeGraphemeNotAllowed => "E0056",
+            Self::NegativeLineBreakNotAllowed => "E0057",
+            Self::NegativeStartNotAllowed(_) => "E0024",
+            Self::ParseError { .. } => "E0025",
+            Self::PatternTooLarge { .. } => "E0055",
+            Self::RecursiveVariable { .. } => "E0026",
+            Self::RepeatedQuantifierModifier(_) => "E0069",
+            Self::SurrogateCodePoint(..) => "E0027",
+            Self::TargetRejectedOutput { .. } => "E0053",
+            Self::TestCapturesWithoutMatch => "E0054",
+            Self::UnbalancedRawRegex { .. } => "E0064",
+            Self::UnexpectedAssertionInQuantifier => "E0028",
+            Self::UnexpectedBoundaryInQuantifier => "E0029",
+            Self::UnexpectedIdentifierForNonCaptureGroup => "E0030",
+            Self::UnexpectedMultiCharacterAtomInClass => "E0031",
+            Self::UnexpectedNegativeRangeInClass => "E0032",
+            Self::UnexpectedQuantifierInQuantifier => "E0033",
+            Self::UnexpectedSkippedNodeInQuantifier => "E0034",
+            Self::UnexpectedSpecialSymbolInQuantifier => "E0035",
+            Self::UnexpectedSymbolInClass => "E0036",
+            Self::UnexpectedVariableDeclarationInQuantifier => "E0037",
+            Self::UninitializedVariable { .. } => "E0038",
+            Self::UnknownBackreference(..) => "E0039",
+            Self::UnknownConditionalCapture(..) => "E0065",
+            Self::UnknownFlag(..) => "E0040",
+            Self::UnknownParameter(..) => "E0041",
+            Self::UnknownRecursionGroup(..) => "E0066",
+            Self::UnknownUnicodeProperty { .. } => "E0042",
+            Self::UnrecognizedAssertion => "E0043",
+            Self::UnrecognizedGroup => "E0044",
+            Self::UnrecognizedSyntax => "E0045",
+            Self::UnrecognizedSymbol { .. } => "E0046",
+            Self::UnrecognizedSymbolNamespace => "E0047",
+            Self::UnrecognizedUnicodeCategory => "E0048",
+            Self::UnsupportedByTarget { .. } => "E0049",
+            Self::UpToZero => "E0050",
+            Self::VariableLengthLookbehind { .. } => "E0060",
+            Self::CaptureInNegativeAssertion { .. } => "E0061",
+            Self::NestingTooDeep { .. } => "E0051",
+            Self::NonAsciiInBytesMode { .. } => "E0067",
+            Self::NothingOutsideEither => "E0062",
+            Self::ZeroQuantifier => "E0052",
+        }
+    }
+
+    /// A one line suggestion on how to fix the error, if there is one
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub fn help(&self) -> Option<String> {
+        let help = match self {
+            Self::ArityMismatch { name, expected, .. } => format!("pass {expected} arguments to `.{name}`"),
+            Self::CodePointOutOfRange(_) => String::from("use a code point between 0 and 10FFFF"),
+            Self::CouldNotParseAnAmount(_) => String::from("use a whole number, e.g. `3 or more of`"),
+            Self::DeniedWarning(_) => String::from("address the warning, or compile at the standard strictness"),
+            Self::DuplicateCaptureName { .. } => String::from("rename one of the capture groups"),
+            Self::DuplicateFlag(_) => String::from("remove the repeated flag"),
+            Self::DuplicateParameter(_) => String::from("rename or remove the repeated parameter"),
+            Self::DuplicateSymbol { .. } => String::from("rename the custom symbol"),
+            Self::EmptyAlternation => String::from("add a branch to the either group or remove it"),
+            Self::EmptyQuantifiedBlock => String::from("add a statement to the block or remove the quantifier"),
+            Self::EmptyRange { start, end, .. } => format!("swap the bounds of the range, i.e. `{end} to {start}`"),
+            Self::ImportCycle { .. } => String::from("remove one of the imports of the cycle"),
+            Self::ImportError { error, .. } => return error.help(),
+            Self::ImportNotFound(_) => String::from("check the path of the import"),
+            Self::InvalidBackreferenceIndex { group_count: 0, .. } => {
+                String::from("add a capture group for the backreference to refer to")
+            }
+            Self::InvalidBackreferenceIndex { group_count, .. } => {
+                format!("use a capture group index between 1 and {group_count}")
+            }
+            Self::InvalidCaptureName { .. } => String::from("rename the capture group"),
+            Self::InvalidHexEscape(_) => String::from("hex escapes can only contain the digits 0 to 9 and a to f"),
+            Self::InvalidQuantifierRange(_) => String::from("use a range whose start is not larger than its end"),
+            Self::LazyPossessiveQuantifier => String::from("remove either `lazy` or `possessive`"),
+            Self::MisplacedFlagsBlock => String::from("move the flags block to the start of the source"),
+            Self::MissingImportResolver(_) => String::from("compile with an import resolver to use imports"),
+            Self::NegativeCharNotAllowed
+            | Self::NegativeCustomSymbol(_)
+            | Self::NegativeEndNotAllowed(_)
+            | Self::NegativeGraphemeNotAllowed
+            | Self::NegativeLineBreakNotAllowed
+            | Self::NegativeStartNotAllowed(_) => String::from("remove the `not`"),
+            Self::NestingTooDeep { .. } => {
+                String::from("move some of the nested blocks into variables at the top level")
+            }
+            Self::NonAsciiInBytesMode { .. } => String::from("enable unicode mode, or count the quantifier in `chars`"),
+            Self::NothingOutsideEither => {
+                String::from("remove the statement, or wrap it and its alternatives in an `either` group")
+            }
+            Self::PatternTooLarge { .. } => {
+                String::from("lower the bounds of the quantifiers or move repeated statements out of them")
+            }
+            Self::RecursiveVariable { .. } => String::from("variables cannot invoke themselves, break the cycle"),
+            Self::RepeatedQuantifierModifier(modifier) => format!("remove one of the `{modifier}` modifiers"),
+            Self::SurrogateCodePoint(_) => String::from("match the full code point instead of one of its surrogates"),
+            Self::TargetRejectedOutput { .. } => {
+                String::from("the pattern may exceed a size limit of the engine, otherwise please report it as a bug")
+            }
+            Self::TestCapturesWithoutMatch => String::from("remove the captures or change `no match` to `match`"),
+            Self::UnbalancedRawRegex { .. } => String::from("balance the parentheses and brackets, or escape them"),
+            Self::UnexpectedAssertionInQuantifier
+            | Self::UnexpectedBoundaryInQuantifier
+            | Self::UnexpectedSpecialSymbolInQuantifier => {
+                String::from("this statement matches no characters, remove the quantifier")
+            }
+            Self::UnexpectedIdentifierForNonCaptureGroup => {
+                String::from("only `capture` groups may be named; remove the identifier or change `match` to `capture`")
+            }
+            Self::UnexpectedMultiCharacterAtomInClass => {
+                String::from("split the atom into one statement per character")
+            }
+            Self::UnexpectedNegativeRangeInClass => String::from("use a positive range in a `not class { ... }`"),
+            Self::UnexpectedQuantifierInQuantifier => String::from("wrap the inner quantifier in a `match` group"),
+            Self::UnexpectedSymbolInClass => String::from("move the symbol out of the class into an `either` group"),
+            Self::UnexpectedVariableDeclarationInQuantifier => {
+                String::from("declare the variable before the quantifier")
+            }
+            Self::UninitializedVariable { .. } => String::from("declare the variable with `let` before invoking it"),
+            Self::UnknownBackreference(name) => format!("declare a capture group named `{name}` before referencing it"),
+            Self::UnknownConditionalCapture(name) => format!("declare a capture group `{name}` before the conditional"),
+            Self::UnknownParameter(name) => format!("add `{name}` to the parameters of the variable"),
+            Self::UnknownRecursionGroup(name) => format!("declare a capture group named `{name}`"),
+            Self::UnrecognizedSymbolNamespace => {
+                String::from("use one of the `category`, `script` or `property` namespaces")
+            }
+            Self::UnsupportedByTarget { .. } => String::from("compile for another target or avoid the construct"),
+            Self::UpToZero => String::from("use an amount of at least 1"),
+            Self::CaptureInNegativeAssertion { .. } => {
+                String::from("use a `match` group within the assertion, or move the capture out of it")
+            }
+            Self::VariableLengthLookbehind { target, .. } if target.supports_variable_length_lookbehind() => {
+                String::from("bound the quantifiers of the assertion, e.g. `1 to 8 of` rather than `some of`")
+            }
+            Self::VariableLengthLookbehind { .. } => {
+                String::from("make every branch and quantifier of the assertion match the same number of characters")
+            }
+            Self::ZeroQuantifier => String::from("remove the statement, it matches nothing"),
+            Self::MissingStatementTerminator { .. } => String::from("add `;` at the end of the statement"),
+            Self::MissingNode { .. }
+            | Self::MissingRootNode
+            | Self::ParseError { .. }
+            | Self::UnexpectedSkippedNodeInQuantifier
+            | Self::UnknownFlag(_)
+            | Self::UnknownUnicodeProperty { .. }
+            | Self::UnrecognizedAssertion
+            | Self::UnrecognizedGroup
+            | Self::UnrecognizedSyntax
+            | Self::UnrecognizedSymbol { .. }
+            | Self::UnrecognizedUnicodeCategory => return None,
+        };
+
+        Some(help)
+    }
+
+    /// The span of the source the error points at, if the error holds one, for errors involving several
+    /// locations the one the error was found at (e.g. the second capture group of a duplicate name)
+    #[must_use]
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::DuplicateCaptureName { second_span, .. } | Self::DuplicateSymbol { second_span, .. } => {
+                Some(*second_span)
+            }
+            Self::RecursiveVariable { spans, .. } => spans.first().copied(),
+            Self::CaptureInNegativeAssertion { capture_span, .. } => Some(*capture_span),
+            Self::DeniedWarning(warning) => Some(warning.span()),
+            Self::EmptyRange { span, .. }
+            | Self::MissingNode { span, .. }
+            | Self::MissingStatementTerminator { span }
+            | Self::NonAsciiInBytesMode { span }
+            | Self::UnbalancedRawRegex { span, .. }
+            | Self::UnsupportedByTarget { span, .. }
+            | Self::VariableLengthLookbehind { span, .. } => Some(*span),
+            Self::ParseError {
+                found,
+                line,
+                column,
+                offset,
+                ..
+            } => Some(Span {
+                start: *offset,
+                end: offset + found.len(),
+                line: u32::try_from(*line).unwrap_or(u32::MAX),
+                column: u32::try_from(*column).unwrap_or(u32::MAX),
+            }),
+            _ => None,
+        }
+        .filter(|span| span.line > 0)
+    }
+}
+
+fn format_expected(expected: &[String]) -> String {
+    match expected {
+        [] => String::from("a valid statement"),
+        [single] => single.clone(),
+        [rest @ .., last] => format!("{}, or {last}", rest.join(", ")),
+    }
+}
+
+fn format_suggestion(suggestion: Option<&str>) -> String {
+    suggestion.map_or_else(String::new, |suggestion| format!(", did you mean `{suggestion}`?"))
+}
+
+fn format_found(found: &str) -> String {
+    if found.is_empty() {
+        String::from("end of input")
+    } else {
+        format!("`{found}`")
+    }
 }
@@ -1,21 +1,40 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code)]
 #![allow(clippy::module_name_repetitions)]
 
-#[cfg(not(feature = "fuzzer"))]
-mod ast;
-#[cfg(feature = "fuzzer")]
+extern crate alloc;
+
 pub mod ast;
+mod builder;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
 pub mod errors;
-mod format;
+#[cfg(feature = "regex")]
+pub mod matching;
+pub mod metadata;
+pub mod options;
+mod prelude;
 mod regex;
+pub mod target;
+pub mod testing;
+pub mod tokens;
 mod types;
+pub mod warnings;
 
-use ast::to_ast;
-use format::format;
-#[cfg(not(feature = "fuzzer"))]
-use regex::ast_to_regex;
+use self::builder::compile_parsed;
+pub use self::builder::{CompileOutput, Compiler};
 #[cfg(feature = "fuzzer")]
-pub use regex::ast_to_regex::ast_to_regex;
+pub use self::regex::ast_to_regex::ast_to_regex;
+#[cfg(not(feature = "fuzzer"))]
+use self::regex::ast_to_regex::ast_to_regex;
+use self::regex::{ast_to_annotated_regex, flags_to_letters, flags_to_regex};
+use crate::prelude::*;
+use ast::source_to_ast::{to_ast_with_flags_and_options, to_ast_with_flags_imports_and_options};
+use ast::types::ast::{Flag, PatternFlags, ViableAst};
+use ast::{approximate_graphemes, check_astral_ranges, check_bytes_mode, check_capture_names, optimize};
+use metadata::{CaptureInfo, SourceMap};
+use options::{CompilerOptions, EmitStyle, Strictness};
+use target::Target;
 use types::Result;
 
 /**
@@ -37,11 +56,416 @@ assert_eq!(output.unwrap(), "A{1,5}");
 ```
 */
 pub fn compiler(source: &str) -> Result<String> {
-    let formatted_source = format(source);
+    compile_for(source, Target::default())
+}
+
+/**
+Compiles Viable source code to a regular expression for a specific [`Target`].
+
+Flags declared in a `flags` block are prefixed to the output using the target's inline flag syntax.
+For targets without one, use [`compile_with_flags`].
+
+# Errors
+
+Returns an [`errors::CompilerError`] upon encountering a syntax error or a construct the target does not support
+
+# Example
+
+```rust
+use viable_compiler::compile_for;
+use viable_compiler::target::Target;
+
+let source = r#"possessive some of "A";"#;
+let output = compile_for(source, Target::Pcre2);
+
+assert_eq!(output.unwrap(), "A++");
+```
+*/
+pub fn compile_for(source: &str, target: Target) -> Result<String> {
+    compile_with_options(source, target, &CompilerOptions::default())
+}
+
+/**
+Compiles Viable source code to a regular expression for a specific [`Target`] with the given [`CompilerOptions`].
+
+# Errors
+
+Returns an [`errors::CompilerError`] upon encountering a syntax error or a construct the target does not support
+
+# Example
+
+```rust
+use viable_compiler::compile_with_options;
+use viable_compiler::options::CompilerOptions;
+use viable_compiler::target::Target;
+
+let source = r#"either { "a"; "b"; }"#;
+let options = CompilerOptions {
+    either_to_class: false,
+    ..CompilerOptions::default()
+};
+let output = compile_with_options(source, Target::default(), &options);
+
+assert_eq!(output.unwrap(), "(?:a|b)");
+```
+*/
+pub fn compile_with_options(source: &str, target: Target, options: &CompilerOptions) -> Result<String> {
+    let (ast, flags, warnings) = to_ast_with_flags_and_options(source, options)?;
+    let output = compile_parsed(ast, &flags, warnings, target, options, Some(source))?;
+
+    inline_pattern(output, &flags, target)
+}
+
+/**
+Compiles Viable source code to a regular expression for a specific [`Target`], returning the pattern
+along with the flags to pass to the engine alongside it.
+
+Targets with an inline flag syntax have their flags prefixed to the pattern and return no separate flags.
+
+# Errors
+
+Returns an [`errors::CompilerError`] upon encountering a syntax error or a construct the target does not support
+
+# Example
+
+```rust
+use viable_compiler::compile_with_flags;
+use viable_compiler::target::Target;
+
+let source = r#"flags { insensitive; multiline; } "A";"#;
+let output = compile_with_flags(source, Target::EcmaScript);
+
+assert_eq!(output.unwrap(), ("A".to_owned(), "im".to_owned()));
+```
+*/
+pub fn compile_with_flags(source: &str, target: Target) -> Result<(String, String)> {
+    let output = Compiler::new().target(target).compile(source)?;
+
+    Ok((output.pattern, output.flags))
+}
+
+/**
+Compiles Viable source code to a regular expression for a specific [`Target`], resolving
+`import "...";` statements with `resolver`.
+
+The resolver receives the path of each imported file, relative to the root source, and returns its source
+or `None` if it does not exist. See [`ast::to_ast_with_imports`].
+
+# Errors
+
+Returns an [`errors::CompilerError`] upon encountering a syntax error, a construct the target does not support
+or a missing or cyclic import
+
+# Example
+
+```rust
+use viable_compiler::compile_with_imports;
+use viable_compiler::target::Target;
+
+let source = r#"import "common.viable"; .greeting;"#;
+let output = compile_with_imports(source, Target::default(), |path| {
+    (path == "common.viable").then(|| r#"let .greeting = { "hello"; }"#.to_owned())
+});
+
+assert_eq!(output.unwrap(), "hello");
+```
+*/
+pub fn compile_with_imports(
+    source: &str,
+    target: Target,
+    resolver: impl Fn(&str) -> Option<String> + 'static,
+) -> Result<String> {
+    compile_with_imports_and_metadata(source, target, &CompilerOptions::default(), resolver).map(|(pattern, _)| pattern)
+}
+
+/**
+Compiles Viable source code to a regular expression for a specific [`Target`] with the given [`CompilerOptions`],
+returning the pattern along with its capture groups in the order the pattern numbers them, and its
+[`SourceMap`] with [`CompilerOptions::source_map`].
+
+A variable containing a capture group contributes a separate group each time it is invoked, see [`CaptureInfo`].
+Invoking a variable with a named capture more than once requires
+[`CompilerOptions::allow_duplicate_capture_names`].
+
+The source map locates the statements of a compact pattern, so isn't built for [`EmitStyle::Annotated`].
+
+# Errors
+
+Returns an [`errors::CompilerError`] upon encountering a syntax error or a construct the target does not support
+
+# Example
+
+```rust
+use viable_compiler::compile_with_metadata;
+use viable_compiler::options::CompilerOptions;
+use viable_compiler::target::Target;
+
+let source = r#"capture { "a"; } capture name { "b"; }"#;
+let (output, captures, _) = compile_with_metadata(source, Target::default(), &CompilerOptions::default()).unwrap();
+
+assert_eq!(output, "(a)(?<name>b)");
+assert_eq!(captures[1].index, 2);
+assert_eq!(captures[1].name.as_deref(), Some("name"));
+```
+*/
+pub fn compile_with_metadata(
+    source: &str,
+    target: Target,
+    options: &CompilerOptions,
+) -> Result<(String, Vec<CaptureInfo>, Option<SourceMap>)> {
+    let (ast, flags, warnings) = to_ast_with_flags_and_options(source, options)?;
+    let output = compile_parsed(ast, &flags, warnings, target, options, Some(source))?;
+    let (captures, source_map) = (output.captures.clone(), output.source_map.clone());
+
+    Ok((inline_pattern(output, &flags, target)?, captures, source_map))
+}
 
-    let ast = to_ast(&formatted_source)?;
+/**
+Compiles an AST, e.g. one composed with [`ViableAst::concat`] and the other combinators, to a regular expression
+for a specific [`Target`] with the given [`CompilerOptions`], along with its capture groups like
+[`compile_with_metadata`].
+
+# Errors
+
+Returns an [`errors::CompilerError`] upon encountering a construct the target does not support, or
+[`errors::CompilerError::DuplicateCaptureName`] for capture groups sharing a name, e.g. from different parts
+
+# Example
+
+```rust
+use viable_compiler::ast::to_ast;
+use viable_compiler::ast::types::ast::ViableAst;
+use viable_compiler::compile_ast;
+use viable_compiler::options::CompilerOptions;
+use viable_compiler::target::Target;
+
+let digits = to_ast("some of <digit>;").unwrap();
+let ast = ViableAst::concat(vec![ViableAst::atom("v"), digits.captured(None)]);
+let (output, captures) = compile_ast(&ast, Target::default(), &CompilerOptions::default()).unwrap();
+
+assert_eq!(output, r"v(\d+)");
+assert_eq!(captures.len(), 1);
+```
+*/
+pub fn compile_ast(ast: &ViableAst, target: Target, options: &CompilerOptions) -> Result<(String, Vec<CaptureInfo>)> {
+    // without a source, annotated patterns have no statements to quote
+    let output = compile_parsed(ast.clone(), &PatternFlags::default(), Vec::new(), target, options, None)?;
+
+    Ok((output.pattern, output.captures))
+}
+
+/**
+Compiles Viable source code to a regular expression for a specific [`Target`] like [`compile_with_metadata`],
+resolving `import "...";` statements with `resolver` like [`compile_with_imports`].
+
+# Errors
 
-    let output = ast_to_regex(&ast);
+Returns an [`errors::CompilerError`] upon encountering a syntax error, a construct the target does not support
+or a missing or cyclic import
+
+# Example
+
+```rust
+use viable_compiler::compile_with_imports_and_metadata;
+use viable_compiler::options::CompilerOptions;
+use viable_compiler::target::Target;
+
+let source = r#"import "common.viable"; capture word { .word; }"#;
+let (output, captures) =
+    compile_with_imports_and_metadata(source, Target::default(), &CompilerOptions::default(), |path| {
+        (path == "common.viable").then(|| r#"let .word = { some of <word>; }"#.to_owned())
+    })
+    .unwrap();
+
+assert_eq!(output, r"(?<word>\w+)");
+assert_eq!(captures[0].name.as_deref(), Some("word"));
+```
+*/
+pub fn compile_with_imports_and_metadata(
+    source: &str,
+    target: Target,
+    options: &CompilerOptions,
+    resolver: impl Fn(&str) -> Option<String> + 'static,
+) -> Result<(String, Vec<CaptureInfo>)> {
+    let (ast, flags, warnings) = to_ast_with_flags_imports_and_options(source, options, resolver)?;
+    // the statements of imported declarations have spans within their own files, so aren't quoted
+    let output = compile_parsed(ast, &flags, warnings, target, options, None)?;
+    let captures = output.captures.clone();
+
+    Ok((inline_pattern(output, &flags, target)?, captures))
+}
+
+/**
+Compiles Viable source code to a [`Regex`](::regex::Regex) of the `regex` crate.
+
+Requires the `regex` feature.
+
+# Errors
+
+Returns an [`errors::CompilerError`] upon encountering a syntax error or a construct the `regex` crate does not
+support, or [`errors::CompilerError::TargetRejectedOutput`] if the `regex` crate fails to build the pattern
+
+# Example
+
+```rust
+use viable_compiler::compile_to_regex;
+
+let regex = compile_to_regex(r#"capture major { some of <digit>; } "."; capture minor { some of <digit>; }"#).unwrap();
+let captures = regex.captures("1.74").unwrap();
+
+assert_eq!(&captures["major"], "1");
+assert_eq!(&captures["minor"], "74");
+```
+*/
+#[cfg(feature = "regex")]
+pub fn compile_to_regex(source: &str) -> Result<::regex::Regex> {
+    let pattern = compile_for(source, Target::RustRegex)?;
+
+    ::regex::Regex::new(&pattern).map_err(|error| errors::CompilerError::TargetRejectedOutput {
+        message: error.to_string(),
+        emitted: pattern,
+    })
+}
+
+/**
+Compiles Viable source code to a [`RegexBuilder`](::regex::RegexBuilder) of the `regex` crate, to set size limits
+or options before building the regex.
+
+The flags of the source are set on the builder rather than prefixed to the pattern, so they can be changed too.
+Requires the `regex` feature.
+
+# Errors
+
+Returns an [`errors::CompilerError`] upon encountering a syntax error or a construct the `regex` crate does not
+support
+
+# Example
+
+```rust
+use viable_compiler::compile_to_regex_builder;
+
+let mut builder = compile_to_regex_builder(r#"flags { insensitive; } "a";"#).unwrap();
+let regex = builder.size_limit(1 << 20).build().unwrap();
+
+assert!(regex.is_match("A"));
+```
+*/
+#[cfg(feature = "regex")]
+pub fn compile_to_regex_builder(source: &str) -> Result<::regex::RegexBuilder> {
+    let options = CompilerOptions::default();
+    let (ast, flags, warnings) = to_ast_with_flags_and_options(source, &options)?;
+    // compiled without flags, which are set on the builder instead
+    let output = compile_parsed(
+        ast,
+        &PatternFlags::default(),
+        warnings,
+        Target::RustRegex,
+        &options,
+        Some(source),
+    )?;
+
+    // the `regex` crate always matches by code point, so `unicode` needs no option
+    let mut builder = ::regex::RegexBuilder::new(&output.pattern);
+    builder
+        .case_insensitive(flags.contains(Flag::Insensitive))
+        .multi_line(flags.contains(Flag::Multiline))
+        .dot_matches_new_line(flags.contains(Flag::DotAll));
+
+    Ok(builder)
+}
+
+/// Converts a lowered AST and its flags to a pattern in the emit style of the options, quoting `source` in
+/// annotated patterns, along with the flags prefixed to it and the flag letters to pass to the engine instead
+/// for a target without an inline flag syntax
+fn emit(
+    ast: &ViableAst,
+    flags: &PatternFlags,
+    target: Target,
+    options: &CompilerOptions,
+    source: &str,
+) -> Result<(String, PatternFlags, String)> {
+    let flags = mode_flags(flags, options);
+    if target.matches_bytes() {
+        check_bytes_mode(ast, flags.contains(Flag::Unicode))?;
+    }
+    if target == Target::EcmaScript && !flags.contains(Flag::Unicode) {
+        check_astral_ranges(ast, target)?;
+    }
+
+    let (inline_flags, letters) = if target.supports_inline_flags() {
+        (flags, String::new())
+    } else {
+        (PatternFlags::default(), flags_to_letters(&flags))
+    };
+
+    let pattern = match options.emit_style {
+        EmitStyle::Compact => format!(
+            "{}{}",
+            flags_to_regex(&inline_flags, target)?,
+            ast_to_regex(ast, target)?
+        ),
+        EmitStyle::Annotated => ast_to_annotated_regex(ast, &inline_flags, target, source)?,
+    };
+
+    Ok((pattern, inline_flags, letters))
+}
+
+/// The pattern of a compiled source, for the compile functions which return no flags along with it
+fn inline_pattern(output: CompileOutput, flags: &PatternFlags, target: Target) -> Result<String> {
+    if output.flags.is_empty() {
+        Ok(output.pattern)
+    } else {
+        Err(inline_flags_error(flags, target))
+    }
+}
+
+/// The error of compiling flags for a target without an inline flag syntax, where they can't be returned
+/// separately
+const fn inline_flags_error(flags: &PatternFlags, target: Target) -> errors::CompilerError {
+    errors::CompilerError::UnsupportedByTarget {
+        construct: "inline flags",
+        target,
+        span: flags.span,
+    }
+}
+
+/// The flags of the source, with `unicode` added in [`CompilerOptions::unicode_mode`]
+fn mode_flags(flags: &PatternFlags, options: &CompilerOptions) -> PatternFlags {
+    let mut flags = flags.clone();
+    if options.unicode_mode && !flags.contains(Flag::Unicode) {
+        flags.flags.push(Flag::Unicode);
+    }
+    flags
+}
+
+/// Rewrites constructs the target can't express into approximations the options allow
+fn lower(ast: ViableAst, target: Target, options: &CompilerOptions) -> ViableAst {
+    if options.approximate_graphemes && !target.supports_grapheme_clusters() {
+        approximate_graphemes(ast)
+    } else {
+        ast
+    }
+}
+
+/// Rejects ASTs whose estimated pattern is longer than the options allow
+fn check_size(ast: &ViableAst, options: &CompilerOptions) -> Result<()> {
+    let Some(limit) = options.max_estimated_regex_len else {
+        return Ok(());
+    };
+
+    let estimated = ast.metrics().estimated_regex_len;
+    if estimated > limit {
+        return Err(errors::CompilerError::PatternTooLarge { estimated, limit });
+    }
+
+    Ok(())
+}
 
-    Ok(output)
+/// Rejects capture groups sharing a name unless the options allow it
+fn check_captures(ast: &ViableAst, options: &CompilerOptions) -> Result<()> {
+    if options.allow_duplicate_capture_names || options.strictness == Strictness::Lenient {
+        Ok(())
+    } else {
+        check_capture_names(ast)
+    }
 }
@@ -0,0 +1,117 @@
+use crate::errors::CompilerError;
+use crate::metadata::CaptureInfo;
+use crate::options::CompilerOptions;
+use crate::target::Target;
+use crate::types::Result;
+use std::collections::HashMap;
+
+/**
+A pattern compiled for the `regex` crate along with its capture groups, to match it against many haystacks.
+
+Captures are returned keyed by their Viable names, and unnamed captures by their 1-based index, e.g. `"1"`.
+Groups that didn't participate in a match are absent.
+
+# Example
+
+```rust
+use viable_compiler::matching::CompiledPattern;
+
+let pattern = CompiledPattern::new(r#"capture key { some of <word>; } "="; capture { some of <digit>; }"#).unwrap();
+let captures = pattern.captures("limit=10").unwrap();
+
+assert_eq!(captures["key"], "limit");
+assert_eq!(captures["2"], "10");
+```
+*/
+#[derive(Debug, Clone)]
+pub struct CompiledPattern {
+    regex: ::regex::Regex,
+    capture_groups: Vec<CaptureInfo>,
+}
+
+impl CompiledPattern {
+    /**
+    Compiles Viable source code for the `regex` crate.
+
+    # Errors
+
+    Returns a [`CompilerError`] upon encountering a syntax error or a construct the `regex` crate does not
+    support, or [`CompilerError::TargetRejectedOutput`] if the `regex` crate fails to build the pattern
+    */
+    pub fn new(source: &str) -> Result<Self> {
+        let (pattern, capture_groups, _) =
+            crate::compile_with_metadata(source, Target::RustRegex, &CompilerOptions::default())?;
+
+        let regex = ::regex::Regex::new(&pattern).map_err(|error| CompilerError::TargetRejectedOutput {
+            message: error.to_string(),
+            emitted: pattern,
+        })?;
+
+        Ok(Self { regex, capture_groups })
+    }
+
+    /// The compiled regex
+    #[must_use]
+    pub const fn regex(&self) -> &::regex::Regex {
+        &self.regex
+    }
+
+    /// The capture groups of the pattern in the order the pattern numbers them
+    #[must_use]
+    pub fn capture_groups(&self) -> &[CaptureInfo] {
+        &self.capture_groups
+    }
+
+    /// The captures of the first match in `haystack`, `None` if the pattern doesn't match it
+    #[must_use]
+    pub fn captures(&self, haystack: &str) -> Option<HashMap<String, String>> {
+        self.regex
+            .captures(haystack)
+            .map(|captures| self.capture_map(&captures))
+    }
+
+    /// The captures of each successive non-overlapping match in `haystack`
+    pub fn captures_iter<'pattern>(
+        &'pattern self,
+        haystack: &'pattern str,
+    ) -> impl Iterator<Item = HashMap<String, String>> + 'pattern {
+        self.regex
+            .captures_iter(haystack)
+            .map(|captures| self.capture_map(&captures))
+    }
+
+    fn capture_map(&self, captures: &::regex::Captures<'_>) -> HashMap<String, String> {
+        self.capture_groups
+            .iter()
+            .filter_map(|group| {
+                let capture = captures.get(group.index)?;
+                let key = group.name.clone().unwrap_or_else(|| group.index.to_string());
+
+                Some((key, capture.as_str().to_owned()))
+            })
+            .collect()
+    }
+}
+
+/**
+Matches Viable source code against a haystack, returning the captures of the first match like
+[`CompiledPattern::captures`]. Use a [`CompiledPattern`] to match the same source more than once.
+
+# Errors
+
+Returns a [`CompilerError`] upon encountering a syntax error or a construct the `regex` crate does not support
+
+# Example
+
+```rust
+use viable_compiler::matching::match_captures;
+
+let captures = match_captures(r#"capture major { some of <digit>; } option of capture patch { "+"; }"#, "12").unwrap();
+
+assert_eq!(captures.as_ref().and_then(|captures| captures.get("major")).map(String::as_str), Some("12"));
+assert_eq!(captures.and_then(|captures| captures.get("patch").cloned()), None);
+```
+*/
+pub fn match_captures(source: &str, haystack: &str) -> Result<Option<HashMap<String, String>>> {
+    Ok(CompiledPattern::new(source)?.captures(haystack))
+}
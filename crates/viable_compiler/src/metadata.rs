@@ -0,0 +1,117 @@
+use crate::ast::types::ast::Span;
+use crate::prelude::*;
+
+/// A capture group of a compiled pattern, see [`crate::compile_with_metadata`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaptureInfo {
+    /// The 1-based number of the group in the compiled pattern
+    pub index: usize,
+    /// The name of the group, `None` for unnamed captures
+    pub name: Option<String>,
+    /// The span of the `capture` statement, shared by every copy of a capture within an invoked variable
+    pub span: Span,
+    /// Whether the group is within a negative assertion, so it never holds a value after a match. Every target
+    /// numbers it all the same
+    pub in_negative_assertion: bool,
+}
+
+/// What a [`Mapping`] of a source map covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeKind {
+    /// the inline flags of a `flags` block, prefixed to the pattern
+    Flags,
+    /// `match`, `either`, `atomic` and flag groups
+    Group,
+    /// `capture { ... }` or `capture name { ... }`
+    Capture,
+    /// `ahead { ... }`, `behind { ... }` and their negations
+    Assertion,
+    /// a quantifier along with the expression it repeats
+    Quantifier,
+    /// a literal or raw string
+    Atom,
+    /// `from ... to ...` and its negation
+    Range,
+    /// a symbol, e.g. `<digit>`, including custom symbols
+    Symbol,
+    /// `<start>` or `<end>`
+    SpecialSymbol,
+    /// a Unicode category or property, e.g. `<category::letter>`
+    Unicode,
+    /// a `class` block or a negated class
+    CharClass,
+    /// the statements of an invoked variable, e.g. `.name;`
+    VariableInvocation,
+    /// `same as capture ...`
+    Backreference,
+    /// `if captured ... { ... }`
+    Conditional,
+    /// `recurse;` or `recurse name;`
+    Recursion,
+    /// `raw_regex "...";`
+    RawRegex,
+}
+
+/// A fragment of a compiled pattern and the statement it was compiled from, see [`SourceMap`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mapping {
+    /// The byte offset of the fragment in the pattern
+    pub regex_start: usize,
+    /// The byte offset just past the fragment in the pattern
+    pub regex_end: usize,
+    /// The span of the statement in the source, the declaration of a variable's statements for an invocation
+    pub source_span: Span,
+    pub node_kind: NodeKind,
+}
+
+/**
+The fragments of a compiled pattern along with the statements they were compiled from, see
+[`crate::options::CompilerOptions::source_map`].
+
+Every statement emitting at least a character of the pattern has a mapping, in pattern order with a statement
+preceding the ones nested within it. The map is built from the optimized AST, so statements merged by optimization
+(e.g. consecutive literals) share a mapping spanning all of them.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceMap {
+    pub mappings: Vec<Mapping>,
+}
+
+/**
+Finds the innermost statement a byte offset of a compiled pattern was compiled from, e.g. to point an engine's
+error at the source.
+
+# Example
+
+```rust
+use viable_compiler::compile_with_metadata;
+use viable_compiler::metadata::{locate, NodeKind};
+use viable_compiler::options::CompilerOptions;
+use viable_compiler::target::Target;
+
+let options = CompilerOptions {
+    source_map: true,
+    ..CompilerOptions::default()
+};
+let (pattern, _, source_map) = compile_with_metadata(r#""a"; capture { <digit>; }"#, Target::default(), &options).unwrap();
+let source_map = source_map.unwrap();
+
+assert_eq!(pattern, r"a(\d)");
+assert_eq!(locate(1, &source_map).unwrap().node_kind, NodeKind::Capture);
+assert_eq!(locate(3, &source_map).unwrap().source_span.start, 15);
+assert_eq!(locate(5, &source_map), None);
+```
+*/
+#[must_use]
+pub fn locate(regex_offset: usize, source_map: &SourceMap) -> Option<&Mapping> {
+    // nested statements follow the statements holding them, so the last mapping holding the offset is the innermost
+    source_map
+        .mappings
+        .iter()
+        .rev()
+        .find(|mapping| (mapping.regex_start..mapping.regex_end).contains(&regex_offset))
+}
@@ -0,0 +1,160 @@
+use crate::ast::source_to_ast::DEFAULT_NESTING_LIMIT;
+use crate::ast::types::ast::ViableAst;
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+
+/// Options controlling how Viable source is compiled, see [`crate::compile_with_options`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct CompilerOptions {
+    /// Which optimizations are applied to the AST before it is compiled
+    pub optimization: OptimizationLevel,
+    /// Which likely mistakes are rejected rather than warned about, [`Strictness::Standard`] by default
+    pub strictness: Strictness,
+    /// How the pattern is laid out, [`EmitStyle::Compact`] by default
+    pub emit_style: EmitStyle,
+    /// Whether [`crate::compile_with_metadata`] maps the fragments of a compact pattern back to the statements
+    /// they were compiled from, see [`crate::metadata::SourceMap`]. Disabled by default
+    pub source_map: bool,
+    /// Whether `either` groups of single characters, symbols and ranges are compiled to a character class
+    /// (e.g. `[abc]` rather than `(?:a|b|c)`) from [`OptimizationLevel::Basic`] on, enabled by default
+    pub either_to_class: bool,
+    /// Whether `either` groups that are a branch of another `either` group, directly or as the only statement of
+    /// a variable, are flattened into it (e.g. `(?:a|b|c)` rather than `(?:(?:a|b)|c)`) and repeated branches
+    /// without capture groups removed from [`OptimizationLevel::Basic`] on, enabled by default
+    pub flatten_either: bool,
+    /// Whether consecutive negated ranges, classes and symbols in a block are merged into a single negated class
+    /// (e.g. `not a to z; not 0 to 9;` to `[^a-z0-9]` rather than `[^a-z][^0-9]`). The merged class matches one
+    /// character none of them match, where the statements would each match a character, so this is disabled
+    /// by default. Only applies from [`OptimizationLevel::Basic`] on
+    pub merge_negated_classes: bool,
+    /// Whether consecutive atoms are merged before compiling, see [`crate::ast::types::ast::ViableAst::normalize`],
+    /// enabled by default
+    pub normalize: bool,
+    /// Whether capture groups may share a name, for engines that accept duplicate names (e.g. .NET, or PCRE2
+    /// with the `J` option), disabled by default
+    pub allow_duplicate_capture_names: bool,
+    /// How deep blocks and the expansions of variables may be nested, see
+    /// [`crate::ast::source_to_ast::DEFAULT_NESTING_LIMIT`]
+    pub nesting_limit: usize,
+    /// Whether quantifiers may repeat their expression zero times (e.g. `0 of`), which are otherwise rejected
+    /// with [`crate::errors::CompilerError::ZeroQuantifier`], disabled by default
+    pub allow_zero_quantifiers: bool,
+    /// Whether capture groups within negative assertions, which never capture anything, are rejected with
+    /// [`crate::errors::CompilerError::CaptureInNegativeAssertion`] rather than warned about. Disabled by default
+    pub deny_captures_in_negative_assertions: bool,
+    /// The longest pattern compilation may emit, estimated from the AST before it is compiled (see
+    /// [`crate::ast::AstMetrics::estimated_regex_len`]), longer patterns are rejected with
+    /// [`crate::errors::CompilerError::PatternTooLarge`]. Unlimited by default
+    pub max_estimated_regex_len: Option<usize>,
+    /// Whether `<grapheme>` is compiled to `(?:\P{M}\p{M}*)` for targets without `\X` rather than being rejected
+    /// with [`crate::errors::CompilerError::UnsupportedByTarget`]. The approximation keeps combining marks with
+    /// the character before them but splits emoji sequences and `\r\n`. Disabled by default
+    pub approximate_graphemes: bool,
+    /// Whether the pattern matches by code point, as if the source declared `flags { unicode; }`. Without it,
+    /// patterns for targets matching byte strings (e.g. [`crate::target::Target::RustRegexBytes`]) match by byte, and may only
+    /// hold ASCII characters outside of `chars` quantifiers ([`crate::errors::CompilerError::NonAsciiInBytesMode`]).
+    /// Disabled by default
+    pub unicode_mode: bool,
+    /// Variables in scope from the start of the source, as with [`crate::ast::to_ast_with_variables`]. Declarations
+    /// in the source may shadow them. Empty by default
+    pub variables: BTreeMap<String, ViableAst>,
+    /// Symbols available to the source besides the built in ones, e.g. `<uuid_char>` for the name `uuid_char`.
+    /// They expand like variables, and can't be negated or take the name of a built in symbol
+    /// ([`crate::errors::CompilerError::DuplicateSymbol`]). Empty by default
+    pub custom_symbols: BTreeMap<String, ViableAst>,
+}
+
+impl Default for CompilerOptions {
+    fn default() -> Self {
+        Self {
+            optimization: OptimizationLevel::default(),
+            strictness: Strictness::default(),
+            emit_style: EmitStyle::default(),
+            source_map: false,
+            either_to_class: true,
+            flatten_either: true,
+            merge_negated_classes: false,
+            normalize: true,
+            allow_duplicate_capture_names: false,
+            nesting_limit: DEFAULT_NESTING_LIMIT,
+            allow_zero_quantifiers: false,
+            deny_captures_in_negative_assertions: false,
+            max_estimated_regex_len: None,
+            approximate_graphemes: false,
+            unicode_mode: false,
+            variables: BTreeMap::new(),
+            custom_symbols: BTreeMap::new(),
+        }
+    }
+}
+
+/// How much the AST is rewritten before being compiled, each level includes the optimizations of the previous one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum OptimizationLevel {
+    /// The AST is compiled as written, even with [`CompilerOptions::either_to_class`],
+    /// [`CompilerOptions::flatten_either`] or [`CompilerOptions::merge_negated_classes`] enabled
+    None,
+    /// Applies the rewrites that [`CompilerOptions::either_to_class`], [`CompilerOptions::flatten_either`] and
+    /// [`CompilerOptions::merge_negated_classes`] enable, each only if its option is
+    #[default]
+    Basic,
+    /// Factors the longest common prefix and suffix out of `either` groups of literals
+    /// (e.g. `foob(?:ar|az)` rather than `(?:foobar|foobaz)`)
+    Aggressive,
+}
+
+/// How likely mistakes in the source are reported, see [`crate::Compiler::strictness`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Strictness {
+    /// Capture groups may also share a name, which is warned about with
+    /// [`crate::warnings::Warning::DuplicateCaptureName`], e.g. while prototyping a pattern
+    Lenient,
+    /// Likely mistakes are warned about and mistakes are errors
+    #[default]
+    Standard,
+    /// Every warning is an error, [`crate::errors::CompilerError::DeniedWarning`] or
+    /// [`crate::errors::CompilerError::CaptureInNegativeAssertion`] for a capture group within a negative assertion
+    Strict,
+}
+
+/// How a compiled pattern is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmitStyle {
+    /// The pattern on a single line, without whitespace or parentheses it doesn't need
+    #[default]
+    Compact,
+    /**
+    The pattern in free-spacing form, prefixed with `(?x)`, with each statement on a line of its own followed by
+    a `#` comment quoting the first line of its source. Statements within groups and assertions are indented.
+
+    Whitespace and `#` within literals are escaped, so the pattern matches what the compact one does. Only
+    targets with a free-spacing mode are supported (see [`crate::target::Target::supports_free_spacing`]),
+    others are rejected with [`crate::errors::CompilerError::UnsupportedByTarget`].
+
+    ```text
+    (?x)
+    (?<word>  # capture word {
+      \w+     #   some of <word>;
+    )
+    \         # " "
+    (?!       # not ahead { <digit>; }
+      \d      #   <digit>
+    )
+    ```
+    */
+    Annotated,
+}
+
+/// Options controlling how an AST is formatted back to Viable source, see [`crate::ast::ast_to_source`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// The number of spaces statements are indented by within a block, 2 by default
+    pub indent: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self { indent: 2 }
+    }
+}
@@ -0,0 +1,9 @@
+//! The items of the `std` prelude that `alloc` provides, imported by modules using them so that the crate builds
+//! without `std`
+
+pub use alloc::borrow::ToOwned;
+pub use alloc::boxed::Box;
+pub use alloc::format;
+pub use alloc::string::{String, ToString};
+pub use alloc::vec;
+pub use alloc::vec::Vec;
@@ -1,4 +1,10 @@
+mod annotated;
 pub mod ast_to_regex;
+mod captures;
+mod source_map;
 mod utils;
 
-pub use self::ast_to_regex::ast_to_regex;
+pub use self::annotated::ast_to_annotated_regex;
+pub use self::ast_to_regex::{flags_to_letters, flags_to_regex};
+pub use self::captures::capture_groups;
+pub use self::source_map::source_map;
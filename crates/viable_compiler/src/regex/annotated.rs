@@ -0,0 +1,197 @@
+use super::ast_to_regex::{
+    assertion_opener, ast_to_regex, expression_to_regex, flags_to_regex, group_opener, node_to_regex,
+};
+use super::utils::wrap_quantified;
+use crate::ast::types::ast::{Expression, Group, GroupKind, PatternFlags, Span, Spanned, ViableAst, ViableAstNode};
+use crate::errors::CompilerError;
+use crate::prelude::*;
+use crate::target::Target;
+use crate::types::Result;
+
+/// Converts a Viable AST to a free-spacing regular expression for the given target, with each statement on a line
+/// of its own commented with the first line of its `source`, see [`crate::options::EmitStyle::Annotated`]
+///
+/// # Errors
+///
+/// Returns [`CompilerError::UnsupportedByTarget`] if the target has no free-spacing mode or the AST uses a
+/// construct the target cannot express
+pub fn ast_to_annotated_regex(ast: &ViableAst, flags: &PatternFlags, target: Target, source: &str) -> Result<String> {
+    if !target.supports_free_spacing() {
+        return Err(CompilerError::UnsupportedByTarget {
+            construct: "free-spacing output",
+            target,
+            span: Span::default(),
+        });
+    }
+
+    // the compact pattern is made of the same constructs, so compiling it rejects what the target can't express
+    ast_to_regex(ast, target)?;
+
+    // Python only accepts flags at the start of the pattern, so `x` joins the declared ones
+    let flags_regex = flags_to_regex(flags, target)?;
+    let flags_regex = match flags_regex.strip_suffix(')') {
+        Some(letters) if flags_regex.contains("(?") => format!("{letters}x)"),
+        _ => format!("{flags_regex}(?x)"),
+    };
+
+    let mut annotator = Annotator {
+        target,
+        source,
+        lines: Vec::new(),
+    };
+
+    annotator.push(0, flags_regex, flags.span);
+    annotator.ast(ast, 0)?;
+
+    Ok(annotator.render())
+}
+
+struct Line {
+    depth: usize,
+    code: String,
+    comment: Option<String>,
+}
+
+struct Annotator<'source> {
+    target: Target,
+    source: &'source str,
+    lines: Vec<Line>,
+}
+
+impl Annotator<'_> {
+    fn ast(&mut self, ast: &ViableAst, depth: usize) -> Result<()> {
+        let ViableAst::Root(nodes) = ast else {
+            return Ok(());
+        };
+
+        // a line break ends a numbered backreference, so `\1` and a literal `2` need no separating group
+        for node in nodes.iter().filter(|node| !matches!(node.node, ViableAstNode::Skip)) {
+            self.node(node, depth)?;
+        }
+
+        Ok(())
+    }
+
+    fn node(&mut self, node: &Spanned<ViableAstNode>, depth: usize) -> Result<()> {
+        match &node.node {
+            ViableAstNode::Group(group) => self.group(group, node.span, depth, ""),
+            ViableAstNode::Assertion(assertion) => {
+                self.push(depth, String::from(assertion_opener(assertion)), node.span);
+                self.ast(&assertion.statements, depth + 1)?;
+                self.push(depth, String::from(")"), Span::default());
+                Ok(())
+            }
+            ViableAstNode::Quantifier(quantifier) => {
+                // a quantifier counting in a unit is kept on one line, along with the group switching to the unit
+                let (Expression::Group(group), None) = (&quantifier.expression.node, quantifier.unit) else {
+                    let code = free_spacing(&node_to_regex(node, self.target)?, self.target);
+                    self.push(depth, code, node.span);
+                    return Ok(());
+                };
+
+                // the quantifier follows the group, as compiled without its statements
+                let expression = wrap_quantified(expression_to_regex(&quantifier.expression, self.target)?);
+                let regex = node_to_regex(node, self.target)?;
+                let suffix = regex.get(expression.len()..).unwrap_or_default();
+
+                self.group(group, node.span, depth, suffix)
+            }
+            // an empty branch of an `either`, kept on a line of its own to comment it
+            ViableAstNode::Empty => {
+                self.push(depth, String::new(), node.span);
+                Ok(())
+            }
+            _ => {
+                let code = free_spacing(&node_to_regex(node, self.target)?, self.target);
+                self.push(depth, code, node.span);
+                Ok(())
+            }
+        }
+    }
+
+    /// Pushes a group over several lines, `closer` following its closing parenthesis
+    fn group(&mut self, group: &Group, span: Span, depth: usize, closer: &str) -> Result<()> {
+        self.push(depth, group_opener(group, span, self.target)?, span);
+
+        match (&group.kind, group.statements.as_ref()) {
+            (GroupKind::Either, ViableAst::Root(branches)) => {
+                let branches = branches.iter().filter(|node| !matches!(node.node, ViableAstNode::Skip));
+                for (index, branch) in branches.enumerate() {
+                    if index > 0 {
+                        self.push(depth, String::from("|"), Span::default());
+                    }
+                    self.node(branch, depth + 1)?;
+                }
+            }
+            (_, statements) => self.ast(statements, depth + 1)?,
+        }
+
+        self.push(depth, format!("){closer}"), Span::default());
+        Ok(())
+    }
+
+    fn push(&mut self, depth: usize, code: String, span: Span) {
+        let comment = (span.line > 0)
+            .then(|| self.source.get(span.start..span.end))
+            .flatten()
+            .and_then(|snippet| snippet.lines().next())
+            .map(|snippet| snippet.trim().to_owned())
+            .filter(|snippet| !snippet.is_empty());
+
+        self.lines.push(Line { depth, code, comment });
+    }
+
+    /// Joins the lines, aligning their comments past the longest commented line
+    fn render(self) -> String {
+        let indented = |line: &Line| line.depth * 2 + line.code.chars().count();
+        let width = self
+            .lines
+            .iter()
+            .filter(|line| line.comment.is_some())
+            .map(indented)
+            .max()
+            .unwrap_or_default();
+
+        let mut rendered = String::new();
+
+        for line in &self.lines {
+            let code = format!("{}{}", "  ".repeat(line.depth), line.code);
+            if let Some(comment) = &line.comment {
+                let padding = " ".repeat(width - indented(line) + 2);
+                rendered.push_str(&format!("{code}{padding}# {}{comment}\n", "  ".repeat(line.depth)));
+            } else {
+                rendered.push_str(&code);
+                rendered.push('\n');
+            }
+        }
+
+        rendered
+    }
+}
+
+/// Escapes the whitespace and `#` of a compiled statement, which free-spacing mode would otherwise ignore or
+/// read as the start of a comment
+fn free_spacing(regex: &str, target: Target) -> String {
+    let mut escaped = String::with_capacity(regex.len());
+    let mut chars = regex.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => {
+                escaped.push(char);
+                escaped.extend(chars.next());
+            }
+            ' ' | '#' => {
+                escaped.push('\\');
+                escaped.push(char);
+            }
+            char if char.is_whitespace() && target == Target::PythonRe => {
+                escaped.push_str(&format!("\\u{:04X}", u32::from(char)));
+            }
+            char if char.is_whitespace() => escaped.push_str(&format!("\\x{{{:X}}}", u32::from(char))),
+            char => escaped.push(char),
+        }
+    }
+
+    escaped
+}
@@ -1,141 +1,758 @@
-use super::utils::{mark_lazy, wrap_quantified};
+use super::utils::{escape_for_target, mark_lazy, mark_possessive, supports_control_escapes, wrap_quantified};
 use crate::ast::types::ast::{
-    Assertion, AssertionKind, Expression, Group, GroupKind, ViableAst, ViableAstNode, Quantifier, QuantifierKind,
-    Range, SpecialSymbolKind, Symbol, SymbolKind, UnicodeCategory, UnicodeCategoryKind, VariableInvocation,
+    Assertion, AssertionKind, Backreference, CharClass, ClassItem, Conditional, Expression, Flag, Group, GroupKind,
+    PatternFlags, Quantifier, QuantifierKind, QuantifierUnit, Range, Recursion, Span, Spanned, SpecialSymbolKind,
+    Symbol, SymbolKind, UnicodeCategory, UnicodeCategoryKind, UnicodeProperty, UnicodePropertyKind, VariableInvocation,
+    ViableAst, ViableAstNode,
 };
+use crate::ast::{atom_text, supports_lookbehind_body};
+use crate::errors::CompilerError;
+use crate::prelude::*;
+use crate::target::Target;
+use crate::types::Result;
 
-#[must_use]
-pub fn ast_to_regex(ast: &ViableAst) -> String {
+/// Converts a Viable AST to a regular expression for the given target
+///
+/// # Errors
+///
+/// Returns [`CompilerError::UnsupportedByTarget`] if the AST uses a construct the target cannot express
+pub fn ast_to_regex(ast: &ViableAst, target: Target) -> Result<String> {
     match ast {
-        ViableAst::Root(nodes) => nodes.iter().map(node_to_regex).collect(),
-        ViableAst::Empty => String::new(),
+        ViableAst::Root(nodes) => {
+            let mut regex = String::new();
+            let mut after_numbered_backreference = false;
+
+            for node in nodes {
+                let fragment = node_to_regex(node, target)?;
+
+                if fragment.is_empty() {
+                    continue;
+                }
+
+                // `\1` followed by a literal `2` would otherwise be read as `\12`
+                if after_numbered_backreference && fragment.starts_with(|char: char| char.is_ascii_digit()) {
+                    regex.push_str("(?:)");
+                }
+
+                after_numbered_backreference = !fragment.ends_with(')') && ends_with_numbered_backreference(&node.node);
+                regex.push_str(&fragment);
+            }
+
+            Ok(regex)
+        }
+        ViableAst::Empty => Ok(String::new()),
     }
 }
 
-pub fn node_to_regex(node: &ViableAstNode) -> String {
-    match node {
-        ViableAstNode::Quantifier(quantifier) => transform_quantifier(quantifier),
-        ViableAstNode::Assertion(assertion) => transform_assertion(assertion),
-        ViableAstNode::SpecialSymbol(special_symbol) => transform_special_symbol(special_symbol),
-        ViableAstNode::Group(group) => transform_group(group),
-        ViableAstNode::Atom(atom) => atom.clone(),
-        ViableAstNode::Symbol(symbol) => transform_symbol(symbol),
-        ViableAstNode::UnicodeCategory(category) => transform_unicode_category(category),
-        ViableAstNode::Range(range) => transform_range(range),
-        ViableAstNode::NegativeCharClass(negative_char_class) => transform_negative_char_class(negative_char_class),
-        ViableAstNode::VariableInvocation(variable_invocation) => transform_variable_invocation(variable_invocation),
-        ViableAstNode::Skip => String::new(),
+/// Converts pattern flags to the target's inline flag syntax, to be prefixed to the pattern
+///
+/// # Errors
+///
+/// Returns [`CompilerError::UnsupportedByTarget`] if flags are declared and the target has no inline flag syntax
+pub fn flags_to_regex(flags: &PatternFlags, target: Target) -> Result<String> {
+    // a target matching byte strings matches by code point by default, so bytes mode clears the `u` flag
+    let bytes_mode = target.matches_bytes() && !flags.contains(Flag::Unicode);
+
+    if flags.is_empty() && !bytes_mode {
+        return Ok(String::new());
+    }
+
+    if !target.supports_inline_flags() {
+        return Err(CompilerError::UnsupportedByTarget {
+            construct: "inline flags",
+            target,
+            span: flags.span,
+        });
+    }
+
+    // unicode mode is a start-of-pattern verb rather than an option letter for PCRE2, and always on for RE2
+    let verb = if flags.contains(Flag::Unicode) && target == Target::Pcre2 {
+        "(*UTF)"
+    } else {
+        ""
+    };
+    let unicode_letter = matches!(target, Target::PythonRe | Target::RustRegex | Target::RustRegexBytes);
+
+    let letters: String = flags
+        .flags
+        .iter()
+        .filter(|flag| **flag != Flag::Unicode || unicode_letter)
+        .map(|flag| flag_letter(*flag))
+        .collect();
+
+    let cleared = if bytes_mode { "-u" } else { "" };
+
+    if letters.is_empty() && cleared.is_empty() {
+        Ok(verb.to_owned())
+    } else {
+        Ok(format!("{verb}(?{letters}{cleared})"))
     }
 }
 
-fn expression_to_regex(expression: &Expression) -> String {
-    match expression {
-        Expression::Group(group) => transform_group(group),
-        Expression::Atom(atom) => atom.clone(),
-        Expression::Range(range) => transform_range(range),
-        Expression::Symbol(symbol) => transform_symbol(symbol),
-        Expression::UnicodeCategory(category) => transform_unicode_category(category),
-        Expression::NegativeCharClass(negative_char_class) => transform_negative_char_class(negative_char_class),
+/// Converts pattern flags to the flag letters passed to the engine alongside the pattern (e.g. `"im"`)
+pub fn flags_to_letters(flags: &PatternFlags) -> String {
+    flags.flags.iter().map(|flag| flag_letter(*flag)).collect()
+}
+
+const fn flag_letter(flag: Flag) -> char {
+    match flag {
+        Flag::Insensitive => 'i',
+        Flag::Multiline => 'm',
+        Flag::DotAll => 's',
+        Flag::Unicode => 'u',
     }
 }
 
-fn transform_special_symbol(special_symbol: &SpecialSymbolKind) -> String {
-    let transformed_special_symbol = match special_symbol {
-        SpecialSymbolKind::Start => '^',
-        SpecialSymbolKind::End => '$',
+pub(super) fn ends_with_numbered_backreference(node: &ViableAstNode) -> bool {
+    let statements = match node {
+        ViableAstNode::Backreference(Backreference::Numbered(_)) => return true,
+        ViableAstNode::VariableInvocation(VariableInvocation { statements, .. }) => statements.as_ref(),
+        // a `match` group is emitted without parentheses within a sequence
+        ViableAstNode::Group(Group {
+            kind: GroupKind::Match,
+            statements,
+            ..
+        }) => statements.as_ref(),
+        _ => return false,
+    };
+
+    match statements {
+        ViableAst::Root(nodes) => nodes
+            .iter()
+            .rev()
+            .find(|node| !matches!(node.node, ViableAstNode::Skip))
+            .is_some_and(|node| ends_with_numbered_backreference(&node.node)),
+        ViableAst::Empty => false,
+    }
+}
+
+pub fn node_to_regex(node: &Spanned<ViableAstNode>, target: Target) -> Result<String> {
+    let span = node.span;
+
+    let regex = match &node.node {
+        ViableAstNode::Quantifier(quantifier) => transform_quantifier(quantifier, span, target)?,
+        ViableAstNode::Assertion(assertion) => transform_assertion(assertion, span, target)?,
+        ViableAstNode::Conditional(conditional) => transform_conditional(conditional, span, target)?,
+        ViableAstNode::SpecialSymbol(special_symbol) => transform_special_symbol(special_symbol, target),
+        ViableAstNode::Group(group) => transform_group(group, span, target, Context::Statement)?,
+        ViableAstNode::Atom(atom) => transform_atom(atom, target),
+        ViableAstNode::Symbol(symbol) => transform_symbol(symbol, span, target)?,
+        ViableAstNode::UnicodeCategory(category) => transform_unicode_category(category, span, target)?,
+        ViableAstNode::UnicodeProperty(property) => transform_unicode_property(property, span, target)?,
+        ViableAstNode::Range(range) => transform_range(range, target),
+        ViableAstNode::NegativeCharClass(items) => transform_negative_char_class(items, span, target)?,
+        ViableAstNode::CharClass(class) => transform_char_class(class, span, target)?,
+        ViableAstNode::VariableInvocation(variable_invocation) => {
+            transform_variable_invocation(variable_invocation, target)?
+        }
+        ViableAstNode::Backreference(backreference) => transform_backreference(backreference, span, target)?,
+        ViableAstNode::Recursion(recursion) => transform_recursion(recursion, span, target)?,
+        ViableAstNode::RawRegex(regex) => regex.to_string(),
+        // parameters are substituted when a variable is invoked, so only remain in unexpanded declarations, and
+        // `nothing` is joined with the other branches of its `either` group, leaving an empty alternative
+        ViableAstNode::Skip | ViableAstNode::Parameter(_) | ViableAstNode::Empty => String::new(),
+        ViableAstNode::Error => return Err(CompilerError::UnrecognizedSyntax),
+    };
+
+    Ok(regex)
+}
+
+pub(super) fn expression_to_regex(expression: &Spanned<Expression>, target: Target) -> Result<String> {
+    let regex = match &expression.node {
+        Expression::Group(group) => transform_group(group, expression.span, target, Context::Quantified)?,
+        Expression::Atom(atom) => transform_atom(atom, target),
+        Expression::Range(range) => transform_range(range, target),
+        Expression::Symbol(symbol) => transform_symbol(symbol, expression.span, target)?,
+        Expression::UnicodeCategory(category) => transform_unicode_category(category, expression.span, target)?,
+        Expression::UnicodeProperty(property) => transform_unicode_property(property, expression.span, target)?,
+        Expression::NegativeCharClass(items) => transform_negative_char_class(items, expression.span, target)?,
+        Expression::CharClass(class) => transform_char_class(class, expression.span, target)?,
+        Expression::Backreference(backreference) => transform_backreference(backreference, expression.span, target)?,
+    };
+
+    Ok(regex)
+}
+
+fn transform_special_symbol(special_symbol: &SpecialSymbolKind, target: Target) -> String {
+    let transformed_special_symbol = match (special_symbol, target) {
+        (SpecialSymbolKind::Start | SpecialSymbolKind::LineStart, _) => "^",
+        (SpecialSymbolKind::End | SpecialSymbolKind::LineEnd, _) => "$",
+        // ECMAScript has no input anchors, so they're expressed as the absence of a surrounding character
+        (SpecialSymbolKind::InputStart, Target::EcmaScript) => "(?<![\\s\\S])",
+        (SpecialSymbolKind::InputEnd, Target::EcmaScript) => "(?![\\s\\S])",
+        (SpecialSymbolKind::InputStart, _) => "\\A",
+        // Python's `\Z` is the very end of the input, unlike PCRE2's which also matches before a final newline
+        (SpecialSymbolKind::InputEnd, Target::PythonRe) => "\\Z",
+        (SpecialSymbolKind::InputEnd, _) => "\\z",
+        (SpecialSymbolKind::WordBoundary, _) => "\\b",
+        (SpecialSymbolKind::NotWordBoundary, _) => "\\B",
     };
 
     String::from(transformed_special_symbol)
 }
 
-fn transform_quantifier(quantifier: &Quantifier) -> String {
-    let wrapped_expression = wrap_quantified(expression_to_regex(&quantifier.expression));
+fn transform_quantifier(quantifier: &Quantifier, span: Span, target: Target) -> Result<String> {
+    if quantifier.possessive && !target.supports_possessive_quantifiers() {
+        return Err(CompilerError::UnsupportedByTarget {
+            construct: "a possessive quantifier",
+            target,
+            span,
+        });
+    }
+
+    let wrapped_expression = quantified_to_regex(quantifier, span, target)?;
     let formatted_quantifier = match &quantifier.kind {
         QuantifierKind::Range { start, end } => format!("{wrapped_expression}{{{start},{end}}}"),
         QuantifierKind::Some => format!("{wrapped_expression}+"),
         QuantifierKind::Any => format!("{wrapped_expression}*"),
-        QuantifierKind::Over(amount) => format!("{wrapped_expression}{{{amount},}}"),
+        QuantifierKind::Over(amount) | QuantifierKind::AtLeast(amount) => {
+            format!("{wrapped_expression}{{{amount},}}")
+        }
+        QuantifierKind::AtMost(amount) => format!("{wrapped_expression}{{0,{amount}}}"),
         QuantifierKind::Option => format!("{wrapped_expression}?"),
         QuantifierKind::Amount(amount) => format!("{wrapped_expression}{{{amount}}}"),
     };
 
-    mark_lazy(formatted_quantifier, quantifier.lazy)
+    let formatted_quantifier = mark_lazy(formatted_quantifier, quantifier.lazy);
+
+    Ok(mark_possessive(formatted_quantifier, quantifier.possessive))
 }
 
-fn transform_assertion(assertion: &Assertion) -> String {
-    let body_source = ast_to_regex(&assertion.statements);
+/// The expression of a quantifier as it is repeated, in a group if the quantifier can't apply to it directly or, on
+/// targets matching byte strings, to match it in the unit of the quantifier
+pub(super) fn quantified_to_regex(quantifier: &Quantifier, span: Span, target: Target) -> Result<String> {
+    let expression = expression_to_regex(&quantifier.expression, target)?;
 
-    match assertion.kind {
-        AssertionKind::Ahead => {
-            if assertion.negative {
-                format!("(?!{body_source})")
-            } else {
-                format!("(?={body_source})")
-            }
-        }
-        AssertionKind::Behind => {
-            if assertion.negative {
-                format!("(?<!{body_source})")
-            } else {
-                format!("(?<={body_source})")
-            }
-        }
+    match (quantifier.unit, target.matches_bytes()) {
+        (Some(QuantifierUnit::Chars), true) => Ok(format!("(?u:{expression})")),
+        (Some(QuantifierUnit::Bytes), true) => Ok(format!("(?-u:{expression})")),
+        // other targets only match text, so count in characters
+        (Some(QuantifierUnit::Bytes), false) => Err(CompilerError::UnsupportedByTarget {
+            construct: "a quantifier counting bytes",
+            target,
+            span,
+        }),
+        (Some(QuantifierUnit::Chars) | None, _) => Ok(wrap_quantified(expression)),
     }
 }
 
-fn transform_negative_char_class(class: &str) -> String {
-    format!("[^{class}]")
+fn transform_assertion(assertion: &Assertion, span: Span, target: Target) -> Result<String> {
+    if !target.supports_lookaround() {
+        let construct = match assertion.kind {
+            AssertionKind::Ahead => "a lookahead assertion",
+            AssertionKind::Behind => "a lookbehind assertion",
+        };
+        return Err(CompilerError::UnsupportedByTarget {
+            construct,
+            target,
+            span,
+        });
+    }
+
+    if matches!(assertion.kind, AssertionKind::Behind) && !supports_lookbehind_body(&assertion.statements, target) {
+        return Err(CompilerError::VariableLengthLookbehind { span, target });
+    }
+
+    let body_source = ast_to_regex(&assertion.statements, target)?;
+
+    Ok(format!("{}{body_source})", assertion_opener(assertion)))
 }
 
-fn transform_variable_invocation(variable_invocation: &VariableInvocation) -> String {
-    ast_to_regex(&variable_invocation.statements)
+fn transform_conditional(conditional: &Conditional, span: Span, target: Target) -> Result<String> {
+    let opener = conditional_opener(conditional, span, target)?;
+    let then = conditional_branch(&conditional.then, target)?;
+
+    let regex = match &conditional.otherwise {
+        Some(otherwise) => format!("{opener}{then}|{})", conditional_branch(otherwise, target)?),
+        None => format!("{opener}{then})"),
+    };
+
+    Ok(regex)
 }
 
-fn transform_group(group: &Group) -> String {
-    match group.kind {
+/// The start of a conditional up to its first branch, e.g. `(?(1)` or `(?(<name>)`
+pub(super) fn conditional_opener(conditional: &Conditional, span: Span, target: Target) -> Result<String> {
+    if !target.supports_conditionals() {
+        return Err(CompilerError::UnsupportedByTarget {
+            construct: "a conditional",
+            target,
+            span,
+        });
+    }
+
+    let opener = match (&conditional.condition, target) {
+        // PCRE2 reads some bare names as other conditions, e.g. `(?(R)` as a test for recursion
+        (Backreference::Named(name), Target::Pcre2) => format!("(?(<{name}>)"),
+        (Backreference::Named(name), _) => format!("(?({name})"),
+        (Backreference::Numbered(index), _) => format!("(?({index})"),
+    };
+
+    Ok(opener)
+}
+
+/// A branch of a conditional, grouped if it holds an alternation as a conditional has at most two branches
+pub(super) fn conditional_branch(ast: &ViableAst, target: Target) -> Result<String> {
+    let regex = ast_to_regex(ast, target)?;
+
+    if has_top_level_alternation(&regex) {
+        return Ok(format!("(?:{regex})"));
+    }
+
+    Ok(regex)
+}
+
+/// The start of an assertion up to its statements, which are followed by `)`
+pub(super) const fn assertion_opener(assertion: &Assertion) -> &'static str {
+    match (&assertion.kind, assertion.negative) {
+        (AssertionKind::Ahead, false) => "(?=",
+        (AssertionKind::Ahead, true) => "(?!",
+        (AssertionKind::Behind, false) => "(?<=",
+        (AssertionKind::Behind, true) => "(?<!",
+    }
+}
+
+fn transform_negative_char_class(items: &[ClassItem], span: Span, target: Target) -> Result<String> {
+    Ok(format!("[^{}]", transform_class_items(items, span, target)?))
+}
+
+fn transform_backreference(backreference: &Backreference, span: Span, target: Target) -> Result<String> {
+    if !target.supports_backreferences() {
+        return Err(CompilerError::UnsupportedByTarget {
+            construct: "a backreference",
+            target,
+            span,
+        });
+    }
+
+    let regex = match (backreference, target) {
+        (Backreference::Named(name), Target::PythonRe) => format!("(?P={name})"),
+        (Backreference::Named(name), _) => format!("\\k<{name}>"),
+        (Backreference::Numbered(index), _) => format!("\\{index}"),
+    };
+
+    Ok(regex)
+}
+
+fn transform_recursion(recursion: &Recursion, span: Span, target: Target) -> Result<String> {
+    if !target.supports_recursion() {
+        return Err(CompilerError::UnsupportedByTarget {
+            construct: "recursion",
+            target,
+            span,
+        });
+    }
+
+    let regex = match recursion {
+        Recursion::Pattern => String::from("(?R)"),
+        Recursion::Group(name) => format!("(?&{name})"),
+    };
+
+    Ok(regex)
+}
+
+fn transform_variable_invocation(variable_invocation: &VariableInvocation, target: Target) -> Result<String> {
+    ast_to_regex(&variable_invocation.statements, target)
+}
+
+/// Where a group is emitted, which decides whether a `match` group needs its parentheses
+#[derive(Clone, Copy)]
+enum Context {
+    /// a statement of a sequence or a branch of an alternation, where concatenation binds tighter than `|`
+    Statement,
+    /// the expression of a quantifier, which only applies to the last character of a sequence
+    Quantified,
+}
+
+fn transform_group(group: &Group, span: Span, target: Target, context: Context) -> Result<String> {
+    let opener = group_opener(group, span, target)?;
+
+    let regex = match &group.kind {
         GroupKind::Match => {
-            let body = ast_to_regex(&group.statements);
-            format!("(?:{body})")
-        }
-        GroupKind::Capture => {
-            let body = ast_to_regex(&group.statements);
-            if let Some(ident) = group.ident.as_ref() {
-                format!("(?<{ident}>{body})")
-            } else {
-                format!("({body})")
+            let body = ast_to_regex(&group.statements, target)?;
+            match context {
+                Context::Statement if !has_top_level_alternation(&body) => body,
+                Context::Statement | Context::Quantified => format!("{opener}{body})"),
             }
         }
         GroupKind::Either => {
             let body = if let ViableAst::Root(statements) = group.statements.as_ref() {
-                statements.iter().map(node_to_regex).collect::<Vec<String>>().join("|")
+                statements
+                    .iter()
+                    .map(|node| node_to_regex(node, target))
+                    .collect::<Result<Vec<String>>>()?
+                    .join("|")
             } else {
-                ast_to_regex(&group.statements)
+                ast_to_regex(&group.statements, target)?
             };
-            format!("(?:{body})")
+            format!("{opener}{body})")
+        }
+        GroupKind::Atomic | GroupKind::Flagged { .. } | GroupKind::Capture => {
+            format!("{opener}{})", ast_to_regex(&group.statements, target)?)
+        }
+    };
+
+    Ok(regex)
+}
+
+/// The start of a group up to its statements, which are followed by `)`
+///
+/// # Errors
+///
+/// Returns [`CompilerError::UnsupportedByTarget`] for an atomic group on a target without them, and a
+/// [`CompilerError::InvalidCaptureName`] for a name the target rejects
+pub(super) fn group_opener(group: &Group, span: Span, target: Target) -> Result<String> {
+    let opener = match &group.kind {
+        GroupKind::Match | GroupKind::Either => String::from("(?:"),
+        GroupKind::Atomic => {
+            if !target.supports_atomic_groups() {
+                return Err(CompilerError::UnsupportedByTarget {
+                    construct: "an atomic group",
+                    target,
+                    span,
+                });
+            }
+            String::from("(?>")
+        }
+        GroupKind::Flagged { set, clear } => {
+            let set: String = set.iter().map(|flag| flag_letter(*flag)).collect();
+            let clear: String = clear.iter().map(|flag| flag_letter(*flag)).collect();
+            if clear.is_empty() {
+                format!("(?{set}:")
+            } else {
+                format!("(?{set}-{clear}:")
+            }
+        }
+        GroupKind::Capture => {
+            if let Some(ident) = group.ident.as_ref() {
+                validate_capture_name(ident, target)?;
+            }
+            match group.ident.as_ref() {
+                Some(ident) if target.uses_python_named_groups() => format!("(?P<{ident}>"),
+                Some(ident) => format!("(?<{ident}>"),
+                None => String::from("("),
+            }
+        }
+    };
+
+    Ok(opener)
+}
+
+/// Whether a regex has a `|` outside of any group or class, which raw atoms can hold (e.g. `` `a|b` ``)
+pub(super) fn has_top_level_alternation(regex: &str) -> bool {
+    let mut depth = 0_usize;
+    let mut in_class = false;
+    let mut chars = regex.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => {
+                chars.next();
+            }
+            ']' if in_class => in_class = false,
+            _ if in_class => {}
+            '[' => in_class = true,
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '|' if depth == 0 => return true,
+            _ => {}
         }
     }
+
+    false
 }
 
-fn transform_range(range: &Range) -> String {
+/// Checks a capture group name against the target's naming rules, which all require word characters
+///
+/// RE2 is the only target accepting names that start with a digit and PCRE2 limits names to 32 characters
+fn validate_capture_name(name: &str, target: Target) -> Result<()> {
+    let reason = if name.chars().any(|char| !(char.is_alphanumeric() || char == '_')) {
+        "names can only contain letters, digits and underscores"
+    } else if target != Target::Re2 && name.starts_with(|char: char| char.is_ascii_digit()) {
+        "names cannot start with a digit"
+    } else if target == Target::Pcre2 && name.chars().count() > 32 {
+        "names are limited to 32 characters"
+    } else {
+        return Ok(());
+    };
+
+    Err(CompilerError::InvalidCaptureName {
+        name: name.to_owned(),
+        target,
+        reason,
+    })
+}
+
+/// Atoms of plain text are escaped for the target, those holding other regex syntax come from raw input and are
+/// emitted as written
+fn transform_atom(atom: &str, target: Target) -> String {
+    match atom_text(atom) {
+        Some(text) => escape_for_target(&text, target, false),
+        None => atom.to_owned(),
+    }
+}
+
+fn transform_range(range: &Range, target: Target) -> String {
+    let (negative, start, end) = match range {
+        Range::CharRange(range) => (range.negative, range.start, range.end),
+        Range::NumericRange(range) => (range.negative, range.start, range.end),
+    };
+
+    let prefix = if negative { "[^" } else { "[" };
+
+    format!(
+        "{prefix}{}-{}]",
+        escape_class_char(start, target),
+        escape_class_char(end, target)
+    )
+}
+
+/// Whether a class uses set operations that the compiler can't compute itself, so the target must support them
+pub fn needs_class_set_operations(class: &CharClass) -> bool {
+    let (items, set_operations) = partition_set_operations(class);
+
+    !set_operations.is_empty() && ascii_class_set(&items, &set_operations).is_none()
+}
+
+fn partition_set_operations(class: &CharClass) -> (Vec<&ClassItem>, Vec<&ClassItem>) {
+    class
+        .items
+        .iter()
+        .partition(|item| !matches!(item, ClassItem::Subtract(_) | ClassItem::Intersect(_)))
+}
+
+fn transform_char_class(class: &CharClass, span: Span, target: Target) -> Result<String> {
+    let (items, set_operations) = partition_set_operations(class);
+
+    if !set_operations.is_empty() {
+        if let Some(set) = ascii_class_set(&items, &set_operations) {
+            return Ok(ascii_set_to_class(&set, class.negative, target));
+        }
+
+        if !target.supports_class_set_operations() {
+            return Err(CompilerError::UnsupportedByTarget {
+                construct: "a class set operation on non-ASCII items",
+                target,
+                span,
+            });
+        }
+    }
+
+    let mut regex = String::from(if class.negative { "[^" } else { "[" });
+
+    // set operations apply to the union of the other items, so they're emitted last
+    for item in items.into_iter().chain(set_operations) {
+        regex.push_str(&transform_class_item(item, span, target)?);
+    }
+
+    regex.push(']');
+
+    Ok(regex)
+}
+
+fn transform_class_item(item: &ClassItem, span: Span, target: Target) -> Result<String> {
+    let regex = match item {
+        ClassItem::Range(range) => {
+            let (start, end) = range_bounds(range);
+            format!(
+                "{}-{}",
+                escape_class_char(start, target),
+                escape_class_char(end, target)
+            )
+        }
+        ClassItem::Char(char) => escape_class_char(*char, target),
+        ClassItem::Raw(raw) => raw.to_string(),
+        ClassItem::Symbol(symbol) => transform_class_symbol(symbol, target)?.to_owned(),
+        ClassItem::UnicodeCategory(category) => transform_unicode_category(category, span, target)?,
+        ClassItem::UnicodeProperty(property) => transform_unicode_property(property, span, target)?,
+        ClassItem::Subtract(items) => format!("--[{}]", transform_class_items(items, span, target)?),
+        ClassItem::Intersect(items) => format!("&&[{}]", transform_class_items(items, span, target)?),
+    };
+
+    Ok(regex)
+}
+
+fn transform_class_items(items: &[ClassItem], span: Span, target: Target) -> Result<String> {
+    items
+        .iter()
+        .map(|item| transform_class_item(item, span, target))
+        .collect()
+}
+
+const fn range_bounds(range: &Range) -> (char, char) {
     match range {
-        Range::AsciiRange(range) => {
-            if range.negative {
-                format!("[^{}-{}]", range.start, range.end)
-            } else {
-                format!("[{}-{}]", range.start, range.end)
+        Range::CharRange(range) => (range.start, range.end),
+        Range::NumericRange(range) => (range.start, range.end),
+    }
+}
+
+/// Computes the ASCII characters matched by a class with set operations,
+/// `None` if any item may match a character outside of ASCII
+fn ascii_class_set(items: &[&ClassItem], set_operations: &[&ClassItem]) -> Option<[bool; 128]> {
+    let mut set = ascii_union(items.iter().copied())?;
+
+    for operation in set_operations {
+        match operation {
+            ClassItem::Subtract(items) => {
+                let subtracted = ascii_union(items)?;
+                for (matched, removed) in set.iter_mut().zip(subtracted) {
+                    *matched &= !removed;
+                }
             }
+            ClassItem::Intersect(items) => {
+                let intersected = ascii_union(items)?;
+                for (matched, kept) in set.iter_mut().zip(intersected) {
+                    *matched &= kept;
+                }
+            }
+            _ => return None,
         }
-        Range::NumericRange(range) => {
-            if range.negative {
-                format!("[^{}-{}]", range.start, range.end)
-            } else {
-                format!("[{}-{}]", range.start, range.end)
+    }
+
+    Some(set)
+}
+
+fn ascii_union<'item>(items: impl IntoIterator<Item = &'item ClassItem>) -> Option<[bool; 128]> {
+    let mut set = [false; 128];
+
+    for item in items {
+        let mut include = |start: char, end: char| {
+            for char in start..=end {
+                set[usize::from(u8::try_from(char).ok().filter(u8::is_ascii)?)] = true;
             }
+            Some(())
+        };
+
+        match item {
+            ClassItem::Range(range) => {
+                let (start, end) = range_bounds(range);
+                include(start, end)?;
+            }
+            ClassItem::Char(char) => include(*char, *char)?,
+            ClassItem::Symbol(symbol) if !symbol.negative => match symbol.kind {
+                SymbolKind::Space => include(' ', ' ')?,
+                SymbolKind::Newline => include('\n', '\n')?,
+                SymbolKind::Return => include('\r', '\r')?,
+                SymbolKind::Tab => include('\t', '\t')?,
+                SymbolKind::Null => include('\0', '\0')?,
+                SymbolKind::Feed => include('\u{c}', '\u{c}')?,
+                SymbolKind::Backspace => include('\u{8}', '\u{8}')?,
+                SymbolKind::Digit => include('0', '9')?,
+                SymbolKind::Alphabetic => {
+                    include('a', 'z')?;
+                    include('A', 'Z')?;
+                }
+                SymbolKind::Alphanumeric | SymbolKind::Word => {
+                    include('a', 'z')?;
+                    include('A', 'Z')?;
+                    include('0', '9')?;
+                    if matches!(symbol.kind, SymbolKind::Word) {
+                        include('_', '_')?;
+                    }
+                }
+                // `\s` and `\v` match unicode whitespace on most targets
+                SymbolKind::Whitespace
+                | SymbolKind::Vertical
+                | SymbolKind::Char
+                | SymbolKind::Boundary
+                | SymbolKind::Grapheme
+                | SymbolKind::LineBreak => return None,
+            },
+            _ => return None,
+        }
+    }
+
+    Some(set)
+}
+
+fn ascii_set_to_class(set: &[bool; 128], negative: bool, target: Target) -> String {
+    if !set.contains(&true) {
+        // an empty class, which not every target accepts as `[]`
+        return String::from(if negative { "[\\s\\S]" } else { "[^\\s\\S]" });
+    }
+
+    let mut regex = String::from(if negative { "[^" } else { "[" });
+    let mut index = 0;
+
+    while index < set.len() {
+        if !set[index] {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        while index < set.len() && set[index] {
+            index += 1;
         }
+        let end = index - 1;
+
+        let start_char = escape_ascii_class_char(start, target);
+        let end_char = escape_ascii_class_char(end, target);
+
+        match end - start {
+            0 => regex.push_str(&start_char),
+            1 => regex.push_str(&format!("{start_char}{end_char}")),
+            _ => regex.push_str(&format!("{start_char}-{end_char}")),
+        }
+    }
+
+    regex.push(']');
+
+    regex
+}
+
+fn escape_ascii_class_char(index: usize, target: Target) -> String {
+    escape_class_char(char::from(u8::try_from(index).unwrap_or_default()), target)
+}
+
+fn escape_class_char(char: char, target: Target) -> String {
+    escape_for_target(char.encode_utf8(&mut [0; 4]), target, true)
+}
+
+fn transform_class_symbol(symbol: &Symbol, target: Target) -> Result<&'static str> {
+    if let (Some(escape), false) = (hex_control_escape(&symbol.kind, target), symbol.negative) {
+        return Ok(escape);
     }
+
+    let transformed_symbol = match (&symbol.kind, symbol.negative) {
+        (SymbolKind::Whitespace, true) => "\\S",
+        (SymbolKind::Digit, true) => "\\D",
+        (SymbolKind::Word, true) => "\\W",
+        (SymbolKind::Space, false) => " ",
+        (SymbolKind::Newline, false) => "\\n",
+        (SymbolKind::Vertical, false) => "\\v",
+        (SymbolKind::Return, false) => "\\r",
+        (SymbolKind::Tab, false) => "\\t",
+        (SymbolKind::Null, false) => "\\0",
+        (SymbolKind::Whitespace, false) => "\\s",
+        (SymbolKind::Alphabetic, false) => "a-zA-Z",
+        (SymbolKind::Alphanumeric, false) => "a-zA-Z0-9",
+        (SymbolKind::Digit, false) => "\\d",
+        (SymbolKind::Word, false) => "\\w",
+        (SymbolKind::Feed, false) => "\\f",
+        (SymbolKind::Backspace, false) => "\\b",
+        _ => return Err(CompilerError::UnexpectedSymbolInClass),
+    };
+
+    Ok(transformed_symbol)
 }
 
-fn transform_symbol(symbol: &Symbol) -> String {
+fn transform_symbol(symbol: &Symbol, span: Span, target: Target) -> Result<String> {
+    if let Some(escape) = hex_control_escape(&symbol.kind, target) {
+        return Ok(match (symbol.negative, &symbol.kind) {
+            (true, _) => format!("[^{escape}]"),
+            (false, SymbolKind::Backspace) => format!("[{escape}]"),
+            (false, _) => String::from(escape),
+        });
+    }
+
+    if symbol.kind == SymbolKind::Grapheme && !target.supports_grapheme_clusters() {
+        return Err(CompilerError::UnsupportedByTarget {
+            construct: "a grapheme cluster",
+            target,
+            span,
+        });
+    }
+
     let transformed_symbol = if symbol.negative {
         match symbol.kind {
             SymbolKind::Space => "[^ ]",
@@ -153,6 +770,8 @@ fn transform_symbol(symbol: &Symbol) -> String {
             SymbolKind::Feed => "[^\\f]",
             SymbolKind::Backspace => "[^\\b]",
             SymbolKind::Boundary => "\\B",
+            // negated graphemes and line breaks are rejected when parsing
+            SymbolKind::Grapheme | SymbolKind::LineBreak => "\\X",
         }
     } else {
         match symbol.kind {
@@ -171,16 +790,54 @@ fn transform_symbol(symbol: &Symbol) -> String {
             SymbolKind::Feed => "\\f",
             SymbolKind::Backspace => "[\\b]",
             SymbolKind::Boundary => "\\b",
+            SymbolKind::Grapheme => "\\X",
+            SymbolKind::LineBreak if target.supports_line_break_escape() => "\\R",
+            SymbolKind::LineBreak => "(?:\\r\\n|\\r|\\n)",
         }
     };
 
-    String::from(transformed_symbol)
+    Ok(String::from(transformed_symbol))
+}
+
+/// The hex escape for a control symbol whose usual escape means something else on the target,
+/// PCRE2 reads `\v` as any vertical whitespace
+const fn hex_control_escape(kind: &SymbolKind, target: Target) -> Option<&'static str> {
+    match kind {
+        SymbolKind::Null if !supports_control_escapes(target) => Some("\\x00"),
+        SymbolKind::Backspace if !supports_control_escapes(target) => Some("\\x08"),
+        SymbolKind::Vertical if matches!(target, Target::Pcre2) => Some("\\x0B"),
+        _ => None,
+    }
 }
 
-fn transform_unicode_category(category: &UnicodeCategory) -> String {
+fn transform_unicode_category(category: &UnicodeCategory, span: Span, target: Target) -> Result<String> {
+    if !target.supports_unicode_categories() {
+        return Err(CompilerError::UnsupportedByTarget {
+            construct: "a unicode category escape",
+            target,
+            span,
+        });
+    }
+
     let prefix = if category.negative { "\\P" } else { "\\p" };
 
-    let transformed_category = match category.kind {
+    let transformed_category = match (&category.kind, target) {
+        (UnicodeCategoryKind::CasedLetter, Target::Re2) => {
+            return Err(CompilerError::UnsupportedByTarget {
+                construct: "the cased letter category",
+                target,
+                span,
+            });
+        }
+        (UnicodeCategoryKind::CasedLetter, Target::RustRegex | Target::RustRegexBytes) => "LC",
+        (kind, _) => category_name(kind),
+    };
+
+    Ok(format!("{prefix}{{{transformed_category}}}"))
+}
+
+const fn category_name(kind: &UnicodeCategoryKind) -> &'static str {
+    match kind {
         UnicodeCategoryKind::CasedLetter => "L&",
         UnicodeCategoryKind::ClosePunctuation => "Pe",
         UnicodeCategoryKind::ConnectorPunctuation => "Pc",
@@ -219,7 +876,27 @@ fn transform_unicode_category(category: &UnicodeCategory) -> String {
         UnicodeCategoryKind::TitlecaseLetter => "Lt",
         UnicodeCategoryKind::Unassigned => "Cn",
         UnicodeCategoryKind::UppercaseLetter => "Lu",
+    }
+}
+
+fn transform_unicode_property(property: &UnicodeProperty, span: Span, target: Target) -> Result<String> {
+    if !target.supports_unicode_properties() {
+        return Err(CompilerError::UnsupportedByTarget {
+            construct: "a unicode property escape",
+            target,
+            span,
+        });
+    }
+
+    let prefix = if property.negative { "\\P" } else { "\\p" };
+    let name = &property.name;
+
+    let regex = match (property.kind, target) {
+        (UnicodePropertyKind::Script, Target::EcmaScript) => format!("{prefix}{{Script={name}}}"),
+        (UnicodePropertyKind::Script | UnicodePropertyKind::Binary, _) => {
+            format!("{prefix}{{{name}}}")
+        }
     };
 
-    format!("{prefix}{{{transformed_category}}}")
+    Ok(regex)
 }
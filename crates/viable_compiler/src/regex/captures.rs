@@ -0,0 +1,60 @@
+use crate::ast::types::ast::{Assertion, Group, GroupKind, Span, ViableAst};
+use crate::ast::visitor::{walk_assertion, walk_group};
+use crate::ast::{raw_regex_captures, walk, Visitor};
+use crate::metadata::CaptureInfo;
+use crate::prelude::*;
+
+/// Lists the capture groups of an AST in the order the compiled pattern numbers them
+///
+/// Variables are expanded at every invocation, so each invocation contributes its own copies of the
+/// variable's captures. Every other group kind compiles to a non-capturing group and isn't numbered.
+pub fn capture_groups(ast: &ViableAst) -> Vec<CaptureInfo> {
+    let mut collector = CaptureCollector {
+        captures: Vec::new(),
+        negative_depth: 0,
+    };
+    walk(ast, &mut collector);
+    collector.captures
+}
+
+struct CaptureCollector {
+    captures: Vec<CaptureInfo>,
+    /// how many negative assertions the visited node is within
+    negative_depth: usize,
+}
+
+impl Visitor for CaptureCollector {
+    fn visit_group(&mut self, group: &Group, span: Span) {
+        // a group is numbered by its opening parenthesis, so before the groups it contains
+        if group.kind == GroupKind::Capture {
+            self.captures.push(CaptureInfo {
+                index: self.captures.len() + 1,
+                name: group.ident.as_deref().map(str::to_owned),
+                span,
+                in_negative_assertion: self.negative_depth > 0,
+            });
+        }
+
+        walk_group(self, group);
+    }
+
+    fn visit_raw_regex(&mut self, regex: &str, span: Span) {
+        // the groups of a raw regex have no statement of their own, so they take the span of the whole statement
+        for name in raw_regex_captures(regex).unwrap_or_default() {
+            self.captures.push(CaptureInfo {
+                index: self.captures.len() + 1,
+                name: name.map(str::to_owned),
+                span,
+                in_negative_assertion: self.negative_depth > 0,
+            });
+        }
+    }
+
+    fn visit_assertion(&mut self, assertion: &Assertion, _span: Span) {
+        let negative = usize::from(assertion.negative);
+
+        self.negative_depth += negative;
+        walk_assertion(self, assertion);
+        self.negative_depth -= negative;
+    }
+}
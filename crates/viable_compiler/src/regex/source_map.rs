@@ -0,0 +1,202 @@
+use super::ast_to_regex::{
+    assertion_opener, ast_to_regex, conditional_branch, conditional_opener, ends_with_numbered_backreference,
+    expression_to_regex, flags_to_regex, group_opener, has_top_level_alternation, node_to_regex, quantified_to_regex,
+};
+use crate::ast::types::ast::{
+    Conditional, Expression, Group, GroupKind, PatternFlags, Span, Spanned, ViableAst, ViableAstNode,
+};
+use crate::metadata::{Mapping, NodeKind, SourceMap};
+use crate::prelude::*;
+use crate::target::Target;
+use crate::types::Result;
+
+/// Maps the fragments of the pattern [`ast_to_regex`] and [`flags_to_regex`] compile an AST and its flags
+/// to back to the statements they were compiled from
+///
+/// # Errors
+///
+/// Returns [`crate::errors::CompilerError::UnsupportedByTarget`] if the AST uses a construct the target cannot
+/// express
+pub fn source_map(ast: &ViableAst, flags: &PatternFlags, target: Target) -> Result<SourceMap> {
+    let mut mapper = Mapper {
+        target,
+        mappings: Vec::new(),
+    };
+
+    let flags_len = flags_to_regex(flags, target)?.len();
+    mapper.push(0, flags_len, flags.span, NodeKind::Flags);
+    mapper.ast(ast, flags_len)?;
+
+    Ok(SourceMap {
+        mappings: mapper.mappings,
+    })
+}
+
+/// Walks an AST the way it is compiled, following the offset each statement is emitted at
+struct Mapper {
+    target: Target,
+    mappings: Vec<Mapping>,
+}
+
+impl Mapper {
+    fn ast(&mut self, ast: &ViableAst, start: usize) -> Result<()> {
+        let ViableAst::Root(nodes) = ast else {
+            return Ok(());
+        };
+
+        let mut offset = start;
+        let mut after_numbered_backreference = false;
+
+        for node in nodes {
+            let fragment = node_to_regex(node, self.target)?;
+
+            if fragment.is_empty() {
+                continue;
+            }
+
+            // skips the group separating `\1` from a literal `2`, as `ast_to_regex` emits it
+            if after_numbered_backreference && fragment.starts_with(|char: char| char.is_ascii_digit()) {
+                offset += "(?:)".len();
+            }
+
+            after_numbered_backreference = !fragment.ends_with(')') && ends_with_numbered_backreference(&node.node);
+            self.node(node, offset, fragment.len())?;
+            offset += fragment.len();
+        }
+
+        Ok(())
+    }
+
+    fn node(&mut self, node: &Spanned<ViableAstNode>, start: usize, len: usize) -> Result<()> {
+        let Some(kind) = node_kind(&node.node) else {
+            return Ok(());
+        };
+
+        self.push(start, len, node.span, kind);
+
+        match &node.node {
+            ViableAstNode::Group(group) => self.group(group, node.span, start, false),
+            ViableAstNode::Assertion(assertion) => {
+                self.ast(&assertion.statements, start + assertion_opener(assertion).len())
+            }
+            ViableAstNode::Quantifier(quantifier) => {
+                // an expression the quantifier can't apply to directly, or counts in a unit, is wrapped in a group
+                let expression = expression_to_regex(&quantifier.expression, self.target)?;
+                let repeated = quantified_to_regex(quantifier, node.span, self.target)?;
+                let offset = if repeated == expression {
+                    start
+                } else {
+                    start + repeated.len() - expression.len() - ")".len()
+                };
+
+                let span = quantifier.expression.span;
+                self.push(
+                    offset,
+                    expression.len(),
+                    span,
+                    expression_kind(&quantifier.expression.node),
+                );
+                match &quantifier.expression.node {
+                    Expression::Group(group) => self.group(group, span, offset, true),
+                    _ => Ok(()),
+                }
+            }
+            ViableAstNode::Conditional(conditional) => self.conditional(conditional, node.span, start),
+            ViableAstNode::VariableInvocation(variable_invocation) => self.ast(&variable_invocation.statements, start),
+            _ => Ok(()),
+        }
+    }
+
+    fn group(&mut self, group: &Group, span: Span, start: usize, quantified: bool) -> Result<()> {
+        let opener = group_opener(group, span, self.target)?;
+
+        match (&group.kind, group.statements.as_ref()) {
+            // a `match` group is only parenthesized where it is quantified or holds a raw alternation
+            (GroupKind::Match, statements) => {
+                let parenthesized = quantified || has_top_level_alternation(&ast_to_regex(statements, self.target)?);
+                self.ast(statements, if parenthesized { start + opener.len() } else { start })
+            }
+            (GroupKind::Either, ViableAst::Root(branches)) => {
+                let mut offset = start + opener.len();
+                for branch in branches {
+                    let fragment = node_to_regex(branch, self.target)?;
+                    self.node(branch, offset, fragment.len())?;
+                    offset += fragment.len() + "|".len();
+                }
+                Ok(())
+            }
+            (_, statements) => self.ast(statements, start + opener.len()),
+        }
+    }
+
+    fn conditional(&mut self, conditional: &Conditional, span: Span, start: usize) -> Result<()> {
+        let mut offset = start + conditional_opener(conditional, span, self.target)?.len();
+        let branches = core::iter::once(&conditional.then).chain(&conditional.otherwise);
+
+        for branch in branches {
+            let regex = conditional_branch(branch, self.target)?;
+            // a branch holding an alternation is wrapped in `(?:` and `)`
+            let wrapped = regex != ast_to_regex(branch, self.target)?;
+            self.ast(branch, if wrapped { offset + "(?:".len() } else { offset })?;
+            offset += regex.len() + "|".len();
+        }
+
+        Ok(())
+    }
+
+    /// Records a fragment, unless it is empty or of a statement added by a pass rather than parsed from the source
+    fn push(&mut self, start: usize, len: usize, span: Span, node_kind: NodeKind) {
+        if len == 0 || span == Span::default() {
+            return;
+        }
+
+        self.mappings.push(Mapping {
+            regex_start: start,
+            regex_end: start + len,
+            source_span: span,
+            node_kind,
+        });
+    }
+}
+
+const fn node_kind(node: &ViableAstNode) -> Option<NodeKind> {
+    let kind = match node {
+        ViableAstNode::Group(Group {
+            kind: GroupKind::Capture,
+            ..
+        }) => NodeKind::Capture,
+        ViableAstNode::Group(_) => NodeKind::Group,
+        ViableAstNode::Assertion(_) => NodeKind::Assertion,
+        ViableAstNode::Conditional(_) => NodeKind::Conditional,
+        ViableAstNode::Quantifier(_) => NodeKind::Quantifier,
+        ViableAstNode::Atom(_) => NodeKind::Atom,
+        ViableAstNode::Range(_) => NodeKind::Range,
+        ViableAstNode::Symbol(_) => NodeKind::Symbol,
+        ViableAstNode::SpecialSymbol(_) => NodeKind::SpecialSymbol,
+        ViableAstNode::UnicodeCategory(_) | ViableAstNode::UnicodeProperty(_) => NodeKind::Unicode,
+        ViableAstNode::NegativeCharClass(_) | ViableAstNode::CharClass(_) => NodeKind::CharClass,
+        ViableAstNode::VariableInvocation(_) => NodeKind::VariableInvocation,
+        ViableAstNode::Backreference(_) => NodeKind::Backreference,
+        ViableAstNode::Recursion(_) => NodeKind::Recursion,
+        ViableAstNode::RawRegex(_) => NodeKind::RawRegex,
+        ViableAstNode::Parameter(_) | ViableAstNode::Empty | ViableAstNode::Skip | ViableAstNode::Error => return None,
+    };
+
+    Some(kind)
+}
+
+const fn expression_kind(expression: &Expression) -> NodeKind {
+    match expression {
+        Expression::Group(Group {
+            kind: GroupKind::Capture,
+            ..
+        }) => NodeKind::Capture,
+        Expression::Group(_) => NodeKind::Group,
+        Expression::Atom(_) => NodeKind::Atom,
+        Expression::Range(_) => NodeKind::Range,
+        Expression::Symbol(_) => NodeKind::Symbol,
+        Expression::UnicodeCategory(_) | Expression::UnicodeProperty(_) => NodeKind::Unicode,
+        Expression::NegativeCharClass(_) | Expression::CharClass(_) => NodeKind::CharClass,
+        Expression::Backreference(_) => NodeKind::Backreference,
+    }
+}
@@ -1,3 +1,6 @@
+use crate::prelude::*;
+use crate::target::Target;
+
 pub fn wrap_quantified(value: String) -> String {
     if directly_quantifiable(&value) {
         value
@@ -25,7 +28,8 @@ fn directly_quantifiable(value: &str) -> bool {
             Some('\\') => {
                 let has_unicode_group_prefix = value.starts_with("\\p{") || value.starts_with("\\P{");
                 let has_unicode_group_suffix = value.ends_with('}');
-                has_unicode_group_prefix && has_unicode_group_suffix
+                let is_named_backreference = value.starts_with("\\k<") && value.ends_with('>');
+                (has_unicode_group_prefix && has_unicode_group_suffix) || is_named_backreference
             }
             _ => false,
         },
@@ -39,3 +43,62 @@ pub fn mark_lazy(quantifier: String, lazy: bool) -> String {
         quantifier
     }
 }
+
+pub fn mark_possessive(quantifier: String, possessive: bool) -> String {
+    if possessive {
+        format!("{quantifier}+")
+    } else {
+        quantifier
+    }
+}
+
+/// Whether the target accepts `\0` and `[\b]`, which RE2 and Rust regex reserve for octal escapes and word boundaries
+pub const fn supports_control_escapes(target: Target) -> bool {
+    !matches!(target, Target::Re2 | Target::RustRegex | Target::RustRegexBytes)
+}
+
+/// Escapes text to match literally on the target, either in the pattern or within a bracket expression
+///
+/// Outside of a class the metacharacters every target shares are escaped, along with `/` for ECMAScript so the
+/// pattern can be written as a literal. Within a class `]`, `[`, `^`, `-` and `\` are, along with the punctuation
+/// Rust regex and Python `re` reserve for set operations. Control characters without a named escape on the target
+/// are written as `\xNN`, `\b` only names a backspace within a class.
+pub fn escape_for_target(text: &str, target: Target, in_class: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for char in text.chars() {
+        let named = match char {
+            '\n' => Some('n'),
+            '\r' => Some('r'),
+            '\t' => Some('t'),
+            '\u{c}' => Some('f'),
+            '\u{8}' if in_class && supports_control_escapes(target) => Some('b'),
+            _ => None,
+        };
+
+        let reserved = if in_class {
+            matches!(char, ']' | '[' | '^' | '-' | '\\')
+                || (matches!(char, '&' | '~' | '|')
+                    && matches!(target, Target::RustRegex | Target::RustRegexBytes | Target::PythonRe))
+        } else {
+            METACHARACTERS.contains(char) || (char == '/' && target == Target::EcmaScript)
+        };
+
+        match named {
+            Some(named) => {
+                escaped.push('\\');
+                escaped.push(named);
+            }
+            _ if char.is_control() => escaped.push_str(&format!("\\x{:02X}", u32::from(char))),
+            _ if reserved => {
+                escaped.push('\\');
+                escaped.push(char);
+            }
+            _ => escaped.push(char),
+        }
+    }
+
+    escaped
+}
+
+const METACHARACTERS: &str = "[](){}*+?|^$.\\";
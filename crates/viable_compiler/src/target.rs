@@ -0,0 +1,241 @@
+mod compatibility;
+
+pub use self::compatibility::{check_target_compatibility, Incompatibility};
+use core::fmt::{self, Display};
+
+/// A regular expression engine to compile Viable source for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Target {
+    /// JavaScript (`RegExp`)
+    #[default]
+    EcmaScript,
+    /// PCRE2 (used by PHP, R and others)
+    Pcre2,
+    /// RE2 (used by Go and others)
+    Re2,
+    /// Python's `re` module (3.11 or later)
+    PythonRe,
+    /// Rust's `regex` crate
+    RustRegex,
+    /// The `regex::bytes` module of Rust's `regex` crate, matching byte strings by byte unless in unicode mode
+    RustRegexBytes,
+}
+
+impl Target {
+    /// Every target, in the order their names are listed
+    pub const ALL: [Self; 6] = [
+        Self::EcmaScript,
+        Self::Pcre2,
+        Self::Re2,
+        Self::PythonRe,
+        Self::RustRegex,
+        Self::RustRegexBytes,
+    ];
+
+    /// The name of the target in lowercase (e.g. `ecmascript`), as accepted by [`Target::from_name`]
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::EcmaScript => "ecmascript",
+            Self::Pcre2 => "pcre2",
+            Self::Re2 => "re2",
+            Self::PythonRe => "python",
+            Self::RustRegex => "rust",
+            Self::RustRegexBytes => "rust_bytes",
+        }
+    }
+
+    /// The target of the given [name](Target::name), ignoring case, also accepting `js`, `javascript`, `pcre` and
+    /// `py`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = match name.to_ascii_lowercase().as_str() {
+            "js" | "javascript" => "ecmascript",
+            "pcre" => "pcre2",
+            "py" => "python",
+            _ => name,
+        };
+
+        Self::ALL
+            .into_iter()
+            .find(|target| target.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Whether the target supports possessive quantifiers (`a++`)
+    #[must_use]
+    pub const fn supports_possessive_quantifiers(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Re2 | Self::RustRegex | Self::RustRegexBytes => false,
+            Self::Pcre2 | Self::PythonRe => true,
+        }
+    }
+
+    /// Whether the target has a free-spacing mode (`(?x)`), ignoring whitespace and `#` comments in the pattern,
+    /// see [`crate::options::EmitStyle::Annotated`]
+    #[must_use]
+    pub const fn supports_free_spacing(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Re2 => false,
+            Self::Pcre2 | Self::PythonRe | Self::RustRegex | Self::RustRegexBytes => true,
+        }
+    }
+
+    /// Whether the target supports setting pattern flags from within the pattern (`(?i)`)
+    ///
+    /// Flags for targets that don't are passed to the engine alongside the pattern instead
+    #[must_use]
+    pub const fn supports_inline_flags(self) -> bool {
+        match self {
+            Self::EcmaScript => false,
+            Self::Pcre2 | Self::Re2 | Self::PythonRe | Self::RustRegex | Self::RustRegexBytes => true,
+        }
+    }
+
+    /// Whether the target supports unicode script and binary property escapes (`\p{Script=Greek}`)
+    #[must_use]
+    pub const fn supports_unicode_properties(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Pcre2 | Self::Re2 | Self::RustRegex | Self::RustRegexBytes => true,
+            Self::PythonRe => false,
+        }
+    }
+
+    /// Whether the target supports unicode general category escapes (`\p{Lu}`)
+    #[must_use]
+    pub const fn supports_unicode_categories(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Pcre2 | Self::Re2 | Self::RustRegex | Self::RustRegexBytes => true,
+            Self::PythonRe => false,
+        }
+    }
+
+    /// Whether the target supports matching an extended grapheme cluster (`\X`)
+    #[must_use]
+    pub const fn supports_grapheme_clusters(self) -> bool {
+        match self {
+            Self::Pcre2 => true,
+            Self::EcmaScript | Self::Re2 | Self::PythonRe | Self::RustRegex | Self::RustRegexBytes => false,
+        }
+    }
+
+    /// Whether the target supports the generic line break escape (`\R`)
+    #[must_use]
+    pub const fn supports_line_break_escape(self) -> bool {
+        match self {
+            Self::Pcre2 => true,
+            Self::EcmaScript | Self::Re2 | Self::PythonRe | Self::RustRegex | Self::RustRegexBytes => false,
+        }
+    }
+
+    /// Whether the target supports class subtraction and intersection (`[a-z--[aeiou]]`, `[\w&&[0-9]]`)
+    ///
+    /// Set operations on ASCII-only classes are computed by the compiler for every target
+    #[must_use]
+    pub const fn supports_class_set_operations(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Pcre2 | Self::Re2 | Self::PythonRe => false,
+            Self::RustRegex | Self::RustRegexBytes => true,
+        }
+    }
+
+    /// Whether the target supports atomic groups (`(?>...)`)
+    #[must_use]
+    pub const fn supports_atomic_groups(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Re2 | Self::RustRegex | Self::RustRegexBytes => false,
+            Self::Pcre2 | Self::PythonRe => true,
+        }
+    }
+
+    /// Whether the target supports lookahead and lookbehind assertions (`(?=...)`, `(?<=...)`)
+    ///
+    /// Linear time engines can't look around the current position
+    #[must_use]
+    pub const fn supports_lookaround(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Pcre2 | Self::PythonRe => true,
+            Self::Re2 | Self::RustRegex | Self::RustRegexBytes => false,
+        }
+    }
+
+    /// Whether the target supports lookbehind assertions that can match strings of different lengths (`(?<=a|bc)`)
+    #[must_use]
+    pub const fn supports_variable_length_lookbehind(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Pcre2 => true,
+            Self::Re2 | Self::PythonRe | Self::RustRegex | Self::RustRegexBytes => false,
+        }
+    }
+
+    /// Whether the target supports lookbehind assertions that can match strings of any length (`(?<=a+)`), rather
+    /// than only of a bounded length
+    #[must_use]
+    pub const fn supports_unbounded_lookbehind(self) -> bool {
+        match self {
+            Self::EcmaScript => true,
+            Self::Pcre2 | Self::Re2 | Self::PythonRe | Self::RustRegex | Self::RustRegexBytes => false,
+        }
+    }
+
+    /// Whether the target supports backreferences to capture groups (`\1`, `\k<name>`)
+    #[must_use]
+    pub const fn supports_backreferences(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Pcre2 | Self::PythonRe => true,
+            Self::Re2 | Self::RustRegex | Self::RustRegexBytes => false,
+        }
+    }
+
+    /// Whether the target supports conditionals, matching one of two branches depending on whether a capture
+    /// group took part in the match (`(?(1)a|b)`)
+    #[must_use]
+    pub const fn supports_conditionals(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Re2 | Self::RustRegex | Self::RustRegexBytes => false,
+            Self::Pcre2 | Self::PythonRe => true,
+        }
+    }
+
+    /// Whether the target supports recursion into the whole pattern or a capture group (`(?R)`, `(?&name)`)
+    #[must_use]
+    pub const fn supports_recursion(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Re2 | Self::PythonRe | Self::RustRegex | Self::RustRegexBytes => false,
+            Self::Pcre2 => true,
+        }
+    }
+
+    /// Whether the target matches byte strings, where a pattern matches by byte unless in unicode mode (`(?u)`),
+    /// see [`crate::options::CompilerOptions::unicode_mode`]
+    #[must_use]
+    pub const fn matches_bytes(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Pcre2 | Self::Re2 | Self::PythonRe | Self::RustRegex => false,
+            Self::RustRegexBytes => true,
+        }
+    }
+
+    /// Whether named capture groups are written `(?P<name>...)` rather than `(?<name>...)`
+    #[must_use]
+    pub const fn uses_python_named_groups(self) -> bool {
+        match self {
+            Self::EcmaScript | Self::Pcre2 | Self::RustRegex | Self::RustRegexBytes => false,
+            Self::Re2 | Self::PythonRe => true,
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::EcmaScript => "ECMAScript",
+            Self::Pcre2 => "PCRE2",
+            Self::Re2 => "RE2",
+            Self::PythonRe => "Python re",
+            Self::RustRegex => "Rust regex",
+            Self::RustRegexBytes => "Rust regex::bytes",
+        };
+
+        formatter.write_str(name)
+    }
+}
@@ -0,0 +1,213 @@
+use super::Target;
+use crate::ast::types::ast::{
+    Assertion, AssertionKind, CharClass, ClassItem, Conditional, Expression, Group, GroupKind, QuantifierUnit, Span,
+    Symbol, SymbolKind, UnicodeCategory, UnicodeCategoryKind, ViableAst, ViableAstNode,
+};
+use crate::ast::{supports_lookbehind_body, width_bounds};
+use crate::prelude::*;
+use crate::regex::ast_to_regex::needs_class_set_operations;
+
+/// A construct that the target cannot express
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incompatibility {
+    /// what the construct is, e.g. `"a lookbehind assertion"`
+    pub construct: &'static str,
+    pub span: Span,
+}
+
+/**
+Lists every construct of an AST that the target cannot express, in source order.
+
+Compiling an AST for the target fails on the first of these with [`crate::errors::CompilerError::UnsupportedByTarget`],
+or [`crate::errors::CompilerError::VariableLengthLookbehind`] for the body of a lookbehind assertion.
+Pattern flags aren't part of the AST and are therefore not checked, and neither are raw regexes
+(`raw_regex "...";`), which are emitted as written.
+
+# Example
+
+```rust
+use viable_compiler::ast::to_ast;
+use viable_compiler::target::{check_target_compatibility, Target};
+
+let ast = to_ast(r#"ahead { "a"; } possessive some of "b";"#).unwrap();
+let incompatibilities = check_target_compatibility(&ast, Target::Re2);
+
+assert_eq!(incompatibilities[0].construct, "a lookahead assertion");
+assert_eq!(incompatibilities[1].construct, "a possessive quantifier");
+```
+*/
+#[must_use]
+pub fn check_target_compatibility(ast: &ViableAst, target: Target) -> Vec<Incompatibility> {
+    let mut checker = Checker {
+        target,
+        incompatibilities: Vec::new(),
+    };
+
+    checker.ast(ast);
+
+    checker.incompatibilities
+}
+
+struct Checker {
+    target: Target,
+    incompatibilities: Vec<Incompatibility>,
+}
+
+impl Checker {
+    fn report(&mut self, construct: &'static str, span: Span) {
+        self.incompatibilities.push(Incompatibility { construct, span });
+    }
+
+    fn ast(&mut self, ast: &ViableAst) {
+        let ViableAst::Root(nodes) = ast else {
+            return;
+        };
+
+        for node in nodes {
+            self.node(&node.node, node.span);
+        }
+    }
+
+    fn node(&mut self, node: &ViableAstNode, span: Span) {
+        match node {
+            ViableAstNode::Group(group) => self.group(group, span),
+            ViableAstNode::Assertion(assertion) => self.assertion(assertion, span),
+            ViableAstNode::Conditional(conditional) => self.conditional(conditional, span),
+            ViableAstNode::Quantifier(quantifier) => {
+                if quantifier.possessive && !self.target.supports_possessive_quantifiers() {
+                    self.report("a possessive quantifier", span);
+                }
+                if quantifier.unit == Some(QuantifierUnit::Bytes) && !self.target.matches_bytes() {
+                    self.report("a quantifier counting bytes", span);
+                }
+                self.expression(&quantifier.expression.node, quantifier.expression.span);
+            }
+            ViableAstNode::UnicodeCategory(category) => self.unicode_category(category, span),
+            ViableAstNode::UnicodeProperty(_) => self.unicode_property(span),
+            ViableAstNode::CharClass(class) => self.char_class(class, span),
+            ViableAstNode::VariableInvocation(invocation) => self.ast(&invocation.statements),
+            ViableAstNode::Backreference(_) => self.backreference(span),
+            ViableAstNode::Recursion(_) => self.recursion(span),
+            ViableAstNode::Symbol(symbol) => self.symbol(symbol, span),
+            ViableAstNode::Atom(_)
+            | ViableAstNode::Range(_)
+            | ViableAstNode::SpecialSymbol(_)
+            | ViableAstNode::NegativeCharClass(_)
+            | ViableAstNode::Parameter(_)
+            // what a raw regex holds is unknown, so the user is trusted to have written one the target supports
+            | ViableAstNode::RawRegex(_)
+            | ViableAstNode::Empty
+            | ViableAstNode::Skip
+            | ViableAstNode::Error => {}
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression, span: Span) {
+        match expression {
+            Expression::Group(group) => self.group(group, span),
+            Expression::UnicodeCategory(category) => self.unicode_category(category, span),
+            Expression::UnicodeProperty(_) => self.unicode_property(span),
+            Expression::CharClass(class) => self.char_class(class, span),
+            Expression::Backreference(_) => self.backreference(span),
+            Expression::Symbol(symbol) => self.symbol(symbol, span),
+            Expression::Atom(_) | Expression::Range(_) | Expression::NegativeCharClass(_) => {}
+        }
+    }
+
+    fn group(&mut self, group: &Group, span: Span) {
+        if group.kind == GroupKind::Atomic && !self.target.supports_atomic_groups() {
+            self.report("an atomic group", span);
+        }
+
+        self.ast(&group.statements);
+    }
+
+    fn assertion(&mut self, assertion: &Assertion, span: Span) {
+        let behind = matches!(assertion.kind, AssertionKind::Behind);
+
+        if !self.target.supports_lookaround() {
+            self.report(
+                if behind {
+                    "a lookbehind assertion"
+                } else {
+                    "a lookahead assertion"
+                },
+                span,
+            );
+        } else if behind && !supports_lookbehind_body(&assertion.statements, self.target) {
+            let unbounded = width_bounds(&assertion.statements).is_some_and(|(_, max)| max.is_none());
+            self.report(
+                if unbounded && self.target.supports_variable_length_lookbehind() {
+                    "an unbounded lookbehind assertion"
+                } else {
+                    "a variable length lookbehind assertion"
+                },
+                span,
+            );
+        }
+
+        self.ast(&assertion.statements);
+    }
+
+    fn unicode_category(&mut self, category: &UnicodeCategory, span: Span) {
+        if !self.target.supports_unicode_categories() {
+            self.report("a unicode category escape", span);
+        } else if matches!(category.kind, UnicodeCategoryKind::CasedLetter) && self.target == Target::Re2 {
+            self.report("the cased letter category", span);
+        }
+    }
+
+    fn unicode_property(&mut self, span: Span) {
+        if !self.target.supports_unicode_properties() {
+            self.report("a unicode property escape", span);
+        }
+    }
+
+    fn symbol(&mut self, symbol: &Symbol, span: Span) {
+        if symbol.kind == SymbolKind::Grapheme && !self.target.supports_grapheme_clusters() {
+            self.report("a grapheme cluster", span);
+        }
+    }
+
+    fn conditional(&mut self, conditional: &Conditional, span: Span) {
+        if !self.target.supports_conditionals() {
+            self.report("a conditional", span);
+        }
+
+        self.ast(&conditional.then);
+        if let Some(otherwise) = &conditional.otherwise {
+            self.ast(otherwise);
+        }
+    }
+
+    fn backreference(&mut self, span: Span) {
+        if !self.target.supports_backreferences() {
+            self.report("a backreference", span);
+        }
+    }
+
+    fn recursion(&mut self, span: Span) {
+        if !self.target.supports_recursion() {
+            self.report("recursion", span);
+        }
+    }
+
+    fn char_class(&mut self, class: &CharClass, span: Span) {
+        if needs_class_set_operations(class) && !self.target.supports_class_set_operations() {
+            self.report("a class set operation on non-ASCII items", span);
+        }
+
+        self.class_items(&class.items, span);
+    }
+
+    fn class_items(&mut self, items: &[ClassItem], span: Span) {
+        for item in items {
+            match item {
+                ClassItem::UnicodeCategory(category) => self.unicode_category(category, span),
+                ClassItem::UnicodeProperty(_) => self.unicode_property(span),
+                ClassItem::Subtract(items) | ClassItem::Intersect(items) => self.class_items(items, span),
+                ClassItem::Range(_) | ClassItem::Char(_) | ClassItem::Raw(_) | ClassItem::Symbol(_) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Test cases embedded in a source with `tests { ... }` blocks
+//!
+//! The cases are collected separately from the AST by [`to_ast_with_tests`](crate::ast::to_ast_with_tests), so
+//! they never affect the compiled pattern. [`run_embedded_tests`] checks them against the pattern compiled for
+//! the `regex` crate.
+
+use crate::ast::types::ast::Span;
+use crate::prelude::*;
+use core::fmt::{self, Display, Formatter};
+
+/// Whether a test case expects the pattern to match its input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Expectation {
+    /// `match "...";`
+    Match,
+    /// `no match "...";`
+    NoMatch,
+}
+
+/// A test case of a `tests` block, e.g. `match "2024-01-01" captures { year = "2024" };`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EmbeddedTest {
+    pub expectation: Expectation,
+    /// the input with its escapes resolved
+    pub input: String,
+    /// the text each named capture group is expected to capture, in source order
+    pub captures: Vec<(String, String)>,
+    /// the span of the whole test case
+    pub span: Span,
+}
+
+/// A named capture group that didn't capture what a test case expected
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CaptureMismatch {
+    pub name: String,
+    pub expected: String,
+    /// the text the group captured, `None` if it didn't participate in the match or doesn't exist
+    pub actual: Option<String>,
+}
+
+/// The outcome of running an [`EmbeddedTest`]
+///
+/// Displays as a description of the outcome, e.g. `expected "2024-01-01" to match`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TestResult {
+    pub test: EmbeddedTest,
+    pub passed: bool,
+    /// the expected captures that differ from those of the first match, empty if the input didn't match
+    pub mismatches: Vec<CaptureMismatch>,
+}
+
+impl TestResult {
+    /// The span of the test case, to point at the failing line
+    #[must_use]
+    pub const fn span(&self) -> Span {
+        self.test.span
+    }
+}
+
+impl Display for TestResult {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        let input = &self.test.input;
+
+        match (self.test.expectation, self.passed) {
+            (Expectation::Match, true) => write!(formatter, "{input:?} matches"),
+            (Expectation::NoMatch, true) => write!(formatter, "{input:?} doesn't match"),
+            (Expectation::NoMatch, false) => write!(formatter, "expected {input:?} not to match"),
+            (Expectation::Match, false) if self.mismatches.is_empty() => {
+                write!(formatter, "expected {input:?} to match")
+            }
+            (Expectation::Match, false) => {
+                let mismatches: Vec<String> = self
+                    .mismatches
+                    .iter()
+                    .map(|mismatch| match &mismatch.actual {
+                        Some(actual) => format!(
+                            "`{}` to capture {:?} but it captured {actual:?}",
+                            mismatch.name, mismatch.expected
+                        ),
+                        None => format!(
+                            "`{}` to capture {:?} but it didn't participate",
+                            mismatch.name, mismatch.expected
+                        ),
+                    })
+                    .collect();
+
+                write!(formatter, "expected {} in {input:?}", mismatches.join(", "))
+            }
+        }
+    }
+}
+
+/**
+Runs the test cases of the `tests` blocks of a source against the pattern compiled for the `regex` crate.
+
+A `match` case passes if the pattern matches anywhere in its input and each expected capture equals the text
+captured by the first match, a `no match` case passes if the pattern doesn't match anywhere in its input.
+Requires the `regex` feature.
+
+# Errors
+
+Returns an [`errors::CompilerError`](crate::errors::CompilerError) if the source fails to compile, see
+[`compile_to_regex`](crate::compile_to_regex)
+
+# Example
+
+```rust
+use viable_compiler::testing::run_embedded_tests;
+
+let source = r#"
+capture year { 4 of <digit>; } "-"; 2 of <digit>;
+
+tests {
+    match "2024-01" captures { year = "2024" };
+    no match "24-01";
+}
+"#;
+let results = run_embedded_tests(source).unwrap();
+
+assert!(results.iter().all(|result| result.passed));
+```
+*/
+#[cfg(feature = "regex")]
+pub fn run_embedded_tests(source: &str) -> crate::types::Result<Vec<TestResult>> {
+    let (_, tests) = crate::ast::to_ast_with_tests(source)?;
+    let regex = crate::compile_to_regex(source)?;
+
+    Ok(tests.into_iter().map(|test| run(&regex, test)).collect())
+}
+
+#[cfg(feature = "regex")]
+fn run(regex: &::regex::Regex, test: EmbeddedTest) -> TestResult {
+    let captures = regex.captures(&test.input);
+
+    let (passed, mismatches) = match (test.expectation, captures) {
+        (Expectation::NoMatch, captures) => (captures.is_none(), Vec::new()),
+        (Expectation::Match, None) => (false, Vec::new()),
+        (Expectation::Match, Some(captures)) => {
+            let mismatches: Vec<CaptureMismatch> = test
+                .captures
+                .iter()
+                .filter_map(|(name, expected)| {
+                    let actual = captures.name(name).map(|capture| capture.as_str());
+                    (actual != Some(expected.as_str())).then(|| CaptureMismatch {
+                        name: name.clone(),
+                        expected: expected.clone(),
+                        actual: actual.map(str::to_owned),
+                    })
+                })
+                .collect();
+
+            (mismatches.is_empty(), mismatches)
+        }
+    };
+
+    TestResult {
+        test,
+        passed,
+        mismatches,
+    }
+}
@@ -0,0 +1,230 @@
+//! A lenient tokenizer for syntax highlighting, see [`tokenize`]
+
+use crate::prelude::*;
+
+/// The words the grammar reserves, including group kinds, quantities and the names of scoped flags
+pub const KEYWORDS: &[&str] = &[
+    "ahead",
+    "and",
+    "any",
+    "as",
+    "atomic",
+    "behind",
+    "bytes",
+    "capture",
+    "captured",
+    "captures",
+    "char",
+    "chars",
+    "class",
+    "define",
+    "dot_all",
+    "either",
+    "else",
+    "flags",
+    "if",
+    "import",
+    "insensitive",
+    "lazy",
+    "let",
+    "many",
+    "match",
+    "more",
+    "multiline",
+    "no",
+    "not",
+    "nothing",
+    "of",
+    "option",
+    "or",
+    "over",
+    "possessive",
+    "raw_regex",
+    "recurse",
+    "same",
+    "some",
+    "symbol",
+    "tests",
+    "to",
+    "up",
+    "without",
+];
+
+/// What a [`Token`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TokenKind {
+    /// one of [`KEYWORDS`], e.g. `capture`
+    Keyword,
+    /// a quoted literal, e.g. `"a"` or `'a'`, which may be unterminated
+    String,
+    /// a raw regex, e.g. `` `[abc]` ``, which may be unterminated
+    Raw,
+    /// an amount or a code point, e.g. `5` or `0x41`
+    Number,
+    /// any other word, e.g. the name of a variable, capture group or flag
+    Identifier,
+    /// a symbol including its angle brackets, e.g. `<digit>`, which may be unterminated
+    Symbol,
+    /// one of `{`, `}`, `(`, `)`, `;`, `,`, `=` and `.`
+    Punctuation,
+    /// a line or block comment including its delimiters
+    Comment,
+    /// a character that starts no other token
+    Unknown,
+}
+
+/// A token of a source, from the byte offset `start` up to `end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/**
+Splits a source into tokens for syntax highlighting.
+
+Unlike the parser this never fails, so that sources being edited can be highlighted: unterminated strings end at
+the end of their line, unterminated symbols after their name and unterminated block comments at the end of the
+source, and characters that start no token are returned as [`TokenKind::Unknown`]. Offsets are in bytes and
+whitespace is not part of any token.
+
+# Example
+
+```rust
+use viable_compiler::tokens::{tokenize, Token, TokenKind};
+
+let tokens = tokenize(r#"some of "a"#);
+
+assert_eq!(
+    tokens,
+    vec![
+        Token { kind: TokenKind::Keyword, start: 0, end: 4 },
+        Token { kind: TokenKind::Keyword, start: 5, end: 7 },
+        Token { kind: TokenKind::String, start: 8, end: 10 },
+    ]
+);
+```
+*/
+#[must_use]
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+
+    while let Some(char) = source[offset..].chars().next() {
+        if char.is_whitespace() {
+            offset += char.len_utf8();
+            continue;
+        }
+
+        let rest = &source[offset..];
+        let (kind, length) = match char {
+            '/' if rest.starts_with("//") => (TokenKind::Comment, line_length(rest)),
+            '/' if rest.starts_with("/*") => (TokenKind::Comment, block_comment_length(rest)),
+            '"' | '\'' => (TokenKind::String, quoted_length(rest, char)),
+            '`' => (TokenKind::Raw, quoted_length(rest, '`')),
+            '<' => (TokenKind::Symbol, symbol_length(rest)),
+            '{' | '}' | '(' | ')' | ';' | ',' | '=' | '.' => (TokenKind::Punctuation, 1),
+            '0'..='9' => (TokenKind::Number, number_length(rest)),
+            _ if is_word_start(char) => {
+                let length = word_length(rest);
+                let kind = if KEYWORDS.contains(&&rest[..length]) {
+                    TokenKind::Keyword
+                } else {
+                    TokenKind::Identifier
+                };
+                (kind, length)
+            }
+            _ => (TokenKind::Unknown, char.len_utf8()),
+        };
+
+        tokens.push(Token {
+            kind,
+            start: offset,
+            end: offset + length,
+        });
+        offset += length;
+    }
+
+    tokens
+}
+
+const fn is_word_start(char: char) -> bool {
+    char.is_ascii_alphabetic() || char == '_'
+}
+
+/// Capture names may contain digits and hyphens
+const fn is_word_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || char == '_' || char == '-'
+}
+
+fn word_length(rest: &str) -> usize {
+    rest.find(|char| !is_word_char(char)).unwrap_or(rest.len())
+}
+
+fn number_length(rest: &str) -> usize {
+    if let Some(digits) = rest.strip_prefix("0x") {
+        return 2 + digits
+            .find(|char: char| !char.is_ascii_hexdigit())
+            .unwrap_or(digits.len());
+    }
+
+    rest.find(|char: char| !char.is_ascii_digit()).unwrap_or(rest.len())
+}
+
+fn line_length(rest: &str) -> usize {
+    rest.find('\n').unwrap_or(rest.len())
+}
+
+/// Block comments nest, an unterminated one runs to the end of the source
+fn block_comment_length(rest: &str) -> usize {
+    let mut depth = 0;
+    let mut offset = 0;
+
+    while offset < rest.len() {
+        if rest[offset..].starts_with("/*") {
+            depth += 1;
+            offset += 2;
+        } else if rest[offset..].starts_with("*/") {
+            depth -= 1;
+            offset += 2;
+            if depth == 0 {
+                return offset;
+            }
+        } else {
+            offset += rest[offset..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    rest.len()
+}
+
+/// A string or raw regex may span lines, an unterminated one ends at the end of its line
+fn quoted_length(rest: &str, quote: char) -> usize {
+    let mut chars = rest.char_indices().skip(1);
+
+    while let Some((index, char)) = chars.next() {
+        if char == '\\' {
+            chars.next();
+        } else if char == quote {
+            return index + char.len_utf8();
+        }
+    }
+
+    line_length(rest)
+}
+
+/// An unterminated symbol ends after the characters a symbol name may contain
+fn symbol_length(rest: &str) -> usize {
+    let name_length = rest[1..]
+        .find(|char: char| !(char.is_ascii_alphabetic() || char == '_' || char == ':'))
+        .unwrap_or(rest.len() - 1);
+
+    if rest[1 + name_length..].starts_with('>') {
+        name_length + 2
+    } else {
+        name_length + 1
+    }
+}
@@ -1,3 +1,13 @@
 use crate::errors::CompilerError;
 
+#[cfg(feature = "std")]
+pub use std::collections::{HashMap, HashSet};
+
+#[cfg(not(feature = "std"))]
+pub use hashbrown::{HashMap, HashSet};
+
+/// The hasher of the maps returned by the public API, the same with and without `std` so that the features are
+/// additive
+pub use hashbrown::hash_map::DefaultHashBuilder as RandomState;
+
 pub type Result<T> = core::result::Result<T, CompilerError>;
@@ -0,0 +1,70 @@
+use crate::ast::types::ast::Span;
+use crate::prelude::*;
+use thiserror::Error;
+
+/// A likely mistake that does not prevent compilation
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// emitted if a range starts and ends with the same character (e.g. `a to a`)
+    #[error("range only matches `{char}`, use a literal instead")]
+    SingleCharacterRange { char: char, span: Span },
+    /// emitted if an exact amount quantifier is lazy (e.g. `lazy 4 of`), which matches the same as a greedy one
+    #[error("lazy has no effect on an exact amount quantifier")]
+    LazyExactQuantifier { span: Span },
+    /// emitted if a quantifier repeats its expression exactly once (e.g. `1 of` or `1 to 1 of`), which matches
+    /// the same as the expression alone
+    #[error("quantifier repeats its expression exactly once, remove it")]
+    RedundantQuantifier { span: Span },
+    /// emitted if a quantifier is written with `over` (e.g. `over 2 of`), which repeats its expression more than,
+    /// rather than at least, the amount
+    #[error("`over` is deprecated, use `{replacement}` instead")]
+    DeprecatedOver { replacement: String, span: Span },
+    /// emitted if an open-ended quantifier has a keyword of its own (e.g. `1 or more of` is `some of`)
+    #[error("quantifier is the same as `{keyword}`, use it instead")]
+    QuantifierKeyword { keyword: &'static str, span: Span },
+    /// emitted if a capture group matches nothing (e.g. it only invokes an empty variable), which always
+    /// captures an empty string
+    #[error("capture group is empty")]
+    EmptyCapture { span: Span },
+    /// emitted if a variable is declared but never invoked
+    #[error("variable `{name}` is never used")]
+    UnusedVariable { name: String, span: Span },
+    /// emitted if a variable is declared with the name of an earlier variable, which it replaces from then on
+    #[error("variable `{name}` shadows an earlier declaration")]
+    ShadowedVariable {
+        name: String,
+        first_span: Span,
+        second_span: Span,
+    },
+    /// emitted if a capture group is within a negative assertion (e.g. `not ahead { capture { ... } }`), which
+    /// only matches when its statements don't, so the group never holds a value
+    #[error("capture group within a negative assertion never captures anything")]
+    CaptureInNegativeAssertion { assertion_span: Span, capture_span: Span },
+    /// emitted instead of [`crate::errors::CompilerError::DuplicateCaptureName`] when compiling at
+    /// [`crate::options::Strictness::Lenient`], most targets reject the pattern
+    #[error("capture group name `{name}` is used more than once")]
+    DuplicateCaptureName {
+        name: String,
+        first_span: Span,
+        second_span: Span,
+    },
+}
+
+impl Warning {
+    /// The span of the construct the warning refers to
+    #[must_use]
+    pub const fn span(&self) -> Span {
+        match self {
+            Self::SingleCharacterRange { span, .. }
+            | Self::LazyExactQuantifier { span }
+            | Self::RedundantQuantifier { span }
+            | Self::DeprecatedOver { span, .. }
+            | Self::QuantifierKeyword { span, .. }
+            | Self::EmptyCapture { span }
+            | Self::UnusedVariable { span, .. }
+            | Self::ShadowedVariable { second_span: span, .. }
+            | Self::DuplicateCaptureName { second_span: span, .. }
+            | Self::CaptureInNegativeAssertion { capture_span: span, .. } => *span,
+        }
+    }
+}
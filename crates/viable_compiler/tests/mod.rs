@@ -1,6 +1,46 @@
 #![cfg(test)]
 use indoc::indoc;
+use std::collections::{BTreeMap, HashSet};
+use std::rc::Rc;
+use viable_compiler::ast::from_regex::{regex_to_viable, Dialect, ImportError};
+use viable_compiler::ast::outline::{outline, OutlineItem, OutlineKind};
+use viable_compiler::ast::redos::{RedosWarning, Severity};
+use viable_compiler::ast::references::variable_references;
+use viable_compiler::ast::transform::fold_node;
+use viable_compiler::ast::types::ast::{
+    Backreference, Conditional, Flag, QuantifierKind, Span, Spanned, ViableAst, ViableAstNode,
+};
+use viable_compiler::ast::{
+    analyze_redos, ast_to_source, ast_to_source_with_flags, explain, explain_at, fold, literal_prefix, to_ast,
+    to_ast_all_errors, to_ast_json, to_ast_lenient, to_ast_with_custom_symbols, to_ast_with_diagnostics,
+    to_ast_with_flags, to_ast_with_imports, to_ast_with_nesting_limit, to_ast_with_symbols, to_ast_with_tests,
+    to_ast_with_variables, walk, AstMetrics, IncrementalParser, PrefixInfo, Transform, Visitor, DEFAULT_NESTING_LIMIT,
+};
 use viable_compiler::compiler;
+use viable_compiler::errors::CompilerError;
+#[cfg(feature = "regex")]
+use viable_compiler::matching::{match_captures, CompiledPattern};
+use viable_compiler::metadata::{locate, NodeKind, SourceMap};
+use viable_compiler::options::{CompilerOptions, EmitStyle, FormatOptions, OptimizationLevel, Strictness};
+use viable_compiler::target::{check_target_compatibility, Target};
+#[cfg(feature = "regex")]
+use viable_compiler::testing::run_embedded_tests;
+use viable_compiler::testing::{EmbeddedTest, Expectation};
+use viable_compiler::tokens::{tokenize, TokenKind};
+use viable_compiler::warnings::Warning;
+use viable_compiler::{
+    compile_ast, compile_for, compile_with_flags, compile_with_imports, compile_with_imports_and_metadata,
+    compile_with_metadata, compile_with_options, Compiler,
+};
+#[cfg(feature = "regex")]
+use viable_compiler::{compile_to_regex, compile_to_regex_builder};
+
+fn root_nodes(ast: &ViableAst) -> &[Spanned<ViableAstNode>] {
+    match ast {
+        ViableAst::Root(nodes) => nodes,
+        ViableAst::Empty => &[],
+    }
+}
 
 #[test]
 fn quantifier_test() {
@@ -135,6 +175,31 @@ fn symbol_test() {
     );
 }
 
+#[test]
+fn symbol_alias_test() {
+    let output = compiler(indoc! {
+        r"
+        <carriage_return>;
+        not <carriage_return>;
+        <vertical_tab>;
+        not <vertical_tab>;
+        <form_feed>;
+        not <form_feed>;
+        <line_break>;
+        "
+    });
+    assert_eq!(output.unwrap(), r"\r[^\r]\v[^\v]\f[^\f](?:\r\n|\r|\n)");
+
+    assert!(matches!(
+        to_ast("not <line_break>;"),
+        Err(CompilerError::NegativeLineBreakNotAllowed)
+    ));
+    assert!(matches!(
+        to_ast("class { <line_break>; }"),
+        Err(CompilerError::UnexpectedSymbolInClass)
+    ));
+}
+
 #[test]
 fn symbol_unicode_category_test() {
     let output = compiler(indoc! {
@@ -262,7 +327,7 @@ fn comment_test() {
         }
         "#,
     });
-    assert_eq!(output.unwrap(), "[0-5](?:x)");
+    assert_eq!(output.unwrap(), "[0-5]x");
 }
 
 #[test]
@@ -404,6 +469,44 @@ fn negative_char_class_test() {
     assert_eq!(output.unwrap(), "[^abcd][^abcd]{5}");
 }
 
+#[test]
+fn class_shorthand_test() {
+    let output = compiler(indoc! {
+        r"
+        class abc;
+        5 of class a-f0-9;
+        class ]^-;
+        ",
+    });
+    assert_eq!(output.unwrap(), r"[abc][a-f0-9]{5}[\]\^\-]");
+
+    let (ast, _) = to_ast_with_symbols("class abc;").unwrap();
+    assert!(matches!(
+        &root_nodes(&ast)[0].node,
+        ViableAstNode::CharClass(class) if !class.negative && class.items.len() == 3
+    ));
+}
+
+#[test]
+fn negative_char_class_escape_test() {
+    let cases = [
+        ("not abc];", r"[^abc\]]"),
+        ("not ^a;", r"[^\^a]"),
+        ("not -a;", r"[^\-a]"),
+        ("not a-z_;", r"[^a-z_]"),
+        ("not a-z-;", r"[^a-z\-]"),
+        (r"not \n;", r"[^\\n]"),
+    ];
+
+    for (source, expected) in cases {
+        assert_eq!(compiler(source).unwrap(), expected, "{source:?}");
+    }
+
+    // members can't be separated by whitespace
+    assert!(matches!(compiler("not a b;"), Err(CompilerError::ParseError { .. })));
+    assert!(matches!(compiler("not z-a;"), Err(CompilerError::EmptyRange { .. })));
+}
+
 #[test]
 fn single_quote_test() {
     let output = compiler(indoc! {
@@ -476,3 +579,6626 @@ fn literal_hyphen() {
     });
     assert_eq!(output.unwrap(), r"-");
 }
+
+#[test]
+fn span_test() {
+    let source = indoc! {
+        r#"
+        let .variable = {
+          "A";
+        }
+        5 of "B";
+        capture {
+          .variable;
+        }
+        "#
+    };
+
+    let ast = to_ast(source).unwrap();
+    let nodes = root_nodes(&ast);
+
+    let spanned_text = |span: Span| &source[span.start..span.end];
+
+    // the declaration isn't part of the AST
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(spanned_text(nodes[0].span), "5 of \"B\";");
+    assert_eq!((nodes[0].span.line, nodes[0].span.column), (4, 1));
+    assert_eq!(spanned_text(nodes[1].span), "capture {\n  .variable;\n}");
+
+    let ViableAstNode::Quantifier(quantifier) = &nodes[0].node else {
+        unreachable!("expected a quantifier");
+    };
+    assert_eq!(spanned_text(quantifier.expression.span), "\"B\"");
+
+    let ViableAstNode::Group(group) = &nodes[1].node else {
+        unreachable!("expected a group");
+    };
+    let group_nodes = root_nodes(&group.statements);
+    assert_eq!(spanned_text(group_nodes[0].span), ".variable");
+    assert_eq!((group_nodes[0].span.line, group_nodes[0].span.column), (6, 3));
+
+    let ViableAstNode::VariableInvocation(invocation) = &group_nodes[0].node else {
+        unreachable!("expected a variable invocation");
+    };
+    let variable_nodes = root_nodes(&invocation.statements);
+    // expanded nodes keep pointing at the declaration site
+    assert_eq!(spanned_text(variable_nodes[0].span), "\"A\"");
+    assert_eq!((variable_nodes[0].span.line, variable_nodes[0].span.column), (2, 3));
+}
+
+#[test]
+fn all_errors_test() {
+    let source = indoc! {
+        r#"
+        .first;
+        "a";
+        capture {
+          .second;
+        }
+        not ahead {
+          .third;
+        }
+        "#
+    };
+
+    let errors = to_ast_all_errors(source).unwrap_err();
+
+    assert_eq!(errors.len(), 3);
+    assert!(matches!(errors[0].node, CompilerError::UninitializedVariable { .. }));
+    assert_eq!(errors[0].span.line, 1);
+    assert!(matches!(errors[1].node, CompilerError::UninitializedVariable { .. }));
+    assert_eq!(errors[1].span.line, 4);
+    assert!(matches!(errors[2].node, CompilerError::UninitializedVariable { .. }));
+    assert_eq!(errors[2].span.line, 7);
+
+    // the single error API keeps reporting the first error
+    assert!(matches!(
+        to_ast(source),
+        Err(CompilerError::UninitializedVariable { .. })
+    ));
+}
+
+fn recursive_cycle(source: &str) -> Vec<String> {
+    let Err(CompilerError::RecursiveVariable { cycle, .. }) = to_ast(source) else {
+        unreachable!("expected a recursive variable error");
+    };
+    cycle
+}
+
+#[test]
+fn recursive_variable_self_reference_test() {
+    let source = indoc! {
+        r#"
+        let .a = {
+          "x";
+          .a;
+        }
+        "#
+    };
+
+    assert_eq!(recursive_cycle(source), ["a", "a"]);
+    assert_eq!(
+        to_ast(source).unwrap_err().to_string(),
+        "recursive variable `a` (a -> a) [E0026]"
+    );
+}
+
+#[test]
+fn recursive_variable_two_variable_cycle_test() {
+    let source = indoc! {
+        r"
+        let .a = {
+          .b;
+        }
+        let .b = {
+          .a;
+        }
+        "
+    };
+
+    assert_eq!(recursive_cycle(source), ["a", "b", "a"]);
+}
+
+#[test]
+fn recursive_variable_nested_declaration_test() {
+    let source = indoc! {
+        r"
+        let .a = {
+          let .b = {
+            .a;
+          }
+          .b;
+        }
+        "
+    };
+
+    assert_eq!(recursive_cycle(source), ["a", "b", "a"]);
+}
+
+#[test]
+fn recursive_variable_long_chain_test() {
+    let source = indoc! {
+        r"
+        let .a = {
+          .b;
+        }
+        let .b = {
+          .c;
+        }
+        let .c = {
+          .d;
+        }
+        let .d = {
+          .a;
+        }
+        "
+    };
+
+    assert_eq!(recursive_cycle(source), ["a", "b", "c", "d", "a"]);
+}
+
+#[test]
+fn variable_diamond_test() {
+    let source = indoc! {
+        r#"
+        let .d = {
+          "d";
+        }
+        let .b = {
+          .d;
+        }
+        let .c = {
+          .d;
+        }
+        let .a = {
+          .b;
+          .c;
+        }
+        .a;
+        "#
+    };
+
+    assert_eq!(compiler(source).unwrap(), "dd");
+}
+
+#[test]
+fn variable_diamond_out_of_order_test() {
+    // invoking a variable before it is declared isn't a cycle when the declarations don't lead back
+    let source = indoc! {
+        r#"
+        let .a = {
+          .b;
+          .c;
+        }
+        let .b = {
+          .d;
+        }
+        let .c = {
+          .d;
+        }
+        let .d = {
+          "d";
+        }
+        "#
+    };
+
+    assert!(matches!(
+        to_ast(source),
+        Err(CompilerError::UninitializedVariable { .. })
+    ));
+}
+
+#[test]
+fn all_errors_success_test() {
+    let ast = to_ast_all_errors(r#"5 of "A";"#).unwrap();
+    assert_eq!(root_nodes(&ast).len(), 1);
+}
+
+#[test]
+fn parse_error_position_test() {
+    let cases = [
+        ("5 of ;", 1, 6, 5, ";"),
+        ("capture {\n  \"a\";\n", 3, 1, 17, ""),
+        ("<digit>;\n  x;", 2, 3, 11, "x;"),
+    ];
+
+    for (source, expected_line, expected_column, expected_offset, expected_found) in cases {
+        let Err(CompilerError::ParseError {
+            expected,
+            found,
+            line,
+            column,
+            offset,
+            ..
+        }) = to_ast(source)
+        else {
+            unreachable!("expected a parse error for {source:?}");
+        };
+
+        assert_eq!(
+            (line, column, offset),
+            (expected_line, expected_column, expected_offset)
+        );
+        assert_eq!(found, expected_found);
+        assert!(expected.contains(&String::from("literal")));
+    }
+}
+
+#[test]
+fn parse_error_display_test() {
+    let error = compiler(r#"let .a = "a";"#).unwrap_err();
+    assert_eq!(error.to_string(), r#"expected block at 1:10, found `"a";` [E0025]"#);
+    let error = compiler(r#"up to x of "a";"#).unwrap_err();
+    assert_eq!(error.to_string(), "expected amount at 1:7, found `x` [E0025]");
+}
+
+#[test]
+fn line_comment_only_test() {
+    let ast = to_ast("// nothing to see here").unwrap();
+    assert!(root_nodes(&ast).is_empty());
+    assert_eq!(compiler("// nothing to see here\n").unwrap(), "");
+}
+
+#[test]
+fn line_comment_test() {
+    let output = compiler(indoc! {
+        r#"
+        // top level
+        "a"; // trailing
+        let .variable = {
+          // inside a variable
+          "b";
+        }
+        capture {
+          // inside a capture
+          .variable;
+        }
+        either {
+          "c"; // inside an either
+          "d";
+        }
+        ahead {
+          // inside an assertion
+          "e";
+        }
+        5 of // between the quantity and the expression
+          "f";
+        lazy some of
+          // on its own line
+          "g";
+        "//"; `//`;
+        "#,
+    });
+    assert_eq!(output.unwrap(), r"a(b)[cd](?=e)f{5}g+?\/\/\/\/");
+}
+
+#[test]
+fn line_comment_span_test() {
+    let source = "// comment\n\"a\";";
+    let ast = to_ast(source).unwrap();
+    let span = root_nodes(&ast)[0].span;
+    assert_eq!(&source[span.start..span.end], "\"a\"");
+}
+
+#[test]
+fn block_comment_test() {
+    let output = compiler(indoc! {
+        r#"
+        "a";
+        /*
+        capture {
+          /* a nested comment */
+          "b";
+        }
+        */
+        "c"; /* trailing */
+        5 of /* between the quantity and the expression */ "d";
+        "#,
+    });
+    assert_eq!(output.unwrap(), "acd{5}");
+}
+
+#[test]
+fn block_comment_ast_test() {
+    let commented_source = indoc! {
+        r#"
+        "a";
+        /* capture { /* "b"; */ "c"; } */
+        "d";
+        "#,
+    };
+    let plain_source = indoc! {
+        r#"
+        "a";
+        "d";
+        "#,
+    };
+    let commented = to_ast(commented_source).unwrap();
+    let plain = to_ast(plain_source).unwrap();
+    assert_eq!(root_nodes(&commented).len(), root_nodes(&plain).len());
+    assert_eq!(compiler(commented_source).unwrap(), compiler(plain_source).unwrap());
+}
+
+#[test]
+fn unterminated_block_comment_test() {
+    assert!(matches!(
+        to_ast("/* /* */ \"a\";"),
+        Err(CompilerError::ParseError { .. })
+    ));
+}
+
+#[test]
+fn up_to_test() {
+    let output = compiler(indoc! {
+        r#"
+        up to 5 of "a";
+        lazy up to 5 of "a";
+        up to 1 of "bc";
+        up to 1000000 of <digit>;
+        "#,
+    });
+    assert_eq!(output.unwrap(), r"a{0,5}a{0,5}?(?:bc){0,1}\d{0,1000000}");
+}
+
+#[test]
+fn up_to_zero_test() {
+    assert!(matches!(compiler(r#"up to 0 of "a";"#), Err(CompilerError::UpToZero)));
+    assert!(matches!(
+        compiler(r#"up to 99999999999999999999999 of "a";"#),
+        Err(CompilerError::CouldNotParseAnAmount(amount)) if amount == "99999999999999999999999"
+    ));
+}
+
+#[test]
+fn quantifier_amounts_test() {
+    let ast = to_ast(r#"3 of "a"; 2 to 5 of "b"; 08 to 012 of "c";"#).unwrap();
+    let amounts: Vec<QuantifierKind> = root_nodes(&ast)
+        .iter()
+        .filter_map(|node| match &node.node {
+            ViableAstNode::Quantifier(quantifier) => Some(quantifier.kind.clone()),
+            _ => None,
+        })
+        .collect();
+
+    assert!(matches!(
+        amounts[..],
+        [
+            QuantifierKind::Amount(3),
+            QuantifierKind::Range { start: 2, end: 5 },
+            QuantifierKind::Range { start: 8, end: 12 }
+        ]
+    ));
+
+    assert!(matches!(
+        to_ast(r#"99999999999999999999999 of "a";"#),
+        Err(CompilerError::CouldNotParseAnAmount(amount)) if amount == "99999999999999999999999"
+    ));
+    assert!(matches!(
+        to_ast(r#"over 99999999999999999999999 of "a";"#),
+        Err(CompilerError::CouldNotParseAnAmount(amount)) if amount == "99999999999999999999999"
+    ));
+    assert!(matches!(
+        to_ast("same as capture 99999999999999999999999;"),
+        Err(CompilerError::CouldNotParseAnAmount(amount)) if amount == "99999999999999999999999"
+    ));
+
+    let error = to_ast(r#"1 to 99999999999999999999999 of "a";"#).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid quantifier range `1 to 99999999999999999999999` [E0016]"
+    );
+    assert_eq!(
+        to_ast(r#"5 to 3 of "a";"#).unwrap_err().to_string(),
+        "invalid quantifier range `5 to 3` [E0016]"
+    );
+}
+
+#[test]
+fn possessive_test() {
+    let output = compile_for(
+        indoc! {
+            r#"
+            possessive any of "A";
+            possessive some of "A";
+            possessive option of "A";
+            possessive 5 of "A";
+            possessive over 5 of "A";
+            possessive up to 5 of "A";
+            possessive 5 to 6 of "A";
+            possessive some of "AB";
+            "#,
+        },
+        Target::Pcre2,
+    );
+    assert_eq!(output.unwrap(), r"A*+A++A?+A{5}+A{6,}+A{0,5}+A{5,6}+(?:AB)++");
+    assert!(matches!(
+        compile_for(r#"lazy lazy some of "a";"#, Target::Pcre2),
+        Err(CompilerError::RepeatedQuantifierModifier(modifier)) if modifier == "lazy"
+    ));
+    assert!(matches!(
+        compile_for(r#"possessive possessive 2 to 3 of "a";"#, Target::Pcre2),
+        Err(CompilerError::RepeatedQuantifierModifier(modifier)) if modifier == "possessive"
+    ));
+}
+
+#[test]
+fn possessive_unsupported_test() {
+    let source = indoc! {
+        r#"
+        "a";
+        possessive some of "b";
+        "#
+    };
+    let Err(CompilerError::UnsupportedByTarget { target, span, .. }) = compile_for(source, Target::EcmaScript) else {
+        unreachable!("expected an unsupported by target error");
+    };
+    assert_eq!(target, Target::EcmaScript);
+    assert_eq!(&source[span.start..span.end], "possessive some of \"b\";");
+}
+
+#[test]
+fn lazy_possessive_test() {
+    assert!(matches!(
+        compile_for(r#"lazy possessive some of "a";"#, Target::Pcre2),
+        Err(CompilerError::LazyPossessiveQuantifier)
+    ));
+    assert!(matches!(
+        compile_for(r#"possessive lazy some of "a";"#, Target::Pcre2),
+        Err(CompilerError::LazyPossessiveQuantifier)
+    ));
+}
+
+#[test]
+fn atomic_group_test() {
+    let output = compile_for(
+        indoc! {
+            r#"
+            atomic {
+              either {
+                "ab";
+                "a";
+              }
+            }
+            3 of atomic {
+              "c";
+            }
+            "#,
+        },
+        Target::Pcre2,
+    );
+    assert_eq!(output.unwrap(), "(?>(?:ab|a))(?>c){3}");
+}
+
+#[test]
+fn atomic_group_unsupported_test() {
+    assert!(matches!(
+        compile_for(r#"some of atomic { "a"; }"#, Target::EcmaScript),
+        Err(CompilerError::UnsupportedByTarget { .. })
+    ));
+}
+
+#[test]
+fn non_capture_group_identifier_test() {
+    assert!(matches!(
+        compiler(r#"atomic name { "a"; }"#),
+        Err(CompilerError::UnexpectedIdentifierForNonCaptureGroup)
+    ));
+    assert!(matches!(
+        compiler(r#"match name { "a"; }"#),
+        Err(CompilerError::UnexpectedIdentifierForNonCaptureGroup)
+    ));
+}
+
+#[test]
+fn backreference_test() {
+    let output = compiler(indoc! {
+        r#"
+        capture quote {
+          either {
+            "'";
+            '"';
+          }
+        }
+        some of <word>;
+        same as quote;
+        "#,
+    });
+    assert_eq!(output.unwrap(), r#"(?<quote>['"])\w+\k<quote>"#);
+}
+
+#[test]
+fn quantified_backreference_test() {
+    let output = compiler(indoc! {
+        r"
+        match {
+          capture word {
+            some of <word>;
+          }
+        }
+        <space>;
+        2 of same as word;
+        ",
+    });
+    assert_eq!(output.unwrap(), r"(?<word>\w+) \k<word>{2}");
+}
+
+#[test]
+fn variable_backreference_test() {
+    let output = compiler(indoc! {
+        r"
+        let .digits = {
+          capture digits {
+            some of <digit>;
+          }
+        }
+        .digits;
+        same as digits;
+        ",
+    });
+    assert_eq!(output.unwrap(), r"(?<digits>\d+)\k<digits>");
+}
+
+#[test]
+fn unknown_backreference_test() {
+    let sources = [
+        "same as name;",
+        r#"same as name; capture name { "a"; }"#,
+        r"capture name { same as name; }",
+        r#"let .unused = { capture name { "a"; } } same as name;"#,
+    ];
+    for source in sources {
+        let Err(CompilerError::UnknownBackreference(name)) = compiler(source) else {
+            unreachable!("expected an unknown backreference error for {source:?}");
+        };
+        assert_eq!(name, "name");
+    }
+}
+
+#[test]
+fn numbered_backreference_test() {
+    let output = compiler(indoc! {
+      r#"
+      capture { "a"; }
+      capture name { "b"; }
+      same as capture 1;
+      same as capture 2;
+      "#
+    });
+
+    assert_eq!(output.unwrap(), r"(a)(?<name>b)\1\2");
+}
+
+#[test]
+fn numbered_backreference_digit_test() {
+    let output = compiler(indoc! {
+      r#"
+      capture { "a"; }
+      same as capture 1;
+      "2";
+      "#
+    });
+
+    assert_eq!(output.unwrap(), r"(a)\1(?:)2");
+}
+
+#[test]
+fn numbered_backreference_variable_test() {
+    let output = compiler(indoc! {
+      r#"
+      let .letter = {
+        capture { "a"; }
+      }
+      .letter;
+      .letter;
+      same as capture 2;
+      "#
+    });
+
+    assert_eq!(output.unwrap(), r"(a)(a)\2");
+}
+
+#[test]
+fn invalid_backreference_index_test() {
+    let sources = [
+        (r#"capture { "a"; } same as capture 2;"#, 2, 1),
+        (
+            r#"let .letter = { capture { "a"; } } .letter; .letter; same as capture 3;"#,
+            3,
+            2,
+        ),
+        (r#"match { "a"; } same as capture 1;"#, 1, 0),
+        (r#"capture { "a"; } same as capture 0;"#, 0, 1),
+    ];
+    for (source, expected_index, expected_group_count) in sources {
+        let Err(CompilerError::InvalidBackreferenceIndex { index, group_count }) = compiler(source) else {
+            unreachable!("expected an invalid backreference index error for {source:?}");
+        };
+        assert_eq!((index, group_count), (expected_index, expected_group_count));
+    }
+}
+
+#[test]
+fn flags_test() {
+    let source = indoc! {
+      r#"
+      flags {
+        insensitive;
+        multiline;
+        dot_all;
+      }
+      "a";
+      "#
+    };
+
+    let (ast, flags) = to_ast_with_flags(source).unwrap();
+
+    assert_eq!(flags.flags, [Flag::Insensitive, Flag::Multiline, Flag::DotAll]);
+    assert_eq!((flags.span.line, flags.span.column), (1, 1));
+    assert_eq!(root_nodes(&ast).len(), 1);
+
+    assert_eq!(compile_for(source, Target::Pcre2).unwrap(), "(?ims)a");
+    assert_eq!(
+        compile_with_flags(source, Target::EcmaScript).unwrap(),
+        ("a".to_owned(), "ims".to_owned())
+    );
+    assert_eq!(
+        compile_with_flags(source, Target::Pcre2).unwrap(),
+        ("(?ims)a".to_owned(), String::new())
+    );
+}
+
+#[test]
+fn unicode_flag_test() {
+    let output = compile_for(r#"flags { unicode; insensitive; } "a";"#, Target::Pcre2);
+
+    assert_eq!(output.unwrap(), "(*UTF)(?i)a");
+
+    let output = compile_for(r#"flags { unicode; } "a";"#, Target::Pcre2);
+
+    assert_eq!(output.unwrap(), "(*UTF)a");
+}
+
+#[test]
+fn no_flags_test() {
+    let source = r#"flags { } "a";"#;
+
+    assert_eq!(compiler(source).unwrap(), "a");
+    assert_eq!(
+        compile_with_flags(r#""a";"#, Target::EcmaScript).unwrap(),
+        ("a".to_owned(), String::new())
+    );
+}
+
+#[test]
+fn inline_flags_unsupported_test() {
+    let output = compiler(r#"flags { insensitive; } "a";"#);
+
+    let Err(CompilerError::UnsupportedByTarget { construct, target, .. }) = output else {
+        unreachable!("expected an unsupported by target error");
+    };
+
+    assert_eq!((construct, target), ("inline flags", Target::EcmaScript));
+}
+
+#[test]
+fn flag_errors_test() {
+    let Err(errors) = to_ast_all_errors(r#"flags { insensitive; caseless; insensitive; } "a";"#) else {
+        unreachable!("expected flag errors");
+    };
+
+    assert_eq!(errors.len(), 2);
+
+    let CompilerError::UnknownFlag(name) = &errors[0].node else {
+        unreachable!("expected an unknown flag error");
+    };
+    assert_eq!((name.as_str(), errors[0].span.column), ("caseless", 22));
+
+    let CompilerError::DuplicateFlag(name) = &errors[1].node else {
+        unreachable!("expected a duplicate flag error");
+    };
+    assert_eq!((name.as_str(), errors[1].span.column), ("insensitive", 32));
+}
+
+#[test]
+fn misplaced_flags_block_test() {
+    let sources = [
+        indoc! {
+          r#"
+          "a";
+          flags { insensitive; }
+          "#
+        },
+        indoc! {
+          r#"
+          capture {
+            flags { insensitive; }
+            "a";
+          }
+          "#
+        },
+    ];
+
+    let Err(errors) = to_ast_all_errors(sources[0]) else {
+        unreachable!("expected a misplaced flags block error");
+    };
+
+    assert!(matches!(errors[0].node, CompilerError::MisplacedFlagsBlock));
+    assert_eq!((errors[0].span.line, errors[0].span.column), (2, 1));
+
+    let Err(errors) = to_ast_all_errors(sources[1]) else {
+        unreachable!("expected a misplaced flags block error");
+    };
+
+    assert!(matches!(errors[0].node, CompilerError::MisplacedFlagsBlock));
+    assert_eq!((errors[0].span.line, errors[0].span.column), (2, 3));
+}
+
+#[test]
+fn flag_group_test() {
+    let output = compiler(indoc! {
+      r#"
+      insensitive {
+        "a";
+      }
+      insensitive not multiline dot_all {
+        "b";
+      }
+      not dot_all {
+        "c";
+      }
+      "#
+    });
+
+    assert_eq!(output.unwrap(), "(?i:a)(?is-m:b)(?-s:c)");
+}
+
+#[test]
+fn nested_flag_group_test() {
+    let output = compiler(indoc! {
+      r#"
+      insensitive {
+        "a";
+        capture {
+          not insensitive {
+            "b";
+          }
+        }
+        either {
+          "c";
+          multiline {
+            "d";
+          }
+        }
+      }
+      "#
+    });
+
+    assert_eq!(output.unwrap(), "(?i:a((?-i:b))(?:c|(?m:d)))");
+}
+
+#[test]
+fn quantified_flag_group_test() {
+    let output = compiler(indoc! {
+      r#"
+      3 of insensitive {
+        "abc";
+      }
+      "#
+    });
+
+    assert_eq!(output.unwrap(), "(?i:abc){3}");
+}
+
+#[test]
+fn duplicate_scoped_flag_test() {
+    let sources = [
+        r#"insensitive insensitive { "a"; }"#,
+        r#"insensitive not insensitive { "a"; }"#,
+    ];
+    for source in sources {
+        let Err(CompilerError::DuplicateFlag(name)) = compiler(source) else {
+            unreachable!("expected a duplicate flag error for {source:?}");
+        };
+        assert_eq!(name, "insensitive");
+    }
+}
+
+#[test]
+fn unicode_property_test() {
+    let source = indoc! {
+      r"
+      <script::greek>;
+      not <script::old_italic>;
+      <property::alphabetic>;
+      not <property::ascii_hex_digit>;
+      some of <property::emoji>;
+      "
+    };
+
+    assert_eq!(
+        compiler(source).unwrap(),
+        r"\p{Script=Greek}\P{Script=Old_Italic}\p{Alphabetic}\P{ASCII_Hex_Digit}\p{Emoji}+"
+    );
+    assert_eq!(
+        compile_for(source, Target::Pcre2).unwrap(),
+        r"\p{Greek}\P{Old_Italic}\p{Alphabetic}\P{ASCII_Hex_Digit}\p{Emoji}+"
+    );
+}
+
+#[test]
+fn unknown_unicode_property_test() {
+    let Err(error) = compiler("<script::greeek>;") else {
+        unreachable!("expected an unknown unicode property error");
+    };
+
+    assert_eq!(
+        error.to_string(),
+        "unknown unicode property `greeek`, did you mean `greek`? [E0042]"
+    );
+
+    let Err(error) = compiler("<property::alphabetik>;") else {
+        unreachable!("expected an unknown unicode property error");
+    };
+
+    assert_eq!(
+        error.to_string(),
+        "unknown unicode property `alphabetik`, did you mean `alphabetic`? [E0042]"
+    );
+
+    let Err(CompilerError::UnknownUnicodeProperty { name, suggestion }) = compiler("<script::klingon>;") else {
+        unreachable!("expected an unknown unicode property error");
+    };
+
+    assert_eq!((name.as_str(), suggestion), ("klingon", None));
+}
+
+#[test]
+fn did_you_mean_test() {
+    let symbol_suggestion = |source| match to_ast(source) {
+        Err(CompilerError::UnrecognizedSymbol { suggestion, .. }) => suggestion,
+        _ => unreachable!("expected an unrecognized symbol error for {source:?}"),
+    };
+    assert_eq!(symbol_suggestion("<digt>;").as_deref(), Some("digit"));
+    assert_eq!(symbol_suggestion("<wrod>;").as_deref(), Some("word"));
+    assert_eq!(symbol_suggestion("class { <spcae>; }").as_deref(), Some("space"));
+    assert_eq!(symbol_suggestion("<xyzzy>;"), None);
+    assert_eq!(
+        to_ast("<whitspace>;").unwrap_err().to_string(),
+        "usage of an unrecognized symbol `<whitspace>`, did you mean `<whitespace>`? [E0046]"
+    );
+
+    // custom symbols are suggested as well
+    let options = CompilerOptions {
+        custom_symbols: BTreeMap::from([(String::from("uuid_char"), to_ast("0 to 9;").unwrap())]),
+        ..CompilerOptions::default()
+    };
+    let Err(CompilerError::UnrecognizedSymbol { suggestion, .. }) =
+        compile_with_options("<uuid_chr>;", Target::default(), &options)
+    else {
+        unreachable!("expected an unrecognized symbol error");
+    };
+    assert_eq!(suggestion.as_deref(), Some("uuid_char"));
+
+    let variable_suggestion = |source| match to_ast(source) {
+        Err(CompilerError::UninitializedVariable { suggestion, .. }) => suggestion,
+        _ => unreachable!("expected an uninitialized variable error for {source:?}"),
+    };
+    assert_eq!(
+        variable_suggestion(r#"let .userID = { "a"; } .userIDd;"#).as_deref(),
+        Some("userID")
+    );
+    assert_eq!(
+        variable_suggestion(r#"let .userID = { "a"; } .usreID;"#).as_deref(),
+        Some("userID")
+    );
+    // only variables declared before the invocation are in scope
+    assert_eq!(variable_suggestion(r#".userid; let .userID = { "a"; }"#), None);
+    assert_eq!(variable_suggestion(r#"let .foo = { "a"; } .bar;"#), None);
+    assert_eq!(
+        to_ast(r#"let .digits = { "1"; } .digts;"#).unwrap_err().to_string(),
+        "usage of an uninitialized variable `.digts`, did you mean `.digits`? [E0038]"
+    );
+
+    let keyword_suggestion = |source| match to_ast(source) {
+        Err(CompilerError::ParseError { suggestion, .. }) => suggestion,
+        _ => unreachable!("expected a parse error for {source:?}"),
+    };
+    assert_eq!(keyword_suggestion(r#"captur { "a"; }"#).as_deref(), Some("capture"));
+    assert_eq!(
+        keyword_suggestion(r#"captrue name { "a"; }"#).as_deref(),
+        Some("capture")
+    );
+    assert_eq!(keyword_suggestion(r#"eihter { "a"; "b"; }"#).as_deref(), Some("either"));
+    assert_eq!(keyword_suggestion(r#"not ahaed { "a"; }"#).as_deref(), Some("ahead"));
+    assert_eq!(keyword_suggestion(r#"behnid { "a"; }"#).as_deref(), Some("behind"));
+    assert_eq!(keyword_suggestion(r#"banana { "a"; }"#), None);
+    // only words followed by a block are taken for a misspelled group
+    assert_eq!(keyword_suggestion(r#"match { "a"; } matc;"#), None);
+    assert_eq!(
+        to_ast(r#"mach { "a"; }"#).unwrap_err().to_string(),
+        "expected root at 1:1, found `mach`, did you mean `match`? [E0025]"
+    );
+}
+
+#[test]
+fn char_class_test() {
+    let output = compiler(indoc! {
+      r#"
+      class {
+        a to f;
+        A to F;
+        0 to 9;
+        "_";
+        <space>;
+        not <digit>;
+        <category::letter>;
+      }
+      "#
+    });
+
+    assert_eq!(output.unwrap(), r"[a-fA-F0-9_ \D\p{L}]");
+}
+
+#[test]
+fn negative_char_class_block_test() {
+    let output = compiler(indoc! {
+      r"
+      not class {
+        a to z;
+        <whitespace>;
+      }
+      "
+    });
+
+    assert_eq!(output.unwrap(), r"[^a-z\s]");
+}
+
+#[test]
+fn quantified_char_class_test() {
+    let output = compiler(indoc! {
+      r"
+      some of class {
+        a to f;
+        0 to 9;
+      }
+      "
+    });
+
+    assert_eq!(output.unwrap(), "[a-f0-9]+");
+}
+
+#[test]
+fn char_class_escape_test() {
+    let output = compiler(indoc! {
+      r#"
+      class {
+        "]";
+        "^";
+        "-";
+        "\\";
+        "\n";
+        `x-z`;
+      }
+      "#
+    });
+
+    assert_eq!(output.unwrap(), r"[\]\^\-\\\nx-z]");
+}
+
+#[test]
+fn char_class_errors_test() {
+    let Err(CompilerError::UnexpectedMultiCharacterAtomInClass) = compiler(r#"class { "ab"; }"#) else {
+        unreachable!("expected a multi character atom error");
+    };
+
+    let Err(CompilerError::UnexpectedNegativeRangeInClass) = compiler("class { not a to z; }") else {
+        unreachable!("expected a negative range error");
+    };
+
+    for source in ["class { <char>; }", "class { not <space>; }", "class { <start>; }"] {
+        let Err(CompilerError::UnexpectedSymbolInClass) = compiler(source) else {
+            unreachable!("expected an unexpected symbol error for {source:?}");
+        };
+    }
+}
+
+#[test]
+fn char_class_subtract_test() {
+    let output = compiler(indoc! {
+      r#"
+      class {
+        a to z;
+        without {
+          "a";
+          "e";
+          "i";
+          "o";
+          "u";
+        }
+      }
+      "#
+    });
+
+    assert_eq!(output.unwrap(), "[b-df-hj-np-tv-z]");
+}
+
+#[test]
+fn char_class_intersect_test() {
+    let output = compiler(indoc! {
+      r"
+      some of class {
+        <word>;
+        and {
+          0 to 9;
+          a to f;
+          A to F;
+        }
+      }
+      "
+    });
+
+    assert_eq!(output.unwrap(), "[0-9A-Fa-f]+");
+}
+
+#[test]
+fn negative_char_class_set_operation_test() {
+    let output = compiler(indoc! {
+      r"
+      not class {
+        <digit>;
+        <tab>;
+        without {
+          5 to 9;
+        }
+      }
+      "
+    });
+
+    assert_eq!(output.unwrap(), r"[^\t0-4]");
+
+    let output = compiler(r#"class { "a"; without { "a"; } }"#);
+
+    assert_eq!(output.unwrap(), r"[^\s\S]");
+}
+
+#[test]
+fn char_class_set_operation_unsupported_test() {
+    let sources = [
+        r#"class { <category::letter>; without { "a"; } }"#,
+        "class { a to z; and { <whitespace>; } }",
+    ];
+    for source in sources {
+        let Err(CompilerError::UnsupportedByTarget { target, .. }) = compile_for(source, Target::Pcre2) else {
+            unreachable!("expected an unsupported by target error for {source:?}");
+        };
+        assert_eq!(target, Target::Pcre2);
+    }
+}
+
+#[test]
+fn code_point_escape_test() {
+    let output = compiler(indoc! {
+      r#"
+      "\x41\u{42}";
+      '\u{1F600}';
+      "\x2A\u{7B}";
+      "\x00\u{7F}";
+      "#
+    });
+
+    assert_eq!(output.unwrap(), r"AB😀\*\{\x00\x7F");
+}
+
+#[test]
+fn code_point_statement_test() {
+    let output = compiler(indoc! {
+      r"
+      char 0x1F600;
+      some of char 0x2E;
+      class {
+        char 0x61;
+        char 0x5D;
+      }
+      "
+    });
+
+    let output = output.unwrap();
+
+    assert_eq!(output, r"😀\.+[a\]]");
+    assert_eq!(output.len(), 12);
+}
+
+#[test]
+fn code_point_errors_test() {
+    let sources = [
+        (r#""\xZZ";"#, r"\xZZ"),
+        (r#""\x4";"#, r"\x4"),
+        (r#""\u{12G}";"#, r"\u{12G}"),
+        (r#""\u{}";"#, r"\u{}"),
+    ];
+    for (source, expected) in sources {
+        let Err(CompilerError::InvalidHexEscape(escape)) = compiler(source) else {
+            unreachable!("expected an invalid hex escape error for {source:?}");
+        };
+        assert_eq!(escape, expected);
+    }
+
+    let Err(CompilerError::SurrogateCodePoint(digits)) = compiler(r#""\u{D800}";"#) else {
+        unreachable!("expected a surrogate code point error");
+    };
+    assert_eq!(digits, "D800");
+
+    let Err(CompilerError::SurrogateCodePoint(_)) = compiler("char 0xDFFF;") else {
+        unreachable!("expected a surrogate code point error");
+    };
+
+    let Err(CompilerError::CodePointOutOfRange(digits)) = compiler(r#""\u{110000}";"#) else {
+        unreachable!("expected a code point out of range error");
+    };
+    assert_eq!(digits, "110000");
+
+    let Err(CompilerError::CodePointOutOfRange(_)) = compiler("char 0xFFFFFFFFFF;") else {
+        unreachable!("expected a code point out of range error");
+    };
+}
+
+#[test]
+fn unicode_range_test() {
+    let output = compiler(indoc! {
+      r"
+      some of 一 to 龥;
+      à to ÿ;
+      not α to ω;
+      class {
+        À to Ö;
+        a to z;
+      }
+      "
+    });
+
+    assert_eq!(output.unwrap(), "[一-龥]+[à-ÿ][^α-ω][À-Öa-z]");
+}
+
+#[test]
+fn astral_range_test() {
+    let source = "flags { unicode; } some of 😀 to 🙏; class { 🌀 to 🌊; a to z; }";
+
+    let (pattern, flags) = compile_with_flags(source, Target::EcmaScript).unwrap();
+    assert_eq!((pattern.as_str(), flags.as_str()), ("[😀-🙏]+[🌀-🌊a-z]", "u"));
+    assert!(regress::Regex::with_flags(&pattern, flags.as_str()).is_ok());
+
+    // without the `u` flag, ECMAScript reads the endpoints as surrogate pairs
+    let source = "\"a\"; not 😀 to 😀;";
+    let Err(CompilerError::UnsupportedByTarget { target, span, .. }) = compile_for(source, Target::EcmaScript) else {
+        unreachable!("expected an unsupported by target error");
+    };
+    assert_eq!(target, Target::EcmaScript);
+    assert_eq!(&source[span.start..span.end], "not 😀 to 😀");
+    assert!(matches!(
+        compile_with_flags("class { 😀 to 😀; }", Target::EcmaScript),
+        Err(CompilerError::UnsupportedByTarget { .. })
+    ));
+
+    assert_eq!(compile_for("😀 to 🙏;", Target::Pcre2).unwrap(), "[😀-🙏]");
+}
+
+#[test]
+fn escaped_range_test() {
+    let output = compiler("[ to ^;");
+
+    assert_eq!(output.unwrap(), r"[\[-\^]");
+}
+
+#[test]
+fn empty_range_test() {
+    let sources = [
+        ("z to a;", 'z', 'a', 0),
+        ("\"x\"; 9 to 0;", '9', '0', 5),
+        ("ÿ to à;", 'ÿ', 'à', 0),
+    ];
+    for (source, expected_start, expected_end, expected_offset) in sources {
+        let Err(CompilerError::EmptyRange { start, end, span }) = compiler(source) else {
+            unreachable!("expected an empty range error for {source:?}");
+        };
+        assert_eq!(
+            (start, end, span.start),
+            (expected_start, expected_end, expected_offset)
+        );
+    }
+}
+
+#[test]
+fn single_character_range_test() {
+    let (ast, warnings) = to_ast_with_diagnostics("a to a; 5 to 5; class { b to b; }").unwrap();
+
+    assert_eq!(root_nodes(&ast).len(), 3);
+    assert_eq!(warnings.len(), 3);
+    assert_eq!(
+        warnings[0],
+        Warning::SingleCharacterRange {
+            char: 'a',
+            span: Span {
+                start: 0,
+                end: 6,
+                line: 1,
+                column: 1,
+            },
+        }
+    );
+    assert_eq!(warnings[1].span().start, 8);
+    assert_eq!(warnings[2].to_string(), "range only matches `b`, use a literal instead");
+
+    let (_, warnings) = to_ast_with_diagnostics("a to z; 0 to 9;").unwrap();
+
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn word_boundary_test() {
+    let output = compiler(indoc! {
+      r#"
+      <word_boundary>;
+      "cat";
+      not <word_boundary>;
+      some of <word>;
+      <word_boundary>;
+      "#
+    });
+
+    assert_eq!(output.unwrap(), r"\bcat\B\w+\b");
+}
+
+#[test]
+fn either_word_boundary_test() {
+    let output = compiler(indoc! {
+      r#"
+      either {
+        <word_boundary>;
+        ",";
+        match {
+          not <word_boundary>;
+          "-";
+        }
+      }
+      "#
+    });
+
+    assert_eq!(output.unwrap(), r"(?:\b|,|\B-)");
+}
+
+#[test]
+fn quantified_word_boundary_test() {
+    let sources = [
+        "some of <word_boundary>;",
+        "2 of not <word_boundary>;",
+        "option of <boundary>;",
+    ];
+    for source in sources {
+        let Err(CompilerError::UnexpectedBoundaryInQuantifier) = compiler(source) else {
+            unreachable!("expected an unexpected boundary error for {source:?}");
+        };
+    }
+
+    // the statements of a variable are checked as if written in place
+    let sources = [
+        "let .e = { <start>; } 3 of .e;",
+        "let .e = { <start>; <end>; } let .f = { .e; } some of .f;",
+    ];
+    for source in sources {
+        let Err(CompilerError::UnexpectedSpecialSymbolInQuantifier) = compiler(source) else {
+            unreachable!("expected an unexpected special symbol error for {source:?}");
+        };
+    }
+    assert!(matches!(
+        compiler("let .b = { <word_boundary>; } option of .b;"),
+        Err(CompilerError::UnexpectedBoundaryInQuantifier)
+    ));
+    assert_eq!(compiler(r#"let .e = { <start>; "a"; } 3 of .e;"#).unwrap(), "(?:^a){3}");
+}
+
+#[test]
+fn line_and_input_anchors_test() {
+    let source = indoc! {
+      r#"
+      <input_start>;
+      <line_start>;
+      "a";
+      <line_end>;
+      <input_end>;
+      "#
+    };
+
+    assert_eq!(compile_for(source, Target::Pcre2).unwrap(), r"\A^a$\z");
+    assert_eq!(compiler(source).unwrap(), r"(?<![\s\S])^a$(?![\s\S])");
+}
+
+#[test]
+fn negative_anchors_test() {
+    for name in ["line_start", "input_start"] {
+        let Err(CompilerError::NegativeStartNotAllowed(symbol)) = compiler(&format!("not <{name}>;")) else {
+            unreachable!("expected a negative start error for <{name}>");
+        };
+        assert_eq!(symbol, name);
+    }
+    for name in ["line_end", "input_end"] {
+        let Err(CompilerError::NegativeEndNotAllowed(symbol)) = compiler(&format!("not <{name}>;")) else {
+            unreachable!("expected a negative end error for <{name}>");
+        };
+        assert_eq!(symbol, name);
+    }
+}
+
+#[test]
+fn quantified_variable_test() {
+    let output = compiler(indoc! {
+      r"
+      let .digit_pair = {
+        2 of <digit>;
+      }
+      5 of .digit_pair;
+      lazy some of .digit_pair;
+      "
+    });
+
+    assert_eq!(output.unwrap(), r"(?:\d{2}){5}(?:\d{2})+?");
+}
+
+#[test]
+fn quantified_variable_capture_test() {
+    let output = compiler(indoc! {
+      r"
+      let .letter = {
+        capture {
+          a to z;
+        }
+      }
+      some of .letter;
+      .letter;
+      same as capture 2;
+      "
+    });
+
+    assert_eq!(output.unwrap(), r"(?:([a-z]))+([a-z])\2");
+
+    let Err(CompilerError::InvalidBackreferenceIndex { group_count, .. }) =
+        compiler("let .letter = { capture { \"a\"; } } 3 of .letter; same as capture 2;")
+    else {
+        unreachable!("expected an invalid backreference index error");
+    };
+
+    assert_eq!(group_count, 1);
+}
+
+#[test]
+fn quantified_nested_variable_test() {
+    let output = compiler(indoc! {
+      r#"
+      let .digit = {
+        <digit>;
+      }
+      let .separated = {
+        .digit;
+        "-";
+      }
+      option of .separated;
+      "#
+    });
+
+    assert_eq!(output.unwrap(), r"(?:\d-)?");
+}
+
+#[test]
+fn match_group_parentheses_test() {
+    let cases = [
+        // a sequence binds tighter than anything around it
+        (r#"match { "a"; <digit>; } "b";"#, r"a\db"),
+        (r#"capture { match { "a"; "b"; } }"#, "(ab)"),
+        (r#"either { match { "a"; "b"; } "c"; }"#, "(?:ab|c)"),
+        (r#"ahead { match { "a"; "b"; } }"#, "(?=ab)"),
+        (r#""a"; match { match { "b"; "c"; } "d"; }"#, "abcd"),
+        // a quantifier would only repeat the last character
+        (r#"some of match { "a"; "b"; }"#, "(?:ab)+"),
+        (r#"option of { "a"; "b"; }"#, "(?:ab)?"),
+        (r#"2 of match { some of "a"; "b"; }"#, "(?:a+b){2}"),
+        // an alternation would take in the statements around the group
+        (r#""a"; match { either { "b"; "cd"; } } "e";"#, "a(?:b|cd)e"),
+        (r#""a"; match { `b|c`; } "d";"#, "a(?:b|c)d"),
+        (r#""a"; match { `[|]`; `(b|c)`; }"#, "a[|](b|c)"),
+        // `\1` followed by `0` would be read as `\10`
+        (r#"capture { "a"; } match { same as capture 1; } "0";"#, r"(a)\1(?:)0"),
+    ];
+
+    for (source, expected) in cases {
+        assert_eq!(compiler(source).unwrap(), expected, "{source}");
+    }
+}
+
+#[test]
+fn quantified_block_test() {
+    let output = compiler(indoc! {
+      r#"
+      3 of {
+        "ab";
+        <digit>;
+      }
+      lazy some of {
+        capture {
+          "c";
+        }
+        option of "d";
+      }
+      "#
+    });
+
+    assert_eq!(output.unwrap(), r"(?:ab\d){3}(?:(c)d?)+?");
+}
+
+#[test]
+fn quantified_block_errors_test() {
+    let Err(CompilerError::EmptyQuantifiedBlock) = compiler("3 of {}") else {
+        unreachable!("expected an empty quantified block error");
+    };
+
+    let Err(CompilerError::UnexpectedVariableDeclarationInQuantifier) = compiler(r#"3 of { let .a = { "a"; } .a; }"#)
+    else {
+        unreachable!("expected an unexpected variable declaration error");
+    };
+}
+
+#[test]
+fn lazy_quantifier_matrix_test() {
+    let cases = [
+        ("option of", "a?", "a??"),
+        ("any of", "a*", "a*?"),
+        ("some of", "a+", "a+?"),
+        ("3 to 5 of", "a{3,5}", "a{3,5}?"),
+        ("4 of", "a{4}", "a{4}?"),
+        ("over 2 of", "a{3,}", "a{3,}?"),
+        ("up to 2 of", "a{0,2}", "a{0,2}?"),
+    ];
+    for (quantity, greedy, lazy) in cases {
+        let greedy_source = format!(r#"{quantity} "a";"#);
+        let lazy_source = format!(r#"lazy {quantity} "a";"#);
+
+        assert_eq!(compiler(&greedy_source).unwrap(), greedy, "{greedy_source}");
+        assert_eq!(compiler(&lazy_source).unwrap(), lazy, "{lazy_source}");
+    }
+}
+
+#[test]
+fn lazy_exact_quantifier_warning_test() {
+    let (_, warnings) = to_ast_with_diagnostics(r#""a"; lazy 4 of "b";"#).unwrap();
+
+    assert_eq!(warnings.len(), 1);
+    let Warning::LazyExactQuantifier { span } = warnings[0] else {
+        unreachable!("expected a lazy exact quantifier warning");
+    };
+    assert_eq!((span.start, span.end), (5, 19));
+
+    for source in [r#"lazy 3 to 5 of "a";"#, r#"4 of "a";"#, r#"possessive 4 of "a";"#] {
+        let (_, warnings) = to_ast_with_diagnostics(source).unwrap();
+        assert!(warnings.is_empty(), "{source}");
+    }
+}
+
+#[test]
+fn zero_quantifier_test() {
+    let allowed = CompilerOptions {
+        allow_zero_quantifiers: true,
+        ..CompilerOptions::default()
+    };
+
+    for (source, output) in [(r#"0 of "a";"#, "a{0}"), (r#"0 to 0 of "a";"#, "a{0,0}")] {
+        assert!(
+            matches!(compiler(source), Err(CompilerError::ZeroQuantifier)),
+            "{source}"
+        );
+        assert_eq!(
+            compile_with_options(source, Target::default(), &allowed).unwrap(),
+            output,
+            "{source}"
+        );
+    }
+
+    // only quantifiers that can't repeat their expression at all are rejected
+    assert_eq!(compiler(r#"0 to 1 of "a";"#).unwrap(), "a{0,1}");
+    assert_eq!(compiler(r#"up to 1 of "a";"#).unwrap(), "a{0,1}");
+
+    let error = to_ast_all_errors(r#""a"; 0 of "b";"#).unwrap_err();
+    assert!(matches!(error[..], [Spanned { node: CompilerError::ZeroQuantifier, span }] if span.start == 5));
+}
+
+#[test]
+fn redundant_quantifier_warning_test() {
+    for source in [r#""a"; 1 of "b";"#, r#""a"; 1 to 1 of "b";"#] {
+        let (_, warnings) = to_ast_with_diagnostics(source).unwrap();
+
+        let [Warning::RedundantQuantifier { span }] = warnings[..] else {
+            unreachable!("expected a redundant quantifier warning for {source}");
+        };
+        assert_eq!(span.start, 5, "{source}");
+        assert_eq!(span.end, source.len(), "{source}");
+    }
+
+    for source in [
+        r#"2 of "a";"#,
+        r#"1 to 2 of "a";"#,
+        r#"0 to 1 of "a";"#,
+        r#"2 or more of "a";"#,
+    ] {
+        let (_, warnings) = to_ast_with_diagnostics(source).unwrap();
+        assert!(warnings.is_empty(), "{source}");
+    }
+
+    assert_eq!(compiler(r#"1 of "a";"#).unwrap(), "a{1}");
+}
+
+#[test]
+fn over_zero_test() {
+    let ast = to_ast(r#"over 0 of "a";"#).unwrap();
+    let [Spanned {
+        node: ViableAstNode::Quantifier(quantifier),
+        ..
+    }] = root_nodes(&ast)
+    else {
+        unreachable!("expected a quantifier");
+    };
+    assert!(matches!(quantifier.kind, QuantifierKind::Some));
+
+    assert_eq!(compiler(r#"over 0 of "a";"#).unwrap(), "a+");
+    assert_eq!(compiler(r#"lazy over 0 of "a";"#).unwrap(), "a+?");
+    assert_eq!(compiler(r#"over 1 of "a";"#).unwrap(), "a{2,}");
+}
+
+#[test]
+fn unused_variable_warning_test() {
+    let source = indoc! {
+        r#"
+        let .used = {
+          "a";
+        }
+        let .unused = {
+          "b";
+        }
+        .used;
+        "#
+    };
+
+    let (ast, warnings) = to_ast_with_diagnostics(source).unwrap();
+
+    assert_eq!(warnings.len(), 1);
+    let Warning::UnusedVariable { name, span } = &warnings[0] else {
+        unreachable!("expected an unused variable warning");
+    };
+    assert_eq!(name, "unused");
+    assert_eq!((span.line, span.column), (4, 1));
+    assert_eq!(warnings[0].to_string(), "variable `unused` is never used");
+
+    // warnings don't change the resulting AST
+    assert_eq!(format!("{ast:?}"), format!("{:?}", to_ast(source).unwrap()));
+}
+
+#[test]
+fn variable_used_by_variable_warning_test() {
+    let source = indoc! {
+        r#"
+        let .inner = {
+          "a";
+        }
+        let .outer = {
+          .inner;
+        }
+        .outer;
+        "#
+    };
+
+    let (_, warnings) = to_ast_with_diagnostics(source).unwrap();
+
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn shadowed_variable_warning_test() {
+    let source = indoc! {
+        r#"
+        let .variable = {
+          "a";
+        }
+        .variable;
+        let .variable = {
+          "b";
+        }
+        .variable;
+        "#
+    };
+
+    let (ast, warnings) = to_ast_with_diagnostics(source).unwrap();
+
+    assert_eq!(warnings.len(), 1);
+    let Warning::ShadowedVariable {
+        name,
+        first_span,
+        second_span,
+    } = &warnings[0]
+    else {
+        unreachable!("expected a shadowed variable warning");
+    };
+    assert_eq!(name, "variable");
+    assert_eq!(first_span.line, 1);
+    assert_eq!(second_span.line, 5);
+    assert_eq!(warnings[0].span(), *second_span);
+
+    assert_eq!(format!("{ast:?}"), format!("{:?}", to_ast(source).unwrap()));
+    assert_eq!(compiler(source).unwrap(), "ab");
+}
+
+#[test]
+fn shadowed_unused_variable_warning_test() {
+    let source = indoc! {
+        r#"
+        let .variable = {
+          "a";
+        }
+        let .variable = {
+          "b";
+        }
+        .variable;
+        "#
+    };
+
+    let (_, warnings) = to_ast_with_diagnostics(source).unwrap();
+
+    // the first declaration is replaced before it is ever invoked
+    assert_eq!(warnings.len(), 2);
+    assert!(matches!(&warnings[0], Warning::UnusedVariable { span, .. } if span.line == 1));
+    assert!(matches!(&warnings[1], Warning::ShadowedVariable { .. }));
+}
+
+#[test]
+fn variable_symbols_test() {
+    let source = indoc! {
+        r#"
+        let .inner = {
+          "a";
+        }
+        let .outer = {
+          .inner;
+          "b";
+        }
+        let .inner = {
+          "c";
+        }
+        .outer;
+        "#
+    };
+
+    let (ast, variables) = to_ast_with_symbols(source).unwrap();
+
+    assert_eq!(format!("{ast:?}"), format!("{:?}", to_ast(source).unwrap()));
+    assert_eq!(variables.len(), 2);
+
+    // the last declaration wins
+    let inner = root_nodes(&variables["inner"]);
+    assert!(matches!(&inner[0].node, ViableAstNode::Atom(atom) if &**atom == "c"));
+
+    // invocations within a variable body are stored expanded
+    let outer = root_nodes(&variables["outer"]);
+    assert_eq!(outer.len(), 2);
+    let ViableAstNode::VariableInvocation(invocation) = &outer[0].node else {
+        unreachable!("expected a variable invocation");
+    };
+    let invoked = root_nodes(&invocation.statements);
+    assert!(matches!(&invoked[0].node, ViableAstNode::Atom(atom) if &**atom == "a"));
+}
+
+#[test]
+fn nested_invocation_name_test() {
+    let source = r#"let .inner = { "a"; } let .outer = { .inner; "b"; } .outer;"#;
+    let ast = to_ast(source).unwrap();
+    let nodes = root_nodes(&ast);
+
+    let ViableAstNode::VariableInvocation(outer) = &nodes[0].node else {
+        unreachable!("expected a variable invocation");
+    };
+    assert_eq!(&*outer.name, "outer");
+    assert_eq!(&source[outer.span.start..outer.span.end], ".outer");
+
+    let ViableAstNode::VariableInvocation(inner) = &root_nodes(&outer.statements)[0].node else {
+        unreachable!("expected a nested variable invocation");
+    };
+    assert_eq!(&*inner.name, "inner");
+    assert_eq!(&source[inner.span.start..inner.span.end], ".inner");
+}
+
+#[test]
+fn empty_variable_symbols_test() {
+    let (_, variables) = to_ast_with_symbols(r#""a";"#).unwrap();
+    assert!(variables.is_empty());
+}
+
+#[test]
+fn custom_symbol_test() {
+    let options = CompilerOptions {
+        custom_symbols: BTreeMap::from([(String::from("uuid_char"), to_ast("either { 0 to 9; a to f; }").unwrap())]),
+        ..CompilerOptions::default()
+    };
+    let compile = |source| compile_with_options(source, Target::default(), &options);
+
+    // quantified as a whole, like a variable
+    assert_eq!(compile(r#"8 of <uuid_char>; "-";"#).unwrap(), "(?:[0-9a-f]){8}-");
+    assert_eq!(
+        compile(r#"8 of <uuid_char>; "-";"#).unwrap(),
+        compile(r#"let .uuid_char = { either { 0 to 9; a to f; } } 8 of .uuid_char; "-";"#).unwrap()
+    );
+    assert_eq!(
+        compile(r#"define symbol pair = { <uuid_char>; "x"; } 2 of <pair>;"#).unwrap(),
+        "(?:[0-9a-f]x){2}"
+    );
+    // built in symbols are looked up first and custom symbols are only known to the options that define them
+    assert_eq!(compile("<digit>;").unwrap(), r"\d");
+    assert!(matches!(
+        to_ast("<uuid_char>;"),
+        Err(CompilerError::UnrecognizedSymbol { .. })
+    ));
+
+    let (_, captures, _) = compile_with_metadata(
+        "define symbol group = { capture { <word>; } } <group>; <group>;",
+        Target::default(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(captures.len(), 2);
+
+    let Err(CompilerError::NegativeCustomSymbol(name)) = compile("not <uuid_char>;") else {
+        unreachable!("expected a negative custom symbol error");
+    };
+    assert_eq!(name, "uuid_char");
+    assert!(matches!(
+        compile("class { <uuid_char>; }"),
+        Err(CompilerError::UnexpectedSymbolInClass)
+    ));
+
+    let (_, symbols) = to_ast_with_custom_symbols(
+        r#"let .dash = { "-"; } define symbol pair = { 2 of <uuid_char>; } <pair>; .dash;"#,
+        options.custom_symbols.clone(),
+    )
+    .unwrap();
+    assert_eq!(symbols.keys().collect::<Vec<_>>(), ["dash", "<uuid_char>", "<pair>"]);
+    assert!(matches!(
+        &root_nodes(&symbols["<pair>"])[0].node,
+        ViableAstNode::Quantifier(_)
+    ));
+}
+
+#[test]
+fn custom_symbol_shadowing_test() {
+    let options = CompilerOptions {
+        custom_symbols: BTreeMap::from([(String::from("digit"), to_ast(r#""0";"#).unwrap())]),
+        ..CompilerOptions::default()
+    };
+    let Err(CompilerError::DuplicateSymbol { name, second_span, .. }) =
+        compile_with_options(r#""a";"#, Target::default(), &options)
+    else {
+        unreachable!("expected a duplicate symbol error");
+    };
+    assert_eq!((name.as_str(), second_span.line), ("digit", 0));
+
+    let source = "define symbol hex = { 0 to 9; } define symbol hex = { a to f; }";
+    let Err(CompilerError::DuplicateSymbol {
+        name,
+        first_span,
+        second_span,
+    }) = to_ast(source)
+    else {
+        unreachable!("expected a duplicate symbol error");
+    };
+    assert_eq!(name, "hex");
+    assert_eq!(
+        &source[first_span.start..first_span.end],
+        "define symbol hex = { 0 to 9; }"
+    );
+    assert_eq!(
+        &source[second_span.start..second_span.end],
+        "define symbol hex = { a to f; }"
+    );
+
+    for source in [
+        r#"define symbol word = { "w"; }"#,
+        r#"define symbol category::letter = { "l"; }"#,
+    ] {
+        let Err(CompilerError::DuplicateSymbol { first_span, .. }) = to_ast(source) else {
+            unreachable!("expected a duplicate symbol error for {source:?}");
+        };
+        assert_eq!(first_span, Span::default());
+    }
+
+    // an edited definition is picked up by the symbols that use it
+    let mut parser = IncrementalParser::new();
+    parser.update(r#"define symbol a = { "a"; } <a>; "b";"#).unwrap();
+    let source = r#"define symbol a = { "c"; } <a>; "b";"#;
+    let ast = format!("{:?}", parser.update(source).unwrap());
+    assert_eq!(ast, format!("{:?}", to_ast(source).unwrap()));
+}
+
+#[test]
+fn deterministic_symbols_test() {
+    let source = indoc! {r#"
+        let .zeta = { "z"; }
+        let .alpha = { "a"; }
+        let .mid = { .alpha; .zeta; }
+        let .beta = { "b"; }
+        let .alpha = { "A"; }
+        .mid; .beta;
+        "#
+    };
+    let dump = || {
+        let (_, variables) = to_ast_with_symbols(source).unwrap();
+        variables
+            .iter()
+            .map(|(name, ast)| format!("{name}: {}", ast_to_source(ast, &FormatOptions::default())))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let first = dump();
+    for _ in 0..50 {
+        assert_eq!(dump(), first);
+    }
+
+    // listed in the order the names are first declared, with the last declaration of each name
+    let (_, variables) = to_ast_with_symbols(source).unwrap();
+    assert_eq!(variables.keys().collect::<Vec<_>>(), ["zeta", "alpha", "mid", "beta"]);
+    assert!(matches!(&root_nodes(&variables["alpha"])[0].node, ViableAstNode::Atom(atom) if &**atom == "A"));
+
+    let (_, warnings) =
+        to_ast_with_diagnostics(r#"let .b = { "b"; } let .a = { "a"; } let .c = { "c"; } .a;"#).unwrap();
+    let unused: Vec<_> = warnings
+        .iter()
+        .filter_map(|warning| match warning {
+            Warning::UnusedVariable { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(unused, ["b", "c"]);
+}
+
+#[test]
+fn predefined_variable_test() {
+    let predefined = BTreeMap::from([(String::from("countryCode"), ViableAst::atom("U.S"))]);
+
+    let ast = to_ast_with_variables(r#""+"; .countryCode;"#, predefined).unwrap();
+    let nodes = root_nodes(&ast);
+
+    assert_eq!(nodes.len(), 2);
+    let ViableAstNode::VariableInvocation(invocation) = &nodes[1].node else {
+        unreachable!("expected a variable invocation");
+    };
+    let invoked = root_nodes(&invocation.statements);
+    assert!(matches!(&invoked[0].node, ViableAstNode::Atom(atom) if &**atom == r"U\.S"));
+}
+
+#[test]
+fn predefined_variable_shadowing_test() {
+    let predefined = BTreeMap::from([(String::from("code"), ViableAst::atom("a"))]);
+    let source = indoc! {
+        r#"
+        let .code = {
+          "b";
+        }
+        .code;
+        "#
+    };
+
+    let ast = to_ast_with_variables(source, predefined).unwrap();
+    let nodes = root_nodes(&ast);
+
+    let ViableAstNode::VariableInvocation(invocation) = &nodes[0].node else {
+        unreachable!("expected a variable invocation");
+    };
+    let invoked = root_nodes(&invocation.statements);
+    assert!(matches!(&invoked[0].node, ViableAstNode::Atom(atom) if &**atom == "b"));
+}
+
+#[test]
+fn predefined_variable_missing_test() {
+    assert!(matches!(
+        to_ast_with_variables(".code;", BTreeMap::new()),
+        Err(CompilerError::UninitializedVariable { .. })
+    ));
+}
+
+#[test]
+fn ast_from_nodes_test() {
+    let ast = ViableAst::from_nodes(vec![ViableAstNode::Atom("a".into()), ViableAstNode::Atom("b".into())]);
+    let nodes = root_nodes(&ast);
+
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[1].span, Span::default());
+}
+
+fn modules(files: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+    move |path| {
+        files
+            .iter()
+            .find(|(name, _)| *name == path)
+            .map(|(_, source)| (*source).to_owned())
+    }
+}
+
+#[test]
+fn import_test() {
+    let resolver = modules(&[(
+        "common.viable",
+        indoc! {
+            r#"
+            let .digits = {
+              some of <digit>;
+            }
+            // top level statements of imported files are not included
+            "ignored";
+            "#
+        },
+    )]);
+
+    let source = r#"import "common.viable"; .digits; "."; .digits;"#;
+
+    assert_eq!(
+        compile_with_imports(source, Target::default(), resolver).unwrap(),
+        r"\d+\.\d+"
+    );
+}
+
+#[test]
+fn import_symbol_test() {
+    let resolver = modules(&[
+        (
+            "symbols.viable",
+            r#"import "hex.viable"; define symbol byte = { 2 of <hex>; }"#,
+        ),
+        ("hex.viable", "define symbol hex = { class { 0 to 9; a to f; } }"),
+    ]);
+
+    let source = r#"import "symbols.viable"; <byte>; ":"; <hex>;"#;
+
+    assert_eq!(
+        compile_with_imports(source, Target::default(), resolver).unwrap(),
+        "(?:[0-9a-f]){2}:[0-9a-f]"
+    );
+}
+
+#[test]
+fn import_options_test() {
+    let resolver = || modules(&[("common.viable", r"let .word = { some of <word>; }")]);
+    let source = r#"import "common.viable"; .word; <vowel>;"#;
+
+    // sources with imports are compiled with every option, like other sources
+    let options = CompilerOptions {
+        custom_symbols: BTreeMap::from([(String::from("vowel"), to_ast(r#"either { "a"; "e"; }"#).unwrap())]),
+        ..CompilerOptions::default()
+    };
+    let (output, _) = compile_with_imports_and_metadata(source, Target::Pcre2, &options, resolver()).unwrap();
+    assert_eq!(output, r"\w+[ae]");
+
+    let options = CompilerOptions {
+        strictness: Strictness::Strict,
+        ..CompilerOptions::default()
+    };
+    assert!(matches!(
+        compile_with_imports_and_metadata(
+            r#"import "common.viable"; let .a = { "a"; } .word;"#,
+            Target::Pcre2,
+            &options,
+            resolver()
+        ),
+        Err(CompilerError::DeniedWarning(Warning::UnusedVariable { .. }))
+    ));
+
+    let options = CompilerOptions {
+        max_estimated_regex_len: Some(4),
+        ..CompilerOptions::default()
+    };
+    assert!(matches!(
+        compile_with_imports_and_metadata(
+            r#"import "common.viable"; 3 of .word;"#,
+            Target::Pcre2,
+            &options,
+            resolver()
+        ),
+        Err(CompilerError::PatternTooLarge { .. })
+    ));
+
+    // and are checked against the target like other sources
+    assert!(matches!(
+        compile_with_imports(
+            r#"import "common.viable"; .word; 😀 to 🙏;"#,
+            Target::EcmaScript,
+            resolver()
+        ),
+        Err(CompilerError::UnsupportedByTarget {
+            target: Target::EcmaScript,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn nested_relative_import_test() {
+    let resolver = modules(&[
+        (
+            "lib/common.viable",
+            r#"import "./base.viable"; let .word = { .letters; }"#,
+        ),
+        ("lib/base.viable", r"let .letters = { some of <alphabetic>; }"),
+    ]);
+
+    let ast = to_ast_with_imports(r#"import "lib/common.viable"; .word;"#, resolver).unwrap();
+    let nodes = root_nodes(&ast);
+
+    assert_eq!(nodes.len(), 1);
+    assert!(matches!(nodes[0].node, ViableAstNode::VariableInvocation(_)));
+}
+
+#[test]
+fn import_cycle_test() {
+    let resolver = modules(&[
+        ("a.viable", r#"import "lib/b.viable"; let .a = { "a"; }"#),
+        ("lib/b.viable", r#"import "../a.viable"; let .b = { "b"; }"#),
+    ]);
+
+    let error = to_ast_with_imports(r#"import "a.viable";"#, resolver).unwrap_err();
+    let CompilerError::ImportCycle { chain } = &error else {
+        unreachable!("expected an import cycle error");
+    };
+
+    assert_eq!(chain, &["a.viable", "lib/b.viable", "a.viable"]);
+    assert_eq!(
+        error.to_string(),
+        "import cycle (a.viable -> lib/b.viable -> a.viable) [E0010]"
+    );
+}
+
+#[test]
+fn import_not_found_test() {
+    let resolver = modules(&[("lib/common.viable", r#"import "missing.viable";"#)]);
+
+    let error = to_ast_with_imports(r#"import "lib/common.viable";"#, resolver).unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "in import `lib/common.viable`: could not find the imported file `lib/missing.viable` [E0012]"
+    );
+    let CompilerError::ImportError { error, .. } = error else {
+        unreachable!("expected an import error");
+    };
+    let CompilerError::ImportNotFound(path) = *error else {
+        unreachable!("expected an import not found error");
+    };
+    assert_eq!(path, "lib/missing.viable");
+}
+
+#[test]
+fn import_error_test() {
+    let resolver = modules(&[("common.viable", r"let .a = { .missing; }")]);
+
+    let error = to_ast_with_imports(r#"import "common.viable";"#, resolver).unwrap_err();
+
+    let CompilerError::ImportError { path, error } = error else {
+        unreachable!("expected an import error");
+    };
+    assert_eq!(path, "common.viable");
+    assert!(matches!(*error, CompilerError::UninitializedVariable { .. }));
+}
+
+#[test]
+fn import_without_resolver_test() {
+    assert!(matches!(
+        to_ast(r#"import "common.viable";"#),
+        Err(CompilerError::MissingImportResolver(path)) if path == "common.viable"
+    ));
+}
+
+#[test]
+fn parameterized_variable_test() {
+    let source = indoc! {
+        r#"
+        let .pair(separator) = {
+          <digit>;
+          .separator;
+          <digit>;
+        }
+        .pair(",");
+        .pair(a to f);
+        "#
+    };
+
+    assert_eq!(compiler(source).unwrap(), r"\d,\d\d[a-f]\d");
+
+    let ast = to_ast(source).unwrap();
+    let nodes = root_nodes(&ast);
+    let (ViableAstNode::VariableInvocation(first), ViableAstNode::VariableInvocation(second)) =
+        (&nodes[0].node, &nodes[1].node)
+    else {
+        unreachable!("expected variable invocations");
+    };
+
+    // each invocation is expanded with its own arguments
+    assert_eq!(first.arguments.len(), 1);
+    assert!(matches!(root_nodes(&first.statements)[1].node, ViableAstNode::Atom(ref atom) if &**atom == ","));
+    assert!(matches!(
+        root_nodes(&second.statements)[1].node,
+        ViableAstNode::Range(_)
+    ));
+    assert_ne!(format!("{:?}", first.statements), format!("{:?}", second.statements));
+}
+
+#[test]
+fn parameterized_variable_multiple_parameters_test() {
+    let source = indoc! {
+        r#"
+        let .wrapped(open, close) = {
+          .open;
+          some of <word>;
+          .close;
+        }
+        .wrapped("(", ")");
+        .wrapped(<space>, "!");
+        "#
+    };
+
+    assert_eq!(compiler(source).unwrap(), r"\(\w+\) \w+!");
+}
+
+#[test]
+fn parameterized_variable_quantified_parameter_test() {
+    let source = indoc! {
+        r#"
+        let .three(item) = {
+          3 of .item;
+        }
+        .three("ab");
+        "#
+    };
+
+    assert_eq!(compiler(source).unwrap(), "(?:ab){3}");
+}
+
+#[test]
+fn parameterized_variable_arity_test() {
+    let declaration = "let .pair(separator) = { <digit>; .separator; <digit>; }";
+
+    for (invocation, expected, found) in [(".pair;", 1, 0), (r#".pair(",", "-");"#, 1, 2)] {
+        let errors = to_ast_all_errors(&format!("{declaration}\n{invocation}")).unwrap_err();
+
+        let CompilerError::ArityMismatch {
+            name,
+            expected: error_expected,
+            found: error_found,
+        } = &errors[0].node
+        else {
+            unreachable!("expected an arity mismatch error");
+        };
+        assert_eq!(name, "pair");
+        assert_eq!((*error_expected, *error_found), (expected, found));
+        assert_eq!(errors[0].span.line, 2);
+    }
+
+    let source = r#"let .plain = { "a"; } .plain("b");"#;
+    assert!(matches!(
+        to_ast(source),
+        Err(CompilerError::ArityMismatch {
+            expected: 0,
+            found: 1,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn parameterized_variable_unknown_parameter_test() {
+    let source = indoc! {
+        r"
+        let .pair(separator) = {
+          <digit>;
+          .seperator;
+        }
+        "
+    };
+
+    let errors = to_ast_all_errors(source).unwrap_err();
+
+    assert!(matches!(&errors[0].node, CompilerError::UnknownParameter(name) if name == "seperator"));
+    assert_eq!((errors[0].span.line, errors[0].span.column), (3, 3));
+}
+
+#[test]
+fn parameterized_variable_duplicate_parameter_test() {
+    assert!(matches!(
+        to_ast(r"let .pair(a, a) = { .a; }"),
+        Err(CompilerError::DuplicateParameter(name)) if name == "a"
+    ));
+}
+
+#[test]
+fn parameterized_variable_argument_kind_test() {
+    let source = r#"let .wrap(item) = { .item; } .wrap(capture { "a"; });"#;
+    assert!(matches!(to_ast(source), Err(CompilerError::ParseError { .. })));
+}
+
+#[test]
+fn either_to_class_test() {
+    let output = compiler(indoc! {
+        r#"
+        either {
+          "a";
+          "b";
+          <digit>;
+          x to z;
+        }
+        some of either {
+          "]";
+          "-";
+          "^";
+          "\\";
+          ".";
+        }
+        "#
+    });
+
+    assert_eq!(output.unwrap(), r"[ab\dx-z][\]\-\^\\.]+");
+}
+
+#[test]
+fn either_to_class_disabled_test() {
+    let sources = [
+        // a member longer than one character
+        r#"either { "a"; "bc"; }"#,
+        // a negated member
+        r#"either { "a"; not <digit>; }"#,
+        r#"either { "a"; not b to c; }"#,
+        // a symbol that can't be placed in a class
+        r#"either { "a"; <char>; }"#,
+        // a raw metacharacter
+        r#"either { "a"; `.`; }"#,
+    ];
+
+    for source in sources {
+        let output = compiler(source).unwrap();
+        assert!(output.starts_with("(?:"), "{source} compiled to {output}");
+    }
+}
+
+#[test]
+fn either_to_class_nested_test() {
+    let output = compiler(indoc! {
+        r#"
+        capture {
+          either {
+            "a";
+            "b";
+          }
+        }
+        ahead {
+          either {
+            "c";
+            "d";
+          }
+        }
+        "#
+    });
+
+    assert_eq!(output.unwrap(), "([ab])(?=[cd])");
+}
+
+#[test]
+fn either_to_class_opt_out_test() {
+    // the option is what lowers groups to a class, at every level that optimizes
+    for optimization in [OptimizationLevel::Basic, OptimizationLevel::Aggressive] {
+        let options = CompilerOptions {
+            optimization,
+            either_to_class: false,
+            ..CompilerOptions::default()
+        };
+        let output = compile_with_options(r#"either { "a"; "b"; }"#, Target::default(), &options);
+
+        assert_eq!(output.unwrap(), "(?:a|b)");
+    }
+}
+
+#[test]
+fn flatten_either_test() {
+    let source = indoc! {
+        r#"
+        let .short = {
+          either {
+            "ab";
+            "cd";
+          }
+        }
+        either {
+          either {
+            "ef";
+            either {
+              "gh";
+              "ab";
+            }
+          }
+          .short;
+          "ij";
+          "cd";
+        }
+        "#
+    };
+
+    assert_eq!(compiler(source).unwrap(), "(?:ef|gh|ab|cd|ij)");
+
+    let options = CompilerOptions {
+        flatten_either: false,
+        ..CompilerOptions::default()
+    };
+    assert_eq!(
+        compile_with_options(source, Target::default(), &options).unwrap(),
+        "(?:(?:ef|(?:gh|ab))|(?:ab|cd)|ij|cd)"
+    );
+
+    // flattened branches of single characters can become a class as a whole
+    assert_eq!(compiler(r#"either { either { "a"; "b"; } "c"; }"#).unwrap(), "[abc]");
+    // branches matching alike are removed however they're written
+    assert_eq!(compiler(r#"either { "ab"; "a"; 'a'; `ab`; }"#).unwrap(), "(?:ab|a)");
+}
+
+#[test]
+fn flatten_either_captures_test() {
+    // an alternation within a capture or a quantifier is a group of its own
+    assert_eq!(
+        compiler(r#"either { capture { either { "a"; "bc"; } } some of either { "d"; "ef"; } }"#).unwrap(),
+        "(?:((?:a|bc))|(?:d|ef)+)"
+    );
+
+    // repeated branches holding a capture are kept, removing them would renumber the capture groups after them
+    let source = r#"either { capture { "a"; } capture { "a"; } } capture { "b"; }"#;
+    let (output, captures, _) = compile_with_metadata(source, Target::default(), &CompilerOptions::default()).unwrap();
+    assert_eq!(output, "(?:(a)|(a))(b)");
+    assert_eq!(captures.len(), 3);
+
+    // flattening keeps the order of the branches, and so the numbering of their captures
+    let source = r#"either { either { capture { "a"; } "b"; } capture { "c"; } } same as capture 2;"#;
+    assert_eq!(compiler(source).unwrap(), r"(?:(a)|b|(c))\2");
+
+    // an empty alternation matches the empty string, so it isn't flattened away
+    assert_eq!(compiler(r#"either { "ab"; either { nothing; } }"#).unwrap(), "(?:ab|)");
+}
+
+#[test]
+fn flatten_either_property_test() {
+    const BRANCHES: [&str; 5] = [r#""a";"#, r#""b";"#, r#""ab";"#, r#""ba";"#, "nothing;"];
+
+    fn either(next: &mut impl FnMut() -> usize, depth: usize) -> String {
+        let branches: String = (0..2 + next() % 3)
+            .map(|_| match next() % 4 {
+                0 if depth > 0 => either(next, depth - 1),
+                1 if depth > 0 => format!("capture {{ {} }}", either(next, depth - 1)),
+                _ => String::from(BRANCHES[next() % BRANCHES.len()]),
+            })
+            .collect();
+        format!("either {{ {branches} }} ")
+    }
+
+    // a fixed seed keeps failures reproducible without a randomness dependency
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        usize::try_from(state % 1024).unwrap_or_default()
+    };
+
+    let unflattened = CompilerOptions {
+        flatten_either: false,
+        ..CompilerOptions::default()
+    };
+    let inputs = ["", "a", "b", "ab", "ba", "aa", "bb", "aba", "bab", "abab", "baba"];
+    let mut rewritten = 0;
+
+    for _ in 0..200 {
+        let source = format!("<start>; {} <end>;", either(&mut next, 3));
+        let compiled = |options: &CompilerOptions| {
+            let pattern = compile_with_options(&source, Target::default(), options).unwrap();
+            let Ok(regex) = regress::Regex::new(&pattern) else {
+                unreachable!("expected `{pattern}` to be a valid ECMAScript pattern");
+            };
+            (pattern, regex)
+        };
+        let (flattened_pattern, flattened) = compiled(&CompilerOptions::default());
+        let (pattern, regex) = compiled(&unflattened);
+        rewritten += usize::from(flattened_pattern != pattern);
+
+        for input in inputs {
+            let groups = |regex: &regress::Regex| {
+                regex.find(input).map(|found| {
+                    found
+                        .groups()
+                        .map(|group| group.map(|range| &input[range]))
+                        .collect::<Vec<_>>()
+                })
+            };
+            assert_eq!(
+                groups(&flattened),
+                groups(&regex),
+                "{flattened_pattern} and {pattern} on {input:?}"
+            );
+        }
+    }
+
+    assert!(rewritten > 100, "only {rewritten} patterns were flattened");
+}
+
+#[test]
+fn negated_range_test() {
+    // a lone negated range is a single negated class, also under a quantifier
+    assert_eq!(compiler("not a to z;").unwrap(), "[^a-z]");
+    assert_eq!(compiler("some of not 0 to 9;").unwrap(), "[^0-9]+");
+    assert_eq!(compiler("3 of not a to f;").unwrap(), "[^a-f]{3}");
+
+    // negated classes match newlines, which have to be excluded explicitly
+    assert_eq!(compiler("not class { a to z; <newline>; }").unwrap(), r"[^a-z\n]");
+
+    // without merging, each negated statement matches a character of its own
+    assert_eq!(compiler("not a to z; not 0 to 9;").unwrap(), "[^a-z][^0-9]");
+}
+
+#[test]
+fn merge_negated_classes_test() {
+    let options = CompilerOptions {
+        merge_negated_classes: true,
+        ..CompilerOptions::default()
+    };
+    let compile = |source: &str| compile_with_options(source, Target::default(), &options).unwrap();
+
+    let cases = [
+        ("not a to z; not 0 to 9;", "[^a-z0-9]"),
+        ("not a to z; not <newline>;", r"[^a-z\n]"),
+        (r#"not 0 to 9; not class { "_"; <whitespace>; }"#, r"[^0-9_\s]"),
+        ("not <digit>; // a comment\nnot <newline>;", r"[^\d\n]"),
+        // a lone negated range is kept as written
+        ("not a to z;", "[^a-z]"),
+        // quantified ranges and other statements end a run
+        (r#"not a to z; "-"; not 0 to 9;"#, "[^a-z]-[^0-9]"),
+        ("some of not a to z; not 0 to 9;", "[^a-z]+[^0-9]"),
+        // branches of an `either` are alternatives, so they aren't merged
+        ("either { not a to z; not 0 to 9; }", "(?:[^a-z]|[^0-9])"),
+        ("match { not a to z; not 0 to 9; }", "[^a-z0-9]"),
+        // set operations exclude fewer characters than their members, so they aren't merged
+        (r#"not class { a to z; without { "a"; } } not 0 to 9;"#, "[^b-z][^0-9]"),
+    ];
+
+    for (source, expected) in cases {
+        assert_eq!(compile(source), expected, "{source}");
+    }
+}
+
+fn compile_aggressive(source: &str) -> String {
+    let options = CompilerOptions {
+        optimization: OptimizationLevel::Aggressive,
+        ..CompilerOptions::default()
+    };
+    compile_with_options(source, Target::default(), &options).unwrap()
+}
+
+#[test]
+fn either_prefix_factoring_test() {
+    let source = r#"either { "foobar"; "foobaz"; "foobin"; }"#;
+
+    assert_eq!(compiler(source).unwrap(), "(?:foobar|foobaz|foobin)");
+    assert_eq!(compile_aggressive(source), "foob(?:ar|az|in)");
+}
+
+#[test]
+fn either_suffix_factoring_test() {
+    assert_eq!(
+        compile_aggressive(r#"either { "preview"; "review"; "view"; }"#),
+        "(?:pre|re|)view"
+    );
+
+    // remainders of a single character become a class
+    assert_eq!(compile_aggressive(r#"either { "a.txt"; "b.txt"; }"#), r"[ab]\.txt");
+}
+
+#[test]
+fn either_factoring_escapes_test() {
+    // escapes are never split
+    assert_eq!(compile_aggressive(r#"either { "\x01a"; "\x02a"; }"#), r"[\x01\x02]a");
+    assert_eq!(compile_aggressive(r#"either { ".a"; ".b"; }"#), r"\.[ab]");
+}
+
+#[test]
+fn either_factoring_untouched_test() {
+    let sources = [
+        // branches other than literals
+        r#"either { "ab"; some of "ac"; }"#,
+        r#"either { "ab"; ahead { "ac"; } }"#,
+        // raw metacharacters
+        r"either { `a+`; `a?`; }",
+        // nothing in common
+        r#"either { "ab"; "cd"; }"#,
+    ];
+
+    for source in sources {
+        assert_eq!(compile_aggressive(source), compiler(source).unwrap(), "{source}");
+    }
+}
+
+#[test]
+fn either_factoring_capture_test() {
+    // only non-capturing groups are added, so capture numbering is unchanged
+    let output = compile_aggressive(r#"capture { either { "foobar"; "foobaz"; } } same as capture 1;"#);
+
+    assert_eq!(output, r"(fooba[rz])\1");
+}
+
+#[test]
+fn either_factoring_equivalence_test() {
+    let sources = [
+        r#"either { "foobar"; "foobaz"; "foobin"; }"#,
+        r#"either { "ab"; "a"; "abab"; "b"; }"#,
+        r#"either { "ba"; "aba"; "a"; }"#,
+        r#"some of either { "ab"; "aab"; "b"; }"#,
+    ];
+    let alphabet = ['a', 'b', 'f', 'o', 'r', 'z', 'i', 'n'];
+
+    // every string over the alphabet of up to 4 characters
+    let mut inputs = vec![String::new()];
+    for length in 0..4 {
+        let longest: Vec<String> = inputs.iter().filter(|input| input.len() == length).cloned().collect();
+        for input in longest {
+            inputs.extend(alphabet.iter().map(|char| format!("{input}{char}")));
+        }
+    }
+    inputs.extend(["foobar", "foobaz", "foobin", "fooba"].map(String::from));
+
+    for source in sources {
+        let unoptimized = compiler(source).unwrap();
+        let optimized = compile_aggressive(source);
+
+        let unoptimized = regress::Regex::new(&format!("^(?:{unoptimized})$")).unwrap();
+        let optimized = regress::Regex::new(&format!("^(?:{optimized})$")).unwrap();
+
+        for input in &inputs {
+            assert_eq!(
+                unoptimized.find(input).is_some(),
+                optimized.find(input).is_some(),
+                "{source} on {input:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn no_optimization_test() {
+    // no rewrite applies without optimizations, even those whose options are enabled
+    let options = CompilerOptions {
+        optimization: OptimizationLevel::None,
+        either_to_class: true,
+        flatten_either: true,
+        merge_negated_classes: true,
+        ..CompilerOptions::default()
+    };
+    let output = compile_with_options(r#"either { "a"; "b"; }"#, Target::default(), &options);
+    assert_eq!(output.unwrap(), "(?:a|b)");
+
+    let output = compile_with_options(
+        r#"either { either { "ab"; "cd"; } "ef"; }"#,
+        Target::default(),
+        &options,
+    );
+    assert_eq!(output.unwrap(), "(?:(?:ab|cd)|ef)");
+
+    let output = compile_with_options("not a to z; not 0 to 9;", Target::default(), &options);
+    assert_eq!(output.unwrap(), "[^a-z][^0-9]");
+}
+
+fn atoms(ast: &ViableAst) -> Vec<String> {
+    root_nodes(ast)
+        .iter()
+        .filter_map(|node| match &node.node {
+            ViableAstNode::Atom(atom) => Some(atom.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn normalize_test() {
+    let source = indoc! {
+        r#"
+        "foo";
+        "bar";
+        `[a]`;
+        <digit>;
+        "a.";
+        "b";
+        some of "c";
+        "d";
+        "#
+    };
+
+    let ast = to_ast(source).unwrap().normalize();
+    let nodes = root_nodes(&ast);
+
+    assert_eq!(atoms(&ast), ["foobar[a]", r"a\.b", "d"]);
+    assert_eq!(
+        &source[nodes[0].span.start..nodes[0].span.end],
+        "\"foo\";\n\"bar\";\n`[a]`"
+    );
+    assert!(matches!(nodes[3].node, ViableAstNode::Quantifier(_)));
+
+    assert_eq!(compiler(source).unwrap(), r"foobar[a]\da\.bc+d");
+}
+
+#[test]
+fn normalize_nested_test() {
+    let source = indoc! {
+        r#"
+        let .variable = {
+          "a";
+          "b";
+        }
+        capture {
+          "c";
+          "d";
+        }
+        not ahead {
+          "e";
+          "f";
+        }
+        .variable;
+        either {
+          "g";
+          "h";
+        }
+        "#
+    };
+
+    let ast = to_ast(source).unwrap().normalize();
+    let nodes = root_nodes(&ast);
+
+    let ViableAstNode::Group(capture) = &nodes[0].node else {
+        unreachable!("expected a capture group");
+    };
+    assert_eq!(atoms(&capture.statements), ["cd"]);
+
+    let ViableAstNode::Assertion(assertion) = &nodes[1].node else {
+        unreachable!("expected an assertion");
+    };
+    assert_eq!(atoms(&assertion.statements), ["ef"]);
+
+    let ViableAstNode::VariableInvocation(invocation) = &nodes[2].node else {
+        unreachable!("expected a variable invocation");
+    };
+    assert_eq!(atoms(&invocation.statements), ["ab"]);
+
+    // the branches of an either group stay apart
+    let ViableAstNode::Group(either) = &nodes[3].node else {
+        unreachable!("expected an either group");
+    };
+    assert_eq!(atoms(&either.statements), ["g", "h"]);
+}
+
+#[test]
+fn normalize_option_test() {
+    let source = r#""a"; "b"; either { "c"; "d"; }"#;
+    let options = CompilerOptions {
+        normalize: false,
+        ..CompilerOptions::default()
+    };
+
+    assert_eq!(
+        compile_with_options(source, Target::default(), &options).unwrap(),
+        compiler(source).unwrap()
+    );
+}
+
+#[test]
+fn empty_match_group_removed_test() {
+    let source = indoc! {
+        r#"
+        let .empty = {
+          let .unused = {
+            "x";
+          }
+        }
+        "a";
+        match {
+          .empty;
+        }
+        insensitive {
+          match {
+            .empty;
+          }
+        }
+        "b";
+        "#
+    };
+
+    assert_eq!(compiler(source).unwrap(), "ab");
+}
+
+#[test]
+fn empty_alternation_test() {
+    let source = indoc! {
+        r#"
+        let .empty = {
+          let .unused = {
+            "x";
+          }
+        }
+        either {
+          .empty;
+        }
+        "#
+    };
+
+    let errors = to_ast_all_errors(source).unwrap_err();
+
+    assert!(matches!(errors[0].node, CompilerError::EmptyAlternation));
+    assert_eq!(errors[0].span.line, 6);
+}
+
+#[test]
+fn either_skipped_branches_test() {
+    let source = indoc! {
+        r#"
+        either {
+          let .variable = {
+            "x";
+          }
+          .variable;
+          "ab";
+        }
+        "#
+    };
+
+    assert_eq!(compiler(source).unwrap(), "(?:x|ab)");
+}
+
+#[test]
+fn nothing_branch_test() {
+    let source = indoc! {
+        r#"
+        capture {
+          either {
+            "a";
+            nothing;
+          }
+        }
+        capture {
+          "b";
+        }
+        "#
+    };
+
+    let (output, captures, _) = compile_with_metadata(source, Target::default(), &CompilerOptions::default()).unwrap();
+    assert_eq!(output, "((?:a|))(b)");
+    let indices: Vec<_> = captures.iter().map(|capture| capture.index).collect();
+    assert_eq!(indices, [1, 2]);
+
+    assert_eq!(compiler(r#"either { nothing; "a"; }"#).unwrap(), "(?:|a)");
+
+    let ast = to_ast(r#"either { "a"; nothing; }"#).unwrap();
+    assert_eq!(
+        ast_to_source(&ast, &FormatOptions::default()),
+        "either {\n  \"a\";\n  nothing;\n}\n"
+    );
+}
+
+#[test]
+fn nothing_outside_either_test() {
+    let sources = [
+        "nothing;",
+        "some of nothing;",
+        r#"match { "a"; nothing; }"#,
+        r#"either { match { nothing; } "a"; }"#,
+        "ahead { nothing; }",
+    ];
+
+    for source in sources {
+        assert!(
+            matches!(compiler(source), Err(CompilerError::NothingOutsideEither)),
+            "{source}"
+        );
+    }
+
+    let errors = to_ast_all_errors("\"a\";\nnothing;").unwrap_err();
+    assert_eq!(errors[0].span.line, 2);
+}
+
+#[test]
+fn empty_capture_warning_test() {
+    let source = indoc! {
+        r#"
+        let .empty = {
+          let .unused = {
+            "x";
+          }
+        }
+        capture {
+          .empty;
+        }
+        "#
+    };
+
+    let (_, warnings) = to_ast_with_diagnostics(source).unwrap();
+
+    assert!(warnings
+        .iter()
+        .any(|warning| matches!(warning, Warning::EmptyCapture { span } if span.line == 6)));
+    assert_eq!(compiler(source).unwrap(), "()");
+}
+
+#[test]
+fn quantified_empty_expression_test() {
+    let declaration = indoc! {
+        r#"
+        let .empty = {
+          let .unused = {
+            "x";
+          }
+        }
+        "#
+    };
+
+    for quantifier in ["3 of .empty;", "some of match { .empty; }", r#"option of "";"#] {
+        let errors = to_ast_all_errors(&format!("{declaration}{quantifier}")).unwrap_err();
+        assert!(
+            matches!(errors[0].node, CompilerError::EmptyQuantifiedBlock),
+            "{quantifier}"
+        );
+    }
+}
+
+const CONFORMANCE_TARGETS: [Target; 5] = [
+    Target::EcmaScript,
+    Target::Pcre2,
+    Target::Re2,
+    Target::PythonRe,
+    Target::RustRegex,
+];
+
+/// The expected output of each source per target in the order of [`CONFORMANCE_TARGETS`],
+/// `None` where the target rejects the construct
+const CONFORMANCE_TABLE: &[(&str, [Option<&str>; 5])] = &[
+    (
+        r#"capture name { "a"; }"#,
+        [
+            Some("(?<name>a)"),
+            Some("(?<name>a)"),
+            Some("(?P<name>a)"),
+            Some("(?P<name>a)"),
+            Some("(?<name>a)"),
+        ],
+    ),
+    (
+        r#"capture name { "a"; } same as name;"#,
+        [
+            Some(r"(?<name>a)\k<name>"),
+            Some(r"(?<name>a)\k<name>"),
+            None,
+            Some("(?P<name>a)(?P=name)"),
+            None,
+        ],
+    ),
+    (
+        r#"capture { "a"; } same as capture 1;"#,
+        [Some(r"(a)\1"), Some(r"(a)\1"), None, Some(r"(a)\1"), None],
+    ),
+    (
+        r#"ahead { "a"; }"#,
+        [Some("(?=a)"), Some("(?=a)"), None, Some("(?=a)"), None],
+    ),
+    (
+        r#"not behind { "a"; }"#,
+        [Some("(?<!a)"), Some("(?<!a)"), None, Some("(?<!a)"), None],
+    ),
+    (
+        "<input_start>; <input_end>;",
+        [
+            Some(r"(?<![\s\S])(?![\s\S])"),
+            Some(r"\A\z"),
+            Some(r"\A\z"),
+            Some(r"\A\Z"),
+            Some(r"\A\z"),
+        ],
+    ),
+    (
+        r#"possessive some of "a";"#,
+        [None, Some("a++"), None, Some("a++"), None],
+    ),
+    (r#"atomic { "a"; }"#, [None, Some("(?>a)"), None, Some("(?>a)"), None]),
+    (
+        r#"flags { insensitive; } "a";"#,
+        [None, Some("(?i)a"), Some("(?i)a"), Some("(?i)a"), Some("(?i)a")],
+    ),
+    (
+        r#"flags { unicode; } "a";"#,
+        [None, Some("(*UTF)a"), Some("a"), Some("(?u)a"), Some("(?u)a")],
+    ),
+    (
+        "<script::greek>;",
+        [
+            Some(r"\p{Script=Greek}"),
+            Some(r"\p{Greek}"),
+            Some(r"\p{Greek}"),
+            None,
+            Some(r"\p{Greek}"),
+        ],
+    ),
+    (
+        "<category::uppercase_letter>;",
+        [Some(r"\p{Lu}"), Some(r"\p{Lu}"), Some(r"\p{Lu}"), None, Some(r"\p{Lu}")],
+    ),
+    (
+        "<category::cased_letter>;",
+        [Some(r"\p{L&}"), Some(r"\p{L&}"), None, None, Some(r"\p{LC}")],
+    ),
+    (
+        "class { <category::letter>; without { \"a\"; } }",
+        [None, None, None, None, Some(r"[\p{L}--[a]]")],
+    ),
+    (
+        "<null>;",
+        [Some(r"\0"), Some(r"\0"), Some(r"\x00"), Some(r"\0"), Some(r"\x00")],
+    ),
+    (
+        "<backspace>;",
+        [
+            Some(r"[\b]"),
+            Some(r"[\b]"),
+            Some(r"[\x08]"),
+            Some(r"[\b]"),
+            Some(r"[\x08]"),
+        ],
+    ),
+    (
+        "class { <backspace>; <tab>; }",
+        [
+            Some(r"[\b\t]"),
+            Some(r"[\b\t]"),
+            Some(r"[\x08\t]"),
+            Some(r"[\b\t]"),
+            Some(r"[\x08\t]"),
+        ],
+    ),
+    ("<grapheme>;", [None, Some(r"\X"), None, None, None]),
+    ("3 of <grapheme>;", [None, Some(r"\X{3}"), None, None, None]),
+    (
+        "<line_break>;",
+        [
+            Some(r"(?:\r\n|\r|\n)"),
+            Some(r"\R"),
+            Some(r"(?:\r\n|\r|\n)"),
+            Some(r"(?:\r\n|\r|\n)"),
+            Some(r"(?:\r\n|\r|\n)"),
+        ],
+    ),
+    (
+        "2 of <line_break>;",
+        [
+            Some(r"(?:\r\n|\r|\n){2}"),
+            Some(r"\R{2}"),
+            Some(r"(?:\r\n|\r|\n){2}"),
+            Some(r"(?:\r\n|\r|\n){2}"),
+            Some(r"(?:\r\n|\r|\n){2}"),
+        ],
+    ),
+    (
+        "not <vertical>;",
+        [
+            Some(r"[^\v]"),
+            Some(r"[^\x0B]"),
+            Some(r"[^\v]"),
+            Some(r"[^\v]"),
+            Some(r"[^\v]"),
+        ],
+    ),
+    (
+        r#"class { "a"; <vertical>; }"#,
+        [
+            Some(r"[a\v]"),
+            Some(r"[a\x0B]"),
+            Some(r"[a\v]"),
+            Some(r"[a\v]"),
+            Some(r"[a\v]"),
+        ],
+    ),
+    (
+        r#""a/b";"#,
+        [Some(r"a\/b"), Some("a/b"), Some("a/b"), Some("a/b"), Some("a/b")],
+    ),
+    (
+        r#""a\x5Cb";"#,
+        [
+            Some(r"a\\b"),
+            Some(r"a\\b"),
+            Some(r"a\\b"),
+            Some(r"a\\b"),
+            Some(r"a\\b"),
+        ],
+    ),
+    (
+        r#"class { "&"; "~"; "-"; "/"; }"#,
+        [
+            Some(r"[&~\-/]"),
+            Some(r"[&~\-/]"),
+            Some(r"[&~\-/]"),
+            Some(r"[\&\~\-/]"),
+            Some(r"[\&\~\-/]"),
+        ],
+    ),
+    (
+        r#"class { "\x08"; "]"; }"#,
+        [
+            Some(r"[\b\]]"),
+            Some(r"[\b\]]"),
+            Some(r"[\x08\]]"),
+            Some(r"[\b\]]"),
+            Some(r"[\x08\]]"),
+        ],
+    ),
+    ("recurse;", [None, Some("(?R)"), None, None, None]),
+    (
+        r#"capture a { "a"; } recurse a;"#,
+        [None, Some("(?<a>a)(?&a)"), None, None, None],
+    ),
+];
+
+#[test]
+fn target_conformance_test() {
+    for &(source, expected) in CONFORMANCE_TABLE {
+        for (target, expected) in CONFORMANCE_TARGETS.into_iter().zip(expected) {
+            let output = compile_for(source, target);
+            match expected {
+                Some(expected) => assert_eq!(output.unwrap(), expected, "{source:?} for {target}"),
+                None => assert!(
+                    matches!(output, Err(CompilerError::UnsupportedByTarget { target: error_target, .. }) if error_target == target),
+                    "expected {source:?} to be unsupported by {target}, got {output:?}"
+                ),
+            }
+        }
+    }
+}
+
+#[test]
+fn unsupported_lookbehind_span_test() {
+    let source = indoc! {
+      r#"
+      "a";
+      behind { "b"; }
+      "#
+    };
+
+    let Err(CompilerError::UnsupportedByTarget {
+        construct,
+        target,
+        span,
+    }) = compile_for(source, Target::Re2)
+    else {
+        unreachable!("expected an unsupported by target error");
+    };
+
+    assert_eq!((construct, target), ("a lookbehind assertion", Target::Re2));
+    assert_eq!(&source[span.start..span.end], r#"behind { "b"; }"#);
+}
+
+#[test]
+fn target_compatibility_test() {
+    let source = indoc! {
+      r#"
+      let .quote = {
+        capture quote { "'"; }
+        same as quote;
+      }
+      behind { "a"; }
+      some of match {
+        possessive some of "b";
+        .quote;
+      }
+      "#
+    };
+
+    let ast = to_ast(source).unwrap();
+    let incompatibilities = check_target_compatibility(&ast, Target::Re2);
+
+    let constructs: Vec<_> = incompatibilities
+        .iter()
+        .map(|incompatibility| {
+            let span = incompatibility.span;
+            (incompatibility.construct, &source[span.start..span.end])
+        })
+        .collect();
+
+    assert_eq!(
+        constructs,
+        [
+            ("a lookbehind assertion", r#"behind { "a"; }"#),
+            ("a possessive quantifier", r#"possessive some of "b";"#),
+            ("a backreference", "same as quote"),
+        ]
+    );
+    assert!(check_target_compatibility(&ast, Target::Pcre2).is_empty());
+}
+
+#[test]
+fn grapheme_approximation_test() {
+    let options = CompilerOptions {
+        approximate_graphemes: true,
+        ..CompilerOptions::default()
+    };
+    let compile = |source, target| compile_with_options(source, target, &options);
+
+    assert_eq!(compile("<grapheme>;", Target::RustRegex).unwrap(), r"\P{M}\p{M}*");
+    assert_eq!(
+        compile(r#"3 of <grapheme>; "!";"#, Target::EcmaScript).unwrap(),
+        r"(?:\P{M}\p{M}*){3}!"
+    );
+    // targets with `\X` keep it
+    assert_eq!(compile("some of <grapheme>;", Target::Pcre2).unwrap(), r"\X+");
+    // the approximation needs unicode categories
+    let Err(CompilerError::UnsupportedByTarget { construct, .. }) = compile("<grapheme>;", Target::PythonRe) else {
+        unreachable!("expected an unsupported by target error");
+    };
+    assert_eq!(construct, "a unicode category escape");
+
+    let Err(CompilerError::UnsupportedByTarget { construct, span, .. }) =
+        compile_for(r#""a"; <grapheme>;"#, Target::Re2)
+    else {
+        unreachable!("expected an unsupported by target error");
+    };
+    assert_eq!((construct, span.start), ("a grapheme cluster", 5));
+
+    let ast = to_ast("option of <grapheme>;").unwrap();
+    assert_eq!(
+        check_target_compatibility(&ast, Target::RustRegex)[0].construct,
+        "a grapheme cluster"
+    );
+    assert!(check_target_compatibility(&ast, Target::Pcre2).is_empty());
+
+    assert!(matches!(
+        to_ast("not <grapheme>;"),
+        Err(CompilerError::NegativeGraphemeNotAllowed)
+    ));
+    // a grapheme cluster has no fixed width
+    assert!(matches!(
+        compile_for("behind { <grapheme>; }", Target::PythonRe),
+        Err(CompilerError::VariableLengthLookbehind { .. })
+    ));
+}
+
+#[test]
+fn variable_length_lookbehind_test() {
+    let fixed = r#"behind { either { "ab"; "cd"; } } 2 of <digit>;"#;
+    let variable = r#"behind { either { "a"; "bc"; } } some of <digit>;"#;
+
+    assert_eq!(compile_for(fixed, Target::PythonRe).unwrap(), r"(?<=(?:ab|cd))\d{2}");
+    assert_eq!(compile_for(variable, Target::EcmaScript).unwrap(), r"(?<=(?:a|bc))\d+");
+
+    let Err(CompilerError::VariableLengthLookbehind { target, span }) = compile_for(variable, Target::PythonRe) else {
+        unreachable!("expected a variable length lookbehind error");
+    };
+    assert_eq!(
+        (target, &variable[span.start..span.end]),
+        (Target::PythonRe, r#"behind { either { "a"; "bc"; } }"#)
+    );
+
+    let ast = to_ast(variable).unwrap();
+    assert_eq!(check_target_compatibility(&ast, Target::PythonRe).len(), 1);
+    assert!(check_target_compatibility(&to_ast(fixed).unwrap(), Target::PythonRe).is_empty());
+}
+
+#[test]
+fn lookbehind_length_test() {
+    let fixed = [
+        r#"behind { "ab"; 3 of <digit>; }"#,
+        r#"behind { either { "ab"; 2 of <word>; } <word_boundary>; }"#,
+        // nested blocks and zero-width statements count towards the length too
+        r#"behind { match { "a"; ahead { some of "b"; } } 2 to 2 of either { "c"; "d"; } }"#,
+        r#"behind { 2 of match { "a"; not ahead { "b"; } } <start>; }"#,
+    ];
+    let bounded = [
+        r#"behind { 1 to 3 of "a"; }"#,
+        r#"behind { either { "a"; "bc"; } }"#,
+        r#"behind { option of "a"; "b"; }"#,
+        "behind { <line_break>; }",
+    ];
+    let unbounded = [
+        r#"behind { some of "a"; }"#,
+        r#"behind { "a"; any of <digit>; }"#,
+        r#"behind { either { "a"; over 2 of "b"; } }"#,
+        r#"behind { 3 of match { "a"; any of "b"; } }"#,
+    ];
+
+    // targets without lookbehind at all are reported as unsupported instead
+    let cases = [
+        (Target::PythonRe, [true, false, false]),
+        (Target::Pcre2, [true, true, false]),
+        (Target::EcmaScript, [true, true, true]),
+    ];
+
+    for (target, supported) in cases {
+        for (sources, supported) in [fixed, bounded, unbounded].iter().zip(supported) {
+            for source in sources {
+                let output = compile_for(source, target);
+                if supported {
+                    assert!(output.is_ok(), "{source} on {target}");
+                } else {
+                    assert!(
+                        matches!(output, Err(CompilerError::VariableLengthLookbehind { target: error_target, .. }) if error_target == target),
+                        "{source} on {target}"
+                    );
+                }
+                let ast = to_ast(source).unwrap();
+                assert_eq!(
+                    check_target_compatibility(&ast, target).is_empty(),
+                    supported,
+                    "{source} on {target}"
+                );
+            }
+        }
+    }
+
+    let ast = to_ast(unbounded[0]).unwrap();
+    assert_eq!(
+        check_target_compatibility(&ast, Target::Pcre2)[0].construct,
+        "an unbounded lookbehind assertion"
+    );
+    assert_eq!(
+        check_target_compatibility(&ast, Target::PythonRe)[0].construct,
+        "a variable length lookbehind assertion"
+    );
+    assert!(matches!(
+        compile_for(unbounded[0], Target::Re2),
+        Err(CompilerError::UnsupportedByTarget { .. })
+    ));
+}
+
+#[test]
+fn capture_metadata_test() {
+    let source = indoc! {
+      r#"
+      let .pair = {
+        capture digit { <digit>; }
+      }
+      capture { "a"; }
+      match { .pair; }
+      either { "b"; .pair; }
+      some of capture last { "c"; }
+      "#
+    };
+
+    let options = CompilerOptions {
+        allow_duplicate_capture_names: true,
+        ..CompilerOptions::default()
+    };
+    let (output, captures, _) = compile_with_metadata(source, Target::PythonRe, &options).unwrap();
+
+    assert_eq!(output, r"(a)(?P<digit>\d)(?:b|(?P<digit>\d))(?P<last>c)+");
+
+    let captures: Vec<_> = captures
+        .iter()
+        .map(|capture| {
+            let span = capture.span;
+            (capture.index, capture.name.as_deref(), &source[span.start..span.end])
+        })
+        .collect();
+
+    assert_eq!(
+        captures,
+        [
+            (1, None, r#"capture { "a"; }"#),
+            (2, Some("digit"), "capture digit { <digit>; }"),
+            (3, Some("digit"), "capture digit { <digit>; }"),
+            (4, Some("last"), r#"capture last { "c"; }"#),
+        ]
+    );
+}
+
+#[test]
+fn negative_assertion_capture_test() {
+    let source = r#"not ahead { capture word { "a"; } } capture { "b"; }"#;
+
+    let (_, warnings) = to_ast_with_diagnostics(source).unwrap();
+    let [Warning::CaptureInNegativeAssertion {
+        assertion_span,
+        capture_span,
+    }] = warnings.as_slice()
+    else {
+        unreachable!("expected a capture in negative assertion warning");
+    };
+    assert_eq!(
+        &source[assertion_span.start..assertion_span.end],
+        r#"not ahead { capture word { "a"; } }"#
+    );
+    assert_eq!(
+        &source[capture_span.start..capture_span.end],
+        r#"capture word { "a"; }"#
+    );
+    assert_eq!(warnings[0].span(), *capture_span);
+
+    // the capture is numbered all the same, it just never holds a value
+    let (output, captures, _) = compile_with_metadata(source, Target::default(), &CompilerOptions::default()).unwrap();
+    assert_eq!(output, "(?!(?<word>a))(b)");
+    let captures: Vec<_> = captures
+        .iter()
+        .map(|capture| (capture.index, capture.in_negative_assertion))
+        .collect();
+    assert_eq!(captures, [(1, true), (2, false)]);
+
+    let options = CompilerOptions {
+        deny_captures_in_negative_assertions: true,
+        ..CompilerOptions::default()
+    };
+    assert!(matches!(
+        compile_with_options(source, Target::default(), &options),
+        Err(CompilerError::CaptureInNegativeAssertion { .. })
+    ));
+}
+
+#[test]
+fn strictness_test() {
+    let compile = |source: &str, strictness| Compiler::new().strictness(strictness).compile(source);
+
+    // likely mistakes are warnings, unless every warning is an error
+    let source = r#"let .unused = { "a"; } 1 of "b"; c to c;"#;
+    for strictness in [Strictness::Lenient, Strictness::Standard] {
+        let Ok(output) = compile(source, strictness) else {
+            unreachable!("expected `{source}` to compile at {strictness:?}");
+        };
+        assert_eq!(output.pattern, "b{1}[c-c]");
+        assert!(matches!(
+            output.warnings.as_slice(),
+            [
+                Warning::UnusedVariable { .. },
+                Warning::RedundantQuantifier { .. },
+                Warning::SingleCharacterRange { char: 'c', .. }
+            ]
+        ));
+    }
+    let Err(CompilerError::DeniedWarning(Warning::UnusedVariable { span, .. })) = compile(source, Strictness::Strict)
+    else {
+        unreachable!("expected the unused variable to be an error");
+    };
+    assert_eq!(span.column, 1);
+
+    // a capture within a negative assertion is rejected as it is with `deny_captures_in_negative_assertions`
+    let source = r#"not ahead { capture { "a"; } } "b";"#;
+    assert_eq!(compile(source, Strictness::Standard).unwrap().warnings.len(), 1);
+    assert!(matches!(
+        compile(source, Strictness::Strict),
+        Err(CompilerError::CaptureInNegativeAssertion { .. })
+    ));
+
+    // duplicate capture names are only warned about when lenient
+    let source = r#"capture x { "a"; } capture x { "b"; } capture x { "c"; }"#;
+    let Ok(output) = compile(source, Strictness::Lenient) else {
+        unreachable!("expected `{source}` to compile when lenient");
+    };
+    assert_eq!(output.pattern, "(?<x>a)(?<x>b)(?<x>c)");
+    let [Warning::DuplicateCaptureName { name, first_span, .. }, Warning::DuplicateCaptureName { second_span, .. }] =
+        output.warnings.as_slice()
+    else {
+        unreachable!("expected a warning for each repeated name");
+    };
+    assert_eq!(name, "x");
+    assert_eq!((first_span.column, second_span.column), (1, 39));
+    for strictness in [Strictness::Standard, Strictness::Strict] {
+        assert!(matches!(
+            compile(source, strictness),
+            Err(CompilerError::DuplicateCaptureName { .. })
+        ));
+    }
+
+    // a source without warnings compiles alike at every level
+    for strictness in [Strictness::Lenient, Strictness::Standard, Strictness::Strict] {
+        let output = compile(r#"some of "a";"#, strictness).unwrap();
+        assert_eq!((output.pattern.as_str(), output.warnings.len()), ("a+", 0));
+    }
+}
+
+#[test]
+fn strictness_options_test() {
+    let options = CompilerOptions {
+        strictness: Strictness::Strict,
+        ..CompilerOptions::default()
+    };
+    assert!(matches!(
+        compile_with_options("a to a;", Target::default(), &options),
+        Err(CompilerError::DeniedWarning(Warning::SingleCharacterRange { .. }))
+    ));
+
+    // a configured compiler is reused for any number of sources
+    let compiler = Compiler::new()
+        .target(Target::PythonRe)
+        .options(options)
+        .strictness(Strictness::Lenient);
+    let sources = [r#"capture x { "a"; } capture x { "b"; }"#, "a to a;"];
+    let patterns: Vec<_> = sources
+        .iter()
+        .map(|source| compiler.compile(source).unwrap().pattern)
+        .collect();
+    assert_eq!(patterns, ["(?P<x>a)(?P<x>b)", "[a-a]"]);
+}
+
+#[test]
+fn positive_assertion_capture_test() {
+    let source = r#"ahead { capture word { "a"; } } "a";"#;
+
+    let (_, warnings) = to_ast_with_diagnostics(source).unwrap();
+    assert_eq!(warnings, []);
+
+    let (_, captures, _) = compile_with_metadata(source, Target::default(), &CompilerOptions::default()).unwrap();
+    assert!(!captures[0].in_negative_assertion);
+
+    let options = CompilerOptions {
+        deny_captures_in_negative_assertions: true,
+        ..CompilerOptions::default()
+    };
+    assert_eq!(
+        compile_with_options(source, Target::default(), &options).unwrap(),
+        "(?=(?<word>a))a"
+    );
+}
+
+#[test]
+fn nested_assertion_capture_test() {
+    let sources = [
+        // the outermost negative assertion is reported
+        (
+            r#"not ahead { match { ahead { capture { "a"; } } } }"#,
+            r#"not ahead { match { ahead { capture { "a"; } } } }"#,
+        ),
+        (
+            r#"ahead { match { not behind { "b"; capture { "a"; } } } }"#,
+            r#"not behind { "b"; capture { "a"; } }"#,
+        ),
+        (
+            "let .digit = { capture { <digit>; } } not behind { .digit; }",
+            "not behind { .digit; }",
+        ),
+    ];
+
+    for (source, assertion) in sources {
+        let (_, warnings) = to_ast_with_diagnostics(source).unwrap();
+        let [Warning::CaptureInNegativeAssertion { assertion_span, .. }] = warnings.as_slice() else {
+            unreachable!("expected a capture in negative assertion warning for `{source}`");
+        };
+        assert_eq!(&source[assertion_span.start..assertion_span.end], assertion);
+
+        let (_, captures, _) = compile_with_metadata(source, Target::default(), &CompilerOptions::default()).unwrap();
+        assert!(captures[0].in_negative_assertion, "{source}");
+    }
+
+    // a capture after the assertion within the same group is unaffected
+    let (_, warnings) = to_ast_with_diagnostics(r#"match { not ahead { "a"; } capture { "b"; } }"#).unwrap();
+    assert_eq!(warnings, []);
+}
+
+#[test]
+fn duplicate_capture_name_test() {
+    let sources = [
+        (
+            r#"capture name { "a"; } capture name { "b"; }"#,
+            r#"capture name { "a"; }"#,
+            r#"capture name { "b"; }"#,
+        ),
+        (
+            r#"capture name { "a"; } match { ahead { capture name { "b"; } } }"#,
+            r#"capture name { "a"; }"#,
+            r#"capture name { "b"; }"#,
+        ),
+        (
+            r"let .digit = { capture digit { <digit>; } } .digit; some of .digit;",
+            "capture digit { <digit>; }",
+            "capture digit { <digit>; }",
+        ),
+    ];
+
+    for (source, first, second) in sources {
+        let Err(CompilerError::DuplicateCaptureName {
+            first_span,
+            second_span,
+            ..
+        }) = compiler(source)
+        else {
+            unreachable!("expected a duplicate capture name error for {source:?}");
+        };
+        assert_eq!(&source[first_span.start..first_span.end], first);
+        assert_eq!(&source[second_span.start..second_span.end], second);
+    }
+
+    let options = CompilerOptions {
+        allow_duplicate_capture_names: true,
+        ..CompilerOptions::default()
+    };
+
+    assert_eq!(
+        compile_with_options(sources[0].0, Target::Pcre2, &options).unwrap(),
+        "(?<name>a)(?<name>b)"
+    );
+}
+
+#[test]
+fn invalid_capture_name_test() {
+    let long_name = "a".repeat(33);
+    let long_source = format!(r#"capture {long_name} {{ "a"; }}"#);
+
+    let cases = [
+        (
+            r#"capture 1st { "a"; }"#,
+            Target::EcmaScript,
+            "names cannot start with a digit",
+        ),
+        (
+            r#"capture 1st { "a"; }"#,
+            Target::PythonRe,
+            "names cannot start with a digit",
+        ),
+        (
+            r#"capture first-name { "a"; }"#,
+            Target::Pcre2,
+            "names can only contain letters, digits and underscores",
+        ),
+        (
+            long_source.as_str(),
+            Target::Pcre2,
+            "names are limited to 32 characters",
+        ),
+    ];
+
+    for (source, target, expected_reason) in cases {
+        let Err(CompilerError::InvalidCaptureName { reason, .. }) = compile_for(source, target) else {
+            unreachable!("expected an invalid capture name error for {source:?} on {target}");
+        };
+        assert_eq!(reason, expected_reason);
+    }
+
+    assert_eq!(
+        compile_for(r#"capture 1st { "a"; }"#, Target::Re2).unwrap(),
+        "(?P<1st>a)"
+    );
+    assert_eq!(
+        compile_for(&long_source, Target::EcmaScript).unwrap(),
+        format!("(?<{long_name}>a)")
+    );
+}
+
+#[test]
+fn capture_name_whitespace_test() {
+    assert_eq!(
+        compiler(r#"capture   name   { "a"; } same as   name;"#).unwrap(),
+        r"(?<name>a)\k<name>"
+    );
+    assert!(matches!(
+        compiler(r#"capture na me { "a"; }"#),
+        Err(CompilerError::ParseError { .. })
+    ));
+}
+
+const FORMAT_CORPUS: &[&str] = &[
+    r#""a"; 'b"'; `c+`; "\n\t"; "a.b";"#,
+    r#"lazy some of "a"; possessive 2 to 5 of <digit>; over 3 of "b"; up to 2 of "c"; option of "d"; any of <word>;"#,
+    r#"capture name { either { "a"; "b"; match { "c"; "d"; } } } same as name; same as capture 1;"#,
+    r#"not ahead { "a"; } behind { <start>; } atomic { "b"; } insensitive not multiline { "c"; }"#,
+    "a to z; not 0 to 9; not abc; not a-z-; class a-f; not <whitespace>; <word_boundary>; not <boundary>;",
+    r#"class { "\""; char 0x7; a to f; <digit>; without { "b"; } } not class { `]`; <category::letter>; }"#,
+    "<category::uppercase_letter>; not <script::old_italic>; <property::emoji>; <input_start>; <line_end>;",
+    r#"let .digit = { <digit>; } let .pair = { .digit; "-"; .digit; } capture { .pair; } 3 of .digit;"#,
+    r#"let .quoted(quote) = { .quote; some of <word>; .quote; } .quoted("'"); some of .quoted('"');"#,
+    r#"let .a = { "x"; } .a; let .a = { "y"; } .a;"#,
+    r#"let .x = { "a"; } .x; let .x = { "b"; } .x;"#,
+    r#"let .x = { "a"; } let .y = { .x; "c"; } let .x = { "b"; } capture { .y; .x; } .x;"#,
+    r#"let .x = { "a"; } match { .x; let .x = { "b"; } .x; }"#,
+    r#"let .pair(open, close) = { .open; some of <word>; .close; } .pair("(", ")"); either { .pair("[", "]"); "-"; }"#,
+    r#"let .wrap(inner) = { "<"; .inner; ">"; } .wrap(a to z); let .wrap(inner) = { .inner; .inner; } .wrap(<digit>);"#,
+    r#"let .digit = { <digit>; } let .num(sign) = { option of .sign; some of .digit; } .num("-"); .digit;"#,
+];
+
+#[test]
+fn format_round_trip_test() {
+    for source in FORMAT_CORPUS {
+        let ast = to_ast(source).unwrap();
+        let formatted = ast_to_source(&ast, &FormatOptions::default());
+        let reformatted = ast_to_source(&to_ast(&formatted).unwrap(), &FormatOptions::default());
+
+        assert!(to_ast(&formatted).unwrap().semantically_eq(&ast), "{formatted}");
+
+        assert_eq!(
+            compile_for(&formatted, Target::Pcre2).unwrap(),
+            compile_for(source, Target::Pcre2).unwrap(),
+            "{formatted}"
+        );
+        assert_eq!(reformatted, formatted);
+    }
+}
+
+#[test]
+fn format_test() {
+    let source = r#"let .word = { some of <word>; } capture name { either { "a"; .word; } } lazy 2 of match { "b"; }"#;
+
+    let output = ast_to_source(&to_ast(source).unwrap(), &FormatOptions { indent: 4 });
+
+    assert_eq!(
+        output,
+        indoc! {
+          r#"
+          let .word = {
+              some of <word>;
+          }
+          capture name {
+              either {
+                  "a";
+                  .word;
+              }
+          }
+          lazy 2 of match {
+              "b";
+          }
+          "#
+        }
+    );
+}
+
+#[test]
+fn format_flags_test() {
+    let source = r#"flags { multiline; insensitive; } let .a(b) = { .b; } .a("c");"#;
+    let (ast, flags) = to_ast_with_flags(source).unwrap();
+
+    let formatted = ast_to_source_with_flags(&ast, &flags, &FormatOptions::default());
+
+    assert_eq!(
+        formatted,
+        indoc! {
+          r#"
+          flags {
+            multiline;
+            insensitive;
+          }
+          let .a(b) = {
+            .b;
+          }
+          .a("c");
+          "#
+        }
+    );
+    assert_eq!(to_ast_with_flags(&formatted).unwrap().1.flags, flags.flags);
+}
+
+/// A repetition of the sources the parser is benchmarked with, see `benches/benchmark.rs`
+const BENCHMARK_CORPUS: &str = r#"
+16 of "na";
+2 of match { <space>; "batman"; }
+/* 🦇🦸‍♂️ */
+let .digits_a = { some of <digit>; }
+.digits_a;
+capture group_c { 2 of "2"; }
+some of match { <space>; "batman"; }
+"4";
+let .word_a = { some of <word>; }
+capture group_a { 1 to 3 of "ab"; not ahead { <digit>; } }
+either { "a"; 'b'; `c`; a to f; 0 to 9; }
+lazy over 2 of match { .word_a; <space>; }
+class { "x"; a to z; without { "q"; } }
+not abc;
+same as group_a;
+up to 4 of char 0x41;
+let .pair_a(x) = { .x; "-"; .x; }
+.pair_a("y");
+"#;
+
+#[test]
+fn benchmark_corpus_snapshot_test() {
+    // changes to how the parser walks the parse tree must leave the AST unchanged
+    let ast = format!("{:#?}\n", to_ast(BENCHMARK_CORPUS).unwrap());
+
+    assert_eq!(ast, include_str!("snapshots/benchmark_corpus_ast.txt"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip_test() {
+    for source in FORMAT_CORPUS {
+        let ast = to_ast(source).unwrap();
+
+        let json = serde_json::to_string(&ast).unwrap();
+        let deserialized: ViableAst = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(format!("{deserialized:?}"), format!("{ast:?}"), "{source}");
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_snapshot_test() {
+    let source = r#"let .digit = { <digit>; } capture year { 4 of .digit; } lazy 1 to 3 of class { a to f; "_"; }"#;
+
+    let json = serde_json::to_string_pretty(&to_ast(source).unwrap()).unwrap();
+
+    assert_eq!(json.trim_end(), include_str!("snapshots/serde_ast.json").trim_end());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_unvalidated_test() {
+    // an identifier on a non capture group is only rejected by the parser
+    let json = r#"{
+      "type": "Root",
+      "value": [{
+        "node": {
+          "type": "Group",
+          "value": {
+            "ident": "name",
+            "kind": { "type": "Match" },
+            "statements": { "type": "Root", "value": [] }
+          }
+        },
+        "span": { "start": 0, "end": 0, "line": 1, "column": 1 }
+      }]
+    }"#;
+
+    let ast: ViableAst = serde_json::from_str(json).unwrap();
+    let nodes = root_nodes(&ast);
+
+    let ViableAstNode::Group(group) = &nodes[0].node else {
+        unreachable!("expected a group");
+    };
+    assert_eq!(group.ident.as_deref(), Some("name"));
+}
+
+#[test]
+fn ast_json_test() {
+    let source = r#"capture name { lazy some of not <digit>; } not behind { "a"; }"#;
+
+    let json: serde_json::Value = serde_json::from_str(&to_ast_json(source)).unwrap();
+    let nodes = &json["ast"];
+
+    assert_eq!(nodes[0]["kind"], "group");
+    assert_eq!(nodes[0]["group_kind"], "capture");
+    assert_eq!(nodes[0]["name"], "name");
+    assert_eq!(nodes[0]["span"]["start"], 0);
+    assert_eq!(nodes[0]["span"]["column"], 1);
+
+    let quantifier = &nodes[0]["children"][0];
+    assert_eq!(quantifier["kind"], "quantifier");
+    assert_eq!(quantifier["quantifier_kind"], "some");
+    assert_eq!(quantifier["lazy"], true);
+    assert_eq!(quantifier["possessive"], false);
+    assert_eq!(quantifier["children"][0]["kind"], "symbol");
+    assert_eq!(quantifier["children"][0]["symbol"], "digit");
+    assert_eq!(quantifier["children"][0]["negative"], true);
+
+    assert_eq!(nodes[1]["kind"], "assertion");
+    assert_eq!(nodes[1]["assertion_kind"], "behind");
+    assert_eq!(nodes[1]["negative"], true);
+    assert_eq!(nodes[1]["children"][0]["value"], "a");
+
+    assert!(json.get("error").is_none());
+}
+
+#[test]
+fn ast_json_error_test() {
+    let json: serde_json::Value = serde_json::from_str(&to_ast_json("some of )")).unwrap();
+    let error = &json["error"];
+
+    assert_eq!(error["line"], 1);
+    assert_eq!(error["column"], 9);
+    assert_eq!(error["offset"], 8);
+    assert_eq!(error["found"], ")");
+    assert!(error["expected"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("quantified_block")));
+    assert!(error["message"].as_str().unwrap().starts_with("expected "));
+    assert!(json.get("ast").is_none());
+}
+
+struct AtomCollector {
+    atoms: Vec<String>,
+    invocation_statements: bool,
+}
+
+impl Visitor for AtomCollector {
+    fn visit_invocation_statements(&self) -> bool {
+        self.invocation_statements
+    }
+
+    fn visit_atom(&mut self, atom: &str, _span: Span) {
+        self.atoms.push(atom.to_owned());
+    }
+}
+
+#[test]
+fn visitor_test() {
+    let source = r#"
+        let .word = { "w"; }
+        "a";
+        capture { either { "b"; 2 of "c"; } }
+        not ahead { "d"; behind { "e"; } }
+        some of match { "f"; .word; }
+        .word;
+    "#;
+    let ast = to_ast(source).unwrap();
+
+    let mut collector = AtomCollector {
+        atoms: Vec::new(),
+        invocation_statements: true,
+    };
+    walk(&ast, &mut collector);
+    assert_eq!(collector.atoms, ["a", "b", "c", "d", "e", "f", "w", "w"]);
+
+    let mut collector = AtomCollector {
+        atoms: Vec::new(),
+        invocation_statements: false,
+    };
+    walk(&ast, &mut collector);
+    assert_eq!(collector.atoms, ["a", "b", "c", "d", "e", "f"]);
+}
+
+struct Uppercase;
+
+impl Transform for Uppercase {
+    fn transform_atom(&mut self, atom: Rc<str>, _span: Span) -> Rc<str> {
+        atom.to_uppercase().into()
+    }
+}
+
+/// Splits atoms into one statement per character and removes symbols
+struct SplitAtoms;
+
+impl Transform for SplitAtoms {
+    fn transform_node(&mut self, node: Spanned<ViableAstNode>) -> Vec<Spanned<ViableAstNode>> {
+        match node.node {
+            ViableAstNode::Atom(atom) => atom
+                .chars()
+                .map(|char| Spanned {
+                    node: ViableAstNode::Atom(char.to_string().into()),
+                    span: node.span,
+                })
+                .collect(),
+            ViableAstNode::Symbol(_) => Vec::new(),
+            _ => vec![fold_node(self, node)],
+        }
+    }
+}
+
+#[test]
+fn transform_test() {
+    let source = r#"capture { "ab"; } not ahead { "c"; } 2 of "d"; some of match { "e"; <digit>; }"#;
+
+    let ast = fold(to_ast(source).unwrap(), &mut Uppercase);
+    assert_eq!(
+        compile_for(&ast_to_source(&ast, &FormatOptions::default()), Target::Pcre2).unwrap(),
+        "(AB)(?!C)D{2}(?:E\\d)+"
+    );
+
+    let ast = fold(to_ast(source).unwrap(), &mut SplitAtoms);
+    let ViableAstNode::Group(group) = &root_nodes(&ast)[0].node else {
+        unreachable!("expected a group");
+    };
+    assert_eq!(root_nodes(&group.statements).len(), 2);
+    assert_eq!(
+        ast_to_source(&ast, &FormatOptions::default()),
+        indoc! {
+          r#"
+          capture {
+            "a";
+            "b";
+          }
+          not ahead {
+            "c";
+          }
+          2 of "d";
+          some of match {
+            "e";
+          }
+          "#
+        }
+    );
+}
+
+/// Every kind of error with its code, codes are part of the public interface and must never change
+#[allow(clippy::too_many_lines)]
+fn error_codes() -> Vec<(CompilerError, &'static str)> {
+    let span = Span::default();
+
+    vec![
+        (
+            CompilerError::ArityMismatch {
+                name: String::new(),
+                expected: 0,
+                found: 0,
+            },
+            "E0001",
+        ),
+        (CompilerError::CodePointOutOfRange(String::new()), "E0002"),
+        (CompilerError::CouldNotParseAnAmount(String::new()), "E0003"),
+        (
+            CompilerError::DuplicateCaptureName {
+                name: String::new(),
+                first_span: span,
+                second_span: span,
+            },
+            "E0004",
+        ),
+        (CompilerError::DuplicateFlag(String::new()), "E0005"),
+        (CompilerError::DuplicateParameter(String::new()), "E0006"),
+        (CompilerError::EmptyAlternation, "E0007"),
+        (CompilerError::EmptyQuantifiedBlock, "E0008"),
+        (
+            CompilerError::EmptyRange {
+                start: 'b',
+                end: 'a',
+                span,
+            },
+            "E0009",
+        ),
+        (CompilerError::ImportCycle { chain: Vec::new() }, "E0010"),
+        (
+            CompilerError::ImportError {
+                path: String::new(),
+                error: Box::new(CompilerError::MissingNode {
+                    context: "quantifier",
+                    span,
+                }),
+            },
+            "E0011",
+        ),
+        (CompilerError::ImportNotFound(String::new()), "E0012"),
+        (
+            CompilerError::InvalidBackreferenceIndex {
+                index: 1,
+                group_count: 0,
+            },
+            "E0013",
+        ),
+        (
+            CompilerError::InvalidCaptureName {
+                name: String::new(),
+                target: Target::EcmaScript,
+                reason: "",
+            },
+            "E0014",
+        ),
+        (CompilerError::InvalidHexEscape(String::new()), "E0015"),
+        (CompilerError::InvalidQuantifierRange(String::new()), "E0016"),
+        (CompilerError::LazyPossessiveQuantifier, "E0017"),
+        (CompilerError::MisplacedFlagsBlock, "E0018"),
+        (CompilerError::MissingImportResolver(String::new()), "E0019"),
+        (
+            CompilerError::MissingNode {
+                context: "quantifier",
+                span,
+            },
+            "E0020",
+        ),
+        (CompilerError::MissingRootNode, "E0021"),
+        (CompilerError::NegativeCharNotAllowed, "E0022"),
+        (CompilerError::NegativeEndNotAllowed(String::from("end")), "E0023"),
+        (CompilerError::NegativeStartNotAllowed(String::from("start")), "E0024"),
+        (
+            CompilerError::ParseError {
+                expected: Vec::new(),
+                found: String::new(),
+                line: 1,
+                column: 1,
+                offset: 0,
+                suggestion: None,
+            },
+            "E0025",
+        ),
+        (
+            CompilerError::RecursiveVariable {
+                name: String::new(),
+                cycle: Vec::new(),
+                spans: Vec::new(),
+            },
+            "E0026",
+        ),
+        (CompilerError::SurrogateCodePoint(String::new()), "E0027"),
+        (CompilerError::UnexpectedAssertionInQuantifier, "E0028"),
+        (CompilerError::UnexpectedBoundaryInQuantifier, "E0029"),
+        (CompilerError::UnexpectedIdentifierForNonCaptureGroup, "E0030"),
+        (CompilerError::UnexpectedMultiCharacterAtomInClass, "E0031"),
+        (CompilerError::UnexpectedNegativeRangeInClass, "E0032"),
+        (CompilerError::UnexpectedQuantifierInQuantifier, "E0033"),
+        (CompilerError::UnexpectedSkippedNodeInQuantifier, "E0034"),
+        (CompilerError::UnexpectedSpecialSymbolInQuantifier, "E0035"),
+        (CompilerError::UnexpectedSymbolInClass, "E0036"),
+        (CompilerError::UnexpectedVariableDeclarationInQuantifier, "E0037"),
+        (
+            CompilerError::UninitializedVariable {
+                name: String::new(),
+                suggestion: None,
+            },
+            "E0038",
+        ),
+        (CompilerError::UnknownBackreference(String::new()), "E0039"),
+        (CompilerError::UnknownFlag(String::new()), "E0040"),
+        (CompilerError::UnknownParameter(String::new()), "E0041"),
+        (
+            CompilerError::UnknownUnicodeProperty {
+                name: String::new(),
+                suggestion: None,
+            },
+            "E0042",
+        ),
+        (CompilerError::UnrecognizedAssertion, "E0043"),
+        (CompilerError::UnrecognizedGroup, "E0044"),
+        (CompilerError::UnrecognizedSyntax, "E0045"),
+        (
+            CompilerError::UnrecognizedSymbol {
+                name: String::new(),
+                suggestion: None,
+            },
+            "E0046",
+        ),
+        (CompilerError::UnrecognizedSymbolNamespace, "E0047"),
+        (CompilerError::UnrecognizedUnicodeCategory, "E0048"),
+        (
+            CompilerError::UnsupportedByTarget {
+                construct: "",
+                target: Target::EcmaScript,
+                span,
+            },
+            "E0049",
+        ),
+        (CompilerError::UpToZero, "E0050"),
+        (CompilerError::NestingTooDeep { limit: 0 }, "E0051"),
+        (CompilerError::ZeroQuantifier, "E0052"),
+        (
+            CompilerError::TargetRejectedOutput {
+                message: String::new(),
+                emitted: String::new(),
+            },
+            "E0053",
+        ),
+        (CompilerError::TestCapturesWithoutMatch, "E0054"),
+        (CompilerError::PatternTooLarge { estimated: 2, limit: 1 }, "E0055"),
+        (CompilerError::NegativeGraphemeNotAllowed, "E0056"),
+        (CompilerError::NegativeLineBreakNotAllowed, "E0057"),
+        (
+            CompilerError::DuplicateSymbol {
+                name: String::from("a"),
+                first_span: Span::default(),
+                second_span: Span::default(),
+            },
+            "E0058",
+        ),
+        (CompilerError::NegativeCustomSymbol(String::from("a")), "E0059"),
+        (
+            CompilerError::VariableLengthLookbehind {
+                span,
+                target: Target::PythonRe,
+            },
+            "E0060",
+        ),
+        (
+            CompilerError::CaptureInNegativeAssertion {
+                assertion_span: span,
+                capture_span: span,
+            },
+            "E0061",
+        ),
+        (CompilerError::NothingOutsideEither, "E0062"),
+        (CompilerError::DeniedWarning(Warning::EmptyCapture { span }), "E0063"),
+        (CompilerError::UnbalancedRawRegex { unbalanced: '(', span }, "E0064"),
+        (CompilerError::UnknownConditionalCapture(String::from("a")), "E0065"),
+        (CompilerError::UnknownRecursionGroup(String::from("a")), "E0066"),
+        (CompilerError::NonAsciiInBytesMode { span }, "E0067"),
+        (CompilerError::MissingStatementTerminator { span }, "E0068"),
+        (CompilerError::RepeatedQuantifierModifier(String::from("lazy")), "E0069"),
+    ]
+}
+
+#[test]
+fn error_code_test() {
+    let error_codes = error_codes();
+    let mut codes = std::collections::HashSet::new();
+
+    for (error, code) in &error_codes {
+        assert_eq!(error.code(), *code, "{error:?}");
+        assert!(codes.insert(code), "{code} is used more than once");
+
+        if !matches!(error, CompilerError::ImportError { .. }) {
+            assert!(error.to_string().ends_with(&format!(" [{code}]")), "{error}");
+        }
+    }
+}
+
+#[test]
+fn error_help_test() {
+    let error = to_ast(r#"match name { "a"; }"#).unwrap_err();
+
+    assert_eq!(error.code(), "E0030");
+    assert_eq!(
+        error.help().as_deref(),
+        Some("only `capture` groups may be named; remove the identifier or change `match` to `capture`")
+    );
+
+    let error = to_ast("z to a;").unwrap_err();
+    assert_eq!(
+        error.help().as_deref(),
+        Some("swap the bounds of the range, i.e. `a to z`")
+    );
+
+    let error = to_ast("some of )").unwrap_err();
+    assert!(error.help().is_none());
+}
+
+#[cfg(feature = "diagnostics")]
+#[test]
+fn render_error_test() {
+    use viable_compiler::diagnostics::render_error;
+
+    let source = r#"capture year { "a"; } capture year { "b"; }"#;
+    let error = compile_for(source, Target::EcmaScript).unwrap_err();
+    assert_eq!(
+        render_error(source, &error),
+        indoc! {
+          r#"
+          error[E0004]: the capture group name `year` is used more than once
+           --> 1:23
+            |
+          1 | capture year { "a"; } capture year { "b"; }
+            |                       ^^^^^^^^^^^^^^^^^^^^^ used again here
+          1 | capture year { "a"; } capture year { "b"; }
+            | --------------------- first used here
+            = help: rename one of the capture groups
+          "#
+        }
+    );
+
+    let source = indoc! {
+      r#"
+      let .a = {
+        .b;
+      }
+      let .b = {
+        "x";
+        .a;
+      }
+      "#
+    };
+    let error = to_ast(source).unwrap_err();
+    assert_eq!(
+        render_error(source, &error),
+        indoc! {
+          "
+          error[E0026]: recursive variable `b` (a -> b -> a)
+           --> 2:3
+            |
+          2 |   .b;
+            |   ^^ `b` is invoked here
+          6 |   .a;
+            |   -- `a` is invoked here
+            = help: variables cannot invoke themselves, break the cycle
+          "
+        }
+    );
+
+    let source = r#"some of "a" "b";"#;
+    let error = to_ast(source).unwrap_err();
+    assert_eq!(
+        render_error(source, &error),
+        indoc! {
+          r#"
+          error[E0068]: missing `;` at the end of a statement
+           --> 1:12
+            |
+          1 | some of "a" "b";
+            |            ^
+            = help: add `;` at the end of the statement
+          "#
+        }
+    );
+}
+
+#[test]
+fn lenient_ast_test() {
+    let source = r#""a"; up to 0 of "b"; "c"; capture { "d"; 3 to 1 of "e"; "f"; }"#;
+
+    let (ast, errors) = to_ast_lenient(source);
+    let nodes = root_nodes(&ast);
+
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(errors[0].node, CompilerError::UpToZero));
+    assert!(matches!(&errors[1].node, CompilerError::InvalidQuantifierRange(range) if range == "3 to 1"));
+
+    assert!(matches!(&nodes[0].node, ViableAstNode::Atom(atom) if &**atom == "a"));
+    assert!(matches!(nodes[1].node, ViableAstNode::Error));
+    assert_eq!(&source[nodes[1].span.start..nodes[1].span.end], r#"up to 0 of "b";"#);
+    assert!(matches!(&nodes[2].node, ViableAstNode::Atom(atom) if &**atom == "c"));
+
+    // the error only replaces the failing statement of the group
+    let ViableAstNode::Group(group) = &nodes[3].node else {
+        unreachable!("expected a group");
+    };
+    let statements = root_nodes(&group.statements);
+    assert!(matches!(&statements[0].node, ViableAstNode::Atom(atom) if &**atom == "d"));
+    assert!(matches!(statements[1].node, ViableAstNode::Error));
+    assert!(matches!(&statements[2].node, ViableAstNode::Atom(atom) if &**atom == "f"));
+
+    let (ast, errors) = to_ast_lenient("some of )");
+    assert!(matches!(ast, ViableAst::Empty));
+    assert!(matches!(
+        errors[..],
+        [Spanned {
+            node: CompilerError::ParseError { .. },
+            ..
+        }]
+    ));
+
+    assert!(to_ast(source).is_err());
+}
+
+#[test]
+fn incremental_parser_test() {
+    let edits = [
+        ("let .a = { \"a\"; }\n.a;\n\"b\";\nsome of \"c\";", 4),
+        // only the edited statement is converted again
+        ("let .a = { \"a\"; }\n.a;\n\"bb\";\nsome of \"c\";", 1),
+        // statements after the edit have their spans moved
+        ("let .a = { \"a\"; }\n.a;\n\"x\";\n\"bb\";\nsome of \"c\";", 2),
+        // invocations of an edited declaration are converted again
+        ("let .a = { \"z\"; }\n.a;\n\"x\";\n\"bb\";\nsome of \"c\";", 2),
+        (
+            "let .a = { \"z\"; }\n.a;\nlet .a = { \"y\"; }\n\"bb\";\nsome of \"c\";",
+            1,
+        ),
+        ("let .a = { \"z\"; }\n.a;\nlet .a = { \"y\"; }\n\"bb\";\n.a;", 1),
+        // removing a declaration converts the statements invoking it again
+        ("let .a = { \"z\"; }\n.a;\n\"bb\";\n.a;", 1),
+        // a comment can run on into the statements after the edit
+        ("let .a = { \"z\"; }\n.a;\n\"bb\"; // .a;\n\"c\";", 1),
+        ("let .a = { \"z\"; }\n.a;\n\"bb\"; /* .a;\n\"c\";", 0),
+        ("let .a = { \"z\"; }\n.a;\n\"bb\"; /* .a; */\n\"c\";", 0),
+        ("🦇\n\"🦇\";\n.a;", 0),
+        ("", 0),
+        (r#"let .a = { "a"; } .a;"#, 2),
+    ];
+
+    let mut parser = IncrementalParser::new();
+
+    for (source, converted) in edits {
+        let result = parser.update(source).map(|ast| format!("{ast:?}"));
+
+        match to_ast_with_diagnostics(source) {
+            Ok((ast, warnings)) => {
+                assert_eq!(result.ok(), Some(format!("{ast:?}")), "{source}");
+                assert_eq!(parser.warnings(), warnings, "{source}");
+                assert_eq!(parser.converted(), converted, "{source}");
+            }
+            Err(error) => {
+                let Err(incremental_error) = result else {
+                    unreachable!("expected `{source}` to fail");
+                };
+                assert_eq!(incremental_error.to_string(), error.to_string());
+            }
+        }
+    }
+
+    // every source after another, and each with a statement appended
+    for source in FORMAT_CORPUS {
+        for source in [(*source).to_owned(), format!("{source}\n\"end\";")] {
+            let Ok(ast) = parser.update(&source) else {
+                unreachable!("expected `{source}` to convert");
+            };
+            let Ok(expected) = to_ast(&source) else {
+                unreachable!("expected `{source}` to convert");
+            };
+            assert_eq!(format!("{ast:?}"), format!("{expected:?}"), "{source}");
+        }
+    }
+}
+
+#[test]
+fn incremental_parser_unused_variable_test() {
+    let mut parser = IncrementalParser::new();
+
+    let Ok(_) = parser.update(r#"let .a = { "a"; } .a; .a;"#) else {
+        unreachable!("expected the source to convert");
+    };
+    assert!(parser.warnings().is_empty());
+
+    // the reused invocation still uses the declaration
+    let Ok(_) = parser.update(r#"let .a = { "a"; } "b"; .a;"#) else {
+        unreachable!("expected the source to convert");
+    };
+    assert!(parser.warnings().is_empty());
+    assert_eq!(parser.converted(), 1);
+
+    let Ok(_) = parser.update(r#"let .a = { "a"; } "b"; "c";"#) else {
+        unreachable!("expected the source to convert");
+    };
+    assert!(matches!(
+        parser.warnings(),
+        [Warning::UnusedVariable { name, .. }] if name == "a"
+    ));
+}
+
+#[test]
+fn nesting_limit_test() {
+    let limit = DEFAULT_NESTING_LIMIT;
+    let nested = |open: &str, count: usize| format!("{}\"a\";{}", open.repeat(count), "}".repeat(count));
+
+    for source in [
+        nested("match { ", 50_000),
+        nested("2 of { ", 50_000),
+        nested("ahead { ", limit + 1),
+        format!("{}\"a\";", "/* ".repeat(50_000)),
+    ] {
+        let Err(CompilerError::NestingTooDeep { limit: 256 }) = compiler(&source) else {
+            unreachable!("expected a nesting error for {:?}", &source[..20]);
+        };
+    }
+
+    assert!(to_ast_with_nesting_limit(&nested("match { ", 64), 64).is_ok());
+    assert!(to_ast_with_nesting_limit(&nested("match { ", 65), 64).is_err());
+    // braces within literals and comments don't nest anything
+    assert!(compiler(&format!("\"{}\"; // {}", "{".repeat(50_000), "{".repeat(50_000))).is_ok());
+
+    let Err(errors) = to_ast_all_errors(&nested("match {\n", 300)) else {
+        unreachable!("expected a nesting error");
+    };
+    assert_eq!(errors[0].span.start, 256 * 8 + 6);
+    assert_eq!((errors[0].span.line, errors[0].span.column), (257, 7));
+
+    let source = r#"match { match { "a"; } }"#;
+    assert!(to_ast_with_nesting_limit(source, 2).is_ok());
+    assert!(matches!(
+        to_ast_with_nesting_limit(source, 1),
+        Err(CompilerError::NestingTooDeep { limit: 1 })
+    ));
+
+    let options = CompilerOptions {
+        nesting_limit: 1,
+        ..CompilerOptions::default()
+    };
+    assert!(compile_with_options(source, Target::default(), &options).is_err());
+}
+
+#[test]
+fn nesting_limit_variable_test() {
+    // the invocation nests the statements of the variable within the block it is invoked in
+    let source = r#"let .a = { match { "a"; } } match { .a; }"#;
+    assert!(to_ast_with_nesting_limit(source, 3).is_ok());
+    assert!(matches!(
+        to_ast_with_nesting_limit(source, 2),
+        Err(CompilerError::NestingTooDeep { limit: 2 })
+    ));
+
+    // identifiers can't contain digits, so the index is spelled with a letter for each digit
+    let name = |index: usize| -> String {
+        index
+            .to_string()
+            .chars()
+            .filter_map(|digit| char::from_digit(digit.to_digit(10)? + 10, 36))
+            .collect()
+    };
+    let mut source = String::from("let .start = { \"a\"; }\n");
+    for index in 0..300 {
+        let invoked = if index == 0 {
+            String::from("start")
+        } else {
+            name(index - 1)
+        };
+        source.push_str(&format!("let .{} = {{ .{invoked}; }}\n", name(index)));
+    }
+    source.push_str(&format!(".{};", name(299)));
+
+    let Err(errors) = to_ast_all_errors(&source) else {
+        unreachable!("expected a nesting error");
+    };
+    assert!(errors
+        .iter()
+        .all(|error| matches!(error.node, CompilerError::NestingTooDeep { limit: 256 })));
+}
+
+#[test]
+fn shared_invocation_statements_test() {
+    let ast = to_ast(r#"let .a = { some of "a"; capture { "b"; } } .a; .a; match { .a; }"#).unwrap();
+
+    let [first, second, third] = root_nodes(&ast) else {
+        unreachable!("expected three statements");
+    };
+    let (ViableAstNode::VariableInvocation(first), ViableAstNode::VariableInvocation(second)) =
+        (&first.node, &second.node)
+    else {
+        unreachable!("expected invocations");
+    };
+    assert!(Rc::ptr_eq(&first.statements, &second.statements));
+
+    let ViableAstNode::Group(group) = &third.node else {
+        unreachable!("expected a group");
+    };
+    let [Spanned {
+        node: ViableAstNode::VariableInvocation(nested),
+        ..
+    }] = root_nodes(&group.statements)
+    else {
+        unreachable!("expected an invocation");
+    };
+    assert!(Rc::ptr_eq(&first.statements, &nested.statements));
+
+    // each invocation is still an expansion of its own
+    assert_eq!(
+        compiler(r#"let .a = { some of "a"; capture { "b"; } } .a; .a; same as capture 2;"#).unwrap(),
+        r"a+(b)a+(b)\2"
+    );
+
+    // parameterized invocations get statements of their own
+    let ast = to_ast(r#"let .a(x) = { .x; } .a("b"); .a("b");"#).unwrap();
+    let [Spanned {
+        node: ViableAstNode::VariableInvocation(first),
+        ..
+    }, Spanned {
+        node: ViableAstNode::VariableInvocation(second),
+        ..
+    }] = root_nodes(&ast)
+    else {
+        unreachable!("expected two invocations");
+    };
+    assert!(!Rc::ptr_eq(&first.statements, &second.statements));
+}
+
+#[test]
+fn interned_strings_test() {
+    let ast = to_ast(r#""if"; 'if'; `if`; "i\x66"; "a.b"; "a.b";"#).unwrap();
+
+    let atoms: Vec<&Rc<str>> = root_nodes(&ast)
+        .iter()
+        .filter_map(|node| match &node.node {
+            ViableAstNode::Atom(atom) => Some(atom),
+            _ => None,
+        })
+        .collect();
+    let [first, second, third, escaped, dot, second_dot] = atoms[..] else {
+        unreachable!("expected six atoms");
+    };
+
+    // the same text is allocated once whichever way it is written
+    assert!(Rc::ptr_eq(first, second));
+    assert!(Rc::ptr_eq(first, third));
+    assert!(Rc::ptr_eq(first, escaped));
+    assert!(Rc::ptr_eq(dot, second_dot));
+    assert_eq!(&**dot, r"a\.b");
+
+    assert_eq!(
+        compiler(r#"capture word { "if"; } same as word; "if";"#).unwrap(),
+        r"(?<word>if)\k<word>if"
+    );
+}
+
+#[test]
+fn skipped_nodes_test() {
+    let ast = to_ast(r#"let .x = { "a"; }"#).unwrap();
+    assert!(root_nodes(&ast).is_empty());
+    assert_eq!(format!("{ast:?}"), format!("{:?}", ViableAst::Root(Vec::new())));
+
+    // declarations inside blocks leave nothing behind either
+    let ast = to_ast(r#"capture { let .x = { "a"; } .x; } ahead { let .y = { "b"; } }"#).unwrap();
+    let [Spanned {
+        node: ViableAstNode::Group(group),
+        ..
+    }, Spanned {
+        node: ViableAstNode::Assertion(assertion),
+        ..
+    }] = root_nodes(&ast)
+    else {
+        unreachable!("expected a group and an assertion");
+    };
+    assert_eq!(root_nodes(&group.statements).len(), 1);
+    assert!(root_nodes(&assertion.statements).is_empty());
+
+    let mut parser = IncrementalParser::new();
+    let Ok(ast) = parser.update(r#"let .x = { "a"; }"#) else {
+        unreachable!("expected the source to convert");
+    };
+    assert!(root_nodes(ast).is_empty());
+}
+
+#[test]
+fn ast_equality_test() {
+    let source = r#"let .digits = { some of <digit>; } capture { .digits; } 2 to 4 of "a";"#;
+
+    assert_eq!(to_ast(source).unwrap(), to_ast(source).unwrap());
+    assert_ne!(to_ast(source).unwrap(), to_ast("capture { some of <digit>; }").unwrap());
+
+    let asts: HashSet<ViableAst> = [source, source, r#""a";"#].map(|source| to_ast(source).unwrap()).into();
+    assert_eq!(asts.len(), 2);
+}
+
+#[test]
+fn semantically_eq_test() {
+    let first = to_ast(indoc! {
+        r#"
+        let .digits = { some of <digit>; }
+        "a";
+        "b";
+        .digits;
+        3 to 3 of "c";
+        either { "x"; "y"; }
+        "#
+    })
+    .unwrap();
+    let second = to_ast(indoc! {
+        r#"
+        // the same pattern, written differently
+        'ab';
+        let .digits = {
+          some of <digit>;
+        }
+        .digits;
+        3 of `c`;
+        either { 'x'; "y"; }
+        "#
+    })
+    .unwrap();
+
+    assert_ne!(first, second);
+    assert!(first.semantically_eq(&second));
+    assert!(second.semantically_eq(&first));
+
+    // skipped nodes, and the difference between an empty root and an empty AST, are ignored
+    let skipped = ViableAst::from_nodes(vec![
+        ViableAstNode::Atom("a".into()),
+        ViableAstNode::Skip,
+        ViableAstNode::Atom("b".into()),
+    ]);
+    assert!(skipped.semantically_eq(&to_ast(r#""ab";"#).unwrap()));
+    assert!(ViableAst::Empty.semantically_eq(&to_ast(r#"let .a = { "a"; }"#).unwrap()));
+
+    // the branches of an `either` group are not merged
+    assert!(!to_ast(r#""a"; "b";"#)
+        .unwrap()
+        .semantically_eq(&to_ast(r#"either { "a"; "b"; }"#).unwrap()));
+    assert!(!to_ast(r#"2 to 3 of "a";"#)
+        .unwrap()
+        .semantically_eq(&to_ast(r#"2 of "a";"#).unwrap()));
+}
+
+#[test]
+fn target_names_test() {
+    for target in Target::ALL {
+        assert_eq!(Target::from_name(target.name()), Some(target));
+    }
+
+    assert_eq!(Target::from_name("ECMAScript"), Some(Target::EcmaScript));
+    assert_eq!(Target::from_name("JS"), Some(Target::EcmaScript));
+    assert_eq!(Target::from_name("pcre"), Some(Target::Pcre2));
+    assert_eq!(Target::from_name("perl"), None);
+}
+
+#[test]
+fn error_span_test() {
+    let error = to_ast(r#""a"; z to a;"#).unwrap_err();
+    assert_eq!(error.span().map(|span| (span.start, span.end)), Some((5, 11)));
+
+    let error = to_ast("some of )").unwrap_err();
+    assert_eq!(error.span().map(|span| (span.line, span.column)), Some((1, 9)));
+
+    assert!(CompilerError::EmptyAlternation.span().is_none());
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn compile_to_regex_test() {
+    let cases = [
+        (
+            r#"capture year { 4 of <digit>; } "-"; capture month { 2 of <digit>; }"#,
+            "2024-11",
+            true,
+        ),
+        (r#"<start>; 2 to 3 of "ab"; <end>;"#, "ababab", true),
+        (r#"<start>; 2 to 3 of "ab"; <end>;"#, "ab", false),
+        ("<start>; lazy over 1 of <word>; <end>;", "ab", true),
+        (r#"<start>; some of class { a to f; "_"; } <end>;"#, "a_f", true),
+        (r#"<start>; some of class { a to f; "_"; } <end>;"#, "ag", false),
+        ("<start>; not <digit>; <end>;", "a", true),
+        (r#"<word_boundary>; "cat"; <word_boundary>;"#, "a cat!", true),
+        (r#"<word_boundary>; "cat"; <word_boundary>;"#, "concatenate", false),
+        (r#"either { "a"; "bc"; } option of "d";"#, "bcd", true),
+        (r#"<start>; either { "a"; nothing; } <end>;"#, "", true),
+        (r#"<start>; either { "a"; nothing; } <end>;"#, "b", false),
+        ("<start>; <category::uppercase_letter>; <end>;", "Å", true),
+        (r#"flags { insensitive; } "viable";"#, "VIABLE", true),
+    ];
+
+    for (source, input, is_match) in cases {
+        let Ok(regex) = compile_to_regex(source) else {
+            unreachable!("expected `{source}` to compile");
+        };
+        assert_eq!(regex.is_match(input), is_match, "{source} on {input}");
+    }
+
+    let Ok(regex) = compile_to_regex(r#"capture year { 4 of <digit>; } "-"; capture month { 2 of <digit>; }"#) else {
+        unreachable!("expected the source to compile");
+    };
+    let Some(captures) = regex.captures("on 2024-11") else {
+        unreachable!("expected the regex to match");
+    };
+    assert_eq!(&captures["year"], "2024");
+    assert_eq!(&captures["month"], "11");
+
+    // constructs the regex crate doesn't support are rejected when compiling
+    assert!(matches!(
+        compile_to_regex(r#"ahead { "a"; }"#),
+        Err(CompilerError::UnsupportedByTarget { .. })
+    ));
+
+    let Err(CompilerError::TargetRejectedOutput { emitted, .. }) =
+        compile_to_regex("1000 of match { 1000 of <word>; }")
+    else {
+        unreachable!("expected the pattern to exceed the size limit");
+    };
+    assert_eq!(emitted, r"(?:\w{1000}){1000}");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn literal_escape_property_test() {
+    const PUNCTUATION: &[u8] = b"!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+    // a fixed seed keeps failures reproducible without a randomness dependency
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for _ in 0..500 {
+        let length = usize::try_from(next() % 12).unwrap_or_default() + 1;
+        let text: String = (0..length)
+            .map(|_| char::from(PUNCTUATION[usize::try_from(next()).unwrap_or_default() % PUNCTUATION.len()]))
+            .collect();
+        let quoted = text.replace('\\', r"\\").replace('"', "\\\"");
+
+        let source = format!(r#"<start>; "{quoted}"; <end>;"#);
+        let Ok(regex) = compile_to_regex(&source) else {
+            unreachable!("expected `{source}` to compile");
+        };
+        assert!(regex.is_match(&text), "{source} on {text}");
+        assert!(!regex.is_match(&format!("{text}a")), "{source} on {text}a");
+
+        // single characters in an `either` become a class, exercising class escaping
+        let branches: String = text
+            .chars()
+            .map(|char| match char {
+                '\\' | '"' => format!(r#""\{char}";"#),
+                char => format!(r#""{char}";"#),
+            })
+            .collect();
+        let source = format!("<start>; some of either {{ {branches} }} <end>;");
+        let Ok(regex) = compile_to_regex(&source) else {
+            unreachable!("expected `{source}` to compile");
+        };
+        assert!(regex.is_match(&text), "{source} on {text}");
+        assert!(!regex.is_match("a"), "{source} on a");
+
+        // the `u` flag rejects escapes of characters that aren't syntax characters
+        let Ok(pattern) = compiler(&source) else {
+            unreachable!("expected `{source}` to compile");
+        };
+        let Ok(regex) = regress::Regex::with_flags(&pattern, "u") else {
+            unreachable!("expected `{pattern}` to be a valid ECMAScript pattern");
+        };
+        assert!(
+            regex.find(&text).is_some_and(|found| found.range() == (0..text.len())),
+            "{pattern} on {text}"
+        );
+    }
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn compile_to_regex_builder_test() {
+    let Ok(mut builder) = compile_to_regex_builder(r#"flags { insensitive; } <start>; "a"; <end>;"#) else {
+        unreachable!("expected the source to compile");
+    };
+    let Ok(regex) = builder.build() else {
+        unreachable!("expected the regex to build");
+    };
+    assert!(regex.is_match("A"));
+    assert_eq!(regex.as_str(), "^a$");
+
+    // the flags of the source can be overridden
+    let Ok(regex) = builder.case_insensitive(false).build() else {
+        unreachable!("expected the regex to build");
+    };
+    assert!(!regex.is_match("A"));
+
+    let Ok(mut builder) = compile_to_regex_builder("1000 of match { 1000 of <word>; }") else {
+        unreachable!("expected the source to compile");
+    };
+    assert!(builder.size_limit(1 << 10).build().is_err());
+}
+
+#[test]
+fn tests_block_test() {
+    let source = indoc! {r#"
+        capture year { 4 of <digit>; }
+        tests {
+            match "2024" captures { year = "2024", };
+            no match "\"24\"";
+        }
+    "#};
+
+    let Ok((ast, tests)) = to_ast_with_tests(source) else {
+        unreachable!("expected the source to parse");
+    };
+    assert_eq!(
+        ast,
+        to_ast("capture year { 4 of <digit>; }").unwrap_or(ViableAst::Empty)
+    );
+    assert_eq!(
+        tests,
+        vec![
+            EmbeddedTest {
+                expectation: Expectation::Match,
+                input: "2024".to_owned(),
+                captures: vec![("year".to_owned(), "2024".to_owned())],
+                span: Span {
+                    start: 43,
+                    end: 84,
+                    line: 3,
+                    column: 5,
+                },
+            },
+            EmbeddedTest {
+                expectation: Expectation::NoMatch,
+                input: "\"24\"".to_owned(),
+                captures: Vec::new(),
+                span: Span {
+                    start: 89,
+                    end: 107,
+                    line: 4,
+                    column: 5,
+                },
+            },
+        ]
+    );
+
+    // the tests are ignored by codegen
+    assert_eq!(compiler(source).unwrap(), r"(?<year>\d{4})");
+    assert_eq!(compiler(r#"tests { match "a"; }"#).unwrap(), "");
+
+    assert!(matches!(
+        to_ast_with_tests(r#""a"; tests { no match "b" captures { x = "b"; } }"#),
+        Err(CompilerError::TestCapturesWithoutMatch)
+    ));
+    // only allowed at the top level
+    assert!(matches!(
+        to_ast(r#"match { tests { match "a"; } }"#),
+        Err(CompilerError::ParseError { .. })
+    ));
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn run_embedded_tests_test() {
+    let source = indoc! {r#"
+        capture year { 4 of <digit>; } "-"; capture month { 2 of <digit>; }
+        tests {
+            match "2024-01";
+            match "on 2024-01-01" captures { year = "2024"; month = "01"; }
+            match "2024-1";
+            no match "2024-01";
+            match "2024-01" captures { year = "2023"; day = "01"; }
+        }
+    "#};
+
+    let Ok(results) = run_embedded_tests(source) else {
+        unreachable!("expected the source to compile");
+    };
+    let outcomes: Vec<(bool, String, u32)> = results
+        .iter()
+        .map(|result| (result.passed, result.to_string(), result.span().line))
+        .collect();
+
+    assert_eq!(
+        outcomes,
+        vec![
+            (true, r#""2024-01" matches"#.to_owned(), 3),
+            (true, r#""on 2024-01-01" matches"#.to_owned(), 4),
+            (false, r#"expected "2024-1" to match"#.to_owned(), 5),
+            (false, r#"expected "2024-01" not to match"#.to_owned(), 6),
+            (
+                false,
+                r#"expected `year` to capture "2023" but it captured "2024", `day` to capture "01" but it didn't participate in "2024-01""#.to_owned(),
+                7
+            ),
+        ]
+    );
+
+    assert!(matches!(
+        run_embedded_tests(r#"<foo>; tests { match "a"; }"#),
+        Err(CompilerError::UnrecognizedSymbol { .. })
+    ));
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn match_captures_test() {
+    let source = r#"capture key { some of <word>; } option of match { "="; capture value { some of <digit>; } } capture { ";"; }"#;
+    let captures = |haystack| -> Vec<_> {
+        let Ok(Some(captures)) = match_captures(source, haystack) else {
+            unreachable!("expected {haystack:?} to match");
+        };
+        let mut captures: Vec<_> = captures.into_iter().collect();
+        captures.sort();
+        captures
+    };
+
+    assert_eq!(
+        captures("limit=10;"),
+        vec![
+            ("3".to_owned(), ";".to_owned()),
+            ("key".to_owned(), "limit".to_owned()),
+            ("value".to_owned(), "10".to_owned()),
+        ]
+    );
+    // an optional group that didn't participate is absent rather than empty
+    assert_eq!(
+        captures("verbose;"),
+        vec![
+            ("3".to_owned(), ";".to_owned()),
+            ("key".to_owned(), "verbose".to_owned())
+        ]
+    );
+    assert!(matches!(match_captures(source, "!"), Ok(None)));
+    assert!(matches!(
+        match_captures("<foo>;", "a"),
+        Err(CompilerError::UnrecognizedSymbol { .. })
+    ));
+
+    let Ok(pattern) = CompiledPattern::new(source) else {
+        unreachable!("expected the source to compile");
+    };
+    let values: Vec<_> = pattern
+        .captures_iter("a=1; b; c=22;")
+        .map(|captures| (captures["key"].clone(), captures.get("value").cloned()))
+        .collect();
+
+    assert_eq!(
+        values,
+        vec![
+            ("a".to_owned(), Some("1".to_owned())),
+            ("b".to_owned(), None),
+            ("c".to_owned(), Some("22".to_owned())),
+        ]
+    );
+    assert_eq!(pattern.capture_groups().len(), 3);
+}
+
+fn token_texts(source: &str) -> Vec<(TokenKind, &str)> {
+    tokenize(source)
+        .into_iter()
+        .map(|token| (token.kind, &source[token.start..token.end]))
+        .collect()
+}
+
+#[test]
+fn tokenize_test() {
+    let source = indoc! {r#"
+        // a version
+        let .digits = { some of <digit>; }
+        capture major-version { .digits; } "."; lazy 1 to 3 of `\d`;
+        not <word>; char 0x41; /* a /* nested */ comment */ unicode; @
+    "#};
+
+    assert_eq!(
+        token_texts(source),
+        vec![
+            (TokenKind::Comment, "// a version"),
+            (TokenKind::Keyword, "let"),
+            (TokenKind::Punctuation, "."),
+            (TokenKind::Identifier, "digits"),
+            (TokenKind::Punctuation, "="),
+            (TokenKind::Punctuation, "{"),
+            (TokenKind::Keyword, "some"),
+            (TokenKind::Keyword, "of"),
+            (TokenKind::Symbol, "<digit>"),
+            (TokenKind::Punctuation, ";"),
+            (TokenKind::Punctuation, "}"),
+            (TokenKind::Keyword, "capture"),
+            (TokenKind::Identifier, "major-version"),
+            (TokenKind::Punctuation, "{"),
+            (TokenKind::Punctuation, "."),
+            (TokenKind::Identifier, "digits"),
+            (TokenKind::Punctuation, ";"),
+            (TokenKind::Punctuation, "}"),
+            (TokenKind::String, r#"".""#),
+            (TokenKind::Punctuation, ";"),
+            (TokenKind::Keyword, "lazy"),
+            (TokenKind::Number, "1"),
+            (TokenKind::Keyword, "to"),
+            (TokenKind::Number, "3"),
+            (TokenKind::Keyword, "of"),
+            (TokenKind::Raw, r"`\d`"),
+            (TokenKind::Punctuation, ";"),
+            (TokenKind::Keyword, "not"),
+            (TokenKind::Symbol, "<word>"),
+            (TokenKind::Punctuation, ";"),
+            (TokenKind::Keyword, "char"),
+            (TokenKind::Number, "0x41"),
+            (TokenKind::Punctuation, ";"),
+            (TokenKind::Comment, "/* a /* nested */ comment */"),
+            (TokenKind::Identifier, "unicode"),
+            (TokenKind::Punctuation, ";"),
+            (TokenKind::Unknown, "@"),
+        ]
+    );
+}
+
+#[test]
+fn tokenize_incomplete_test() {
+    // an unterminated string ends at the end of its line
+    let tokens = tokenize("some of \"a\\\"b;\n<digit");
+    let boundaries: Vec<(TokenKind, usize, usize)> = tokens
+        .iter()
+        .map(|token| (token.kind, token.start, token.end))
+        .collect();
+    assert_eq!(
+        boundaries,
+        vec![
+            (TokenKind::Keyword, 0, 4),
+            (TokenKind::Keyword, 5, 7),
+            (TokenKind::String, 8, 14),
+            (TokenKind::Symbol, 15, 21),
+        ]
+    );
+
+    // strings may span lines
+    assert_eq!(
+        token_texts("'a\nb' <"),
+        vec![(TokenKind::String, "'a\nb'"), (TokenKind::Symbol, "<")]
+    );
+
+    assert_eq!(
+        token_texts("capture { /* unterminated\n\"a\";"),
+        vec![
+            (TokenKind::Keyword, "capture"),
+            (TokenKind::Punctuation, "{"),
+            (TokenKind::Comment, "/* unterminated\n\"a\";"),
+        ]
+    );
+
+    // multi-byte characters
+    assert_eq!(
+        token_texts("\"é\"; é"),
+        vec![
+            (TokenKind::String, "\"é\""),
+            (TokenKind::Punctuation, ";"),
+            (TokenKind::Unknown, "é"),
+        ]
+    );
+    assert!(tokenize("").is_empty());
+}
+
+#[test]
+fn outline_test() {
+    let source = indoc! {r#"
+        let .date = {
+          capture year { 4 of <digit>; }
+          "-";
+          capture month { either { "0"; "1"; } <digit>; }
+        }
+        some of match {
+          .date;
+          let .time = { capture { 2 of <digit>; } }
+          .time;
+        }
+        z to a;
+        not ahead { "x"; }
+    "#};
+
+    let Ok(items) = outline(source) else {
+        unreachable!("expected the source to parse");
+    };
+
+    let summary = |items: &[OutlineItem]| -> Vec<(OutlineKind, Option<String>, &str, usize)> {
+        items
+            .iter()
+            .map(|item| {
+                (
+                    item.kind,
+                    item.name.clone(),
+                    &source[item.span.start..item.span.end],
+                    item.children.len(),
+                )
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        summary(&items),
+        vec![
+            (
+                OutlineKind::Variable,
+                Some("date".to_owned()),
+                &source[..source.find("\nsome").unwrap_or_default()],
+                2
+            ),
+            (
+                OutlineKind::Group,
+                None,
+                &source[source.find("match {").unwrap_or_default()..source.find("\nz to a").unwrap_or_default()],
+                1
+            ),
+            (OutlineKind::Assertion, None, r#"not ahead { "x"; }"#, 0),
+        ]
+    );
+
+    assert_eq!(
+        summary(&items[0].children),
+        vec![
+            (
+                OutlineKind::CaptureGroup,
+                Some("year".to_owned()),
+                "capture year { 4 of <digit>; }",
+                0
+            ),
+            (
+                OutlineKind::CaptureGroup,
+                Some("month".to_owned()),
+                r#"capture month { either { "0"; "1"; } <digit>; }"#,
+                1
+            ),
+        ]
+    );
+    assert_eq!(
+        summary(&items[0].children[1].children),
+        vec![(OutlineKind::Group, None, r#"either { "0"; "1"; }"#, 0)]
+    );
+
+    // the declaration within the group is nested in it, invocations don't repeat the statements of variables
+    assert_eq!(
+        summary(&items[1].children),
+        vec![(
+            OutlineKind::Variable,
+            Some("time".to_owned()),
+            "let .time = { capture { 2 of <digit>; } }",
+            1
+        )]
+    );
+    assert_eq!(
+        summary(&items[1].children[0].children),
+        vec![(OutlineKind::CaptureGroup, None, "capture { 2 of <digit>; }", 0)]
+    );
+
+    assert!(matches!(outline("some of"), Err(CompilerError::ParseError { .. })));
+    assert!(outline("").is_ok_and(|items| items.is_empty()));
+}
+
+#[test]
+fn variable_references_test() {
+    let source = indoc! {r"
+        let .digit = { <digit>; }
+        let .pair = { 2 of .digit; }
+        capture { .digit; }
+        let .digit = { 0 to 7; }
+        ahead { .digit; } some of .pair;
+        .digit;
+    "};
+
+    let Ok(usages) = variable_references(source) else {
+        unreachable!("expected the source to parse");
+    };
+
+    let texts: Vec<_> = usages
+        .iter()
+        .map(|usage| {
+            let references = usage
+                .references
+                .iter()
+                .map(|span| (span.line, &source[span.start..span.end]))
+                .collect::<Vec<_>>();
+            (
+                usage.name.as_str(),
+                &source[usage.declaration.start..usage.declaration.end],
+                references,
+            )
+        })
+        .collect();
+
+    // invocations before the second declaration refer to the first, including the one in `.pair` which keeps
+    // matching a digit wherever `.pair` is invoked
+    assert_eq!(
+        texts,
+        vec![
+            ("digit", "let .digit = { <digit>; }", vec![(2, ".digit"), (3, ".digit")]),
+            ("pair", "let .pair = { 2 of .digit; }", vec![(5, ".pair")]),
+            ("digit", "let .digit = { 0 to 7; }", vec![(5, ".digit"), (6, ".digit")]),
+        ]
+    );
+
+    let Ok(usages) = variable_references("let .unused = { \"a\"; } z to a; .unused;") else {
+        unreachable!("expected the source to parse");
+    };
+    assert_eq!(usages[0].references.len(), 1);
+}
+
+#[test]
+fn explain_test() {
+    let cases = [
+        ("\"foo.\";", "literal 'foo.'"),
+        ("3 to 5 of <digit>;", "between 3 and 5 of: any digit"),
+        (
+            "lazy some of <word>;",
+            "one or more, as few as possible, of: any word character",
+        ),
+        ("over 2 of \"a\";", "at least 3 of: literal 'a'"),
+        ("option of not <space>;", "optionally one of: anything but a space"),
+        ("not behind { \"foo\"; }", "negative lookbehind for: literal 'foo'"),
+        ("a to f;", "any character from 'a' to 'f'"),
+        ("not 0 to 7;", "any digit except '0' to '7'"),
+        ("not abc;", "any character except: 'a', 'b', 'c'"),
+        (
+            "class { a to f; \"_\"; <digit>; }",
+            "any of: 'a' to 'f', '_', any digit",
+        ),
+        (
+            "capture year { 4 of <digit>; \"-\"; }",
+            "capture group 'year' of: exactly 4 of: any digit, then literal '-'",
+        ),
+        (
+            "either { \"a\"; <start>; }",
+            "either: literal 'a', or the start of the input",
+        ),
+        (
+            "<category::uppercase_letter>;",
+            "any character in the category 'uppercase letter'",
+        ),
+        ("not <script::greek>;", "any character not of the Greek script"),
+        ("`a|b`;", "raw regex `a|b`"),
+        (
+            "let .digits = { some of <digit>; } .digits;",
+            "variable .digits matching: one or more of: any digit",
+        ),
+        (
+            "match { \"first\"; \"second\"; \"third\"; \"fourth\"; \"fifth\"; \"sixth\"; }",
+            "group of: literal 'first', then literal 'second', then literal 'third', and 3 more statements",
+        ),
+    ];
+
+    for (source, expected) in cases {
+        let Ok(ast) = to_ast(source) else {
+            unreachable!("expected {source} to parse");
+        };
+        assert_eq!(explain(&root_nodes(&ast)[0].node), expected, "{source}");
+    }
+}
+
+#[test]
+fn explain_at_test() {
+    let source = indoc! {r#"
+        let .digits = { some of <digit>; }
+        capture { 2 of .digits; "-"; }
+    "#};
+
+    let explained = |offset| {
+        let Ok(explained) = explain_at(source, offset) else {
+            unreachable!("expected the source to parse");
+        };
+        explained.map(|(span, description)| (&source[span.start..span.end], description))
+    };
+
+    assert_eq!(explained(25), Some(("<digit>", String::from("any digit"))));
+    assert_eq!(
+        explained(4),
+        Some((
+            "let .digits = { some of <digit>; }",
+            String::from("variable .digits matching: one or more of: any digit")
+        ))
+    );
+    assert_eq!(
+        explained(45),
+        Some((
+            "2 of .digits;",
+            String::from("exactly 2 of: group of: variable .digits matching: one or more of: any digit")
+        ))
+    );
+    assert_eq!(explained(59), Some(("\"-\"", String::from("literal '-'"))));
+    assert_eq!(
+        explained(35),
+        Some((
+            "capture { 2 of .digits; \"-\"; }",
+            String::from("capture group of: exactly 2 of: group of: variable .digits matching: one or more of: any digit, and 1 more statement")
+        ))
+    );
+    assert_eq!(explained(200), None);
+}
+
+#[test]
+fn analyze_redos_test() {
+    let warnings = |source: &str| {
+        let Ok(ast) = to_ast(source) else {
+            unreachable!("expected {source} to parse");
+        };
+        analyze_redos(&ast)
+    };
+    let text = |source: &'static str, span: Span| &source[span.start..span.end];
+
+    // `(a+)+`
+    let source = r#"some of match { some of "a"; }"#;
+    let [RedosWarning::NestedQuantifiers { outer, inner }] = warnings(source)[..] else {
+        unreachable!("expected nested quantifiers");
+    };
+    assert_eq!(text(source, outer), source);
+    assert_eq!(text(source, inner), r#"some of "a";"#);
+
+    // `(a|a)*`
+    let source = r#"any of either { "a"; "a"; }"#;
+    let found = warnings(source);
+    let [RedosWarning::OverlappingBranches {
+        first_branch,
+        second_branch,
+        ..
+    }] = found[..]
+    else {
+        unreachable!("expected overlapping branches");
+    };
+    assert_eq!((first_branch.start, second_branch.start), (16, 21));
+    assert_eq!(found[0].severity(), Severity::Exponential);
+
+    // `\d+\d+`
+    let found = warnings("some of <digit>; some of 0 to 9;");
+    assert!(matches!(
+        found[..],
+        [RedosWarning::OverlappingAdjacentQuantifiers { .. }]
+    ));
+    assert_eq!(found[0].severity(), Severity::Polynomial);
+
+    // variables are analyzed once expanded, and reported once however often they are invoked
+    let source = indoc! {r#"
+        let .letters = { some of <alphabetic>; option of "-"; }
+        some of .letters;
+        "=";
+        any of match { .letters; }
+    "#};
+    let found = warnings(source);
+    assert_eq!(found.len(), 2);
+    assert!(found
+        .iter()
+        .all(|warning| matches!(warning, RedosWarning::NestedQuantifiers { inner, .. } if text(source, *inner) == "some of <alphabetic>;")));
+
+    let controls = [
+        r#"some of match { some of <digit>; ","; }"#,
+        r#"any of either { "a"; "b"; }"#,
+        "some of <digit>; some of <alphabetic>;",
+        r#"possessive some of match { some of "a"; }"#,
+        r#"some of atomic { some of "a"; }"#,
+        r#"some of match { 2 of "a"; }"#,
+        r#"some of "a"; "b"; some of "a";"#,
+    ];
+    for source in controls {
+        assert_eq!(warnings(source), [], "{source}");
+    }
+}
+
+#[test]
+fn metrics_test() {
+    let Ok(ast) = to_ast(indoc! {r#"
+        let .octet = { 1 to 3 of <digit>; }
+        capture ip { .octet; 3 of match { "."; .octet; } }
+        option of either { ":"; "/"; }
+    "#}) else {
+        unreachable!("expected the source to parse");
+    };
+
+    assert_eq!(
+        ast.metrics(),
+        AstMetrics {
+            node_count: 14,
+            max_depth: 6,
+            capture_count: 1,
+            alternation_count: 1,
+            estimated_regex_len: 80,
+            max_repeat_bound: 3,
+        }
+    );
+
+    let Ok(ast) = to_ast(r#"5 of match { 4 of match { "ab"; not <digit>; } }"#) else {
+        unreachable!("expected the source to parse");
+    };
+
+    assert_eq!(
+        ast.metrics(),
+        AstMetrics {
+            node_count: 6,
+            max_depth: 5,
+            capture_count: 0,
+            alternation_count: 0,
+            estimated_regex_len: 198,
+            max_repeat_bound: 5,
+        }
+    );
+    assert_eq!(ViableAst::Empty.metrics(), AstMetrics::default());
+}
+
+#[test]
+fn max_estimated_regex_len_test() {
+    let options = |max_estimated_regex_len| CompilerOptions {
+        max_estimated_regex_len,
+        ..CompilerOptions::default()
+    };
+    let source = "1000 of match { 1000 of <digit>; }";
+
+    assert!(matches!(
+        compile_with_options(source, Target::Re2, &options(Some(1 << 20))),
+        Err(CompilerError::PatternTooLarge {
+            estimated: 2_010_006,
+            limit: 1_048_576
+        })
+    ));
+    assert!(compile_with_options(source, Target::Re2, &options(Some(1 << 22))).is_ok());
+    assert!(compile_with_options(source, Target::Re2, &options(None)).is_ok());
+    assert!(matches!(
+        compile_with_metadata(source, Target::Re2, &options(Some(1000))),
+        Err(CompilerError::PatternTooLarge { .. })
+    ));
+}
+
+#[test]
+fn literal_prefix_test() {
+    let prefix = |source: &str| {
+        let Ok(ast) = to_ast(source) else {
+            unreachable!("expected {source} to parse");
+        };
+        literal_prefix(&ast)
+    };
+
+    assert_eq!(
+        prefix(r#""ab"; 3 of "c"; "."; <tab>; match { "d"; }"#),
+        PrefixInfo {
+            exact: true,
+            prefix: String::from("abccc.\td"),
+            anchored_start: false,
+            anchored_end: false,
+        }
+    );
+
+    assert_eq!(
+        prefix(r#"<start>; "id-"; 2 of "0"; class { a to f; <digit>; } <end>;"#),
+        PrefixInfo {
+            exact: false,
+            prefix: String::from("id-00"),
+            anchored_start: true,
+            anchored_end: true,
+        }
+    );
+
+    assert_eq!(
+        prefix(r#"either { "foobar"; "foobaz"; match { "fo"; some of "o"; } } "!";"#),
+        PrefixInfo {
+            exact: false,
+            prefix: String::from("foo"),
+            anchored_start: false,
+            anchored_end: false,
+        }
+    );
+
+    // a quantifier that may match nothing, or a case insensitive group, ends the prefix
+    assert_eq!(prefix(r#""a"; option of "b"; "c";"#).prefix, "a");
+    assert_eq!(prefix(r#""a"; insensitive { "b"; }"#).prefix, "a");
+    assert_eq!(prefix(r#""a"; 2 to 3 of "bc"; "d";"#).prefix, "abcbc");
+    assert!(prefix(r#"either { match { <start>; "a"; } match { <input_start>; "b"; } }"#).anchored_start);
+    assert!(!prefix(r#"either { match { <start>; "a"; } "b"; }"#).anchored_start);
+}
+
+#[test]
+fn regex_to_viable_test() {
+    let patterns = [
+        r"^(?<year>\d{4})-(\d\d)$",
+        r"a+?b*c?d{2,}e{0,3}f{1,2}",
+        r"(?:foo|ba[rz]|)\.\*",
+        r"[^a-c\d_.\-\]]+",
+        r"\bcat\B|(?=x)y(?!z)|(?<=a)b(?<!c)d",
+        r"(a|b)\1(?<n>c)",
+        r"[\t-\r ]*\x41\u0042[;]",
+        r"\w+\W\s\S.",
+        r"{a}x{1,b}\\",
+    ];
+    let inputs = [
+        "",
+        "2024-01",
+        "abbcdd",
+        "aabcddeeef",
+        "foo.*",
+        "bar.*",
+        ".*",
+        "xyz",
+        "d_-]",
+        "cat",
+        "xy",
+        "ab",
+        "abd",
+        "aacc",
+        "bbcc",
+        "\t\n AB;",
+        "AB",
+        "ab  ",
+        "a_ b!",
+        "{a}x{1,b}\\",
+        "catz",
+    ];
+
+    for pattern in patterns {
+        let source = regex_to_viable(pattern, Dialect::EcmaScript).unwrap();
+        let output = compile_for(&source, Target::EcmaScript).unwrap();
+
+        let original = regress::Regex::new(&format!("^(?:{pattern})$")).unwrap();
+        let imported = regress::Regex::new(&format!("^(?:{output})$")).unwrap();
+
+        for input in inputs {
+            assert_eq!(
+                original.find(input).is_some(),
+                imported.find(input).is_some(),
+                "{pattern} imported as {source} on {input:?}"
+            );
+        }
+    }
+
+    assert_eq!(
+        regex_to_viable(r"(?i:ab)++(?>c)(?#comment)\z", Dialect::Pcre).unwrap(),
+        "possessive some of insensitive {\n  \"ab\";\n}\natomic {\n  \"c\";\n}\n<input_end>;\n"
+    );
+    assert_eq!(
+        regex_to_viable(r"(?P<word>\w)(?P=word)\Z", Dialect::Python).unwrap(),
+        "capture word {\n  <word>;\n}\nsame as word;\n<input_end>;\n"
+    );
+}
+
+#[test]
+fn regex_to_viable_errors_test() {
+    assert_eq!(
+        regex_to_viable(r"a\p{L}", Dialect::Pcre),
+        Err(ImportError::Unsupported {
+            construct: String::from("the `\\p` escape"),
+            offset: 1,
+        })
+    );
+    assert_eq!(
+        regex_to_viable("ab(?i)c", Dialect::Python),
+        Err(ImportError::Unsupported {
+            construct: String::from("inline flags for the rest of the pattern"),
+            offset: 2,
+        })
+    );
+    assert!(matches!(
+        regex_to_viable("x[[:alpha:]]", Dialect::Pcre),
+        Err(ImportError::Unsupported { offset: 2, .. })
+    ));
+    assert!(matches!(
+        regex_to_viable("(?>a)", Dialect::EcmaScript),
+        Err(ImportError::Unsupported { offset: 0, .. })
+    ));
+    assert!(matches!(
+        regex_to_viable("(a", Dialect::Pcre),
+        Err(ImportError::InvalidRegex { .. })
+    ));
+    assert!(matches!(
+        regex_to_viable("a)", Dialect::Pcre),
+        Err(ImportError::InvalidRegex { .. })
+    ));
+    assert!(matches!(
+        regex_to_viable("*a", Dialect::Pcre),
+        Err(ImportError::InvalidRegex { .. })
+    ));
+    assert!(matches!(
+        regex_to_viable("[z-a]", Dialect::Pcre),
+        Err(ImportError::InvalidRegex { .. })
+    ));
+}
+
+#[test]
+fn annotated_emit_test() {
+    let options = CompilerOptions {
+        emit_style: EmitStyle::Annotated,
+        ..CompilerOptions::default()
+    };
+
+    let source = indoc! {
+      r##"
+      capture scheme {
+        either {
+          "https";
+          "ftp";
+        }
+      }
+      "://";
+      some of match {
+        <word>;
+        "# ";
+      }
+      not ahead { <digit>; }
+      "##
+    };
+
+    assert_eq!(
+        compile_with_options(source, Target::Pcre2, &options).unwrap(),
+        indoc! {
+          r##"
+          (?x)
+          (?<scheme>  # capture scheme {
+            (?:       #   either {
+              https   #     "https"
+            |
+              ftp     #     "ftp"
+            )
+          )
+          ://         # "://"
+          (?:         # some of match {
+            \w        #   <word>
+            \#\       #   "# "
+          )+
+          (?!         # not ahead { <digit>; }
+            \d        #   <digit>
+          )
+          "##
+        }
+    );
+
+    let source = indoc! {
+      r#"
+      flags { insensitive; }
+      capture word { 2 to 3 of "a b"; }
+      "#
+    };
+
+    assert_eq!(
+        compile_with_options(source, Target::PythonRe, &options).unwrap(),
+        indoc! {
+          r#"
+          (?ix)            # flags { insensitive; }
+          (?P<word>        # capture word { 2 to 3 of "a b"; }
+            (?:a\ b){2,3}  #   2 to 3 of "a b";
+          )
+          "#
+        }
+    );
+
+    assert!(matches!(
+        compile_with_options(r#""a";"#, Target::EcmaScript, &options),
+        Err(CompilerError::UnsupportedByTarget {
+            construct: "free-spacing output",
+            target: Target::EcmaScript,
+            ..
+        })
+    ));
+
+    let output = Compiler::new()
+        .target(Target::RustRegex)
+        .options(options)
+        .compile(r#""a";"#)
+        .unwrap();
+    assert_eq!(output.pattern, "(?x)\na  # \"a\"\n");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn annotated_emit_matching_test() {
+    let annotated = CompilerOptions {
+        emit_style: EmitStyle::Annotated,
+        ..CompilerOptions::default()
+    };
+
+    let source = indoc! {
+      r##"
+      <start>;
+      capture key { some of <word>; }
+      any of " ";
+      either {
+        "#";
+        "= ";
+        nothing;
+      }
+      option of match { <tab>; "x y"; }
+      <end>;
+      "##
+    };
+
+    let compact = compile_with_options(source, Target::RustRegex, &CompilerOptions::default()).unwrap();
+    let compact = regex::Regex::new(&compact).unwrap();
+    let annotated = compile_with_options(source, Target::RustRegex, &annotated).unwrap();
+    let annotated = regex::Regex::new(&annotated).unwrap();
+
+    for input in [
+        "key",
+        "key  #",
+        "key= ",
+        "key =",
+        "key#\tx y",
+        "key\tx y",
+        "key#x y",
+        "#",
+        "key#\txy",
+    ] {
+        assert_eq!(compact.is_match(input), annotated.is_match(input), "{input:?}");
+    }
+}
+
+#[test]
+fn source_map_test() {
+    let options = CompilerOptions {
+        source_map: true,
+        ..CompilerOptions::default()
+    };
+
+    let source = indoc! {
+      r#"
+      flags { insensitive; }
+      let .digits = { some of <digit>; }
+      "id";
+      capture value {
+        either {
+          .digits;
+          "none";
+        }
+      }
+      not ahead { "x"; }
+      3 of match { "a"; <word>; }
+      "#
+    };
+
+    let (pattern, _, source_map) = compile_with_metadata(source, Target::Pcre2, &options).unwrap();
+    let source_map = source_map.unwrap();
+    assert_eq!(pattern, r"(?i)id(?<value>(?:\d+|none))(?!x)(?:a\w){3}");
+
+    let located = |offset: usize| {
+        let Some(mapping) = locate(offset, &source_map) else {
+            unreachable!()
+        };
+        (
+            &pattern[mapping.regex_start..mapping.regex_end],
+            &source[mapping.source_span.start..mapping.source_span.end],
+            mapping.node_kind,
+        )
+    };
+
+    assert_eq!(located(1), ("(?i)", "flags { insensitive; }", NodeKind::Flags));
+    assert_eq!(located(5), ("id", r#""id""#, NodeKind::Atom));
+    assert_eq!(located(7), (r"(?<value>(?:\d+|none))", located(7).1, NodeKind::Capture));
+    assert!(located(7).1.starts_with("capture value {"));
+    assert_eq!(located(15), (r"(?:\d+|none)", located(15).1, NodeKind::Group));
+    assert!(located(15).1.starts_with("either {"));
+    assert_eq!(located(18), (r"\d", "<digit>", NodeKind::Symbol));
+    assert_eq!(located(20), (r"\d+", "some of <digit>;", NodeKind::Quantifier));
+    assert_eq!(located(21), (r"(?:\d+|none)", located(15).1, NodeKind::Group));
+    assert_eq!(located(22), ("none", r#""none""#, NodeKind::Atom));
+    assert_eq!(located(28), ("(?!x)", r#"not ahead { "x"; }"#, NodeKind::Assertion));
+    assert_eq!(located(31), ("x", r#""x""#, NodeKind::Atom));
+    assert_eq!(located(36), ("a", r#""a""#, NodeKind::Atom));
+    assert_eq!(located(37), (r"\w", "<word>", NodeKind::Symbol));
+    assert_eq!(located(40).2, NodeKind::Quantifier);
+    assert_eq!(locate(pattern.len(), &source_map), None);
+
+    let (_, _, source_map) = compile_with_metadata(source, Target::Pcre2, &CompilerOptions::default()).unwrap();
+    assert_eq!(source_map, None);
+}
+
+#[test]
+fn source_map_nesting_test() {
+    let sources = [
+        r#"capture { "a"; } same as capture 1; "2"; some of either { "b"; nothing; } "c";"#,
+        r#"let .x = { capture { "a"; } "b"; } .x; 2 of .x; behind { "y"; <word_boundary>; }"#,
+        r#"either { match { "a"; "b"; } `c|d`; } match { `e|f`; } "g"; <start>; not a to z;"#,
+        r#"some of match { either { "h"; "i"; } "j"; } class { a to f; <digit>; } <category::letter>;"#,
+        r#"either { "foobar"; "foobaz"; either { "k"; "l"; } } "m"; "n"; 2 of "o";"#,
+    ];
+
+    // the map is built from the AST the passes leave, whichever of them run
+    let levels = [
+        OptimizationLevel::None,
+        OptimizationLevel::Basic,
+        OptimizationLevel::Aggressive,
+    ];
+    for (source, optimization) in sources
+        .into_iter()
+        .flat_map(|source| levels.map(|level| (source, level)))
+    {
+        let options = CompilerOptions {
+            optimization,
+            source_map: true,
+            ..CompilerOptions::default()
+        };
+        let (pattern, _, source_map) = compile_with_metadata(source, Target::Pcre2, &options).unwrap();
+        let Some(SourceMap { mappings }) = source_map else {
+            unreachable!()
+        };
+
+        // statements emit a fragment of their own or nest within the one before them
+        for (index, mapping) in mappings.iter().enumerate() {
+            assert!(mapping.regex_start < mapping.regex_end && mapping.regex_end <= pattern.len());
+            for before in &mappings[..index] {
+                let nested = before.regex_start <= mapping.regex_start && mapping.regex_end <= before.regex_end;
+                assert!(
+                    nested || before.regex_end <= mapping.regex_start,
+                    "{source}: {mapping:?}"
+                );
+            }
+        }
+
+        // leaves compile to the text of their own statement
+        for mapping in &mappings {
+            let fragment = &pattern[mapping.regex_start..mapping.regex_end];
+            let statement = &source[mapping.source_span.start..mapping.source_span.end];
+            if matches!(mapping.node_kind, NodeKind::Atom | NodeKind::Symbol | NodeKind::Range) {
+                let compiled = compile_with_options(&format!("{statement};"), Target::Pcre2, &options).unwrap();
+                assert_eq!(fragment, compiled, "{source}: {statement}");
+            }
+        }
+    }
+}
+
+#[test]
+fn compose_test() {
+    let options = CompilerOptions::default();
+
+    let scheme =
+        ViableAst::any_of(vec![ViableAst::atom("https"), ViableAst::atom("ftp")]).captured(Some("scheme".into()));
+    let Ok(host) = to_ast("some of <word>;") else {
+        unreachable!()
+    };
+    let Ok(digits) = to_ast("some of <digit>;") else {
+        unreachable!()
+    };
+    let port = ViableAst::concat(vec![ViableAst::atom(":"), digits.captured(Some("port".into()))]).optional();
+
+    let url = ViableAst::concat(vec![scheme, ViableAst::atom("://"), host.clone().captured(None), port]);
+    let (output, captures) = compile_ast(&url, Target::default(), &options).unwrap();
+
+    assert_eq!(output, r"(?<scheme>(?:https|ftp)):\/\/(\w+)(?::(?<port>\d+))?");
+    let captures: Vec<_> = captures
+        .iter()
+        .map(|capture| (capture.index, capture.name.as_deref()))
+        .collect();
+    assert_eq!(captures, [(1, Some("scheme")), (2, None), (3, Some("port"))]);
+
+    let (output, _) = compile_ast(&url, Target::PythonRe, &options).unwrap();
+    assert_eq!(output, r"(?P<scheme>(?:https|ftp))://(\w+)(?::(?P<port>\d+))?");
+
+    let compiled = |ast: ViableAst| compile_ast(&ast, Target::default(), &options).unwrap().0;
+    assert_eq!(compiled(ViableAst::atom("ab").repeated(2, Some(4))), "(?:ab){2,4}");
+    assert_eq!(compiled(ViableAst::atom("a").repeated(3, None)), "a{3,}");
+    assert_eq!(compiled(ViableAst::atom("a").repeated(3, Some(1))), "a{3}");
+    assert_eq!(
+        compiled(ViableAst::atom("a").repeated(1, None).repeated(2, None)),
+        "(?:a+){2,}"
+    );
+    assert_eq!(
+        compiled(ViableAst::any_of(vec![ViableAst::atom("ab"), ViableAst::Empty])),
+        "(?:ab|)"
+    );
+    assert_eq!(
+        compiled(ViableAst::any_of(vec![
+            ViableAst::concat(vec![ViableAst::atom("a"), host]),
+            ViableAst::atom("b")
+        ])),
+        r"(?:a\w+|b)"
+    );
+}
+
+#[test]
+fn compose_duplicate_capture_test() {
+    let first = ViableAst::atom("a").captured(Some("part".into()));
+    let second = ViableAst::atom("b").repeated(0, None).captured(Some("part".into()));
+    let ast = ViableAst::concat(vec![first, second]);
+
+    assert!(matches!(
+        compile_ast(&ast, Target::default(), &CompilerOptions::default()),
+        Err(CompilerError::DuplicateCaptureName { .. })
+    ));
+
+    let options = CompilerOptions {
+        allow_duplicate_capture_names: true,
+        ..CompilerOptions::default()
+    };
+    let Ok((output, captures)) = compile_ast(&ast, Target::Pcre2, &options) else {
+        unreachable!()
+    };
+    assert_eq!(output, "(?<part>a)(?<part>b*)");
+    assert_eq!(captures.len(), 2);
+}
+
+#[test]
+fn raw_regex_test() {
+    let source = indoc! {r#"
+        "a";
+        raw_regex "(?P>name)\\d";
+        some of raw_regex "ab|c";
+        option of raw_regex "[)]";
+    "#};
+
+    assert_eq!(
+        compile_for(source, Target::Pcre2).unwrap(),
+        r"a(?P>name)\d(?:ab|c)+(?:[)])?"
+    );
+    assert_eq!(
+        ast_to_source(&to_ast(source).unwrap(), &FormatOptions::default()),
+        indoc! {r#"
+            "a";
+            raw_regex "(?P>name)\\d";
+            some of raw_regex "ab|c";
+            option of raw_regex "[)]";
+        "#}
+    );
+}
+
+#[test]
+fn raw_regex_captures_test() {
+    let source = indoc! {r#"
+        raw_regex "(x)(?:y(?<inner>z))[(](?<=\\()";
+        capture last { "w"; }
+        same as capture 2;
+    "#};
+
+    let (output, captures, _) = compile_with_metadata(source, Target::default(), &CompilerOptions::default()).unwrap();
+    assert_eq!(output, r"(x)(?:y(?<inner>z))[(](?<=\()(?<last>w)\2");
+
+    let captures: Vec<_> = captures
+        .iter()
+        .map(|capture| (capture.index, capture.name.as_deref()))
+        .collect();
+    assert_eq!(captures, [(1, None), (2, Some("inner")), (3, Some("last"))]);
+
+    let Ok(regex) = regress::Regex::new(&output) else {
+        unreachable!()
+    };
+    let Some(found) = regex.find("xyz(wz") else {
+        unreachable!()
+    };
+    assert_eq!(found.group(3), Some(4..5));
+}
+
+#[test]
+fn unbalanced_raw_regex_test() {
+    let unbalanced = |source: &str| match compiler(source) {
+        Err(CompilerError::UnbalancedRawRegex { unbalanced, span }) => Some((unbalanced, span.start)),
+        _ => None,
+    };
+
+    assert_eq!(unbalanced(r#""a"; raw_regex "(a";"#), Some(('(', 5)));
+    assert_eq!(unbalanced(r#"raw_regex "a)";"#), Some((')', 0)));
+    assert_eq!(unbalanced(r#"raw_regex "[a";"#), Some(('[', 0)));
+    assert_eq!(unbalanced(r#"raw_regex "a]";"#), Some((']', 0)));
+    assert_eq!(unbalanced(r#"raw_regex "\\(a";"#), None);
+    assert_eq!(unbalanced(r#"raw_regex "[]a]";"#), None);
+}
+
+#[test]
+fn conditional_test() {
+    let source = indoc! {r#"
+        option of capture open { "<"; }
+        some of <word>;
+        if captured open { ">"; } else { ";"; }
+    "#};
+
+    assert_eq!(
+        compile_for(source, Target::Pcre2).unwrap(),
+        r"(?<open><)?\w+(?(<open>)>|;)"
+    );
+    assert_eq!(
+        compile_for(source, Target::PythonRe).unwrap(),
+        r"(?P<open><)?\w+(?(open)>|;)"
+    );
+
+    for target in [Target::EcmaScript, Target::Re2, Target::RustRegex] {
+        assert!(matches!(
+            compile_for(source, target),
+            Err(CompilerError::UnsupportedByTarget {
+                construct: "a conditional",
+                ..
+            })
+        ));
+        assert_eq!(
+            check_target_compatibility(&to_ast(source).unwrap(), target)
+                .iter()
+                .map(|incompatibility| incompatibility.construct)
+                .collect::<Vec<_>>(),
+            ["a conditional"]
+        );
+    }
+
+    let compiled = |source: &str| compile_for(source, Target::Pcre2).unwrap();
+    assert_eq!(
+        compiled(r#"option of capture { "a"; } if captured 1 { "b"; }"#),
+        "(a)?(?(1)b)"
+    );
+    assert_eq!(
+        compiled(r#"option of capture { "a"; } some of if captured 1 { "b"; } else { "c"; }"#),
+        "(a)?(?:(?(1)b|c))+"
+    );
+    assert_eq!(
+        compiled(indoc! {r#"
+            option of capture a { "a"; }
+            option of capture b { "b"; }
+            if captured a {
+              if captured b { "x"; } else { "y"; }
+            } else {
+              either { "z"; "zz"; }
+            }
+        "#}),
+        "(?<a>a)?(?<b>b)?(?(<a>)(?(<b>)x|y)|(?:z|zz))"
+    );
+    assert_eq!(
+        compiled(r#"option of capture { "a"; } if captured 1 { raw_regex "b|c"; } else { "d"; }"#),
+        "(a)?(?(1)(?:b|c)|d)"
+    );
+}
+
+#[test]
+fn conditional_format_test() {
+    let source = indoc! {r#"
+        option of capture a {
+          "a";
+        }
+        if captured a {
+          "b";
+        } else {
+          "c";
+        }
+        some of if captured 1 {
+          "d";
+        }
+    "#};
+
+    assert_eq!(
+        ast_to_source(&to_ast(source).unwrap(), &FormatOptions::default()),
+        source
+    );
+    assert_eq!(
+        explain(&ViableAstNode::Conditional(Conditional {
+            condition: Backreference::Named("a".into()),
+            then: Box::new(to_ast(r#""b";"#).unwrap()),
+            otherwise: None,
+        })),
+        "if capture group 'a' matched: literal 'b', otherwise: nothing"
+    );
+}
+
+#[test]
+fn unknown_conditional_capture_test() {
+    let unknown = |source: &str| match compile_for(source, Target::Pcre2) {
+        Err(CompilerError::UnknownConditionalCapture(name)) => Some(name),
+        _ => None,
+    };
+
+    assert_eq!(unknown(r#"if captured a { "b"; }"#).as_deref(), Some("a"));
+    assert_eq!(
+        unknown(r#"if captured a { "b"; } capture a { "a"; }"#).as_deref(),
+        Some("a")
+    );
+    assert_eq!(unknown(r#"capture a { if captured a { "b"; } }"#).as_deref(), Some("a"));
+    assert_eq!(
+        unknown(r#"capture { "a"; } if captured 2 { "b"; }"#).as_deref(),
+        Some("2")
+    );
+    assert_eq!(unknown(r#"if captured 0 { "b"; }"#).as_deref(), Some("0"));
+    assert_eq!(unknown(r#"raw_regex "(a)"; if captured 1 { "b"; }"#), None);
+}
+
+#[test]
+fn recursion_test() {
+    let source = indoc! {r#"
+        capture parens {
+          "(";
+          any of either {
+            not class { "("; ")"; }
+            recurse parens;
+          }
+          ")";
+        }
+    "#};
+
+    let pattern = compile_for(source, Target::Pcre2).unwrap();
+    assert_eq!(pattern, r"(?<parens>\((?:[^()]|(?&parens))*\))");
+
+    assert_eq!(
+        compile_for(r#""a"; option of recurse; "b";"#, Target::Pcre2).unwrap(),
+        "a(?:(?R))?b"
+    );
+    assert_eq!(
+        compile_for(r#"recurse later; capture later { "a"; }"#, Target::Pcre2).unwrap(),
+        "(?&later)(?<later>a)"
+    );
+    assert_eq!(
+        compile_for(r#"raw_regex "(?<a>x)"; recurse a;"#, Target::Pcre2).unwrap(),
+        "(?<a>x)(?&a)"
+    );
+    let formatted = |source: &str| ast_to_source(&to_ast(source).unwrap(), &FormatOptions::default());
+    assert_eq!(formatted("recurse;"), "recurse;\n");
+    assert_eq!(formatted("option of recurse;"), "option of recurse;\n");
+    assert!(formatted(source).contains("\n    recurse parens;\n"));
+
+    for target in [Target::EcmaScript, Target::Re2, Target::PythonRe, Target::RustRegex] {
+        assert!(matches!(
+            compile_for(source, target),
+            Err(CompilerError::UnsupportedByTarget {
+                construct: "recursion",
+                ..
+            })
+        ));
+    }
+}
+
+#[test]
+fn unknown_recursion_group_test() {
+    let Err(CompilerError::UnknownRecursionGroup(name)) =
+        compile_for(r#"capture a { "a"; } recurse b;"#, Target::Pcre2)
+    else {
+        unreachable!()
+    };
+    assert_eq!(name, "b");
+}
+
+#[test]
+fn quantified_recursion_redos_test() {
+    let ast = to_ast(r#"some of match { "a"; option of recurse; }"#).unwrap();
+    let warnings = analyze_redos(&ast);
+
+    let [RedosWarning::QuantifiedRecursion { quantifier, recursion }] = warnings.as_slice() else {
+        unreachable!()
+    };
+    assert_eq!(warnings[0].severity(), Severity::Exponential);
+    assert!(quantifier.start < recursion.start && recursion.end <= quantifier.end);
+
+    let ast = to_ast(r#"possessive some of match { "a"; option of recurse; }"#).unwrap();
+    assert!(analyze_redos(&ast).is_empty());
+}
+
+#[test]
+fn negated_symbols_test() {
+    let negated = [
+        ("space", Some("[^ ]")),
+        ("newline", Some(r"[^\n]")),
+        ("vertical", Some(r"[^\v]")),
+        ("vertical_tab", Some(r"[^\v]")),
+        ("word", Some(r"\W")),
+        ("digit", Some(r"\D")),
+        ("whitespace", Some(r"\S")),
+        ("alphabetic", Some("[^a-zA-Z]")),
+        ("alphanumeric", Some("[^a-zA-Z0-9]")),
+        ("return", Some(r"[^\r]")),
+        ("carriage_return", Some(r"[^\r]")),
+        ("tab", Some(r"[^\t]")),
+        ("null", Some(r"[^\0]")),
+        ("feed", Some(r"[^\f]")),
+        ("form_feed", Some(r"[^\f]")),
+        ("backspace", Some(r"[^\b]")),
+        ("boundary", Some(r"\B")),
+        ("word_boundary", Some(r"\B")),
+        ("char", None),
+        ("grapheme", None),
+        ("line_break", None),
+        ("start", None),
+        ("end", None),
+        ("line_start", None),
+        ("line_end", None),
+        ("input_start", None),
+        ("input_end", None),
+    ];
+
+    // every built in symbol outside of a namespace is covered
+    let mut names: Vec<_> = negated.iter().map(|(name, _)| *name).collect();
+    names.sort_unstable();
+    let mut builtin = [
+        "alphabetic",
+        "alphanumeric",
+        "backspace",
+        "boundary",
+        "carriage_return",
+        "char",
+        "digit",
+        "end",
+        "feed",
+        "form_feed",
+        "grapheme",
+        "input_end",
+        "input_start",
+        "line_break",
+        "line_end",
+        "line_start",
+        "newline",
+        "null",
+        "return",
+        "space",
+        "start",
+        "tab",
+        "vertical",
+        "vertical_tab",
+        "whitespace",
+        "word",
+        "word_boundary",
+    ];
+    builtin.sort_unstable();
+    assert_eq!(names, builtin);
+
+    for (name, expected) in negated {
+        let compiled = compiler(&format!("not <{name}>;"));
+        if let Some(expected) = expected {
+            assert_eq!(compiled.unwrap(), expected, "not <{name}>");
+            continue;
+        }
+
+        let Err(error) = compiled else {
+            unreachable!("expected `not <{name}>` to be rejected");
+        };
+        assert!(
+            matches!(
+                error,
+                CompilerError::NegativeCharNotAllowed
+                    | CompilerError::NegativeGraphemeNotAllowed
+                    | CompilerError::NegativeLineBreakNotAllowed
+                    | CompilerError::NegativeStartNotAllowed(_)
+                    | CompilerError::NegativeEndNotAllowed(_)
+            ),
+            "not <{name}>: {error:?}"
+        );
+        // the message names the symbol
+        let message = error.to_string();
+        assert!(
+            message.contains(name) || message.contains(&name.replace('_', " ")),
+            "{message}"
+        );
+    }
+
+    assert_eq!(compiler("some of not <digit>;").unwrap(), r"\D+");
+    assert_eq!(compiler("3 of not <newline>;").unwrap(), r"[^\n]{3}");
+    assert_eq!(compiler("lazy any of not <word>;").unwrap(), r"\W*?");
+}
+
+#[test]
+fn quantifier_unit_test() {
+    let bytes = |source| compile_for(source, Target::RustRegexBytes);
+    let unicode = CompilerOptions {
+        unicode_mode: true,
+        ..CompilerOptions::default()
+    };
+
+    // patterns for `regex::bytes` match by byte unless unicode mode is enabled
+    assert_eq!(bytes(r#""a";"#).unwrap(), "(?-u)a");
+    assert_eq!(
+        compile_with_options(r#""a";"#, Target::RustRegexBytes, &unicode).unwrap(),
+        "(?u)a"
+    );
+    assert_eq!(
+        compile_with_options(r#""a";"#, Target::RustRegex, &unicode).unwrap(),
+        "(?u)a"
+    );
+    assert_eq!(
+        compile_with_options(r#""a";"#, Target::PythonRe, &unicode).unwrap(),
+        "(?u)a"
+    );
+    assert_eq!(
+        compile_with_options(r#""a";"#, Target::Pcre2, &unicode).unwrap(),
+        "(*UTF)a"
+    );
+
+    // a unit switches the quantified expression to counting characters or bytes
+    assert_eq!(bytes("5 chars of <char>;").unwrap(), "(?-u)(?u:.){5}");
+    assert_eq!(bytes("5 bytes of <char>;").unwrap(), "(?-u)(?-u:.){5}");
+    assert_eq!(
+        compile_with_options("2 to 3 bytes of <char>;", Target::RustRegexBytes, &unicode).unwrap(),
+        "(?u)(?-u:.){2,3}"
+    );
+    assert_eq!(compile_for("5 chars of <char>;", Target::RustRegex).unwrap(), ".{5}");
+    let Err(CompilerError::UnsupportedByTarget { construct, target, .. }) =
+        compile_for("5 bytes of <char>;", Target::RustRegex)
+    else {
+        unreachable!()
+    };
+    assert_eq!(construct, "a quantifier counting bytes");
+    assert_eq!(target, Target::RustRegex);
+
+    // non-ASCII characters can only be matched in unicode mode
+    assert!(matches!(
+        bytes(r#""😀";"#),
+        Err(CompilerError::NonAsciiInBytesMode { span }) if (span.start, span.end) == (0, 6)
+    ));
+    assert!(matches!(
+        bytes("<category::letter>;"),
+        Err(CompilerError::NonAsciiInBytesMode { .. })
+    ));
+    assert!(matches!(
+        bytes("class { a to z; à to ÿ; }"),
+        Err(CompilerError::NonAsciiInBytesMode { .. })
+    ));
+    assert!(matches!(
+        bytes(r#"2 bytes of "é";"#),
+        Err(CompilerError::NonAsciiInBytesMode { .. })
+    ));
+    assert_eq!(
+        compile_with_options(r#""😀";"#, Target::RustRegexBytes, &unicode).unwrap(),
+        "(?u)😀"
+    );
+    assert_eq!(bytes(r#"2 chars of "é";"#).unwrap(), "(?-u)(?u:é){2}");
+    assert_eq!(compile_for(r#""😀";"#, Target::RustRegex).unwrap(), "😀");
+
+    // the unit is kept when formatting
+    let source = "5 bytes of <char>;";
+    let formatted = ast_to_source(&to_ast(source).unwrap(), &FormatOptions::default());
+    assert!(formatted.contains("5 bytes of <char>;"), "{formatted}");
+    let json: serde_json::Value = serde_json::from_str(&to_ast_json("3 chars of <char>;")).unwrap();
+    assert_eq!(json["ast"][0]["unit"], "chars");
+}
+
+#[test]
+#[cfg(feature = "regex")]
+fn quantifier_unit_regex_bytes_test() {
+    let pattern = compile_for("<start>; 2 bytes of <char>; <end>;", Target::RustRegexBytes).unwrap();
+    let regex = ::regex::bytes::Regex::new(&pattern).unwrap();
+    assert!(regex.is_match("é".as_bytes()));
+    assert!(!regex.is_match(b"a"));
+
+    let pattern = compile_for("<start>; 1 chars of <char>; <end>;", Target::RustRegexBytes).unwrap();
+    let regex = ::regex::bytes::Regex::new(&pattern).unwrap();
+    assert!(regex.is_match("é".as_bytes()));
+    assert!(!regex.is_match(b"\xff"));
+}
+
+#[test]
+fn compiler_builder_test() {
+    let variables = BTreeMap::from([(String::from("sep"), ViableAst::atom("-"))]);
+    let source = r#"capture year { 4 of <digit>; } .sep; capture { either { "a"; "b"; } }"#;
+
+    // a strict compiler for PCRE2 with predefined variables
+    let strict = Compiler::new()
+        .target(Target::Pcre2)
+        .optimization(OptimizationLevel::Basic)
+        .strict(true)
+        .max_depth(8)
+        .variables(variables.clone());
+    let output = strict.compile(source).unwrap();
+    assert_eq!(output.pattern, r"(?<year>\d{4})-([ab])");
+    assert_eq!(output.captures.len(), 2);
+    assert_eq!(output.captures[0].name.as_deref(), Some("year"));
+    assert_eq!(output.captures[1].index, 2);
+    assert!(output.warnings.is_empty());
+    assert_eq!(output.source_map, None);
+    assert_eq!(root_nodes(&output.ast).len(), 3);
+    assert!(matches!(
+        strict.compile(r#"let .unused = { "a"; } "b";"#),
+        Err(CompilerError::DeniedWarning(Warning::UnusedVariable { .. }))
+    ));
+    assert!(matches!(
+        strict.clone().max_depth(1).compile(source),
+        Err(CompilerError::NestingTooDeep { limit: 1 })
+    ));
+
+    // an unoptimized compiler for Rust building the source map, whose AST is the one parsed
+    let mapped = Compiler::new()
+        .target(Target::RustRegex)
+        .optimization(OptimizationLevel::None)
+        .variables(variables)
+        .source_map(true);
+    let output = mapped.compile(source).unwrap();
+    assert_eq!(output.pattern, r"(?<year>\d{4})-((?:a|b))");
+    assert_eq!(output.ast, mapped.parse(source).unwrap());
+    let Some(source_map) = output.source_map else {
+        unreachable!("expected a source map");
+    };
+    let (_, _, expected) = compile_with_metadata(
+        source,
+        Target::RustRegex,
+        &CompilerOptions {
+            optimization: OptimizationLevel::None,
+            source_map: true,
+            variables: BTreeMap::from([(String::from("sep"), ViableAst::atom("-"))]),
+            ..CompilerOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(Some(source_map), expected);
+
+    // a lenient compiler for ECMAScript without variables, warning about what the others reject
+    let lenient = Compiler::new()
+        .target(Target::EcmaScript)
+        .strictness(Strictness::Lenient)
+        .strict(false);
+    assert!(matches!(
+        lenient.compile(source),
+        Err(CompilerError::UninitializedVariable { .. })
+    ));
+    let output = lenient.compile(r#"let .unused = { "a"; } 1 of "b";"#).unwrap();
+    assert_eq!(output.pattern, "b{1}");
+    assert_eq!(output.warnings.len(), 2);
+
+    // configuring a clone leaves the compiler it was cloned from as it was
+    let relaxed = strict.clone().strict(false);
+    assert!(relaxed.compile(r#"let .unused = { "a"; } "b";"#).is_ok());
+    assert!(strict.compile(r#"let .unused = { "a"; } "b";"#).is_err());
+
+    // `to_ast` parses like a default compiler
+    assert_eq!(to_ast(source).is_err(), Compiler::new().parse(source).is_err());
+    assert_eq!(
+        to_ast(r#"some of "a";"#).unwrap(),
+        Compiler::new().parse(r#"some of "a";"#).unwrap()
+    );
+}
+
+/// Sources fuzzing found tricky, or that end within each construct, which must be rejected or compiled
+/// without panicking
+const FUZZ_CORPUS: &[&str] = &[
+    "",
+    ";",
+    "\"",
+    "\"\\",
+    "'",
+    "`",
+    "\u{0}",
+    "😀",
+    "\"😀\"; 2 bytes of \"é\";",
+    "{",
+    "}",
+    "<",
+    "<>;",
+    "<category::>;",
+    "<::letter>;",
+    "not",
+    "not ;",
+    "not not <digit>;",
+    "a to",
+    "a to ;",
+    "z to a;",
+    "1 to 0;",
+    "0 to 99999999999999999999;",
+    "99999999999999999999 of \"a\";",
+    "18446744073709551615 to 18446744073709551615 of \"a\";",
+    "over 18446744073709551615 of \"a\";",
+    "up to 0 of \"a\";",
+    "3 to of \"a\";",
+    "3 chars bytes of \"a\";",
+    "lazy possessive some of \"a\";",
+    "some of;",
+    "some of nothing;",
+    "some of <start>;",
+    "some of ahead { \"a\"; }",
+    "match {",
+    "match { \"a\";",
+    "capture 1 { \"a\"; }",
+    "capture { } same as 0;",
+    "same as;",
+    "same as 18446744073709551616;",
+    "either { }",
+    "either { nothing; nothing; }",
+    "flags {",
+    "flags { } flags { }",
+    "\"a\"; flags { insensitive; }",
+    "insensitive no insensitive { \"a\"; }",
+    "no { \"a\"; }",
+    "class { }",
+    "class { without { } }",
+    "class { a to; }",
+    "class { z to a; }",
+    "not class { and { } }",
+    "char ;",
+    "char 1114112;",
+    "\"\\u{110000}\";",
+    "\"\\u{}\";",
+    "\"\\x\";",
+    "let",
+    "let .a",
+    "let .a = {",
+    "let .a = { .a; } .a;",
+    "let .a(x, x) = { x; }",
+    "let .a(x) = { x; } .a;",
+    "let .a(x) = { x; } .a(\"b\", \"c\");",
+    ".a;",
+    "define symbol",
+    "define symbol digit = { \"a\"; }",
+    "define symbol a = { <a>; } <a>;",
+    "raw_regex \"(\";",
+    "raw_regex \")\";",
+    "raw_regex \"\\\\\";",
+    "recurse missing;",
+    "if captured",
+    "if captured missing { \"a\"; }",
+    "if captured 0 { \"a\"; } else { }",
+    "ahead behind { }",
+    "not behind { some of \"a\"; }",
+    "import \"a\";",
+    "tests {",
+    "tests { match; }",
+    "tests { no match \"a\" with { x: \"a\" }; }",
+    "// \"a\";",
+    "/* \"a\";",
+    "\r\n\t;",
+    "match { match { match { match { match { match { match { match { \"a\"; } } } } } } } }",
+];
+
+#[test]
+fn fuzz_corpus_test() {
+    let options = CompilerOptions {
+        emit_style: EmitStyle::Annotated,
+        approximate_graphemes: true,
+        ..CompilerOptions::default()
+    };
+    let compiler = Compiler::new()
+        .source_map(true)
+        .optimization(OptimizationLevel::Aggressive);
+
+    for source in FUZZ_CORPUS {
+        // each source is either rejected or parsed, a panic fails the test
+        let parsed = to_ast(source);
+        drop(to_ast_lenient(source));
+        drop(to_ast_all_errors(source));
+        drop(tokenize(source));
+        drop(explain_at(source, source.len() / 2));
+
+        for target in Target::ALL {
+            let output = compiler.clone().target(target).compile(source);
+            assert!(
+                output.is_err() || parsed.is_ok(),
+                "compiled a source the parser rejects: {source}"
+            );
+            drop(compile_with_options(source, target, &options));
+        }
+
+        if let Ok(ast) = parsed {
+            drop(ast_to_source(&ast, &FormatOptions::default()));
+            drop(analyze_redos(&ast));
+        }
+    }
+
+    // a parse tree lacking a child names the construct being parsed and where
+    let error = CompilerError::MissingNode {
+        context: "quantifier",
+        span: Span {
+            start: 13,
+            end: 20,
+            line: 3,
+            column: 14,
+        },
+    };
+    assert_eq!(
+        error.to_string(),
+        "expected an inner node while parsing `quantifier` at 3:14 [E0020]"
+    );
+    assert_eq!(error.span().map(|span| span.start), Some(13));
+}
+
+#[test]
+fn statement_terminator_test() {
+    let terminator_position = |source: &str| match to_ast(source) {
+        Err(CompilerError::MissingStatementTerminator { span }) => Some((span.line, span.column, span.start, span.end)),
+        _ => None,
+    };
+
+    // the `;` is reported missing right after the statement lacking it
+    assert_eq!(terminator_position("\"a\"\n\"b\";"), Some((1, 4, 3, 3)));
+    assert_eq!(terminator_position(r#"match { "a" }"#), Some((1, 12, 11, 11)));
+    assert_eq!(terminator_position(r#"class { "a" }"#), Some((1, 12, 11, 11)));
+    assert_eq!(terminator_position(r#"some of "a" "b";"#), Some((1, 12, 11, 11)));
+    assert_eq!(terminator_position(r#""a"; <digit>"#), Some((1, 13, 12, 12)));
+
+    // a `;` after one already ending a statement is skipped
+    assert_eq!(compiler(r#""a";;"#).unwrap(), "a");
+    assert_eq!(compiler(r#"capture { "a"; };; "b";"#).unwrap(), "(a)b");
+    assert!(matches!(to_ast("match { ; }"), Err(CompilerError::ParseError { .. })));
+
+    let errors = to_ast_all_errors("\"a\"\n<digit>;\n\"b\"").unwrap_err();
+    assert!(errors
+        .iter()
+        .all(|error| matches!(error.node, CompilerError::MissingStatementTerminator { .. })));
+    assert_eq!(errors.iter().map(|error| error.span.line).collect::<Vec<_>>(), [1, 3]);
+}
+
+#[test]
+fn at_least_quantifier_test() {
+    for source in [r#"3 or more of "a";"#, r#"3 to many of "a";"#] {
+        let ast = to_ast(source).unwrap();
+        let [Spanned {
+            node: ViableAstNode::Quantifier(quantifier),
+            ..
+        }] = root_nodes(&ast)
+        else {
+            unreachable!("expected a single quantifier for {source}");
+        };
+        assert_eq!(quantifier.kind, QuantifierKind::AtLeast(3));
+        assert_eq!(ast_to_source(&ast, &FormatOptions::default()), "3 or more of \"a\";\n");
+    }
+
+    assert_eq!(compiler(r#"3 or more of "a";"#).unwrap(), "a{3,}");
+    assert_eq!(compiler(r#"lazy 3 or more of "ab";"#).unwrap(), "(?:ab){3,}?");
+    assert_eq!(
+        compile_for(r#"possessive 3 to many of "a";"#, Target::Pcre2).unwrap(),
+        "a{3,}+"
+    );
+    assert_eq!(
+        regex_to_viable("a{3,}", Dialect::Pcre).unwrap(),
+        "3 or more of \"a\";\n"
+    );
+
+    // `over n` repeats more than n times on every target
+    for target in Target::ALL {
+        let over = compile_for(r#"over 2 of "a";"#, target).unwrap();
+        assert!(over.ends_with("a{3,}"), "{target:?}");
+        assert_eq!(compile_for(r#"3 or more of "a";"#, target).unwrap(), over, "{target:?}");
+    }
+
+    let warning = |source: &str| to_ast_with_diagnostics(source).unwrap().1;
+    assert_eq!(
+        warning(r#""a"; over 2 of "b";"#),
+        [Warning::DeprecatedOver {
+            replacement: String::from("3 or more of"),
+            span: Span {
+                start: 5,
+                end: 19,
+                line: 1,
+                column: 6,
+            },
+        }]
+    );
+    assert!(matches!(
+        &warning(r#"lazy over 0 of "a";"#)[..],
+        [Warning::DeprecatedOver { replacement, .. }] if replacement == "some of"
+    ));
+    assert!(matches!(
+        warning(r#"0 or more of "a";"#)[..],
+        [Warning::QuantifierKeyword { keyword: "any of", .. }]
+    ));
+    assert!(matches!(
+        warning(r#"1 to many of "a";"#)[..],
+        [Warning::QuantifierKeyword { keyword: "some of", .. }]
+    ));
+    assert_eq!(
+        warning(r#"0 or more of "a";"#)[0].to_string(),
+        "quantifier is the same as `any of`, use it instead"
+    );
+    assert_eq!(compiler(r#"0 or more of "a";"#).unwrap(), "a{0,}");
+
+    let largest = format!("{} or more of \"a\";", usize::MAX);
+    assert_eq!(compiler(&largest).unwrap(), format!("a{{{},}}", usize::MAX));
+    assert!(matches!(
+        to_ast(r#"99999999999999999999999 or more of "a";"#),
+        Err(CompilerError::CouldNotParseAnAmount(_))
+    ));
+    assert!(matches!(
+        to_ast(&format!("over {} of \"a\";", usize::MAX)),
+        Err(CompilerError::CouldNotParseAnAmount(_))
+    ));
+}
+
+#[test]
+fn separate_flags_test() {
+    let source = r#"flags { insensitive; multiline; } "a";"#;
+
+    // ECMAScript has no inline flag syntax, so its flags are returned along with the pattern
+    let output = Compiler::new().compile(source).unwrap();
+    assert_eq!((output.pattern.as_str(), output.flags.as_str()), ("a", "im"));
+
+    let output = Compiler::new().target(Target::PythonRe).compile(source).unwrap();
+    assert_eq!((output.pattern.as_str(), output.flags.as_str()), ("(?im)a", ""));
+
+    let options = CompilerOptions {
+        unicode_mode: true,
+        ..CompilerOptions::default()
+    };
+    let output = Compiler::new().options(options).compile(r#""a";"#).unwrap();
+    assert_eq!(output.flags, "u");
+
+    let output = Compiler::new()
+        .compile_with_imports(r#"flags { dot_all; } import "lib.viable"; .a;"#, |path| {
+            (path == "lib.viable").then(|| r#"let .a = { "a"; }"#.to_owned())
+        })
+        .unwrap();
+    assert_eq!((output.pattern.as_str(), output.flags.as_str()), ("a", "s"));
+
+    // the functions returning only a pattern have nowhere to put them
+    assert!(matches!(
+        compile_for(source, Target::EcmaScript),
+        Err(CompilerError::UnsupportedByTarget {
+            target: Target::EcmaScript,
+            ..
+        })
+    ));
+}
@@ -0,0 +1,52 @@
+#ifndef VIABLE_H
+#define VIABLE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* status codes returned by viable_compile */
+#define VIABLE_OK 0
+#define VIABLE_COMPILE_ERROR 1
+#define VIABLE_NULL_POINTER -1
+#define VIABLE_INVALID_UTF8 -2
+#define VIABLE_UNKNOWN_TARGET -3
+#define VIABLE_PANIC -4
+
+/* targets accepted by viable_compile */
+#define VIABLE_TARGET_ECMASCRIPT 0
+#define VIABLE_TARGET_PCRE2 1
+#define VIABLE_TARGET_RE2 2
+#define VIABLE_TARGET_PYTHON 3
+#define VIABLE_TARGET_RUST 4
+#define VIABLE_TARGET_RUST_BYTES 5
+
+typedef struct ViableResult {
+  /* the compiled regex, or NULL if the source failed to compile */
+  char *regex;
+  /* the number of the error's code (e.g. 9 for E0009), 0 if the source compiled or the error has no code */
+  uint32_t error_code;
+  /* the error message, or NULL if the source compiled */
+  char *message;
+  /* the byte offsets into the source of the error, both 0 for errors without a location */
+  size_t start;
+  size_t end;
+} ViableResult;
+
+/*
+ * Compiles a null terminated UTF-8 Viable source for a target, writing the outcome to out and returning
+ * a status code. Unless the status is VIABLE_NULL_POINTER, out has to be freed with viable_free_result.
+ */
+int32_t viable_compile(const char *source, uint32_t target, ViableResult *out);
+
+/* Frees the strings of a result, freeing a result twice does nothing */
+void viable_free_result(ViableResult *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
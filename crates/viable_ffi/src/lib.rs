@@ -0,0 +1,185 @@
+#![deny(unsafe_op_in_unsafe_fn)]
+#![allow(clippy::module_name_repetitions)]
+
+//! C bindings for the Viable compiler, declared for C in `include/viable.h`
+//!
+//! Strings returned by the bindings are owned by the [`ViableResult`] holding them and are freed together with it
+//! by [`viable_free_result`].
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use viable_compiler::ast::to_ast_all_errors;
+use viable_compiler::compile_for;
+use viable_compiler::errors::CompilerError;
+use viable_compiler::target::Target;
+
+/// The source compiled, the result holds the regex
+pub const VIABLE_OK: i32 = 0;
+/// The source failed to compile, the result holds the first error
+pub const VIABLE_COMPILE_ERROR: i32 = 1;
+/// The source or the result is a null pointer, nothing is written to the result
+pub const VIABLE_NULL_POINTER: i32 = -1;
+/// The source is not valid UTF-8
+pub const VIABLE_INVALID_UTF8: i32 = -2;
+/// The target is not one of the `VIABLE_TARGET_*` constants
+pub const VIABLE_UNKNOWN_TARGET: i32 = -3;
+/// The compiler panicked, which is a bug in the compiler
+pub const VIABLE_PANIC: i32 = -4;
+
+pub const VIABLE_TARGET_ECMASCRIPT: u32 = 0;
+pub const VIABLE_TARGET_PCRE2: u32 = 1;
+pub const VIABLE_TARGET_RE2: u32 = 2;
+pub const VIABLE_TARGET_PYTHON: u32 = 3;
+pub const VIABLE_TARGET_RUST: u32 = 4;
+pub const VIABLE_TARGET_RUST_BYTES: u32 = 5;
+
+/// The outcome of [`viable_compile`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct ViableResult {
+    /// the compiled regex, or null if the source failed to compile
+    pub regex: *mut c_char,
+    /// the number of the error's code (e.g. 9 for `E0009`), 0 if the source compiled or the error has no code
+    pub error_code: u32,
+    /// the error message, or null if the source compiled
+    pub message: *mut c_char,
+    /// the byte offset into the source the error starts at, 0 for errors without a location
+    pub start: usize,
+    /// the byte offset into the source the error ends at, 0 for errors without a location
+    pub end: usize,
+}
+
+impl ViableResult {
+    fn regex(regex: &str) -> Self {
+        Self {
+            regex: c_string(regex),
+            error_code: 0,
+            message: ptr::null_mut(),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    fn error(message: &str) -> Self {
+        Self {
+            regex: ptr::null_mut(),
+            error_code: 0,
+            message: c_string(message),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    fn compiler_error(error: &CompilerError, start: usize, end: usize) -> Self {
+        let code = error.code();
+        let message = error.to_string();
+        let message = message.strip_suffix(&format!(" [{code}]")).unwrap_or(&message);
+
+        Self {
+            error_code: code.trim_start_matches('E').parse().unwrap_or_default(),
+            start,
+            end,
+            ..Self::error(message)
+        }
+    }
+}
+
+/**
+Compiles a null terminated UTF-8 Viable source for a target (one of the `VIABLE_TARGET_*` constants), writing the
+outcome to `out` and returning one of the `VIABLE_*` status codes
+
+Unless the status is [`VIABLE_NULL_POINTER`], `out` holds either the regex or an error message and has to be freed
+with [`viable_free_result`]. Panics of the compiler are caught and reported as [`VIABLE_PANIC`].
+
+# Safety
+
+`source` has to be null or point to a null terminated string, and `out` has to be null or point to memory valid
+for writing a [`ViableResult`]
+*/
+#[no_mangle]
+pub unsafe extern "C" fn viable_compile(source: *const c_char, target: u32, out: *mut ViableResult) -> i32 {
+    if source.is_null() || out.is_null() {
+        return VIABLE_NULL_POINTER;
+    }
+
+    // SAFETY: the caller guarantees that a non null source is a null terminated string
+    let source = unsafe { CStr::from_ptr(source) };
+
+    let (status, result) = catch_unwind(AssertUnwindSafe(|| compile(source, target)))
+        .unwrap_or_else(|_| (VIABLE_PANIC, ViableResult::error("the compiler panicked")));
+
+    // SAFETY: the caller guarantees that a non null `out` is valid for writes, it may be uninitialized so the
+    // previous value is not dropped
+    unsafe { ptr::write(out, result) };
+
+    status
+}
+
+/**
+Frees the strings of a result written by [`viable_compile`], leaving them null so that freeing the result again
+does nothing
+
+# Safety
+
+`result` has to be null or point to a result written by [`viable_compile`] whose strings have not been replaced
+*/
+#[no_mangle]
+pub unsafe extern "C" fn viable_free_result(result: *mut ViableResult) {
+    // SAFETY: the caller guarantees that a non null result was written by `viable_compile`
+    let Some(result) = (unsafe { result.as_mut() }) else {
+        return;
+    };
+
+    for string in [&mut result.regex, &mut result.message] {
+        if !string.is_null() {
+            // SAFETY: non null strings of a result are created by `CString::into_raw` in `c_string`
+            drop(unsafe { CString::from_raw(*string) });
+            *string = ptr::null_mut();
+        }
+    }
+}
+
+fn compile(source: &CStr, target: u32) -> (i32, ViableResult) {
+    let Ok(source) = source.to_str() else {
+        return (
+            VIABLE_INVALID_UTF8,
+            ViableResult::error("the source is not valid UTF-8"),
+        );
+    };
+
+    let Some(target) = usize::try_from(target).ok().and_then(|index| Target::ALL.get(index)) else {
+        return (
+            VIABLE_UNKNOWN_TARGET,
+            ViableResult::error(&format!("unknown target {target}")),
+        );
+    };
+
+    if let Err(errors) = to_ast_all_errors(source) {
+        if let Some(error) = errors.first() {
+            return (
+                VIABLE_COMPILE_ERROR,
+                ViableResult::compiler_error(&error.node, error.span.start, error.span.end),
+            );
+        }
+    }
+
+    match compile_for(source, *target) {
+        Ok(regex) => (VIABLE_OK, ViableResult::regex(&regex)),
+        Err(error) => {
+            let span = error.span().unwrap_or_default();
+            (
+                VIABLE_COMPILE_ERROR,
+                ViableResult::compiler_error(&error, span.start, span.end),
+            )
+        }
+    }
+}
+
+/// A null terminated copy of a string to hand over to C, with any null character written as `\x00` (which
+/// regexes and messages read the same)
+fn c_string(string: &str) -> *mut c_char {
+    CString::new(string.replace('\0', r"\x00"))
+        .unwrap_or_default()
+        .into_raw()
+}
@@ -0,0 +1,41 @@
+#include "viable.h"
+
+#include <stdio.h>
+#include <string.h>
+
+#define CHECK(condition)                                                    \
+  if (!(condition)) {                                                       \
+    fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition); \
+    return 1;                                                               \
+  }
+
+int main(void) {
+  ViableResult result;
+
+  CHECK(viable_compile("capture name { some of \"a\"; }", VIABLE_TARGET_PYTHON, &result) == VIABLE_OK);
+  CHECK(strcmp(result.regex, "(?P<name>a+)") == 0);
+  CHECK(result.message == NULL);
+  viable_free_result(&result);
+  CHECK(result.regex == NULL);
+  viable_free_result(&result);
+
+  CHECK(viable_compile("\"a\"; z to a;", VIABLE_TARGET_ECMASCRIPT, &result) == VIABLE_COMPILE_ERROR);
+  CHECK(result.regex == NULL);
+  CHECK(result.error_code == 9);
+  CHECK(strcmp(result.message, "empty range, `z` comes after `a`") == 0);
+  CHECK(result.start == 5 && result.end == 11);
+  viable_free_result(&result);
+
+  CHECK(viable_compile("\"\xff\";", VIABLE_TARGET_ECMASCRIPT, &result) == VIABLE_INVALID_UTF8);
+  viable_free_result(&result);
+
+  CHECK(viable_compile("\"a\";", 42, &result) == VIABLE_UNKNOWN_TARGET);
+  viable_free_result(&result);
+
+  CHECK(viable_compile(NULL, VIABLE_TARGET_ECMASCRIPT, &result) == VIABLE_NULL_POINTER);
+  CHECK(viable_compile("\"a\";", VIABLE_TARGET_ECMASCRIPT, NULL) == VIABLE_NULL_POINTER);
+  viable_free_result(NULL);
+
+  printf("ok\n");
+  return 0;
+}
@@ -0,0 +1,130 @@
+use std::ffi::{CStr, CString};
+use std::path::PathBuf;
+use std::process::Command;
+use std::ptr;
+use viable_ffi::{
+    viable_compile, viable_free_result, ViableResult, VIABLE_COMPILE_ERROR, VIABLE_INVALID_UTF8, VIABLE_NULL_POINTER,
+    VIABLE_OK, VIABLE_TARGET_ECMASCRIPT, VIABLE_TARGET_PCRE2, VIABLE_UNKNOWN_TARGET,
+};
+
+fn compile(source: &[u8], target: u32) -> (i32, ViableResult) {
+    let Ok(source) = CString::new(source) else {
+        unreachable!("expected a source without null characters");
+    };
+    let mut result = ViableResult {
+        regex: ptr::null_mut(),
+        error_code: 0,
+        message: ptr::null_mut(),
+        start: 0,
+        end: 0,
+    };
+
+    let status = unsafe { viable_compile(source.as_ptr(), target, &mut result) };
+    (status, result)
+}
+
+fn string(string: *const std::ffi::c_char) -> Option<String> {
+    (!string.is_null()).then(|| unsafe { CStr::from_ptr(string) }.to_string_lossy().into_owned())
+}
+
+#[test]
+fn compile_test() {
+    let (status, mut result) = compile(br#"possessive some of "a";"#, VIABLE_TARGET_PCRE2);
+
+    assert_eq!(status, VIABLE_OK);
+    assert_eq!(string(result.regex).as_deref(), Some("a++"));
+    assert_eq!(string(result.message), None);
+
+    unsafe { viable_free_result(&mut result) };
+    assert!(result.regex.is_null());
+}
+
+#[test]
+fn compile_error_test() {
+    let (status, mut result) = compile(br#""a"; z to a;"#, VIABLE_TARGET_ECMASCRIPT);
+
+    assert_eq!(status, VIABLE_COMPILE_ERROR);
+    assert_eq!(string(result.regex), None);
+    assert_eq!(result.error_code, 9);
+    assert_eq!(
+        string(result.message).as_deref(),
+        Some("empty range, `z` comes after `a`")
+    );
+    assert_eq!((result.start, result.end), (5, 11));
+    unsafe { viable_free_result(&mut result) };
+
+    // errors found when generating the regex are reported too
+    let (status, mut result) = compile(br#"ahead { "a"; }"#, viable_ffi::VIABLE_TARGET_RUST);
+    assert_eq!(status, VIABLE_COMPILE_ERROR);
+    assert_eq!(result.error_code, 49);
+    unsafe { viable_free_result(&mut result) };
+}
+
+#[test]
+fn invalid_input_test() {
+    let (status, mut result) = compile(b"\"\xff\";", VIABLE_TARGET_ECMASCRIPT);
+    assert_eq!(status, VIABLE_INVALID_UTF8);
+    assert_eq!(result.error_code, 0);
+    assert!(string(result.message).is_some());
+    unsafe { viable_free_result(&mut result) };
+
+    let (status, mut result) = compile(br#""a";"#, 6);
+    assert_eq!(status, VIABLE_UNKNOWN_TARGET);
+    unsafe { viable_free_result(&mut result) };
+
+    let mut result = compile(b"", VIABLE_TARGET_ECMASCRIPT).1;
+    assert_eq!(
+        unsafe { viable_compile(ptr::null(), VIABLE_TARGET_ECMASCRIPT, &mut result) },
+        VIABLE_NULL_POINTER
+    );
+    assert_eq!(
+        unsafe { viable_compile(c"\"a\";".as_ptr(), VIABLE_TARGET_ECMASCRIPT, ptr::null_mut()) },
+        VIABLE_NULL_POINTER
+    );
+
+    // freeing twice or freeing null does nothing
+    unsafe {
+        viable_free_result(&mut result);
+        viable_free_result(&mut result);
+        viable_free_result(ptr::null_mut());
+    }
+}
+
+/// Builds and runs the C program of `tests/c` against the shared library built alongside the tests
+#[test]
+fn c_program_test() {
+    let Ok(test) = std::env::current_exe() else {
+        unreachable!("expected the path of the test binary");
+    };
+    // the shared library is built next to the test binary
+    let Some(library_directory) = test.parent() else {
+        unreachable!("expected the test binary to be in a directory");
+    };
+    let crate_directory = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let program = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("viable_ffi_test");
+
+    let status = Command::new("cc")
+        .arg(crate_directory.join("tests/c/test.c"))
+        .arg("-I")
+        .arg(crate_directory.join("include"))
+        .arg("-L")
+        .arg(library_directory)
+        .arg("-lviable_ffi")
+        .arg("-o")
+        .arg(&program)
+        .status();
+    assert!(
+        status.is_ok_and(|status| status.success()),
+        "expected the C program to build"
+    );
+
+    let output = Command::new(&program)
+        .env("LD_LIBRARY_PATH", library_directory)
+        .env("DYLD_LIBRARY_PATH", library_directory)
+        .output();
+    let Ok(output) = output else {
+        unreachable!("expected the C program to run");
+    };
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
@@ -0,0 +1,192 @@
+#![forbid(unsafe_code)]
+#![allow(clippy::module_name_repetitions)]
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, LitStr, Token};
+use viable_compiler::ast::to_ast_all_errors;
+use viable_compiler::compile_for;
+use viable_compiler::errors::CompilerError;
+use viable_compiler::target::Target;
+
+/**
+Compiles a Viable pattern at compile time, expanding to the regular expression as a `&'static str`.
+
+The pattern is compiled for Rust's `regex` crate unless a target is given before it, one of `ecmascript`,
+`pcre2`, `re2`, `python` or `rust`. Errors in the pattern are reported as compile errors pointing at the
+pattern.
+
+# Example
+
+```rust
+use viable_macros::viable;
+
+const VERSION: &str = viable!(r#"some of <digit>; "."; some of <digit>;"#);
+assert_eq!(VERSION, r"\d+\.\d+");
+
+let word = viable!(target = "ecmascript", r#"capture word { some of <word>; }"#);
+assert_eq!(word, r"(?<word>\w+)");
+```
+*/
+#[proc_macro]
+pub fn viable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as MacroInput);
+
+    match compile(&input.pattern, input.target) {
+        Ok(regex) => quote!(#regex).into(),
+        Err(error) => compile_errors(&error),
+    }
+}
+
+/**
+Compiles a Viable pattern at compile time, expanding to a `&'static regex::Regex` built on first use.
+
+The pattern is always compiled for Rust's `regex` crate, which the invoking crate has to depend on. The regex
+is checked when the macro expands, so building it can't fail at runtime.
+
+# Example
+
+```rust
+use viable_macros::viable_regex;
+
+let version = viable_regex!(r#"some of <digit>; "."; some of <digit>;"#);
+assert!(version.is_match("1.74"));
+```
+*/
+#[cfg(feature = "regex")]
+#[proc_macro]
+pub fn viable_regex(input: TokenStream) -> TokenStream {
+    let pattern = parse_macro_input!(input as LitStr);
+
+    let regex = compile(&pattern, Target::RustRegex).and_then(|regex| match regex::Regex::new(&regex.value()) {
+        Ok(_) => Ok(regex),
+        Err(error) => Err(syn::Error::new(pattern.span(), error.to_string())),
+    });
+
+    match regex {
+        Ok(regex) => quote!({
+            static REGEX: ::std::sync::OnceLock<::regex::Regex> = ::std::sync::OnceLock::new();
+            REGEX.get_or_init(|| ::regex::Regex::new(#regex).expect("the regex is checked when the macro expands"))
+        })
+        .into(),
+        Err(error) => compile_errors(&error),
+    }
+}
+
+/// The errors as an expression, so that every error is reported rather than only the first
+fn compile_errors(error: &syn::Error) -> TokenStream {
+    let errors = error.to_compile_error();
+    quote!({ #errors }).into()
+}
+
+/// `target = "...",` (optional) followed by the pattern
+struct MacroInput {
+    target: Target,
+    pattern: LitStr,
+}
+
+impl Parse for MacroInput {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut target = Target::RustRegex;
+
+        if input.peek(syn::Ident) {
+            let argument: syn::Ident = input.parse()?;
+            if argument != "target" {
+                return Err(syn::Error::new(argument.span(), "expected `target` or a pattern"));
+            }
+            input.parse::<Token![=]>()?;
+            target = parse_target(&input.parse()?)?;
+            input.parse::<Token![,]>()?;
+        }
+
+        let pattern = input.parse()?;
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(Self { target, pattern })
+    }
+}
+
+fn parse_target(name: &LitStr) -> syn::Result<Target> {
+    let value = name.value();
+
+    Target::from_name(&value).ok_or_else(|| {
+        let names: Vec<String> = Target::ALL
+            .iter()
+            .map(|target| format!("`{}`", target.name()))
+            .collect();
+        syn::Error::new(
+            name.span(),
+            format!("unknown target `{value}`, expected one of {}", names.join(", ")),
+        )
+    })
+}
+
+/// Compiles the pattern, reporting every error of the conversion to an AST or else the error of compiling it
+fn compile(pattern: &LitStr, target: Target) -> syn::Result<LitStr> {
+    let source = pattern.value();
+
+    if let Err(errors) = to_ast_all_errors(&source) {
+        let mut errors = errors
+            .into_iter()
+            .map(|error| pattern_error(pattern, &error.node, error.span));
+
+        let mut combined = errors
+            .next()
+            .unwrap_or_else(|| syn::Error::new(pattern.span(), "invalid pattern"));
+        combined.extend(errors);
+
+        return Err(combined);
+    }
+
+    compile_for(&source, target)
+        .map(|regex| LitStr::new(&regex, pattern.span()))
+        .map_err(|error| pattern_error(pattern, &error, error.span().unwrap_or_default()))
+}
+
+/// An error pointing at `span` of the pattern, with its location in the pattern and its help text on lines of
+/// their own
+fn pattern_error(pattern: &LitStr, error: &CompilerError, span: viable_compiler::ast::types::ast::Span) -> syn::Error {
+    let mut message = error.to_string();
+
+    // parse errors already name their location
+    if span.line > 0 && !matches!(error, CompilerError::ParseError { .. }) {
+        message.push_str(&format!("\n--> {}:{} of the pattern", span.line, span.column));
+    }
+    if let Some(help) = error.help() {
+        message.push_str(&format!("\nhelp: {help}"));
+    }
+
+    let span = if span.line > 0 {
+        offset_span(pattern, span.start, span.end)
+    } else {
+        pattern.span()
+    };
+
+    syn::Error::new(span, message)
+}
+
+/// The span of a range of the pattern's value within the literal
+///
+/// Offsets into the value only match the source of the literal when it has no escapes, and subspans are only
+/// available on nightly compilers, so the span of the whole literal is used otherwise
+fn offset_span(pattern: &LitStr, start: usize, end: usize) -> Span {
+    let literal = pattern.token().to_string();
+    let value = pattern.value();
+
+    let prefix = if let Some(raw) = literal.strip_prefix('r') {
+        raw.find('"').map(|quote| quote + 2)
+    } else if literal.get(1..literal.len().saturating_sub(1)) == Some(value.as_str()) {
+        Some(1)
+    } else {
+        None
+    };
+
+    prefix
+        .filter(|_| start <= end && end <= value.len())
+        .and_then(|prefix| pattern.token().subspan(prefix + start..prefix + end))
+        .unwrap_or_else(|| pattern.span())
+}
@@ -0,0 +1,15 @@
+#[test]
+fn pass_test() {
+    trybuild::TestCases::new().pass("tests/ui/pass/*.rs");
+}
+
+#[test]
+fn fail_test() {
+    trybuild::TestCases::new().compile_fail("tests/ui/fail/*.rs");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex_test() {
+    trybuild::TestCases::new().pass("tests/ui/regex/*.rs");
+}
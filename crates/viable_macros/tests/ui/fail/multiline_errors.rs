@@ -0,0 +1,11 @@
+use viable_macros::viable;
+
+fn main() {
+    let _ = viable!(
+        r#"
+        z to a;
+        "b";
+        9 to 1;
+        "#
+    );
+}
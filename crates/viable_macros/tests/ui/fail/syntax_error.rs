@@ -0,0 +1,5 @@
+use viable_macros::viable;
+
+fn main() {
+    let _ = viable!("some of )");
+}
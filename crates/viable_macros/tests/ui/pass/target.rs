@@ -0,0 +1,7 @@
+use viable_macros::viable;
+
+fn main() {
+    assert_eq!(viable!(target = "ecmascript", r#"capture word { "a"; }"#), "(?<word>a)");
+    assert_eq!(viable!(target = "python", r#"capture word { "a"; }"#), "(?P<word>a)");
+    assert_eq!(viable!(target = "pcre2", r#"possessive some of "a";"#), "a++");
+}
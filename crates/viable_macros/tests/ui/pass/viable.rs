@@ -0,0 +1,22 @@
+use viable_macros::viable;
+
+const VERSION: &str = viable!(r#"some of <digit>; "."; some of <digit>;"#);
+
+fn main() {
+    assert_eq!(VERSION, r"\d+\.\d+");
+
+    let date = viable!(
+        r#"
+        capture year {
+          4 of <digit>;
+        }
+        "-";
+        capture month {
+          2 of <digit>;
+        }
+        "#
+    );
+    assert_eq!(date, r"(?<year>\d{4})-(?<month>\d{2})");
+
+    assert_eq!(viable!("\"a\";",), "a");
+}
@@ -0,0 +1,15 @@
+use viable_macros::viable_regex;
+
+fn version() -> &'static regex::Regex {
+    viable_regex!(r#"<start>; capture major { some of <digit>; } "."; capture minor { some of <digit>; } <end>;"#)
+}
+
+fn main() {
+    let captures = version().captures("1.74").unwrap();
+    assert_eq!(&captures["major"], "1");
+    assert_eq!(&captures["minor"], "74");
+
+    // the regex is built once
+    assert!(std::ptr::eq(version(), version()));
+    assert!(!version().is_match("1.74.0"));
+}
@@ -2,6 +2,13 @@
 #![allow(clippy::unused_unit)]
 #![allow(clippy::module_name_repetitions)]
 
+use js_sys::{Array, Object, Reflect, JSON};
+use viable_compiler::ast::types::ast::Span;
+use viable_compiler::ast::{ast_to_json, ast_to_source, to_ast, to_ast_all_errors};
+use viable_compiler::errors::CompilerError;
+use viable_compiler::options::FormatOptions;
+use viable_compiler::target::Target;
+use viable_compiler::Compiler;
 use wasm_bindgen::prelude::*;
 
 /**
@@ -52,7 +59,149 @@ pub fn compiler(source: &str) -> Result<String, JsError> {
     output.map_err(|error| JsError::new(&error.to_string()))
 }
 
+/**
+Compiles Viable source code for a target (e.g. `"ecmascript"` or `"python"`), returning a result object rather
+than throwing
+
+The result is an object of:
+
+- `ok`, whether the source compiled
+- `regex`, the regular expression if the source compiled
+- `flags`, the flags to pass to the `RegExp` constructor along with `regex` if the source compiled (e.g. `"im"`),
+  empty for targets whose patterns hold their flags
+- `ast`, the nodes of the source (in the JSON format of the compiler) if the source converted to an AST
+- `errors`, if the source didn't compile, an object of the `code`, `message`, `start` and `end` of every error,
+  where `start` and `end` are indices into the source string (or `0` for errors without a location)
+
+An unknown target is reported as an error without a code.
+
+# Example
+
+```js
+const result = compile(`some of "a";`, "ecmascript");
+result.ok; // true
+result.regex; // "a+"
+
+const { regex, flags } = compile(`flags { insensitive; } "a";`, "ecmascript");
+new RegExp(regex, flags).test("A"); // true
+
+const { errors } = compile(`z to a;`, "ecmascript");
+errors[0].code; // "E0009"
+```
+*/
+#[wasm_bindgen]
+#[must_use]
+pub fn compile(source: &str, target: &str) -> JsValue {
+    let result = Object::new();
+
+    let Some(target) = Target::from_name(target) else {
+        let names: Vec<&str> = Target::ALL.iter().map(|target| target.name()).collect();
+        let error = error_object(
+            None,
+            &format!("unknown target `{target}`, expected one of {}", names.join(", ")),
+            0,
+            0,
+        );
+        set(&result, "ok", false);
+        set(&result, "errors", Array::of1(&error));
+        return result.into();
+    };
+
+    let ast = match to_ast_all_errors(source) {
+        Ok(ast) => ast,
+        Err(errors) => {
+            let errors: Array = errors
+                .iter()
+                .map(|error| compiler_error_object(source, &error.node, error.span))
+                .collect();
+            set(&result, "ok", false);
+            set(&result, "errors", errors);
+            return result.into();
+        }
+    };
+
+    if let Ok(ast) = JSON::parse(&ast_to_json(&ast)) {
+        set(&result, "ast", ast);
+    }
+
+    match Compiler::new().target(target).compile(source) {
+        Ok(output) => {
+            set(&result, "ok", true);
+            set(&result, "regex", output.pattern);
+            set(&result, "flags", output.flags);
+        }
+        Err(error) => {
+            let error = compiler_error_object(source, &error, error.span().unwrap_or_default());
+            set(&result, "ok", false);
+            set(&result, "errors", Array::of1(&error));
+        }
+    }
+
+    result.into()
+}
+
+/**
+Formats Viable source code to its canonical form, one statement per line
+
+Comments and flags are not kept.
+
+# Errors
+
+Throws an error if the source doesn't convert to an AST
+
+# Example
+
+```js
+format(`"a";some of "b";`); // "\"a\";\nsome of \"b\";\n"
+```
+*/
+#[wasm_bindgen]
+pub fn format(source: &str) -> Result<String, JsError> {
+    let ast = to_ast(source).map_err(|error| JsError::new(&error.to_string()))?;
+    Ok(ast_to_source(&ast, &FormatOptions::default()))
+}
+
+fn set(object: &Object, key: &str, value: impl Into<JsValue>) {
+    // setting a property of a plain object can't fail
+    let _ = Reflect::set(object, &JsValue::from_str(key), &value.into());
+}
+
+fn compiler_error_object(source: &str, error: &CompilerError, span: Span) -> Object {
+    let code = error.code();
+    let message = error.to_string();
+    let message = message.strip_suffix(&format!(" [{code}]")).unwrap_or(&message);
+
+    error_object(
+        Some(code),
+        message,
+        string_index(source, span.start),
+        string_index(source, span.end),
+    )
+}
+
+fn error_object(code: Option<&str>, message: &str, start: usize, end: usize) -> Object {
+    let error = Object::new();
+
+    if let Some(code) = code {
+        set(&error, "code", code);
+    }
+    set(&error, "message", message);
+    // indices fit in a JS number, strings are at most 2^53 - 1 code units long
+    #[allow(clippy::cast_precision_loss)]
+    {
+        set(&error, "start", start as f64);
+        set(&error, "end", end as f64);
+    }
+
+    error
+}
+
+/// The index of a byte offset of the source in a JS string, which is indexed by UTF-16 code units
+fn string_index(source: &str, offset: usize) -> usize {
+    source.get(..offset).map_or(0, |prefix| prefix.encode_utf16().count())
+}
+
 #[wasm_bindgen(start)]
-pub fn main() {
+pub fn start() {
     console_error_panic_hook::set_once();
 }
@@ -0,0 +1,76 @@
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Array, Reflect};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn get(value: &JsValue, key: &str) -> JsValue {
+    Reflect::get(value, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+}
+
+#[wasm_bindgen_test]
+fn compile_test() {
+    let result = viable_wasm::compile(r#"capture name { some of "a"; }"#, "python");
+
+    assert_eq!(get(&result, "ok").as_bool(), Some(true));
+    assert_eq!(get(&result, "regex").as_string().as_deref(), Some("(?P<name>a+)"));
+    assert_eq!(get(&result, "flags").as_string().as_deref(), Some(""));
+    assert!(get(&result, "errors").is_undefined());
+
+    let ast = Array::from(&get(&result, "ast"));
+    assert_eq!(ast.length(), 1);
+    assert_eq!(get(&ast.get(0), "kind").as_string().as_deref(), Some("group"));
+}
+
+#[wasm_bindgen_test]
+fn compile_flags_test() {
+    // ECMAScript has no inline flag syntax, so the flags are passed to `RegExp` separately
+    let result = viable_wasm::compile(r#"flags { insensitive; multiline; } "a";"#, "ecmascript");
+    assert_eq!(get(&result, "ok").as_bool(), Some(true));
+    assert_eq!(get(&result, "regex").as_string().as_deref(), Some("a"));
+    assert_eq!(get(&result, "flags").as_string().as_deref(), Some("im"));
+
+    let result = viable_wasm::compile(r#"flags { insensitive; } "a";"#, "python");
+    assert_eq!(get(&result, "regex").as_string().as_deref(), Some("(?i)a"));
+    assert_eq!(get(&result, "flags").as_string().as_deref(), Some(""));
+}
+
+#[wasm_bindgen_test]
+fn compile_errors_test() {
+    // `é` is two bytes long but a single code unit of a JS string
+    let result = viable_wasm::compile("\"é\"; z to a; 9 to 1;", "ecmascript");
+
+    assert_eq!(get(&result, "ok").as_bool(), Some(false));
+    assert!(get(&result, "regex").is_undefined());
+
+    let errors = Array::from(&get(&result, "errors"));
+    assert_eq!(errors.length(), 2);
+
+    let error = errors.get(0);
+    assert_eq!(get(&error, "code").as_string().as_deref(), Some("E0009"));
+    assert_eq!(
+        get(&error, "message").as_string().as_deref(),
+        Some("empty range, `z` comes after `a`")
+    );
+    assert_eq!(get(&error, "start").as_f64(), Some(5.0));
+    assert_eq!(get(&error, "end").as_f64(), Some(11.0));
+
+    // an error found when generating the regex still comes with the AST
+    let result = viable_wasm::compile(r#"ahead { "a"; }"#, "rust");
+    let errors = Array::from(&get(&result, "errors"));
+    assert_eq!(get(&errors.get(0), "code").as_string().as_deref(), Some("E0049"));
+    assert!(Array::is_array(&get(&result, "ast")));
+
+    let result = viable_wasm::compile(r#""a";"#, "perl");
+    let errors = Array::from(&get(&result, "errors"));
+    assert!(get(&errors.get(0), "code").is_undefined());
+}
+
+#[wasm_bindgen_test]
+fn format_test() {
+    assert_eq!(
+        viable_wasm::format(r#""a";some of "b";"#).ok().as_deref(),
+        Some("\"a\";\nsome of \"b\";\n")
+    );
+    assert!(viable_wasm::format(r#"some of "a""#).is_err());
+}
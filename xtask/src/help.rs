@@ -61,6 +61,7 @@ impl Help {
                 match command {
                     "deploy" => print_suggestion(None, "publish"),
                     "bench" => print_suggestion(None, "benchmark"),
+                    "nostd" | "no_std" => print_suggestion(None, "no-std"),
                     "fuzzer" => print_suggestion(None, "fuzz"),
                     "WASM" => print_suggestion(None, "wasm"),
                     _ => {}
@@ -103,6 +104,7 @@ impl Display for Help {
                 r"commands:
       run [arguments]          runs the viable_cli binary
       benchmark                runs benchmarks
+      no-std                   builds the compiler without std for an embedded target
       fuzz <crate>             runs fuzz testing on specific crates
       publish <target>         publishes specific crates or projects
       wasm <target>            builds wasm dependencies for specific projects
@@ -27,6 +27,7 @@ fn try_main() -> anyhow::Result<()> {
     match task.as_deref() {
         Some("run") => run()?,
         Some("benchmark") => benchmark()?,
+        Some("no-std") => no_std()?,
         Some("fuzz") => fuzz()?,
         Some("publish") => publish()?,
         Some("wasm") => wasm()?,
@@ -54,6 +55,19 @@ fn benchmark() -> anyhow::Result<()> {
     Ok(())
 }
 
+fn no_std() -> anyhow::Result<()> {
+    let shell = shell_in_dir("crates/viable_compiler")?;
+
+    // a target without `std` so that any use of it fails to build
+    cmd!(
+        shell,
+        "cargo build --no-default-features --features serde,diagnostics --target thumbv7em-none-eabi"
+    )
+    .run()?;
+
+    Ok(())
+}
+
 fn publish() -> anyhow::Result<()> {
     let target = positional_argument(2);
 